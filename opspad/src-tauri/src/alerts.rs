@@ -0,0 +1,209 @@
+//! User-defined alert rules, evaluated against what the monitoring subsystem already collects:
+//! host checks ([`monitor`](crate::monitor)), HTTP checks ([`http_checks`](crate::http_checks)),
+//! resource samples ([`sampler`](crate::sampler)) and headless job output ([`jobs`](crate::jobs)).
+//!
+//! A firing rule opens one alert per rule, host and subject (a mount point, say) and keeps it
+//! until it's resolved, so a host that stays down doesn't raise a new alert every check. Host-down,
+//! HTTP and disk alerts resolve themselves once the condition clears; output matches are resolved
+//! by hand. New alerts are emitted as `alert:raised` and shown as notifications; auto-resolved ones
+//! as `alert:resolved`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::db::{Alert, AlertRule, HttpCheck};
+use crate::http_checks::HttpCheckStatus;
+use crate::monitor::{HostStatus, Status};
+use crate::notifications;
+use crate::sampler::HostMetrics;
+use crate::AppState;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum AlertCondition {
+    /// The host failed this many checks in a row.
+    HostDown { checks: u32 },
+    /// An HTTP check failed this many times in a row. Host-scoped rules cover the checks
+    /// attached to that host.
+    HttpCheckFailing { checks: u32 },
+    /// A filesystem is more than `percent` full; `mount` limits it to one mount point.
+    DiskUsage { percent: u8, mount: Option<String> },
+    /// A line of a job's output matches `pattern`.
+    OutputMatch { pattern: String },
+}
+
+pub fn validate_rule(name: &str, condition: &AlertCondition) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("alert rule name must not be empty".to_string());
+    }
+    match condition {
+        AlertCondition::HostDown { checks } | AlertCondition::HttpCheckFailing { checks } if *checks == 0 => {
+            Err("failed checks must be at least 1".to_string())
+        }
+        AlertCondition::DiskUsage { percent, .. } if *percent == 0 || *percent >= 100 => {
+            Err("disk usage threshold must be between 1 and 99 percent".to_string())
+        }
+        AlertCondition::OutputMatch { pattern } if pattern.trim().is_empty() => {
+            Err("output pattern must not be empty".to_string())
+        }
+        AlertCondition::OutputMatch { pattern } => Regex::new(pattern)
+            .map(|_| ())
+            .map_err(|e| format!("invalid output pattern: {e}")),
+        _ => Ok(()),
+    }
+}
+
+/// Enabled rules that apply to `host_id` (`None`: not tied to a host, e.g. a local job).
+fn rules_for(state: &AppState, host_id: Option<&str>) -> Vec<AlertRule> {
+    match state.db.alert_rules_list() {
+        Ok(rules) => rules
+            .into_iter()
+            .filter(|r| r.enabled && (r.host_id.is_none() || r.host_id.as_deref() == host_id))
+            .collect(),
+        Err(e) => {
+            log::warn!("alert rules not loaded: {e}");
+            Vec::new()
+        }
+    }
+}
+
+fn host_label(state: &AppState, host_id: &str) -> String {
+    state
+        .db
+        .hosts_get(host_id)
+        .ok()
+        .flatten()
+        .map_or_else(|| host_id.to_string(), |h| h.label)
+}
+
+fn raise(app: &AppHandle, state: &AppState, rule: &AlertRule, host_id: Option<&str>, subject: &str, message: &str) {
+    match state.db.alerts_raise(rule, host_id, subject, message) {
+        Ok(Some(alert)) => {
+            notifications::notify(app, notifications::Kind::Alert, &format!("Alert: {}", rule.name), message);
+            let _ = app.emit("alert:raised", alert);
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("alert not raised: {e}"),
+    }
+}
+
+fn auto_resolve(app: &AppHandle, state: &AppState, rule: &AlertRule, host_id: &str, still_firing: &[String]) {
+    match state.db.alerts_auto_resolve(&rule.id, host_id, still_firing) {
+        Ok(resolved) => {
+            for alert in resolved {
+                let _ = app.emit("alert:resolved", alert);
+            }
+        }
+        Err(e) => log::warn!("alerts not resolved: {e}"),
+    }
+}
+
+/// Evaluate host-down rules after every check (not only on status changes: the failure count
+/// grows while the status stays down).
+pub fn on_host_status(app: &AppHandle, state: &AppState, status: &HostStatus) {
+    for rule in rules_for(state, Some(&status.host_id)) {
+        let AlertCondition::HostDown { checks } = rule.condition else {
+            continue;
+        };
+        if status.status == Status::Down && status.failures >= checks {
+            let message = format!(
+                "{} is down ({} failed checks): {}",
+                host_label(state, &status.host_id),
+                status.failures,
+                status.detail.as_deref().unwrap_or("no response")
+            );
+            raise(app, state, &rule, Some(&status.host_id), "down", &message);
+        } else if status.status != Status::Down {
+            auto_resolve(app, state, &rule, &status.host_id, &[]);
+        }
+    }
+}
+
+/// Evaluate HTTP check rules after every run of `check`.
+pub fn on_http_status(app: &AppHandle, state: &AppState, check: &HttpCheck, status: &HttpCheckStatus) {
+    let subject = format!("http:{}", check.id);
+    for rule in rules_for(state, check.host_id.as_deref()) {
+        let AlertCondition::HttpCheckFailing { checks } = rule.condition else {
+            continue;
+        };
+        if status.status == Status::Down && status.failures >= checks {
+            let message = format!(
+                "{} ({}) is failing ({} checks in a row): {}",
+                check.name,
+                check.url,
+                status.failures,
+                status.detail.as_deref().unwrap_or("no response")
+            );
+            raise(app, state, &rule, check.host_id.as_deref(), &subject, &message);
+        } else if status.status != Status::Down {
+            match state.db.alerts_resolve_subject(&rule.id, &subject) {
+                Ok(resolved) => {
+                    for alert in resolved {
+                        let _ = app.emit("alert:resolved", alert);
+                    }
+                }
+                Err(e) => log::warn!("alerts not resolved: {e}"),
+            }
+        }
+    }
+}
+
+/// Evaluate disk rules against a resource sample. Failed samples are skipped rather than
+/// treated as the disks having cleared.
+pub fn on_metrics(app: &AppHandle, state: &AppState, metrics: &HostMetrics) {
+    if metrics.error.is_some() {
+        return;
+    }
+    for rule in rules_for(state, Some(&metrics.host_id)) {
+        let AlertCondition::DiskUsage { percent, mount } = &rule.condition else {
+            continue;
+        };
+        let mount = mount.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        let firing: Vec<_> = metrics
+            .disks
+            .iter()
+            .filter(|d| mount.is_none_or(|m| d.mount == m) && d.use_percent > *percent)
+            .collect();
+        for disk in &firing {
+            let message = format!(
+                "{}: {} is {}% full ({} of {} used)",
+                host_label(state, &metrics.host_id),
+                disk.mount,
+                disk.use_percent,
+                disk.used,
+                disk.size
+            );
+            raise(app, state, &rule, Some(&metrics.host_id), &disk.mount, &message);
+        }
+        let mounts: Vec<String> = firing.iter().map(|d| d.mount.clone()).collect();
+        auto_resolve(app, state, &rule, &metrics.host_id, &mounts);
+    }
+}
+
+/// Evaluate output rules against a finished job's output. `label` names the job's target.
+pub fn on_output(app: &AppHandle, state: &AppState, host_id: Option<&str>, label: &str, output: &str) {
+    for rule in rules_for(state, host_id) {
+        let AlertCondition::OutputMatch { pattern } = &rule.condition else {
+            continue;
+        };
+        // Validated on save; a bad pattern here just never matches.
+        let Ok(re) = Regex::new(pattern) else {
+            continue;
+        };
+        if let Some(line) = output.lines().find(|l| re.is_match(l)) {
+            let line: String = line.trim().chars().take(200).collect();
+            let message = format!("{label}: {line}");
+            raise(app, state, &rule, host_id, "output", &message);
+        }
+    }
+}
+
+/// The alert after an acknowledge/resolve, for the UI to replace its row.
+pub fn get(state: &AppState, id: &str) -> Result<Alert, String> {
+    state
+        .db
+        .alerts_get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "alert not found".to_string())
+}
@@ -0,0 +1,279 @@
+//! App lock: a lock screen over the whole app, for walking away from open sessions.
+//!
+//! While engaged, terminal input is refused, session output is held back from the UI (and
+//! delivered on unlock), and the vault is locked too. It engages on request, after a period
+//! without terminal input, or when the OS session locks or the machine wakes from sleep, and
+//! releases with the app PIN or, if allowed, the platform's own verification prompt (Windows
+//! Hello, Touch ID, polkit). Like the vault passphrase, the PIN is only stored as a salted hash.
+//! Changes are announced as `app:lock` events.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::user_presence;
+use crate::db::Db;
+use crate::vault_autolock::{self, LockReason, Verifier, FAILED_UNLOCK_DELAY, SLEEP_GAP};
+use crate::{secrets, AppState};
+
+const CONFIG_KEY: &str = "app.lock";
+const PIN_KEY: &str = "app.lock.pin";
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+const MIN_PIN_CHARS: usize = 4;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockConfig {
+    /// Lock after this many minutes without terminal input; 0 disables.
+    #[serde(default)]
+    pub idle_minutes: u32,
+    /// Lock when the OS session locks or the machine wakes from sleep.
+    #[serde(default)]
+    pub lock_on_os_lock: bool,
+    /// The platform verification prompt can unlock instead of the PIN.
+    #[serde(default)]
+    pub os_auth: bool,
+}
+
+impl AppLockConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(CONFIG_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if self.os_auth && user_presence::presence_check("").is_none() {
+            return Err("user verification isn't available on this system".to_string());
+        }
+        if self.enabled() && !can_unlock(db, self) {
+            return Err("set a PIN or allow OS verification before enabling auto-lock".to_string());
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(CONFIG_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn enabled(&self) -> bool {
+        self.idle_minutes > 0 || self.lock_on_os_lock
+    }
+}
+
+/// Whether there's a way back in once locked.
+fn can_unlock(db: &Db, config: &AppLockConfig) -> bool {
+    Verifier::load_key(db, PIN_KEY).is_some() || (config.os_auth && user_presence::presence_check("").is_some())
+}
+
+struct LockState {
+    locked: bool,
+    last_activity: Instant,
+    /// The app lock locked the vault, so unlocking the app unlocks it again.
+    holds_vault: bool,
+}
+
+pub struct AppLock {
+    inner: Mutex<LockState>,
+}
+
+impl Default for AppLock {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(LockState {
+                locked: false,
+                last_activity: Instant::now(),
+                holds_vault: false,
+            }),
+        }
+    }
+}
+
+impl AppLock {
+    pub fn is_locked(&self) -> bool {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).locked
+    }
+
+    /// Note terminal input; the idle timer counts from the last call.
+    pub fn touch(&self) {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).last_activity = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).last_activity.elapsed()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub locked: bool,
+    pub has_pin: bool,
+    pub idle_minutes: u32,
+    pub lock_on_os_lock: bool,
+    pub os_auth: bool,
+    /// Seconds until the idle timer locks the app, when it's running.
+    pub locks_in_secs: Option<u64>,
+}
+
+/// Payload of the `app:lock` event.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppLockEvent {
+    locked: bool,
+    reason: Option<LockReason>,
+}
+
+pub fn status(state: &AppState) -> AppLockStatus {
+    let config = AppLockConfig::load(&state.db);
+    let locked = state.app_lock.is_locked();
+    let locks_in_secs = (!locked && config.idle_minutes > 0).then(|| {
+        let timeout = u64::from(config.idle_minutes) * 60;
+        timeout.saturating_sub(state.app_lock.idle_for().as_secs())
+    });
+    AppLockStatus {
+        locked,
+        has_pin: Verifier::load_key(&state.db, PIN_KEY).is_some(),
+        idle_minutes: config.idle_minutes,
+        lock_on_os_lock: config.lock_on_os_lock,
+        os_auth: config.os_auth,
+        locks_in_secs,
+    }
+}
+
+fn engage(app: &AppHandle, state: &AppState, reason: LockReason) {
+    {
+        let mut st = state.app_lock.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if st.locked {
+            return;
+        }
+        st.locked = true;
+        st.holds_vault = !state.vault_lock.is_locked();
+    }
+    state.terminal.set_locked(app, true);
+    vault_autolock::set_locked(app, &state.vault_lock, true, Some(LockReason::AppLocked));
+    log::info!("app locked ({reason:?})");
+    let _ = app.emit(
+        "app:lock",
+        AppLockEvent {
+            locked: true,
+            reason: Some(reason),
+        },
+    );
+}
+
+/// Lock the app now. Needs a PIN or OS verification, otherwise there'd be no way back in.
+pub fn lock(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    if !can_unlock(&state.db, &AppLockConfig::load(&state.db)) {
+        return Err("set a PIN or allow OS verification before locking the app".to_string());
+    }
+    engage(app, state, LockReason::Manual);
+    Ok(())
+}
+
+/// Unlock with the PIN, or with `pin` = None through the platform verification prompt (when
+/// allowed). Blocks while the prompt is up.
+pub fn unlock(app: &AppHandle, state: &AppState, pin: Option<&str>) -> Result<(), String> {
+    match pin {
+        Some(pin) => {
+            let verifier = Verifier::load_key(&state.db, PIN_KEY).ok_or_else(|| "no PIN is set".to_string())?;
+            if !verifier.matches(pin) {
+                thread::sleep(FAILED_UNLOCK_DELAY);
+                return Err("wrong PIN".to_string());
+            }
+        }
+        None if AppLockConfig::load(&state.db).os_auth => secrets::verify_presence("Unlock OpsPad")?,
+        None => return Err("enter the PIN to unlock".to_string()),
+    }
+
+    let holds_vault = {
+        let mut st = state.app_lock.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if !st.locked {
+            return Ok(());
+        }
+        st.locked = false;
+        st.last_activity = Instant::now();
+        std::mem::take(&mut st.holds_vault)
+    };
+    state.terminal.set_locked(app, false);
+    if holds_vault {
+        vault_autolock::set_locked(app, &state.vault_lock, false, None);
+    }
+    log::info!("app unlocked");
+    let _ = app.emit(
+        "app:lock",
+        AppLockEvent {
+            locked: false,
+            reason: None,
+        },
+    );
+    Ok(())
+}
+
+/// Set, change, or (with `new` = None) remove the PIN. Changing or removing one needs it; removing
+/// it turns auto-lock off unless OS verification can still unlock.
+pub fn set_pin(state: &AppState, current: Option<&str>, new: Option<&str>) -> Result<(), String> {
+    if let Some(verifier) = Verifier::load_key(&state.db, PIN_KEY) {
+        if !current.is_some_and(|c| verifier.matches(c)) {
+            thread::sleep(FAILED_UNLOCK_DELAY);
+            return Err("current PIN is wrong".to_string());
+        }
+    }
+    match new {
+        Some(p) if p.chars().count() < MIN_PIN_CHARS => {
+            Err(format!("the PIN must be at least {MIN_PIN_CHARS} characters"))
+        }
+        Some(p) => {
+            let json = serde_json::to_string(&Verifier::new(p)?).map_err(|e| e.to_string())?;
+            state.db.settings_set(PIN_KEY, &json).map_err(|e| e.to_string())
+        }
+        None => {
+            // Settings have no delete; `null` reads back as "no PIN".
+            state.db.settings_set(PIN_KEY, "null").map_err(|e| e.to_string())?;
+            let config = AppLockConfig::load(&state.db);
+            if !config.os_auth {
+                AppLockConfig::default().save(&state.db)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Watch for inactivity, OS session locks, and wake-from-sleep; emits `app:lock` when it locks.
+pub fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_tick = SystemTime::now();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            // Monotonic time stops during sleep; the wall clock doesn't.
+            let now = SystemTime::now();
+            let gap = now.duration_since(last_tick).unwrap_or_default();
+            last_tick = now;
+
+            let state = app.state::<Arc<AppState>>();
+            if state.app_lock.is_locked() {
+                continue;
+            }
+            let config = AppLockConfig::load(&state.db);
+            if !config.enabled() || !can_unlock(&state.db, &config) {
+                continue;
+            }
+            let reason = if config.lock_on_os_lock && gap > WATCH_INTERVAL + SLEEP_GAP {
+                Some(LockReason::Resumed)
+            } else if config.idle_minutes > 0
+                && state.app_lock.idle_for() >= Duration::from_secs(u64::from(config.idle_minutes) * 60)
+            {
+                Some(LockReason::Idle)
+            } else if config.lock_on_os_lock && vault_autolock::os_session_locked() {
+                Some(LockReason::OsLocked)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                engage(&app, &state, reason);
+            }
+        }
+    });
+}
@@ -3,7 +3,11 @@
 //! This module is intentionally "plumbing only": interfaces + platform-neutral helpers.
 
 pub mod paths;
+pub mod pdf;
+pub mod ping;
+pub mod screen_lock;
 pub mod shell;
 pub mod ssh;
+pub mod user_presence;
 pub mod vault;
 
@@ -0,0 +1,87 @@
+//! Locating a headless renderer that can print HTML to PDF.
+//!
+//! We don't bundle one: a Chromium-family browser (Chrome, Chromium, Edge) is on most machines,
+//! and `wkhtmltopdf` is the usual fallback on servers.
+
+use std::path::{Path, PathBuf};
+
+use super::shell::ShellCommand;
+
+const CHROMIUM_PROGRAMS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "msedge",
+    "microsoft-edge",
+];
+
+#[cfg(target_os = "macos")]
+fn known_browser_locations() -> Vec<PathBuf> {
+    [
+        "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+        "/Applications/Chromium.app/Contents/MacOS/Chromium",
+        "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect()
+}
+
+#[cfg(windows)]
+fn known_browser_locations() -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for var in ["ProgramFiles", "ProgramFiles(x86)", "LocalAppData"] {
+        if let Ok(base) = std::env::var(var) {
+            let base = Path::new(&base);
+            out.push(base.join("Google").join("Chrome").join("Application").join("chrome.exe"));
+            out.push(base.join("Microsoft").join("Edge").join("Application").join("msedge.exe"));
+        }
+    }
+    out
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn known_browser_locations() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+fn file_url(path: &Path) -> String {
+    let s = path.to_string_lossy().replace('\\', "/");
+    if s.starts_with('/') {
+        format!("file://{s}")
+    } else {
+        format!("file:///{s}")
+    }
+}
+
+/// Command that prints the HTML file `input` to the PDF `output`, if a renderer is installed.
+pub fn html_to_pdf_command(input: &Path, output: &Path) -> Option<ShellCommand> {
+    let browser = CHROMIUM_PROGRAMS
+        .iter()
+        .find_map(|p| which::which(p).ok())
+        .or_else(|| known_browser_locations().into_iter().find(|p| p.exists()));
+    if let Some(browser) = browser {
+        return Some(ShellCommand::new(
+            browser.to_string_lossy().to_string(),
+            vec![
+                "--headless".to_string(),
+                "--disable-gpu".to_string(),
+                "--no-pdf-header-footer".to_string(),
+                format!("--print-to-pdf={}", output.to_string_lossy()),
+                file_url(input),
+            ],
+        ));
+    }
+    which::which("wkhtmltopdf").ok().map(|p| {
+        ShellCommand::new(
+            p.to_string_lossy().to_string(),
+            vec![
+                "--quiet".to_string(),
+                input.to_string_lossy().to_string(),
+                output.to_string_lossy().to_string(),
+            ],
+        )
+    })
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use crate::arch::shell::ShellCommand;
+
+/// A single ICMP echo via the system `ping` (raw sockets need privileges we don't have).
+///
+/// Exits 0 when a reply arrived within `timeout`.
+pub fn ping_command(host: &str, timeout: Duration) -> ShellCommand {
+    #[cfg(windows)]
+    {
+        ShellCommand::new(
+            "ping",
+            vec![
+                "-n".to_string(),
+                "1".to_string(),
+                "-w".to_string(),
+                timeout.as_millis().to_string(),
+                host.to_string(),
+            ],
+        )
+    }
+
+    // macOS takes -W in milliseconds, Linux in seconds.
+    #[cfg(target_os = "macos")]
+    {
+        ShellCommand::new(
+            "ping",
+            vec![
+                "-c".to_string(),
+                "1".to_string(),
+                "-W".to_string(),
+                timeout.as_millis().to_string(),
+                host.to_string(),
+            ],
+        )
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        ShellCommand::new(
+            "ping",
+            vec![
+                "-c".to_string(),
+                "1".to_string(),
+                "-W".to_string(),
+                timeout.as_secs().max(1).to_string(),
+                host.to_string(),
+            ],
+        )
+    }
+}
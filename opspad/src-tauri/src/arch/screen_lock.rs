@@ -0,0 +1,62 @@
+//! Asking the OS whether the user's session is currently locked.
+//!
+//! There's no portable API for this, so each platform gets a small probe command whose output
+//! contains a known marker while the screen is locked.
+
+use super::shell::ShellCommand;
+
+pub struct LockProbe {
+    pub command: ShellCommand,
+    locked_marker: &'static str,
+}
+
+impl LockProbe {
+    pub fn is_locked(&self, stdout: &str) -> bool {
+        stdout.contains(self.locked_marker)
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn probe() -> Option<LockProbe> {
+    // The console user's session dictionary carries this flag only while locked.
+    Some(LockProbe {
+        command: ShellCommand::new("ioreg", vec!["-n".to_string(), "Root".to_string(), "-d1".to_string()]),
+        locked_marker: "\"CGSSessionScreenIsLocked\"=Yes",
+    })
+}
+
+#[cfg(windows)]
+pub fn probe() -> Option<LockProbe> {
+    // LogonUI owns the lock screen; it isn't running in an unlocked session.
+    Some(LockProbe {
+        command: ShellCommand::new(
+            "tasklist",
+            vec![
+                "/FI".to_string(),
+                "IMAGENAME eq LogonUI.exe".to_string(),
+                "/NH".to_string(),
+            ],
+        ),
+        locked_marker: "LogonUI.exe",
+    })
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn probe() -> Option<LockProbe> {
+    // systemd-logind tracks the hint screen lockers set; without logind we can't tell.
+    let loginctl = which::which("loginctl").ok()?;
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    Some(LockProbe {
+        command: ShellCommand::new(
+            loginctl.to_string_lossy().to_string(),
+            vec![
+                "show-session".to_string(),
+                session,
+                "-p".to_string(),
+                "LockedHint".to_string(),
+                "--value".to_string(),
+            ],
+        ),
+        locked_marker: "yes",
+    })
+}
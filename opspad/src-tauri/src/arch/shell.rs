@@ -93,3 +93,25 @@ pub fn default_shell_command() -> ShellCommand {
         ShellCommand::new("zsh", vec![])
     }
 }
+
+/// Shell invocation that runs `command` non-interactively and exits (headless jobs).
+pub fn exec_command(command: &str) -> ShellCommand {
+    let shell = default_shell_command();
+    #[cfg(windows)]
+    {
+        ShellCommand::new(
+            shell.program,
+            vec![
+                "-NoProfile".to_string(),
+                "-NonInteractive".to_string(),
+                "-Command".to_string(),
+                command.to_string(),
+            ],
+        )
+    }
+
+    #[cfg(not(windows))]
+    {
+        ShellCommand::new(shell.program, vec!["-c".to_string(), command.to_string()])
+    }
+}
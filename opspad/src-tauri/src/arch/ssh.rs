@@ -49,3 +49,87 @@ pub fn ssh_program_checked() -> Result<String, String> {
 
     Err("ssh binary not found. Install OpenSSH client or set OPSPAD_SSH to a full path.".to_string())
 }
+
+/// Resolve the `sftp` client that comes with the resolved `ssh`, falling back to PATH.
+pub fn sftp_program_checked() -> Result<String, String> {
+    let ssh = ssh_program_checked()?;
+    let ssh = std::path::Path::new(&ssh);
+    if ssh.parent().is_some_and(|dir| !dir.as_os_str().is_empty()) {
+        let sftp = ssh.with_file_name(if cfg!(windows) { "sftp.exe" } else { "sftp" });
+        if sftp.exists() {
+            return Ok(sftp.to_string_lossy().to_string());
+        }
+    }
+    which::which("sftp")
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|_| "sftp binary not found. Install the OpenSSH client.".to_string())
+}
+
+/// `s` as a single word for the remote POSIX shell.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Arguments for a non-interactive `ssh` invocation that runs `remote_command` and exits.
+///
+/// `BatchMode` makes ssh fail fast instead of prompting for passwords/host keys, since there is
+/// no terminal to answer them.
+pub fn exec_args(
+    user: &str,
+    host: &str,
+    port: Option<u16>,
+    identity_file: Option<&str>,
+    remote_command: &str,
+) -> Vec<String> {
+    let mut args = vec![
+        "-T".to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+    ];
+    if let Some(p) = port {
+        args.push("-p".to_string());
+        args.push(p.to_string());
+    }
+    if let Some(id) = identity_file.map(str::trim).filter(|s| !s.is_empty()) {
+        args.push("-i".to_string());
+        args.push(id.to_string());
+    }
+    args.push(format!("{user}@{host}"));
+    args.push("--".to_string());
+    args.push(remote_command.to_string());
+    args
+}
+
+/// Options that reuse one connection per host across non-interactive `ssh` calls (OpenSSH
+/// connection multiplexing), for commands run on an interval. The master lingers for a few
+/// minutes after the last call.
+///
+/// Windows' OpenSSH doesn't support multiplexing; there each call connects anew.
+pub fn multiplex_args() -> Vec<String> {
+    #[cfg(windows)]
+    {
+        Vec::new()
+    }
+
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+
+        // Socket paths are limited to ~100 bytes, too short for the app data dir on macOS.
+        let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+        let dir = std::path::Path::new("/tmp").join(format!("opspad-ssh-{user}"));
+        if std::fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir).is_err() {
+            return Vec::new();
+        }
+        vec![
+            "-o".to_string(),
+            "ControlMaster=auto".to_string(),
+            "-o".to_string(),
+            format!("ControlPath={}/%C", dir.display()),
+            "-o".to_string(),
+            "ControlPersist=5m".to_string(),
+        ]
+    }
+}
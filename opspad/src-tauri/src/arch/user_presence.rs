@@ -0,0 +1,130 @@
+//! Asking the user to prove they're at the keyboard with the platform's own prompt.
+//!
+//! Windows Hello and Touch ID have no CLI, so we drive their APIs from the scripting host each
+//! OS ships (Windows PowerShell's WinRT projection, JavaScript for Automation). On Linux, polkit's
+//! agent prompt (password or fingerprint, per PAM) stands in.
+
+use super::shell::ShellCommand;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Presence {
+    Verified,
+    Denied,
+    /// No biometric or credential prompt is set up on this machine.
+    Unavailable,
+}
+
+pub struct PresenceCheck {
+    pub command: ShellCommand,
+    unavailable_exit: Option<i32>,
+}
+
+impl PresenceCheck {
+    /// Interpret the probe's exit code: 0 is verified, the platform's "not set up" code is
+    /// unavailable, and anything else (cancelled, failed, timed out) is denied.
+    pub fn result(&self, exit_code: Option<i32>) -> Presence {
+        match exit_code {
+            Some(0) => Presence::Verified,
+            code if code.is_some() && code == self.unavailable_exit => Presence::Unavailable,
+            _ => Presence::Denied,
+        }
+    }
+}
+
+#[cfg(windows)]
+const HELLO_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+Add-Type -AssemblyName System.Runtime.WindowsRuntime
+$asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {
+  $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and
+  $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1'
+} | Select-Object -First 1
+function Await($op, [Type]$type) {
+  $task = $asTask.MakeGenericMethod($type).Invoke($null, @($op))
+  $task.Wait(-1) | Out-Null
+  $task.Result
+}
+$ucv = [Windows.Security.Credentials.UI.UserConsentVerifier, Windows.Security.Credentials.UI, ContentType = WindowsRuntime]
+$availability = Await ($ucv::CheckAvailabilityAsync()) ([Windows.Security.Credentials.UI.UserConsentVerifierAvailability])
+if ($availability -ne [Windows.Security.Credentials.UI.UserConsentVerifierAvailability]::Available) { exit 2 }
+$result = Await ($ucv::RequestVerificationAsync('__REASON__')) ([Windows.Security.Credentials.UI.UserConsentVerificationResult])
+if ($result -eq [Windows.Security.Credentials.UI.UserConsentVerificationResult]::Verified) { exit 0 }
+exit 1
+"#;
+
+#[cfg(windows)]
+pub fn presence_check(reason: &str) -> Option<PresenceCheck> {
+    use base64::Engine as _;
+
+    // Only Windows PowerShell (5.1) projects WinRT types; pwsh 7 dropped it.
+    let powershell = which::which("powershell.exe").ok()?;
+    let script = HELLO_SCRIPT.replace("__REASON__", &reason.replace('\'', "''"));
+    // -EncodedCommand (UTF-16LE, base64) sidesteps command-line quoting entirely.
+    let utf16: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    Some(PresenceCheck {
+        command: ShellCommand::new(
+            powershell.to_string_lossy().to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-NonInteractive".to_string(),
+                "-EncodedCommand".to_string(),
+                base64::engine::general_purpose::STANDARD.encode(utf16),
+            ],
+        ),
+        unavailable_exit: Some(2),
+    })
+}
+
+#[cfg(target_os = "macos")]
+const TOUCH_ID_SCRIPT: &str = r#"
+ObjC.import('LocalAuthentication');
+ObjC.import('stdlib');
+function run(argv) {
+  // LAPolicyDeviceOwnerAuthentication: Touch ID, falling back to the login password.
+  const policy = 2;
+  const context = $.LAContext.alloc.init;
+  if (!context.canEvaluatePolicyError(policy, null)) $.exit(2);
+  let verified = null;
+  context.evaluatePolicyLocalizedReasonReply(policy, argv[0], (ok) => { verified = ok; });
+  while (verified === null) {
+    $.NSRunLoop.currentRunLoop.runUntilDate($.NSDate.dateWithTimeIntervalSinceNow(0.1));
+  }
+  $.exit(verified ? 0 : 1);
+}
+"#;
+
+#[cfg(target_os = "macos")]
+pub fn presence_check(reason: &str) -> Option<PresenceCheck> {
+    Some(PresenceCheck {
+        command: ShellCommand::new(
+            "osascript",
+            vec![
+                "-l".to_string(),
+                "JavaScript".to_string(),
+                "-e".to_string(),
+                TOUCH_ID_SCRIPT.to_string(),
+                reason.to_string(),
+            ],
+        ),
+        unavailable_exit: Some(2),
+    })
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn presence_check(_reason: &str) -> Option<PresenceCheck> {
+    // polkit's agent shows its own message; the reason can't be passed through.
+    let pkcheck = which::which("pkcheck").ok()?;
+    Some(PresenceCheck {
+        command: ShellCommand::new(
+            pkcheck.to_string_lossy().to_string(),
+            vec![
+                "--action-id".to_string(),
+                "org.freedesktop.policykit.exec".to_string(),
+                "--process".to_string(),
+                std::process::id().to_string(),
+                "--allow-user-interaction".to_string(),
+            ],
+        ),
+        unavailable_exit: None,
+    })
+}
@@ -1,9 +1,14 @@
 use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
 
 #[derive(Debug)]
 pub enum VaultError {
     Unsupported,
     NotFound,
+    Locked,
     Backend(String),
 }
 
@@ -12,6 +17,7 @@ impl fmt::Display for VaultError {
         match self {
             VaultError::Unsupported => write!(f, "vault operation unsupported on this platform"),
             VaultError::NotFound => write!(f, "secret not found"),
+            VaultError::Locked => write!(f, "vault is locked"),
             VaultError::Backend(msg) => write!(f, "vault backend error: {msg}"),
         }
     }
@@ -19,10 +25,227 @@ impl fmt::Display for VaultError {
 
 impl std::error::Error for VaultError {}
 
+/// How long before `rotate_by` a secret counts as due. Writing a new value inside this window
+/// (or later) counts as the rotation and clears the date.
+pub const ROTATION_WINDOW_SECS: i64 = 14 * 24 * 60 * 60;
+
+/// What we know about a stored secret, without its value.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretMetadata {
+    pub key: String,
+    pub label: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+    pub last_used_at: Option<i64>,
+    /// Reading the value for the user needs a platform verification prompt first.
+    pub require_presence: bool,
+    /// Epoch seconds by which the secret should be rotated.
+    pub rotate_by: Option<i64>,
+}
+
 pub trait VaultProvider: Send + Sync {
     fn set_secret(&self, key: &str, secret: &[u8]) -> Result<(), VaultError>;
     fn get_secret(&self, key: &str) -> Result<Option<Vec<u8>>, VaultError>;
     fn delete_secret(&self, key: &str) -> Result<(), VaultError>;
+
+    /// Every stored secret's metadata. Backends that can't enumerate (the OS keyring) leave
+    /// this unsupported; wrap them in [`IndexedVault`].
+    fn list_keys(&self) -> Result<Vec<SecretMetadata>, VaultError> {
+        Err(VaultError::Unsupported)
+    }
+
+    fn metadata(&self, _key: &str) -> Result<Option<SecretMetadata>, VaultError> {
+        Err(VaultError::Unsupported)
+    }
+
+    fn set_label(&self, _key: &str, _label: Option<&str>) -> Result<(), VaultError> {
+        Err(VaultError::Unsupported)
+    }
+
+    fn set_require_presence(&self, _key: &str, _required: bool) -> Result<(), VaultError> {
+        Err(VaultError::Unsupported)
+    }
+
+    fn set_rotate_by(&self, _key: &str, _rotate_by: Option<i64>) -> Result<(), VaultError> {
+        Err(VaultError::Unsupported)
+    }
+}
+
+/// Bookkeeping store for [`IndexedVault`]: which keys exist and when they were touched.
+pub trait SecretIndex: Send + Sync {
+    fn record_set(&self, key: &str) -> Result<(), String>;
+    fn record_used(&self, key: &str) -> Result<(), String>;
+    fn record_deleted(&self, key: &str) -> Result<(), String>;
+    fn list(&self) -> Result<Vec<SecretMetadata>, String>;
+    fn get(&self, key: &str) -> Result<Option<SecretMetadata>, String>;
+    fn set_label(&self, key: &str, label: Option<&str>) -> Result<(), String>;
+    fn set_require_presence(&self, key: &str, required: bool) -> Result<(), String>;
+    fn set_rotate_by(&self, key: &str, rotate_by: Option<i64>) -> Result<(), String>;
+}
+
+/// Adds key listing and metadata to a provider that can only get/set/delete by key.
+///
+/// The index only learns about secrets written or read through it; secrets stored by older
+/// versions show up the first time they are used.
+pub struct IndexedVault {
+    inner: Box<dyn VaultProvider>,
+    index: Arc<dyn SecretIndex>,
+}
+
+impl IndexedVault {
+    pub fn new(inner: Box<dyn VaultProvider>, index: Arc<dyn SecretIndex>) -> Self {
+        Self { inner, index }
+    }
+}
+
+impl VaultProvider for IndexedVault {
+    fn set_secret(&self, key: &str, secret: &[u8]) -> Result<(), VaultError> {
+        self.inner.set_secret(key, secret)?;
+        self.index.record_set(key).map_err(VaultError::Backend)
+    }
+
+    fn get_secret(&self, key: &str) -> Result<Option<Vec<u8>>, VaultError> {
+        let secret = self.inner.get_secret(key)?;
+        if secret.is_some() {
+            // Usage tracking is best-effort; never fail a read over it.
+            let _ = self.index.record_used(key);
+        }
+        Ok(secret)
+    }
+
+    fn delete_secret(&self, key: &str) -> Result<(), VaultError> {
+        self.inner.delete_secret(key)?;
+        self.index.record_deleted(key).map_err(VaultError::Backend)
+    }
+
+    fn list_keys(&self) -> Result<Vec<SecretMetadata>, VaultError> {
+        self.index.list().map_err(VaultError::Backend)
+    }
+
+    fn metadata(&self, key: &str) -> Result<Option<SecretMetadata>, VaultError> {
+        self.index.get(key).map_err(VaultError::Backend)
+    }
+
+    fn set_label(&self, key: &str, label: Option<&str>) -> Result<(), VaultError> {
+        self.index.set_label(key, label).map_err(VaultError::Backend)
+    }
+
+    fn set_require_presence(&self, key: &str, required: bool) -> Result<(), VaultError> {
+        self.index.set_require_presence(key, required).map_err(VaultError::Backend)
+    }
+
+    fn set_rotate_by(&self, key: &str, rotate_by: Option<i64>) -> Result<(), VaultError> {
+        self.index.set_rotate_by(key, rotate_by).map_err(VaultError::Backend)
+    }
+}
+
+/// Locked/unlocked state shared by [`LockingVault`] and whatever decides when to lock.
+pub struct VaultLock {
+    inner: Mutex<LockState>,
+}
+
+struct LockState {
+    locked: bool,
+    last_activity: Instant,
+}
+
+impl VaultLock {
+    pub fn new(locked: bool) -> Self {
+        Self {
+            inner: Mutex::new(LockState {
+                locked,
+                last_activity: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.lock().expect("poisoned vault lock").locked
+    }
+
+    /// Returns whether the state actually changed. Unlocking restarts the idle clock.
+    pub fn set_locked(&self, locked: bool) -> bool {
+        let mut st = self.inner.lock().expect("poisoned vault lock");
+        if st.locked == locked {
+            return false;
+        }
+        st.locked = locked;
+        if !locked {
+            st.last_activity = Instant::now();
+        }
+        true
+    }
+
+    /// Note user activity; the idle timer counts from the last call.
+    pub fn touch(&self) {
+        self.inner.lock().expect("poisoned vault lock").last_activity = Instant::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.inner.lock().expect("poisoned vault lock").last_activity.elapsed()
+    }
+}
+
+/// Refuses to read, write, or delete secrets while `lock` is locked.
+///
+/// Listing and labels stay available: they never expose a secret value.
+pub struct LockingVault {
+    inner: Box<dyn VaultProvider>,
+    lock: Arc<VaultLock>,
+}
+
+impl LockingVault {
+    pub fn new(inner: Box<dyn VaultProvider>, lock: Arc<VaultLock>) -> Self {
+        Self { inner, lock }
+    }
+
+    fn check(&self) -> Result<(), VaultError> {
+        if self.lock.is_locked() {
+            return Err(VaultError::Locked);
+        }
+        self.lock.touch();
+        Ok(())
+    }
+}
+
+impl VaultProvider for LockingVault {
+    fn set_secret(&self, key: &str, secret: &[u8]) -> Result<(), VaultError> {
+        self.check()?;
+        self.inner.set_secret(key, secret)
+    }
+
+    fn get_secret(&self, key: &str) -> Result<Option<Vec<u8>>, VaultError> {
+        self.check()?;
+        self.inner.get_secret(key)
+    }
+
+    fn delete_secret(&self, key: &str) -> Result<(), VaultError> {
+        self.check()?;
+        self.inner.delete_secret(key)
+    }
+
+    fn list_keys(&self) -> Result<Vec<SecretMetadata>, VaultError> {
+        self.inner.list_keys()
+    }
+
+    fn metadata(&self, key: &str) -> Result<Option<SecretMetadata>, VaultError> {
+        self.inner.metadata(key)
+    }
+
+    fn set_label(&self, key: &str, label: Option<&str>) -> Result<(), VaultError> {
+        self.inner.set_label(key, label)
+    }
+
+    fn set_require_presence(&self, key: &str, required: bool) -> Result<(), VaultError> {
+        self.inner.set_require_presence(key, required)
+    }
+
+    fn set_rotate_by(&self, key: &str, rotate_by: Option<i64>) -> Result<(), VaultError> {
+        self.inner.set_rotate_by(key, rotate_by)
+    }
 }
 
 /// MVP vault provider.
@@ -73,10 +296,12 @@ impl VaultProvider for OsKeyringVault {
     }
 }
 
-/// Construct the MVP vault provider.
+/// Construct the MVP vault provider, indexed by `index` so secrets can be listed, and gated
+/// by `lock`.
 ///
 /// Callers should depend on the `VaultProvider` trait, not on the concrete type,
 /// so we can swap/extend implementations later (macOS Keychain, encrypted vault, etc.).
-pub fn default_vault_provider() -> Box<dyn VaultProvider> {
-    Box::new(OsKeyringVault::new("OpsPad"))
+pub fn default_vault_provider(index: Arc<dyn SecretIndex>, lock: Arc<VaultLock>) -> Box<dyn VaultProvider> {
+    let indexed = IndexedVault::new(Box::new(OsKeyringVault::new("OpsPad")), index);
+    Box::new(LockingVault::new(Box::new(indexed), lock))
 }
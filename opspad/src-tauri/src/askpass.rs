@@ -0,0 +1,392 @@
+//! SSH login prompts as structured events instead of text in the terminal.
+//!
+//! When turned on, ssh sessions are started with OpsPad itself as `SSH_ASKPASS` (and
+//! `SSH_ASKPASS_REQUIRE=force`, OpenSSH 8.4+), so every question ssh asks (password, key
+//! passphrase, keyboard-interactive prompts such as 2FA codes, host key confirmation) runs a
+//! short-lived copy of the app instead of reading from the terminal. That copy hands the prompt to
+//! the running app over a loopback socket ([`handle_invocation`]) and prints the answer for ssh.
+//!
+//! The app answers from the host's bound credentials where it can (as `ssh_login` does for
+//! prompts in the terminal), and otherwise emits `auth:prompt` for the UI, which replies with
+//! `auth_prompt_answer`. `auth:prompt_closed` follows when a prompt is answered, cancelled or times
+//! out. Older ssh versions ignore the bridge and keep prompting in the terminal.
+//!
+//! Notifications (a security key waiting for a touch) need no answer: their askpass copy waits
+//! until ssh ends it, which is when `auth:prompt_closed` follows.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::db::{Db, Host};
+use crate::{security_key, ssh_login, AppState};
+
+const SETTINGS_KEY: &str = "auth_prompts";
+/// Set for ssh (and so for the askpass copy of the app): `<port>:<token>:<channel>`.
+const BRIDGE_ENV: &str = "OPSPAD_ASKPASS";
+/// How long a prompt waits for the user before ssh is told it was cancelled.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl AuthPromptConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthPromptKind {
+    Password,
+    Passphrase,
+    /// A keyboard-interactive verification code (2FA).
+    Otp,
+    /// "Are you sure you want to continue connecting (yes/no/[fingerprint])?"
+    HostKey,
+    /// A yes/no question (`SSH_ASKPASS_PROMPT=confirm`).
+    Confirm,
+    /// Information only (`SSH_ASKPASS_PROMPT=none`): nothing to answer.
+    Notice,
+    /// A security key waiting to be touched (a notice too); see `security_key`.
+    Touch,
+    /// Anything else, typically a keyboard-interactive question (a PIN, a security question).
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPrompt {
+    pub id: String,
+    /// `None` for a prompt that arrived before the session finished opening.
+    pub session_id: Option<String>,
+    pub kind: AuthPromptKind,
+    /// ssh's text, as it would have appeared in the terminal.
+    pub prompt: String,
+    /// Whether the answer may be shown as typed.
+    pub echo: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptClosedEvent {
+    pub id: String,
+    pub answered: bool,
+}
+
+/// What the askpass copy sends.
+#[derive(Serialize, Deserialize)]
+struct BridgeRequest {
+    token: String,
+    channel: String,
+    prompt: String,
+    /// `SSH_ASKPASS_PROMPT`: "confirm", "none" or empty.
+    #[serde(default)]
+    mode: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BridgeReply {
+    answer: Option<String>,
+}
+
+/// The ssh process one channel was handed to.
+struct Channel {
+    session_id: Option<String>,
+    host: Option<Host>,
+    answered: Vec<ssh_login::Prompt>,
+}
+
+struct Pending {
+    prompt: AuthPrompt,
+    reply: Sender<Option<String>>,
+}
+
+#[derive(Default)]
+pub struct AuthPrompts {
+    /// Port and token of the bridge listener, once started.
+    endpoint: Mutex<Option<(u16, String)>>,
+    channels: Mutex<HashMap<String, Channel>>,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl AuthPrompts {
+    /// Prompts waiting for the user.
+    pub fn list(&self) -> Vec<AuthPrompt> {
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.values().map(|p| p.prompt.clone()).collect()
+    }
+
+    /// Answer a prompt; `None` cancels it (ssh treats that like an empty Ctrl-C).
+    pub fn answer(&self, id: &str, answer: Option<String>) -> Result<(), String> {
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let p = pending.get(id).ok_or_else(|| "the prompt is no longer waiting".to_string())?;
+        p.reply.send(answer).map_err(|_| "the prompt is no longer waiting".to_string())
+    }
+
+    /// Note which session an ssh started with [`session_env`]'s channel became.
+    pub fn bind(&self, channel: &str, session_id: &str) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(c) = channels.get_mut(channel) {
+            c.session_id = Some(session_id.to_string());
+        }
+    }
+
+    /// Forget a channel whose ssh never started.
+    pub fn discard(&self, channel: &str) {
+        self.channels.lock().unwrap_or_else(|e| e.into_inner()).remove(channel);
+    }
+
+    /// Forget the channel of a session that is gone.
+    pub fn release(&self, session_id: &str) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels.retain(|_, c| c.session_id.as_deref() != Some(session_id));
+    }
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// What an ssh session needs to use the bridge.
+pub struct BridgeEnv {
+    /// To [`AuthPrompts::bind`] once the session id is known.
+    pub channel: String,
+    pub env: Vec<(String, String)>,
+}
+
+/// Environment for an ssh session so its prompts come through the bridge. `None` when the
+/// feature is off.
+pub fn session_env(app: &AppHandle, state: &AppState, host: Option<Host>) -> Result<Option<BridgeEnv>, String> {
+    if !AuthPromptConfig::load(&state.db).enabled {
+        return Ok(None);
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let (port, token) = ensure_server(app, state)?;
+    let channel = Uuid::new_v4().to_string();
+    state.auth_prompts.channels.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        channel.clone(),
+        Channel {
+            session_id: None,
+            host,
+            answered: Vec::new(),
+        },
+    );
+    Ok(Some(BridgeEnv {
+        env: vec![
+            ("SSH_ASKPASS".to_string(), exe.to_string_lossy().to_string()),
+            ("SSH_ASKPASS_REQUIRE".to_string(), "force".to_string()),
+            (BRIDGE_ENV.to_string(), format!("{port}:{token}:{channel}")),
+        ],
+        channel,
+    }))
+}
+
+fn ensure_server(app: &AppHandle, state: &AppState) -> Result<(u16, String), String> {
+    let mut endpoint = state.auth_prompts.endpoint.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(endpoint) = endpoint.as_ref() {
+        return Ok(endpoint.clone());
+    }
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let token = new_token()?;
+    let app = app.clone();
+    let expected = token.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let (app, expected) = (app.clone(), expected.clone());
+            // Each prompt may wait minutes for the user.
+            thread::spawn(move || {
+                if let Err(e) = serve(&app, &expected, stream) {
+                    log::warn!("askpass request failed: {e}");
+                }
+            });
+        }
+    });
+    *endpoint = Some((port, token.clone()));
+    Ok((port, token))
+}
+
+fn classify(prompt: &str, mode: &str) -> (AuthPromptKind, bool) {
+    let lower = prompt.to_lowercase();
+    match mode {
+        "none" if prompt.contains(security_key::TOUCH_MARKER) => (AuthPromptKind::Touch, true),
+        "none" => (AuthPromptKind::Notice, true),
+        "confirm" => (AuthPromptKind::Confirm, true),
+        _ if lower.contains("continue connecting") => (AuthPromptKind::HostKey, true),
+        _ if lower.contains("passphrase") => (AuthPromptKind::Passphrase, false),
+        _ if ["verification code", "one-time password", "otp", "authenticator code", "token code"]
+            .iter()
+            .any(|k| lower.contains(k)) =>
+        {
+            (AuthPromptKind::Otp, false)
+        }
+        _ if lower.contains("password") => (AuthPromptKind::Password, false),
+        _ => (AuthPromptKind::Other, false),
+    }
+}
+
+fn serve(app: &AppHandle, expected: &str, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_BYTES)).read_line(&mut line)?;
+    let request: BridgeRequest = serde_json::from_str(&line).map_err(std::io::Error::other)?;
+    if request.token != expected {
+        return Ok(());
+    }
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| s.inner().clone()) else {
+        return Ok(());
+    };
+    let answer = prompt(app, &state, &request, &stream);
+    if request.mode == "none" {
+        // ssh has ended the notification's askpass copy by now; there's no one to reply to.
+        return Ok(());
+    }
+    let mut json = serde_json::to_vec(&BridgeReply { answer }).map_err(std::io::Error::other)?;
+    json.push(b'\n');
+    (&stream).write_all(&json)
+}
+
+fn prompt(app: &AppHandle, state: &AppState, request: &BridgeRequest, stream: &TcpStream) -> Option<String> {
+    let prompts = &state.auth_prompts;
+    let (session_id, host) = {
+        let channels = prompts.channels.lock().unwrap_or_else(|e| e.into_inner());
+        // Only ssh processes we started know a channel.
+        let channel = channels.get(&request.channel)?;
+        (channel.session_id.clone(), channel.host.clone())
+    };
+    let (kind, echo) = classify(&request.prompt, &request.mode);
+
+    if let Some(host) = host.as_ref().filter(|_| matches!(kind, AuthPromptKind::Password | AuthPromptKind::Passphrase | AuthPromptKind::Otp)) {
+        let mut answered = {
+            let mut channels = prompts.channels.lock().unwrap_or_else(|e| e.into_inner());
+            std::mem::take(&mut channels.get_mut(&request.channel)?.answered)
+        };
+        let secret = ssh_login::answer_for(state, host, &request.prompt, &mut answered);
+        if let Some(c) = prompts.channels.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&request.channel) {
+            c.answered = answered;
+        }
+        if secret.is_some() {
+            return secret;
+        }
+    }
+
+    let prompt = AuthPrompt {
+        id: Uuid::new_v4().to_string(),
+        session_id,
+        kind,
+        prompt: request.prompt.clone(),
+        echo,
+    };
+    if matches!(kind, AuthPromptKind::Notice | AuthPromptKind::Touch) {
+        let _ = app.emit("auth:prompt", &prompt);
+        // The askpass copy waits for a reply that never comes, until ssh ends it.
+        let _ = stream.set_read_timeout(Some(PROMPT_TIMEOUT));
+        let ended = match (&*stream).read(&mut [0u8; 1]) {
+            Ok(_) => true,
+            Err(e) => !matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut),
+        };
+        let _ = app.emit(
+            "auth:prompt_closed",
+            AuthPromptClosedEvent {
+                id: prompt.id,
+                answered: ended,
+            },
+        );
+        return None;
+    }
+    let (tx, rx) = mpsc::channel();
+    let id = prompt.id.clone();
+    prompts.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        id.clone(),
+        Pending {
+            prompt: prompt.clone(),
+            reply: tx,
+        },
+    );
+    let _ = app.emit("auth:prompt", &prompt);
+    let answer = rx.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+    prompts.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    let _ = app.emit(
+        "auth:prompt_closed",
+        AuthPromptClosedEvent {
+            id,
+            answered: answer.is_some(),
+        },
+    );
+    answer
+}
+
+/// Entry point for `main`, before anything else: when this process was started by ssh as its
+/// askpass program, relay the prompt and return the exit code (0 with the answer on stdout, 1 when
+/// cancelled).
+pub fn handle_invocation() -> Option<i32> {
+    let bridge = std::env::var(BRIDGE_ENV).ok()?;
+    // ssh passes the prompt as the only argument; anything else is a normal launch from a shell
+    // that inherited the variable.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() != 1 || std::env::var_os("SSH_ASKPASS").is_none() {
+        return None;
+    }
+    let mut parts = bridge.splitn(3, ':');
+    let (Some(port), Some(token), Some(channel)) = (parts.next(), parts.next(), parts.next()) else {
+        return Some(1);
+    };
+    let request = BridgeRequest {
+        token: token.to_string(),
+        channel: channel.to_string(),
+        prompt: args[0].clone(),
+        mode: std::env::var("SSH_ASKPASS_PROMPT").unwrap_or_default(),
+    };
+    let Ok(port) = port.parse() else {
+        return Some(1);
+    };
+    match relay(port, &request) {
+        Some(answer) => {
+            println!("{answer}");
+            Some(0)
+        }
+        None => Some(1),
+    }
+}
+
+fn relay(port: u16, request: &BridgeRequest) -> Option<String> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(PROMPT_TIMEOUT + IO_TIMEOUT)).ok()?;
+    let mut json = serde_json::to_vec(request).ok()?;
+    json.push(b'\n');
+    (&stream).write_all(&json).ok()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    serde_json::from_str::<BridgeReply>(&line).ok()?.answer
+}
@@ -0,0 +1,592 @@
+//! A local HTTP API for scripts and external tools: list hosts, open a session and run a command
+//! in it, start headless jobs, read history.
+//!
+//! Off by default; turned on in settings (key `automation_api`). It listens on 127.0.0.1 only and
+//! every request needs `Authorization: Bearer <token>`. The token is kept in the vault under
+//! [`TOKEN_KEY`], so the API can't start while the vault is locked. The port and token are written
+//! to `<app data>/automation.json` while it runs, for scripts to pick up. Requests from browsers (any
+//! `Origin` header) and requests addressed to another host name (DNS rebinding) are refused.
+//!
+//! Commands run through the API, in a session or as a job, get the same treatment as CommandDock
+//! runs: plugins, the environment's destructive-command policy (pass `confirmation` where the
+//! policy asks for one, with `confirmedHash`, the SHA-256 hex of the command, and `approval` where
+//! it needs four eyes), the confirmation audit trail, history. Sessions it opens show up as tabs (`automation:session_opened`).
+//!
+//! Routes (JSON in and out):
+//! - `GET /v1/hosts`
+//! - `GET /v1/sessions`, `POST /v1/sessions` `{"hostId"?, "environmentTag"?}` (no host: local)
+//! - `POST /v1/sessions/<id>/run` `{"command", "confirmation"?, "confirmedHash"?, "approval"?}`
+//! - `GET /v1/sessions/<id>/output?lines=N`, `DELETE /v1/sessions/<id>`
+//! - `GET /v1/jobs?limit=N`, `POST /v1/jobs` `{"target", "command", "timeoutSecs"?, "confirmation"?,
+//!   "confirmedHash"?, "approval"?}`,
+//!   `GET /v1/jobs/<id>`, `POST /v1/jobs/<id>/cancel`
+//! - `GET /v1/history?limit=N`
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::paths;
+use crate::arch::vault::VaultProvider;
+use crate::db::Db;
+use crate::redact::Redactor;
+use crate::terminal::session_manager::WriteMeta;
+use crate::{jobs, transcript, AppState};
+
+pub const TOKEN_KEY: &str = "opspad.automation-api-token";
+
+const SETTINGS_KEY: &str = "automation_api";
+const ENDPOINT_FILE: &str = "automation.json";
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const DEFAULT_OUTPUT_LINES: usize = 200;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Fixed port; `None` picks a free one each start.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Generated when the API is first turned on. Stored in the vault, not with the settings;
+    /// `None` while the vault is locked.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl AutomationApiConfig {
+    pub fn load(db: &Db, vault: &dyn VaultProvider) -> Self {
+        let mut config: Self = db
+            .settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default();
+        // Earlier versions kept the token in the settings; move it to the vault.
+        if let Some(token) = config.token.take().filter(|t| !t.is_empty()) {
+            match vault.set_secret(TOKEN_KEY, token.as_bytes()) {
+                Ok(()) => {
+                    if let Err(e) = config.save(db, vault) {
+                        log::warn!("automation api token moved to the vault, but settings kept a copy: {e}");
+                    }
+                }
+                Err(e) => log::warn!("automation api token not moved to the vault: {e}"),
+            }
+        }
+        config.token = vault
+            .get_secret(TOKEN_KEY)
+            .ok()
+            .flatten()
+            .and_then(|b| String::from_utf8(b).ok());
+        config
+    }
+
+    /// The token (when given) goes to the vault, the rest to the settings.
+    pub fn save(&self, db: &Db, vault: &dyn VaultProvider) -> Result<(), String> {
+        if let Some(token) = self.token.as_deref().filter(|t| !t.is_empty()) {
+            vault.set_secret(TOKEN_KEY, token.as_bytes()).map_err(|e| e.to_string())?;
+        }
+        let settings = Self {
+            token: None,
+            ..self.clone()
+        };
+        let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationApiStatus {
+    pub url: String,
+    pub port: u16,
+}
+
+#[derive(Serialize)]
+struct Endpoint<'a> {
+    url: &'a str,
+    token: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionOpenedEvent {
+    pub session_id: String,
+    pub host_id: Option<String>,
+    pub title: String,
+    pub environment_tag: String,
+}
+
+struct Running {
+    status: AutomationApiStatus,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+pub struct AutomationApi {
+    running: Mutex<Option<Running>>,
+}
+
+impl AutomationApi {
+    pub fn status(&self) -> Option<AutomationApiStatus> {
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|r| r.status.clone())
+    }
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 24];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compare without returning early, so response timing doesn't leak how much of a guess matched.
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn endpoint_path(app: &AppHandle) -> Option<PathBuf> {
+    paths::app_data_dir(app).ok().map(|dir| dir.join(ENDPOINT_FILE))
+}
+
+/// The file holds the token, so only the owner may read it (including a file left by an older
+/// version, which was created with default permissions).
+fn write_endpoint(path: &Path, json: &str) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(json.as_bytes())
+}
+
+/// A new token; the old one stops working once the API restarts (see [`apply`]).
+pub fn rotate_token(db: &Db, vault: &dyn VaultProvider) -> Result<AutomationApiConfig, String> {
+    let mut config = AutomationApiConfig::load(db, vault);
+    config.token = Some(new_token()?);
+    config.save(db, vault)?;
+    Ok(config)
+}
+
+/// Start, stop or restart the server to match the saved settings.
+pub fn apply(app: &AppHandle, state: &AppState) -> Result<Option<AutomationApiStatus>, String> {
+    let mut running = state.automation_api.running.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(old) = running.take() {
+        old.stop.store(true, Ordering::SeqCst);
+        if let Some(path) = endpoint_path(app) {
+            let _ = fs::remove_file(path);
+        }
+        log::info!("automation api stopped");
+    }
+    let config = AutomationApiConfig::load(&state.db, state.vault.as_ref());
+    if !config.enabled {
+        return Ok(None);
+    }
+    // Read again rather than trusting `config.token`: a locked vault must not get a fresh token.
+    let stored = state.vault.get_secret(TOKEN_KEY).map_err(|e| e.to_string())?;
+    let token = match stored.and_then(|b| String::from_utf8(b).ok()).filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let token = new_token()?;
+            state.vault.set_secret(TOKEN_KEY, token.as_bytes()).map_err(|e| e.to_string())?;
+            token
+        }
+    };
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port.unwrap_or(0))).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let status = AutomationApiStatus {
+        url: format!("http://127.0.0.1:{port}/v1"),
+        port,
+    };
+    if let Some(path) = endpoint_path(app) {
+        let endpoint = Endpoint {
+            url: &status.url,
+            token: &token,
+        };
+        let json = serde_json::to_string_pretty(&endpoint).map_err(|e| e.to_string())?;
+        if let Err(e) = write_endpoint(&path, &json) {
+            log::warn!("{}: {e}", path.display());
+        }
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    *running = Some(Running {
+        status: status.clone(),
+        stop: stop.clone(),
+    });
+
+    let server = Arc::new(Server {
+        app: app.clone(),
+        token,
+        port,
+    });
+    thread::spawn(move || server.accept_loop(listener, &stop));
+    log::info!("automation api listening on port {port}");
+    Ok(Some(status))
+}
+
+struct Server {
+    app: AppHandle,
+    token: String,
+    port: u16,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .find_map(|kv| kv.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+    }
+
+    fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, Failure> {
+        serde_json::from_slice(&self.body).map_err(|e| bad_request(format!("invalid body: {e}")))
+    }
+}
+
+/// An error response: status line and message.
+type Failure = (&'static str, String);
+type Reply = Result<(&'static str, Value), Failure>;
+
+fn bad_request(message: impl Into<String>) -> Failure {
+    ("400 Bad Request", message.into())
+}
+
+fn not_found(what: &str) -> Failure {
+    ("404 Not Found", format!("{what} not found"))
+}
+
+fn ok(value: impl Serialize) -> Reply {
+    serde_json::to_value(value)
+        .map(|v| ("200 OK", v))
+        .map_err(|e| ("500 Internal Server Error", e.to_string()))
+}
+
+fn read_request(stream: &TcpStream) -> std::io::Result<Request> {
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES + MAX_BODY_BYTES as u64));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && !line.trim().is_empty() {
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        line.clear();
+    }
+    let length: usize = headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Err(std::io::Error::other("request body too large"));
+    }
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body,
+    })
+}
+
+fn respond(mut stream: &TcpStream, status: &str, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+         X-Content-Type-Options: nosniff\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())
+}
+
+impl Server {
+    fn accept_loop(self: Arc<Self>, listener: TcpListener, stop: &AtomicBool) {
+        while !stop.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let server = self.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = server.serve(stream) {
+                            log::debug!("automation api request failed: {e}");
+                        }
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(e) => {
+                    log::warn!("automation api accept failed: {e}");
+                    thread::sleep(ACCEPT_POLL);
+                }
+            }
+        }
+        // Dropping the listener here closes the port.
+    }
+
+    fn serve(&self, stream: TcpStream) -> std::io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let request = match read_request(&stream) {
+            Ok(request) => request,
+            Err(e) => return respond(&stream, "400 Bad Request", &json!({ "error": e.to_string() })),
+        };
+        let reply = self.check(&request).and_then(|()| self.route(&request));
+        match reply {
+            Ok((status, body)) => respond(&stream, status, &body),
+            Err((status, error)) => respond(&stream, status, &json!({ "error": error })),
+        }
+    }
+
+    fn check(&self, request: &Request) -> Result<(), Failure> {
+        if request.header("origin").is_some() {
+            return Err(("403 Forbidden", "browser requests are not allowed".to_string()));
+        }
+        let host = request.header("host").unwrap_or("");
+        if host != format!("127.0.0.1:{}", self.port) && host != format!("localhost:{}", self.port) {
+            return Err(("403 Forbidden", "unexpected host".to_string()));
+        }
+        let given = request
+            .header("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if !token_matches(given.trim(), &self.token) {
+            return Err(("401 Unauthorized", "missing or wrong token".to_string()));
+        }
+        Ok(())
+    }
+
+    fn route(&self, request: &Request) -> Reply {
+        let state = self
+            .app
+            .try_state::<Arc<AppState>>()
+            .map(|s| s.inner().clone())
+            .ok_or_else(|| ("503 Service Unavailable", "starting up".to_string()))?;
+        let Some(path) = request.path.strip_prefix("/v1/") else {
+            return Err(not_found("route"));
+        };
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let limit = |default: i64, max: i64| {
+            request
+                .query_param("limit")
+                .and_then(|v| v.parse::<i64>().ok())
+                .unwrap_or(default)
+                .clamp(1, max)
+        };
+        let db_err = |e: rusqlite::Error| ("500 Internal Server Error", e.to_string());
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["hosts"]) => ok(state.db.hosts_list().map_err(db_err)?),
+            ("GET", ["sessions"]) => ok(state.terminal.list()),
+            ("POST", ["sessions"]) => self.open_session(&state, request),
+            ("POST", ["sessions", id, "run"]) => self.run(&state, id, request),
+            ("GET", ["sessions", id, "output"]) => output(&state, id, request),
+            ("DELETE", ["sessions", id]) => {
+                state.terminal.close(id).map_err(|_| not_found("session"))?;
+                let _ = state.db.terminal_session_scope_delete(id);
+                ok(json!({ "closed": true }))
+            }
+            ("GET", ["jobs"]) => ok(state.db.jobs_list(limit(50, 200), None).map_err(db_err)?.items),
+            ("POST", ["jobs"]) => self.start_job(&state, request),
+            ("GET", ["jobs", id]) => ok(state.db.jobs_get(id).map_err(db_err)?.ok_or_else(|| not_found("job"))?),
+            ("POST", ["jobs", id, "cancel"]) => ok(json!({ "cancelled": state.jobs.cancel(id) })),
+            ("GET", ["history"]) => ok(state.db.dock_history_list(limit(200, 500), false, None).map_err(db_err)?.items),
+            _ => Err(not_found("route")),
+        }
+    }
+
+    fn open_session(&self, state: &Arc<AppState>, request: &Request) -> Reply {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body {
+            host_id: Option<String>,
+            environment_tag: Option<String>,
+        }
+        let body: Body = if request.body.is_empty() {
+            Body {
+                host_id: None,
+                environment_tag: None,
+            }
+        } else {
+            request.json()?
+        };
+        let event = match body.host_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(host_id) => {
+                let host = state
+                    .db
+                    .hosts_get(host_id)
+                    .map_err(|e| ("500 Internal Server Error", e.to_string()))?
+                    .ok_or_else(|| not_found("host"))?;
+                let session_id = crate::open_ssh_session(
+                    self.app.clone(),
+                    state,
+                    host.username.clone(),
+                    host.hostname.clone(),
+                    Some(host.port),
+                    host.identity_file.clone(),
+                    Vec::new(),
+                    Some(host.environment_tag.clone()),
+                    Some(host.id.clone()),
+                )
+                .map_err(bad_request)?;
+                SessionOpenedEvent {
+                    session_id,
+                    host_id: Some(host.id),
+                    title: host.label,
+                    environment_tag: host.environment_tag,
+                }
+            }
+            None => {
+                let env = body.environment_tag.unwrap_or_else(|| "LOCAL".to_string());
+                let session_id =
+                    crate::open_local_session(&self.app, state, Some(env.clone())).map_err(bad_request)?;
+                SessionOpenedEvent {
+                    session_id,
+                    host_id: None,
+                    title: "Terminal".to_string(),
+                    environment_tag: env,
+                }
+            }
+        };
+        let _ = self.app.emit("automation:session_opened", &event);
+        ok(json!({ "sessionId": event.session_id }))
+    }
+
+    fn start_job(&self, state: &Arc<AppState>, request: &Request) -> Reply {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body {
+            target: String,
+            command: String,
+            timeout_secs: Option<u64>,
+            confirmation: Option<String>,
+            approval: Option<String>,
+            confirmed_hash: Option<String>,
+        }
+        let body: Body = request.json()?;
+        if body.command.trim().is_empty() {
+            return Err(bad_request("command must not be empty"));
+        }
+        let checked = jobs::check(
+            state,
+            &body.target,
+            &body.command,
+            body.confirmation.as_deref(),
+            body.approval.as_deref(),
+            body.confirmed_hash.as_deref(),
+        )
+        .map_err(|e| ("403 Forbidden", e))?;
+        let job = jobs::start(self.app.clone(), state.clone(), checked, body.timeout_secs).map_err(bad_request)?;
+        ok(job)
+    }
+
+    fn run(&self, state: &AppState, session_id: &str, request: &Request) -> Reply {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body {
+            command: String,
+            confirmation: Option<String>,
+            approval: Option<String>,
+            confirmed_hash: Option<String>,
+        }
+        let body: Body = request.json()?;
+        if body.command.trim().is_empty() {
+            return Err(bad_request("command must not be empty"));
+        }
+        state.terminal.window(session_id).map_err(|_| not_found("session"))?;
+        let mut data = body.command;
+        if !data.ends_with(['\r', '\n']) {
+            data.push('\r');
+        }
+        let data = crate::prepare_dock_run(
+            &self.app,
+            state,
+            session_id,
+            &data,
+            None,
+            None,
+            None,
+            body.confirmation.as_deref(),
+            body.approval.as_deref(),
+            body.confirmed_hash.as_deref(),
+        )
+        .map_err(|e| ("403 Forbidden", e))?;
+        state
+            .terminal
+            .write_with_meta(
+                session_id,
+                &data,
+                WriteMeta {
+                    origin: Some("automation".to_string()),
+                },
+            )
+            .map_err(|e| bad_request(e.to_string()))?;
+        ok(json!({ "written": data }))
+    }
+}
+
+/// The session's recent output as plain text.
+fn output(state: &AppState, session_id: &str, request: &Request) -> Reply {
+    let lines = request
+        .query_param("lines")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_OUTPUT_LINES)
+        .max(1);
+    let transcript = state.terminal.transcript(session_id).map_err(|_| not_found("session"))?;
+    let raw: String = transcript.chunks.iter().map(|c| c.text.as_str()).collect();
+    let redactor = Redactor::new(
+        &state
+            .db
+            .redaction_rules_list()
+            .map_err(|e| ("500 Internal Server Error", e.to_string()))?,
+    );
+    let text = redactor.redact(&transcript::clean(&raw));
+    let all: Vec<&str> = text.lines().collect();
+    let tail = all[all.len().saturating_sub(lines)..].join("\n");
+    ok(json!({
+        "text": tail,
+        "truncated": transcript.truncated || all.len() > lines,
+    }))
+}
@@ -0,0 +1,188 @@
+//! Broadcast groups: terminal sessions that receive the same keystrokes, e.g. every session
+//! opened by `terminal_open_group`.
+//!
+//! Groups live in memory only. Sessions that have ended drop out, and a group with none left
+//! goes away with them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use crate::db::HostQuery;
+use crate::{detach, AppState};
+
+/// Pause between batches in [`open_group`], so a big group's ssh handshakes (and any bastion or
+/// MFA prompts) don't all land at once.
+const OPEN_STAGGER: Duration = Duration::from_millis(750);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastGroup {
+    pub id: String,
+    pub name: String,
+    pub session_ids: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Broadcasts {
+    groups: Mutex<HashMap<String, BroadcastGroup>>,
+}
+
+impl Broadcasts {
+    pub fn register(&self, name: &str, session_ids: Vec<String>) -> BroadcastGroup {
+        let group = BroadcastGroup {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            session_ids,
+        };
+        self.groups
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(group.id.clone(), group.clone());
+        group
+    }
+
+    /// Drop ended sessions, and groups left without any.
+    fn prune(&self, state: &AppState) -> std::sync::MutexGuard<'_, HashMap<String, BroadcastGroup>> {
+        let live: Vec<String> = state.terminal.list().into_iter().map(|s| s.session_id).collect();
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        for group in groups.values_mut() {
+            group.session_ids.retain(|id| live.contains(id));
+        }
+        groups.retain(|_, g| !g.session_ids.is_empty());
+        groups
+    }
+
+    pub fn list(&self, state: &AppState) -> Vec<BroadcastGroup> {
+        let mut out: Vec<BroadcastGroup> = self.prune(state).values().cloned().collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        out
+    }
+
+    /// Forget a group; its sessions stay open.
+    pub fn remove(&self, id: &str) -> bool {
+        self.groups
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id)
+            .is_some()
+    }
+
+    /// Send `data` to every live session in the group, as if typed into each. Sessions routed to
+    /// another window are skipped. Returns how many sessions it was written to.
+    pub fn write(&self, state: &AppState, window: &str, id: &str, data: &str) -> Result<usize, String> {
+        let session_ids = self
+            .prune(state)
+            .get(id)
+            .map(|g| g.session_ids.clone())
+            .ok_or_else(|| "broadcast group not found".to_string())?;
+        let mut written = 0;
+        for session_id in &session_ids {
+            if detach::check_owner(state, session_id, window).is_err() {
+                continue;
+            }
+            match state.terminal.write(session_id, data) {
+                Ok(()) => written += 1,
+                Err(e) => log::warn!("broadcast to terminal {session_id} failed: {e}"),
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupSession {
+    pub session_id: String,
+    pub host_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupOpenFailure {
+    pub host_id: String,
+    pub label: String,
+    pub error: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupOpen {
+    /// The opened sessions, registered as a broadcast group named after the host group.
+    pub broadcast: BroadcastGroup,
+    /// Which host each session is connected to.
+    pub sessions: Vec<GroupSession>,
+    /// Hosts whose session couldn't be started.
+    pub failures: Vec<GroupOpenFailure>,
+}
+
+/// Open a session to every host in a host group, in batches of the work queue's parallelism, and
+/// register them as a broadcast group. Fails only if no session could be opened.
+pub fn open_group(app: &AppHandle, state: &Arc<AppState>, group_id: &str) -> Result<GroupOpen, String> {
+    let group = state
+        .db
+        .host_groups_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|g| g.id == group_id)
+        .ok_or_else(|| "host group not found".to_string())?;
+    let hosts = state
+        .db
+        .hosts_query(&HostQuery {
+            group_id: Some(group.id.clone()),
+            ..Default::default()
+        })
+        .map_err(|e| e.to_string())?;
+    if hosts.is_empty() {
+        return Err(format!("{} has no hosts", group.name));
+    }
+
+    let batch = state.queue.config().max_parallel.max(1);
+    let mut sessions = Vec::new();
+    let mut failures = Vec::new();
+    for (i, chunk) in hosts.chunks(batch).enumerate() {
+        if i > 0 {
+            thread::sleep(OPEN_STAGGER);
+        }
+        for host in chunk {
+            let opened = crate::open_ssh_session(
+                app.clone(),
+                state,
+                host.username.clone(),
+                host.hostname.clone(),
+                Some(host.port),
+                host.identity_file.clone(),
+                Vec::new(),
+                Some(host.environment_tag.clone()),
+                Some(host.id.clone()),
+            );
+            match opened {
+                Ok(session_id) => sessions.push(GroupSession {
+                    session_id,
+                    host_id: host.id.clone(),
+                }),
+                Err(error) => failures.push(GroupOpenFailure {
+                    host_id: host.id.clone(),
+                    label: host.label.clone(),
+                    error,
+                }),
+            }
+        }
+    }
+    if sessions.is_empty() {
+        let first = failures.first().map(|f| f.error.clone()).unwrap_or_default();
+        return Err(format!("no session could be opened: {first}"));
+    }
+    let session_ids = sessions.iter().map(|s| s.session_id.clone()).collect();
+    let broadcast = state.broadcasts.register(&group.name, session_ids);
+    Ok(GroupOpen {
+        broadcast,
+        sessions,
+        failures,
+    })
+}
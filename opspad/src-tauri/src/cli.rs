@@ -0,0 +1,318 @@
+//! Companion CLI: `opspad connect <host-label>` and `opspad run <command-title> --host <label>`.
+//!
+//! A running instance listens on a loopback socket whose port and token are written to
+//! `<app data>/cli.json`. A second launch with CLI arguments forwards them there, prints the reply
+//! and exits; if nothing answers, the app starts normally and handles the arguments itself. (If an
+//! instance is running but its socket isn't, the single-instance plugin still hands the arguments
+//! over; the caller just doesn't get a reply.)
+//!
+//! The backend only resolves labels and titles; the UI performs the action (opening the tab,
+//! running the command through the usual confirmation flow), so CLI runs get the same checks as
+//! clicks. On Windows release builds there's no console, so only the exit code is visible.
+//!
+//! An `opspad://` link as the only argument (how Windows and Linux launch link handlers) takes
+//! the same path; see [`crate::deep_link`].
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::{DockCommand, Host, Runbook};
+use crate::{deep_link, AppState};
+
+/// Must match `identifier` in tauri.conf.json: Tauri's app data dir is `<data dir>/<identifier>`.
+const APP_IDENTIFIER: &str = "com.opspad.opspad";
+const ENDPOINT_FILE: &str = "cli.json";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+const USAGE: &str = "usage:\n  opspad connect <host-label>\n  opspad run <command-title> --host <host-label>";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CliRequest {
+    Connect { host: String },
+    Run { command: String, host: String },
+    Link { url: String },
+}
+
+/// What the UI should do, with labels already resolved.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum CliAction {
+    Connect { host: Host },
+    Run { host: Host, command: Box<DockCommand> },
+    Runbook { runbook: Runbook },
+    /// Run a dock command in whichever terminal is active (tray menu).
+    Command { command: Box<DockCommand> },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ForwardRequest {
+    token: String,
+    args: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ForwardReply {
+    ok: bool,
+    message: String,
+}
+
+/// Parse the arguments after the program name. `Ok(None)` means a plain launch: anything that
+/// doesn't start with a subcommand (macOS, for one, passes its own flags) is left to the app.
+pub fn parse(args: &[String]) -> Result<Option<CliRequest>, String> {
+    let Some((sub, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    match sub.as_str() {
+        "connect" => match rest {
+            [host] => Ok(Some(CliRequest::Connect { host: host.clone() })),
+            _ => Err(USAGE.to_string()),
+        },
+        "run" => {
+            let mut command = None;
+            let mut host = None;
+            let mut it = rest.iter();
+            while let Some(arg) = it.next() {
+                if arg == "--host" {
+                    host = it.next().cloned();
+                } else if let Some(v) = arg.strip_prefix("--host=") {
+                    host = Some(v.to_string());
+                } else if command.is_none() {
+                    command = Some(arg.clone());
+                } else {
+                    return Err(USAGE.to_string());
+                }
+            }
+            match (command, host) {
+                (Some(command), Some(host)) => Ok(Some(CliRequest::Run { command, host })),
+                _ => Err(USAGE.to_string()),
+            }
+        }
+        "help" | "--help" | "-h" => Err(USAGE.to_string()),
+        link if link.starts_with(&format!("{}://", deep_link::SCHEME)) => match rest {
+            [] => Ok(Some(CliRequest::Link { url: link.to_string() })),
+            _ => Err(USAGE.to_string()),
+        },
+        _ => Ok(None),
+    }
+}
+
+fn find_host(state: &AppState, label: &str) -> Result<Host, String> {
+    let hosts = state.db.hosts_list().map_err(|e| e.to_string())?;
+    let mut matches: Vec<Host> = hosts.into_iter().filter(|h| h.label.eq_ignore_ascii_case(label)).collect();
+    match matches.len() {
+        0 => Err(format!("no host labelled \"{label}\"")),
+        1 => Ok(matches.remove(0)),
+        n => Err(format!("{n} hosts are labelled \"{label}\"; rename one to target it")),
+    }
+}
+
+fn find_command(state: &AppState, title: &str) -> Result<DockCommand, String> {
+    let commands = state.db.dock_commands_list().map_err(|e| e.to_string())?;
+    let mut matches: Vec<DockCommand> =
+        commands.into_iter().filter(|c| c.title.eq_ignore_ascii_case(title)).collect();
+    match matches.len() {
+        0 => Err(format!("no command titled \"{title}\"")),
+        1 => Ok(matches.remove(0)),
+        n => Err(format!("{n} commands are titled \"{title}\"; rename one to target it")),
+    }
+}
+
+pub fn resolve(state: &AppState, request: &CliRequest) -> Result<CliAction, String> {
+    match request {
+        CliRequest::Connect { host } => Ok(CliAction::Connect {
+            host: find_host(state, host)?,
+        }),
+        CliRequest::Run { command, host } => Ok(CliAction::Run {
+            host: find_host(state, host)?,
+            command: Box::new(find_command(state, command)?),
+        }),
+        CliRequest::Link { url } => deep_link::resolve(state, url),
+    }
+}
+
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Resolve a request and hand it to the UI; returns a one-line description for the caller.
+pub fn dispatch(app: &AppHandle, state: &AppState, request: &CliRequest) -> Result<String, String> {
+    Ok(deliver(app, state, resolve(state, request)?))
+}
+
+/// Hand an action to the UI. It's parked until the UI takes it (via `cli_take_action`), so one
+/// that arrives before the window has loaded isn't lost.
+pub fn deliver(app: &AppHandle, state: &AppState, action: CliAction) -> String {
+    let message = match &action {
+        CliAction::Connect { host } => format!("connecting to {}", host.label),
+        CliAction::Run { host, command } => format!("running \"{}\" on {}", command.title, host.label),
+        CliAction::Runbook { runbook } => format!("opening runbook \"{}\"", runbook.title),
+        CliAction::Command { command } => format!("running \"{}\"", command.title),
+    };
+    *state.cli_action.lock().expect("poisoned cli action lock") = Some(action);
+    let _ = app.emit("cli:action", ());
+    focus_main_window(app);
+    message
+}
+
+fn endpoint_path() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_IDENTIFIER).join(ENDPOINT_FILE))
+}
+
+fn write_endpoint(path: &Path, endpoint: &Endpoint) -> std::io::Result<()> {
+    let json = serde_json::to_vec(endpoint).map_err(std::io::Error::other)?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(&json)
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Start accepting forwarded CLI requests from later launches.
+pub fn spawn_server(app: AppHandle) -> Result<(), String> {
+    let path = endpoint_path().ok_or_else(|| "no data directory for the CLI endpoint".to_string())?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    let endpoint = Endpoint {
+        port: listener.local_addr().map_err(|e| e.to_string())?.port(),
+        token: new_token()?,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    write_endpoint(&path, &endpoint).map_err(|e| e.to_string())?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if let Err(e) = serve(&app, &endpoint.token, stream) {
+                log::warn!("cli request failed: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve(app: &AppHandle, token: &str, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_BYTES)).read_line(&mut line)?;
+    let request: ForwardRequest = serde_json::from_str(&line).map_err(std::io::Error::other)?;
+    if request.token != token {
+        // Not one of ours; don't tell it anything.
+        return Ok(());
+    }
+    let state = app.state::<Arc<AppState>>();
+    let result = match parse(&request.args) {
+        Ok(Some(req)) => dispatch(app, &state, &req),
+        Ok(None) => {
+            focus_main_window(app);
+            Ok("OpsPad is already running".to_string())
+        }
+        Err(e) => Err(e),
+    };
+    let reply = match result {
+        Ok(message) => ForwardReply { ok: true, message },
+        Err(message) => ForwardReply { ok: false, message },
+    };
+    let mut json = serde_json::to_vec(&reply).map_err(std::io::Error::other)?;
+    json.push(b'\n');
+    (&stream).write_all(&json)
+}
+
+/// Try to hand `args` to a running instance. `None` means nothing answered and the app should start
+/// (and handle the arguments itself); otherwise the reply and whether it succeeded.
+fn forward(args: &[String]) -> Option<(bool, String)> {
+    let raw = fs::read_to_string(endpoint_path()?).ok()?;
+    let endpoint: Endpoint = serde_json::from_str(&raw).ok()?;
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, endpoint.port));
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(IO_TIMEOUT)).ok()?;
+    let request = ForwardRequest {
+        token: endpoint.token,
+        args: args.to_vec(),
+    };
+    let mut json = serde_json::to_vec(&request).ok()?;
+    json.push(b'\n');
+    (&stream).write_all(&json).ok()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).ok()?;
+    let reply: ForwardReply = serde_json::from_str(&line).ok()?;
+    Some((reply.ok, reply.message))
+}
+
+/// Entry point for `main`, before the app starts: returns an exit code when the command line was
+/// fully handled (bad usage, or forwarded to a running instance) and the process should exit.
+pub fn handle_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match parse(&args) {
+        Ok(None) => None,
+        Err(usage) => {
+            eprintln!("{usage}");
+            Some(2)
+        }
+        Ok(Some(_)) => {
+            let (ok, message) = forward(&args)?;
+            if ok {
+                println!("{message}");
+                Some(0)
+            } else {
+                eprintln!("opspad: {message}");
+                Some(1)
+            }
+        }
+    }
+}
+
+/// Another copy was launched while we're running (the single-instance plugin hands us its argv
+/// and exits it): act on its arguments, or just come to the front.
+pub fn handle_second_launch(app: &AppHandle, argv: &[String]) {
+    let request = match parse(argv.get(1..).unwrap_or_default()) {
+        Ok(Some(request)) => request,
+        _ => {
+            focus_main_window(app);
+            return;
+        }
+    };
+    let state = app.state::<Arc<AppState>>();
+    if let Err(e) = dispatch(app, &state, &request) {
+        log::warn!("arguments from second launch ignored: {e}");
+        focus_main_window(app);
+    }
+}
+
+/// A CLI request the app was launched with, if any; handled once setup is done.
+pub fn launch_request() -> Option<CliRequest> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    parse(&args).ok().flatten()
+}
@@ -0,0 +1,135 @@
+//! Opt-in history of text copied out of terminals, so something copied a while ago can be found
+//! again.
+//!
+//! Entries live in memory only (gone when the app quits) and pass through the redaction rules
+//! before they're kept. Off by default; turning it off drops what was collected.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::Db;
+use crate::redact::Redactor;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "clipboard_history";
+const MAX_ENTRIES_CAP: usize = 500;
+/// Longer copies are cut to this many bytes (on a character boundary).
+const MAX_ENTRY_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_max_entries() -> usize {
+    50
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+impl ClipboardConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub id: String,
+    /// Redacted.
+    pub text: String,
+    pub session_id: Option<String>,
+    /// Environment of the session it was copied from, when known.
+    pub environment_tag: Option<String>,
+    /// Epoch seconds.
+    pub copied_at: i64,
+    pub truncated: bool,
+}
+
+/// Newest first.
+#[derive(Default)]
+pub struct ClipboardHistory {
+    entries: Mutex<VecDeque<ClipboardEntry>>,
+}
+
+impl ClipboardHistory {
+    pub fn list(&self) -> Vec<ClipboardEntry> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+pub fn set_config(state: &AppState, config: ClipboardConfig) -> Result<ClipboardConfig, String> {
+    let config = ClipboardConfig {
+        max_entries: config.max_entries.clamp(1, MAX_ENTRIES_CAP),
+        ..config
+    };
+    config.save(&state.db)?;
+    let mut entries = state.clipboard.entries.lock().unwrap_or_else(|e| e.into_inner());
+    if config.enabled {
+        entries.truncate(config.max_entries);
+    } else {
+        entries.clear();
+    }
+    Ok(config)
+}
+
+/// Record a copy from a terminal. `None` when history is off or the text is blank.
+pub fn add(state: &AppState, text: &str, session_id: Option<&str>) -> Result<Option<ClipboardEntry>, String> {
+    let config = ClipboardConfig::load(&state.db);
+    if !config.enabled || text.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut cut = text.len().min(MAX_ENTRY_BYTES);
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let redactor = Redactor::new(&state.db.redaction_rules_list().map_err(|e| e.to_string())?);
+    let environment_tag = session_id
+        .and_then(|id| state.terminal.list().into_iter().find(|s| s.session_id == id))
+        .map(|s| s.environment_tag);
+    let entry = ClipboardEntry {
+        id: Uuid::new_v4().to_string(),
+        text: redactor.redact(&text[..cut]),
+        session_id: session_id.map(str::to_string),
+        environment_tag,
+        copied_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        truncated: cut < text.len(),
+    };
+    let mut entries = state.clipboard.entries.lock().unwrap_or_else(|e| e.into_inner());
+    // Copying the same text again moves it to the top.
+    entries.retain(|e| e.text != entry.text);
+    entries.push_front(entry.clone());
+    entries.truncate(config.max_entries.clamp(1, MAX_ENTRIES_CAP));
+    Ok(Some(entry))
+}
@@ -0,0 +1,250 @@
+//! How long CommandDock commands take, measured from the shell's own prompt marks.
+//!
+//! When a CommandDock command is sent, the session's output is watched for the shell-integration
+//! marks (OSC 133) that bracket it. The command is timed from when it starts running (or from the
+//! write, if the shell doesn't say), until the shell reports it finished. The duration and exit
+//! status go into its history entry and out as a `command:finished` event. Shells without
+//! integration never report an end, so their commands just stay untimed.
+//!
+//! A dock command with a timeout is interrupted with Ctrl-C once it has run that long, and, if
+//! it asked for escalation and is still going a few seconds later, sent Ctrl-\ (SIGQUIT). Its
+//! history entry is marked as timed out and a `command:timeout` event goes out. The timeout only
+//! applies once the shell has said the command started, so a shell without integration never
+//! gets a stray Ctrl-C at its prompt.
+
+use std::collections::HashMap;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::DockCommand;
+use crate::notifications;
+use crate::redact::Redactor;
+use crate::terminal::prompt_marks::{PromptMark, PromptMarkParser};
+use crate::terminal::session_manager::WriteMeta;
+use crate::AppState;
+
+/// Commands at least this long also raise a notification when they finish.
+const NOTIFY_AFTER: Duration = Duration::from_secs(30);
+/// How long Ctrl-C gets to work before an escalating timeout sends Ctrl-\.
+const ESCALATE_AFTER: Duration = Duration::from_secs(5);
+
+/// How long a dock command may run before it's interrupted.
+#[derive(Clone, Copy, Debug)]
+pub struct Timeout {
+    pub after: Duration,
+    /// Follow up with Ctrl-\ if Ctrl-C didn't end it.
+    pub escalate: bool,
+}
+
+impl Timeout {
+    pub fn of(cmd: &DockCommand) -> Option<Self> {
+        cmd.timeout_secs.filter(|s| *s > 0).map(|secs| Self {
+            after: Duration::from_secs(u64::from(secs)),
+            escalate: cmd.timeout_escalate,
+        })
+    }
+}
+
+/// The CommandDock command each session is currently timing (session id -> history entry id).
+#[derive(Default)]
+pub struct CommandTimings {
+    pending: Mutex<HashMap<String, String>>,
+}
+
+impl CommandTimings {
+    fn is_current(&self, session_id: &str, history_id: &str) -> bool {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_id)
+            .is_some_and(|id| id == history_id)
+    }
+
+    fn finish(&self, session_id: &str, history_id: &str) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.get(session_id).is_some_and(|id| id == history_id) {
+            pending.remove(session_id);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandFinishedEvent {
+    pub session_id: String,
+    pub history_id: String,
+    /// Redacted.
+    pub command_text: String,
+    pub duration_ms: i64,
+    /// `None` when the shell didn't report one.
+    pub exit_status: Option<i32>,
+}
+
+/// Payload of `command:timeout`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTimeoutEvent {
+    pub session_id: String,
+    pub history_id: String,
+    pub timeout_secs: u64,
+    /// Ctrl-\ followed the Ctrl-C.
+    pub escalated: bool,
+}
+
+/// Send `data` (a control character) to interrupt a timed-out command.
+fn interrupt(state: &AppState, session_id: &str, data: &str) -> bool {
+    let written = state.terminal.write_with_meta(
+        session_id,
+        data,
+        WriteMeta {
+            origin: Some("timeout".to_string()),
+        },
+    );
+    if let Err(e) = &written {
+        log::warn!("couldn't interrupt a timed-out command in {session_id}: {e}");
+    }
+    written.is_ok()
+}
+
+/// Start timing a CommandDock command about to be written to `session_id`. Must be called before
+/// the write, so the marks it produces aren't missed. A later command in the same session
+/// replaces this one. With a `timeout`, the command is interrupted if it runs longer.
+pub fn watch(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    history_id: &str,
+    command_text: &str,
+    timeout: Option<Timeout>,
+) {
+    let output = match state.terminal.subscribe_output(session_id) {
+        Ok(rx) => rx,
+        Err(e) => {
+            log::debug!("command timing for {session_id} not started: {e}");
+            return;
+        }
+    };
+    state
+        .command_timings
+        .pending
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(session_id.to_string(), history_id.to_string());
+    // So its `terminal:command_start`/`terminal:command_end` name the history entry too.
+    let _ = state.terminal.tag_next_command(session_id, history_id);
+
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    let history_id = history_id.to_string();
+    let command_text = command_text.replace('\r', "").trim().to_string();
+    let written = Instant::now();
+    thread::spawn(move || {
+        let Some(state) = app.try_state::<Arc<AppState>>().map(|s| s.inner().clone()) else {
+            return;
+        };
+        let mut parser = PromptMarkParser::default();
+        let mut running_since = None;
+        let mut finished = None;
+        // When the timeout next acts: first the Ctrl-C, then (escalating) the Ctrl-\.
+        let mut deadline = timeout.map(|t| written + t.after);
+        let mut interrupted = false;
+        'read: loop {
+            let received = match deadline {
+                Some(at) => output.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => output.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            if !state.command_timings.is_current(&session_id, &history_id) {
+                return;
+            }
+            let chunk = match received {
+                Ok(chunk) => chunk,
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    let Some(timeout) = timeout else {
+                        continue;
+                    };
+                    if running_since.is_none() {
+                        // No sign the shell reports commands; leave it alone.
+                        log::debug!("timeout for {history_id} skipped: no shell integration marks");
+                        deadline = None;
+                        continue;
+                    }
+                    let escalated = interrupted;
+                    if !interrupt(&state, &session_id, if escalated { "\x1c" } else { "\x03" }) {
+                        deadline = None;
+                        continue;
+                    }
+                    if !escalated {
+                        interrupted = true;
+                        if let Err(e) = state.db.dock_history_set_timed_out(&history_id) {
+                            log::warn!("timeout not recorded: {e}");
+                        }
+                    }
+                    log::info!("command {history_id} in {session_id} timed out after {:?}", timeout.after);
+                    deadline = (timeout.escalate && !escalated).then(|| Instant::now() + ESCALATE_AFTER);
+                    let _ = app.emit(
+                        "command:timeout",
+                        CommandTimeoutEvent {
+                            session_id: session_id.clone(),
+                            history_id: history_id.clone(),
+                            timeout_secs: timeout.after.as_secs(),
+                            escalated,
+                        },
+                    );
+                    continue;
+                }
+            };
+            for mark in parser.feed(&chunk) {
+                match mark {
+                    PromptMark::OutputStart => {
+                        running_since.get_or_insert_with(Instant::now);
+                    }
+                    PromptMark::CommandEnd { exit } => {
+                        finished = Some(exit);
+                        break 'read;
+                    }
+                    PromptMark::PromptStart { .. } | PromptMark::CommandStart => {}
+                }
+            }
+        }
+        state.command_timings.finish(&session_id, &history_id);
+        // The session ended first.
+        let Some(exit_status) = finished else {
+            return;
+        };
+
+        let elapsed = running_since.unwrap_or(written).elapsed();
+        let duration_ms = elapsed.as_millis() as i64;
+        if let Err(e) = state.db.dock_history_finish(&history_id, duration_ms, exit_status) {
+            log::warn!("command duration not recorded: {e}");
+        }
+        let command_text = match state.db.redaction_rules_list() {
+            Ok(rules) => Redactor::new(&rules).redact(&command_text),
+            Err(_) => String::new(),
+        };
+        if elapsed >= NOTIFY_AFTER {
+            let title = match exit_status {
+                Some(0) | None => "Command finished".to_string(),
+                Some(code) => format!("Command failed (exit {code})"),
+            };
+            let secs = elapsed.as_secs();
+            let body = format!("{command_text} · {}m {:02}s", secs / 60, secs % 60);
+            notifications::notify(&app, notifications::Kind::Command, &title, &body);
+        }
+        let _ = app.emit(
+            "command:finished",
+            CommandFinishedEvent {
+                session_id,
+                history_id,
+                command_text,
+                duration_ms,
+                exit_status,
+            },
+        );
+    });
+}
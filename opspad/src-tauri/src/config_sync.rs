@@ -0,0 +1,815 @@
+//! Optional Git-backed sync of hosts, dock commands, runbooks, and snippets.
+//!
+//! OpsPad owns a small working tree (`hosts.json`, `commands.json`, `runbooks.json`, one
+//! `runbooks/<id>.md` per runbook, and `snippets.json`) in a local repository, commits it on every sync, and pulls and
+//! pushes a remote when one is configured. Secrets never leave the vault, and machine-local
+//! fields (identity file paths, host groups, usage stats, shortcuts) stay out of the repo.
+//!
+//! Merging is three-way, field by field, per item id: the base is the tree of the last commit
+//! this installation synced, "ours" is the database, "theirs" is the remote branch. A field
+//! changed on one side takes that side's value; a field changed differently on both sides keeps
+//! ours and is reported as a conflict (theirs is still in the Git history).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db::{
+    CommandVisibility, ConnectionType, Db, DnsExpansion, DnsSelection, DockCommand, Host, Runbook, Snippet,
+};
+use crate::exec;
+use crate::template::ParamSpec;
+
+const SETTINGS_KEY: &str = "sync.git";
+const STATE_KEY: &str = "sync.git.state";
+/// Generous: fetch and push may go over a slow network.
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+const COMMIT_MESSAGE: &str = "OpsPad configuration sync";
+
+/// Serializes sync passes.
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSyncConfig {
+    pub enabled: bool,
+    /// Local repository; created (and `git init`ed) on first sync if missing.
+    pub path: Option<String>,
+    /// Remote URL to pull from and push to as `origin`. None keeps history local only.
+    pub remote: Option<String>,
+}
+
+impl GitSyncConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if self.enabled && self.dir().is_none() {
+            return Err("choose a folder for the sync repository".to_string());
+        }
+        let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // The last synced commit belongs to the old repository; don't merge against it.
+        let previous = Self::load(db);
+        if previous.dir() != self.dir() || previous.remote() != self.remote() {
+            SyncState::default().save(db)?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn dir(&self) -> Option<PathBuf> {
+        self.path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+
+    fn remote(&self) -> Option<&str> {
+        self.remote.as_deref().map(str::trim).filter(|r| !r.is_empty())
+    }
+}
+
+/// What the last sync left behind (persisted next to the config).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    last_commit: Option<String>,
+    last_sync_at: Option<i64>,
+    last_error: Option<String>,
+    conflicts: Vec<String>,
+}
+
+impl SyncState {
+    fn load(db: &Db) -> Self {
+        db.settings_get(STATE_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(STATE_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSyncStatus {
+    pub enabled: bool,
+    pub path: Option<String>,
+    pub remote: Option<String>,
+    pub last_commit: Option<String>,
+    pub last_sync_at: Option<i64>,
+    pub last_error: Option<String>,
+    /// Items that conflicted in the last sync (ours was kept).
+    pub conflicts: Vec<String>,
+    /// Local changes made since the last sync.
+    pub pending_changes: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSyncReport {
+    /// Items created, updated, or removed locally from the remote's changes.
+    pub pulled: Vec<String>,
+    pub committed: Option<String>,
+    pub pushed: bool,
+    /// Items changed differently on both sides; ours was kept.
+    pub conflicts: Vec<String>,
+}
+
+// -- Repository file format --------------------------------------------------------------------
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedHost {
+    id: String,
+    label: String,
+    hostname: String,
+    port: u16,
+    username: String,
+    environment_tag: String,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default, skip_serializing_if = "ConnectionType::is_ssh")]
+    connection_type: ConnectionType,
+    #[serde(default, skip_serializing_if = "DnsExpansion::is_off")]
+    dns_expansion: DnsExpansion,
+    #[serde(default, skip_serializing_if = "DnsSelection::is_pick")]
+    dns_selection: DnsSelection,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedCommand {
+    id: String,
+    title: String,
+    command: String,
+    #[serde(default)]
+    requires_confirm: bool,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    params: Vec<ParamSpec>,
+    #[serde(default)]
+    visibility: CommandVisibility,
+}
+
+/// Runbook metadata; the markdown lives in `runbooks/<id>.md`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedRunbook {
+    id: String,
+    title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    params: Vec<ParamSpec>,
+    #[serde(default, skip_serializing)]
+    markdown: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedSnippet {
+    id: String,
+    trigger: String,
+    expansion: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default = "enabled_default")]
+    enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+const HOSTS_FILE: &str = "hosts.json";
+const COMMANDS_FILE: &str = "commands.json";
+const RUNBOOKS_FILE: &str = "runbooks.json";
+const RUNBOOKS_DIR: &str = "runbooks";
+const SNIPPETS_FILE: &str = "snippets.json";
+
+/// One side of the merge: each collection as JSON objects, in display order.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Snapshot {
+    hosts: Vec<Value>,
+    commands: Vec<Value>,
+    /// Metadata plus a `markdown` field.
+    runbooks: Vec<Value>,
+    snippets: Vec<Value>,
+}
+
+fn to_values<T: Serialize>(items: impl IntoIterator<Item = T>) -> Result<Vec<Value>, String> {
+    items
+        .into_iter()
+        .map(|i| serde_json::to_value(i).map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn item_id(v: &Value) -> Option<&str> {
+    v.get("id").and_then(Value::as_str)
+}
+
+/// Runbook ids become file names; refuse anything that could escape the directory.
+fn safe_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Snapshot {
+    fn from_db(db: &Db) -> Result<Self, String> {
+        // Items from team subscriptions are someone else's source of truth; leave them out, and
+        // ephemeral hosts, which won't outlive their expiry.
+        let hosts = db.hosts_list().map_err(|e| e.to_string())?;
+        let hosts = hosts.into_iter().filter(|h| h.subscription_id.is_none() && h.expires_at.is_none());
+        let commands = db.dock_commands_list().map_err(|e| e.to_string())?;
+        let commands = commands.into_iter().filter(|c| c.subscription_id.is_none());
+        let runbooks = db.runbooks_list().map_err(|e| e.to_string())?;
+        let runbooks: Vec<Runbook> = runbooks.into_iter().filter(|rb| rb.subscription_id.is_none()).collect();
+        let mut runbook_values = Vec::with_capacity(runbooks.len());
+        for rb in runbooks {
+            let mut v = serde_json::to_value(SyncedRunbook {
+                id: rb.id,
+                title: rb.title,
+                tags: rb.tags,
+                params: rb.params,
+                markdown: String::new(),
+            })
+            .map_err(|e| e.to_string())?;
+            v["markdown"] = Value::String(rb.markdown);
+            runbook_values.push(v);
+        }
+        let snippets = db.snippets_list().map_err(|e| e.to_string())?;
+        Ok(Self {
+            hosts: to_values(hosts.map(|h| SyncedHost {
+                id: h.id,
+                label: h.label,
+                hostname: h.hostname,
+                port: h.port,
+                username: h.username,
+                environment_tag: h.environment_tag,
+                color: h.color,
+                connection_type: h.connection_type,
+                dns_expansion: h.dns_expansion,
+                dns_selection: h.dns_selection,
+            }))?,
+            commands: to_values(commands.map(|c| SyncedCommand {
+                id: c.id,
+                title: c.title,
+                command: c.command,
+                requires_confirm: c.requires_confirm,
+                color: c.color,
+                category: c.category,
+                params: c.params,
+                visibility: c.visibility,
+            }))?,
+            runbooks: runbook_values,
+            snippets: to_values(snippets.into_iter().map(|s| SyncedSnippet {
+                id: s.id,
+                trigger: s.trigger,
+                expansion: s.expansion,
+                description: s.description,
+                scope: s.scope,
+                enabled: s.enabled,
+            }))?,
+        })
+    }
+
+    /// The repository files for this snapshot, keyed by path relative to the repo root.
+    fn files(&self) -> Result<BTreeMap<String, String>, String> {
+        let pretty = |v: &Vec<Value>| serde_json::to_string_pretty(v).map(|s| s + "\n").map_err(|e| e.to_string());
+        let mut meta = Vec::with_capacity(self.runbooks.len());
+        let mut out = BTreeMap::new();
+        for rb in &self.runbooks {
+            let mut rb = rb.clone();
+            let markdown = rb
+                .as_object_mut()
+                .and_then(|o| o.remove("markdown"))
+                .and_then(|m| m.as_str().map(str::to_string))
+                .unwrap_or_default();
+            if let Some(id) = item_id(&rb).filter(|id| safe_id(id)) {
+                out.insert(format!("{RUNBOOKS_DIR}/{id}.md"), markdown);
+            }
+            meta.push(rb);
+        }
+        out.insert(HOSTS_FILE.to_string(), pretty(&self.hosts)?);
+        out.insert(COMMANDS_FILE.to_string(), pretty(&self.commands)?);
+        out.insert(RUNBOOKS_FILE.to_string(), pretty(&meta)?);
+        out.insert(SNIPPETS_FILE.to_string(), pretty(&self.snippets)?);
+        Ok(out)
+    }
+
+    /// Parse repository files; `read` returns None for files that don't exist.
+    fn from_files(read: impl Fn(&str) -> Result<Option<String>, String>) -> Result<Self, String> {
+        let list = |name: &str| -> Result<Vec<Value>, String> {
+            match read(name)? {
+                Some(text) => serde_json::from_str(&text).map_err(|e| format!("{name}: {e}")),
+                None => Ok(Vec::new()),
+            }
+        };
+        // Round-trip through the typed structs so malformed entries fail here, not mid-apply.
+        let hosts: Vec<SyncedHost> = serde_json::from_value(Value::Array(list(HOSTS_FILE)?))
+            .map_err(|e| format!("{HOSTS_FILE}: {e}"))?;
+        let commands: Vec<SyncedCommand> = serde_json::from_value(Value::Array(list(COMMANDS_FILE)?))
+            .map_err(|e| format!("{COMMANDS_FILE}: {e}"))?;
+        let runbooks: Vec<SyncedRunbook> = serde_json::from_value(Value::Array(list(RUNBOOKS_FILE)?))
+            .map_err(|e| format!("{RUNBOOKS_FILE}: {e}"))?;
+        let snippets: Vec<SyncedSnippet> = serde_json::from_value(Value::Array(list(SNIPPETS_FILE)?))
+            .map_err(|e| format!("{SNIPPETS_FILE}: {e}"))?;
+        let mut runbook_values = Vec::with_capacity(runbooks.len());
+        for rb in runbooks {
+            if !safe_id(&rb.id) {
+                return Err(format!("{RUNBOOKS_FILE}: invalid runbook id {:?}", rb.id));
+            }
+            let markdown = read(&format!("{RUNBOOKS_DIR}/{}.md", rb.id))?.unwrap_or_default();
+            let mut v = serde_json::to_value(rb).map_err(|e| e.to_string())?;
+            v["markdown"] = Value::String(markdown);
+            runbook_values.push(v);
+        }
+        Ok(Self {
+            hosts: to_values(hosts)?,
+            commands: to_values(commands)?,
+            runbooks: runbook_values,
+            snippets: to_values(snippets)?,
+        })
+    }
+
+    fn from_rev(dir: &Path, rev: &str) -> Result<Self, String> {
+        let present: HashSet<String> = git(dir, &["ls-tree", "-r", "--name-only", rev])?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        Self::from_files(|name| {
+            if !present.contains(name) {
+                return Ok(None);
+            }
+            git(dir, &["show", &format!("{rev}:{name}")]).map(Some)
+        })
+    }
+}
+
+// -- Three-way merge ---------------------------------------------------------------------------
+
+/// Merge one value. Returns the result and whether both sides changed it differently.
+fn merge_value(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> (Option<Value>, bool) {
+    if ours == theirs {
+        return (ours.cloned(), false);
+    }
+    if ours == base {
+        return (theirs.cloned(), false);
+    }
+    if theirs == base {
+        return (ours.cloned(), false);
+    }
+    // Both changed. Objects on both sides (an item edited on both) merge key by key.
+    if let (Some(Value::Object(o)), Some(Value::Object(t))) = (ours, theirs) {
+        let b = base.and_then(Value::as_object);
+        let mut merged = o.clone();
+        let mut conflict = false;
+        let keys: Vec<&String> = o.keys().chain(t.keys().filter(|k| !o.contains_key(*k))).collect();
+        for key in keys {
+            let (v, c) = merge_value(b.and_then(|b| b.get(key)), o.get(key), t.get(key));
+            conflict |= c;
+            match v {
+                Some(v) => {
+                    merged.insert(key.clone(), v);
+                }
+                None => {
+                    merged.remove(key);
+                }
+            }
+        }
+        return (Some(Value::Object(merged)), conflict);
+    }
+    // Edited on one side, deleted on the other, or scalars changed both ways: keep whichever
+    // side still has content, preferring ours.
+    (ours.or(theirs).cloned(), true)
+}
+
+fn describe(kind: &str, v: &Value) -> String {
+    let name = ["label", "title", "trigger"]
+        .iter()
+        .find_map(|k| v.get(*k).and_then(Value::as_str))
+        .or_else(|| item_id(v))
+        .unwrap_or("?");
+    format!("{kind} {name}")
+}
+
+/// Merge a collection by item id. Order follows ours, with items new on their side appended.
+fn merge_list(kind: &str, base: &[Value], ours: &[Value], theirs: &[Value], conflicts: &mut Vec<String>) -> Vec<Value> {
+    let index = |items: &[Value]| -> HashMap<String, Value> {
+        items
+            .iter()
+            .filter_map(|v| item_id(v).map(|id| (id.to_string(), v.clone())))
+            .collect()
+    };
+    let (b, o, t) = (index(base), index(ours), index(theirs));
+    let mut order: Vec<String> = Vec::new();
+    for v in ours.iter().chain(theirs) {
+        if let Some(id) = item_id(v) {
+            if !order.iter().any(|o| o == id) {
+                order.push(id.to_string());
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for id in order {
+        let (merged, conflict) = merge_value(b.get(&id), o.get(&id), t.get(&id));
+        if conflict {
+            if let Some(v) = merged.as_ref().or(o.get(&id)).or(t.get(&id)) {
+                conflicts.push(describe(kind, v));
+            }
+        }
+        out.extend(merged);
+    }
+    out
+}
+
+fn merge(base: &Snapshot, ours: &Snapshot, theirs: &Snapshot, conflicts: &mut Vec<String>) -> Snapshot {
+    Snapshot {
+        hosts: merge_list("host", &base.hosts, &ours.hosts, &theirs.hosts, conflicts),
+        commands: merge_list("command", &base.commands, &ours.commands, &theirs.commands, conflicts),
+        runbooks: merge_list("runbook", &base.runbooks, &ours.runbooks, &theirs.runbooks, conflicts),
+        snippets: merge_list("snippet", &base.snippets, &ours.snippets, &theirs.snippets, conflicts),
+    }
+}
+
+// -- Applying a merge result -------------------------------------------------------------------
+
+/// Write the items of `merged` that differ from `ours` into the database.
+fn apply(db: &Db, ours: &Snapshot, merged: &Snapshot, report: &mut GitSyncReport) -> Result<(), String> {
+    fn changes<'a>(ours: &'a [Value], merged: &'a [Value]) -> (Vec<&'a Value>, Vec<&'a str>) {
+        let before: HashMap<&str, &Value> = ours.iter().filter_map(|v| item_id(v).map(|id| (id, v))).collect();
+        let after: HashSet<&str> = merged.iter().filter_map(item_id).collect();
+        let upserts = merged
+            .iter()
+            .filter(|v| item_id(v).is_some_and(|id| before.get(id) != Some(v)))
+            .collect();
+        let removals = before.keys().copied().filter(|id| !after.contains(id)).collect();
+        (upserts, removals)
+    }
+    let (upserts, removals) = changes(&ours.hosts, &merged.hosts);
+    for v in upserts {
+        let h: SyncedHost = serde_json::from_value(v.clone()).map_err(|e| e.to_string())?;
+        let local = db.hosts_get(&h.id).map_err(|e| e.to_string())?;
+        report.pulled.push(format!("host {}", h.label));
+        db.hosts_upsert(&Host {
+            id: h.id,
+            label: h.label,
+            hostname: h.hostname,
+            port: h.port,
+            username: h.username,
+            environment_tag: h.environment_tag,
+            identity_file: local.as_ref().and_then(|l| l.identity_file.clone()),
+            color: h.color,
+            group_id: local.as_ref().and_then(|l| l.group_id.clone()),
+            subscription_id: None,
+            password_vault_key: local.as_ref().and_then(|l| l.password_vault_key.clone()),
+            passphrase_vault_key: local.as_ref().and_then(|l| l.passphrase_vault_key.clone()),
+            pinned: local.as_ref().is_some_and(|l| l.pinned),
+            last_connected_at: local.as_ref().and_then(|l| l.last_connected_at),
+            connection_type: h.connection_type,
+            identity_agent: local.as_ref().and_then(|l| l.identity_agent.clone()),
+            security_key_provider: local.and_then(|l| l.security_key_provider),
+            dns_expansion: h.dns_expansion,
+            dns_selection: h.dns_selection,
+            expires_at: None,
+            archived_at: None,
+            server_info: None,
+            effective_color: None,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    for id in removals {
+        if let Some(v) = ours.hosts.iter().find(|v| item_id(v) == Some(id)) {
+            report.pulled.push(format!("{} (removed)", describe("host", v)));
+        }
+        db.hosts_delete(id, false).map_err(|e| e.to_string())?;
+    }
+
+    let (upserts, removals) = changes(&ours.commands, &merged.commands);
+    for v in upserts {
+        let c: SyncedCommand = serde_json::from_value(v.clone()).map_err(|e| e.to_string())?;
+        report.pulled.push(format!("command {}", c.title));
+        db.dock_commands_upsert(&DockCommand {
+            id: c.id,
+            title: c.title,
+            command: c.command,
+            requires_confirm: c.requires_confirm,
+            color: c.color,
+            params: c.params,
+            visibility: c.visibility,
+            category: c.category,
+            run_count: 0,
+            last_run_at: None,
+            shortcut: None,
+            subscription_id: None,
+            effective_color: None,
+            timeout_secs: None,
+            timeout_escalate: false,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    for id in removals {
+        if let Some(v) = ours.commands.iter().find(|v| item_id(v) == Some(id)) {
+            report.pulled.push(format!("{} (removed)", describe("command", v)));
+        }
+        db.dock_commands_delete(id).map_err(|e| e.to_string())?;
+    }
+
+    let (upserts, removals) = changes(&ours.runbooks, &merged.runbooks);
+    for v in upserts {
+        let rb: SyncedRunbook = serde_json::from_value(v.clone()).map_err(|e| e.to_string())?;
+        report.pulled.push(format!("runbook {}", rb.title));
+        db.runbooks_upsert(&Runbook {
+            id: rb.id,
+            title: rb.title,
+            markdown: rb.markdown,
+            tags: rb.tags,
+            params: rb.params,
+            created_at: 0,
+            updated_at: 0,
+            file_name: None,
+            sync_conflict: false,
+            synced_hash: None,
+            subscription_id: None,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    for id in removals {
+        if let Some(v) = ours.runbooks.iter().find(|v| item_id(v) == Some(id)) {
+            report.pulled.push(format!("{} (removed)", describe("runbook", v)));
+        }
+        db.runbooks_delete(id).map_err(|e| e.to_string())?;
+    }
+
+    // Removals first: a trigger may have moved to a snippet with another id.
+    let (upserts, removals) = changes(&ours.snippets, &merged.snippets);
+    for id in removals {
+        if let Some(v) = ours.snippets.iter().find(|v| item_id(v) == Some(id)) {
+            report.pulled.push(format!("{} (removed)", describe("snippet", v)));
+        }
+        db.snippets_delete(id).map_err(|e| e.to_string())?;
+    }
+    for v in upserts {
+        let s: SyncedSnippet = serde_json::from_value(v.clone()).map_err(|e| e.to_string())?;
+        report.pulled.push(format!("snippet {}", s.trigger));
+        db.snippets_upsert(&Snippet {
+            id: s.id,
+            trigger: s.trigger,
+            expansion: s.expansion,
+            description: s.description,
+            scope: s.scope,
+            enabled: s.enabled,
+            created_at: 0,
+            updated_at: 0,
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// -- Git plumbing ------------------------------------------------------------------------------
+
+fn git_output(dir: &Path, args: &[&str]) -> Result<exec::ExecOutput, String> {
+    let mut full = vec!["-C".to_string(), dir.to_string_lossy().to_string()];
+    full.extend(args.iter().map(|a| a.to_string()));
+    let out = exec::run("git", &full, GIT_TIMEOUT).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => "Git sync needs git installed and on PATH".to_string(),
+        _ => format!("git: {e}"),
+    })?;
+    if out.timed_out {
+        return Err(format!("git {} timed out", args.first().unwrap_or(&"")));
+    }
+    Ok(out)
+}
+
+/// Run git in `dir`, returning stdout, or an error carrying git's last stderr line.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let out = git_output(dir, args)?;
+    if out.exit_code != Some(0) {
+        let detail = out
+            .stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("failed")
+            .trim()
+            .to_string();
+        return Err(format!("git {}: {detail}", args.first().unwrap_or(&"")));
+    }
+    Ok(out.stdout)
+}
+
+/// Whether git exits 0 (for predicate commands like `merge-base --is-ancestor`).
+fn git_ok(dir: &Path, args: &[&str]) -> Result<bool, String> {
+    Ok(git_output(dir, args)?.exit_code == Some(0))
+}
+
+fn rev_parse(dir: &Path, rev: &str) -> Result<Option<String>, String> {
+    let spec = format!("{rev}^{{commit}}");
+    let out = git_output(dir, &["rev-parse", "--verify", "--quiet", &spec])?;
+    Ok((out.exit_code == Some(0)).then(|| out.stdout.trim().to_string()))
+}
+
+/// Commit `tree` on top of `parents`, using a fallback identity if the user has none configured.
+fn commit_tree(dir: &Path, tree: &str, parents: &[String]) -> Result<String, String> {
+    let mut args: Vec<String> = Vec::new();
+    if !git_ok(dir, &["config", "user.email"])? {
+        args.extend(["-c", "user.name=OpsPad", "-c", "user.email=opspad@localhost"].map(String::from));
+    }
+    args.extend(["commit-tree".to_string(), tree.to_string(), "-m".to_string(), COMMIT_MESSAGE.to_string()]);
+    for p in parents {
+        args.extend(["-p".to_string(), p.clone()]);
+    }
+    let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    Ok(git(dir, &refs)?.trim().to_string())
+}
+
+/// Replace the OpsPad-owned files in `dir` with `files`.
+fn write_tree(dir: &Path, files: &BTreeMap<String, String>) -> Result<(), String> {
+    let runbooks_dir = dir.join(RUNBOOKS_DIR);
+    fs::create_dir_all(&runbooks_dir).map_err(|e| format!("{}: {e}", runbooks_dir.display()))?;
+    if let Ok(entries) = fs::read_dir(&runbooks_dir) {
+        for entry in entries.flatten() {
+            let name = format!("{RUNBOOKS_DIR}/{}", entry.file_name().to_string_lossy());
+            if name.ends_with(".md") && !files.contains_key(&name) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::write(&path, content).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Open (or create) the repository and point `origin` at the configured remote.
+fn prepare_repo(dir: &Path, remote: Option<&str>) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet"])?;
+    }
+    let current = git_output(dir, &["remote", "get-url", "origin"])?;
+    match remote {
+        Some(url) if current.exit_code != Some(0) => {
+            git(dir, &["remote", "add", "origin", url])?;
+        }
+        Some(url) if current.stdout.trim() != url => {
+            git(dir, &["remote", "set-url", "origin", url])?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn run_sync(db: &Db, cfg: &GitSyncConfig, state: &mut SyncState) -> Result<GitSyncReport, String> {
+    let dir = cfg.dir().ok_or_else(|| "choose a folder for the sync repository".to_string())?;
+    let remote = cfg.remote();
+    prepare_repo(&dir, remote)?;
+    let branch = git(&dir, &["symbolic-ref", "--short", "HEAD"])
+        .map_err(|_| "the sync repository has a detached HEAD; check out a branch".to_string())?
+        .trim()
+        .to_string();
+
+    let mut report = GitSyncReport::default();
+    let head = rev_parse(&dir, "HEAD")?;
+    let theirs_rev = match remote {
+        Some(_) => {
+            // An empty remote has nothing to fetch for this branch yet; that's fine.
+            let _ = git(&dir, &["fetch", "--quiet", "origin", &branch]);
+            rev_parse(&dir, &format!("refs/remotes/origin/{branch}"))?
+        }
+        None => head.clone(),
+    };
+    let base_rev = match state.last_commit.as_deref() {
+        Some(c) => rev_parse(&dir, c)?,
+        None => None,
+    };
+
+    let ours = Snapshot::from_db(db)?;
+    let merged = match &theirs_rev {
+        Some(t) if Some(t) != base_rev.as_ref() => {
+            let theirs = Snapshot::from_rev(&dir, t)?;
+            let base = match &base_rev {
+                Some(b) => Snapshot::from_rev(&dir, b)?,
+                None => Snapshot::default(),
+            };
+            let merged = merge(&base, &ours, &theirs, &mut report.conflicts);
+            apply(db, &ours, &merged, &mut report)?;
+            merged
+        }
+        _ => ours,
+    };
+
+    write_tree(&dir, &merged.files()?)?;
+    git(&dir, &["add", "--all", "--", HOSTS_FILE, COMMANDS_FILE, RUNBOOKS_FILE, RUNBOOKS_DIR, SNIPPETS_FILE])?;
+    let tree = git(&dir, &["write-tree"])?.trim().to_string();
+
+    let mut parents: Vec<String> = Vec::new();
+    for rev in [&head, &theirs_rev].into_iter().flatten() {
+        if !parents.contains(rev) {
+            parents.push(rev.clone());
+        }
+    }
+    if parents.len() == 2 {
+        // Drop a parent the other already contains (plain fast-forward either way).
+        if git_ok(&dir, &["merge-base", "--is-ancestor", &parents[0], &parents[1]])? {
+            parents.remove(0);
+        } else if git_ok(&dir, &["merge-base", "--is-ancestor", &parents[1], &parents[0]])? {
+            parents.remove(1);
+        }
+    }
+    let unchanged = match parents.as_slice() {
+        [only] => git(&dir, &["rev-parse", &format!("{only}^{{tree}}")])?.trim() == tree,
+        _ => false,
+    };
+    let new_head = if unchanged {
+        parents[0].clone()
+    } else {
+        let commit = commit_tree(&dir, &tree, &parents)?;
+        report.committed = Some(commit.clone());
+        commit
+    };
+    if head.as_ref() != Some(&new_head) {
+        git(&dir, &["update-ref", "HEAD", &new_head])?;
+    }
+    state.last_commit = Some(new_head.clone());
+    state.conflicts = report.conflicts.clone();
+
+    if remote.is_some() && theirs_rev.as_ref() != Some(&new_head) {
+        git(&dir, &["push", "--quiet", "origin", &format!("HEAD:refs/heads/{branch}")])?;
+        report.pushed = true;
+    }
+    Ok(report)
+}
+
+/// One sync pass: merge with the remote (if any), commit, push.
+pub fn sync_now(db: &Db) -> Result<GitSyncReport, String> {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let cfg = GitSyncConfig::load(db);
+    if !cfg.enabled {
+        return Err("Git sync is turned off".to_string());
+    }
+    let mut state = SyncState::load(db);
+    let result = run_sync(db, &cfg, &mut state);
+    state.last_sync_at = Some(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+    );
+    state.last_error = result.as_ref().err().cloned();
+    state.save(db)?;
+    result
+}
+
+pub fn status(db: &Db) -> Result<GitSyncStatus, String> {
+    let cfg = GitSyncConfig::load(db);
+    let state = SyncState::load(db);
+    let pending_changes = match (cfg.enabled, cfg.dir()) {
+        (true, Some(dir)) => {
+            let files = Snapshot::from_db(db)?.files()?;
+            files
+                .iter()
+                .any(|(name, content)| fs::read_to_string(dir.join(name)).ok().as_deref() != Some(content.as_str()))
+                || fs::read_dir(dir.join(RUNBOOKS_DIR)).is_ok_and(|entries| {
+                    entries
+                        .flatten()
+                        .filter(|e| e.file_name().to_string_lossy().ends_with(".md"))
+                        .count()
+                        != files.keys().filter(|k| k.starts_with(RUNBOOKS_DIR)).count()
+                })
+        }
+        _ => false,
+    };
+    Ok(GitSyncStatus {
+        enabled: cfg.enabled,
+        path: cfg.path,
+        remote: cfg.remote,
+        last_commit: state.last_commit,
+        last_sync_at: state.last_sync_at,
+        last_error: state.last_error,
+        conflicts: state.conflicts,
+        pending_changes,
+    })
+}
@@ -0,0 +1,371 @@
+//! Crash reports: panics (and errors funneled through [`capture_error`]) are written as JSON files
+//! under `<app data>/crashes`, and, only if the user opted in, uploaded to a Sentry-compatible
+//! endpoint with paths, user names, addresses, and redaction-rule matches scrubbed out.
+//!
+//! Background threads panic without taking the app down, so without this they die unnoticed.
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::db::Db;
+use crate::redact::Redactor;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "crash_reports";
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+/// Local reports beyond this many are deleted, oldest first.
+const KEEP_REPORTS: usize = 50;
+
+/// Where reports go and what to stamp them with; set once by [`install`].
+struct Context {
+    dir: PathBuf,
+    version: String,
+    app: AppHandle,
+}
+
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashConfig {
+    /// Opt-in: upload reports to `dsn`. Off by default; reports are always kept locally.
+    #[serde(default)]
+    pub upload: bool,
+    /// Sentry-style DSN: `https://<public key>@<host>/<project id>`.
+    #[serde(default)]
+    pub dsn: Option<String>,
+}
+
+impl CrashConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if let Some(dsn) = self.dsn.as_deref() {
+            store_url(dsn)?;
+        } else if self.upload {
+            return Err("set a DSN to upload crash reports to".to_string());
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CrashKind {
+    Panic,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub kind: CrashKind,
+    /// Epoch seconds.
+    pub timestamp: i64,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    #[serde(default)]
+    pub uploaded: bool,
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Install the panic hook. Call as early as possible so setup panics are caught too.
+pub fn install(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join("crashes");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let ctx = Context {
+        dir,
+        version: app.package_info().version.to_string(),
+        app: app.clone(),
+    };
+    if CONTEXT.set(ctx).is_err() {
+        return Ok(());
+    }
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        record_panic(info);
+        previous(info);
+    }));
+    Ok(())
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic with a non-string payload".to_string())
+}
+
+fn record_panic(info: &PanicHookInfo<'_>) {
+    let message = panic_message(info);
+    let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+    log::error!(
+        "panic in thread {}: {message} ({})",
+        thread::current().name().unwrap_or("<unnamed>"),
+        location.as_deref().unwrap_or("unknown location")
+    );
+    record(CrashKind::Panic, message, location, Some(Backtrace::force_capture().to_string()));
+}
+
+/// Report an unexpected error that didn't panic but should be looked at.
+pub fn capture_error(context: &str, error: &str) {
+    log::error!("{context}: {error}");
+    record(CrashKind::Error, format!("{context}: {error}"), None, None);
+}
+
+fn record(kind: CrashKind, message: String, location: Option<String>, backtrace: Option<String>) {
+    let Some(ctx) = CONTEXT.get() else {
+        return;
+    };
+    let report = CrashReport {
+        id: Uuid::new_v4().simple().to_string(),
+        kind,
+        timestamp: now_epoch_secs(),
+        message,
+        location,
+        thread: thread::current().name().map(str::to_string),
+        backtrace,
+        app_version: ctx.version.clone(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        uploaded: false,
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&report) {
+        let _ = fs::write(report_path(&ctx.dir, &report), json);
+    }
+    prune(&ctx.dir);
+    spawn_upload();
+}
+
+fn report_path(dir: &Path, report: &CrashReport) -> PathBuf {
+    // Timestamp first so file names sort chronologically.
+    dir.join(format!("{}-{}.json", report.timestamp, report.id))
+}
+
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|p| p.extension().is_some_and(|e| e == "json"));
+    files.sort();
+    files
+}
+
+fn prune(dir: &Path) {
+    let files = report_files(dir);
+    for old in files.iter().take(files.len().saturating_sub(KEEP_REPORTS)) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// Local reports, newest first.
+pub fn list() -> Vec<CrashReport> {
+    let Some(ctx) = CONTEXT.get() else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReport> = report_files(&ctx.dir)
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .filter_map(|s| serde_json::from_str(&s).ok())
+        .collect();
+    reports.reverse();
+    reports
+}
+
+pub fn clear() -> Result<(), String> {
+    let Some(ctx) = CONTEXT.get() else {
+        return Ok(());
+    };
+    for file in report_files(&ctx.dir) {
+        fs::remove_file(&file).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// `https://key@host/path/42` -> (`https://host/path/api/42/store/`, `key`).
+fn store_url(dsn: &str) -> Result<(String, String), String> {
+    let invalid = || format!("not a valid DSN: {dsn}");
+    let (scheme, rest) = dsn.trim().split_once("://").ok_or_else(invalid)?;
+    if scheme != "https" && scheme != "http" {
+        return Err(invalid());
+    }
+    let (key, location) = rest.split_once('@').ok_or_else(invalid)?;
+    let key = key.split(':').next().unwrap_or(key);
+    let (base, project) = location.trim_end_matches('/').rsplit_once('/').ok_or_else(invalid)?;
+    if key.is_empty() || base.is_empty() || project.is_empty() {
+        return Err(invalid());
+    }
+    Ok((format!("{scheme}://{base}/api/{project}/store/"), key.to_string()))
+}
+
+/// Strips what could identify the user or their infrastructure before anything leaves the machine.
+struct Scrubber {
+    redactor: Redactor,
+    replacements: Vec<(String, &'static str)>,
+    patterns: Vec<(Regex, &'static str)>,
+}
+
+impl Scrubber {
+    fn new(db: &Db) -> Self {
+        let mut replacements = Vec::new();
+        for var in ["HOME", "USERPROFILE"] {
+            if let Some(home) = std::env::var(var).ok().filter(|h| h.len() > 1) {
+                replacements.push((home, "~"));
+            }
+        }
+        for var in ["USER", "USERNAME"] {
+            if let Some(user) = std::env::var(var).ok().filter(|u| u.len() > 2) {
+                replacements.push((user, "<user>"));
+            }
+        }
+        let patterns = [
+            (r"[\w.+-]+@[\w-]+(\.[\w-]+)+", "<email>"),
+            (r"\b\d{1,3}(\.\d{1,3}){3}\b", "<ip>"),
+            (r"\b[\w.-]+@[\w.-]+", "<user@host>"),
+        ]
+        .into_iter()
+        .filter_map(|(p, r)| Regex::new(p).ok().map(|re| (re, r)))
+        .collect();
+        Self {
+            redactor: Redactor::new(&db.redaction_rules_list().unwrap_or_default()),
+            replacements,
+            patterns,
+        }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        let mut out = self.redactor.redact(text);
+        for (needle, replacement) in &self.replacements {
+            out = out.replace(needle.as_str(), replacement);
+        }
+        for (re, replacement) in &self.patterns {
+            out = re.replace_all(&out, *replacement).into_owned();
+        }
+        out
+    }
+}
+
+fn sentry_event(report: &CrashReport, scrubber: &Scrubber) -> serde_json::Value {
+    let message = scrubber.scrub(&report.message);
+    serde_json::json!({
+        "event_id": report.id,
+        "timestamp": report.timestamp,
+        "platform": "native",
+        "level": match report.kind {
+            CrashKind::Panic => "fatal",
+            CrashKind::Error => "error",
+        },
+        "logger": "opspad",
+        "release": format!("opspad@{}", report.app_version),
+        "message": { "formatted": message },
+        "exception": { "values": [{
+            "type": match report.kind {
+                CrashKind::Panic => "panic",
+                CrashKind::Error => "error",
+            },
+            "value": message,
+        }]},
+        "contexts": { "os": { "name": report.os }, "device": { "arch": report.arch } },
+        "tags": { "thread": report.thread.as_deref().map(|t| scrubber.scrub(t)) },
+        "extra": {
+            "location": report.location.as_deref().map(|l| scrubber.scrub(l)),
+            "backtrace": report.backtrace.as_deref().map(|b| scrubber.scrub(b)),
+        },
+    })
+}
+
+fn upload(report: &CrashReport, url: &str, key: &str, scrubber: &Scrubber) -> Result<(), String> {
+    let auth = format!(
+        "Sentry sentry_version=7, sentry_key={key}, sentry_client=opspad/{}",
+        report.app_version
+    );
+    ureq::AgentBuilder::new()
+        .timeout(UPLOAD_TIMEOUT)
+        .build()
+        .post(url)
+        .set("X-Sentry-Auth", &auth)
+        .set("Content-Type", "application/json")
+        .send_string(&sentry_event(report, scrubber).to_string())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Upload every report not sent yet, if the user opted in. Returns how many were sent.
+pub fn upload_pending(db: &Db) -> Result<usize, String> {
+    let Some(ctx) = CONTEXT.get() else {
+        return Ok(0);
+    };
+    let config = CrashConfig::load(db);
+    let (true, Some(dsn)) = (config.upload, config.dsn.as_deref()) else {
+        return Ok(0);
+    };
+    let (url, key) = store_url(dsn)?;
+    let scrubber = Scrubber::new(db);
+    let mut sent = 0;
+    for file in report_files(&ctx.dir) {
+        let Some(mut report) = fs::read_to_string(&file)
+            .ok()
+            .and_then(|s| serde_json::from_str::<CrashReport>(&s).ok())
+        else {
+            continue;
+        };
+        if report.uploaded {
+            continue;
+        }
+        upload(&report, &url, &key, &scrubber)?;
+        report.uploaded = true;
+        if let Ok(json) = serde_json::to_string_pretty(&report) {
+            let _ = fs::write(&file, json);
+        }
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// Upload pending reports on a background thread (a no-op until app state is managed).
+pub fn spawn_upload() {
+    let Some(ctx) = CONTEXT.get() else {
+        return;
+    };
+    let app = ctx.app.clone();
+    thread::spawn(move || {
+        let Some(state) = app.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        if let Err(e) = upload_pending(&state.db) {
+            log::warn!("crash report upload failed: {e}");
+        }
+    });
+}
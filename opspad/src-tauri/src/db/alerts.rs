@@ -0,0 +1,297 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+use crate::alerts::AlertCondition;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub condition: AlertCondition,
+    /// Limit the rule to one host; `None` applies it to every host.
+    pub host_id: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRuleCreate {
+    pub name: String,
+    pub condition: AlertCondition,
+    pub host_id: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub id: String,
+    /// `None` once the rule has been deleted.
+    pub rule_id: Option<String>,
+    pub rule_name: String,
+    pub host_id: Option<String>,
+    /// What fired within the rule (a mount point, a job id); one open alert per rule, host and subject.
+    pub subject: String,
+    pub message: String,
+    /// "open" | "acknowledged" | "resolved"
+    pub status: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    pub acknowledged_at: Option<i64>,
+    pub resolved_at: Option<i64>,
+}
+
+/// Resolved alerts kept.
+const ALERT_RETENTION: i64 = 500;
+
+const ALERT_COLUMNS: &str =
+    "id, rule_id, rule_name, host_id, subject, message, status, created_at, acknowledged_at, resolved_at";
+
+impl Db {
+    fn alert_rule_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<AlertRule> {
+        let condition_json: String = r.get(2)?;
+        let condition = serde_json::from_str(&condition_json)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+        Ok(AlertRule {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            condition,
+            host_id: r.get(3)?,
+            enabled: r.get::<_, i64>(4)? != 0,
+        })
+    }
+
+    fn alert_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Alert> {
+        Ok(Alert {
+            id: r.get(0)?,
+            rule_id: r.get(1)?,
+            rule_name: r.get(2)?,
+            host_id: r.get(3)?,
+            subject: r.get(4)?,
+            message: r.get(5)?,
+            status: r.get(6)?,
+            created_at: r.get(7)?,
+            acknowledged_at: r.get(8)?,
+            resolved_at: r.get(9)?,
+        })
+    }
+
+    pub fn alert_rules_list(&self) -> rusqlite::Result<Vec<AlertRule>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, name, condition_json, host_id, enabled from alert_rules order by created_at asc",
+        )?;
+        let rows = stmt.query_map([], Self::alert_rule_row)?;
+        rows.collect()
+    }
+
+    pub fn alert_rules_create(&self, input: AlertRuleCreate) -> rusqlite::Result<AlertRule> {
+        let rule = AlertRule {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            condition: input.condition,
+            host_id: input.host_id.filter(|h| !h.trim().is_empty()),
+            enabled: input.enabled.unwrap_or(true),
+        };
+        let condition_json = serde_json::to_string(&rule.condition)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute(
+            "insert into alert_rules (id, name, condition_json, host_id, enabled, created_at) values (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                rule.id,
+                rule.name,
+                condition_json,
+                rule.host_id,
+                if rule.enabled { 1i64 } else { 0i64 },
+                Self::now_epoch_millis()
+            ],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn alert_rules_update(&self, input: AlertRule) -> rusqlite::Result<AlertRule> {
+        let input = AlertRule {
+            host_id: input.host_id.filter(|h| !h.trim().is_empty()),
+            ..input
+        };
+        let condition_json = serde_json::to_string(&input.condition)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute(
+            "update alert_rules set name = ?2, condition_json = ?3, host_id = ?4, enabled = ?5 where id = ?1",
+            params![
+                input.id,
+                input.name,
+                condition_json,
+                input.host_id,
+                if input.enabled { 1i64 } else { 0i64 }
+            ],
+        )?;
+        Ok(input)
+    }
+
+    /// Alerts the rule raised stay in the feed, detached from it.
+    pub fn alert_rules_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("update alerts set rule_id = null where rule_id = ?1", params![id])?;
+        conn.execute("delete from alert_rules where id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Open an alert unless one for the same rule, host and subject is already open or
+    /// acknowledged. Returns the new alert, or `None` if it was a duplicate.
+    pub fn alerts_raise(
+        &self,
+        rule: &AlertRule,
+        host_id: Option<&str>,
+        subject: &str,
+        message: &str,
+    ) -> rusqlite::Result<Option<Alert>> {
+        let conn = self.conn();
+        let existing: Option<String> = conn
+            .query_row(
+                "select id from alerts where rule_id = ?1 and host_id is ?2 and subject = ?3 and status != 'resolved'",
+                params![rule.id, host_id, subject],
+                |r| r.get(0),
+            )
+            .optional()?;
+        if existing.is_some() {
+            return Ok(None);
+        }
+        let alert = Alert {
+            id: Uuid::new_v4().to_string(),
+            rule_id: Some(rule.id.clone()),
+            rule_name: rule.name.clone(),
+            host_id: host_id.map(str::to_string),
+            subject: subject.to_string(),
+            message: message.to_string(),
+            status: "open".to_string(),
+            created_at: Self::now_epoch_millis(),
+            acknowledged_at: None,
+            resolved_at: None,
+        };
+        conn.execute(
+            "insert into alerts (id, rule_id, rule_name, host_id, subject, message, status, created_at)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                alert.id,
+                alert.rule_id,
+                alert.rule_name,
+                alert.host_id,
+                alert.subject,
+                alert.message,
+                alert.status,
+                alert.created_at
+            ],
+        )?;
+        conn.execute(
+            "delete from alerts where id in (\n               select id from alerts where status = 'resolved'\n               order by created_at desc limit -1 offset ?1\n             )",
+            params![ALERT_RETENTION],
+        )?;
+        Ok(Some(alert))
+    }
+
+    /// Resolve a rule's unresolved alerts for a host whose condition has cleared, except the
+    /// subjects in `still_firing`. Returns the alerts resolved.
+    pub fn alerts_auto_resolve(
+        &self,
+        rule_id: &str,
+        host_id: &str,
+        still_firing: &[String],
+    ) -> rusqlite::Result<Vec<Alert>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {ALERT_COLUMNS} from alerts where rule_id = ?1 and host_id = ?2 and status != 'resolved'"
+        ))?;
+        let open = stmt
+            .query_map(params![rule_id, host_id], Self::alert_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let now = Self::now_epoch_secs();
+        let mut resolved = Vec::new();
+        for mut alert in open.into_iter().filter(|a| !still_firing.contains(&a.subject)) {
+            conn.execute(
+                "update alerts set status = 'resolved', resolved_at = ?2 where id = ?1",
+                params![alert.id, now],
+            )?;
+            alert.status = "resolved".to_string();
+            alert.resolved_at = Some(now);
+            resolved.push(alert);
+        }
+        Ok(resolved)
+    }
+
+    /// Resolve a rule's unresolved alert for `subject`, if any. Returns the alerts resolved.
+    pub fn alerts_resolve_subject(&self, rule_id: &str, subject: &str) -> rusqlite::Result<Vec<Alert>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {ALERT_COLUMNS} from alerts where rule_id = ?1 and subject = ?2 and status != 'resolved'"
+        ))?;
+        let open = stmt
+            .query_map(params![rule_id, subject], Self::alert_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let now = Self::now_epoch_secs();
+        conn.execute(
+            "update alerts set status = 'resolved', resolved_at = ?3 where rule_id = ?1 and subject = ?2 and status != 'resolved'",
+            params![rule_id, subject, now],
+        )?;
+        Ok(open
+            .into_iter()
+            .map(|a| Alert {
+                status: "resolved".to_string(),
+                resolved_at: Some(now),
+                ..a
+            })
+            .collect())
+    }
+
+    /// Newest first; `status` filters to one status.
+    pub fn alerts_list(&self, status: Option<&str>, limit: i64) -> rusqlite::Result<Vec<Alert>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {ALERT_COLUMNS} from alerts where (?1 is null or status = ?1) order by created_at desc limit ?2"
+        ))?;
+        let rows = stmt.query_map(params![status, limit], Self::alert_row)?;
+        rows.collect()
+    }
+
+    /// Alerts that fired or resolved from `since` to `until` (inclusive epoch milliseconds).
+    pub fn alerts_between(&self, since: i64, until: i64, limit: i64) -> rusqlite::Result<Vec<Alert>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {ALERT_COLUMNS} from alerts\n             where (created_at >= ?1 and created_at <= ?2) or (resolved_at >= ?1 / 1000 and resolved_at <= ?2 / 1000)\n             order by created_at asc limit ?3"
+        ))?;
+        let rows = stmt.query_map(params![since, until, limit], Self::alert_row)?;
+        rows.collect()
+    }
+
+    pub fn alerts_get(&self, id: &str) -> rusqlite::Result<Option<Alert>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {ALERT_COLUMNS} from alerts where id = ?1"),
+            params![id],
+            Self::alert_row,
+        )
+        .optional()
+    }
+
+    pub fn alerts_acknowledge(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update alerts set status = 'acknowledged', acknowledged_at = ?2 where id = ?1 and status = 'open'",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+
+    pub fn alerts_resolve(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update alerts set status = 'resolved', resolved_at = ?2 where id = ?1 and status != 'resolved'",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+}
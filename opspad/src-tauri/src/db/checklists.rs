@@ -0,0 +1,171 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItem {
+    /// Position among the runbook's checkbox items, from 0.
+    pub index: i64,
+    pub text: String,
+    pub checked_at: Option<i64>,
+    /// The OS account that checked it.
+    pub checked_by: Option<String>,
+}
+
+/// One execution of a runbook's checkbox items, with its own checked state. The items are copied
+/// when the run starts, so later edits to the runbook don't shift a run in progress.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistRun {
+    pub id: String,
+    pub runbook_id: String,
+    /// The runbook's title when the run started.
+    pub runbook_title: String,
+    pub incident_id: Option<String>,
+    pub started_at: i64,
+    /// Set while every item is checked.
+    pub completed_at: Option<i64>,
+    pub checked: i64,
+    pub total: i64,
+    /// Empty in list responses.
+    pub items: Vec<ChecklistItem>,
+}
+
+const RUN_COLUMNS: &str = "r.id, r.runbook_id, r.runbook_title, r.incident_id, r.started_at, r.completed_at, \
+     (select count(1) from checklist_items i where i.run_id = r.id and i.checked_at is not null), \
+     (select count(1) from checklist_items i where i.run_id = r.id)";
+
+impl Db {
+    fn checklist_run_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<ChecklistRun> {
+        Ok(ChecklistRun {
+            id: r.get(0)?,
+            runbook_id: r.get(1)?,
+            runbook_title: r.get(2)?,
+            incident_id: r.get(3)?,
+            started_at: r.get(4)?,
+            completed_at: r.get(5)?,
+            checked: r.get(6)?,
+            total: r.get(7)?,
+            items: Vec::new(),
+        })
+    }
+
+    pub fn checklist_runs_start(
+        &self,
+        runbook_id: &str,
+        runbook_title: &str,
+        incident_id: Option<&str>,
+        items: &[String],
+    ) -> rusqlite::Result<ChecklistRun> {
+        let id = Uuid::new_v4().to_string();
+        {
+            let conn = self.conn();
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "insert into checklist_runs (id, runbook_id, runbook_title, incident_id, started_at) values (?1, ?2, ?3, ?4, ?5)",
+                params![id, runbook_id, runbook_title, incident_id, Self::now_epoch_secs()],
+            )?;
+            for (index, text) in items.iter().enumerate() {
+                tx.execute(
+                    "insert into checklist_items (run_id, idx, text) values (?1, ?2, ?3)",
+                    params![id, index as i64, text],
+                )?;
+            }
+            tx.commit()?;
+        }
+        self.checklist_runs_get(&id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    /// With its items.
+    pub fn checklist_runs_get(&self, id: &str) -> rusqlite::Result<Option<ChecklistRun>> {
+        let conn = self.conn();
+        let Some(mut run) = conn
+            .query_row(
+                &format!("select {RUN_COLUMNS} from checklist_runs r where r.id = ?1"),
+                params![id],
+                Self::checklist_run_row,
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let mut stmt = conn.prepare_cached(
+            "select idx, text, checked_at, checked_by from checklist_items where run_id = ?1 order by idx asc",
+        )?;
+        run.items = stmt
+            .query_map(params![id], |r| {
+                Ok(ChecklistItem {
+                    index: r.get(0)?,
+                    text: r.get(1)?,
+                    checked_at: r.get(2)?,
+                    checked_by: r.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Some(run))
+    }
+
+    /// Newest first, without items; filtered to a runbook and/or an incident.
+    pub fn checklist_runs_list(
+        &self,
+        runbook_id: Option<&str>,
+        incident_id: Option<&str>,
+        limit: i64,
+    ) -> rusqlite::Result<Vec<ChecklistRun>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {RUN_COLUMNS} from checklist_runs r \
+             where (?1 is null or r.runbook_id = ?1) and (?2 is null or r.incident_id = ?2) \
+             order by r.started_at desc, r.rowid desc limit ?3"
+        ))?;
+        let rows = stmt.query_map(params![runbook_id, incident_id, limit], Self::checklist_run_row)?;
+        rows.collect()
+    }
+
+    /// Check or uncheck an item, keeping the run's `completed_at` in step. `None` if the run or
+    /// item doesn't exist.
+    pub fn checklist_items_set(
+        &self,
+        run_id: &str,
+        index: i64,
+        checked: bool,
+        by: &str,
+    ) -> rusqlite::Result<Option<ChecklistRun>> {
+        {
+            let now = Self::now_epoch_secs();
+            let conn = self.conn();
+            let tx = conn.unchecked_transaction()?;
+            let changed = if checked {
+                tx.execute(
+                    "update checklist_items set checked_at = coalesce(checked_at, ?3), checked_by = coalesce(checked_by, ?4)\n                     where run_id = ?1 and idx = ?2",
+                    params![run_id, index, now, by],
+                )?
+            } else {
+                tx.execute(
+                    "update checklist_items set checked_at = null, checked_by = null where run_id = ?1 and idx = ?2",
+                    params![run_id, index],
+                )?
+            };
+            if changed == 0 {
+                return Ok(None);
+            }
+            tx.execute(
+                "update checklist_runs set completed_at = case\n                   when exists (select 1 from checklist_items where run_id = ?1 and checked_at is null) then null\n                   else coalesce(completed_at, ?2) end\n                 where id = ?1",
+                params![run_id, now],
+            )?;
+            tx.commit()?;
+        }
+        self.checklist_runs_get(run_id)
+    }
+
+    pub fn checklist_runs_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("delete from checklist_items where run_id = ?1", params![id])?;
+        tx.execute("delete from checklist_runs where id = ?1", params![id])?;
+        tx.commit()
+    }
+}
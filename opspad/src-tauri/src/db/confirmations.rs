@@ -0,0 +1,140 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cursor, Db, DestructivePolicy, Page};
+
+/// Evidence that a destructive command was confirmed before it ran: who, when, what they typed
+/// and exactly what was sent.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Confirmation {
+    pub id: String,
+    pub confirmed_at: i64,
+    /// The OS account OpsPad ran under.
+    pub os_user: String,
+    /// The incident active at the time, if any.
+    pub incident_id: Option<String>,
+    /// Set for terminal runs; fan-out runs have a host instead.
+    pub session_id: Option<String>,
+    pub scope: Option<String>,
+    pub host_id: Option<String>,
+    pub environment_tag: String,
+    pub dock_command_id: Option<String>,
+    pub dock_command_title: Option<String>,
+    /// The rendered command, redacted like history.
+    pub command_text: String,
+    pub policy: DestructivePolicy,
+    /// What the confirmation dialog sent: the typed environment name or the reason (redacted).
+    pub confirmation_text: Option<String>,
+    /// The CommandDock history entry of the run.
+    pub history_id: Option<String>,
+    /// Who gave the second approval, under a four-eyes environment: the approver's name and key
+    /// fingerprint.
+    pub approved_by: Option<String>,
+}
+
+/// Filters for `confirmations_list`; every field is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConfirmationQuery {
+    pub incident_id: Option<String>,
+    pub scope: Option<String>,
+    /// Epoch seconds, inclusive.
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+const CONFIRMATION_COLUMNS: &str = "id, confirmed_at, os_user, incident_id, session_id, scope, host_id, environment_tag, \
+     dock_command_id, dock_command_title, command_text, policy, confirmation_text, history_id, approved_by";
+
+impl Db {
+    /// Record a confirmation, filed under the active incident. `id` and `confirmed_at` are
+    /// assigned here.
+    pub fn confirmations_add(&self, input: Confirmation) -> rusqlite::Result<Confirmation> {
+        let incident_id = self.incidents_active()?.map(|i| i.id);
+        let conn = self.conn();
+        let redactor = Self::redactor_locked(&conn)?;
+        let confirmation = Confirmation {
+            id: Uuid::new_v4().to_string(),
+            confirmed_at: Self::now_epoch_secs(),
+            incident_id,
+            command_text: redactor.redact(&input.command_text),
+            confirmation_text: input.confirmation_text.map(|t| redactor.redact(&t)),
+            ..input
+        };
+        conn.execute(
+            &format!(
+                "insert into confirmations ({CONFIRMATION_COLUMNS}) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"
+            ),
+            params![
+                confirmation.id,
+                confirmation.confirmed_at,
+                confirmation.os_user,
+                confirmation.incident_id,
+                confirmation.session_id,
+                confirmation.scope,
+                confirmation.host_id,
+                confirmation.environment_tag,
+                confirmation.dock_command_id,
+                confirmation.dock_command_title,
+                confirmation.command_text,
+                confirmation.policy.as_str(),
+                confirmation.confirmation_text,
+                confirmation.history_id,
+                confirmation.approved_by
+            ],
+        )?;
+        Ok(confirmation)
+    }
+
+    /// Oldest first, as an audit trail reads; `cursor` continues after an earlier page.
+    pub fn confirmations_list(
+        &self,
+        query: &ConfirmationQuery,
+        cursor: Option<&Cursor>,
+    ) -> rusqlite::Result<Page<Confirmation>> {
+        let limit = query.limit.unwrap_or(-1);
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {CONFIRMATION_COLUMNS} from confirmations \
+             where (?1 is null or incident_id = ?1) and (?2 is null or scope = ?2) \
+             and (?3 is null or confirmed_at >= ?3) and (?4 is null or confirmed_at <= ?4) \
+             and (?6 is null or (confirmed_at, id) > (?6, ?7)) \
+             order by confirmed_at asc, id asc limit ?5"
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                query.incident_id,
+                query.scope,
+                query.since,
+                query.until,
+                if limit < 0 { -1 } else { limit + 1 },
+                cursor.map(|c| c.at),
+                cursor.map(|c| &c.id)
+            ],
+            |r| {
+                Ok(Confirmation {
+                    id: r.get(0)?,
+                    confirmed_at: r.get(1)?,
+                    os_user: r.get(2)?,
+                    incident_id: r.get(3)?,
+                    session_id: r.get(4)?,
+                    scope: r.get(5)?,
+                    host_id: r.get(6)?,
+                    environment_tag: r.get(7)?,
+                    dock_command_id: r.get(8)?,
+                    dock_command_title: r.get(9)?,
+                    command_text: r.get(10)?,
+                    policy: DestructivePolicy::parse(&r.get::<_, String>(11)?),
+                    confirmation_text: r.get(12)?,
+                    history_id: r.get(13)?,
+                    approved_by: r.get(14)?,
+                })
+            },
+        )?;
+        let rows = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Page::from_rows(rows, limit, |c| Cursor { at: c.confirmed_at, id: c.id.clone() }))
+    }
+}
@@ -0,0 +1,183 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// What happens when a destructive dock command (one with `requires_confirm`) is written to a
+/// terminal in this environment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DestructivePolicy {
+    /// Frontend confirmation only.
+    #[default]
+    Allow,
+    /// The write must carry a confirmation (the user clicked through a confirm dialog).
+    Confirm,
+    /// The write must carry the environment name, typed by the user.
+    TypedName,
+    /// The write must carry a reason, which is kept with the command in history.
+    Reason,
+    Block,
+}
+
+impl DestructivePolicy {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Confirm => "confirm",
+            Self::TypedName => "typedName",
+            Self::Reason => "reason",
+            Self::Block => "block",
+        }
+    }
+
+    pub(super) fn parse(s: &str) -> Self {
+        match s {
+            "confirm" => Self::Confirm,
+            "typedName" => Self::TypedName,
+            "reason" => Self::Reason,
+            "block" => Self::Block,
+            _ => Self::Allow,
+        }
+    }
+}
+
+/// How a destructive command got past its environment's policy; stored with it in history.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyApproval {
+    pub policy: DestructivePolicy,
+    /// The reason given, under [`DestructivePolicy::Reason`].
+    pub reason: Option<String>,
+    /// Whatever the confirmation dialog sent (the typed name, the reason), for the audit trail.
+    #[serde(skip)]
+    pub confirmation: Option<String>,
+    /// The second person who approved it, under [`Environment::four_eyes`].
+    pub approved_by: Option<String>,
+}
+
+/// Execution policy for one environment tag (names are stored upper-case).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    pub name: String,
+    /// Also the color of its hosts and single-environment dock commands that have none of their own.
+    pub color: Option<String>,
+    #[serde(default)]
+    pub destructive_policy: DestructivePolicy,
+    /// Write a warning banner into new sessions' terminals (see `terminal::banner`).
+    #[serde(default)]
+    pub banner: bool,
+    /// Extra line shown in the banner.
+    #[serde(default)]
+    pub banner_text: Option<String>,
+    /// Destructive commands also need a signed approval from a trusted teammate (see `four_eyes`).
+    #[serde(default)]
+    pub four_eyes: bool,
+}
+
+const DEFAULT_ENVIRONMENTS: &[(&str, DestructivePolicy)] = &[
+    ("DEV", DestructivePolicy::Allow),
+    ("STAGE", DestructivePolicy::Allow),
+    ("PROD", DestructivePolicy::TypedName),
+];
+
+const ENVIRONMENT_COLUMNS: &str = "name, color, destructive_policy, banner, banner_text, four_eyes";
+
+impl Db {
+    pub(super) fn maybe_seed_environments(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row("select count(1) from environments", [], |r| r.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        for (i, (name, policy)) in DEFAULT_ENVIRONMENTS.iter().enumerate() {
+            conn.execute(
+                "insert into environments (name, color, destructive_policy, banner, sort_order) values (?1, null, ?2, ?3, ?4)",
+                params![name, policy.as_str(), *policy != DestructivePolicy::Allow, (i as i64) + 1],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn environment_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Environment> {
+        Ok(Environment {
+            name: r.get(0)?,
+            color: r.get(1)?,
+            destructive_policy: DestructivePolicy::parse(&r.get::<_, String>(2)?),
+            banner: r.get::<_, i64>(3)? != 0,
+            banner_text: r.get(4)?,
+            four_eyes: r.get::<_, i64>(5)? != 0,
+        })
+    }
+
+    pub fn environments_list(&self) -> rusqlite::Result<Vec<Environment>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {ENVIRONMENT_COLUMNS} from environments order by sort_order asc nulls last, name asc"
+        ))?;
+        let rows = stmt.query_map([], Self::environment_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    /// Policy for an environment tag; unknown tags get the permissive default.
+    pub fn environment_get(&self, name: &str) -> rusqlite::Result<Environment> {
+        let name = name.trim().to_uppercase();
+        let conn = self.conn();
+        let found = conn
+            .query_row(
+                &format!("select {ENVIRONMENT_COLUMNS} from environments where name = ?1"),
+                params![name],
+                Self::environment_row,
+            )
+            .optional()?;
+        Ok(found.unwrap_or(Environment {
+            name,
+            color: None,
+            destructive_policy: DestructivePolicy::Allow,
+            banner: false,
+            banner_text: None,
+            four_eyes: false,
+        }))
+    }
+
+    pub fn environments_upsert(&self, input: Environment) -> rusqlite::Result<Environment> {
+        let env = Environment {
+            name: input.name.trim().to_uppercase(),
+            banner_text: input.banner_text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+            ..input
+        };
+        let conn = self.conn();
+        let next_sort: i64 = conn.query_row(
+            "select coalesce(max(sort_order), 0) + 1 from environments",
+            [],
+            |r| r.get(0),
+        )?;
+        conn.execute(
+            "insert into environments (name, color, destructive_policy, banner, banner_text, four_eyes, sort_order)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7)\n             on conflict(name) do update set color = excluded.color, destructive_policy = excluded.destructive_policy,\n             banner = excluded.banner, banner_text = excluded.banner_text, four_eyes = excluded.four_eyes",
+            params![
+                env.name,
+                env.color,
+                env.destructive_policy.as_str(),
+                env.banner,
+                env.banner_text,
+                env.four_eyes,
+                next_sort
+            ],
+        )?;
+        Ok(env)
+    }
+
+    pub fn environments_delete(&self, name: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "delete from environments where name = ?1",
+            params![name.trim().to_uppercase()],
+        )?;
+        Ok(())
+    }
+}
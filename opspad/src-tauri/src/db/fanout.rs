@@ -0,0 +1,152 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FanoutHostResult {
+    pub host_id: String,
+    pub host_label: String,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Set when the command could not be run at all (spawn failure, unknown host, ...).
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+}
+
+impl FanoutHostResult {
+    pub fn ok(&self) -> bool {
+        self.error.is_none() && !self.timed_out && self.exit_code == Some(0)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FanoutRun {
+    pub id: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    pub command_id: Option<String>,
+    pub command_title: String,
+    pub command_text: String,
+    pub host_count: i64,
+    pub ok_count: i64,
+    pub failed_count: i64,
+    /// Per-host results (empty in list responses).
+    #[serde(default)]
+    pub results: Vec<FanoutHostResult>,
+}
+
+/// Keep the most recent fan-out reports only.
+const FANOUT_RETENTION: i64 = 50;
+
+impl Db {
+    /// Persist a finished run (stamping `created_at`) and prune old reports.
+    pub fn fanout_run_save(&self, run: &mut FanoutRun) -> rusqlite::Result<()> {
+        run.created_at = Self::now_epoch_millis();
+        let conn = self.conn();
+        let command_text = Self::redactor_locked(&conn)?.redact(&run.command_text);
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "insert into fanout_runs (id, created_at, command_id, command_title, command_text, host_count, ok_count, failed_count)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                run.id,
+                run.created_at,
+                run.command_id,
+                run.command_title,
+                command_text,
+                run.host_count,
+                run.ok_count,
+                run.failed_count
+            ],
+        )?;
+        for r in &run.results {
+            tx.execute(
+                "insert into fanout_results (run_id, host_id, host_label, exit_code, stdout, stderr, error, duration_ms, timed_out)\n                 values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    run.id,
+                    r.host_id,
+                    r.host_label,
+                    r.exit_code,
+                    r.stdout,
+                    r.stderr,
+                    r.error,
+                    r.duration_ms as i64,
+                    r.timed_out as i64
+                ],
+            )?;
+        }
+        tx.execute(
+            "delete from fanout_runs where id in (select id from fanout_runs order by created_at desc limit -1 offset ?1)",
+            params![FANOUT_RETENTION],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn fanout_run_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<FanoutRun> {
+        Ok(FanoutRun {
+            id: r.get(0)?,
+            created_at: r.get(1)?,
+            command_id: r.get(2)?,
+            command_title: r.get(3)?,
+            command_text: r.get(4)?,
+            host_count: r.get(5)?,
+            ok_count: r.get(6)?,
+            failed_count: r.get(7)?,
+            results: Vec::new(),
+        })
+    }
+
+    pub fn fanout_runs_list(&self, limit: i64) -> rusqlite::Result<Vec<FanoutRun>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, created_at, command_id, command_title, command_text, host_count, ok_count, failed_count\n             from fanout_runs order by created_at desc limit ?1",
+        )?;
+        let rows = stmt.query_map(params![limit], Self::fanout_run_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn fanout_run_get(&self, id: &str) -> rusqlite::Result<Option<FanoutRun>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, created_at, command_id, command_title, command_text, host_count, ok_count, failed_count\n             from fanout_runs where id = ?1",
+        )?;
+        let Some(mut run) = stmt.query_map(params![id], Self::fanout_run_row)?.next().transpose()? else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(
+            "select host_id, host_label, exit_code, stdout, stderr, error, duration_ms, timed_out\n             from fanout_results where run_id = ?1 order by host_label asc",
+        )?;
+        let rows = stmt.query_map(params![id], |r| {
+            Ok(FanoutHostResult {
+                host_id: r.get(0)?,
+                host_label: r.get(1)?,
+                exit_code: r.get(2)?,
+                stdout: r.get(3)?,
+                stderr: r.get(4)?,
+                error: r.get(5)?,
+                duration_ms: r.get::<_, i64>(6)? as u64,
+                timed_out: r.get::<_, i64>(7)? != 0,
+            })
+        })?;
+        for row in rows {
+            run.results.push(row?);
+        }
+        Ok(Some(run))
+    }
+
+    pub fn fanout_run_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from fanout_runs where id = ?1", params![id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HighlightSeverity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl HighlightSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "success" => Self::Success,
+            "warning" => Self::Warning,
+            "error" => Self::Error,
+            _ => Self::Info,
+        }
+    }
+}
+
+/// A pattern marked in session output (see `terminal::highlight`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    /// `None`: every session. Otherwise "local", "ssh:<host_id>" or "env:<TAG>".
+    pub scope: Option<String>,
+    pub severity: HighlightSeverity,
+    /// CSS color overriding the severity's.
+    pub color: Option<String>,
+    /// Shown on hover.
+    pub annotation: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightRuleCreate {
+    pub name: String,
+    pub pattern: String,
+    pub scope: Option<String>,
+    pub severity: Option<HighlightSeverity>,
+    pub color: Option<String>,
+    pub annotation: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Built-in rules seeded on first run.
+const DEFAULT_RULES: &[(&str, &str, HighlightSeverity, &str)] = &[
+    (
+        "Errors",
+        r"(?i)\b(?:error|failed|failure|fatal|panic|exception)\b",
+        HighlightSeverity::Error,
+        "Error",
+    ),
+    (
+        "Warnings",
+        r"(?i)\b(?:warn|warning|deprecated)\b",
+        HighlightSeverity::Warning,
+        "Warning",
+    ),
+    (
+        "IPv4 addresses",
+        r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+        HighlightSeverity::Info,
+        "IP address",
+    ),
+    (
+        "Request ids",
+        r"\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b",
+        HighlightSeverity::Info,
+        "Request id",
+    ),
+];
+
+fn clean(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl Db {
+    pub(super) fn maybe_seed_highlight_rules(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row("select count(1) from highlight_rules", [], |r| r.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        for (i, (name, pattern, severity, annotation)) in DEFAULT_RULES.iter().enumerate() {
+            conn.execute(
+                "insert into highlight_rules (id, name, pattern, scope, severity, color, annotation, enabled, sort_order)\n                 values (?1, ?2, ?3, null, ?4, null, ?5, 1, ?6)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    name,
+                    pattern,
+                    severity.as_str(),
+                    annotation,
+                    (i as i64) + 1
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn highlight_rules_list(&self) -> rusqlite::Result<Vec<HighlightRule>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, name, pattern, scope, severity, color, annotation, enabled from highlight_rules\n             order by sort_order asc nulls last, name asc",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(HighlightRule {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                pattern: r.get(2)?,
+                scope: r.get(3)?,
+                severity: HighlightSeverity::parse(&r.get::<_, String>(4)?),
+                color: r.get(5)?,
+                annotation: r.get(6)?,
+                enabled: r.get::<_, i64>(7)? != 0,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn highlight_rules_create(&self, input: HighlightRuleCreate) -> rusqlite::Result<HighlightRule> {
+        let rule = HighlightRule {
+            id: Uuid::new_v4().to_string(),
+            name: input.name.trim().to_string(),
+            pattern: input.pattern,
+            scope: clean(input.scope),
+            severity: input.severity.unwrap_or_default(),
+            color: clean(input.color),
+            annotation: clean(input.annotation),
+            enabled: input.enabled.unwrap_or(true),
+        };
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from highlight_rules", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into highlight_rules (id, name, pattern, scope, severity, color, annotation, enabled, sort_order)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                rule.id,
+                rule.name,
+                rule.pattern,
+                rule.scope,
+                rule.severity.as_str(),
+                rule.color,
+                rule.annotation,
+                if rule.enabled { 1i64 } else { 0i64 },
+                next
+            ],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn highlight_rules_update(&self, input: HighlightRule) -> rusqlite::Result<HighlightRule> {
+        let rule = HighlightRule {
+            name: input.name.trim().to_string(),
+            scope: clean(input.scope),
+            color: clean(input.color),
+            annotation: clean(input.annotation),
+            ..input
+        };
+        let conn = self.conn();
+        conn.execute(
+            "update highlight_rules set name = ?2, pattern = ?3, scope = ?4, severity = ?5, color = ?6, annotation = ?7,\n             enabled = ?8 where id = ?1",
+            params![
+                rule.id,
+                rule.name,
+                rule.pattern,
+                rule.scope,
+                rule.severity.as_str(),
+                rule.color,
+                rule.annotation,
+                if rule.enabled { 1i64 } else { 0i64 }
+            ],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn highlight_rules_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from highlight_rules where id = ?1", params![id])?;
+        Ok(())
+    }
+}
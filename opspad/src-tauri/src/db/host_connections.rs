@@ -0,0 +1,82 @@
+use rusqlite::params;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Db;
+
+/// Connections kept per host; older ones are pruned.
+const KEEP_PER_HOST: i64 = 200;
+
+/// An ssh session opened to a host, with the address it actually went to (a DNS-expanded host's
+/// hostname names many).
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostConnection {
+    pub id: String,
+    pub host_id: String,
+    pub session_id: String,
+    /// The host's hostname when connected.
+    pub hostname: String,
+    pub address: String,
+    pub port: u16,
+    pub connected_at: i64,
+}
+
+impl Db {
+    pub fn host_connections_add(
+        &self,
+        host_id: &str,
+        session_id: &str,
+        hostname: &str,
+        address: &str,
+        port: u16,
+    ) -> rusqlite::Result<HostConnection> {
+        let connection = HostConnection {
+            id: Uuid::new_v4().to_string(),
+            host_id: host_id.to_string(),
+            session_id: session_id.to_string(),
+            hostname: hostname.to_string(),
+            address: address.to_string(),
+            port,
+            connected_at: Self::now_epoch_secs(),
+        };
+        let conn = self.conn();
+        conn.execute(
+            "insert into host_connections (id, host_id, session_id, hostname, address, port, connected_at) values (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                connection.id,
+                connection.host_id,
+                connection.session_id,
+                connection.hostname,
+                connection.address,
+                connection.port as u32,
+                connection.connected_at
+            ],
+        )?;
+        conn.execute(
+            "delete from host_connections where host_id = ?1 and id in (\n               select id from host_connections where host_id = ?1 order by connected_at desc, rowid desc limit -1 offset ?2\n             )",
+            params![host_id, KEEP_PER_HOST],
+        )?;
+        Ok(connection)
+    }
+
+    /// Newest first.
+    pub fn host_connections_list(&self, host_id: &str, limit: i64) -> rusqlite::Result<Vec<HostConnection>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, host_id, session_id, hostname, address, port, connected_at from host_connections\n             where host_id = ?1 order by connected_at desc, rowid desc limit ?2",
+        )?;
+        let rows = stmt.query_map(params![host_id, limit], |r| {
+            Ok(HostConnection {
+                id: r.get(0)?,
+                host_id: r.get(1)?,
+                session_id: r.get(2)?,
+                hostname: r.get(3)?,
+                address: r.get(4)?,
+                port: r.get::<_, u32>(5)? as u16,
+                connected_at: r.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,57 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostGroup {
+    pub id: String,
+    pub name: String,
+}
+
+impl Db {
+    pub fn host_groups_list(&self) -> rusqlite::Result<Vec<HostGroup>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("select id, name from host_groups order by sort_order asc nulls last, name asc")?;
+        let rows = stmt.query_map([], |r| Ok(HostGroup { id: r.get(0)?, name: r.get(1)? }))?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn host_groups_create(&self, name: &str) -> rusqlite::Result<HostGroup> {
+        let group = HostGroup {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+        };
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from host_groups", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into host_groups (id, name, sort_order) values (?1, ?2, ?3)",
+            params![group.id, group.name, next],
+        )?;
+        Ok(group)
+    }
+
+    pub fn host_groups_update(&self, input: HostGroup) -> rusqlite::Result<HostGroup> {
+        let conn = self.conn();
+        conn.execute("update host_groups set name = ?2 where id = ?1", params![input.id, input.name])?;
+        Ok(input)
+    }
+
+    /// Delete a group; member hosts become ungrouped.
+    pub fn host_groups_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("update hosts set group_id = null where group_id = ?1", params![id])?;
+        tx.execute("delete from host_groups where id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+fn default_expected_status() -> u16 {
+    200
+}
+
+/// An HTTP(S) endpoint the monitor requests each round.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheck {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_expected_status")]
+    pub expected_status: u16,
+    /// Responses slower than this count as "slow"; `None` uses the monitor's threshold.
+    pub slow_ms: Option<u64>,
+    #[serde(default)]
+    pub headers: Vec<HttpHeader>,
+    /// The host the service runs on, for badges and host-scoped alert rules; `None` for
+    /// standalone checks.
+    pub host_id: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheckCreate {
+    pub name: String,
+    pub url: String,
+    pub expected_status: Option<u16>,
+    pub slow_ms: Option<u64>,
+    #[serde(default)]
+    pub headers: Vec<HttpHeader>,
+    pub host_id: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// An HTTP check's result changing state, as recorded by the monitor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheckEvent {
+    pub id: i64,
+    pub check_id: String,
+    /// "up" | "slow" | "down"
+    pub status: String,
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+}
+
+/// Transitions kept per check.
+const EVENT_RETENTION: i64 = 200;
+
+const HTTP_CHECK_COLUMNS: &str = "id, name, url, expected_status, slow_ms, headers_json, host_id, enabled";
+
+impl Db {
+    fn http_check_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<HttpCheck> {
+        let headers_json: String = r.get(5)?;
+        Ok(HttpCheck {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            url: r.get(2)?,
+            expected_status: r.get::<_, i64>(3)? as u16,
+            slow_ms: r.get::<_, Option<i64>>(4)?.map(|v| v as u64),
+            headers: serde_json::from_str(&headers_json).unwrap_or_default(),
+            host_id: r.get(6)?,
+            enabled: r.get::<_, i64>(7)? != 0,
+        })
+    }
+
+    pub fn http_checks_list(&self) -> rusqlite::Result<Vec<HttpCheck>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {HTTP_CHECK_COLUMNS} from http_checks order by name collate nocase asc"
+        ))?;
+        let rows = stmt.query_map([], Self::http_check_row)?;
+        rows.collect()
+    }
+
+    pub fn http_checks_get(&self, id: &str) -> rusqlite::Result<Option<HttpCheck>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {HTTP_CHECK_COLUMNS} from http_checks where id = ?1"),
+            params![id],
+            Self::http_check_row,
+        )
+        .optional()
+    }
+
+    pub fn http_checks_create(&self, input: HttpCheckCreate) -> rusqlite::Result<HttpCheck> {
+        let check = HttpCheck {
+            id: Uuid::new_v4().to_string(),
+            name: input.name.trim().to_string(),
+            url: input.url.trim().to_string(),
+            expected_status: input.expected_status.unwrap_or_else(default_expected_status),
+            slow_ms: input.slow_ms,
+            headers: input.headers,
+            host_id: input.host_id.filter(|h| !h.trim().is_empty()),
+            enabled: input.enabled.unwrap_or(true),
+        };
+        let headers_json = serde_json::to_string(&check.headers)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute(
+            "insert into http_checks (id, name, url, expected_status, slow_ms, headers_json, host_id, enabled, created_at)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                check.id,
+                check.name,
+                check.url,
+                check.expected_status as i64,
+                check.slow_ms.map(|v| v as i64),
+                headers_json,
+                check.host_id,
+                if check.enabled { 1i64 } else { 0i64 },
+                Self::now_epoch_millis()
+            ],
+        )?;
+        Ok(check)
+    }
+
+    pub fn http_checks_update(&self, input: HttpCheck) -> rusqlite::Result<HttpCheck> {
+        let check = HttpCheck {
+            name: input.name.trim().to_string(),
+            url: input.url.trim().to_string(),
+            host_id: input.host_id.filter(|h| !h.trim().is_empty()),
+            ..input
+        };
+        let headers_json = serde_json::to_string(&check.headers)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute(
+            "update http_checks set name = ?2, url = ?3, expected_status = ?4, slow_ms = ?5, headers_json = ?6,\n             host_id = ?7, enabled = ?8 where id = ?1",
+            params![
+                check.id,
+                check.name,
+                check.url,
+                check.expected_status as i64,
+                check.slow_ms.map(|v| v as i64),
+                headers_json,
+                check.host_id,
+                if check.enabled { 1i64 } else { 0i64 }
+            ],
+        )?;
+        Ok(check)
+    }
+
+    pub fn http_checks_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from http_check_events where check_id = ?1", params![id])?;
+        conn.execute("delete from http_checks where id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn http_check_record(
+        &self,
+        check_id: &str,
+        status: &str,
+        http_status: Option<u16>,
+        latency_ms: Option<u64>,
+        detail: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "insert into http_check_events (check_id, status, http_status, latency_ms, detail, created_at)\n             values (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                check_id,
+                status,
+                http_status.map(|v| v as i64),
+                latency_ms.map(|v| v as i64),
+                detail,
+                Self::now_epoch_millis()
+            ],
+        )?;
+        conn.execute(
+            "delete from http_check_events where check_id = ?1 and id not in (\n               select id from http_check_events where check_id = ?1 order by id desc limit ?2\n             )",
+            params![check_id, EVENT_RETENTION],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent transitions first.
+    pub fn http_check_history(&self, check_id: &str, limit: i64) -> rusqlite::Result<Vec<HttpCheckEvent>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, check_id, status, http_status, latency_ms, detail, created_at from http_check_events\n             where check_id = ?1 order by id desc limit ?2",
+        )?;
+        let rows = stmt.query_map(params![check_id, limit], |r| {
+            Ok(HttpCheckEvent {
+                id: r.get(0)?,
+                check_id: r.get(1)?,
+                status: r.get(2)?,
+                http_status: r.get::<_, Option<i64>>(3)?.map(|v| v as u16),
+                latency_ms: r.get::<_, Option<i64>>(4)?.map(|v| v as u64),
+                detail: r.get(5)?,
+                created_at: r.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
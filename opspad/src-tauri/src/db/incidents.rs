@@ -0,0 +1,96 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Db;
+
+/// A stretch of incident work. While one is active, destructive-command confirmations are
+/// filed under it.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub id: String,
+    pub title: String,
+    /// The ticket it belongs to, e.g. a PagerDuty incident number.
+    pub reference: Option<String>,
+    pub started_at: i64,
+    /// None while active.
+    pub ended_at: Option<i64>,
+}
+
+const INCIDENT_COLUMNS: &str = "id, title, reference, started_at, ended_at";
+
+impl Db {
+    fn incident_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Incident> {
+        Ok(Incident {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            reference: r.get(2)?,
+            started_at: r.get(3)?,
+            ended_at: r.get(4)?,
+        })
+    }
+
+    /// Start an incident, ending the active one (only one is active at a time).
+    pub fn incidents_start(&self, title: &str, reference: Option<&str>) -> rusqlite::Result<Incident> {
+        let incident = Incident {
+            id: Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            reference: reference.map(str::to_string),
+            started_at: Self::now_epoch_secs(),
+            ended_at: None,
+        };
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "update incidents set ended_at = ?1 where ended_at is null",
+            params![incident.started_at],
+        )?;
+        tx.execute(
+            "insert into incidents (id, title, reference, started_at) values (?1, ?2, ?3, ?4)",
+            params![incident.id, incident.title, incident.reference, incident.started_at],
+        )?;
+        tx.commit()?;
+        Ok(incident)
+    }
+
+    /// End the active incident, if any.
+    pub fn incidents_end(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update incidents set ended_at = ?1 where ended_at is null",
+            params![Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+
+    pub fn incidents_active(&self) -> rusqlite::Result<Option<Incident>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {INCIDENT_COLUMNS} from incidents where ended_at is null order by started_at desc limit 1"),
+            [],
+            Self::incident_row,
+        )
+        .optional()
+    }
+
+    pub fn incidents_get(&self, id: &str) -> rusqlite::Result<Option<Incident>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {INCIDENT_COLUMNS} from incidents where id = ?1"),
+            params![id],
+            Self::incident_row,
+        )
+        .optional()
+    }
+
+    /// Newest first.
+    pub fn incidents_list(&self, limit: i64) -> rusqlite::Result<Vec<Incident>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {INCIDENT_COLUMNS} from incidents order by started_at desc limit ?1"
+        ))?;
+        let rows = stmt.query_map(params![limit], Self::incident_row)?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,180 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cursor, Db, Page};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// Same shape as a terminal scope: "local" or "ssh:<host_id>".
+    pub target: String,
+    pub target_label: String,
+    pub command: String,
+    /// "queued" | "running" | "succeeded" | "failed" | "timedOut" | "cancelled"
+    pub status: String,
+    pub exit_code: Option<i32>,
+    /// Captured output (empty in list responses).
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    pub truncated: bool,
+    pub error: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Final state of a job, written once its process has exited (or failed to start).
+pub struct JobFinish<'a> {
+    pub status: &'a str,
+    pub exit_code: Option<i32>,
+    pub stdout: &'a str,
+    pub stderr: &'a str,
+    pub truncated: bool,
+    pub error: Option<&'a str>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Keep the most recent jobs only.
+const JOB_RETENTION: i64 = 200;
+
+const JOB_COLUMNS: &str = "id, created_at, started_at, finished_at, target, target_label, command, status, exit_code, truncated, error, duration_ms";
+
+impl Db {
+    fn job_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Job> {
+        Ok(Job {
+            id: r.get(0)?,
+            created_at: r.get(1)?,
+            started_at: r.get(2)?,
+            finished_at: r.get(3)?,
+            target: r.get(4)?,
+            target_label: r.get(5)?,
+            command: r.get(6)?,
+            status: r.get(7)?,
+            exit_code: r.get(8)?,
+            stdout: String::new(),
+            stderr: String::new(),
+            truncated: r.get::<_, i64>(9)? != 0,
+            error: r.get(10)?,
+            duration_ms: r.get::<_, Option<i64>>(11)?.map(|v| v as u64),
+        })
+    }
+
+    /// Record a new queued job. The command text is redacted before it is stored.
+    pub fn jobs_create(&self, target: &str, target_label: &str, command: &str) -> rusqlite::Result<Job> {
+        let conn = self.conn();
+        let job = Job {
+            id: Uuid::new_v4().to_string(),
+            created_at: Self::now_epoch_millis(),
+            started_at: None,
+            finished_at: None,
+            target: target.to_string(),
+            target_label: target_label.to_string(),
+            command: Self::redactor_locked(&conn)?.redact(command),
+            status: "queued".to_string(),
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            truncated: false,
+            error: None,
+            duration_ms: None,
+        };
+        conn.execute(
+            "insert into jobs (id, created_at, target, target_label, command, status, stdout, stderr)\n             values (?1, ?2, ?3, ?4, ?5, ?6, '', '')",
+            params![job.id, job.created_at, job.target, job.target_label, job.command, job.status],
+        )?;
+        conn.execute(
+            "delete from jobs where id in (\n               select id from jobs where status not in ('queued', 'running')\n               order by created_at desc limit -1 offset ?1\n             )",
+            params![JOB_RETENTION],
+        )?;
+        Ok(job)
+    }
+
+    pub fn jobs_mark_running(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update jobs set status = 'running', started_at = ?2 where id = ?1",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+
+    /// Put a job back to "queued" ahead of a retry, noting why in `error`.
+    pub fn jobs_requeue(&self, id: &str, note: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update jobs set status = 'queued', error = ?2 where id = ?1",
+            params![id, note],
+        )?;
+        Ok(())
+    }
+
+    pub fn jobs_finish(&self, id: &str, finish: &JobFinish<'_>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update jobs set status = ?2, exit_code = ?3, stdout = ?4, stderr = ?5, truncated = ?6, error = ?7,\n               duration_ms = ?8, finished_at = ?9\n             where id = ?1",
+            params![
+                id,
+                finish.status,
+                finish.exit_code,
+                finish.stdout,
+                finish.stderr,
+                finish.truncated as i64,
+                finish.error,
+                finish.duration_ms.map(|v| v as i64),
+                Self::now_epoch_secs()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Jobs can't survive an app restart; close out any left unfinished by a previous run.
+    pub(super) fn jobs_mark_interrupted(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update jobs set status = 'failed', error = 'interrupted (app exited)', finished_at = ?1\n             where status in ('queued', 'running')",
+            params![Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+
+    /// Newest first, `limit` at a time; `cursor` continues after an earlier page.
+    pub fn jobs_list(&self, limit: i64, cursor: Option<&Cursor>) -> rusqlite::Result<Page<Job>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {JOB_COLUMNS} from jobs where ?2 is null or (created_at, id) < (?2, ?3)\n             order by created_at desc, id desc limit ?1"
+        ))?;
+        let rows = stmt
+            .query_map(params![limit + 1, cursor.map(|c| c.at), cursor.map(|c| &c.id)], Self::job_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Page::from_rows(rows, limit, |j| Cursor { at: j.created_at, id: j.id.clone() }))
+    }
+
+    pub fn jobs_get(&self, id: &str) -> rusqlite::Result<Option<Job>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {JOB_COLUMNS}, stdout, stderr from jobs where id = ?1"
+        ))?;
+        let mut rows = stmt.query_map(params![id], |r| {
+            let mut job = Self::job_row(r)?;
+            job.stdout = r.get(12)?;
+            job.stderr = r.get(13)?;
+            Ok(job)
+        })?;
+        rows.next().transpose()
+    }
+
+    pub fn jobs_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "delete from jobs where id = ?1 and status not in ('queued', 'running')",
+            params![id],
+        )?;
+        Ok(())
+    }
+}
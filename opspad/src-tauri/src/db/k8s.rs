@@ -0,0 +1,66 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// The kube context kubectl dock commands use in a scope (see `k8s.rs`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct K8sPref {
+    /// "local", "ssh:<host_id>" or "env:<TAG>".
+    pub scope: String,
+    /// Kubeconfig file on the machine the command runs on; `None`: kubectl's default.
+    pub kubeconfig: Option<String>,
+    pub context: String,
+    /// Used when the command doesn't pick a namespace itself.
+    pub namespace: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+}
+
+impl Db {
+    pub fn k8s_prefs_list(&self) -> rusqlite::Result<Vec<K8sPref>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select scope, kubeconfig, context, namespace, updated_at from k8s_prefs order by scope asc",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(K8sPref {
+                scope: r.get(0)?,
+                kubeconfig: r.get(1)?,
+                context: r.get(2)?,
+                namespace: r.get(3)?,
+                updated_at: r.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn k8s_prefs_set(
+        &self,
+        scope: &str,
+        kubeconfig: Option<&str>,
+        context: &str,
+        namespace: Option<&str>,
+    ) -> rusqlite::Result<K8sPref> {
+        let now = Self::now_epoch_millis();
+        let conn = self.conn();
+        conn.execute(
+            "insert into k8s_prefs (scope, kubeconfig, context, namespace, updated_at) values (?1, ?2, ?3, ?4, ?5)\n             on conflict(scope) do update set kubeconfig = excluded.kubeconfig, context = excluded.context,\n               namespace = excluded.namespace, updated_at = excluded.updated_at",
+            params![scope, kubeconfig, context, namespace, now],
+        )?;
+        Ok(K8sPref {
+            scope: scope.to_string(),
+            kubeconfig: kubeconfig.map(str::to_string),
+            context: context.to_string(),
+            namespace: namespace.map(str::to_string),
+            updated_at: now,
+        })
+    }
+
+    pub fn k8s_prefs_delete(&self, scope: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from k8s_prefs where scope = ?1", params![scope])?;
+        Ok(())
+    }
+}
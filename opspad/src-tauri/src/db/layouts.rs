@@ -0,0 +1,41 @@
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+
+// Tab/pane layouts per workspace, stored as JSON (see `layout.rs`).
+impl Db {
+    /// The layout JSON and when it was saved (epoch ms).
+    pub fn layouts_get(&self, workspace: &str) -> rusqlite::Result<Option<(String, i64)>> {
+        let conn = self.conn();
+        conn.query_row(
+            "select layout, updated_at from workspace_layouts where workspace = ?1",
+            params![workspace],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+    }
+
+    pub fn layouts_set(&self, workspace: &str, layout: &str) -> rusqlite::Result<i64> {
+        let now = Self::now_epoch_millis();
+        let conn = self.conn();
+        conn.execute(
+            "insert into workspace_layouts (workspace, layout, updated_at) values (?1, ?2, ?3)\n             on conflict(workspace) do update set layout = excluded.layout, updated_at = excluded.updated_at",
+            params![workspace, layout, now],
+        )?;
+        Ok(now)
+    }
+
+    /// Workspaces with a saved layout and when each was saved, most recent first.
+    pub fn layouts_list(&self) -> rusqlite::Result<Vec<(String, i64)>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("select workspace, updated_at from workspace_layouts order by updated_at desc")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    }
+
+    pub fn layouts_delete(&self, workspace: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from workspace_layouts where workspace = ?1", params![workspace])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+use crate::lint::{LintSeverity, Linter, DEFAULT_RULES};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    /// The rule is suppressed when this pattern also matches (e.g. `--dry-run`).
+    pub unless: Option<String>,
+    pub severity: LintSeverity,
+    pub message: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintRuleCreate {
+    pub name: String,
+    pub pattern: String,
+    pub unless: Option<String>,
+    pub severity: Option<LintSeverity>,
+    pub message: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+impl Db {
+    pub(super) fn maybe_seed_lint_rules(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row("select count(1) from lint_rules", [], |r| r.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        for (i, rule) in DEFAULT_RULES.iter().enumerate() {
+            conn.execute(
+                "insert into lint_rules (id, name, pattern, unless_pattern, severity, message, enabled, sort_order) values (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    rule.name,
+                    rule.pattern,
+                    rule.unless,
+                    rule.severity.as_str(),
+                    rule.message,
+                    (i as i64) + 1
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn lint_rules_list(&self) -> rusqlite::Result<Vec<LintRule>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, name, pattern, unless_pattern, severity, message, enabled from lint_rules order by sort_order asc nulls last, name asc",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(LintRule {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                pattern: r.get(2)?,
+                unless: r.get(3)?,
+                severity: LintSeverity::parse(&r.get::<_, String>(4)?),
+                message: r.get(5)?,
+                enabled: r.get::<_, i64>(6)? != 0,
+            })
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    /// Build a linter from the currently persisted rules.
+    pub fn linter(&self) -> rusqlite::Result<Linter> {
+        Ok(Linter::new(&self.lint_rules_list()?))
+    }
+
+    pub fn lint_rules_create(&self, input: LintRuleCreate) -> rusqlite::Result<LintRule> {
+        let rule = LintRule {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            pattern: input.pattern,
+            unless: input.unless.filter(|u| !u.trim().is_empty()),
+            severity: input.severity.unwrap_or_default(),
+            message: input.message.unwrap_or_default(),
+            enabled: input.enabled.unwrap_or(true),
+        };
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from lint_rules", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into lint_rules (id, name, pattern, unless_pattern, severity, message, enabled, sort_order) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                rule.id,
+                rule.name,
+                rule.pattern,
+                rule.unless,
+                rule.severity.as_str(),
+                rule.message,
+                if rule.enabled { 1i64 } else { 0i64 },
+                next
+            ],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn lint_rules_update(&self, input: LintRule) -> rusqlite::Result<LintRule> {
+        let input = LintRule {
+            unless: input.unless.filter(|u| !u.trim().is_empty()),
+            ..input
+        };
+        let conn = self.conn();
+        conn.execute(
+            "update lint_rules set name = ?2, pattern = ?3, unless_pattern = ?4, severity = ?5, message = ?6, enabled = ?7 where id = ?1",
+            params![
+                input.id,
+                input.name,
+                input.pattern,
+                input.unless,
+                input.severity.as_str(),
+                input.message,
+                if input.enabled { 1i64 } else { 0i64 }
+            ],
+        )?;
+        Ok(input)
+    }
+
+    pub fn lint_rules_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from lint_rules where id = ?1", params![id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+/// One step of a macro: input sent as-is (a typed line ending in `\r`, or keys like Ctrl-C or
+/// arrows), after a pause.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroStep {
+    pub text: String,
+    /// Pause before the step is sent (milliseconds); recorded from the gap while typing.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Recorded terminal input, replayable into any session (see `macros.rs`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Macro {
+    pub id: String,
+    pub title: String,
+    pub steps: Vec<MacroStep>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroInput {
+    pub title: String,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Db {
+    fn macro_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Macro> {
+        let steps_json: String = r.get(2)?;
+        Ok(Macro {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            steps: serde_json::from_str(&steps_json).unwrap_or_default(),
+            created_at: r.get(3)?,
+            updated_at: r.get(4)?,
+        })
+    }
+
+    pub fn macros_list(&self) -> rusqlite::Result<Vec<Macro>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, title, steps_json, created_at, updated_at from macros order by sort_order asc nulls last, title asc",
+        )?;
+        let rows = stmt.query_map([], Self::macro_row)?;
+        rows.collect()
+    }
+
+    pub fn macros_get(&self, id: &str) -> rusqlite::Result<Option<Macro>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("select id, title, steps_json, created_at, updated_at from macros where id = ?1")?;
+        let mut rows = stmt.query_map(params![id], Self::macro_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn macros_create(&self, input: MacroInput) -> rusqlite::Result<Macro> {
+        let now = Self::now_epoch_millis();
+        let m = Macro {
+            id: Uuid::new_v4().to_string(),
+            title: input.title.trim().to_string(),
+            steps: input.steps,
+            created_at: now,
+            updated_at: now,
+        };
+        let steps_json = serde_json::to_string(&m.steps).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from macros", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into macros (id, title, steps_json, created_at, updated_at, sort_order) values (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![m.id, m.title, steps_json, m.created_at, m.updated_at, next],
+        )?;
+        Ok(m)
+    }
+
+    /// `None` if the macro doesn't exist.
+    pub fn macros_update(&self, id: &str, input: MacroInput) -> rusqlite::Result<Option<Macro>> {
+        let Some(existing) = self.macros_get(id)? else {
+            return Ok(None);
+        };
+        let m = Macro {
+            title: input.title.trim().to_string(),
+            steps: input.steps,
+            updated_at: Self::now_epoch_millis(),
+            ..existing
+        };
+        let steps_json = serde_json::to_string(&m.steps).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.conn();
+        conn.execute(
+            "update macros set title = ?2, steps_json = ?3, updated_at = ?4 where id = ?1",
+            params![m.id, m.title, steps_json, m.updated_at],
+        )?;
+        Ok(Some(m))
+    }
+
+    pub fn macros_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from macros where id = ?1", params![id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use rusqlite::params;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::Db;
+
+/// A labelled point in a session's timeline ("began failover here"), for postmortems.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalMark {
+    pub id: String,
+    pub session_id: String,
+    /// The session's scope when marked (e.g. "ssh:<host_id>"), so marks outlive the session.
+    pub scope: Option<String>,
+    pub label: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+}
+
+impl Db {
+    fn terminal_mark_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<TerminalMark> {
+        Ok(TerminalMark {
+            id: r.get(0)?,
+            session_id: r.get(1)?,
+            scope: r.get(2)?,
+            label: r.get(3)?,
+            created_at: r.get(4)?,
+        })
+    }
+
+    pub fn terminal_marks_add(
+        &self,
+        session_id: &str,
+        scope: Option<&str>,
+        label: &str,
+    ) -> rusqlite::Result<TerminalMark> {
+        let mark = TerminalMark {
+            id: Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            scope: scope.map(str::to_string),
+            label: label.to_string(),
+            created_at: Self::now_epoch_millis(),
+        };
+        let conn = self.conn();
+        conn.execute(
+            "insert into terminal_marks (id, session_id, scope, label, created_at) values (?1, ?2, ?3, ?4, ?5)",
+            params![mark.id, mark.session_id, mark.scope, mark.label, mark.created_at],
+        )?;
+        Ok(mark)
+    }
+
+    /// Oldest first.
+    pub fn terminal_marks_for_session(&self, session_id: &str) -> rusqlite::Result<Vec<TerminalMark>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, session_id, scope, label, created_at from terminal_marks\n             where session_id = ?1 order by created_at asc, rowid asc",
+        )?;
+        let rows = stmt.query_map(params![session_id], Self::terminal_mark_row)?;
+        rows.collect()
+    }
+
+    /// Every session's marks from `since` to `until` (inclusive epoch milliseconds), oldest first.
+    pub fn terminal_marks_between(&self, since: i64, until: i64, limit: i64) -> rusqlite::Result<Vec<TerminalMark>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, session_id, scope, label, created_at from terminal_marks\n             where created_at >= ?1 and created_at <= ?2 order by created_at asc, rowid asc limit ?3",
+        )?;
+        let rows = stmt.query_map(params![since, until, limit], Self::terminal_mark_row)?;
+        rows.collect()
+    }
+}
@@ -0,0 +1,32 @@
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+
+/// Samples kept per host (a day at the default five-minute interval).
+const METRICS_RETENTION: i64 = 288;
+
+impl Db {
+    /// Store a sample (already serialized by the sampler) and prune old ones.
+    pub fn host_metrics_add(&self, host_id: &str, sampled_at: i64, metrics_json: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "insert into host_metrics (host_id, sampled_at, metrics_json) values (?1, ?2, ?3)",
+            params![host_id, sampled_at, metrics_json],
+        )?;
+        conn.execute(
+            "delete from host_metrics where host_id = ?1 and id not in (\n               select id from host_metrics where host_id = ?1 order by id desc limit ?2\n             )",
+            params![host_id, METRICS_RETENTION],
+        )?;
+        Ok(())
+    }
+
+    pub fn host_metrics_latest(&self, host_id: &str) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn();
+        conn.query_row(
+            "select metrics_json from host_metrics where host_id = ?1 order by id desc limit 1",
+            params![host_id],
+            |r| r.get(0),
+        )
+        .optional()
+    }
+}
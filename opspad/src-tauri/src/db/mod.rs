@@ -1,12 +1,205 @@
+mod alerts;
+mod checklists;
+mod confirmations;
+mod environments;
+mod fanout;
+mod highlight_rules;
+mod host_connections;
+mod host_groups;
+mod http_checks;
+mod incidents;
+mod jobs;
+mod k8s;
+mod layouts;
+mod lint;
+mod macros;
+mod marks;
+mod metrics;
+mod monitor;
+mod pagination;
+mod param_values;
+mod redaction;
+mod runbooks;
+mod schema;
+mod session_events;
+mod sequences;
+mod server_info;
+mod settings;
+mod shortcuts;
+mod snippets;
+mod subscriptions;
+mod sync_profiles;
+mod transfers;
+mod vault_index;
+mod sync;
+
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{mpsc, Mutex, MutexGuard};
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
 use uuid::Uuid;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::template::ParamSpec;
+
+pub use alerts::{Alert, AlertRule, AlertRuleCreate};
+pub use checklists::ChecklistRun;
+pub use confirmations::{Confirmation, ConfirmationQuery};
+pub use environments::{DestructivePolicy, Environment, PolicyApproval};
+pub use fanout::{FanoutHostResult, FanoutRun};
+pub use highlight_rules::{HighlightRule, HighlightRuleCreate, HighlightSeverity};
+pub use host_connections::HostConnection;
+pub use host_groups::HostGroup;
+pub use http_checks::{HttpCheck, HttpCheckCreate, HttpCheckEvent, HttpHeader};
+pub use incidents::Incident;
+pub use jobs::{Job, JobFinish};
+pub use k8s::K8sPref;
+pub use lint::{LintRule, LintRuleCreate};
+pub use macros::{Macro, MacroInput, MacroStep};
+pub use marks::TerminalMark;
+pub use monitor::HostStatusEvent;
+pub use pagination::{Cursor, Page};
+pub use redaction::{RedactionRule, RedactionRuleCreate};
+pub use runbooks::{Runbook, RunbookCreate, RunbookVersion};
+pub use schema::SchemaInfo;
+pub use server_info::ServerInfo;
+pub use sequences::{Sequence, SequenceCreate, SequenceStep};
+pub use shortcuts::{Shortcut, ShortcutTarget};
+pub use snippets::{Snippet, SnippetInput};
+pub use subscriptions::Subscription;
+pub use sync_profiles::{SyncProfile, SyncProfileCreate};
+pub use transfers::{Transfer, TransferCreate};
+
+/// Columns holding epoch milliseconds (`Db::now_epoch_millis`); every other timestamp column is
+/// in seconds.
+const MILLISECOND_COLUMNS: &[(&str, &str)] = &[
+    ("runbooks", "created_at"),
+    ("runbooks", "updated_at"),
+    ("runbook_versions", "created_at"),
+    ("dock_history", "created_at"),
+    ("terminal_session_scopes", "created_at"),
+    ("terminal_prefs", "updated_at"),
+    ("dock_param_values", "updated_at"),
+    ("fanout_runs", "created_at"),
+    ("jobs", "created_at"),
+    ("settings", "updated_at"),
+    ("shortcuts", "created_at"),
+    ("subscriptions", "created_at"),
+    ("vault_index", "created_at"),
+    ("vault_index", "updated_at"),
+    ("host_status_events", "created_at"),
+    ("alert_rules", "created_at"),
+    ("alerts", "created_at"),
+    ("http_checks", "created_at"),
+    ("http_check_events", "created_at"),
+    ("terminal_marks", "created_at"),
+    ("transfers", "created_at"),
+    ("transfers", "updated_at"),
+    ("sync_profiles", "created_at"),
+    ("session_events", "at"),
+    ("snippets", "created_at"),
+    ("snippets", "updated_at"),
+    ("workspace_layouts", "updated_at"),
+    ("k8s_prefs", "updated_at"),
+];
+
+/// How a host is reached: a terminal over ssh, or a remote desktop (`rdp.rs`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionType {
+    #[default]
+    Ssh,
+    Rdp,
+}
+
+impl ConnectionType {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Ssh => "ssh",
+            Self::Rdp => "rdp",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Self {
+        match s {
+            "rdp" => Self::Rdp,
+            _ => Self::Ssh,
+        }
+    }
+
+    pub fn is_ssh(&self) -> bool {
+        *self == Self::Ssh
+    }
+}
+
+/// Whether a host's hostname is a discovery name that `host_dns.rs` expands at connect time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DnsExpansion {
+    /// Connect to the hostname as is.
+    #[default]
+    Off,
+    /// Every A/AAAA record of the name is a target.
+    A,
+    /// The name's SRV records (e.g. `_ssh._tcp.web.example.com`) give targets and ports.
+    Srv,
+}
+
+impl DnsExpansion {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::A => "a",
+            Self::Srv => "srv",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Self {
+        match s {
+            "a" => Self::A,
+            "srv" => Self::Srv,
+            _ => Self::Off,
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        *self == Self::Off
+    }
+}
+
+/// Which of an expanded host's targets a session uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DnsSelection {
+    /// The UI asks (`host_resolve_targets`); sessions opened without asking take the first target.
+    #[default]
+    Pick,
+    /// Each session takes the next target in turn.
+    RoundRobin,
+}
+
+impl DnsSelection {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Pick => "pick",
+            Self::RoundRobin => "roundRobin",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Self {
+        match s {
+            "roundRobin" => Self::RoundRobin,
+            _ => Self::Pick,
+        }
+    }
+
+    pub fn is_pick(&self) -> bool {
+        *self == Self::Pick
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Host {
@@ -18,6 +211,51 @@ pub struct Host {
     pub environment_tag: String,
     pub identity_file: Option<String>,
     pub color: Option<String>,
+    /// Managed via `hosts_set_group`; `hosts_update` leaves it untouched.
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// Team subscription this host comes from; such hosts are read-only.
+    #[serde(default)]
+    pub subscription_id: Option<String>,
+    /// Vault key of the login password. Managed via `hosts_set_credentials`, like the next field.
+    #[serde(default)]
+    pub password_vault_key: Option<String>,
+    /// Vault key of the identity file's passphrase (defaults to `host:<id>:ssh_key_passphrase`).
+    #[serde(default)]
+    pub passphrase_vault_key: Option<String>,
+    /// Shown first in quick connect (tray). Managed via `hosts_set_pinned`.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Maintained by the backend when an ssh or RDP session to the host opens (read-only).
+    #[serde(default)]
+    pub last_connected_at: Option<i64>,
+    #[serde(default)]
+    pub connection_type: ConnectionType,
+    /// ssh's `IdentityAgent` (an agent socket, or "none"). Managed via `hosts_set_security_key`, like
+    /// the next field; both are machine-local.
+    #[serde(default)]
+    pub identity_agent: Option<String>,
+    /// ssh's `SecurityKeyProvider`: the FIDO middleware library for `sk-` keys, when ssh has none
+    /// built in.
+    #[serde(default)]
+    pub security_key_provider: Option<String>,
+    #[serde(default)]
+    pub dns_expansion: DnsExpansion,
+    #[serde(default)]
+    pub dns_selection: DnsSelection,
+    /// Epoch seconds after which an ephemeral host is archived (`host_expiry.rs`); None for
+    /// ordinary hosts. Managed via `hosts_set_expiry`.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// When the host was archived (read-only). Archived hosts are left out of `hosts_list`.
+    #[serde(default)]
+    pub archived_at: Option<i64>,
+    /// What the server reported the last time a session to it was probed (read-only).
+    #[serde(default)]
+    pub server_info: Option<ServerInfo>,
+    /// `color`, or its environment's color when it has none (read-only).
+    #[serde(default)]
+    pub effective_color: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -30,6 +268,17 @@ pub struct HostCreate {
     pub environment_tag: String,
     pub identity_file: Option<String>,
     pub color: Option<String>,
+    #[serde(default)]
+    pub group_id: Option<String>,
+    #[serde(default)]
+    pub connection_type: ConnectionType,
+    #[serde(default)]
+    pub dns_expansion: DnsExpansion,
+    #[serde(default)]
+    pub dns_selection: DnsSelection,
+    /// Makes the host ephemeral.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -43,6 +292,49 @@ pub struct HostUpdate {
     pub environment_tag: String,
     pub identity_file: Option<String>,
     pub color: Option<String>,
+    /// Left unchanged when omitted, like the DNS fields.
+    #[serde(default)]
+    pub connection_type: Option<ConnectionType>,
+    #[serde(default)]
+    pub dns_expansion: Option<DnsExpansion>,
+    #[serde(default)]
+    pub dns_selection: Option<DnsSelection>,
+}
+
+/// Filter and page for `hosts_query`; every field is optional.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HostQuery {
+    pub group_id: Option<String>,
+    pub environment_tag: Option<String>,
+    /// Case-insensitive substring of the label, hostname or username.
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Order by the health monitor's last check. Applied by `hosts_list`, not by the query:
+    /// statuses live in memory.
+    pub sort: HostSort,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HostSort {
+    /// The stored (drag-and-drop) order.
+    #[default]
+    Manual,
+    /// Up, then slow, then unchecked, then down.
+    ReachableFirst,
+    /// Down, then slow, then up, then unchecked, so broken hosts cluster at the top.
+    ByStatus,
+}
+
+impl HostQuery {
+    /// `like` pattern for `search`, with the wildcards in it escaped.
+    fn search_pattern(&self) -> Option<String> {
+        let search = self.search.as_deref().map(str::trim).filter(|s| !s.is_empty())?;
+        let escaped = search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        Some(format!("%{escaped}%"))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -53,6 +345,87 @@ pub struct DockCommand {
     pub command: String,
     pub requires_confirm: bool,
     pub color: Option<String>,
+    /// Parameter schema for `{name}` placeholders. Managed via `dock_commands_set_params`;
+    /// `dock_commands_update` leaves the stored schema untouched.
+    #[serde(default)]
+    pub params: Vec<ParamSpec>,
+    /// Where the command is offered. Managed via `dock_commands_set_visibility`.
+    #[serde(default)]
+    pub visibility: CommandVisibility,
+    /// Free-form grouping label (e.g. "Kubernetes"). Managed via `dock_commands_set_category`.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Usage statistics, maintained by the backend on CommandDock writes (read-only).
+    #[serde(default)]
+    pub run_count: i64,
+    #[serde(default)]
+    pub last_run_at: Option<i64>,
+    /// Bound key chord, if any. Managed via `shortcuts_set` / `shortcuts_clear`.
+    #[serde(default)]
+    pub shortcut: Option<String>,
+    /// Team subscription this command comes from; such commands are read-only.
+    #[serde(default)]
+    pub subscription_id: Option<String>,
+    /// `color`, or, for a command shown in a single environment, that environment's color
+    /// (read-only).
+    #[serde(default)]
+    pub effective_color: Option<String>,
+    /// Interrupt the command (Ctrl-C) if it's still running after this many seconds. Needs shell
+    /// integration to tell when it ends. Managed via `dock_commands_set_timeout`.
+    #[serde(default)]
+    pub timeout_secs: Option<u32>,
+    /// If Ctrl-C doesn't end it, follow up with Ctrl-\ (SIGQUIT).
+    #[serde(default)]
+    pub timeout_escalate: bool,
+}
+
+/// Restricts a dock command to matching sessions. All lists empty means global.
+///
+/// A command is visible when any list matches (environment tag, host id, or host group id).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandVisibility {
+    #[serde(default)]
+    pub environments: Vec<String>,
+    #[serde(default)]
+    pub host_ids: Vec<String>,
+    #[serde(default)]
+    pub group_ids: Vec<String>,
+}
+
+impl CommandVisibility {
+    pub fn is_global(&self) -> bool {
+        self.environments.is_empty() && self.host_ids.is_empty() && self.group_ids.is_empty()
+    }
+
+    pub fn matches(&self, ctx: &ScopeContext) -> bool {
+        if self.is_global() {
+            return true;
+        }
+        let env_match = self
+            .environments
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(&ctx.environment_tag));
+        let host_match = ctx
+            .host_id
+            .as_deref()
+            .is_some_and(|h| self.host_ids.iter().any(|id| id == h));
+        let group_match = ctx
+            .group_id
+            .as_deref()
+            .is_some_and(|g| self.group_ids.iter().any(|id| id == g));
+        env_match || host_match || group_match
+    }
+}
+
+/// What a terminal scope ("local", "ssh:<host_id>", "ssh:user@host:port") resolves to.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeContext {
+    pub scope: String,
+    pub environment_tag: String,
+    pub host_id: Option<String>,
+    pub group_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -62,6 +435,52 @@ pub struct DockCommandCreate {
     pub command: String,
     pub requires_confirm: Option<bool>,
     pub color: Option<String>,
+    #[serde(default)]
+    pub params: Option<Vec<ParamSpec>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockHistoryEntry {
+    pub id: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    pub scope: Option<String>,
+    pub environment_tag: String,
+    pub command_text: String,
+    pub pinned: bool,
+    /// Set when the command was destructive: the environment policy that let it through.
+    pub policy: Option<DestructivePolicy>,
+    pub policy_reason: Option<String>,
+    /// How long the command ran, when the shell reported its end (shell integration).
+    pub duration_ms: Option<i64>,
+    pub exit_status: Option<i32>,
+    /// Set when the entry stands for a watch (see `dock_watch.rs`): how many times it ran.
+    pub watch_runs: Option<i64>,
+    /// The command overran its dock command's timeout and was interrupted.
+    pub timed_out: bool,
+}
+
+/// Every run of one command text in one scope, as a single history row.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockHistoryAggregate {
+    pub scope: Option<String>,
+    pub command_text: String,
+    pub run_count: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub first_run_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub last_run_at: i64,
+    /// The latest run's entry, and its environment and outcome.
+    pub last_id: String,
+    pub environment_tag: String,
+    pub last_duration_ms: Option<i64>,
+    pub last_exit_status: Option<i32>,
+    /// Runs the shell reported as failed (non-zero exit).
+    pub failure_count: i64,
+    /// Any of the runs is pinned.
+    pub pinned: bool,
 }
 
 pub struct Db {
@@ -69,6 +488,13 @@ pub struct Db {
 }
 
 impl Db {
+    /// A panic while the connection was held leaves nothing half-written (an open transaction
+    /// rolls back as it unwinds), so a poisoned lock is recovered rather than taking every later
+    /// query down with it.
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn open(app: &tauri::AppHandle) -> rusqlite::Result<(Self, PathBuf)> {
         let dir = app.path().app_data_dir().map_err(|e| {
             rusqlite::Error::ToSqlConversionFailure(Box::new(std::io::Error::new(
@@ -86,6 +512,8 @@ impl Db {
         let path = dir.join("opspad.db");
         let conn = Connection::open(&path)?;
         conn.pragma_update(None, "foreign_keys", "ON")?;
+        // Room for the hot per-command and per-resize statements alongside everything else.
+        conn.set_prepared_statement_cache_capacity(64);
 
         let db = Self {
             conn: Mutex::new(conn),
@@ -96,11 +524,29 @@ impl Db {
         #[cfg(debug_assertions)]
         db.maybe_seed_demo_hosts()?;
         db.maybe_seed_commanddock()?;
+        db.maybe_seed_runbooks()?;
+        db.maybe_seed_redaction_rules()?;
+        db.maybe_seed_environments()?;
+        db.maybe_seed_lint_rules()?;
+        db.maybe_seed_highlight_rules()?;
+        db.jobs_mark_interrupted()?;
+        db.transfers_requeue_interrupted()?;
         Ok((db, path))
     }
 
+    /// Names of tables as rows are inserted, updated or deleted (one message per row, from any
+    /// writer). Replaces any earlier watcher: SQLite allows one update hook per connection.
+    pub fn watch_changes(&self) -> mpsc::Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        let conn = self.conn();
+        conn.update_hook(Some(move |_action, _db: &str, table: &str, _rowid| {
+            let _ = tx.send(table.to_string());
+        }));
+        rx
+    }
+
     fn migrate(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         conn.execute_batch(
             r#"
             create table if not exists hosts (
@@ -120,11 +566,32 @@ impl Db {
               requires_confirm integer not null default 0
             );
 
+            -- Legacy single runbook; copied into `runbooks` once and no longer written.
             create table if not exists dock_runbook (
               id integer primary key check (id = 1),
               markdown text not null
             );
 
+            create table if not exists runbooks (
+              id text primary key,
+              title text not null,
+              markdown text not null,
+              tags_json text null,
+              sort_order integer null,
+              created_at integer not null,
+              updated_at integer not null
+            );
+
+            create table if not exists runbook_versions (
+              id text primary key,
+              runbook_id text not null references runbooks(id) on delete cascade,
+              title text not null,
+              markdown text not null,
+              created_at integer not null
+            );
+
+            create index if not exists idx_runbook_versions_runbook on runbook_versions(runbook_id, created_at);
+
             create table if not exists dock_history (
               id text primary key,
               created_at integer not null,
@@ -158,6 +625,376 @@ impl Db {
               last_dock_command_template text null,
               updated_at integer not null
             );
+
+            -- User-managed regex rules applied to command text before it is persisted.
+            create table if not exists redaction_rules (
+              id text primary key,
+              name text not null,
+              pattern text not null,
+              replacement text not null,
+              enabled integer not null default 1,
+              sort_order integer null
+            );
+
+            create table if not exists host_groups (
+              id text primary key,
+              name text not null,
+              sort_order integer null
+            );
+
+            -- Ordered multi-step CommandDock procedures (steps stored as JSON).
+            create table if not exists sequences (
+              id text primary key,
+              title text not null,
+              steps_json text not null,
+              sort_order integer null
+            );
+
+            -- Recorded terminal input for replay into other sessions (steps stored as JSON).
+            create table if not exists macros (
+              id text primary key,
+              title text not null,
+              steps_json text not null,
+              created_at integer not null,
+              updated_at integer not null,
+              sort_order integer null
+            );
+
+            -- Last-used CommandDock template variable values per terminal scope (non-secret).
+            create table if not exists dock_param_values (
+              scope text not null,
+              name text not null,
+              value text not null,
+              updated_at integer not null,
+              primary key (scope, name)
+            );
+
+            -- Fan-out runs: one dock command executed headlessly across several hosts.
+            create table if not exists fanout_runs (
+              id text primary key,
+              created_at integer not null,
+              command_id text null,
+              command_title text not null,
+              command_text text not null,
+              host_count integer not null,
+              ok_count integer not null,
+              failed_count integer not null
+            );
+
+            create table if not exists fanout_results (
+              run_id text not null references fanout_runs(id) on delete cascade,
+              host_id text not null,
+              host_label text not null,
+              exit_code integer null,
+              stdout text not null,
+              stderr text not null,
+              error text null,
+              duration_ms integer not null,
+              timed_out integer not null default 0,
+              primary key (run_id, host_id)
+            );
+
+            -- Headless jobs: non-interactive commands run locally or over ssh without a PTY tab.
+            create table if not exists jobs (
+              id text primary key,
+              created_at integer not null,
+              started_at integer null,
+              finished_at integer null,
+              target text not null,
+              target_label text not null,
+              command text not null,
+              status text not null,
+              exit_code integer null,
+              stdout text not null,
+              stderr text not null,
+              truncated integer not null default 0,
+              error text null,
+              duration_ms integer null
+            );
+            create index if not exists idx_jobs_created_at on jobs(created_at);
+
+            -- User-managed lint rules checked before CommandDock writes.
+            create table if not exists lint_rules (
+              id text primary key,
+              name text not null,
+              pattern text not null,
+              unless_pattern text null,
+              severity text not null default 'warning',
+              message text not null default '',
+              enabled integer not null default 1,
+              sort_order integer null
+            );
+
+            -- Per-environment execution policy, keyed by (upper-case) environment tag.
+            create table if not exists environments (
+              name text primary key,
+              color text null,
+              destructive_policy text not null default 'allow',
+              banner integer not null default 0,
+              banner_text text null,
+              four_eyes integer not null default 0,
+              sort_order integer null
+            );
+
+            -- App-level settings (JSON values keyed by name).
+            create table if not exists settings (
+              key text primary key,
+              value text not null,
+              updated_at integer not null
+            );
+
+            -- Key chords bound to dock commands or app actions. One chord per target.
+            create table if not exists shortcuts (
+              id text primary key,
+              chord text not null unique,
+              target_kind text not null,
+              target text not null,
+              created_at integer not null
+            );
+
+            -- Team configuration bundles fetched from a URL. Their items are read-only copies.
+            create table if not exists subscriptions (
+              id text primary key,
+              url text not null unique,
+              name text null,
+              version text null,
+              signer text null,
+              etag text null,
+              last_checked_at integer null,
+              last_error text null,
+              created_at integer not null
+            );
+
+            -- Keys stored in the OS vault (which can't list its own entries), with metadata.
+            create table if not exists vault_index (
+              key text primary key,
+              label text null,
+              created_at integer not null,
+              updated_at integer not null,
+              last_used_at integer null,
+              require_presence integer not null default 0,
+              rotate_by integer null
+            );
+
+            -- Host monitor: check results changing state (not every check).
+            create table if not exists host_status_events (
+              id integer primary key autoincrement,
+              host_id text not null,
+              status text not null,
+              latency_ms integer null,
+              detail text null,
+              created_at integer not null
+            );
+            create index if not exists idx_host_status_events_host on host_status_events(host_id, id);
+
+            -- Resource samples (load, memory, disks) taken over ssh, as JSON.
+            create table if not exists host_metrics (
+              id integer primary key autoincrement,
+              host_id text not null,
+              sampled_at integer not null,
+              metrics_json text not null
+            );
+            create index if not exists idx_host_metrics_host on host_metrics(host_id, id);
+
+            -- User-defined alert rules (condition as JSON) and the alerts they raise.
+            create table if not exists alert_rules (
+              id text primary key,
+              name text not null,
+              condition_json text not null,
+              host_id text null,
+              enabled integer not null default 1,
+              created_at integer not null
+            );
+            create table if not exists alerts (
+              id text primary key,
+              rule_id text null,
+              rule_name text not null,
+              host_id text null,
+              subject text not null,
+              message text not null,
+              status text not null,
+              created_at integer not null,
+              acknowledged_at integer null,
+              resolved_at integer null
+            );
+            create index if not exists idx_alerts_status on alerts(status, created_at);
+
+            -- HTTP(S) service checks run by the monitor (headers as JSON), and their transitions.
+            create table if not exists http_checks (
+              id text primary key,
+              name text not null,
+              url text not null,
+              expected_status integer not null default 200,
+              slow_ms integer null,
+              headers_json text not null default '[]',
+              host_id text null,
+              enabled integer not null default 1,
+              created_at integer not null
+            );
+            create table if not exists http_check_events (
+              id integer primary key autoincrement,
+              check_id text not null,
+              status text not null,
+              http_status integer null,
+              latency_ms integer null,
+              detail text null,
+              created_at integer not null
+            );
+            create index if not exists idx_http_check_events_check on http_check_events(check_id, id);
+
+            -- Labelled points in a session's timeline (`terminal_mark`).
+            create table if not exists terminal_marks (
+              id text primary key,
+              session_id text not null,
+              scope text null,
+              label text not null,
+              created_at integer not null
+            );
+            create index if not exists idx_terminal_marks_session on terminal_marks(session_id, created_at);
+            create index if not exists idx_terminal_marks_scope on terminal_marks(scope, created_at);
+
+            -- File transfer queue (`transfer_enqueue`); survives restarts.
+            create table if not exists transfers (
+              id text primary key,
+              created_at integer not null,
+              updated_at integer not null,
+              finished_at integer null,
+              host_id text not null,
+              host_label text not null,
+              direction text not null,
+              local_path text not null,
+              remote_path text not null,
+              status text not null,
+              attempts integer not null default 0,
+              max_retries integer not null,
+              retry_backoff_ms integer not null,
+              error text null
+            );
+            create index if not exists idx_transfers_status on transfers(status, created_at);
+
+            -- Patterns marked in session output (`terminal:data` spans).
+            create table if not exists highlight_rules (
+              id text primary key,
+              name text not null,
+              pattern text not null,
+              scope text null,
+              severity text not null default 'info',
+              color text null,
+              annotation text null,
+              enabled integer not null default 1,
+              sort_order integer null
+            );
+
+            -- Local/remote folder pairs kept mirrored by `sync_run`.
+            create table if not exists sync_profiles (
+              id text primary key,
+              name text not null,
+              host_id text not null,
+              local_path text not null,
+              remote_path text not null,
+              direction text not null,
+              excludes_json text not null default '[]',
+              last_run_at integer null,
+              created_at integer not null
+            );
+
+            -- ssh sessions opened to saved hosts and the address each went to (`hosts_connections`).
+            create table if not exists host_connections (
+              id text primary key,
+              host_id text not null,
+              session_id text not null,
+              hostname text not null,
+              address text not null,
+              port integer not null,
+              connected_at integer not null
+            );
+            create index if not exists idx_host_connections_host on host_connections(host_id, connected_at);
+
+            -- Stretches of incident work (`incident_start`); at most one has no ended_at.
+            create table if not exists incidents (
+              id text primary key,
+              title text not null,
+              reference text null,
+              started_at integer not null,
+              ended_at integer null
+            );
+
+            -- Audit trail of confirmed destructive commands (`confirmations_list`).
+            create table if not exists confirmations (
+              id text primary key,
+              confirmed_at integer not null,
+              os_user text not null,
+              incident_id text null,
+              session_id text null,
+              scope text null,
+              host_id text null,
+              environment_tag text not null,
+              dock_command_id text null,
+              dock_command_title text null,
+              command_text text not null,
+              policy text not null,
+              confirmation_text text null,
+              history_id text null,
+              approved_by text null
+            );
+            create index if not exists idx_confirmations_incident on confirmations(incident_id, confirmed_at);
+            create index if not exists idx_confirmations_time on confirmations(confirmed_at);
+
+            -- Terminal sessions opening and closing, for timelines (`timeline_export`).
+            create table if not exists session_events (
+              id integer primary key autoincrement,
+              session_id text not null,
+              scope text not null,
+              kind text not null,
+              at integer not null
+            );
+            create index if not exists idx_session_events_at on session_events(at);
+
+            -- Executions of a runbook's checkbox items (`runbook_checklist_start`).
+            create table if not exists checklist_runs (
+              id text primary key,
+              runbook_id text not null,
+              runbook_title text not null,
+              incident_id text null,
+              started_at integer not null,
+              completed_at integer null
+            );
+            create index if not exists idx_checklist_runs_runbook on checklist_runs(runbook_id, started_at);
+            create table if not exists checklist_items (
+              run_id text not null,
+              idx integer not null,
+              text text not null,
+              checked_at integer null,
+              checked_by text null,
+              primary key (run_id, idx)
+            );
+
+            -- Typed abbreviations expanded in terminal input (`terminal_expand`).
+            create table if not exists snippets (
+              id text primary key,
+              trigger_word text not null,
+              expansion text not null,
+              description text null,
+              scope text null,
+              enabled integer not null default 1,
+              created_at integer not null,
+              updated_at integer not null
+            );
+            create unique index if not exists idx_snippets_trigger on snippets(trigger_word, coalesce(scope, ''));
+
+            create table if not exists workspace_layouts (
+              workspace text primary key,
+              layout text not null,
+              updated_at integer not null
+            );
+
+            create table if not exists k8s_prefs (
+              scope text primary key,
+              kubeconfig text null,
+              context text not null,
+              namespace text null,
+              updated_at integer not null
+            );
             "#,
         )?;
 
@@ -199,6 +1036,162 @@ impl Db {
             conn.execute("alter table dock_commands add column color text null", [])?;
         }
 
+        if !Self::column_exists(&conn, "dock_commands", "params_json")? {
+            conn.execute("alter table dock_commands add column params_json text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_commands", "visibility_json")? {
+            conn.execute("alter table dock_commands add column visibility_json text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_commands", "run_count")? {
+            conn.execute("alter table dock_commands add column run_count integer not null default 0", [])?;
+        }
+        if !Self::column_exists(&conn, "dock_commands", "last_run_at")? {
+            conn.execute("alter table dock_commands add column last_run_at integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_commands", "category")? {
+            conn.execute("alter table dock_commands add column category text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_commands", "timeout_secs")? {
+            conn.execute("alter table dock_commands add column timeout_secs integer null", [])?;
+            conn.execute(
+                "alter table dock_commands add column timeout_escalate integer not null default 0",
+                [],
+            )?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "group_id")? {
+            conn.execute("alter table hosts add column group_id text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_history", "pinned")? {
+            conn.execute("alter table dock_history add column pinned integer not null default 0", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_history", "policy")? {
+            conn.execute("alter table dock_history add column policy text null", [])?;
+            conn.execute("alter table dock_history add column policy_reason text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_history", "duration_ms")? {
+            conn.execute("alter table dock_history add column duration_ms integer null", [])?;
+            conn.execute("alter table dock_history add column exit_status integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_history", "watch_runs")? {
+            conn.execute("alter table dock_history add column watch_runs integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "dock_history", "timed_out")? {
+            conn.execute("alter table dock_history add column timed_out integer not null default 0", [])?;
+        }
+
+        if !Self::column_exists(&conn, "runbooks", "file_name")? {
+            conn.execute("alter table runbooks add column file_name text null", [])?;
+            conn.execute("alter table runbooks add column synced_hash text null", [])?;
+            conn.execute(
+                "alter table runbooks add column sync_conflict integer not null default 0",
+                [],
+            )?;
+        }
+
+        if !Self::column_exists(&conn, "runbooks", "params_json")? {
+            conn.execute("alter table runbooks add column params_json text null", [])?;
+        }
+
+        for table in ["hosts", "dock_commands", "runbooks"] {
+            if !Self::column_exists(&conn, table, "subscription_id")? {
+                conn.execute(&format!("alter table {table} add column subscription_id text null"), [])?;
+            }
+        }
+
+        if !Self::column_exists(&conn, "hosts", "password_vault_key")? {
+            conn.execute("alter table hosts add column password_vault_key text null", [])?;
+            conn.execute("alter table hosts add column passphrase_vault_key text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "pinned")? {
+            conn.execute("alter table hosts add column pinned integer not null default 0", [])?;
+            conn.execute("alter table hosts add column last_connected_at integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "connection_type")? {
+            conn.execute("alter table hosts add column connection_type text not null default 'ssh'", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "identity_agent")? {
+            conn.execute("alter table hosts add column identity_agent text null", [])?;
+            conn.execute("alter table hosts add column security_key_provider text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "dns_expansion")? {
+            conn.execute("alter table hosts add column dns_expansion text not null default 'off'", [])?;
+            conn.execute("alter table hosts add column dns_selection text not null default 'pick'", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "expires_at")? {
+            conn.execute("alter table hosts add column expires_at integer null", [])?;
+            conn.execute("alter table hosts add column archived_at integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "hosts", "server_info")? {
+            conn.execute("alter table hosts add column server_info text null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "vault_index", "require_presence")? {
+            conn.execute(
+                "alter table vault_index add column require_presence integer not null default 0",
+                [],
+            )?;
+        }
+
+        if !Self::column_exists(&conn, "vault_index", "rotate_by")? {
+            conn.execute("alter table vault_index add column rotate_by integer null", [])?;
+        }
+
+        if !Self::column_exists(&conn, "environments", "banner")? {
+            conn.execute("alter table environments add column banner integer not null default 0", [])?;
+            conn.execute("alter table environments add column banner_text text null", [])?;
+            // Environments that already guard destructive commands get the banner too.
+            conn.execute("update environments set banner = 1 where destructive_policy != 'allow'", [])?;
+        }
+
+        if !Self::column_exists(&conn, "environments", "four_eyes")? {
+            conn.execute("alter table environments add column four_eyes integer not null default 0", [])?;
+        }
+
+        if !Self::column_exists(&conn, "confirmations", "approved_by")? {
+            conn.execute("alter table confirmations add column approved_by text null", [])?;
+        }
+
+        // `created_at`/`updated_at` were epoch seconds before user_version 1.
+        let user_version: i64 = conn.query_row("pragma user_version", [], |r| r.get(0))?;
+        if user_version < 1 {
+            let tx = conn.unchecked_transaction()?;
+            for (table, column) in MILLISECOND_COLUMNS {
+                tx.execute(
+                    &format!("update {table} set {column} = {column} * 1000 where {column} < 100000000000"),
+                    [],
+                )?;
+            }
+            tx.execute_batch("pragma user_version = 1")?;
+            tx.commit()?;
+        }
+
+        conn.execute_batch(
+            r#"
+            create index if not exists idx_dock_history_scope on dock_history(scope, created_at);
+            create index if not exists idx_dock_history_created on dock_history(created_at, scope);
+            create index if not exists idx_terminal_session_scopes_scope on terminal_session_scopes(scope);
+            create index if not exists idx_hosts_order on hosts(sort_order, environment_tag, label);
+            create index if not exists idx_hosts_environment on hosts(environment_tag);
+            create index if not exists idx_hosts_group on hosts(group_id);
+            "#,
+        )?;
+
         Ok(())
     }
 
@@ -215,7 +1208,7 @@ impl Db {
     }
 
     fn maybe_seed_commanddock(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
 
         let runbook_count: i64 =
             conn.query_row("select count(1) from dock_runbook", [], |r| r.get(0))?;
@@ -255,7 +1248,7 @@ impl Db {
 
     #[cfg(debug_assertions)]
     fn maybe_seed_demo_hosts(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         let count: i64 = conn.query_row("select count(1) from hosts", [], |r| r.get(0))?;
         if count > 0 {
             return Ok(());
@@ -278,23 +1271,77 @@ impl Db {
         Ok(())
     }
 
+    const HOST_COLUMNS: &'static str =
+        "id, label, hostname, port, username, environment_tag, identity_file, color, group_id, subscription_id, \
+         password_vault_key, passphrase_vault_key, pinned, last_connected_at, connection_type, \
+         identity_agent, security_key_provider, dns_expansion, dns_selection, expires_at, archived_at, server_info, \
+         (select e.color from environments e where e.name = upper(hosts.environment_tag))";
+
+    fn host_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Host> {
+        let color: Option<String> = r.get(7)?;
+        Ok(Host {
+            id: r.get(0)?,
+            label: r.get(1)?,
+            hostname: r.get(2)?,
+            port: r.get::<_, u32>(3)? as u16,
+            username: r.get(4)?,
+            environment_tag: r.get(5)?,
+            identity_file: r.get(6)?,
+            effective_color: color.clone().or(r.get(22)?),
+            color,
+            group_id: r.get(8)?,
+            subscription_id: r.get(9)?,
+            password_vault_key: r.get(10)?,
+            passphrase_vault_key: r.get(11)?,
+            pinned: r.get::<_, i64>(12)? != 0,
+            last_connected_at: r.get(13)?,
+            connection_type: ConnectionType::parse(&r.get::<_, String>(14)?),
+            identity_agent: r.get(15)?,
+            security_key_provider: r.get(16)?,
+            dns_expansion: DnsExpansion::parse(&r.get::<_, String>(17)?),
+            dns_selection: DnsSelection::parse(&r.get::<_, String>(18)?),
+            expires_at: r.get(19)?,
+            archived_at: r.get(20)?,
+            server_info: r.get::<_, Option<String>>(21)?.and_then(|json| serde_json::from_str(&json).ok()),
+        })
+    }
+
     pub fn hosts_list(&self) -> rusqlite::Result<Vec<Host>> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare(
-            "select id, label, hostname, port, username, environment_tag, identity_file, color from hosts order by sort_order asc nulls last, environment_tag asc, label asc",
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {} from hosts where archived_at is null order by sort_order asc nulls last, environment_tag asc, label asc",
+            Self::HOST_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], Self::host_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    const HOST_FILTER: &'static str = "archived_at is null and (?1 is null or group_id = ?1) and (?2 is null or environment_tag = ?2) \
+         and (?3 is null or label like ?3 escape '\\' or hostname like ?3 escape '\\' or username like ?3 escape '\\')";
+
+    /// One page of the hosts matching `query`, in `hosts_list` order.
+    pub fn hosts_query(&self, query: &HostQuery) -> rusqlite::Result<Vec<Host>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {} from hosts where {} \
+             order by sort_order asc nulls last, environment_tag asc, label asc limit ?4 offset ?5",
+            Self::HOST_COLUMNS,
+            Self::HOST_FILTER
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                query.group_id,
+                query.environment_tag,
+                query.search_pattern(),
+                query.limit.unwrap_or(-1),
+                query.offset.unwrap_or(0).max(0)
+            ],
+            Self::host_row,
         )?;
-        let rows = stmt.query_map([], |r| {
-            Ok(Host {
-                id: r.get(0)?,
-                label: r.get(1)?,
-                hostname: r.get(2)?,
-                port: r.get::<_, u32>(3)? as u16,
-                username: r.get(4)?,
-                environment_tag: r.get(5)?,
-                identity_file: r.get(6)?,
-                color: r.get(7)?,
-            })
-        })?;
         let mut out = Vec::new();
         for row in rows {
             out.push(row?);
@@ -302,47 +1349,157 @@ impl Db {
         Ok(out)
     }
 
+    /// Hosts matching `query`'s filters, ignoring its limit and offset.
+    pub fn hosts_count(&self, query: &HostQuery) -> rusqlite::Result<i64> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select count(*) from hosts where {}", Self::HOST_FILTER),
+            params![query.group_id, query.environment_tag, query.search_pattern()],
+            |r| r.get(0),
+        )
+    }
+
+    pub fn hosts_get(&self, id: &str) -> rusqlite::Result<Option<Host>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("select {} from hosts where id = ?1", Self::HOST_COLUMNS))?;
+        let mut rows = stmt.query_map(params![id], Self::host_row)?;
+        rows.next().transpose()
+    }
+
+    /// Whether a host other than `except_id` already uses `label` (ignoring case).
+    pub fn hosts_label_taken(&self, label: &str, except_id: Option<&str>) -> rusqlite::Result<bool> {
+        let conn = self.conn();
+        conn.query_row(
+            "select exists(select 1 from hosts where label = ?1 collate nocase and (?2 is null or id != ?2))",
+            params![label, except_id],
+            |r| r.get(0),
+        )
+    }
+
     pub fn hosts_create(&self, input: HostCreate) -> rusqlite::Result<Host> {
         let host = Host {
             id: Uuid::new_v4().to_string(),
             label: input.label,
             hostname: input.hostname,
-            port: input.port.unwrap_or(22),
+            port: input.port.unwrap_or(match input.connection_type {
+                ConnectionType::Ssh => 22,
+                ConnectionType::Rdp => 3389,
+            }),
             username: input.username,
             environment_tag: input.environment_tag,
             identity_file: input.identity_file,
             color: input.color,
+            group_id: input.group_id,
+            subscription_id: None,
+            password_vault_key: None,
+            passphrase_vault_key: None,
+            pinned: false,
+            last_connected_at: None,
+            connection_type: input.connection_type,
+            identity_agent: None,
+            security_key_provider: None,
+            dns_expansion: input.dns_expansion,
+            dns_selection: input.dns_selection,
+            expires_at: input.expires_at,
+            archived_at: None,
+            server_info: None,
+            effective_color: None,
         };
 
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let next: i64 = conn
-            .query_row("select coalesce(max(sort_order), 0) + 1 from hosts", [], |r| r.get(0))
-            .unwrap_or(1);
-        conn.execute(
-            "insert into hosts (id, label, hostname, port, username, environment_tag, identity_file, sort_order, color) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![
-                host.id,
-                host.label,
-                host.hostname,
-                host.port as u32,
-                host.username,
-                host.environment_tag,
-                host.identity_file,
-                next,
-                host.color
-            ],
-        )?;
-        Ok(host)
+        {
+            let conn = self.conn();
+            let next: i64 = conn
+                .query_row("select coalesce(max(sort_order), 0) + 1 from hosts", [], |r| r.get(0))
+                .unwrap_or(1);
+            conn.execute(
+                "insert into hosts (id, label, hostname, port, username, environment_tag, identity_file, sort_order, color, group_id, connection_type, \
+                 dns_expansion, dns_selection, expires_at) values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    host.id,
+                    host.label,
+                    host.hostname,
+                    host.port as u32,
+                    host.username,
+                    host.environment_tag,
+                    host.identity_file,
+                    next,
+                    host.color,
+                    host.group_id,
+                    host.connection_type.as_str(),
+                    host.dns_expansion.as_str(),
+                    host.dns_selection.as_str(),
+                    host.expires_at
+                ],
+            )?;
+        }
+        // The stored row carries the environment's color.
+        Ok(self.hosts_get(&host.id)?.unwrap_or(host))
     }
 
-    pub fn hosts_delete(&self, id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute("delete from hosts where id = ?1", params![id])?;
-        Ok(())
+    /// Delete a host and everything kept about it under its id or `ssh:<id>` scope: terminal
+    /// prefs and session scopes, remembered template values, Kubernetes context, monitor history,
+    /// metric samples, host-scoped alert rules, highlight rules and snippets, and folder sync
+    /// profiles. Its open alerts are resolved, its unfinished file transfers cancelled and its
+    /// HTTP checks become standalone. CommandDock history, timeline marks and connection history
+    /// are kept unless `delete_history`.
+    pub fn hosts_delete(&self, id: &str, delete_history: bool) -> rusqlite::Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let scope = format!("ssh:{id}");
+        tx.execute("delete from terminal_prefs where scope = ?1", params![scope])?;
+        tx.execute("delete from terminal_session_scopes where scope = ?1", params![scope])?;
+        tx.execute("delete from dock_param_values where scope = ?1", params![scope])?;
+        tx.execute("delete from k8s_prefs where scope = ?1", params![scope])?;
+        tx.execute("delete from highlight_rules where scope = ?1", params![scope])?;
+        tx.execute("delete from snippets where scope = ?1", params![scope])?;
+        if delete_history {
+            tx.execute("delete from dock_history where scope = ?1", params![scope])?;
+            tx.execute("delete from terminal_marks where scope = ?1", params![scope])?;
+            tx.execute("delete from host_connections where host_id = ?1", params![id])?;
+        }
+        tx.execute("delete from host_status_events where host_id = ?1", params![id])?;
+        tx.execute("delete from host_metrics where host_id = ?1", params![id])?;
+        tx.execute("delete from alert_rules where host_id = ?1", params![id])?;
+        tx.execute("delete from sync_profiles where host_id = ?1", params![id])?;
+        tx.execute(
+            "update alerts set status = 'resolved', resolved_at = ?2 where host_id = ?1 and status != 'resolved'",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        tx.execute("update http_checks set host_id = null where host_id = ?1", params![id])?;
+        let now = Self::now_epoch_millis();
+        tx.execute(
+            "update transfers set status = 'cancelled', error = 'host deleted', updated_at = ?2, finished_at = ?3\n             where host_id = ?1 and status in ('queued', 'running', 'paused', 'failed')",
+            params![id, now, now / 1000],
+        )?;
+        tx.execute("delete from hosts where id = ?1", params![id])?;
+        tx.commit()
     }
 
     pub fn hosts_update(&self, input: HostUpdate) -> rusqlite::Result<Host> {
-        let host = Host {
+        {
+            let conn = self.conn();
+            conn.execute(
+                "update hosts set label = ?2, hostname = ?3, port = ?4, username = ?5, environment_tag = ?6, identity_file = ?7, color = ?8, \
+                 connection_type = coalesce(?9, connection_type), dns_expansion = coalesce(?10, dns_expansion), \
+                 dns_selection = coalesce(?11, dns_selection) where id = ?1",
+                params![
+                    input.id,
+                    input.label,
+                    input.hostname,
+                    input.port as u32,
+                    input.username,
+                    input.environment_tag,
+                    input.identity_file,
+                    input.color,
+                    input.connection_type.map(ConnectionType::as_str),
+                    input.dns_expansion.map(DnsExpansion::as_str),
+                    input.dns_selection.map(DnsSelection::as_str)
+                ],
+            )?;
+        }
+
+        // Return the stored row so fields managed elsewhere (group) are reported accurately.
+        Ok(self.hosts_get(&input.id)?.unwrap_or(Host {
             id: input.id,
             label: input.label,
             hostname: input.hostname,
@@ -351,28 +1508,116 @@ impl Db {
             environment_tag: input.environment_tag,
             identity_file: input.identity_file,
             color: input.color,
-        };
+            group_id: None,
+            subscription_id: None,
+            password_vault_key: None,
+            passphrase_vault_key: None,
+            pinned: false,
+            last_connected_at: None,
+            connection_type: input.connection_type.unwrap_or_default(),
+            identity_agent: None,
+            security_key_provider: None,
+            dns_expansion: input.dns_expansion.unwrap_or_default(),
+            dns_selection: input.dns_selection.unwrap_or_default(),
+            expires_at: None,
+            archived_at: None,
+            server_info: None,
+            effective_color: None,
+        }))
+    }
+
+    pub fn hosts_set_group(&self, id: &str, group_id: Option<&str>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("update hosts set group_id = ?2 where id = ?1", params![id, group_id])?;
+        Ok(())
+    }
 
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+    /// Bind vault keys for the host's login password and key passphrase (machine-local, never synced).
+    pub fn hosts_set_credentials(
+        &self,
+        id: &str,
+        password_vault_key: Option<&str>,
+        passphrase_vault_key: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn();
         conn.execute(
-            "update hosts set label = ?2, hostname = ?3, port = ?4, username = ?5, environment_tag = ?6, identity_file = ?7, color = ?8 where id = ?1",
-            params![
-                host.id,
-                host.label,
-                host.hostname,
-                host.port as u32,
-                host.username,
-                host.environment_tag,
-                host.identity_file,
-                host.color
-            ],
+            "update hosts set password_vault_key = ?2, passphrase_vault_key = ?3 where id = ?1",
+            params![id, password_vault_key, passphrase_vault_key],
         )?;
+        Ok(())
+    }
 
-        Ok(host)
+    /// Set the ssh options for hardware security keys (machine-local, never synced).
+    pub fn hosts_set_security_key(
+        &self,
+        id: &str,
+        identity_agent: Option<&str>,
+        security_key_provider: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update hosts set identity_agent = ?2, security_key_provider = ?3 where id = ?1",
+            params![id, identity_agent, security_key_provider],
+        )?;
+        Ok(())
+    }
+
+    /// Make a host ephemeral (expiring at `expires_at`) or, with None, an ordinary host again.
+    pub fn hosts_set_expiry(&self, id: &str, expires_at: Option<i64>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("update hosts set expires_at = ?2 where id = ?1", params![id, expires_at])?;
+        Ok(())
+    }
+
+    /// Archive the ephemeral hosts that expired by `now`; returns them.
+    pub fn hosts_archive_expired(&self, now: i64) -> rusqlite::Result<Vec<Host>> {
+        let ids: Vec<String> = {
+            let conn = self.conn();
+            let mut stmt = conn
+                .prepare("update hosts set archived_at = ?1 where archived_at is null and expires_at <= ?1 returning id")?;
+            let rows = stmt.query_map(params![now], |r| r.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut hosts = Vec::with_capacity(ids.len());
+        for id in ids {
+            hosts.extend(self.hosts_get(&id)?);
+        }
+        Ok(hosts)
+    }
+
+    /// Archived hosts, most recently archived first.
+    pub fn hosts_archived_list(&self) -> rusqlite::Result<Vec<Host>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {} from hosts where archived_at is not null order by archived_at desc, label asc",
+            Self::HOST_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], Self::host_row)?;
+        rows.collect()
+    }
+
+    /// Bring an archived host back as an ordinary (non-expiring) host.
+    pub fn hosts_unarchive(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("update hosts set archived_at = null, expires_at = null where id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn hosts_set_pinned(&self, id: &str, pinned: bool) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("update hosts set pinned = ?2 where id = ?1", params![id, pinned as i64])?;
+        Ok(())
+    }
+
+    pub fn hosts_touch_connected(&self, id: &str) -> rusqlite::Result<()> {
+        let now = Self::now_epoch_secs();
+        let conn = self.conn();
+        conn.execute("update hosts set last_connected_at = ?2 where id = ?1", params![id, now])?;
+        Ok(())
     }
 
     pub fn hosts_reorder(&self, ids: &[String]) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         let tx = conn.unchecked_transaction()?;
         for (i, id) in ids.iter().enumerate() {
             tx.execute(
@@ -384,20 +1629,21 @@ impl Db {
         Ok(())
     }
 
+    const DOCK_COMMAND_COLUMNS: &'static str =
+        "id, title, command, requires_confirm, color, params_json, visibility_json, run_count, last_run_at, category, \
+         (select chord from shortcuts s where s.target_kind = 'dockCommand' and s.target = dock_commands.id), \
+         subscription_id, \
+         (select e.color from environments e where json_array_length(dock_commands.visibility_json, '$.environments') = 1 \
+          and e.name = upper(json_extract(dock_commands.visibility_json, '$.environments[0]'))), \
+         timeout_secs, timeout_escalate";
+
     pub fn dock_commands_list(&self) -> rusqlite::Result<Vec<DockCommand>> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare(
-            "select id, title, command, requires_confirm, color from dock_commands order by sort_order asc nulls last, title asc",
-        )?;
-        let rows = stmt.query_map([], |r| {
-            Ok(DockCommand {
-                id: r.get(0)?,
-                title: r.get(1)?,
-                command: r.get(2)?,
-                requires_confirm: r.get::<_, i64>(3)? != 0,
-                color: r.get(4)?,
-            })
-        })?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {} from dock_commands order by sort_order asc nulls last, title asc",
+            Self::DOCK_COMMAND_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], Self::dock_command_row)?;
         let mut out = Vec::new();
         for row in rows {
             out.push(row?);
@@ -405,55 +1651,211 @@ impl Db {
         Ok(out)
     }
 
+    pub fn dock_commands_get(&self, id: &str) -> rusqlite::Result<Option<DockCommand>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {} from dock_commands where id = ?1",
+            Self::DOCK_COMMAND_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map(params![id], Self::dock_command_row)?;
+        rows.next().transpose()
+    }
+
+    fn dock_command_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<DockCommand> {
+        let params_json: Option<String> = r.get(5)?;
+        let visibility_json: Option<String> = r.get(6)?;
+        let color: Option<String> = r.get(4)?;
+        Ok(DockCommand {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            command: r.get(2)?,
+            requires_confirm: r.get::<_, i64>(3)? != 0,
+            effective_color: color.clone().or(r.get(12)?),
+            color,
+            params: params_json
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            visibility: visibility_json
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            run_count: r.get(7)?,
+            last_run_at: r.get(8)?,
+            category: r.get(9)?,
+            shortcut: r.get(10)?,
+            subscription_id: r.get(11)?,
+            timeout_secs: r.get(13)?,
+            timeout_escalate: r.get::<_, i64>(14)? != 0,
+        })
+    }
+
+    /// Count a CommandDock write of `id` toward its usage statistics.
+    pub fn dock_commands_record_run(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update dock_commands set run_count = run_count + 1, last_run_at = ?2 where id = ?1",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+
+    fn params_to_json(params: &[ParamSpec]) -> Option<String> {
+        if params.is_empty() {
+            return None;
+        }
+        serde_json::to_string(params).ok()
+    }
+
     pub fn dock_commands_create(&self, input: DockCommandCreate) -> rusqlite::Result<DockCommand> {
         let cmd = DockCommand {
             id: Uuid::new_v4().to_string(),
             title: input.title,
             command: input.command,
             requires_confirm: input.requires_confirm.unwrap_or(false),
-            color: input.color,
+            color: input.color.clone(),
+            params: input.params.unwrap_or_default(),
+            visibility: CommandVisibility::default(),
+            category: None,
+            run_count: 0,
+            last_run_at: None,
+            shortcut: None,
+            subscription_id: None,
+            effective_color: input.color.clone(),
+            timeout_secs: None,
+            timeout_escalate: false,
         };
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         let next: i64 = conn
             .query_row("select coalesce(max(sort_order), 0) + 1 from dock_commands", [], |r| r.get(0))
             .unwrap_or(1);
         conn.execute(
-            "insert into dock_commands (id, title, command, requires_confirm, sort_order, color) values (?1, ?2, ?3, ?4, ?5, ?6)",
+            "insert into dock_commands (id, title, command, requires_confirm, sort_order, color, params_json) values (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
                 cmd.id,
                 cmd.title,
                 cmd.command,
                 if cmd.requires_confirm { 1i64 } else { 0i64 },
                 next,
-                cmd.color
+                cmd.color,
+                Self::params_to_json(&cmd.params)
             ],
         )?;
         Ok(cmd)
     }
 
     pub fn dock_commands_update(&self, input: DockCommand) -> rusqlite::Result<DockCommand> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        {
+            let conn = self.conn();
+            conn.execute(
+                "update dock_commands set title = ?2, command = ?3, requires_confirm = ?4, color = ?5 where id = ?1",
+                params![
+                    input.id,
+                    input.title,
+                    input.command,
+                    if input.requires_confirm { 1i64 } else { 0i64 },
+                    input.color
+                ],
+            )?;
+        }
+        // Return the stored row so the caller sees the (unchanged) parameter schema.
+        Ok(self.dock_commands_get(&input.id)?.unwrap_or(input))
+    }
+
+    pub fn dock_commands_set_params(&self, id: &str, params: &[ParamSpec]) -> rusqlite::Result<()> {
+        let conn = self.conn();
         conn.execute(
-            "update dock_commands set title = ?2, command = ?3, requires_confirm = ?4, color = ?5 where id = ?1",
-            params![
-                input.id,
-                input.title,
-                input.command,
-                if input.requires_confirm { 1i64 } else { 0i64 },
-                input.color
-            ],
+            "update dock_commands set params_json = ?2 where id = ?1",
+            params![id, Self::params_to_json(params)],
         )?;
-        Ok(input)
+        Ok(())
+    }
+
+    pub fn dock_commands_set_category(&self, id: &str, category: Option<&str>) -> rusqlite::Result<()> {
+        let category = category.map(str::trim).filter(|c| !c.is_empty());
+        let conn = self.conn();
+        conn.execute(
+            "update dock_commands set category = ?2 where id = ?1",
+            params![id, category],
+        )?;
+        Ok(())
+    }
+
+    /// `timeout_secs` = None (or 0) removes the timeout.
+    pub fn dock_commands_set_timeout(&self, id: &str, timeout_secs: Option<u32>, escalate: bool) -> rusqlite::Result<()> {
+        let timeout_secs = timeout_secs.filter(|s| *s > 0);
+        let conn = self.conn();
+        conn.execute(
+            "update dock_commands set timeout_secs = ?2, timeout_escalate = ?3 where id = ?1",
+            params![id, timeout_secs, timeout_secs.is_some() && escalate],
+        )?;
+        Ok(())
+    }
+
+    pub fn dock_commands_set_visibility(&self, id: &str, visibility: &CommandVisibility) -> rusqlite::Result<()> {
+        let json = if visibility.is_global() {
+            None
+        } else {
+            serde_json::to_string(visibility).ok()
+        };
+        let conn = self.conn();
+        conn.execute(
+            "update dock_commands set visibility_json = ?2 where id = ?1",
+            params![id, json],
+        )?;
+        Ok(())
+    }
+
+    /// Dock commands visible in `scope`, in dock order.
+    pub fn dock_commands_for_scope(&self, scope: &str) -> rusqlite::Result<Vec<DockCommand>> {
+        let ctx = self.scope_context(scope)?;
+        Ok(self
+            .dock_commands_list()?
+            .into_iter()
+            .filter(|c| c.visibility.matches(&ctx))
+            .collect())
+    }
+
+    /// Resolve a terminal scope to its environment and (for saved hosts) host/group ids.
+    pub fn scope_context(&self, scope: &str) -> rusqlite::Result<ScopeContext> {
+        if scope == "local" {
+            return Ok(ScopeContext {
+                scope: scope.to_string(),
+                environment_tag: "LOCAL".to_string(),
+                host_id: None,
+                group_id: None,
+            });
+        }
+        if let Some(host_id) = scope.strip_prefix("ssh:") {
+            if let Some(host) = self.hosts_get(host_id)? {
+                return Ok(ScopeContext {
+                    scope: scope.to_string(),
+                    environment_tag: host.environment_tag,
+                    host_id: Some(host.id),
+                    group_id: host.group_id,
+                });
+            }
+        }
+        Ok(ScopeContext {
+            scope: scope.to_string(),
+            environment_tag: self
+                .terminal_prefs_get_env(scope)?
+                .unwrap_or_else(|| "UNKNOWN".to_string()),
+            host_id: None,
+            group_id: None,
+        })
     }
 
     pub fn dock_commands_delete(&self, id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         conn.execute("delete from dock_commands where id = ?1", params![id])?;
+        conn.execute(
+            "delete from shortcuts where target_kind = 'dockCommand' and target = ?1",
+            params![id],
+        )?;
         Ok(())
     }
 
     pub fn dock_commands_reorder(&self, ids: &[String]) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         let tx = conn.unchecked_transaction()?;
         for (i, id) in ids.iter().enumerate() {
             tx.execute(
@@ -465,25 +1867,8 @@ impl Db {
         Ok(())
     }
 
-    pub fn dock_runbook_get(&self) -> rusqlite::Result<String> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let md: String = conn.query_row(
-            "select markdown from dock_runbook where id = 1",
-            [],
-            |r| r.get(0),
-        )?;
-        Ok(md)
-    }
-
-    pub fn dock_runbook_set(&self, markdown: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
-            "insert into dock_runbook (id, markdown) values (1, ?1)\n            on conflict(id) do update set markdown = excluded.markdown",
-            params![markdown],
-        )?;
-        Ok(())
-    }
-
+    /// Returns the new entry's id.
+    #[allow(clippy::too_many_arguments)]
     pub fn dock_history_add(
         &self,
         scope: Option<&str>,
@@ -492,44 +1877,104 @@ impl Db {
         source_command_id: Option<&str>,
         source_command_title: Option<&str>,
         source_command_template: Option<&str>,
-    ) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
-            "insert into dock_history (id, created_at, scope, environment_tag, command_text, source_command_id, source_command_title, source_command_template)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                Uuid::new_v4().to_string(),
-                Self::now_epoch_secs(),
-                scope,
-                environment_tag,
-                command_text,
-                source_command_id,
-                source_command_title,
-                source_command_template
-            ],
+        approval: Option<&PolicyApproval>,
+    ) -> rusqlite::Result<String> {
+        let conn = self.conn();
+        // Mask secrets (tokens, passwords in args, connection strings) before anything hits disk.
+        let redactor = Self::redactor_locked(&conn)?;
+        let command_text = redactor.redact(command_text);
+        // Templates get pasted secrets too.
+        let source_command_template = source_command_template.map(|t| redactor.redact(t));
+        let reason = approval.and_then(|a| a.reason.as_deref()).map(|r| redactor.redact(r));
+        let mut stmt = conn.prepare_cached(
+            "insert into dock_history (id, created_at, scope, environment_tag, command_text, source_command_id, source_command_title, source_command_template, policy, policy_reason)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         )?;
+        let id = Uuid::new_v4().to_string();
+        stmt.execute(params![
+            id,
+            Self::now_epoch_millis(),
+            scope,
+            environment_tag,
+            command_text,
+            source_command_id,
+            source_command_title,
+            source_command_template,
+            approval.map(|a| a.policy.as_str()),
+            reason
+        ])?;
 
-        // Keep history bounded (latest 300).
-        conn.execute_batch(
-            r#"
+        // Keep history bounded (latest 300). Pinned entries are exempt from pruning.
+        let pruned = conn
+            .prepare_cached(
+                r#"
             delete from dock_history
             where id in (
               select id from dock_history
+              where pinned = 0
               order by created_at desc
               limit -1 offset 300
-            );
+            )
             "#,
-        )
-        .ok();
+            )
+            .and_then(|mut stmt| stmt.execute([]));
+        if let Err(e) = pruned {
+            log::warn!("dock history pruning failed: {e}");
+        }
+        Ok(id)
+    }
+
+    /// Record how a command ended, as reported by the shell.
+    pub fn dock_history_finish(&self, id: &str, duration_ms: i64, exit_status: Option<i32>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare_cached("update dock_history set duration_ms = ?2, exit_status = ?3 where id = ?1")?;
+        stmt.execute(params![id, duration_ms, exit_status])?;
+        Ok(())
+    }
+
+    /// The entry's command hit its timeout and was interrupted.
+    pub fn dock_history_set_timed_out(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("update dock_history set timed_out = 1 where id = ?1")?;
+        stmt.execute(params![id])?;
+        Ok(())
+    }
+
+    /// A watch has re-run the entry's command; `runs` counts the first run too.
+    pub fn dock_history_set_watch_runs(&self, id: &str, runs: i64) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("update dock_history set watch_runs = ?2 where id = ?1")?;
+        stmt.execute(params![id, runs])?;
         Ok(())
     }
 
-    pub fn dock_history_list(&self, limit: i64) -> rusqlite::Result<Vec<(String, i64, String, String)>> {
-        // Returns: (id, created_at, environment_tag, command_text)
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare(
-            "select id, created_at, environment_tag, command_text from dock_history order by created_at desc limit ?1",
+    /// Newest first, `limit` at a time; `cursor` continues after an earlier page.
+    pub fn dock_history_list(
+        &self,
+        limit: i64,
+        pinned_only: bool,
+        cursor: Option<&Cursor>,
+    ) -> rusqlite::Result<Page<DockHistoryEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, created_at, scope, environment_tag, command_text, pinned, policy, policy_reason, duration_ms, exit_status, watch_runs,\n             timed_out\n             from dock_history\n             where (?2 = 0 or pinned = 1) and (?3 is null or (created_at, id) < (?3, ?4))\n             order by created_at desc, id desc limit ?1",
         )?;
-        let rows = stmt.query_map(params![limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))?;
+        let rows = stmt
+            .query_map(
+                params![limit + 1, pinned_only as i64, cursor.map(|c| c.at), cursor.map(|c| &c.id)],
+                Self::dock_history_row,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Page::from_rows(rows, limit, |h| Cursor { at: h.created_at, id: h.id.clone() }))
+    }
+
+    /// History for a single terminal scope (e.g. "ssh:<host_id>"), newest first.
+    pub fn dock_history_for_scope(&self, scope: &str, limit: i64) -> rusqlite::Result<Vec<DockHistoryEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, created_at, scope, environment_tag, command_text, pinned, policy, policy_reason, duration_ms, exit_status, watch_runs,\n             timed_out\n             from dock_history\n             where scope = ?1\n             order by created_at desc limit ?2",
+        )?;
+        let rows = stmt.query_map(params![scope, limit], Self::dock_history_row)?;
         let mut out = Vec::new();
         for row in rows {
             out.push(row?);
@@ -537,14 +1982,92 @@ impl Db {
         Ok(out)
     }
 
+    /// Every scope's history from `since` to `until` (inclusive epoch milliseconds), oldest first.
+    pub fn dock_history_between(&self, since: i64, until: i64, limit: i64) -> rusqlite::Result<Vec<DockHistoryEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select id, created_at, scope, environment_tag, command_text, pinned, policy, policy_reason, duration_ms, exit_status, watch_runs,\n             timed_out\n             from dock_history\n             where created_at >= ?1 and created_at <= ?2\n             order by created_at asc, rowid asc limit ?3",
+        )?;
+        let rows = stmt.query_map(params![since, until, limit], Self::dock_history_row)?;
+        rows.collect()
+    }
+
+    /// History grouped by scope and command text, most recently run first; `scope` limits it to
+    /// one terminal scope.
+    pub fn dock_history_aggregate(&self, scope: Option<&str>, limit: i64) -> rusqlite::Result<Vec<DockHistoryAggregate>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            r#"
+            with runs as (
+              select scope, command_text, created_at, id, environment_tag, duration_ms, exit_status,
+                     row_number() over latest as rn,
+                     sum(coalesce(watch_runs, 1)) over runs as run_count,
+                     min(created_at) over runs as first_run_at,
+                     sum(case when exit_status != 0 then 1 else 0 end) over runs as failure_count,
+                     max(pinned) over runs as pinned
+              from dock_history
+              where ?1 is null or scope = ?1
+              window runs as (partition by scope, command_text),
+                     latest as (partition by scope, command_text order by created_at desc, rowid desc)
+            )
+            select scope, command_text, run_count, first_run_at, created_at, id, environment_tag, duration_ms, exit_status,
+                   failure_count, pinned
+            from runs
+            where rn = 1
+            order by created_at desc
+            limit ?2
+            "#,
+        )?;
+        let rows = stmt.query_map(params![scope, limit], |r| {
+            Ok(DockHistoryAggregate {
+                scope: r.get(0)?,
+                command_text: r.get(1)?,
+                run_count: r.get(2)?,
+                first_run_at: r.get(3)?,
+                last_run_at: r.get(4)?,
+                last_id: r.get(5)?,
+                environment_tag: r.get(6)?,
+                last_duration_ms: r.get(7)?,
+                last_exit_status: r.get(8)?,
+                failure_count: r.get(9)?,
+                pinned: r.get::<_, i64>(10)? != 0,
+            })
+        })?;
+        rows.collect()
+    }
+
+    fn dock_history_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<DockHistoryEntry> {
+        Ok(DockHistoryEntry {
+            id: r.get(0)?,
+            created_at: r.get(1)?,
+            scope: r.get(2)?,
+            environment_tag: r.get(3)?,
+            command_text: r.get(4)?,
+            pinned: r.get::<_, i64>(5)? != 0,
+            policy: r.get::<_, Option<String>>(6)?.as_deref().map(DestructivePolicy::parse),
+            policy_reason: r.get(7)?,
+            duration_ms: r.get(8)?,
+            exit_status: r.get(9)?,
+            watch_runs: r.get(10)?,
+            timed_out: r.get::<_, i64>(11)? != 0,
+        })
+    }
+
+    pub fn dock_history_pin(&self, id: &str, pinned: bool) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("update dock_history set pinned = ?2 where id = ?1")?;
+        stmt.execute(params![id, if pinned { 1i64 } else { 0i64 }])?;
+        Ok(())
+    }
+
     pub fn dock_history_delete(&self, id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute("delete from dock_history where id = ?1", params![id])?;
+        let conn = self.conn();
+        conn.prepare_cached("delete from dock_history where id = ?1")?.execute(params![id])?;
         Ok(())
     }
 
     pub fn dock_history_clear(&self) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
+        let conn = self.conn();
         conn.execute("delete from dock_history", [])?;
         Ok(())
     }
@@ -556,18 +2079,31 @@ impl Db {
             .as_secs() as i64
     }
 
+    /// For `created_at`/`updated_at` columns.
+    fn now_epoch_millis() -> i64 {
+        crate::timestamp::now_millis()
+    }
+
+    /// The first call for a session records it as opened.
     pub fn terminal_session_scope_set(&self, session_id: &str, scope: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        let opened = tx
+            .prepare_cached("select 1 from terminal_session_scopes where session_id = ?1")?
+            .exists(params![session_id])?;
+        tx.prepare_cached(
             "insert into terminal_session_scopes (session_id, scope, created_at) values (?1, ?2, ?3)\n            on conflict(session_id) do update set scope = excluded.scope",
-            params![session_id, scope, Self::now_epoch_secs()],
-        )?;
-        Ok(())
+        )?
+        .execute(params![session_id, scope, Self::now_epoch_millis()])?;
+        if !opened {
+            Self::session_event_add(&tx, session_id, scope, "open")?;
+        }
+        tx.commit()
     }
 
     pub fn terminal_session_scope_get(&self, session_id: &str) -> rusqlite::Result<Option<String>> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare("select scope from terminal_session_scopes where session_id = ?1")?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("select scope from terminal_session_scopes where session_id = ?1")?;
         let mut rows = stmt.query(params![session_id])?;
         if let Some(row) = rows.next()? {
             let s: String = row.get(0)?;
@@ -576,27 +2112,38 @@ impl Db {
         Ok(None)
     }
 
+    /// Records the session as closed, once.
     pub fn terminal_session_scope_delete(&self, session_id: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute("delete from terminal_session_scopes where session_id = ?1", params![session_id])?;
-        Ok(())
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        let scope: Option<String> = tx
+            .query_row(
+                "delete from terminal_session_scopes where session_id = ?1 returning scope",
+                params![session_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        if let Some(scope) = scope {
+            Self::session_event_add(&tx, session_id, &scope, "close")?;
+        }
+        tx.commit()
     }
 
     pub fn terminal_prefs_touch(&self, scope: &str, environment_tag: &str) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
             "insert into terminal_prefs (scope, environment_tag, cols, rows, last_dock_command_id, last_dock_command_title, last_dock_command_template, updated_at)\n            values (?1, ?2, null, null, null, null, null, ?3)\n            on conflict(scope) do update set environment_tag = excluded.environment_tag, updated_at = excluded.updated_at",
-            params![scope, environment_tag, Self::now_epoch_secs()],
         )?;
+        stmt.execute(params![scope, environment_tag, Self::now_epoch_millis()])?;
         Ok(())
     }
 
     pub fn terminal_prefs_update_size(&self, scope: &str, cols: u16, rows: u16) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
             "insert into terminal_prefs (scope, environment_tag, cols, rows, last_dock_command_id, last_dock_command_title, last_dock_command_template, updated_at)\n            values (?1, 'UNKNOWN', ?2, ?3, null, null, null, ?4)\n            on conflict(scope) do update set cols = excluded.cols, rows = excluded.rows, updated_at = excluded.updated_at",
-            params![scope, cols as i64, rows as i64, Self::now_epoch_secs()],
         )?;
+        stmt.execute(params![scope, cols as i64, rows as i64, Self::now_epoch_millis()])?;
         Ok(())
     }
 
@@ -607,23 +2154,23 @@ impl Db {
         dock_command_title: Option<&str>,
         dock_command_template: Option<&str>,
     ) -> rusqlite::Result<()> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        conn.execute(
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
             "insert into terminal_prefs (scope, environment_tag, cols, rows, last_dock_command_id, last_dock_command_title, last_dock_command_template, updated_at)\n            values (?1, 'UNKNOWN', null, null, ?2, ?3, ?4, ?5)\n            on conflict(scope) do update set last_dock_command_id = excluded.last_dock_command_id,\n              last_dock_command_title = excluded.last_dock_command_title,\n              last_dock_command_template = excluded.last_dock_command_template,\n              updated_at = excluded.updated_at",
-            params![
-                scope,
-                dock_command_id,
-                dock_command_title,
-                dock_command_template,
-                Self::now_epoch_secs()
-            ],
         )?;
+        stmt.execute(params![
+            scope,
+            dock_command_id,
+            dock_command_title,
+            dock_command_template,
+            Self::now_epoch_millis()
+        ])?;
         Ok(())
     }
 
     pub fn terminal_prefs_get_size(&self, scope: &str) -> rusqlite::Result<Option<(u16, u16)>> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare("select cols, rows from terminal_prefs where scope = ?1")?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("select cols, rows from terminal_prefs where scope = ?1")?;
         let mut rows = stmt.query(params![scope])?;
         if let Some(row) = rows.next()? {
             let cols: Option<i64> = row.get(0)?;
@@ -638,8 +2185,8 @@ impl Db {
     }
 
     pub fn terminal_prefs_get_env(&self, scope: &str) -> rusqlite::Result<Option<String>> {
-        let conn = self.conn.lock().expect("poisoned sqlite lock");
-        let mut stmt = conn.prepare("select environment_tag from terminal_prefs where scope = ?1")?;
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("select environment_tag from terminal_prefs where scope = ?1")?;
         let mut rows = stmt.query(params![scope])?;
         if let Some(row) = rows.next()? {
             let env: String = row.get(0)?;
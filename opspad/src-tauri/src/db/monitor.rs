@@ -0,0 +1,61 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// A host's check result changing state (up, slow, down), as recorded by the monitor.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostStatusEvent {
+    pub id: i64,
+    pub host_id: String,
+    /// "up" | "slow" | "down"
+    pub status: String,
+    pub latency_ms: Option<u64>,
+    pub detail: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+}
+
+/// Transitions kept per host.
+const STATUS_RETENTION: i64 = 200;
+
+impl Db {
+    pub fn host_status_record(
+        &self,
+        host_id: &str,
+        status: &str,
+        latency_ms: Option<u64>,
+        detail: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "insert into host_status_events (host_id, status, latency_ms, detail, created_at)\n             values (?1, ?2, ?3, ?4, ?5)",
+            params![host_id, status, latency_ms.map(|v| v as i64), detail, Self::now_epoch_millis()],
+        )?;
+        conn.execute(
+            "delete from host_status_events where host_id = ?1 and id not in (\n               select id from host_status_events where host_id = ?1 order by id desc limit ?2\n             )",
+            params![host_id, STATUS_RETENTION],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent transitions first.
+    pub fn host_status_history(&self, host_id: &str, limit: i64) -> rusqlite::Result<Vec<HostStatusEvent>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, host_id, status, latency_ms, detail, created_at from host_status_events\n             where host_id = ?1 order by id desc limit ?2",
+        )?;
+        let rows = stmt.query_map(params![host_id, limit], |r| {
+            Ok(HostStatusEvent {
+                id: r.get(0)?,
+                host_id: r.get(1)?,
+                status: r.get(2)?,
+                latency_ms: r.get::<_, Option<i64>>(3)?.map(|v| v as u64),
+                detail: r.get(4)?,
+                created_at: r.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
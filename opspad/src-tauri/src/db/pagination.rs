@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+/// Where a keyset-paginated listing left off: the last row's sort time and id. Rows inserted
+/// while a client pages through don't shift later pages, as they would with offsets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: i64,
+    pub id: String,
+}
+
+impl Cursor {
+    /// Parse the opaque string a page handed out as `next_cursor`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        s.split_once(':')
+            .and_then(|(at, id)| Some(Self { at: at.parse().ok()?, id: id.to_string() }))
+            .filter(|c| !c.id.is_empty())
+            .ok_or_else(|| "cursor: not one handed out by an earlier page".to_string())
+    }
+
+    fn encode(&self) -> String {
+        format!("{}:{}", self.at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as `cursor` for the next page; `None` on the last one.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// `rows` as read with `limit + 1`, so a row past the page tells whether there's another.
+    pub(super) fn from_rows(mut rows: Vec<T>, limit: i64, key: impl Fn(&T) -> Cursor) -> Self {
+        let more = limit >= 0 && rows.len() as i64 > limit;
+        if more {
+            rows.truncate(limit as usize);
+        }
+        let next_cursor = if more { rows.last().map(|r| key(r).encode()) } else { None };
+        Self { items: rows, next_cursor }
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use rusqlite::params;
+
+use super::Db;
+
+impl Db {
+    /// Last-used template variable values for a terminal scope (e.g. `ns -> payments` on "ssh:<host_id>").
+    pub fn dock_param_values_get(&self, scope: &str) -> rusqlite::Result<HashMap<String, String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("select name, value from dock_param_values where scope = ?1")?;
+        let rows = stmt.query_map(params![scope], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        let mut out = HashMap::new();
+        for row in rows {
+            let (name, value): (String, String) = row?;
+            out.insert(name, value);
+        }
+        Ok(out)
+    }
+
+    pub fn dock_param_values_remember(&self, scope: &str, values: &HashMap<String, String>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        let now = Self::now_epoch_millis();
+        for (name, value) in values {
+            tx.execute(
+                "insert into dock_param_values (scope, name, value, updated_at) values (?1, ?2, ?3, ?4)\n                on conflict(scope, name) do update set value = excluded.value, updated_at = excluded.updated_at",
+                params![scope, name, value, now],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
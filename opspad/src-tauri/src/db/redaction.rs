@@ -0,0 +1,119 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+use crate::redact::{Redactor, DEFAULT_RULES};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    pub replacement: String,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRuleCreate {
+    pub name: String,
+    pub pattern: String,
+    pub replacement: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+impl Db {
+    pub(super) fn maybe_seed_redaction_rules(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row("select count(1) from redaction_rules", [], |r| r.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        for (i, (name, pattern, replacement)) in DEFAULT_RULES.iter().enumerate() {
+            conn.execute(
+                "insert into redaction_rules (id, name, pattern, replacement, enabled, sort_order) values (?1, ?2, ?3, ?4, 1, ?5)",
+                params![Uuid::new_v4().to_string(), name, pattern, replacement, (i as i64) + 1],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn redaction_rules_list(&self) -> rusqlite::Result<Vec<RedactionRule>> {
+        let conn = self.conn();
+        Self::redaction_rules_query(&conn)
+    }
+
+    fn redaction_rules_query(conn: &Connection) -> rusqlite::Result<Vec<RedactionRule>> {
+        let mut stmt = conn.prepare_cached(
+            "select id, name, pattern, replacement, enabled from redaction_rules order by sort_order asc nulls last, name asc",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(RedactionRule {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                pattern: r.get(2)?,
+                replacement: r.get(3)?,
+                enabled: r.get::<_, i64>(4)? != 0,
+            })
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    /// Build a redactor from the currently persisted rules (caller already holds the lock).
+    pub(super) fn redactor_locked(conn: &Connection) -> rusqlite::Result<Redactor> {
+        Ok(Redactor::new(&Self::redaction_rules_query(conn)?))
+    }
+
+    pub fn redaction_rules_create(&self, input: RedactionRuleCreate) -> rusqlite::Result<RedactionRule> {
+        let rule = RedactionRule {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            pattern: input.pattern,
+            replacement: input.replacement.unwrap_or_else(|| "****".to_string()),
+            enabled: input.enabled.unwrap_or(true),
+        };
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from redaction_rules", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into redaction_rules (id, name, pattern, replacement, enabled, sort_order) values (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                rule.id,
+                rule.name,
+                rule.pattern,
+                rule.replacement,
+                if rule.enabled { 1i64 } else { 0i64 },
+                next
+            ],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn redaction_rules_update(&self, input: RedactionRule) -> rusqlite::Result<RedactionRule> {
+        let conn = self.conn();
+        conn.execute(
+            "update redaction_rules set name = ?2, pattern = ?3, replacement = ?4, enabled = ?5 where id = ?1",
+            params![
+                input.id,
+                input.name,
+                input.pattern,
+                input.replacement,
+                if input.enabled { 1i64 } else { 0i64 }
+            ],
+        )?;
+        Ok(input)
+    }
+
+    pub fn redaction_rules_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from redaction_rules where id = ?1", params![id])?;
+        Ok(())
+    }
+}
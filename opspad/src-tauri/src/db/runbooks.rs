@@ -0,0 +1,356 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+use crate::template::ParamSpec;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runbook {
+    pub id: String,
+    pub title: String,
+    pub markdown: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Schema for `{name}` placeholders in the markdown. Managed via `runbooks_set_params`.
+    #[serde(default)]
+    pub params: Vec<ParamSpec>,
+    #[serde(default)]
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    #[serde(default)]
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+    /// `.md` file backing this runbook when folder sync is on. Managed by the sync.
+    #[serde(default)]
+    pub file_name: Option<String>,
+    /// Changed both in the app and on disk since the last sync. Managed by the sync.
+    #[serde(default)]
+    pub sync_conflict: bool,
+    /// Content hash at the last sync (backend-only).
+    #[serde(skip)]
+    pub synced_hash: Option<String>,
+    /// Team subscription this runbook comes from; such runbooks are read-only.
+    #[serde(default)]
+    pub subscription_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunbookCreate {
+    pub title: String,
+    pub markdown: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// A prior state of a runbook, captured before each save that changed it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunbookVersion {
+    pub id: String,
+    pub runbook_id: String,
+    pub title: String,
+    /// Content (empty in list responses).
+    pub markdown: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+}
+
+/// Versions kept per runbook; older ones are pruned on save.
+const VERSIONS_PER_RUNBOOK: i64 = 50;
+
+const RUNBOOK_COLUMNS: &str =
+    "id, title, markdown, tags_json, created_at, updated_at, file_name, sync_conflict, synced_hash, params_json, \
+     subscription_id";
+
+fn tags_to_json(tags: &[String]) -> Option<String> {
+    if tags.is_empty() {
+        return None;
+    }
+    serde_json::to_string(tags).ok()
+}
+
+/// Trimmed, de-duplicated (case-insensitive), non-empty tags.
+fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for t in tags {
+        let t = t.trim();
+        if !t.is_empty() && !out.iter().any(|o| o.eq_ignore_ascii_case(t)) {
+            out.push(t.to_string());
+        }
+    }
+    out
+}
+
+impl Db {
+    /// Carry the legacy single `dock_runbook` over as the first runbook.
+    pub(super) fn maybe_seed_runbooks(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let count: i64 = conn.query_row("select count(1) from runbooks", [], |r| r.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        let legacy: Option<String> = conn
+            .query_row("select markdown from dock_runbook where id = 1", [], |r| r.get(0))
+            .optional()?;
+        let now = Self::now_epoch_millis();
+        conn.execute(
+            "insert into runbooks (id, title, markdown, tags_json, sort_order, created_at, updated_at) values (?1, ?2, ?3, null, 1, ?4, ?4)",
+            params![Uuid::new_v4().to_string(), "Runbook", legacy.unwrap_or_default(), now],
+        )?;
+        Ok(())
+    }
+
+    fn runbook_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Runbook> {
+        let tags_json: Option<String> = r.get(3)?;
+        Ok(Runbook {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            markdown: r.get(2)?,
+            tags: tags_json
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            created_at: r.get(4)?,
+            updated_at: r.get(5)?,
+            file_name: r.get(6)?,
+            sync_conflict: r.get::<_, i64>(7)? != 0,
+            synced_hash: r.get(8)?,
+            params: r
+                .get::<_, Option<String>>(9)?
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            subscription_id: r.get(10)?,
+        })
+    }
+
+    pub fn runbooks_list(&self) -> rusqlite::Result<Vec<Runbook>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {RUNBOOK_COLUMNS} from runbooks order by sort_order asc nulls last, title asc"
+        ))?;
+        let rows = stmt.query_map([], Self::runbook_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn runbooks_get(&self, id: &str) -> rusqlite::Result<Option<Runbook>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {RUNBOOK_COLUMNS} from runbooks where id = ?1"),
+            params![id],
+            Self::runbook_row,
+        )
+        .optional()
+    }
+
+    pub fn runbooks_create(&self, input: RunbookCreate) -> rusqlite::Result<Runbook> {
+        let now = Self::now_epoch_millis();
+        let runbook = Runbook {
+            id: Uuid::new_v4().to_string(),
+            title: input.title,
+            markdown: input.markdown.unwrap_or_default(),
+            tags: clean_tags(input.tags.unwrap_or_default()),
+            params: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            file_name: None,
+            sync_conflict: false,
+            synced_hash: None,
+            subscription_id: None,
+        };
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from runbooks", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into runbooks (id, title, markdown, tags_json, sort_order, created_at, updated_at) values (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![runbook.id, runbook.title, runbook.markdown, tags_to_json(&runbook.tags), next, now],
+        )?;
+        Ok(runbook)
+    }
+
+    pub fn runbooks_update(&self, input: Runbook) -> rusqlite::Result<Runbook> {
+        let input = Runbook {
+            tags: clean_tags(input.tags),
+            updated_at: Self::now_epoch_millis(),
+            ..input
+        };
+        {
+            let conn = self.conn();
+            let tx = conn.unchecked_transaction()?;
+            let previous: Option<(String, String)> = tx
+                .query_row(
+                    "select title, markdown from runbooks where id = ?1",
+                    params![input.id],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )
+                .optional()?;
+            if let Some((title, markdown)) = previous.filter(|(t, m)| *t != input.title || *m != input.markdown) {
+                tx.execute(
+                    "insert into runbook_versions (id, runbook_id, title, markdown, created_at) values (?1, ?2, ?3, ?4, ?5)",
+                    params![Uuid::new_v4().to_string(), input.id, title, markdown, input.updated_at],
+                )?;
+                tx.execute(
+                    "delete from runbook_versions where runbook_id = ?1 and id not in (\
+                       select id from runbook_versions where runbook_id = ?1 order by created_at desc, rowid desc limit ?2)",
+                    params![input.id, VERSIONS_PER_RUNBOOK],
+                )?;
+            }
+            tx.execute(
+                "update runbooks set title = ?2, markdown = ?3, tags_json = ?4, updated_at = ?5 where id = ?1",
+                params![input.id, input.title, input.markdown, tags_to_json(&input.tags), input.updated_at],
+            )?;
+            tx.commit()?;
+        }
+        Ok(self.runbooks_get(&input.id)?.unwrap_or(input))
+    }
+
+    pub fn runbooks_set_params(&self, id: &str, params: &[ParamSpec]) -> rusqlite::Result<()> {
+        let json = if params.is_empty() {
+            None
+        } else {
+            serde_json::to_string(params).ok()
+        };
+        let conn = self.conn();
+        conn.execute(
+            "update runbooks set params_json = ?2 where id = ?1",
+            params![id, json],
+        )?;
+        Ok(())
+    }
+
+    pub fn runbooks_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from runbooks where id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn runbooks_reorder(&self, ids: &[String]) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        for (i, id) in ids.iter().enumerate() {
+            tx.execute(
+                "update runbooks set sort_order = ?2 where id = ?1",
+                params![id, (i as i64) + 1],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record folder-sync state for a runbook (`file_name` None detaches it from its file).
+    pub fn runbooks_set_sync_state(
+        &self,
+        id: &str,
+        file_name: Option<&str>,
+        synced_hash: Option<&str>,
+        conflict: bool,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update runbooks set file_name = ?2, synced_hash = ?3, sync_conflict = ?4 where id = ?1",
+            params![id, file_name, synced_hash, if conflict { 1i64 } else { 0i64 }],
+        )?;
+        Ok(())
+    }
+
+    pub fn runbooks_clear_sync_state(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update runbooks set file_name = null, synced_hash = null, sync_conflict = 0",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Newest first, without content.
+    pub fn runbook_versions_list(&self, runbook_id: &str) -> rusqlite::Result<Vec<RunbookVersion>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "select id, runbook_id, title, created_at from runbook_versions where runbook_id = ?1 order by created_at desc, rowid desc",
+        )?;
+        let rows = stmt.query_map(params![runbook_id], |r| {
+            Ok(RunbookVersion {
+                id: r.get(0)?,
+                runbook_id: r.get(1)?,
+                title: r.get(2)?,
+                markdown: String::new(),
+                created_at: r.get(3)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn runbook_versions_get(&self, id: &str) -> rusqlite::Result<Option<RunbookVersion>> {
+        let conn = self.conn();
+        conn.query_row(
+            "select id, runbook_id, title, markdown, created_at from runbook_versions where id = ?1",
+            params![id],
+            |r| {
+                Ok(RunbookVersion {
+                    id: r.get(0)?,
+                    runbook_id: r.get(1)?,
+                    title: r.get(2)?,
+                    markdown: r.get(3)?,
+                    created_at: r.get(4)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    /// Put a version's title and content back. The replaced content becomes a version itself, so a
+    /// restore can be undone.
+    pub fn runbook_versions_restore(&self, id: &str) -> rusqlite::Result<Option<Runbook>> {
+        let Some(version) = self.runbook_versions_get(id)? else {
+            return Ok(None);
+        };
+        let Some(current) = self.runbooks_get(&version.runbook_id)? else {
+            return Ok(None);
+        };
+        self.runbooks_update(Runbook {
+            title: version.title,
+            markdown: version.markdown,
+            ..current
+        })
+        .map(Some)
+    }
+
+    /// Markdown of the first runbook (what the single-runbook `dock_runbook_*` commands see).
+    pub fn dock_runbook_get(&self) -> rusqlite::Result<String> {
+        Ok(self
+            .runbooks_list()?
+            .into_iter()
+            .next()
+            .map(|r| r.markdown)
+            .unwrap_or_default())
+    }
+
+    /// Replace the markdown of the first runbook, creating one if there are none.
+    pub fn dock_runbook_set(&self, markdown: &str) -> rusqlite::Result<()> {
+        match self.runbooks_list()?.into_iter().next() {
+            Some(first) => {
+                self.runbooks_update(Runbook {
+                    markdown: markdown.to_string(),
+                    ..first
+                })?;
+            }
+            None => {
+                self.runbooks_create(RunbookCreate {
+                    title: "Runbook".to_string(),
+                    markdown: Some(markdown.to_string()),
+                    tags: None,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
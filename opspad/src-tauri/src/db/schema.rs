@@ -0,0 +1,52 @@
+use serde::Serialize;
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableInfo {
+    pub name: String,
+    pub rows: i64,
+    /// The `create table` statement, so column migrations can be checked.
+    pub sql: String,
+}
+
+/// Shape of the database (never its contents), for diagnostics.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaInfo {
+    pub sqlite_version: String,
+    pub user_version: i64,
+    pub size_bytes: i64,
+    pub tables: Vec<TableInfo>,
+}
+
+impl Db {
+    pub fn schema_info(&self) -> rusqlite::Result<SchemaInfo> {
+        let conn = self.conn();
+        let sqlite_version: String = conn.query_row("select sqlite_version()", [], |r| r.get(0))?;
+        let user_version: i64 = conn.query_row("pragma user_version", [], |r| r.get(0))?;
+        let page_count: i64 = conn.query_row("pragma page_count", [], |r| r.get(0))?;
+        let page_size: i64 = conn.query_row("pragma page_size", [], |r| r.get(0))?;
+
+        let mut stmt = conn.prepare(
+            "select name, coalesce(sql, '') from sqlite_master \
+             where type = 'table' and name not like 'sqlite_%' order by name asc",
+        )?;
+        let defs = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut tables = Vec::with_capacity(defs.len());
+        for (name, sql) in defs {
+            let rows = conn.query_row(&format!("select count(1) from \"{name}\""), [], |r| r.get(0))?;
+            tables.push(TableInfo { name, rows, sql });
+        }
+
+        Ok(SchemaInfo {
+            sqlite_version,
+            user_version,
+            size_bytes: page_count * page_size,
+            tables,
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+/// One step of a sequence: a dock command rendered with fixed values, then an optional
+/// wait-for-output condition and/or pause before the next step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceStep {
+    pub dock_command_id: String,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    /// Pause after the step completes (milliseconds).
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    /// Regex that must appear in the session output before the next step runs (e.g. a prompt).
+    #[serde(default)]
+    pub wait_for: Option<String>,
+    /// How long to wait for `wait_for` before failing the run (milliseconds, default 60s).
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sequence {
+    pub id: String,
+    pub title: String,
+    pub steps: Vec<SequenceStep>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceCreate {
+    pub title: String,
+    pub steps: Vec<SequenceStep>,
+}
+
+impl Db {
+    fn sequence_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Sequence> {
+        let steps_json: String = r.get(2)?;
+        Ok(Sequence {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            steps: serde_json::from_str(&steps_json).unwrap_or_default(),
+        })
+    }
+
+    pub fn sequences_list(&self) -> rusqlite::Result<Vec<Sequence>> {
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("select id, title, steps_json from sequences order by sort_order asc nulls last, title asc")?;
+        let rows = stmt.query_map([], Self::sequence_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn sequences_get(&self, id: &str) -> rusqlite::Result<Option<Sequence>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("select id, title, steps_json from sequences where id = ?1")?;
+        let mut rows = stmt.query_map(params![id], Self::sequence_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn sequences_create(&self, input: SequenceCreate) -> rusqlite::Result<Sequence> {
+        let seq = Sequence {
+            id: Uuid::new_v4().to_string(),
+            title: input.title,
+            steps: input.steps,
+        };
+        let steps_json = serde_json::to_string(&seq.steps).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from sequences", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into sequences (id, title, steps_json, sort_order) values (?1, ?2, ?3, ?4)",
+            params![seq.id, seq.title, steps_json, next],
+        )?;
+        Ok(seq)
+    }
+
+    pub fn sequences_update(&self, input: Sequence) -> rusqlite::Result<Sequence> {
+        let steps_json = serde_json::to_string(&input.steps).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.conn();
+        conn.execute(
+            "update sequences set title = ?2, steps_json = ?3 where id = ?1",
+            params![input.id, input.title, steps_json],
+        )?;
+        Ok(input)
+    }
+
+    pub fn sequences_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from sequences where id = ?1", params![id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::Db;
+
+/// What the post-connect probe found on a server (see `server_info.rs`). Fields it couldn't
+/// read are `None`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub hostname: Option<String>,
+    /// `uname -a`.
+    pub kernel: Option<String>,
+    /// e.g. "Ubuntu 22.04.4 LTS" (os-release's PRETTY_NAME, or macOS's product name and version).
+    pub distro: Option<String>,
+    /// `uptime` as printed.
+    pub uptime: Option<String>,
+    /// Epoch seconds.
+    pub probed_at: i64,
+    /// Why the probe failed; the other fields are empty then.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Db {
+    /// Keep the latest successful probe on the host record.
+    pub fn hosts_set_server_info(&self, id: &str, info: &ServerInfo) -> rusqlite::Result<()> {
+        let json = serde_json::to_string(info).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute("update hosts set server_info = ?2 where id = ?1", params![id, json])?;
+        Ok(())
+    }
+}
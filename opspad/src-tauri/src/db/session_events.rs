@@ -0,0 +1,49 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use super::Db;
+
+/// A terminal session opening or closing, kept for timelines after the session is gone.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEvent {
+    pub session_id: String,
+    pub scope: String,
+    /// "open" | "close"
+    pub kind: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub at: i64,
+    /// The scope's environment when last opened, if known.
+    pub environment_tag: Option<String>,
+}
+
+/// Events kept; older ones are dropped as new ones come in.
+const SESSION_EVENT_RETENTION: i64 = 20_000;
+
+impl Db {
+    pub(super) fn session_event_add(conn: &Connection, session_id: &str, scope: &str, kind: &str) -> rusqlite::Result<()> {
+        conn.prepare_cached("insert into session_events (session_id, scope, kind, at) values (?1, ?2, ?3, ?4)")?
+            .execute(params![session_id, scope, kind, Self::now_epoch_millis()])?;
+        conn.prepare_cached("delete from session_events where id <= (select max(id) from session_events) - ?1")?
+            .execute(params![SESSION_EVENT_RETENTION])?;
+        Ok(())
+    }
+
+    /// Oldest first; `since` and `until` are inclusive epoch milliseconds.
+    pub fn session_events_between(&self, since: i64, until: i64, limit: i64) -> rusqlite::Result<Vec<SessionEvent>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "select e.session_id, e.scope, e.kind, e.at, p.environment_tag from session_events e\n             left join terminal_prefs p on p.scope = e.scope\n             where e.at >= ?1 and e.at <= ?2 order by e.at asc, e.id asc limit ?3",
+        )?;
+        let rows = stmt.query_map(params![since, until, limit], |r| {
+            Ok(SessionEvent {
+                session_id: r.get(0)?,
+                scope: r.get(1)?,
+                kind: r.get(2)?,
+                at: r.get(3)?,
+                environment_tag: r.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
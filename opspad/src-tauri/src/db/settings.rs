@@ -0,0 +1,21 @@
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+
+// Small key/value store for app-level settings (values are JSON-encoded by callers).
+impl Db {
+    pub fn settings_get(&self, key: &str) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("select value from settings where key = ?1")?;
+        stmt.query_row(params![key], |r| r.get(0)).optional()
+    }
+
+    pub fn settings_set(&self, key: &str, value: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "insert into settings (key, value, updated_at) values (?1, ?2, ?3)\n             on conflict(key) do update set value = excluded.value, updated_at = excluded.updated_at",
+        )?;
+        stmt.execute(params![key, value, Self::now_epoch_millis()])?;
+        Ok(())
+    }
+}
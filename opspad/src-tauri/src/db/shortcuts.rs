@@ -0,0 +1,91 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+/// What a shortcut triggers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ShortcutTarget {
+    DockCommand { id: String },
+    Action { action: String },
+}
+
+impl ShortcutTarget {
+    fn to_columns(&self) -> (&'static str, &str) {
+        match self {
+            Self::DockCommand { id } => ("dockCommand", id),
+            Self::Action { action } => ("action", action),
+        }
+    }
+
+    fn from_columns(kind: &str, target: String) -> Self {
+        match kind {
+            "dockCommand" => Self::DockCommand { id: target },
+            _ => Self::Action { action: target },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shortcut {
+    pub id: String,
+    /// Normalized chord, e.g. "Ctrl+Shift+K".
+    pub chord: String,
+    pub target: ShortcutTarget,
+}
+
+impl Db {
+    pub fn shortcuts_list(&self) -> rusqlite::Result<Vec<Shortcut>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("select id, chord, target_kind, target from shortcuts order by chord asc")?;
+        let rows = stmt.query_map([], |r| {
+            Ok(Shortcut {
+                id: r.get(0)?,
+                chord: r.get(1)?,
+                target: ShortcutTarget::from_columns(&r.get::<_, String>(2)?, r.get(3)?),
+            })
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    /// Bind `chord` (already normalized and conflict-checked) to `target`.
+    ///
+    /// A target has at most one chord, so this replaces any previous binding for it.
+    pub fn shortcuts_set(&self, chord: &str, target: &ShortcutTarget) -> rusqlite::Result<Shortcut> {
+        let (kind, value) = target.to_columns();
+        let shortcut = Shortcut {
+            id: Uuid::new_v4().to_string(),
+            chord: chord.to_string(),
+            target: target.clone(),
+        };
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "delete from shortcuts where target_kind = ?1 and target = ?2",
+            params![kind, value],
+        )?;
+        tx.execute(
+            "insert into shortcuts (id, chord, target_kind, target, created_at) values (?1, ?2, ?3, ?4, ?5)",
+            params![shortcut.id, shortcut.chord, kind, value, Self::now_epoch_millis()],
+        )?;
+        tx.commit()?;
+        Ok(shortcut)
+    }
+
+    pub fn shortcuts_clear(&self, target: &ShortcutTarget) -> rusqlite::Result<()> {
+        let (kind, value) = target.to_columns();
+        let conn = self.conn();
+        conn.execute(
+            "delete from shortcuts where target_kind = ?1 and target = ?2",
+            params![kind, value],
+        )?;
+        Ok(())
+    }
+}
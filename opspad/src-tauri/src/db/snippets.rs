@@ -0,0 +1,134 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+/// Text typed as `trigger` in a terminal is replaced with `expansion` (see `snippets::expand`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub id: String,
+    /// A single word, e.g. ";k8slogs".
+    pub trigger: String,
+    /// May contain `{name}` variables.
+    pub expansion: String,
+    pub description: Option<String>,
+    /// `None`: every session. Otherwise "local", "ssh:<host_id>" or "env:<TAG>".
+    pub scope: Option<String>,
+    pub enabled: bool,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetInput {
+    pub trigger: String,
+    pub expansion: String,
+    pub description: Option<String>,
+    pub scope: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+const COLUMNS: &str = "id, trigger_word, expansion, description, scope, enabled, created_at, updated_at";
+
+fn clean(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl Db {
+    fn snippet_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Snippet> {
+        Ok(Snippet {
+            id: r.get(0)?,
+            trigger: r.get(1)?,
+            expansion: r.get(2)?,
+            description: r.get(3)?,
+            scope: r.get(4)?,
+            enabled: r.get::<_, i64>(5)? != 0,
+            created_at: r.get(6)?,
+            updated_at: r.get(7)?,
+        })
+    }
+
+    pub fn snippets_list(&self) -> rusqlite::Result<Vec<Snippet>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(&format!(
+            "select {COLUMNS} from snippets order by trigger_word asc, scope asc nulls first"
+        ))?;
+        let rows = stmt.query_map([], Self::snippet_row)?;
+        rows.collect()
+    }
+
+    pub fn snippets_get(&self, id: &str) -> rusqlite::Result<Option<Snippet>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {COLUMNS} from snippets where id = ?1"),
+            params![id],
+            Self::snippet_row,
+        )
+        .optional()
+    }
+
+    pub fn snippets_create(&self, input: SnippetInput) -> rusqlite::Result<Snippet> {
+        let now = Self::now_epoch_millis();
+        let snippet = Snippet {
+            id: Uuid::new_v4().to_string(),
+            trigger: input.trigger.trim().to_string(),
+            expansion: input.expansion,
+            description: clean(input.description),
+            scope: clean(input.scope),
+            enabled: input.enabled.unwrap_or(true),
+            created_at: now,
+            updated_at: now,
+        };
+        self.snippets_upsert(&snippet)?;
+        Ok(snippet)
+    }
+
+    /// `None` if the snippet doesn't exist.
+    pub fn snippets_update(&self, id: &str, input: SnippetInput) -> rusqlite::Result<Option<Snippet>> {
+        let Some(existing) = self.snippets_get(id)? else {
+            return Ok(None);
+        };
+        let snippet = Snippet {
+            trigger: input.trigger.trim().to_string(),
+            expansion: input.expansion,
+            description: clean(input.description),
+            scope: clean(input.scope),
+            enabled: input.enabled.unwrap_or(existing.enabled),
+            updated_at: Self::now_epoch_millis(),
+            ..existing
+        };
+        self.snippets_upsert(&snippet)?;
+        Ok(Some(snippet))
+    }
+
+    /// Insert or replace by id (Git sync applies remote snippets with this).
+    pub fn snippets_upsert(&self, s: &Snippet) -> rusqlite::Result<()> {
+        let now = Self::now_epoch_millis();
+        let conn = self.conn();
+        conn.execute(
+            "insert into snippets (id, trigger_word, expansion, description, scope, enabled, created_at, updated_at)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)\n             on conflict(id) do update set trigger_word = excluded.trigger_word, expansion = excluded.expansion,\n               description = excluded.description, scope = excluded.scope, enabled = excluded.enabled,\n               updated_at = excluded.updated_at",
+            params![
+                s.id,
+                s.trigger,
+                s.expansion,
+                s.description,
+                s.scope,
+                if s.enabled { 1i64 } else { 0i64 },
+                if s.created_at > 0 { s.created_at } else { now },
+                if s.updated_at > 0 { s.updated_at } else { now }
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn snippets_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from snippets where id = ?1", params![id])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Db, DockCommand, Host, Runbook};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    /// Bundle name as published (set on the first successful refresh).
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// Fingerprint of the key the bundle must be signed with (pinned on first refresh).
+    pub signer: Option<String>,
+    pub last_checked_at: Option<i64>,
+    pub last_error: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    /// Items currently provided by this subscription.
+    #[serde(default)]
+    pub item_count: i64,
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "id, url, name, version, signer, last_checked_at, last_error, created_at, \
+     (select count(1) from hosts where subscription_id = subscriptions.id) \
+     + (select count(1) from dock_commands where subscription_id = subscriptions.id) \
+     + (select count(1) from runbooks where subscription_id = subscriptions.id)";
+
+fn subscription_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Subscription> {
+    Ok(Subscription {
+        id: r.get(0)?,
+        url: r.get(1)?,
+        name: r.get(2)?,
+        version: r.get(3)?,
+        signer: r.get(4)?,
+        last_checked_at: r.get(5)?,
+        last_error: r.get(6)?,
+        created_at: r.get(7)?,
+        item_count: r.get(8)?,
+    })
+}
+
+impl Db {
+    pub fn subscriptions_list(&self) -> rusqlite::Result<Vec<Subscription>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {SUBSCRIPTION_COLUMNS} from subscriptions order by created_at asc"
+        ))?;
+        let rows = stmt.query_map([], subscription_row)?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    pub fn subscriptions_get(&self, id: &str) -> rusqlite::Result<Option<Subscription>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {SUBSCRIPTION_COLUMNS} from subscriptions where id = ?1"),
+            params![id],
+            subscription_row,
+        )
+        .optional()
+    }
+
+    /// The ETag of the last bundle applied for this subscription (backend-only).
+    pub fn subscriptions_etag(&self, id: &str) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn();
+        Ok(conn
+            .query_row("select etag from subscriptions where id = ?1", params![id], |r| r.get(0))
+            .optional()?
+            .flatten())
+    }
+
+    pub fn subscriptions_create(&self, url: &str, signer: Option<&str>) -> rusqlite::Result<Subscription> {
+        let sub = Subscription {
+            id: Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            name: None,
+            version: None,
+            signer: signer.map(str::to_string),
+            last_checked_at: None,
+            last_error: None,
+            created_at: Self::now_epoch_millis(),
+            item_count: 0,
+        };
+        let conn = self.conn();
+        conn.execute(
+            "insert into subscriptions (id, url, signer, created_at) values (?1, ?2, ?3, ?4)",
+            params![sub.id, sub.url, sub.signer, sub.created_at],
+        )?;
+        Ok(sub)
+    }
+
+    /// Record a refresh that didn't change the items (not modified, or failed with `error`).
+    pub fn subscriptions_mark_checked(&self, id: &str, error: Option<&str>) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update subscriptions set last_checked_at = ?2, last_error = ?3 where id = ?1",
+            params![id, Self::now_epoch_secs(), error],
+        )?;
+        Ok(())
+    }
+
+    /// Replace everything the subscription provides with a freshly verified bundle, atomically.
+    ///
+    /// Item ids must already be namespaced to the subscription. Local-only fields of existing items
+    /// (a host's identity file and group, command usage stats) survive the refresh.
+    #[allow(clippy::too_many_arguments)]
+    pub fn subscriptions_apply(
+        &self,
+        id: &str,
+        name: &str,
+        version: &str,
+        signer: &str,
+        etag: Option<&str>,
+        hosts: &[Host],
+        commands: &[DockCommand],
+        runbooks: &[Runbook],
+    ) -> rusqlite::Result<()> {
+        let now = Self::now_epoch_millis();
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+
+        for table in ["hosts", "dock_commands", "runbooks"] {
+            tx.execute(
+                &format!("update {table} set subscription_id = '-' where subscription_id = ?1"),
+                params![id],
+            )?;
+        }
+
+        let mut order: i64 = tx.query_row("select coalesce(max(sort_order), 0) from hosts", [], |r| r.get(0))?;
+        for h in hosts {
+            order += 1;
+            tx.execute(
+                "insert into hosts (id, label, hostname, port, username, environment_tag, identity_file, sort_order, color, subscription_id, connection_type, \
+                 dns_expansion, dns_selection) \
+                 values (?1, ?2, ?3, ?4, ?5, ?6, null, ?7, ?8, ?9, ?10, ?11, ?12) \
+                 on conflict(id) do update set label = excluded.label, hostname = excluded.hostname, port = excluded.port, \
+                 username = excluded.username, environment_tag = excluded.environment_tag, color = excluded.color, \
+                 subscription_id = excluded.subscription_id, connection_type = excluded.connection_type, \
+                 dns_expansion = excluded.dns_expansion, dns_selection = excluded.dns_selection",
+                params![
+                    h.id,
+                    h.label,
+                    h.hostname,
+                    h.port as u32,
+                    h.username,
+                    h.environment_tag,
+                    order,
+                    h.color,
+                    id,
+                    h.connection_type.as_str(),
+                    h.dns_expansion.as_str(),
+                    h.dns_selection.as_str()
+                ],
+            )?;
+        }
+
+        let mut order: i64 =
+            tx.query_row("select coalesce(max(sort_order), 0) from dock_commands", [], |r| r.get(0))?;
+        for c in commands {
+            order += 1;
+            let visibility = if c.visibility.is_global() {
+                None
+            } else {
+                serde_json::to_string(&c.visibility).ok()
+            };
+            tx.execute(
+                "insert into dock_commands (id, title, command, requires_confirm, sort_order, color, params_json, visibility_json, category, subscription_id) \
+                 values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) \
+                 on conflict(id) do update set title = excluded.title, command = excluded.command, \
+                 requires_confirm = excluded.requires_confirm, color = excluded.color, params_json = excluded.params_json, \
+                 visibility_json = excluded.visibility_json, category = excluded.category, \
+                 subscription_id = excluded.subscription_id",
+                params![
+                    c.id,
+                    c.title,
+                    c.command,
+                    if c.requires_confirm { 1i64 } else { 0i64 },
+                    order,
+                    c.color,
+                    Self::params_to_json(&c.params),
+                    visibility,
+                    c.category,
+                    id
+                ],
+            )?;
+        }
+
+        let mut order: i64 = tx.query_row("select coalesce(max(sort_order), 0) from runbooks", [], |r| r.get(0))?;
+        for rb in runbooks {
+            order += 1;
+            let tags = if rb.tags.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&rb.tags).ok()
+            };
+            let params_json = if rb.params.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&rb.params).ok()
+            };
+            tx.execute(
+                "insert into runbooks (id, title, markdown, tags_json, sort_order, created_at, updated_at, params_json, subscription_id) \
+                 values (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7, ?8) \
+                 on conflict(id) do update set title = excluded.title, markdown = excluded.markdown, \
+                 tags_json = excluded.tags_json, updated_at = excluded.updated_at, params_json = excluded.params_json, \
+                 subscription_id = excluded.subscription_id",
+                params![rb.id, rb.title, rb.markdown, tags, order, now, params_json, id],
+            )?;
+        }
+
+        // Whatever is still marked was dropped from the bundle.
+        tx.execute(
+            "delete from shortcuts where target_kind = 'dockCommand' \
+             and target in (select id from dock_commands where subscription_id = '-')",
+            [],
+        )?;
+        for table in ["hosts", "dock_commands", "runbooks"] {
+            tx.execute(&format!("delete from {table} where subscription_id = '-'"), [])?;
+        }
+
+        tx.execute(
+            "update subscriptions set name = ?2, version = ?3, signer = ?4, etag = ?5, last_checked_at = ?6, last_error = null where id = ?1",
+            params![id, name, version, signer, etag, now],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove the subscription and every item it provided.
+    pub fn subscriptions_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "delete from shortcuts where target_kind = 'dockCommand' \
+             and target in (select id from dock_commands where subscription_id = ?1)",
+            params![id],
+        )?;
+        for table in ["hosts", "dock_commands", "runbooks"] {
+            tx.execute(&format!("delete from {table} where subscription_id = ?1"), params![id])?;
+        }
+        tx.execute("delete from subscriptions where id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Insert-or-update by id, for configuration that arrives from outside the app (Git sync).
+//!
+//! Machine-local fields are left alone on update: a host's identity file and group, a command's
+//! usage statistics and shortcut.
+
+use rusqlite::params;
+
+use super::{Db, DockCommand, Host, Runbook};
+
+impl Db {
+    pub fn hosts_upsert(&self, host: &Host) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from hosts", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into hosts (id, label, hostname, port, username, environment_tag, identity_file, sort_order, color, group_id, connection_type, \
+             dns_expansion, dns_selection) \
+             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13) \
+             on conflict(id) do update set label = excluded.label, hostname = excluded.hostname, port = excluded.port, \
+             username = excluded.username, environment_tag = excluded.environment_tag, color = excluded.color, \
+             connection_type = excluded.connection_type, dns_expansion = excluded.dns_expansion, dns_selection = excluded.dns_selection",
+            params![
+                host.id,
+                host.label,
+                host.hostname,
+                host.port as u32,
+                host.username,
+                host.environment_tag,
+                host.identity_file,
+                next,
+                host.color,
+                host.group_id,
+                host.connection_type.as_str(),
+                host.dns_expansion.as_str(),
+                host.dns_selection.as_str()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn dock_commands_upsert(&self, cmd: &DockCommand) -> rusqlite::Result<()> {
+        let visibility = if cmd.visibility.is_global() {
+            None
+        } else {
+            serde_json::to_string(&cmd.visibility).ok()
+        };
+        let category = cmd.category.as_deref().map(str::trim).filter(|c| !c.is_empty());
+        let conn = self.conn();
+        let next: i64 = conn
+            .query_row("select coalesce(max(sort_order), 0) + 1 from dock_commands", [], |r| r.get(0))
+            .unwrap_or(1);
+        conn.execute(
+            "insert into dock_commands (id, title, command, requires_confirm, sort_order, color, params_json, visibility_json, category) \
+             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) \
+             on conflict(id) do update set title = excluded.title, command = excluded.command, \
+             requires_confirm = excluded.requires_confirm, color = excluded.color, params_json = excluded.params_json, \
+             visibility_json = excluded.visibility_json, category = excluded.category",
+            params![
+                cmd.id,
+                cmd.title,
+                cmd.command,
+                if cmd.requires_confirm { 1i64 } else { 0i64 },
+                next,
+                cmd.color,
+                Self::params_to_json(&cmd.params),
+                visibility,
+                category
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Create the runbook, or update it through `runbooks_update` so the change lands in its
+    /// version history.
+    pub fn runbooks_upsert(&self, rb: &Runbook) -> rusqlite::Result<()> {
+        match self.runbooks_get(&rb.id)? {
+            Some(current) => {
+                self.runbooks_update(Runbook {
+                    title: rb.title.clone(),
+                    markdown: rb.markdown.clone(),
+                    tags: rb.tags.clone(),
+                    ..current
+                })?;
+            }
+            None => {
+                let now = Self::now_epoch_millis();
+                let tags = if rb.tags.is_empty() {
+                    None
+                } else {
+                    serde_json::to_string(&rb.tags).ok()
+                };
+                let conn = self.conn();
+                let next: i64 = conn
+                    .query_row("select coalesce(max(sort_order), 0) + 1 from runbooks", [], |r| r.get(0))
+                    .unwrap_or(1);
+                conn.execute(
+                    "insert into runbooks (id, title, markdown, tags_json, sort_order, created_at, updated_at) values (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                    params![rb.id, rb.title, rb.markdown, tags, next, now],
+                )?;
+            }
+        }
+        self.runbooks_set_params(&rb.id, &rb.params)
+    }
+}
@@ -0,0 +1,149 @@
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+/// A local folder kept mirrored with one on a host (`sync_run`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProfile {
+    pub id: String,
+    pub name: String,
+    pub host_id: String,
+    pub local_path: String,
+    pub remote_path: String,
+    /// "push" (local to remote) | "pull" (remote to local)
+    pub direction: String,
+    /// Glob patterns (`*`, `?`) matched against each relative path and each of its parts.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    /// Epoch seconds of the last run that copied (not a dry run).
+    #[serde(default)]
+    pub last_run_at: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProfileCreate {
+    pub name: String,
+    pub host_id: String,
+    pub local_path: String,
+    pub remote_path: String,
+    pub direction: String,
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+const SYNC_PROFILE_COLUMNS: &str = "id, name, host_id, local_path, remote_path, direction, excludes_json, last_run_at";
+
+fn clean_excludes(excludes: Vec<String>) -> Vec<String> {
+    excludes
+        .into_iter()
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+impl Db {
+    fn sync_profile_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<SyncProfile> {
+        let excludes_json: String = r.get(6)?;
+        Ok(SyncProfile {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            host_id: r.get(2)?,
+            local_path: r.get(3)?,
+            remote_path: r.get(4)?,
+            direction: r.get(5)?,
+            excludes: serde_json::from_str(&excludes_json).unwrap_or_default(),
+            last_run_at: r.get(7)?,
+        })
+    }
+
+    pub fn sync_profiles_list(&self) -> rusqlite::Result<Vec<SyncProfile>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {SYNC_PROFILE_COLUMNS} from sync_profiles order by name collate nocase asc"
+        ))?;
+        let rows = stmt.query_map([], Self::sync_profile_row)?;
+        rows.collect()
+    }
+
+    pub fn sync_profiles_get(&self, id: &str) -> rusqlite::Result<Option<SyncProfile>> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("select {SYNC_PROFILE_COLUMNS} from sync_profiles where id = ?1"),
+            params![id],
+            Self::sync_profile_row,
+        )
+        .optional()
+    }
+
+    pub fn sync_profiles_create(&self, input: SyncProfileCreate) -> rusqlite::Result<SyncProfile> {
+        let profile = SyncProfile {
+            id: Uuid::new_v4().to_string(),
+            name: input.name.trim().to_string(),
+            host_id: input.host_id,
+            local_path: input.local_path.trim().to_string(),
+            remote_path: input.remote_path.trim().to_string(),
+            direction: input.direction,
+            excludes: clean_excludes(input.excludes),
+            last_run_at: None,
+        };
+        let excludes_json = serde_json::to_string(&profile.excludes)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn();
+        conn.execute(
+            "insert into sync_profiles (id, name, host_id, local_path, remote_path, direction, excludes_json, created_at)\n             values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                profile.id,
+                profile.name,
+                profile.host_id,
+                profile.local_path,
+                profile.remote_path,
+                profile.direction,
+                excludes_json,
+                Self::now_epoch_millis()
+            ],
+        )?;
+        Ok(profile)
+    }
+
+    /// `last_run_at` is maintained by `sync_run` and left untouched here.
+    pub fn sync_profiles_update(&self, input: SyncProfile) -> rusqlite::Result<SyncProfile> {
+        let excludes = clean_excludes(input.excludes.clone());
+        let excludes_json =
+            serde_json::to_string(&excludes).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        {
+            let conn = self.conn();
+            conn.execute(
+                "update sync_profiles set name = ?2, host_id = ?3, local_path = ?4, remote_path = ?5, direction = ?6,\n             excludes_json = ?7 where id = ?1",
+                params![
+                    input.id,
+                    input.name.trim(),
+                    input.host_id,
+                    input.local_path.trim(),
+                    input.remote_path.trim(),
+                    input.direction,
+                    excludes_json
+                ],
+            )?;
+        }
+        self.sync_profiles_get(&input.id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    pub fn sync_profiles_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute("delete from sync_profiles where id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn sync_profiles_mark_run(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update sync_profiles set last_run_at = ?2 where id = ?1",
+            params![id, Self::now_epoch_secs()],
+        )?;
+        Ok(())
+    }
+}
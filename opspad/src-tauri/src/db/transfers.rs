@@ -0,0 +1,202 @@
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Db;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transfer {
+    pub id: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub created_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+    pub finished_at: Option<i64>,
+    pub host_id: String,
+    pub host_label: String,
+    /// "upload" | "download"
+    pub direction: String,
+    pub local_path: String,
+    pub remote_path: String,
+    /// "queued" | "running" | "paused" | "succeeded" | "failed" | "cancelled"
+    pub status: String,
+    /// Runs started so far; from the second on, a partial destination file is continued.
+    pub attempts: u32,
+    /// Extra runs after a failure before the transfer is given up on.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each further one.
+    pub retry_backoff_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferCreate {
+    pub host_id: String,
+    pub direction: String,
+    pub local_path: String,
+    pub remote_path: String,
+    pub max_retries: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+}
+
+/// Keep the most recent finished transfers only.
+const TRANSFER_RETENTION: i64 = 200;
+
+const TRANSFER_COLUMNS: &str = "id, created_at, updated_at, finished_at, host_id, host_label, direction, local_path, remote_path, status, attempts, max_retries, retry_backoff_ms, error";
+
+impl Db {
+    fn transfer_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Transfer> {
+        Ok(Transfer {
+            id: r.get(0)?,
+            created_at: r.get(1)?,
+            updated_at: r.get(2)?,
+            finished_at: r.get(3)?,
+            host_id: r.get(4)?,
+            host_label: r.get(5)?,
+            direction: r.get(6)?,
+            local_path: r.get(7)?,
+            remote_path: r.get(8)?,
+            status: r.get(9)?,
+            attempts: r.get::<_, i64>(10)? as u32,
+            max_retries: r.get::<_, i64>(11)? as u32,
+            retry_backoff_ms: r.get::<_, i64>(12)? as u64,
+            error: r.get(13)?,
+        })
+    }
+
+    /// Record a new queued transfer. Limits are expected to be normalized by the caller.
+    pub fn transfers_create(
+        &self,
+        input: &TransferCreate,
+        host_label: &str,
+        max_retries: u32,
+        retry_backoff_ms: u64,
+    ) -> rusqlite::Result<Transfer> {
+        let now = Self::now_epoch_millis();
+        let transfer = Transfer {
+            id: Uuid::new_v4().to_string(),
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+            host_id: input.host_id.clone(),
+            host_label: host_label.to_string(),
+            direction: input.direction.clone(),
+            local_path: input.local_path.clone(),
+            remote_path: input.remote_path.clone(),
+            status: "queued".to_string(),
+            attempts: 0,
+            max_retries,
+            retry_backoff_ms,
+            error: None,
+        };
+        let conn = self.conn();
+        conn.execute(
+            "insert into transfers (id, created_at, updated_at, host_id, host_label, direction, local_path, remote_path,\n               status, attempts, max_retries, retry_backoff_ms)\n             values (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 0, ?9, ?10)",
+            params![
+                transfer.id,
+                transfer.created_at,
+                transfer.host_id,
+                transfer.host_label,
+                transfer.direction,
+                transfer.local_path,
+                transfer.remote_path,
+                transfer.status,
+                transfer.max_retries as i64,
+                transfer.retry_backoff_ms as i64
+            ],
+        )?;
+        conn.execute(
+            "delete from transfers where id in (\n               select id from transfers where status in ('succeeded', 'failed', 'cancelled')\n               order by created_at desc limit -1 offset ?1\n             )",
+            params![TRANSFER_RETENTION],
+        )?;
+        Ok(transfer)
+    }
+
+    pub fn transfers_get(&self, id: &str) -> rusqlite::Result<Option<Transfer>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("select {TRANSFER_COLUMNS} from transfers where id = ?1"))?;
+        let mut rows = stmt.query_map(params![id], Self::transfer_row)?;
+        rows.next().transpose()
+    }
+
+    /// Newest first.
+    pub fn transfers_list(&self, limit: i64) -> rusqlite::Result<Vec<Transfer>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {TRANSFER_COLUMNS} from transfers order by created_at desc, rowid desc limit ?1"
+        ))?;
+        let rows = stmt.query_map(params![limit], Self::transfer_row)?;
+        rows.collect()
+    }
+
+    /// Waiting to run, oldest first.
+    pub fn transfers_queued(&self) -> rusqlite::Result<Vec<Transfer>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "select {TRANSFER_COLUMNS} from transfers where status = 'queued' order by created_at asc, rowid asc"
+        ))?;
+        let rows = stmt.query_map(params![], Self::transfer_row)?;
+        rows.collect()
+    }
+
+    /// Claim a queued transfer for a run. False if it was paused or cancelled meanwhile.
+    pub fn transfers_start_attempt(&self, id: &str) -> rusqlite::Result<bool> {
+        let conn = self.conn();
+        let changed = conn.execute(
+            "update transfers set status = 'running', attempts = attempts + 1, error = null, updated_at = ?2\n             where id = ?1 and status = 'queued'",
+            params![id, Self::now_epoch_millis()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Move a transfer to `status` only if it's currently in one of `from`. Final statuses also
+    /// set `finished_at`. False if the transfer wasn't in any of `from`.
+    pub fn transfers_set_status(
+        &self,
+        id: &str,
+        from: &[&str],
+        status: &str,
+        error: Option<&str>,
+    ) -> rusqlite::Result<bool> {
+        let now = Self::now_epoch_millis();
+        let finished = matches!(status, "succeeded" | "failed" | "cancelled").then_some(now / 1000);
+        let from_list = vec!["?"; from.len()].join(", ");
+        let mut values: Vec<rusqlite::types::Value> = vec![
+            id.to_string().into(),
+            status.to_string().into(),
+            error.map(str::to_string).into(),
+            now.into(),
+            finished.into(),
+        ];
+        values.extend(from.iter().map(|s| s.to_string().into()));
+        let conn = self.conn();
+        let changed = conn.execute(
+            &format!(
+                "update transfers set status = ?2, error = ?3, updated_at = ?4, finished_at = ?5\n                 where id = ?1 and status in ({from_list})"
+            ),
+            rusqlite::params_from_iter(values),
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Transfers cut off by the app exiting go back in the queue, to be continued on startup.
+    pub(super) fn transfers_requeue_interrupted(&self) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "update transfers set status = 'queued', error = 'interrupted (app exited)', updated_at = ?1\n             where status = 'running'",
+            params![Self::now_epoch_millis()],
+        )?;
+        Ok(())
+    }
+
+    pub fn transfers_delete(&self, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn();
+        conn.execute(
+            "delete from transfers where id = ?1 and status not in ('queued', 'running')",
+            params![id],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use rusqlite::{params, OptionalExtension};
+
+use super::Db;
+use crate::arch::vault::{SecretIndex, SecretMetadata, ROTATION_WINDOW_SECS};
+
+// Index of vault keys. The OS keyring can't enumerate its entries, so we track them here.
+impl Db {
+    fn vault_index_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<SecretMetadata> {
+        Ok(SecretMetadata {
+            key: r.get(0)?,
+            label: r.get(1)?,
+            created_at: r.get(2)?,
+            updated_at: r.get(3)?,
+            last_used_at: r.get(4)?,
+            require_presence: r.get::<_, i64>(5)? != 0,
+            rotate_by: r.get(6)?,
+        })
+    }
+}
+
+impl SecretIndex for Db {
+    fn record_set(&self, key: &str) -> Result<(), String> {
+        let conn = self.conn();
+        conn.execute(
+            "insert into vault_index (key, created_at, updated_at) values (?1, ?2, ?2) \
+             on conflict(key) do update set updated_at = excluded.updated_at, \
+             rotate_by = case when rotate_by <= excluded.updated_at / 1000 + ?3 then null else rotate_by end",
+            params![key, Self::now_epoch_millis(), ROTATION_WINDOW_SECS],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn record_used(&self, key: &str) -> Result<(), String> {
+        // A secret we've never seen was stored before the index existed; adopt it.
+        let now = Self::now_epoch_millis();
+        let conn = self.conn();
+        conn.execute(
+            "insert into vault_index (key, created_at, updated_at, last_used_at) values (?1, ?2, ?2, ?2 / 1000) \
+             on conflict(key) do update set last_used_at = excluded.last_used_at",
+            params![key, now],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn record_deleted(&self, key: &str) -> Result<(), String> {
+        let conn = self.conn();
+        conn.execute("delete from vault_index where key = ?1", params![key])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<SecretMetadata>, String> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare("select key, label, created_at, updated_at, last_used_at, require_presence, rotate_by from vault_index order by key asc")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], Self::vault_index_row).map_err(|e| e.to_string())?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row.map_err(|e| e.to_string())?);
+        }
+        Ok(out)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<SecretMetadata>, String> {
+        let conn = self.conn();
+        conn.query_row(
+            "select key, label, created_at, updated_at, last_used_at, require_presence, rotate_by from vault_index where key = ?1",
+            params![key],
+            Self::vault_index_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    fn set_label(&self, key: &str, label: Option<&str>) -> Result<(), String> {
+        let label = label.map(str::trim).filter(|l| !l.is_empty());
+        let conn = self.conn();
+        let changed = conn
+            .execute("update vault_index set label = ?2 where key = ?1", params![key, label])
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("secret not found: {key}"));
+        }
+        Ok(())
+    }
+
+    fn set_require_presence(&self, key: &str, required: bool) -> Result<(), String> {
+        let conn = self.conn();
+        let changed = conn
+            .execute(
+                "update vault_index set require_presence = ?2 where key = ?1",
+                params![key, if required { 1i64 } else { 0i64 }],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("secret not found: {key}"));
+        }
+        Ok(())
+    }
+
+    fn set_rotate_by(&self, key: &str, rotate_by: Option<i64>) -> Result<(), String> {
+        let conn = self.conn();
+        let changed = conn
+            .execute("update vault_index set rotate_by = ?2 where key = ?1", params![key, rotate_by])
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("secret not found: {key}"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! `db:changed` events: which tables were written, batched so a bulk import or a sync pass is one
+//! event instead of one per row. The UI and the tray menu use it to refresh instead of polling.
+
+use std::collections::BTreeSet;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+/// Emit once writes pause for this long...
+const BATCH_WINDOW: Duration = Duration::from_millis(300);
+/// ...or this long after the first, so a steady stream of writes still gets reported.
+const MAX_BATCH: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbChangedEvent {
+    pub tables: Vec<String>,
+}
+
+pub fn spawn_notifier(app: AppHandle) {
+    let changes = app.state::<Arc<AppState>>().db.watch_changes();
+    thread::spawn(move || {
+        while let Ok(first) = changes.recv() {
+            let mut tables = BTreeSet::from([first]);
+            let started = Instant::now();
+            while started.elapsed() < MAX_BATCH {
+                match changes.recv_timeout(BATCH_WINDOW) {
+                    Ok(table) => {
+                        tables.insert(table);
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            let _ = app.emit(
+                "db:changed",
+                DbChangedEvent {
+                    tables: tables.into_iter().collect(),
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,128 @@
+//! `opspad://` links, e.g. from alert tickets and wiki pages:
+//!
+//! - `opspad://connect/<host-id>` opens (or focuses) an ssh tab to the host.
+//! - `opspad://runbook/<runbook-id>` shows the runbook in CommandDock.
+//! - `opspad://command/<command-id>` runs a dock command in the active terminal, and
+//!   `opspad://run/<command-id>/<host-id>` runs it on a host. These only work from links inside
+//!   runbooks (`runbook_link_open`): a link in a ticket or web page mustn't be able to run things.
+//!
+//! macOS delivers links as open-URL events; Windows and Linux pass them as the first argument,
+//! which [`crate::cli`] picks up (including forwarding to an already running instance).
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::cli::{self, CliAction, CliRequest};
+use crate::AppState;
+
+pub const SCHEME: &str = "opspad";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DeepLink {
+    Connect { host_id: String },
+    Runbook { id: String },
+    Command { id: String },
+    Run { command_id: String, host_id: String },
+}
+
+impl DeepLink {
+    fn runs_command(&self) -> bool {
+        matches!(self, Self::Command { .. } | Self::Run { .. })
+    }
+}
+
+fn parse(url: &str) -> Result<DeepLink, String> {
+    let invalid = || format!("unsupported link: {url}");
+    let rest = url
+        .strip_prefix(SCHEME)
+        .and_then(|r| r.strip_prefix("://"))
+        .ok_or_else(invalid)?;
+    // Ignore any query or fragment a wiki or ticket system tacks on.
+    let rest = rest.split(['?', '#']).next().unwrap_or(rest).trim_end_matches('/');
+    let segments: Vec<&str> = rest.split('/').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(invalid());
+    }
+    match segments[..] {
+        ["connect", id] => Ok(DeepLink::Connect { host_id: id.to_string() }),
+        ["runbook", id] => Ok(DeepLink::Runbook { id: id.to_string() }),
+        ["command", id] => Ok(DeepLink::Command { id: id.to_string() }),
+        ["run", command_id, host_id] => Ok(DeepLink::Run {
+            command_id: command_id.to_string(),
+            host_id: host_id.to_string(),
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+fn host(state: &AppState, id: &str) -> Result<crate::db::Host, String> {
+    state
+        .db
+        .hosts_get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("host not found: {id}"))
+}
+
+fn command(state: &AppState, id: &str) -> Result<Box<crate::db::DockCommand>, String> {
+    state
+        .db
+        .dock_commands_get(id)
+        .map_err(|e| e.to_string())?
+        .map(Box::new)
+        .ok_or_else(|| format!("command not found: {id}"))
+}
+
+fn action(state: &AppState, link: DeepLink) -> Result<CliAction, String> {
+    match link {
+        DeepLink::Connect { host_id } => Ok(CliAction::Connect { host: host(state, &host_id)? }),
+        DeepLink::Runbook { id } => {
+            let runbook = state
+                .db
+                .runbooks_get(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("runbook not found: {id}"))?;
+            Ok(CliAction::Runbook { runbook })
+        }
+        DeepLink::Command { id } => Ok(CliAction::Command { command: command(state, &id)? }),
+        DeepLink::Run { command_id, host_id } => Ok(CliAction::Run {
+            host: host(state, &host_id)?,
+            command: command(state, &command_id)?,
+        }),
+    }
+}
+
+/// A link opened from outside the app.
+pub fn resolve(state: &AppState, url: &str) -> Result<CliAction, String> {
+    let link = parse(url)?;
+    if link.runs_command() {
+        return Err(format!("{url} runs a command, so it only works from inside a runbook"));
+    }
+    action(state, link)
+}
+
+/// A link in a runbook's markdown, which may also run commands.
+pub fn resolve_from_runbook(state: &AppState, url: &str) -> Result<CliAction, String> {
+    action(state, parse(url)?)
+}
+
+/// Route links opened while the app is running (and, on macOS, the one it was launched with).
+pub fn listen(app: &AppHandle) {
+    // Installers register the scheme; this covers dev builds and unpackaged Linux/Windows runs.
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("couldn't register {SCHEME}:// links: {e}");
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        let state = handle.state::<Arc<AppState>>();
+        for url in event.urls() {
+            let request = CliRequest::Link { url: url.to_string() };
+            if let Err(e) = cli::dispatch(&handle, &state, &request) {
+                log::warn!("{SCHEME}:// link ignored: {e}");
+            }
+        }
+    });
+}
@@ -0,0 +1,125 @@
+//! Moving a live terminal session into its own window, for spreading an incident across
+//! monitors.
+//!
+//! The session keeps running through the handoff: its `terminal:data` is routed to the new
+//! window only (held back until that window's terminal is listening, see
+//! [`TerminalSessionManager::attach`](crate::terminal::session_manager::TerminalSessionManager::attach)),
+//! and input is accepted only from there. Closing the window hands the session back to the main
+//! window (`terminal:reattached`).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, EventTarget, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::{window_state, AppState};
+
+const WINDOW_PREFIX: &str = "terminal-";
+/// Detached windows share one saved geometry; they're usually all parked on the same monitor.
+const WINDOW_STATE_KEY: &str = "detached";
+
+/// What a detached window shows; also the payload of `terminal:reattached`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetachedSession {
+    pub session_id: String,
+    pub title: String,
+    pub environment_tag: String,
+}
+
+/// Detached windows keyed by window label.
+#[derive(Default)]
+pub struct DetachedWindows {
+    windows: Mutex<HashMap<String, DetachedSession>>,
+}
+
+/// Open a window for `session_id` and route the session to it.
+pub fn detach(app: &AppHandle, state: &AppState, session: DetachedSession) -> Result<(), String> {
+    let session_id = session.session_id.clone();
+    if state.terminal.window(&session_id).map_err(|e| e.to_string())?.is_some() {
+        return Err("session is already in its own window".to_string());
+    }
+    let label = format!("{WINDOW_PREFIX}{session_id}");
+    // Route first so output produced while the window loads is held rather than sent to main.
+    state
+        .terminal
+        .set_window(&session_id, Some(label.clone()))
+        .map_err(|e| e.to_string())?;
+
+    let built = WebviewWindowBuilder::new(app, &label, WebviewUrl::App("index.html".into()))
+        .title(format!("{} - OpsPad", session.title))
+        .inner_size(1000.0, 640.0)
+        .visible(false)
+        .build();
+    let window = match built {
+        Ok(w) => w,
+        Err(e) => {
+            let _ = state.terminal.set_window(&session_id, None);
+            return Err(e.to_string());
+        }
+    };
+    state
+        .detached
+        .windows
+        .lock()
+        .expect("poisoned detached windows lock")
+        .insert(label.clone(), session);
+
+    window_state::restore(&window, &state.db, WINDOW_STATE_KEY);
+    window_state::track(&window, WINDOW_STATE_KEY);
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            reattach(&handle, &label);
+        }
+    });
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+/// The detached window is gone: send the session back to the main window, if it's still alive.
+fn reattach(app: &AppHandle, label: &str) {
+    let state = app.state::<Arc<AppState>>();
+    let Some(session) = state
+        .detached
+        .windows
+        .lock()
+        .expect("poisoned detached windows lock")
+        .remove(label)
+    else {
+        return;
+    };
+    if state.terminal.set_window(&session.session_id, None).is_ok() {
+        let _ = app.emit_to(EventTarget::webview_window("main"), "terminal:reattached", session);
+    }
+}
+
+/// The session a detached window shows.
+pub fn get(state: &AppState, window: &str) -> Result<DetachedSession, String> {
+    state
+        .detached
+        .windows
+        .lock()
+        .expect("poisoned detached windows lock")
+        .get(window)
+        .cloned()
+        .ok_or_else(|| "not a detached terminal window".to_string())
+}
+
+/// Called by the detached window once its terminal is listening: flush the held output.
+pub fn attach(app: &AppHandle, state: &AppState, window: &str) -> Result<(), String> {
+    let session = get(state, window)?;
+    state
+        .terminal
+        .attach(app, &session.session_id, window)
+        .map_err(|e| e.to_string())
+}
+
+/// Reject input for a session from any window but the one it's routed to.
+pub fn check_owner(state: &AppState, session_id: &str, window: &str) -> Result<(), String> {
+    match state.terminal.window(session_id) {
+        Ok(Some(owner)) if owner != window => Err("this session is open in another window".to_string()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,211 @@
+//! Support bundle: one zip with what we usually ask for when something goes wrong.
+//!
+//! The bundle holds versions, tool resolution, the database's shape, live session summaries, and
+//! recent logs (passed through the user's redaction rules). It never holds secrets, settings
+//! values, command text, or terminal output.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::arch::shell::ShellCommand;
+use crate::arch::{pdf, shell, ssh};
+use crate::db::SchemaInfo;
+use crate::redact::Redactor;
+use crate::terminal::session_manager::SessionSummary;
+use crate::{exec, logging, AppState};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    generated_at: i64,
+    app: AppInfo,
+    os: OsInfo,
+    database: Option<SchemaInfo>,
+    database_error: Option<String>,
+    tools: ToolsInfo,
+    vault: VaultInfo,
+    sessions: Vec<SessionSummary>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppInfo {
+    name: String,
+    version: String,
+    identifier: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OsInfo {
+    os: &'static str,
+    family: &'static str,
+    arch: &'static str,
+    version: Option<String>,
+}
+
+/// Either where a tool resolved to, or why it didn't.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resolution {
+    path: Option<String>,
+    error: Option<String>,
+}
+
+impl Resolution {
+    fn from_result(r: Result<String, String>) -> Self {
+        match r {
+            Ok(path) => Self {
+                path: Some(path),
+                error: None,
+            },
+            Err(error) => Self {
+                path: None,
+                error: Some(error),
+            },
+        }
+    }
+
+    fn from_option(p: Option<String>, missing: &str) -> Self {
+        Self::from_result(p.ok_or_else(|| missing.to_string()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolsInfo {
+    ssh: Resolution,
+    shell: Resolution,
+    git: Resolution,
+    pdf_renderer: Resolution,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultInfo {
+    locked: bool,
+    indexed_secrets: Option<usize>,
+}
+
+fn run_probe(cmd: ShellCommand) -> Option<String> {
+    let out = exec::run(&cmd.program, &cmd.args, PROBE_TIMEOUT).ok()?;
+    let text = out.stdout.trim();
+    (out.exit_code == Some(0) && !text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    run_probe(ShellCommand::new("sw_vers", vec!["-productVersion".to_string()]))
+}
+
+#[cfg(windows)]
+fn os_version() -> Option<String> {
+    run_probe(ShellCommand::new("cmd", vec!["/C".to_string(), "ver".to_string()]))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn os_version() -> Option<String> {
+    let pretty = fs::read_to_string("/etc/os-release").ok().and_then(|s| {
+        s.lines()
+            .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+            .map(|v| v.trim_matches('"').to_string())
+    });
+    let kernel = run_probe(ShellCommand::new("uname", vec!["-sr".to_string()]));
+    match (pretty, kernel) {
+        (Some(p), Some(k)) => Some(format!("{p} ({k})")),
+        (p, k) => p.or(k),
+    }
+}
+
+fn tools() -> ToolsInfo {
+    let shell = shell::default_shell_command();
+    let shell_path = which::which(&shell.program)
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|e| format!("{}: {e}", shell.program));
+    let pdf = pdf::html_to_pdf_command(Path::new("in.html"), Path::new("out.pdf")).map(|c| c.program);
+    ToolsInfo {
+        ssh: Resolution::from_result(ssh::ssh_program_checked()),
+        shell: Resolution::from_result(shell_path),
+        git: Resolution::from_option(
+            which::which("git").ok().map(|p| p.to_string_lossy().to_string()),
+            "git not found on PATH",
+        ),
+        pdf_renderer: Resolution::from_option(pdf, "no Chromium-family browser or wkhtmltopdf found"),
+    }
+}
+
+fn report(app: &AppHandle, state: &AppState) -> Report {
+    let info = app.package_info();
+    let (database, database_error) = match state.db.schema_info() {
+        Ok(schema) => (Some(schema), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    Report {
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        app: AppInfo {
+            name: info.name.clone(),
+            version: info.version.to_string(),
+            identifier: app.config().identifier.clone(),
+        },
+        os: OsInfo {
+            os: std::env::consts::OS,
+            family: std::env::consts::FAMILY,
+            arch: std::env::consts::ARCH,
+            version: os_version(),
+        },
+        database,
+        database_error,
+        tools: tools(),
+        vault: VaultInfo {
+            locked: state.vault_lock.is_locked(),
+            indexed_secrets: state.vault.list_keys().ok().map(|k| k.len()),
+        },
+        sessions: state.terminal.list(),
+    }
+}
+
+/// Write the diagnostic bundle to `path` (a `.zip`).
+pub fn export(app: &AppHandle, state: &AppState, path: &Path) -> Result<(), String> {
+    let report = serde_json::to_string_pretty(&report(app, state)).map_err(|e| e.to_string())?;
+    let redactor = Redactor::new(&state.db.redaction_rules_list().map_err(|e| e.to_string())?);
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("report.json", options).map_err(|e| e.to_string())?;
+    zip.write_all(report.as_bytes()).map_err(|e| e.to_string())?;
+
+    // The current log and whatever rotated files are still around.
+    if let Some(dir) = logging::log_file(app)?.parent() {
+        let mut logs: Vec<_> = fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        logs.retain(|p| p.is_file() && p.extension().is_some_and(|e| e == "log"));
+        logs.sort();
+        for log in logs {
+            let Ok(bytes) = fs::read(&log) else {
+                continue;
+            };
+            let name = log.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            zip.start_file(format!("logs/{name}"), options).map_err(|e| e.to_string())?;
+            let text = redactor.redact(&String::from_utf8_lossy(&bytes));
+            zip.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
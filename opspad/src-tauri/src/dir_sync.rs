@@ -0,0 +1,310 @@
+//! Keeping a local folder and one on a host mirrored (`sync_profiles`), rsync-style.
+//!
+//! Both sides are listed; files that are missing on the receiving side, or differ from the
+//! sending side's (size, or an older modification time), are copied over SFTP. Nothing is ever
+//! deleted. A dry run reports what would be copied and touches nothing.
+//!
+//! The remote side is listed with GNU `find` over ssh. Copies keep modification times (`-p`), so
+//! a file copied once isn't copied again until it changes.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::arch::ssh;
+use crate::db::Host;
+use crate::{exec, transfers, AppState};
+
+const LIST_TIMEOUT: Duration = Duration::from_secs(60);
+const COPY_TIMEOUT: Duration = Duration::from_secs(6 * 60 * 60);
+/// Modification times this close count as equal (coarse filesystem timestamps).
+const MTIME_SLACK_SECS: i64 = 2;
+
+#[derive(Clone, Copy, Debug)]
+struct FileInfo {
+    size: u64,
+    mtime: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncItem {
+    /// Relative to the profile's folders, `/`-separated.
+    pub path: String,
+    pub size: u64,
+    /// "new" (missing on the receiving side) | "changed"
+    pub reason: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub profile_id: String,
+    pub dry_run: bool,
+    pub direction: String,
+    /// Copied, or with `dry_run` the ones that would be.
+    pub items: Vec<SyncItem>,
+    pub bytes: u64,
+    pub unchanged: usize,
+    pub excluded: usize,
+}
+
+pub fn validate(name: &str, local_path: &str, remote_path: &str, direction: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("name is required".to_string());
+    }
+    if local_path.trim().is_empty() || remote_path.trim().is_empty() {
+        return Err("local and remote paths are required".to_string());
+    }
+    if !matches!(direction, "push" | "pull") {
+        return Err(format!("unknown sync direction: {direction}"));
+    }
+    Ok(())
+}
+
+/// `*` (any run of characters) and `?` (any one character).
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Matched against the whole relative path and each of its parts, so `node_modules` or `*.log`
+/// exclude at any depth.
+fn is_excluded(path: &str, excludes: &[String]) -> bool {
+    excludes
+        .iter()
+        .any(|pattern| glob_match(pattern, path) || path.split('/').any(|part| glob_match(pattern, part)))
+}
+
+fn list_local(root: &Path, excludes: &[String]) -> Result<BTreeMap<String, FileInfo>, String> {
+    let mut files = BTreeMap::new();
+    if !root.exists() {
+        return Ok(files);
+    }
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(root.join(&dir)).map_err(|e| format!("{}: {e}", root.join(&dir).display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel = if dir.is_empty() { name } else { format!("{dir}/{name}") };
+            // Symlinks aren't followed, as with rsync's defaults.
+            let meta = entry.metadata().map_err(|e| format!("{rel}: {e}"))?;
+            if meta.is_dir() {
+                if !is_excluded(&rel, excludes) {
+                    pending.push(rel);
+                }
+            } else if meta.is_file() {
+                let mtime = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                files.insert(rel, FileInfo { size: meta.len(), mtime });
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// `root` for a POSIX shell, keeping a leading `~/` expandable.
+fn shell_path(root: &str) -> String {
+    match root.strip_prefix("~/") {
+        Some(rest) => format!("\"$HOME\"/{}", ssh::shell_quote(rest)),
+        None if root == "~" => "\"$HOME\"".to_string(),
+        None => ssh::shell_quote(root),
+    }
+}
+
+/// `root` for `sftp`, which starts in the home directory.
+fn sftp_path(root: &str) -> String {
+    match root.strip_prefix("~/") {
+        Some(rest) => rest.to_string(),
+        None if root == "~" => ".".to_string(),
+        None => root.to_string(),
+    }
+}
+
+fn list_remote(host: &Host, root: &str) -> Result<BTreeMap<String, FileInfo>, String> {
+    let dir = shell_path(root);
+    // A folder that isn't there yet is just empty.
+    let command = format!("if [ -d {dir} ]; then cd {dir} && find . -type f -printf '%P\\t%s\\t%T@\\n'; fi");
+    let program = ssh::ssh_program_checked()?;
+    let args = ssh::exec_args(
+        &host.username,
+        &host.hostname,
+        Some(host.port),
+        host.identity_file.as_deref(),
+        &command,
+    );
+    let out = exec::run(&program, &args, LIST_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.exit_code != Some(0) {
+        return Err(format!("listing {root} on {} failed: {}", host.label, out.stderr.trim()));
+    }
+    if out.truncated {
+        return Err(format!("{root} on {} has too many files to list", host.label));
+    }
+    let mut files = BTreeMap::new();
+    for line in out.stdout.lines() {
+        let mut parts = line.rsplitn(3, '\t');
+        let (Some(mtime), Some(size), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        let (Ok(size), Ok(mtime)) = (size.parse::<u64>(), mtime.parse::<f64>()) else {
+            continue;
+        };
+        files.insert(
+            path.to_string(),
+            FileInfo {
+                size,
+                mtime: mtime as i64,
+            },
+        );
+    }
+    Ok(files)
+}
+
+/// Every folder the items live in, parents first.
+fn parent_dirs(items: &[SyncItem]) -> BTreeSet<String> {
+    let mut dirs = BTreeSet::new();
+    for item in items {
+        let mut dir = item.path.as_str();
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            dirs.insert(parent.to_string());
+            dir = parent;
+        }
+    }
+    dirs
+}
+
+pub fn run(state: &AppState, profile_id: &str, dry_run: bool) -> Result<SyncReport, String> {
+    let profile = state
+        .db
+        .sync_profiles_get(profile_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "sync profile not found".to_string())?;
+    let host = state
+        .db
+        .hosts_get(&profile.host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "the profile's host no longer exists".to_string())?;
+    let push = profile.direction == "push";
+    let local_root = Path::new(&profile.local_path);
+    if push && !local_root.is_dir() {
+        return Err(format!("{} is not a folder", profile.local_path));
+    }
+
+    let local = list_local(local_root, &profile.excludes)?;
+    let remote = list_remote(&host, &profile.remote_path)?;
+    let (source, dest) = if push { (&local, &remote) } else { (&remote, &local) };
+
+    let mut report = SyncReport {
+        profile_id: profile.id.clone(),
+        dry_run,
+        direction: profile.direction.clone(),
+        items: Vec::new(),
+        bytes: 0,
+        unchanged: 0,
+        excluded: 0,
+    };
+    for (path, info) in source {
+        if is_excluded(path, &profile.excludes) {
+            report.excluded += 1;
+            continue;
+        }
+        let reason = match dest.get(path) {
+            None => "new",
+            Some(d) if d.size != info.size || info.mtime > d.mtime + MTIME_SLACK_SECS => "changed",
+            Some(_) => {
+                report.unchanged += 1;
+                continue;
+            }
+        };
+        report.bytes += info.size;
+        report.items.push(SyncItem {
+            path: path.clone(),
+            size: info.size,
+            reason,
+        });
+    }
+    if dry_run {
+        return Ok(report);
+    }
+    if !report.items.is_empty() {
+        copy(&host, &profile.local_path, &profile.remote_path, push, &report.items)?;
+    }
+    state.db.sync_profiles_mark_run(&profile.id).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+fn copy(host: &Host, local_root: &str, remote_root: &str, push: bool, items: &[SyncItem]) -> Result<(), String> {
+    let remote_root = sftp_path(remote_root);
+    let remote = |rel: &str| format!("{}/{rel}", remote_root.trim_end_matches('/'));
+    let local = |rel: &str| Path::new(local_root).join(rel).to_string_lossy().to_string();
+
+    let mut batch = String::new();
+    if push {
+        // `mkdir` fails on folders that exist; the leading `-` lets the batch carry on.
+        batch.push_str(&format!("-mkdir {}\n", transfers::quote(&remote_root)));
+        for dir in parent_dirs(items) {
+            batch.push_str(&format!("-mkdir {}\n", transfers::quote(&remote(&dir))));
+        }
+        for item in items {
+            let line = format!(
+                "put -p {} {}\n",
+                transfers::quote(&local(&item.path)),
+                transfers::quote(&remote(&item.path))
+            );
+            batch.push_str(&line);
+        }
+    } else {
+        for dir in parent_dirs(items) {
+            fs::create_dir_all(local(&dir)).map_err(|e| format!("{dir}: {e}"))?;
+        }
+        fs::create_dir_all(local_root).map_err(|e| format!("{local_root}: {e}"))?;
+        for item in items {
+            let line = format!(
+                "get -p {} {}\n",
+                transfers::quote(&remote(&item.path)),
+                transfers::quote(&local(&item.path))
+            );
+            batch.push_str(&line);
+        }
+    }
+
+    let program = ssh::sftp_program_checked()?;
+    let path = std::env::temp_dir().join(format!("opspad-sync-{}.sftp", uuid::Uuid::new_v4()));
+    fs::write(&path, batch).map_err(|e| format!("batch file: {e}"))?;
+    let result = exec::run(&program, &transfers::sftp_args(&path, host), COPY_TIMEOUT);
+    let _ = fs::remove_file(&path);
+    let out = result.map_err(|e| format!("failed to start sftp: {e}"))?;
+    if out.timed_out {
+        return Err("sync timed out".to_string());
+    }
+    if out.exit_code != Some(0) {
+        return Err(format!("sync stopped: {}", transfers::last_error(&out.stderr)));
+    }
+    Ok(())
+}
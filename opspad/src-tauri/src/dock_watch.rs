@@ -0,0 +1,265 @@
+//! Watch mode: a dock command re-run into a live session every few seconds, like `watch`.
+//!
+//! The command is rendered and checked against the environment policy once, when the watch
+//! starts. The first run is recorded in CommandDock history like any other; later runs only
+//! bump that entry's run count (and its timing, which follows the latest run), so a watch left
+//! going for an hour is one history row rather than hundreds. A watch stops when asked, when its
+//! session ends, or when it reaches its maximum duration. Changes are reported as `dock:watch`
+//! events.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::terminal::session_manager::WriteMeta;
+use crate::terminal::TerminalError;
+use crate::{command_timing, k8s, policy, template, AppState};
+
+const MIN_INTERVAL_SECS: u64 = 1;
+const MAX_INTERVAL_SECS: u64 = 3600;
+const DEFAULT_MAX_MINUTES: u64 = 30;
+/// However long a watch is asked to run for, it stops after this.
+const MAX_MINUTES: u64 = 12 * 60;
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockWatch {
+    pub session_id: String,
+    pub dock_command_id: String,
+    pub title: String,
+    pub interval_secs: u64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub started_at: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub expires_at: i64,
+    pub runs: u64,
+    /// The history entry every run is counted against.
+    pub history_id: Option<String>,
+}
+
+/// Payload of `dock:watch`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockWatchEvent {
+    #[serde(flatten)]
+    pub watch: DockWatch,
+    /// "running" (after each run) | "stopped" | "expired" | "ended" (the session closed)
+    pub status: &'static str,
+}
+
+struct Running {
+    watch: DockWatch,
+    runs: Arc<AtomicU64>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Running {
+    fn snapshot(&self) -> DockWatch {
+        DockWatch {
+            runs: self.runs.load(Ordering::SeqCst),
+            ..self.watch.clone()
+        }
+    }
+}
+
+/// Running watches, at most one per session (keyed by session id).
+#[derive(Default)]
+pub struct DockWatches {
+    running: Mutex<HashMap<String, Running>>,
+}
+
+impl DockWatches {
+    pub fn list(&self) -> Vec<DockWatch> {
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<DockWatch> = running.values().map(Running::snapshot).collect();
+        out.sort_by_key(|w| w.started_at);
+        out
+    }
+
+    /// Ask the session's watch to stop; false if it has none.
+    pub fn stop(&self, session_id: &str) -> bool {
+        match self.running.lock().unwrap_or_else(|e| e.into_inner()).get(session_id) {
+            Some(running) => {
+                running.stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Render `dock_command_id` for the session, run it once and keep re-running it every
+/// `interval_secs` until stopped or `max_minutes` (30 by default) have passed. `confirmed_hash`
+/// is the previewed command's hash, required with a confirmation.
+#[allow(clippy::too_many_arguments)]
+pub fn start(
+    app: AppHandle,
+    state: Arc<AppState>,
+    session_id: String,
+    dock_command_id: &str,
+    values: HashMap<String, String>,
+    interval_secs: u64,
+    max_minutes: Option<u64>,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<DockWatch, String> {
+    if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&interval_secs) {
+        return Err(format!(
+            "interval must be between {MIN_INTERVAL_SECS} and {MAX_INTERVAL_SECS} seconds"
+        ));
+    }
+    let max_minutes = max_minutes.unwrap_or(DEFAULT_MAX_MINUTES);
+    if max_minutes == 0 || max_minutes > MAX_MINUTES {
+        return Err(format!("maximum duration must be between 1 and {MAX_MINUTES} minutes"));
+    }
+    if state.watches.running.lock().unwrap_or_else(|e| e.into_inner()).contains_key(&session_id) {
+        return Err("a watch is already running in this session".to_string());
+    }
+    state.terminal.check_writable(&session_id).map_err(|e| e.to_string())?;
+
+    let cmd = state
+        .db
+        .dock_commands_get(dock_command_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "dock command not found".to_string())?;
+    let scope = state.db.terminal_session_scope_get(&session_id).map_err(|e| e.to_string())?;
+    let values = crate::dock_command_values(&state, scope.as_deref(), values)?;
+    let text = template::render(&cmd.command, &cmd.params, &values)?;
+    let text = k8s::for_scope(&state.db, scope.as_deref(), text)?;
+    policy::check_confirmed(&text, confirmation, confirmed_hash)?;
+    let environment = policy::session_environment(&state.db, &session_id)?;
+    let text = crate::command_plugins(
+        &state,
+        &text,
+        Some(&session_id),
+        scope.as_deref(),
+        Some(&environment),
+        Some(&cmd.id),
+    )?;
+    let approval = policy::check_dock_command(&state.db, &environment, &cmd, &text, confirmation, approval)?;
+
+    let data = format!("{text}\r");
+    let timeout = command_timing::Timeout::of(&cmd);
+    let history_id = crate::record_commanddock_write(
+        &state,
+        &session_id,
+        &data,
+        Some(&cmd.id),
+        Some(&cmd.title),
+        Some(&cmd.command),
+        approval.as_ref(),
+    );
+    let started = Instant::now();
+    let started_at = crate::timestamp::now_millis();
+    let watch = DockWatch {
+        session_id: session_id.clone(),
+        dock_command_id: cmd.id,
+        title: cmd.title,
+        interval_secs,
+        started_at,
+        expires_at: started_at + (max_minutes * 60_000) as i64,
+        runs: 0,
+        history_id,
+    };
+    let runs = Arc::new(AtomicU64::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut running = state.watches.running.lock().unwrap_or_else(|e| e.into_inner());
+        if running.contains_key(&session_id) {
+            return Err("a watch is already running in this session".to_string());
+        }
+        running.insert(
+            session_id.clone(),
+            Running {
+                watch: watch.clone(),
+                runs: runs.clone(),
+                stop: stop.clone(),
+            },
+        );
+    }
+
+    let first = watch.clone();
+    let interval = Duration::from_secs(interval_secs);
+    let deadline = started + Duration::from_secs(max_minutes * 60);
+    thread::spawn(move || {
+        let emit = |status: &'static str| {
+            let watch = DockWatch {
+                runs: runs.load(Ordering::SeqCst),
+                ..watch.clone()
+            };
+            let _ = app.emit("dock:watch", DockWatchEvent { watch, status });
+        };
+
+        let status = loop {
+            // Nothing is typed while the app is locked; the watch picks up again on unlock.
+            if state.app_lock.is_locked() {
+                thread::sleep(POLL_INTERVAL);
+                if stop.load(Ordering::SeqCst) {
+                    break "stopped";
+                }
+                if Instant::now() >= deadline {
+                    break "expired";
+                }
+                continue;
+            }
+            if let Some(history_id) = &watch.history_id {
+                command_timing::watch(&app, &state, &session_id, history_id, &data, timeout);
+            }
+            let written = state.terminal.write_with_meta(
+                &session_id,
+                &data,
+                WriteMeta {
+                    origin: Some("commanddock".to_string()),
+                },
+            );
+            match written {
+                Err(TerminalError::NotFound) => break "ended",
+                // Made read-only meanwhile.
+                Err(_) => break "stopped",
+                Ok(()) => {}
+            }
+            let n = runs.fetch_add(1, Ordering::SeqCst) + 1;
+            if let (Some(history_id), true) = (&watch.history_id, n > 1) {
+                if let Err(e) = state.db.dock_history_set_watch_runs(history_id, n as i64) {
+                    log::warn!("watch run count not recorded: {e}");
+                }
+            }
+            emit("running");
+
+            let next = Instant::now() + interval;
+            let stopped = loop {
+                if stop.load(Ordering::SeqCst) {
+                    break Some("stopped");
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    break Some("expired");
+                }
+                if now >= next {
+                    break None;
+                }
+                thread::sleep(POLL_INTERVAL.min(next.min(deadline) - now));
+            };
+            if let Some(status) = stopped {
+                break status;
+            }
+        };
+
+        state
+            .watches
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&session_id);
+        emit(status);
+    });
+
+    Ok(first)
+}
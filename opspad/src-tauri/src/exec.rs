@@ -0,0 +1,173 @@
+//! Headless (non-PTY) process execution with captured output.
+//!
+//! Used for work that doesn't deserve a terminal tab: fan-out runs across hosts, background jobs.
+
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Per-stream capture limit; anything beyond is dropped and `truncated` is set.
+const MAX_CAPTURE_BYTES: usize = 256 * 1024;
+/// How long to keep draining pipes after the process is gone. Background children can inherit
+/// stdout/stderr and hold them open indefinitely; we don't wait for those.
+const DRAIN_GRACE: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutput {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub truncated: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives output chunks as they are read (before the capture limit is applied).
+pub type OutputSink = Arc<dyn Fn(OutputStream, &str) + Send + Sync>;
+
+/// Run `program args...` to completion (or until `timeout`), capturing stdout/stderr.
+pub fn run(program: &str, args: &[String], timeout: Duration) -> std::io::Result<ExecOutput> {
+    run_with(program, args, timeout, None, None)
+}
+
+/// Like [`run`], but the process is killed once `cancel` is set, and output is streamed to `sink`.
+pub fn run_with(
+    program: &str,
+    args: &[String],
+    timeout: Duration,
+    cancel: Option<&AtomicBool>,
+    sink: Option<OutputSink>,
+) -> std::io::Result<ExecOutput> {
+    let started = Instant::now();
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window from the GUI app.
+        cmd.creation_flags(0x0800_0000);
+    }
+    let mut child = cmd.spawn()?;
+
+    let (done_tx, done_rx) = mpsc::channel();
+    let stdout = capture(child.stdout.take(), OutputStream::Stdout, sink.clone(), done_tx.clone());
+    let stderr = capture(child.stderr.take(), OutputStream::Stderr, sink, done_tx);
+
+    let (status, stopped) = wait_until(&mut child, timeout, cancel)?;
+    let drain_deadline = Instant::now() + DRAIN_GRACE;
+    for _ in 0..2 {
+        if done_rx
+            .recv_timeout(drain_deadline.saturating_duration_since(Instant::now()))
+            .is_err()
+        {
+            break;
+        }
+    }
+    let (stdout, out_trunc) = stdout.lock().expect("poisoned capture lock").take();
+    let (stderr, err_trunc) = stderr.lock().expect("poisoned capture lock").take();
+
+    Ok(ExecOutput {
+        exit_code: status.and_then(|s| s.code()),
+        stdout,
+        stderr,
+        duration_ms: started.elapsed().as_millis() as u64,
+        timed_out: matches!(stopped, Some(Stopped::TimedOut)),
+        cancelled: matches!(stopped, Some(Stopped::Cancelled)),
+        truncated: out_trunc || err_trunc,
+    })
+}
+
+#[derive(Default)]
+struct Captured {
+    bytes: Vec<u8>,
+    truncated: bool,
+}
+
+impl Captured {
+    fn take(&mut self) -> (String, bool) {
+        (String::from_utf8_lossy(&self.bytes).to_string(), self.truncated)
+    }
+}
+
+/// Read `stream` on a background thread into a shared buffer; signals `done` at EOF.
+fn capture<R: Read + Send + 'static>(
+    stream: Option<R>,
+    kind: OutputStream,
+    sink: Option<OutputSink>,
+    done: mpsc::Sender<()>,
+) -> Arc<Mutex<Captured>> {
+    let captured = Arc::new(Mutex::new(Captured::default()));
+    let out = captured.clone();
+    thread::spawn(move || {
+        if let Some(mut stream) = stream {
+            let mut buf = [0u8; 8192];
+            loop {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if let Some(sink) = &sink {
+                            sink(kind, &String::from_utf8_lossy(&buf[..n]));
+                        }
+                        let mut c = captured.lock().expect("poisoned capture lock");
+                        let room = MAX_CAPTURE_BYTES.saturating_sub(c.bytes.len());
+                        if n > room {
+                            c.truncated = true;
+                        }
+                        c.bytes.extend_from_slice(&buf[..n.min(room)]);
+                    }
+                }
+            }
+        }
+        let _ = done.send(());
+    });
+    out
+}
+
+enum Stopped {
+    TimedOut,
+    Cancelled,
+}
+
+fn wait_until(
+    child: &mut Child,
+    timeout: Duration,
+    cancel: Option<&AtomicBool>,
+) -> std::io::Result<(Option<std::process::ExitStatus>, Option<Stopped>)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((Some(status), None));
+        }
+        let stopped = if cancel.is_some_and(|c| c.load(Ordering::SeqCst)) {
+            Some(Stopped::Cancelled)
+        } else if Instant::now() >= deadline {
+            Some(Stopped::TimedOut)
+        } else {
+            None
+        };
+        if stopped.is_some() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok((None, stopped));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
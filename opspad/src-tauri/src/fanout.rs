@@ -0,0 +1,176 @@
+//! Run one rendered dock command across many hosts (headless ssh), collecting per-host results.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::arch::ssh;
+use crate::db::{Confirmation, FanoutHostResult, FanoutRun, Host};
+use crate::queue::TaskOutcome;
+use crate::{exec, jobs, policy, template, AppState};
+
+const HOST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// `confirmed_hash` is the hash from the command's unscoped `dock_command_preview`, required
+/// with a confirmation.
+pub fn run(
+    state: &AppState,
+    command_id: &str,
+    host_ids: &[String],
+    values: HashMap<String, String>,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<FanoutRun, String> {
+    if host_ids.is_empty() {
+        return Err("select at least one host".to_string());
+    }
+    let cmd = state
+        .db
+        .dock_commands_get(command_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "dock command not found".to_string())?;
+    let values = crate::dock_command_values(state, None, values)?;
+    let text = template::render(&cmd.command, &cmd.params, &values)?;
+    policy::check_confirmed(&text, confirmation, confirmed_hash)?;
+    let program = ssh::ssh_program_checked()?;
+
+    // A host picked twice would run the command twice (and break the report's per-host key).
+    let mut seen = HashSet::new();
+    let host_ids: Vec<&String> = host_ids.iter().filter(|id| seen.insert(id.as_str())).collect();
+    let mut hosts: Vec<Result<Host, String>> = Vec::with_capacity(host_ids.len());
+    for id in host_ids {
+        hosts.push(
+            state
+                .db
+                .hosts_get(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| id.clone()),
+        );
+    }
+    // Plugins may rewrite the command per host, and each host's environment policy applies to
+    // what it will run; refuse the whole run if any host is blocked.
+    let mut confirmations = Vec::new();
+    let mut host_texts: HashMap<String, String> = HashMap::new();
+    for host in hosts.iter().flatten() {
+        let scope = format!("ssh:{}", host.id);
+        let host_text = crate::command_plugins(
+            state,
+            &text,
+            None,
+            Some(&scope),
+            Some(&host.environment_tag),
+            Some(&cmd.id),
+        )
+        .map_err(|e| format!("{}: {e}", host.label))?;
+        let approval =
+            policy::check_dock_command(&state.db, &host.environment_tag, &cmd, &host_text, confirmation, approval)
+                .map_err(|e| format!("{}: {e}", host.label))?;
+        if let Some(approval) = approval {
+            confirmations.push(Confirmation {
+                scope: Some(scope),
+                host_id: Some(host.id.clone()),
+                dock_command_id: Some(cmd.id.clone()),
+                dock_command_title: Some(cmd.title.clone()),
+                ..policy::confirmation(&approval, &host.environment_tag, &host_text)
+            });
+        }
+        host_texts.insert(host.id.clone(), host_text);
+    }
+    for c in confirmations {
+        state.db.confirmations_add(c).map_err(|e| e.to_string())?;
+    }
+
+    // Hosts go through the shared work queue so a large fan-out respects the global parallelism
+    // limit and never overlaps with other work against the same host.
+    let (tx, rx) = mpsc::channel();
+    for (i, host) in hosts.into_iter().enumerate() {
+        let host = match host {
+            Ok(h) => h,
+            Err(id) => {
+                let _ = tx.send((i, missing_host(id)));
+                continue;
+            }
+        };
+        let tx = tx.clone();
+        let program = program.clone();
+        let text = host_texts.get(&host.id).cloned().unwrap_or_else(|| text.clone());
+        state.queue.submit(Some(format!("ssh:{}", host.id)), move |attempt| {
+            let result = run_on_host(&program, &host, &text);
+            // A command that ran isn't repeated, even when it exited 255 like a failed connection.
+            let transient =
+                result.error.is_some() || jobs::never_ran(result.exit_code, &result.stdout, &result.stderr);
+            if transient && !attempt.is_last {
+                return TaskOutcome::Retry;
+            }
+            let _ = tx.send((i, result));
+            TaskOutcome::Done
+        });
+    }
+    drop(tx);
+    let mut indexed: Vec<(usize, FanoutHostResult)> = rx.iter().collect();
+    indexed.sort_by_key(|(i, _)| *i);
+    let results: Vec<FanoutHostResult> = indexed.into_iter().map(|(_, r)| r).collect();
+
+    let ok_count = results.iter().filter(|r| r.ok()).count() as i64;
+    let mut run = FanoutRun {
+        id: Uuid::new_v4().to_string(),
+        created_at: 0,
+        command_id: Some(cmd.id),
+        command_title: cmd.title,
+        command_text: text,
+        host_count: results.len() as i64,
+        ok_count,
+        failed_count: results.len() as i64 - ok_count,
+        results,
+    };
+    state.db.fanout_run_save(&mut run).map_err(|e| e.to_string())?;
+    Ok(run)
+}
+
+fn missing_host(id: String) -> FanoutHostResult {
+    FanoutHostResult {
+        host_id: id.clone(),
+        host_label: id,
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        error: Some("host not found".to_string()),
+        duration_ms: 0,
+        timed_out: false,
+    }
+}
+
+fn run_on_host(program: &str, host: &Host, command: &str) -> FanoutHostResult {
+    let args = ssh::exec_args(
+        &host.username,
+        &host.hostname,
+        Some(host.port),
+        host.identity_file.as_deref(),
+        command,
+    );
+    match exec::run(program, &args, HOST_TIMEOUT) {
+        Ok(out) => FanoutHostResult {
+            host_id: host.id.clone(),
+            host_label: host.label.clone(),
+            exit_code: out.exit_code,
+            stdout: out.stdout,
+            stderr: out.stderr,
+            error: None,
+            duration_ms: out.duration_ms,
+            timed_out: out.timed_out,
+        },
+        Err(e) => FanoutHostResult {
+            host_id: host.id.clone(),
+            host_label: host.label.clone(),
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(e.to_string()),
+            duration_ms: 0,
+            timed_out: false,
+        },
+    }
+}
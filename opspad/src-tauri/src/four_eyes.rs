@@ -0,0 +1,245 @@
+//! Four-eyes approval: destructive commands in designated environments need a second person.
+//!
+//! An environment with `four_eyes` set still applies its destructive policy, and on top of that
+//! the write must carry an approval token signed by a trusted teammate. The requester hands the
+//! command to the teammate out-of-band as a request code; the teammate's OpsPad shows exactly what
+//! they're approving and signs it with that installation's ed25519 key (the one command packs are
+//! signed with). The token is bound to the environment, the exact command text and the requesting
+//! OS user, and expires after a few minutes. Teammates are trusted by adding their public key;
+//! this installation's own key can't be trusted, so nobody can approve their own command.
+
+use base64::Engine as _;
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+
+use crate::arch::vault::VaultProvider;
+use crate::db::Db;
+use crate::{packs, policy, timestamp};
+
+const APPROVERS_KEY: &str = "four_eyes.approvers";
+const REQUEST_PREFIX: &str = "opspad-approval-request:";
+const TOKEN_PREFIX: &str = "opspad-approval:";
+const TOKEN_FORMAT: &str = "opspad-approval";
+const DEFAULT_VALID_MINUTES: u32 = 15;
+const MAX_VALID_MINUTES: u32 = 60;
+/// Tolerated clock difference between the approver's machine and this one.
+const CLOCK_SKEW_MS: i64 = 2 * 60_000;
+
+/// A teammate whose approvals are accepted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedApprover {
+    pub name: String,
+    /// Base64 ed25519 public key.
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// This installation's approver identity, to hand to teammates.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproverIdentity {
+    pub name: String,
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+/// What a request code carries: the command someone wants a second approval for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRequest {
+    pub environment: String,
+    pub command: String,
+    pub requested_by: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub requested_at: i64,
+}
+
+/// The signed part of an approval token. Field order is the canonical serialization order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApprovalBody {
+    format: String,
+    environment: String,
+    command_sha256: String,
+    requested_by: String,
+    approver: String,
+    /// Epoch milliseconds.
+    issued_at: i64,
+    expires_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApprovalToken {
+    #[serde(flatten)]
+    body: ApprovalBody,
+    signature: packs::PackSignature,
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+}
+
+fn encode<T: Serialize>(prefix: &str, value: &T) -> Result<String, String> {
+    let json = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    Ok(format!("{prefix}{}", b64().encode(json)))
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(prefix: &str, code: &str, what: &str) -> Result<T, String> {
+    let code = code.trim();
+    let json = code
+        .strip_prefix(prefix)
+        .and_then(|rest| b64().decode(rest).ok())
+        .ok_or_else(|| format!("not an OpsPad {what}"))?;
+    serde_json::from_slice(&json).map_err(|e| format!("malformed {what}: {e}"))
+}
+
+fn parse_key(public_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = base64::engine::general_purpose::STANDARD
+        .decode(public_key.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| "not an ed25519 public key".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "not a valid ed25519 public key".to_string())
+}
+
+pub fn identity(vault: &dyn VaultProvider) -> Result<ApproverIdentity, String> {
+    let key = packs::public_key(vault)?;
+    Ok(ApproverIdentity {
+        name: policy::os_user(),
+        public_key: base64::engine::general_purpose::STANDARD.encode(key.as_bytes()),
+        fingerprint: packs::fingerprint(&key),
+    })
+}
+
+pub fn approvers(db: &Db) -> Vec<TrustedApprover> {
+    db.settings_get(APPROVERS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+fn save_approvers(db: &Db, approvers: &[TrustedApprover]) -> Result<(), String> {
+    let json = serde_json::to_string(approvers).map_err(|e| e.to_string())?;
+    db.settings_set(APPROVERS_KEY, &json).map_err(|e| e.to_string())
+}
+
+/// Trust `public_key` (from a teammate's [`identity`]) to approve; re-adding a key renames it.
+pub fn approver_add(
+    db: &Db,
+    vault: &dyn VaultProvider,
+    name: &str,
+    public_key: &str,
+) -> Result<TrustedApprover, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("approver name must not be empty".to_string());
+    }
+    let key = parse_key(public_key)?;
+    if key == packs::public_key(vault)? {
+        return Err("this is your own key; approvals must come from someone else".to_string());
+    }
+    let approver = TrustedApprover {
+        name: name.to_string(),
+        public_key: base64::engine::general_purpose::STANDARD.encode(key.as_bytes()),
+        fingerprint: packs::fingerprint(&key),
+    };
+    let mut all = approvers(db);
+    all.retain(|a| a.fingerprint != approver.fingerprint);
+    all.push(approver.clone());
+    save_approvers(db, &all)?;
+    Ok(approver)
+}
+
+/// Stop trusting the approver with `fingerprint`; false if there was none.
+pub fn approver_remove(db: &Db, fingerprint: &str) -> Result<bool, String> {
+    let mut all = approvers(db);
+    let before = all.len();
+    all.retain(|a| a.fingerprint != fingerprint);
+    if all.len() == before {
+        return Ok(false);
+    }
+    save_approvers(db, &all)?;
+    Ok(true)
+}
+
+/// A request code for `command` in `environment`, to send to a teammate.
+pub fn request(environment: &str, command: &str) -> Result<String, String> {
+    if command.trim().is_empty() {
+        return Err("command must not be empty".to_string());
+    }
+    encode(
+        REQUEST_PREFIX,
+        &ApprovalRequest {
+            environment: environment.trim().to_uppercase(),
+            command: command.trim_end_matches(['\r', '\n']).to_string(),
+            requested_by: policy::os_user(),
+            requested_at: timestamp::now_millis(),
+        },
+    )
+}
+
+/// Decode a request code, to show the approver what they're approving.
+pub fn inspect_request(code: &str) -> Result<ApprovalRequest, String> {
+    decode(REQUEST_PREFIX, code, "approval request")
+}
+
+/// Approve a teammate's request: an approval token valid for `valid_minutes` (15 by default).
+pub fn approve(vault: &dyn VaultProvider, code: &str, valid_minutes: Option<u32>) -> Result<String, String> {
+    let request = inspect_request(code)?;
+    let valid_minutes = valid_minutes.unwrap_or(DEFAULT_VALID_MINUTES);
+    if valid_minutes == 0 || valid_minutes > MAX_VALID_MINUTES {
+        return Err(format!("an approval can be valid for 1 to {MAX_VALID_MINUTES} minutes"));
+    }
+    let issued_at = timestamp::now_millis();
+    let body = ApprovalBody {
+        format: TOKEN_FORMAT.to_string(),
+        environment: request.environment,
+        command_sha256: policy::command_hash(&request.command),
+        requested_by: request.requested_by,
+        approver: policy::os_user(),
+        issued_at,
+        expires_at: issued_at + i64::from(valid_minutes) * 60_000,
+    };
+    let message = serde_json::to_vec(&body).map_err(|e| e.to_string())?;
+    let signature = packs::sign(vault, &message)?;
+    log::info!("approved a command in {} for {}", body.environment, body.requested_by);
+    encode(TOKEN_PREFIX, &ApprovalToken { body, signature })
+}
+
+/// Check that `token` approves `command` in `environment` for this user. Returns who approved it
+/// ("name (fingerprint)"), for the audit trail.
+pub fn verify(db: &Db, environment: &str, command: &str, token: &str) -> Result<String, String> {
+    let token: ApprovalToken = decode(TOKEN_PREFIX, token, "approval token")?;
+    let body = &token.body;
+    if body.format != TOKEN_FORMAT {
+        return Err("not an OpsPad approval token".to_string());
+    }
+    let message = serde_json::to_vec(body).map_err(|e| e.to_string())?;
+    let key = packs::verify(&token.signature, &message).map_err(|e| format!("approval {e}"))?;
+    let fingerprint = packs::fingerprint(&key);
+    let approver = approvers(db)
+        .into_iter()
+        .find(|a| a.fingerprint == fingerprint && parse_key(&a.public_key).is_ok_and(|k| k == key))
+        .ok_or_else(|| format!("the approval was signed by an untrusted key ({fingerprint})"))?;
+
+    if !body.environment.eq_ignore_ascii_case(environment.trim()) {
+        return Err(format!("the approval is for {}, not {}", body.environment, environment.trim()));
+    }
+    if body.command_sha256 != policy::command_hash(command) {
+        return Err("the approval is for a different command".to_string());
+    }
+    if body.requested_by != policy::os_user() {
+        return Err(format!("the approval was given to {}", body.requested_by));
+    }
+    let now = timestamp::now_millis();
+    if body.issued_at > now + CLOCK_SKEW_MS {
+        return Err("the approval is dated in the future; check the approver's clock".to_string());
+    }
+    if now > body.expires_at {
+        return Err("the approval has expired; ask for a new one".to_string());
+    }
+    Ok(format!("{} ({fingerprint})", approver.name))
+}
@@ -0,0 +1,194 @@
+//! System-wide hotkeys (work while another app has focus): show/hide the window, open quick
+//! connect, open a new local terminal.
+//!
+//! Bindings are stored in settings and registered with the OS at startup and on every save. A
+//! chord another application already holds fails to register; the save is then rolled back so
+//! the previous bindings keep working.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::db::Db;
+use crate::{cli, shortcuts, AppState};
+
+const SETTINGS_KEY: &str = "global_hotkeys";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GlobalAction {
+    ToggleWindow,
+    QuickConnect,
+    NewTerminal,
+}
+
+impl GlobalAction {
+    fn label(self) -> &'static str {
+        match self {
+            GlobalAction::ToggleWindow => "show/hide window",
+            GlobalAction::QuickConnect => "quick connect",
+            GlobalAction::NewTerminal => "new local terminal",
+        }
+    }
+}
+
+/// Chords in `shortcuts` notation ("Ctrl+Alt+K"); `None` leaves the action unbound.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalHotkeys {
+    #[serde(default)]
+    pub toggle_window: Option<String>,
+    #[serde(default)]
+    pub quick_connect: Option<String>,
+    #[serde(default)]
+    pub new_terminal: Option<String>,
+}
+
+impl GlobalHotkeys {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    fn store(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn bindings(&self) -> Vec<(GlobalAction, &str)> {
+        [
+            (GlobalAction::ToggleWindow, &self.toggle_window),
+            (GlobalAction::QuickConnect, &self.quick_connect),
+            (GlobalAction::NewTerminal, &self.new_terminal),
+        ]
+        .into_iter()
+        .filter_map(|(action, chord)| chord.as_deref().map(|c| (action, c)))
+        .collect()
+    }
+
+    /// The global action bound to an (already normalized) chord.
+    pub fn action_for(&self, chord: &str) -> Option<&'static str> {
+        self.bindings()
+            .into_iter()
+            .find(|(_, c)| *c == chord)
+            .map(|(action, _)| action.label())
+    }
+
+    /// Normalize every chord and reject ones that would break the terminal, collide with each
+    /// other, or shadow an in-app shortcut.
+    fn validated(&self, db: &Db) -> Result<Self, String> {
+        let normalize = |chord: &Option<String>| -> Result<Option<String>, String> {
+            match chord.as_deref().map(str::trim) {
+                None | Some("") => Ok(None),
+                Some(c) => shortcuts::normalize_chord(c).map(Some),
+            }
+        };
+        let out = Self {
+            toggle_window: normalize(&self.toggle_window)?,
+            quick_connect: normalize(&self.quick_connect)?,
+            new_terminal: normalize(&self.new_terminal)?,
+        };
+        let in_app = db.shortcuts_list().map_err(|e| e.to_string())?;
+        let bindings = out.bindings();
+        for (i, (action, chord)) in bindings.iter().enumerate() {
+            if shortcuts::RESERVED.contains(chord) {
+                return Err(format!("{chord} is reserved for the terminal"));
+            }
+            if let Some((other, _)) = bindings[..i].iter().find(|(_, c)| c == chord) {
+                return Err(format!("{chord} is bound to both {} and {}", other.label(), action.label()));
+            }
+            if in_app.iter().any(|s| s.chord == *chord) {
+                return Err(format!("{chord} is already an in-app shortcut; clear that first"));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// `shortcuts` chords say "Meta"; the OS hotkey parser calls it "Super".
+fn to_os_shortcut(chord: &str) -> Result<Shortcut, String> {
+    let spec: Vec<&str> = chord
+        .split('+')
+        .map(|part| if part == "Meta" { "Super" } else { part })
+        .collect();
+    Shortcut::from_str(&spec.join("+")).map_err(|e| format!("{chord}: {e}"))
+}
+
+fn register<R: Runtime>(app: &AppHandle<R>, config: &GlobalHotkeys) -> Result<(), String> {
+    let manager = app.global_shortcut();
+    manager.unregister_all().map_err(|e| e.to_string())?;
+    for (_, chord) in config.bindings() {
+        if let Err(e) = manager.register(to_os_shortcut(chord)?) {
+            let _ = manager.unregister_all();
+            return Err(format!("couldn't register {chord} (another application may be using it): {e}"));
+        }
+    }
+    Ok(())
+}
+
+/// Register the saved bindings; called once at startup.
+pub fn apply_saved<R: Runtime>(app: &AppHandle<R>, db: &Db) {
+    if let Err(e) = register(app, &GlobalHotkeys::load(db)) {
+        log::warn!("global hotkeys not registered: {e}");
+    }
+}
+
+/// Validate, register with the OS, then persist. On failure the previous bindings are restored.
+pub fn save<R: Runtime>(app: &AppHandle<R>, db: &Db, config: &GlobalHotkeys) -> Result<GlobalHotkeys, String> {
+    let config = config.validated(db)?;
+    let previous = GlobalHotkeys::load(db);
+    if let Err(e) = register(app, &config) {
+        let _ = register(app, &previous);
+        return Err(e);
+    }
+    config.store(db)?;
+    Ok(config)
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalHotkeyEvent {
+    action: GlobalAction,
+}
+
+fn toggle_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let visible = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+    if visible && window.is_focused().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        cli::focus_main_window(app);
+    }
+}
+
+/// Plugin handler: run the action bound to the pressed chord.
+pub fn on_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let state = app.state::<Arc<AppState>>();
+    let config = GlobalHotkeys::load(&state.db);
+    let Some(action) = config
+        .bindings()
+        .into_iter()
+        .find(|(_, chord)| to_os_shortcut(chord).is_ok_and(|s| s == *shortcut))
+        .map(|(action, _)| action)
+    else {
+        return;
+    };
+    match action {
+        GlobalAction::ToggleWindow => toggle_window(app),
+        GlobalAction::QuickConnect | GlobalAction::NewTerminal => {
+            cli::focus_main_window(app);
+            let _ = app.emit("global-hotkey", GlobalHotkeyEvent { action });
+        }
+    }
+}
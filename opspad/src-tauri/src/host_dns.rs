@@ -0,0 +1,173 @@
+//! DNS-expanded hosts: a saved host whose hostname is a discovery name with many records behind
+//! it (`DnsExpansion`). The name is resolved when a session opens, and the session goes to one
+//! target, which the UI picks (`host_resolve_targets`) or which is taken in turn. The address a
+//! session went to is recorded in the host's connection history (`hosts_connections`).
+//!
+//! A/AAAA records come from the system resolver. SRV records have no std API, so they're looked
+//! up with `dig` or `nslookup`, whichever is installed.
+
+use std::collections::{BTreeMap, HashSet};
+use std::net::ToSocketAddrs;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::db::{DnsExpansion, DnsSelection, Host};
+use crate::exec;
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Host id -> index of the target the next round-robin session takes.
+static NEXT_TARGET: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsTarget {
+    /// An IP address (A/AAAA) or the SRV record's target name.
+    pub address: String,
+    pub port: u16,
+    /// SRV only.
+    pub priority: Option<u16>,
+    pub weight: Option<u16>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedTargets {
+    pub host_id: String,
+    pub name: String,
+    pub expansion: DnsExpansion,
+    pub selection: DnsSelection,
+    /// In preference order (SRV: priority, then weight).
+    pub targets: Vec<DnsTarget>,
+    /// The target a session opened without picking would take.
+    pub suggested: usize,
+}
+
+/// The host's targets, or an error if it isn't DNS-expanded or the name has no records.
+pub fn resolve(host: &Host) -> Result<ResolvedTargets, String> {
+    let name = host.hostname.trim();
+    let targets = match host.dns_expansion {
+        DnsExpansion::Off => return Err(format!("{} isn't resolved through DNS", host.label)),
+        DnsExpansion::A => address_records(name, host.port)?,
+        DnsExpansion::Srv => srv_records(name)?,
+    };
+    if targets.is_empty() {
+        return Err(format!("{name} has no {} records", host.dns_expansion.as_str().to_uppercase()));
+    }
+    let suggested = match host.dns_selection {
+        DnsSelection::Pick => 0,
+        DnsSelection::RoundRobin => {
+            let next = NEXT_TARGET.lock().unwrap_or_else(|e| e.into_inner());
+            next.get(&host.id).copied().unwrap_or(0) % targets.len()
+        }
+    };
+    Ok(ResolvedTargets {
+        host_id: host.id.clone(),
+        name: name.to_string(),
+        expansion: host.dns_expansion,
+        selection: host.dns_selection,
+        targets,
+        suggested,
+    })
+}
+
+/// The target a session opened without a picked one goes to; advances the round-robin turn.
+pub fn choose(host: &Host) -> Result<DnsTarget, String> {
+    let resolved = resolve(host)?;
+    if host.dns_selection == DnsSelection::RoundRobin {
+        let mut next = NEXT_TARGET.lock().unwrap_or_else(|e| e.into_inner());
+        next.insert(host.id.clone(), (resolved.suggested + 1) % resolved.targets.len());
+    }
+    Ok(resolved.targets[resolved.suggested].clone())
+}
+
+fn address_records(name: &str, port: u16) -> Result<Vec<DnsTarget>, String> {
+    let addrs = (name, port).to_socket_addrs().map_err(|e| format!("couldn't resolve {name}: {e}"))?;
+    let mut seen = HashSet::new();
+    Ok(addrs
+        .filter(|a| seen.insert(a.ip()))
+        .map(|a| DnsTarget {
+            address: a.ip().to_string(),
+            port,
+            priority: None,
+            weight: None,
+        })
+        .collect())
+}
+
+fn srv_records(name: &str) -> Result<Vec<DnsTarget>, String> {
+    let dig = which::which("dig").ok();
+    let (program, args) = match (&dig, which::which("nslookup")) {
+        (Some(dig), _) => (dig.clone(), vec!["+short".to_string(), "SRV".to_string(), name.to_string()]),
+        (None, Ok(nslookup)) => (nslookup, vec!["-type=SRV".to_string(), name.to_string()]),
+        (None, Err(_)) => return Err("SRV lookups need dig or nslookup, and neither is installed".to_string()),
+    };
+    let out = exec::run(&program.to_string_lossy(), &args, LOOKUP_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.timed_out {
+        return Err(format!("the SRV lookup for {name} timed out"));
+    }
+    let parse = if dig.is_some() { parse_dig } else { parse_nslookup };
+    let mut targets = parse(&out.stdout);
+    if targets.is_empty() && out.exit_code != Some(0) {
+        let detail = if out.stderr.trim().is_empty() { &out.stdout } else { &out.stderr };
+        return Err(format!("SRV lookup for {name} failed: {}", detail.trim()));
+    }
+    // "." as the target means the service is decidedly not available at this domain.
+    targets.retain(|t| !t.address.is_empty());
+    targets.sort_by_key(|t| (t.priority, std::cmp::Reverse(t.weight)));
+    Ok(targets)
+}
+
+fn srv_target(priority: &str, weight: &str, port: &str, target: &str) -> Option<DnsTarget> {
+    Some(DnsTarget {
+        address: target.trim().trim_end_matches('.').to_string(),
+        port: port.trim().parse().ok()?,
+        priority: Some(priority.trim().parse().ok()?),
+        weight: Some(weight.trim().parse().ok()?),
+    })
+}
+
+/// `dig +short SRV`: "10 5 22 web-1.example.com." per record.
+fn parse_dig(stdout: &str) -> Vec<DnsTarget> {
+    stdout
+        .lines()
+        .filter_map(|line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [priority, weight, port, target] => srv_target(priority, weight, port, target),
+            _ => None,
+        })
+        .collect()
+}
+
+/// nslookup prints "name service = 10 5 22 web-1.example.com." per record on Unix, and a block of
+/// "priority = 10", "weight = 5", "port = 22", "svr hostname = web-1.example.com" lines on Windows.
+fn parse_nslookup(stdout: &str) -> Vec<DnsTarget> {
+    let mut targets = Vec::new();
+    let mut block: BTreeMap<&str, &str> = BTreeMap::new();
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let key = key.trim();
+        if key.ends_with("service") {
+            if let [priority, weight, port, target] = value.split_whitespace().collect::<Vec<_>>()[..] {
+                targets.extend(srv_target(priority, weight, port, target));
+            }
+            continue;
+        }
+        let field = match key {
+            "priority" => "priority",
+            "weight" => "weight",
+            "port" => "port",
+            k if k.ends_with("hostname") => "target",
+            _ => continue,
+        };
+        block.insert(field, value);
+        if let (Some(p), Some(w), Some(port), Some(t)) =
+            (block.get("priority"), block.get("weight"), block.get("port"), block.get("target"))
+        {
+            targets.extend(srv_target(p, w, port, t));
+            block.clear();
+        }
+    }
+    targets
+}
@@ -0,0 +1,41 @@
+//! Ephemeral hosts: hosts created with an expiry (`expires_at`), typically instances from an
+//! autoscaling group. Once expired they're archived: hidden from the host list, the monitor and
+//! quick connect, and never written to config sync. `hosts_unarchive` brings one back.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::AppState;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
+
+/// Archive expired hosts now and then every minute; emits `hosts:archived` with the hosts archived.
+pub fn spawn_sweeper(app: AppHandle) {
+    thread::spawn(move || loop {
+        let state = app.state::<Arc<AppState>>();
+        match state.db.hosts_archive_expired(now_epoch_secs()) {
+            Ok(archived) if !archived.is_empty() => {
+                log::info!("archived {} expired ephemeral host(s)", archived.len());
+                let _ = app.emit("hosts:archived", archived);
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("ephemeral host sweep failed: {e}"),
+        }
+        thread::sleep(SWEEP_INTERVAL);
+    });
+}
+
+/// An expiry for a new or existing host: it must still be ahead.
+pub fn check_expiry(expires_at: Option<i64>) -> Result<(), String> {
+    match expires_at {
+        Some(at) if at <= now_epoch_secs() => Err("expiresAt: must be in the future".to_string()),
+        _ => Ok(()),
+    }
+}
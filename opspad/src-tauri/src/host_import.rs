@@ -0,0 +1,609 @@
+//! Importing saved sessions from other SSH clients into hosts: PuTTY (its registry key, or a
+//! `.reg` export of it), Termius (CSV export) and SecureCRT (XML export).
+//!
+//! Each source is parsed into [`ImportedHost`] records plus notes about settings that have no
+//! OpsPad equivalent (proxies, port forwards, PuTTY key files...). The report lists every host
+//! that was imported, every entry that was skipped and why, and those notes, so nobody has to
+//! guess what didn't come across.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{ConnectionType, Db, DnsExpansion, DnsSelection, HostCreate};
+use crate::validate;
+
+#[cfg(windows)]
+const PUTTY_SESSIONS_KEY: &str = r"HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions";
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportSource {
+    /// PuTTY's saved sessions: read from the registry (Windows) when no file is given, otherwise
+    /// a `.reg` export of `HKCU\Software\SimonTatham\PuTTY\Sessions`.
+    Putty,
+    /// A Termius CSV export.
+    Termius,
+    /// A SecureCRT XML export (Tools > Export Settings).
+    SecureCrt,
+}
+
+/// A host as translated from the source, before it's stored.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedHost {
+    pub label: String,
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub identity_file: Option<String>,
+    /// The source's folder or group; mapped to the host group of that name (created if needed).
+    pub group: Option<String>,
+    /// Overrides the import's environment tag (inventory sources map it from a label).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment_tag: Option<String>,
+}
+
+/// Something about one entry of the source: why it was skipped, or a setting that wasn't
+/// translated.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportNote {
+    /// The session/host name in the source.
+    pub entry: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostImportReport {
+    /// Hosts created (or, for a dry run, that would be).
+    pub imported: Vec<ImportedHost>,
+    pub skipped: Vec<ImportNote>,
+    /// Settings of imported hosts that had no equivalent and were dropped.
+    pub notes: Vec<ImportNote>,
+}
+
+impl HostImportReport {
+    pub(crate) fn skip(&mut self, entry: &str, message: impl Into<String>) {
+        self.skipped.push(ImportNote {
+            entry: entry.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn note(&mut self, entry: &str, message: impl Into<String>) {
+        self.notes.push(ImportNote {
+            entry: entry.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+/// Import hosts from `source` (read from `path`, or PuTTY's registry when `path` is `None`) into
+/// `environment_tag`. With `dry_run` nothing is stored; the report says what would happen.
+pub fn import(
+    db: &Db,
+    source: ImportSource,
+    path: Option<&Path>,
+    environment_tag: &str,
+    dry_run: bool,
+) -> Result<HostImportReport, String> {
+    let text = match path {
+        Some(path) => read_text(path)?,
+        None if source == ImportSource::Putty => putty_registry_export()?,
+        None => return Err("choose the exported file to import".to_string()),
+    };
+    let mut report = HostImportReport::default();
+    let hosts = match source {
+        ImportSource::Putty => parse_putty(&text, &mut report),
+        ImportSource::Termius => parse_termius(&text, &mut report)?,
+        ImportSource::SecureCrt => parse_securecrt(&text, &mut report)?,
+    };
+    store(db, hosts, environment_tag, dry_run, &mut report)?;
+    Ok(report)
+}
+
+/// Validate and create `hosts` (in `environment_tag` unless a host names its own), recording each
+/// in `report`. Shared with inventory sources.
+pub(crate) fn store(
+    db: &Db,
+    hosts: Vec<ImportedHost>,
+    environment_tag: &str,
+    dry_run: bool,
+    report: &mut HostImportReport,
+) -> Result<(), String> {
+    let mut groups: HashMap<String, String> = db
+        .host_groups_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|g| (g.name.to_lowercase(), g.id))
+        .collect();
+    let mut labels = HashSet::new();
+    for mut host in hosts {
+        let entry = host.label.clone();
+        if host.username.is_empty() {
+            match local_username() {
+                Some(user) => {
+                    report.note(&entry, format!("no username was saved; using {user}"));
+                    host.username = user;
+                }
+                None => {
+                    report.skip(&entry, "no username was saved");
+                    continue;
+                }
+            }
+        }
+        if !labels.insert(host.label.to_lowercase()) {
+            report.skip(&entry, "another entry in the import has the same name");
+            continue;
+        }
+        let environment_tag = host.environment_tag.as_deref().unwrap_or(environment_tag);
+        if let Err(e) = validate::host(
+            db,
+            None,
+            &host.label,
+            &host.hostname,
+            host.port,
+            &host.username,
+            environment_tag,
+        ) {
+            report.skip(&entry, e.replace('\n', "; "));
+            continue;
+        }
+        if !dry_run {
+            let group_id = match host.group.as_deref() {
+                Some(name) => Some(match groups.get(&name.to_lowercase()) {
+                    Some(id) => id.clone(),
+                    None => {
+                        let group = db.host_groups_create(name).map_err(|e| e.to_string())?;
+                        groups.insert(name.to_lowercase(), group.id.clone());
+                        group.id
+                    }
+                }),
+                None => None,
+            };
+            db.hosts_create(HostCreate {
+                label: host.label.trim().to_string(),
+                hostname: host.hostname.trim().to_string(),
+                port: Some(host.port),
+                username: host.username.trim().to_string(),
+                environment_tag: environment_tag.trim().to_string(),
+                identity_file: host.identity_file.clone(),
+                color: None,
+                group_id,
+                connection_type: ConnectionType::Ssh,
+                dns_expansion: DnsExpansion::Off,
+                dns_selection: DnsSelection::Pick,
+                expires_at: None,
+            })
+            .map_err(|e| e.to_string())?;
+        }
+        report.imported.push(host);
+    }
+    Ok(())
+}
+
+fn read_text(path: &Path) -> Result<String, String> {
+    let meta = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    if meta.len() > MAX_FILE_BYTES {
+        return Err("the file is too large to be an export".to_string());
+    }
+    Ok(decode(&std::fs::read(path).map_err(|e| e.to_string())?))
+}
+
+/// Registry exports are UTF-16LE with a BOM; everything else is UTF-8 (possibly with a BOM).
+fn decode(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[cfg(windows)]
+fn putty_registry_export() -> Result<String, String> {
+    let path = std::env::temp_dir().join(format!("opspad-putty-{}.reg", uuid::Uuid::new_v4()));
+    let args = vec![
+        "export".to_string(),
+        PUTTY_SESSIONS_KEY.to_string(),
+        path.to_string_lossy().to_string(),
+        "/y".to_string(),
+    ];
+    let out = crate::exec::run("reg", &args, std::time::Duration::from_secs(30)).map_err(|e| e.to_string())?;
+    let text = if out.exit_code == Some(0) { read_text(&path) } else { Err(String::new()) };
+    let _ = std::fs::remove_file(&path);
+    text.map_err(|_| "no PuTTY sessions were found in the registry".to_string())
+}
+
+#[cfg(not(windows))]
+fn putty_registry_export() -> Result<String, String> {
+    Err("PuTTY keeps sessions in the Windows registry; import a .reg export of it instead".to_string())
+}
+
+fn local_username() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty() && !u.contains(char::is_whitespace))
+}
+
+/// `user@host` as PuTTY and Termius allow in the host field.
+fn split_user(hostname: &str) -> (Option<&str>, &str) {
+    match hostname.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, hostname),
+    }
+}
+
+/// PuTTY session names are percent-encoded in the registry ("my%20server").
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[derive(Debug)]
+enum RegValue {
+    String(String),
+    Dword(u32),
+    Other,
+}
+
+/// The `[key]` sections of a `.reg` file with their values.
+fn parse_reg(text: &str) -> Vec<(String, BTreeMap<String, RegValue>)> {
+    let mut sections: Vec<(String, BTreeMap<String, RegValue>)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((key.to_string(), BTreeMap::new()));
+            continue;
+        }
+        let Some((_, values)) = sections.last_mut() else { continue };
+        let Some(rest) = line.strip_prefix('"') else { continue };
+        let Some((name, value)) = rest.split_once("\"=") else { continue };
+        let value = if let Some(s) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            RegValue::String(s.replace("\\\"", "\"").replace("\\\\", "\\"))
+        } else if let Some(d) = value.strip_prefix("dword:") {
+            u32::from_str_radix(d, 16).map(RegValue::Dword).unwrap_or(RegValue::Other)
+        } else {
+            RegValue::Other
+        };
+        values.insert(name.to_string(), value);
+    }
+    sections
+}
+
+fn parse_putty(text: &str, report: &mut HostImportReport) -> Vec<ImportedHost> {
+    let mut hosts = Vec::new();
+    for (key, values) in parse_reg(text) {
+        let Some((_, name)) = key.rsplit_once(r"\Sessions\") else { continue };
+        let entry = percent_decode(name);
+        let string = |k: &str| match values.get(k) {
+            Some(RegValue::String(s)) => s.trim().to_string(),
+            _ => String::new(),
+        };
+        let dword = |k: &str| match values.get(k) {
+            Some(RegValue::Dword(d)) => *d,
+            _ => 0,
+        };
+        let hostname = string("HostName");
+        if hostname.is_empty() {
+            if entry != "Default Settings" {
+                report.skip(&entry, "no host name is saved");
+            }
+            continue;
+        }
+        let protocol = string("Protocol");
+        if !protocol.is_empty() && protocol != "ssh" {
+            report.skip(&entry, format!("{protocol} sessions aren't supported, only SSH"));
+            continue;
+        }
+        let (user, hostname) = split_user(&hostname);
+        let username = match string("UserName") {
+            u if u.is_empty() => user.unwrap_or_default().to_string(),
+            u => u,
+        };
+        let mut identity_file = None;
+        let key_file = string("PublicKeyFile");
+        if key_file.to_lowercase().ends_with(".ppk") {
+            report.note(
+                &entry,
+                format!("{key_file} is a PuTTY key; convert it with `puttygen key.ppk -O private-openssh -o key` and set it on the host"),
+            );
+        } else if !key_file.is_empty() {
+            identity_file = Some(key_file);
+        }
+        if dword("ProxyMethod") != 0 {
+            report.note(&entry, "proxy settings weren't imported");
+        }
+        if !string("PortForwardings").is_empty() {
+            report.note(&entry, "port forwardings weren't imported");
+        }
+        if !string("RemoteCommand").is_empty() {
+            report.note(&entry, "the remote command wasn't imported");
+        }
+        if dword("AgentFwd") != 0 {
+            report.note(&entry, "agent forwarding isn't set per host; it was dropped");
+        }
+        let port = dword("PortNumber");
+        hosts.push(ImportedHost {
+            label: entry,
+            hostname: hostname.to_string(),
+            port: u16::try_from(port).ok().filter(|p| *p != 0).unwrap_or(22),
+            username,
+            identity_file,
+            group: None,
+            environment_tag: None,
+        });
+    }
+    hosts
+}
+
+/// RFC 4180 CSV: quoted fields may hold commas, doubled quotes and newlines.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+fn parse_termius(text: &str, report: &mut HostImportReport) -> Result<Vec<ImportedHost>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or_else(|| "the file is empty".to_string())?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let hostname_col = column(&["hostname/ip", "hostname", "address", "host"])
+        .ok_or_else(|| "not a Termius export: there's no Hostname/IP column".to_string())?;
+    let label_col = column(&["label", "name", "alias"]);
+    let port_col = column(&["port"]);
+    let user_col = column(&["username", "user"]);
+    let group_col = column(&["groups", "group"]);
+    let protocol_col = column(&["protocol"]);
+    let password_col = column(&["password"]);
+    let key_col = column(&["ssh_key", "key", "ssh key"]);
+    let tags_col = column(&["tags"]);
+
+    let mut hosts = Vec::new();
+    for row in rows {
+        let get = |col: Option<usize>| col.and_then(|c| row.get(c)).map(|v| v.trim()).unwrap_or_default();
+        let address = get(Some(hostname_col));
+        let entry = match get(label_col) {
+            "" => address.to_string(),
+            label => label.to_string(),
+        };
+        if address.is_empty() {
+            report.skip(&entry, "no hostname");
+            continue;
+        }
+        let protocol = get(protocol_col);
+        if !protocol.is_empty() && !protocol.eq_ignore_ascii_case("ssh") {
+            report.skip(&entry, format!("{protocol} hosts aren't supported, only SSH"));
+            continue;
+        }
+        let port = match get(port_col) {
+            "" => 22,
+            p => match p.parse::<u16>() {
+                Ok(p) if p != 0 => p,
+                _ => {
+                    report.skip(&entry, format!("\"{p}\" isn't a port"));
+                    continue;
+                }
+            },
+        };
+        let (user, address) = split_user(address);
+        if !get(password_col).is_empty() {
+            report.note(&entry, "the password wasn't imported; set it on the host to keep it in the vault");
+        }
+        if !get(key_col).is_empty() {
+            report.note(&entry, "the SSH key wasn't imported; set the key file on the host");
+        }
+        if !get(tags_col).is_empty() {
+            report.note(&entry, "tags weren't imported");
+        }
+        // Nested groups are exported as "Parent/Child"; the whole path names the host group.
+        let group = Some(get(group_col)).filter(|g| !g.is_empty()).map(str::to_string);
+        hosts.push(ImportedHost {
+            label: entry,
+            hostname: address.to_string(),
+            port,
+            username: match get(user_col) {
+                "" => user.unwrap_or_default().to_string(),
+                u => u.to_string(),
+            },
+            identity_file: None,
+            group,
+            environment_tag: None,
+        });
+    }
+    Ok(hosts)
+}
+
+/// An element of a SecureCRT export: `<key name="..">`, `<string name="..">text</string>`, ...
+#[derive(Debug, Default)]
+struct XmlNode {
+    tag: String,
+    name: String,
+    text: String,
+    children: Vec<XmlNode>,
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Just enough XML for SecureCRT's exports: elements with a `name` attribute, text, no mixed
+/// content. Comments, declarations and other attributes are ignored.
+fn parse_xml(text: &str) -> Result<XmlNode, String> {
+    let mut stack = vec![XmlNode::default()];
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let content = &rest[..start];
+        if !content.trim().is_empty() {
+            if let Some(top) = stack.last_mut() {
+                top.text.push_str(&xml_unescape(content));
+            }
+        }
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").ok_or("unterminated comment")?;
+            rest = &rest[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(closing) = tag.strip_prefix('/') {
+            let node = stack.pop().filter(|n| n.tag == closing.trim()).ok_or("mismatched tags")?;
+            stack.last_mut().ok_or("mismatched tags")?.children.push(node);
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (tag_name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let name = attrs
+            .split_once("name=\"")
+            .and_then(|(_, v)| v.split_once('"'))
+            .map(|(v, _)| xml_unescape(v))
+            .unwrap_or_default();
+        let node = XmlNode {
+            tag: tag_name.to_string(),
+            name,
+            ..XmlNode::default()
+        };
+        if self_closing {
+            stack.last_mut().ok_or("mismatched tags")?.children.push(node);
+        } else {
+            stack.push(node);
+        }
+    }
+    if stack.len() != 1 {
+        return Err("the XML ends early".to_string());
+    }
+    Ok(stack.pop().unwrap_or_default())
+}
+
+fn parse_securecrt(text: &str, report: &mut HostImportReport) -> Result<Vec<ImportedHost>, String> {
+    let root = parse_xml(text).map_err(|e| format!("not a SecureCRT export: {e}"))?;
+    let sessions = find_key(&root, "Sessions").ok_or_else(|| "not a SecureCRT export: no Sessions".to_string())?;
+    let mut hosts = Vec::new();
+    securecrt_folder(sessions, &mut Vec::new(), &mut hosts, report);
+    Ok(hosts)
+}
+
+fn find_key<'a>(node: &'a XmlNode, name: &str) -> Option<&'a XmlNode> {
+    node.children.iter().find_map(|c| {
+        if c.tag == "key" && c.name == name {
+            Some(c)
+        } else {
+            find_key(c, name)
+        }
+    })
+}
+
+/// A key with a `Hostname` value is a session; any other key is a folder.
+fn securecrt_folder(folder: &XmlNode, path: &mut Vec<String>, hosts: &mut Vec<ImportedHost>, report: &mut HostImportReport) {
+    for key in folder.children.iter().filter(|c| c.tag == "key") {
+        let value = |name: &str| key.children.iter().find(|c| c.name == name && c.tag != "key");
+        if value("Hostname").is_none() {
+            path.push(key.name.clone());
+            securecrt_folder(key, path, hosts, report);
+            path.pop();
+            continue;
+        }
+        if key.name == "Default" && path.is_empty() {
+            continue;
+        }
+        let string = |name: &str| value(name).map(|v| v.text.trim().to_string()).unwrap_or_default();
+        let entry = key.name.clone();
+        let hostname = string("Hostname");
+        if hostname.is_empty() {
+            report.skip(&entry, "no host name is saved");
+            continue;
+        }
+        let protocol = string("Protocol Name");
+        if !protocol.is_empty() && !protocol.eq_ignore_ascii_case("ssh2") {
+            report.skip(&entry, format!("{protocol} sessions aren't supported, only SSH2"));
+            continue;
+        }
+        let port = string("[SSH2] Port").parse::<u16>().ok().filter(|p| *p != 0).unwrap_or(22);
+        // "path" or "path::rawkey" (the key's own hash), SecureCRT's own public key format.
+        let identity = string("Identity Filename V2");
+        let identity = identity.split("::").next().unwrap_or_default().trim().to_string();
+        let identity_file = if identity.to_lowercase().ends_with(".pub") {
+            Some(identity.trim_end_matches(".pub").to_string())
+        } else {
+            Some(identity).filter(|i| !i.is_empty())
+        };
+        if !matches!(string("Firewall Name").as_str(), "" | "None") {
+            report.note(&entry, "the firewall/proxy setting wasn't imported");
+        }
+        if value("Port Forward Table V2").is_some_and(|v| !v.children.is_empty()) {
+            report.note(&entry, "port forwardings weren't imported");
+        }
+        if !string("Password V2").is_empty() || !string("Password").is_empty() {
+            report.note(&entry, "the saved password wasn't imported; set it on the host to keep it in the vault");
+        }
+        hosts.push(ImportedHost {
+            label: entry,
+            hostname,
+            port,
+            username: string("Username"),
+            identity_file,
+            group: Some(path.join("/")).filter(|p| !p.is_empty()),
+            environment_tag: None,
+        });
+    }
+}
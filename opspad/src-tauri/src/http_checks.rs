@@ -0,0 +1,211 @@
+//! HTTP(S) service checks: a URL, the status it should answer with, and optional request
+//! headers, attached to a host or standalone.
+//!
+//! They run on the host monitor's schedule (and only while it's enabled), with its timeout and,
+//! unless a check sets its own, its slow threshold. Like host checks, current statuses live in
+//! memory and only transitions are stored (`http_check_events`) and emitted as `http:status`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::db::{HttpCheck, HttpHeader};
+use crate::monitor::{MonitorConfig, Status};
+use crate::{alerts, AppState};
+
+/// Checks requested at once.
+const PARALLEL_CHECKS: usize = 8;
+
+/// Latest result for a check; also the `http:status` payload.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpCheckStatus {
+    pub check_id: String,
+    pub host_id: Option<String>,
+    pub status: Status,
+    /// The response's status code; `None` if there was no response.
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    /// Why it's down.
+    pub detail: Option<String>,
+    /// Epoch seconds.
+    pub checked_at: i64,
+    /// When the current status began (epoch seconds).
+    pub since: i64,
+    /// Failed checks in a row (0 while up or slow).
+    pub failures: u32,
+}
+
+/// Current statuses keyed by check id.
+#[derive(Default)]
+pub struct HttpChecks {
+    statuses: Mutex<HashMap<String, HttpCheckStatus>>,
+}
+
+impl HttpChecks {
+    pub fn list(&self) -> Vec<HttpCheckStatus> {
+        let mut out: Vec<HttpCheckStatus> = self
+            .statuses
+            .lock()
+            .expect("poisoned http checks lock")
+            .values()
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| a.check_id.cmp(&b.check_id));
+        out
+    }
+
+    pub(crate) fn clear(&self) {
+        self.statuses.lock().expect("poisoned http checks lock").clear();
+    }
+}
+
+pub fn validate(name: &str, url: &str, expected_status: Option<u16>, headers: &[HttpHeader]) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("check name must not be empty".to_string());
+    }
+    let lower = url.trim().to_ascii_lowercase();
+    if !lower.starts_with("http://") && !lower.starts_with("https://") {
+        return Err("check URL must start with http:// or https://".to_string());
+    }
+    if expected_status.is_some_and(|s| !(100..=599).contains(&s)) {
+        return Err("expected status must be between 100 and 599".to_string());
+    }
+    for h in headers {
+        let name = h.name.trim();
+        if name.is_empty() || name.contains(|c: char| c == ':' || c.is_whitespace() || c.is_control()) {
+            return Err(format!("invalid header name: {:?}", h.name));
+        }
+        if h.value.contains(['\r', '\n']) {
+            return Err(format!("header {name} must not contain line breaks"));
+        }
+    }
+    Ok(())
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+type CheckResult = (Status, Option<u16>, Option<u64>, Option<String>);
+
+fn check(check: &HttpCheck, timeout: Duration, default_slow_ms: u64) -> CheckResult {
+    // Following redirects would hide the 3xx a check may be expecting.
+    let redirects = if (300..400).contains(&check.expected_status) { 0 } else { 5 };
+    let agent = ureq::AgentBuilder::new().timeout(timeout).redirects(redirects).build();
+    let mut req = agent.get(&check.url);
+    for h in &check.headers {
+        req = req.set(h.name.trim(), &h.value);
+    }
+    let started = Instant::now();
+    let code = match req.call() {
+        Ok(resp) => resp.status(),
+        Err(ureq::Error::Status(code, _)) => code,
+        Err(ureq::Error::Transport(e)) => return (Status::Down, None, None, Some(e.kind().to_string())),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    if code != check.expected_status {
+        let detail = format!("HTTP {code}, expected {}", check.expected_status);
+        return (Status::Down, Some(code), Some(latency_ms), Some(detail));
+    }
+    let status = if latency_ms > check.slow_ms.unwrap_or(default_slow_ms) {
+        Status::Slow
+    } else {
+        Status::Up
+    };
+    (status, Some(code), Some(latency_ms), None)
+}
+
+/// Fold a result into the check's status; stores and emits it if the status changed.
+fn update(app: &AppHandle, state: &AppState, check: &HttpCheck, result: CheckResult) -> HttpCheckStatus {
+    let (status, http_status, latency_ms, detail) = result;
+    let now = now_epoch_secs();
+    let (current, changed) = {
+        let mut statuses = state.http_checks.statuses.lock().expect("poisoned http checks lock");
+        let previous = statuses.get(&check.id);
+        let changed = previous.is_none_or(|p| p.status != status);
+        let current = HttpCheckStatus {
+            check_id: check.id.clone(),
+            host_id: check.host_id.clone(),
+            status,
+            http_status,
+            latency_ms,
+            detail,
+            checked_at: now,
+            since: if changed { now } else { previous.map_or(now, |p| p.since) },
+            failures: match status {
+                Status::Down => previous.map_or(0, |p| p.failures) + 1,
+                _ => 0,
+            },
+        };
+        statuses.insert(check.id.clone(), current.clone());
+        (current, changed)
+    };
+    if changed {
+        if let Err(e) = state.db.http_check_record(
+            &check.id,
+            status.as_str(),
+            current.http_status,
+            current.latency_ms,
+            current.detail.as_deref(),
+        ) {
+            log::warn!("http check status not recorded: {e}");
+        }
+        let _ = app.emit("http:status", current.clone());
+    }
+    alerts::on_http_status(app, state, check, &current);
+    current
+}
+
+/// Run one check now, regardless of the schedule (or whether it's enabled).
+pub fn check_now(app: &AppHandle, state: &AppState, check_id: &str) -> Result<HttpCheckStatus, String> {
+    let http_check = state
+        .db
+        .http_checks_get(check_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "check not found".to_string())?;
+    let config = MonitorConfig::load(&state.db);
+    let result = check(&http_check, config.timeout(), config.slow_ms);
+    Ok(update(app, state, &http_check, result))
+}
+
+/// Called from the monitor's round.
+pub(crate) fn run_round(app: &AppHandle, state: &AppState, config: &MonitorConfig) {
+    let checks: Vec<HttpCheck> = match state.db.http_checks_list() {
+        Ok(checks) => checks.into_iter().filter(|c| c.enabled).collect(),
+        Err(e) => {
+            log::warn!("monitor: couldn't list http checks: {e}");
+            return;
+        }
+    };
+    // Forget checks that were deleted or disabled.
+    state
+        .http_checks
+        .statuses
+        .lock()
+        .expect("poisoned http checks lock")
+        .retain(|id, _| checks.iter().any(|c| &c.id == id));
+
+    let timeout = config.timeout();
+    for chunk in checks.chunks(PARALLEL_CHECKS) {
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|c| scope.spawn(move || check(c, timeout, config.slow_ms)))
+                .collect();
+            handles.into_iter().map(|h| h.join()).collect()
+        });
+        for (c, result) in chunk.iter().zip(results) {
+            if let Ok(result) = result {
+                update(app, state, c, result);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Talking to outside services: posting to chat, reading on-call incidents, asking a model for
+//! command suggestions. Endpoints and tokens live in the vault, never in settings, and everything
+//! posted goes through the redaction rules first.
+
+use std::time::Duration;
+
+pub mod oncall;
+pub mod slack;
+pub mod suggest;
+
+const POST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// POST a JSON body; errors name the HTTP status and the service's reply, not the (secret) URL.
+fn post_json(url: &str, body: &serde_json::Value) -> Result<(), String> {
+    let resp = ureq::AgentBuilder::new()
+        .timeout(POST_TIMEOUT)
+        .build()
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string());
+    match resp {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, resp)) => {
+            let reply = resp.into_string().unwrap_or_default();
+            Err(format!("HTTP {code}: {}", reply.trim()))
+        }
+        Err(ureq::Error::Transport(e)) => Err(e.kind().to_string()),
+    }
+}
@@ -0,0 +1,225 @@
+//! Read-only view of the incidents (PagerDuty) or alerts (Opsgenie) currently assigned to you,
+//! so work started in OpsPad can point at the real ticket.
+//!
+//! The provider is chosen in settings; its API key is kept in the vault under [`API_KEY`]. For
+//! PagerDuty the key must be a user token (it defines who "me" is); Opsgenie keys are account
+//! wide, so the user's email selects their alerts.
+
+use std::io::Read;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::arch::vault::VaultProvider;
+use crate::db::Db;
+
+const SETTINGS_KEY: &str = "oncall";
+pub const API_KEY: &str = "opspad.oncall-api-key";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_RESPONSE_BYTES: u64 = 4 * 1024 * 1024;
+const LIMIT: usize = 50;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Provider {
+    #[default]
+    None,
+    PagerDuty,
+    Opsgenie,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OncallConfig {
+    #[serde(default)]
+    pub provider: Provider,
+    /// Opsgenie: whose alerts to list (matched against owner and responders).
+    #[serde(default)]
+    pub email: Option<String>,
+    /// Opsgenie: use the EU API endpoint.
+    #[serde(default)]
+    pub eu: bool,
+}
+
+impl OncallConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// An incident or alert as the provider reports it, trimmed to what a link needs.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OncallIncident {
+    pub provider: Provider,
+    pub id: String,
+    /// Short human number (PagerDuty incident number, Opsgenie tiny id).
+    pub number: Option<String>,
+    pub title: String,
+    /// As reported, e.g. "triggered", "acknowledged", "open".
+    pub status: String,
+    /// PagerDuty urgency or Opsgenie priority.
+    pub urgency: Option<String>,
+    pub service: Option<String>,
+    /// RFC 3339, as reported.
+    pub created_at: Option<String>,
+    /// Link to the incident in the provider's web UI, when the API gives one.
+    pub url: Option<String>,
+}
+
+pub fn set_api_key(vault: &dyn VaultProvider, key: &str) -> Result<(), String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return vault.delete_secret(API_KEY).map_err(|e| e.to_string());
+    }
+    vault.set_secret(API_KEY, key.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn api_key_configured(vault: &dyn VaultProvider) -> Result<bool, String> {
+    Ok(vault.get_secret(API_KEY).map_err(|e| e.to_string())?.is_some())
+}
+
+fn get(url: &str, auth: &str, accept: &str, query: &[(&str, &str)]) -> Result<Value, String> {
+    let mut req = ureq::AgentBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .get(url)
+        .set("Authorization", auth)
+        .set("Accept", accept);
+    for (k, v) in query {
+        req = req.query(k, v);
+    }
+    let resp = match req.call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(401 | 403, _)) => return Err("the API key was rejected".to_string()),
+        Err(ureq::Error::Status(code, _)) => return Err(format!("HTTP {code}")),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut text = String::new();
+    resp.into_reader()
+        .take(MAX_RESPONSE_BYTES)
+        .read_to_string(&mut text)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("unexpected response: {e}"))
+}
+
+fn str_at(v: &Value, pointer: &str) -> Option<String> {
+    match v.pointer(pointer)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn pagerduty(key: &str) -> Result<Vec<OncallIncident>, String> {
+    let auth = format!("Token token={key}");
+    let accept = "application/vnd.pagerduty+json;version=2";
+    let me = get("https://api.pagerduty.com/users/me", &auth, accept, &[])?;
+    let user_id = str_at(&me, "/user/id")
+        .ok_or_else(|| "PagerDuty needs a user API token to know whose incidents to list".to_string())?;
+    let limit = LIMIT.to_string();
+    let body = get(
+        "https://api.pagerduty.com/incidents",
+        &auth,
+        accept,
+        &[
+            ("user_ids[]", &user_id),
+            ("statuses[]", "triggered"),
+            ("statuses[]", "acknowledged"),
+            ("sort_by", "created_at:desc"),
+            ("limit", &limit),
+        ],
+    )?;
+    let incidents = body.get("incidents").and_then(Value::as_array).cloned().unwrap_or_default();
+    Ok(incidents
+        .iter()
+        .filter_map(|i| {
+            Some(OncallIncident {
+                provider: Provider::PagerDuty,
+                id: str_at(i, "/id")?,
+                number: str_at(i, "/incident_number"),
+                title: str_at(i, "/title").unwrap_or_default(),
+                status: str_at(i, "/status").unwrap_or_default(),
+                urgency: str_at(i, "/urgency"),
+                service: str_at(i, "/service/summary"),
+                created_at: str_at(i, "/created_at"),
+                url: str_at(i, "/html_url"),
+            })
+        })
+        .collect())
+}
+
+fn opsgenie(key: &str, config: &OncallConfig) -> Result<Vec<OncallIncident>, String> {
+    let email = config
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .ok_or_else(|| "set your Opsgenie email to list your alerts".to_string())?;
+    let base = if config.eu {
+        "https://api.eu.opsgenie.com"
+    } else {
+        "https://api.opsgenie.com"
+    };
+    let query = format!("status: open AND (owner: \"{email}\" OR responders: \"{email}\")");
+    let limit = LIMIT.to_string();
+    let body = get(
+        &format!("{base}/v2/alerts"),
+        &format!("GenieKey {key}"),
+        "application/json",
+        &[("query", &query), ("sort", "createdAt"), ("order", "desc"), ("limit", &limit)],
+    )?;
+    let alerts = body.get("data").and_then(Value::as_array).cloned().unwrap_or_default();
+    Ok(alerts
+        .iter()
+        .filter_map(|a| {
+            // Acknowledged alerts still report status "open".
+            let status = match a.pointer("/acknowledged").and_then(Value::as_bool) {
+                Some(true) => "acknowledged".to_string(),
+                _ => str_at(a, "/status").unwrap_or_default(),
+            };
+            Some(OncallIncident {
+                provider: Provider::Opsgenie,
+                id: str_at(a, "/id")?,
+                number: str_at(a, "/tinyId"),
+                title: str_at(a, "/message").unwrap_or_default(),
+                status,
+                urgency: str_at(a, "/priority"),
+                service: str_at(a, "/source"),
+                created_at: str_at(a, "/createdAt"),
+                // The alert API doesn't return the account's web URL.
+                url: None,
+            })
+        })
+        .collect())
+}
+
+/// Open incidents/alerts assigned to the configured user, newest first. Empty when no
+/// provider is configured.
+pub fn incidents(db: &Db, vault: &dyn VaultProvider) -> Result<Vec<OncallIncident>, String> {
+    let config = OncallConfig::load(db);
+    if config.provider == Provider::None {
+        return Ok(Vec::new());
+    }
+    let key = vault
+        .get_secret(API_KEY)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "no on-call API key is configured".to_string())?;
+    let key = String::from_utf8(key).map_err(|_| "stored on-call API key is malformed".to_string())?;
+    match config.provider {
+        Provider::PagerDuty => pagerduty(&key).map_err(|e| format!("PagerDuty: {e}")),
+        Provider::Opsgenie => opsgenie(&key, &config).map_err(|e| format!("Opsgenie: {e}")),
+        Provider::None => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,175 @@
+//! Sharing to a Slack channel through an incoming webhook: command output, a section of a
+//! runbook, or an incident summary.
+//!
+//! The webhook URL is a credential (anyone holding it can post to the channel), so it's kept in
+//! the vault under [`WEBHOOK_KEY`].
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::arch::vault::VaultProvider;
+use crate::db::Db;
+use crate::redact::Redactor;
+use crate::runbooks;
+
+pub const WEBHOOK_KEY: &str = "opspad.slack-webhook-url";
+
+const WEBHOOK_PREFIX: &str = "https://hooks.slack.com/";
+/// Slack truncates message text past 40,000 characters; stay well under it.
+const MAX_TEXT_CHARS: usize = 35_000;
+
+/// What to share; each surface that offers "Share to Slack" sends one of these.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SlackShare {
+    /// Command or job output, posted as a code block.
+    Output { title: String, text: String },
+    /// The section of a runbook under `heading` (to the next heading of the same or higher
+    /// level), rendered with `values` like the runbook view does.
+    RunbookSection {
+        runbook_id: String,
+        heading: String,
+        #[serde(default)]
+        values: HashMap<String, String>,
+    },
+    /// A free-form incident summary, optionally followed by the open alerts.
+    Incident {
+        title: String,
+        summary: String,
+        #[serde(default)]
+        include_alerts: bool,
+    },
+}
+
+pub fn set_webhook(vault: &dyn VaultProvider, url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return vault.delete_secret(WEBHOOK_KEY).map_err(|e| e.to_string());
+    }
+    if !url.starts_with(WEBHOOK_PREFIX) {
+        return Err(format!("Slack webhook URLs start with {WEBHOOK_PREFIX}"));
+    }
+    vault.set_secret(WEBHOOK_KEY, url.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn webhook_configured(vault: &dyn VaultProvider) -> Result<bool, String> {
+    Ok(vault.get_secret(WEBHOOK_KEY).map_err(|e| e.to_string())?.is_some())
+}
+
+/// Slack treats `&`, `<` and `>` as control characters in message text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    rest.starts_with(' ').then(|| (level, rest.trim()))
+}
+
+/// The lines under the heading named `name`, including the heading itself.
+fn section(markdown: &str, name: &str) -> Option<String> {
+    let mut lines = markdown.lines();
+    let level = lines
+        .by_ref()
+        .find_map(|l| heading(l).filter(|(_, text)| text.eq_ignore_ascii_case(name.trim())))?
+        .0;
+    let body: Vec<&str> = lines.take_while(|l| heading(l).is_none_or(|(lvl, _)| lvl > level)).collect();
+    Some(format!("{} {}\n{}", "#".repeat(level), name.trim(), body.join("\n")))
+}
+
+/// Markdown to Slack's mrkdwn: headings and bold become `*bold*`, links become `<url|text>`.
+/// Code blocks and inline code pass through unchanged.
+fn mrkdwn(markdown: &str) -> String {
+    let bold = Regex::new(r"\*\*(.+?)\*\*").expect("valid regex");
+    let link = Regex::new(r"\[([^\]]+)\]\(([^)\s]+)\)").expect("valid regex");
+    let mut in_code = false;
+    let mut out = Vec::new();
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_code {
+            out.push(escape(line));
+            continue;
+        }
+        if let Some((_, text)) = heading(line) {
+            out.push(format!("*{}*", escape(text)));
+            continue;
+        }
+        let line = escape(line);
+        let line = bold.replace_all(&line, "*$1*");
+        out.push(link.replace_all(&line, "<$2|$1>").into_owned());
+    }
+    out.join("\n")
+}
+
+fn truncate(text: String) -> String {
+    if text.chars().count() <= MAX_TEXT_CHARS {
+        return text;
+    }
+    let mut cut: String = text.chars().take(MAX_TEXT_CHARS).collect();
+    cut.push_str("\n… (truncated)");
+    cut
+}
+
+fn message(db: &Db, share: &SlackShare) -> Result<String, String> {
+    match share {
+        SlackShare::Output { title, text } => {
+            // A literal fence in the output would end the code block early.
+            let text = text.trim_end().replace("```", "'''");
+            Ok(format!("*{}*\n```\n{}\n```", escape(title), escape(&text)))
+        }
+        SlackShare::RunbookSection {
+            runbook_id,
+            heading,
+            values,
+        } => {
+            let runbook = db
+                .runbooks_get(runbook_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "runbook not found".to_string())?;
+            let rendered = runbooks::render(&runbook, values)?;
+            let section = section(&rendered.markdown, heading)
+                .ok_or_else(|| format!("runbook has no section named \"{heading}\""))?;
+            Ok(format!("_{}_\n{}", escape(&runbook.title), mrkdwn(&section)))
+        }
+        SlackShare::Incident {
+            title,
+            summary,
+            include_alerts,
+        } => {
+            let mut text = format!(":rotating_light: *{}*\n{}", escape(title), mrkdwn(summary));
+            if *include_alerts {
+                let open = db.alerts_list(Some("open"), 50).map_err(|e| e.to_string())?;
+                if !open.is_empty() {
+                    text.push_str("\n\n*Open alerts*");
+                    for alert in open {
+                        text.push_str(&format!("\n• {}: {}", escape(&alert.rule_name), escape(&alert.message)));
+                    }
+                }
+            }
+            Ok(text)
+        }
+    }
+}
+
+/// Post `share` to the configured webhook, after running it through the redaction rules.
+pub fn share(db: &Db, vault: &dyn VaultProvider, share: &SlackShare) -> Result<(), String> {
+    let url = vault
+        .get_secret(WEBHOOK_KEY)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "no Slack webhook is configured".to_string())?;
+    let url = String::from_utf8(url).map_err(|_| "stored Slack webhook URL is malformed".to_string())?;
+    let redactor = Redactor::new(&db.redaction_rules_list().map_err(|e| e.to_string())?);
+    let text = truncate(redactor.redact(&message(db, share)?));
+    super::post_json(&url, &serde_json::json!({ "text": text }))
+        .map_err(|e| format!("posting to Slack failed: {e}"))
+}
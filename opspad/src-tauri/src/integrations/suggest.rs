@@ -0,0 +1,268 @@
+//! Command suggestions from a language model: describe what you want, get candidate commands.
+//!
+//! The provider sits behind [`SuggestionProvider`] so the API key never leaves the backend: it's
+//! kept in the vault under [`API_KEY`]. Any OpenAI-compatible chat completions endpoint works;
+//! "local" is the same protocol against a model served on this machine (Ollama, llama.cpp) and
+//! needs no key. The prompt goes through the redaction rules before it's sent.
+//!
+//! Suggestions are only text: they come back with the same lint findings and policy decision a
+//! dock command preview shows, and running one goes through the usual CommandDock path (lint,
+//! confirmation, environment policy).
+
+use std::io::Read;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::arch::vault::VaultProvider;
+use crate::db::Db;
+use crate::policy::{self, CommandPreview};
+use crate::redact::Redactor;
+
+const SETTINGS_KEY: &str = "command_suggest";
+pub const API_KEY: &str = "opspad.suggest-api-key";
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const LOCAL_BASE_URL: &str = "http://127.0.0.1:11434/v1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+const MAX_PROMPT_CHARS: usize = 2000;
+const DEFAULT_MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Provider {
+    #[default]
+    Disabled,
+    /// OpenAI or any service speaking its chat completions API.
+    OpenAi,
+    /// An OpenAI-compatible server on this machine.
+    Local,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestConfig {
+    #[serde(default)]
+    pub provider: Provider,
+    /// Overrides the provider's default endpoint (up to and including `/v1`).
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub max_suggestions: Option<usize>,
+}
+
+impl SuggestConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// What the model is told about where the command will run. Nothing from the session's output.
+#[derive(Clone, Debug)]
+pub struct SuggestContext {
+    /// "a local shell on linux", "an SSH session to web-1".
+    pub target: String,
+    pub environment_tag: String,
+}
+
+impl SuggestContext {
+    /// Context for a dock scope ("local", "ssh:<host id>").
+    pub fn for_scope(db: &Db, scope: &str) -> Result<Self, String> {
+        let ctx = db.scope_context(scope).map_err(|e| e.to_string())?;
+        let host = match ctx.host_id.as_deref() {
+            Some(id) => db.hosts_get(id).map_err(|e| e.to_string())?,
+            None => None,
+        };
+        let target = match host {
+            Some(host) => format!("an SSH session to {}", host.label),
+            None if scope == "local" => format!("a local shell on {}", std::env::consts::OS),
+            None => "a shell session".to_string(),
+        };
+        Ok(Self {
+            target,
+            environment_tag: ctx.environment_tag,
+        })
+    }
+}
+
+/// A candidate as the model gave it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Suggestion {
+    pub command: String,
+    #[serde(default)]
+    pub explanation: Option<String>,
+}
+
+/// A candidate with its lint findings and what the environment's policy would ask for.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSuggestion {
+    #[serde(flatten)]
+    pub preview: CommandPreview,
+    pub explanation: Option<String>,
+}
+
+pub trait SuggestionProvider {
+    fn suggest(&self, prompt: &str, context: &SuggestContext, max: usize) -> Result<Vec<Suggestion>, String>;
+}
+
+struct OpenAiCompatible {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+const SYSTEM_PROMPT: &str = "You suggest shell commands for an operations engineer. Reply with only a JSON array of \
+objects with \"command\" (a single command line) and \"explanation\" (one short sentence). Prefer read-only, \
+non-destructive commands when they answer the request.";
+
+impl SuggestionProvider for OpenAiCompatible {
+    fn suggest(&self, prompt: &str, context: &SuggestContext, max: usize) -> Result<Vec<Suggestion>, String> {
+        let user = format!(
+            "Target: {}. Environment: {}. Give at most {max} commands.\n\nRequest: {prompt}",
+            context.target, context.environment_tag
+        );
+        let body = json!({
+            "model": self.model,
+            "temperature": 0.2,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": user },
+            ],
+        });
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        let mut req = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .post(&url)
+            .set("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            req = req.set("Authorization", &format!("Bearer {key}"));
+        }
+        let resp = match req.send_string(&body.to_string()) {
+            Ok(resp) => resp,
+            Err(ureq::Error::Status(401 | 403, _)) => return Err("the API key was rejected".to_string()),
+            Err(ureq::Error::Status(code, resp)) => {
+                let reply = resp.into_string().unwrap_or_default();
+                return Err(format!("HTTP {code}: {}", reply.trim()));
+            }
+            Err(ureq::Error::Transport(e)) => return Err(e.kind().to_string()),
+        };
+        let mut text = String::new();
+        resp.into_reader()
+            .take(MAX_RESPONSE_BYTES)
+            .read_to_string(&mut text)
+            .map_err(|e| e.to_string())?;
+        let reply: Value = serde_json::from_str(&text).map_err(|e| format!("unexpected response: {e}"))?;
+        let content = reply
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "the response had no message".to_string())?;
+        Ok(parse_suggestions(content))
+    }
+}
+
+/// The JSON array the model was asked for, or failing that, one command per non-empty line
+/// (models wrap answers in code fences and prose despite being asked not to).
+fn parse_suggestions(content: &str) -> Vec<Suggestion> {
+    if let (Some(start), Some(end)) = (content.find('['), content.rfind(']')) {
+        if start < end {
+            if let Ok(list) = serde_json::from_str::<Vec<Suggestion>>(&content[start..=end]) {
+                return list;
+            }
+        }
+    }
+    content
+        .lines()
+        .map(|l| l.trim().trim_start_matches("$ "))
+        .filter(|l| !l.is_empty() && !l.starts_with("```"))
+        .map(|l| Suggestion {
+            command: l.to_string(),
+            explanation: None,
+        })
+        .collect()
+}
+
+pub fn set_api_key(vault: &dyn VaultProvider, key: &str) -> Result<(), String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return vault.delete_secret(API_KEY).map_err(|e| e.to_string());
+    }
+    vault.set_secret(API_KEY, key.as_bytes()).map_err(|e| e.to_string())
+}
+
+pub fn api_key_configured(vault: &dyn VaultProvider) -> Result<bool, String> {
+    Ok(vault.get_secret(API_KEY).map_err(|e| e.to_string())?.is_some())
+}
+
+/// The configured provider; `None` when suggestions are turned off.
+pub fn provider(config: &SuggestConfig, vault: &dyn VaultProvider) -> Result<Option<Box<dyn SuggestionProvider>>, String> {
+    let api_key = || -> Result<Option<String>, String> {
+        let key = vault.get_secret(API_KEY).map_err(|e| e.to_string())?;
+        key.map(|k| String::from_utf8(k).map(|k| k.trim().to_string()).map_err(|_| "the API key isn't text".to_string()))
+            .transpose()
+    };
+    let model = config.model.clone().map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    let base_url = config.base_url.clone().map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    Ok(match config.provider {
+        Provider::Disabled => None,
+        Provider::OpenAi => Some(Box::new(OpenAiCompatible {
+            base_url: base_url.unwrap_or_else(|| OPENAI_BASE_URL.to_string()),
+            model: model.unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            api_key: Some(api_key()?.ok_or_else(|| "no API key is stored for command suggestions".to_string())?),
+        })),
+        Provider::Local => Some(Box::new(OpenAiCompatible {
+            base_url: base_url.unwrap_or_else(|| LOCAL_BASE_URL.to_string()),
+            model: model.ok_or_else(|| "choose the local model to use".to_string())?,
+            api_key: api_key()?,
+        })),
+    })
+}
+
+/// Candidate commands for `prompt`, each assessed against the policy of the context's environment.
+pub fn suggest(
+    db: &Db,
+    vault: &dyn VaultProvider,
+    prompt: &str,
+    context: &SuggestContext,
+) -> Result<Vec<CommandSuggestion>, String> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Err("describe the command you need".to_string());
+    }
+    let config = SuggestConfig::load(db);
+    let provider = provider(&config, vault)?.ok_or_else(|| "command suggestions are turned off".to_string())?;
+    let redactor = Redactor::new(&db.redaction_rules_list().map_err(|e| e.to_string())?);
+    let prompt: String = redactor.redact(prompt).chars().take(MAX_PROMPT_CHARS).collect();
+    let max = config.max_suggestions.unwrap_or(DEFAULT_MAX_SUGGESTIONS).clamp(1, 10);
+
+    provider
+        .suggest(&prompt, context, max)?
+        .into_iter()
+        .map(|s| Suggestion {
+            command: s.command.trim().to_string(),
+            ..s
+        })
+        .filter(|s| !s.command.is_empty() && !s.command.contains('\n'))
+        .take(max)
+        .map(|s| {
+            Ok(CommandSuggestion {
+                preview: policy::assess(db, &context.environment_tag, s.command, false)?,
+                explanation: s.explanation.filter(|e| !e.trim().is_empty()),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,158 @@
+//! Azure VMs through the Resource Manager API. A VM only references its network interfaces, and
+//! those their public IP resources, so the three lists are fetched and joined by resource id.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use super::{get_json, run_cli, DiscoveredHost, InventoryProvider};
+
+const ARM: &str = "https://management.azure.com";
+const COMPUTE_API_VERSION: &str = "2024-07-01";
+const NETWORK_API_VERSION: &str = "2024-05-01";
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(super) struct ServicePrincipal {
+    pub tenant_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+pub(super) struct AzureVms {
+    pub subscription_id: String,
+    pub resource_group: Option<String>,
+    /// `None` uses the Azure CLI's signed-in account.
+    pub credentials: Option<ServicePrincipal>,
+}
+
+impl AzureVms {
+    fn token(&self) -> Result<String, String> {
+        let token = match &self.credentials {
+            None => {
+                let args: Vec<String> = [
+                    "account",
+                    "get-access-token",
+                    "--resource",
+                    "https://management.azure.com/",
+                    "--subscription",
+                    &self.subscription_id,
+                    "--output",
+                    "json",
+                ]
+                .iter()
+                .map(|a| a.to_string())
+                .collect();
+                let reply: Value = serde_json::from_str(&run_cli("az", &args)?).map_err(|e| e.to_string())?;
+                reply.get("accessToken").and_then(Value::as_str).map(str::to_string)
+            }
+            Some(sp) => {
+                let url = format!("https://login.microsoftonline.com/{}/oauth2/v2.0/token", sp.tenant_id);
+                let resp = ureq::AgentBuilder::new().timeout(TOKEN_TIMEOUT).build().post(&url).send_form(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", &sp.client_id),
+                    ("client_secret", &sp.client_secret),
+                    ("scope", "https://management.azure.com/.default"),
+                ]);
+                let reply: Value = match resp {
+                    Ok(resp) => serde_json::from_str(&resp.into_string().map_err(|e| e.to_string())?)
+                        .map_err(|e| format!("unexpected response: {e}"))?,
+                    Err(ureq::Error::Status(code, resp)) => {
+                        let reply: Value = serde_json::from_str(&resp.into_string().unwrap_or_default()).unwrap_or_default();
+                        let message = reply.get("error_description").and_then(Value::as_str).unwrap_or("no details");
+                        return Err(format!("sign-in failed (HTTP {code}): {}", message.lines().next().unwrap_or("")));
+                    }
+                    Err(ureq::Error::Transport(e)) => return Err(e.kind().to_string()),
+                };
+                reply.get("access_token").and_then(Value::as_str).map(str::to_string)
+            }
+        };
+        token.ok_or_else(|| "no access token was returned".to_string())
+    }
+
+    fn scope(&self) -> String {
+        match &self.resource_group {
+            Some(group) => format!("{ARM}/subscriptions/{}/resourceGroups/{group}", self.subscription_id),
+            None => format!("{ARM}/subscriptions/{}", self.subscription_id),
+        }
+    }
+
+    /// Every item of a list, following `nextLink`.
+    fn list_all(&self, token: &str, provider: &str, api_version: &str) -> Result<Vec<Value>, String> {
+        let mut items = Vec::new();
+        let first = format!("{}/providers/{provider}", self.scope());
+        let mut reply = get_json(&first, token, &[("api-version", api_version)])?;
+        loop {
+            if let Some(values) = reply.get("value").and_then(Value::as_array) {
+                items.extend(values.iter().cloned());
+            }
+            // The link already carries the api-version and continuation token.
+            match reply.get("nextLink").and_then(Value::as_str) {
+                Some(next) if !next.is_empty() => reply = get_json(next, token, &[])?,
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+fn str_at(v: &Value, ptr: &str) -> Option<String> {
+    v.pointer(ptr).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Resource ids differ in case between references and the resources themselves.
+fn id_of(v: &Value) -> Option<String> {
+    str_at(v, "/id").map(|id| id.to_lowercase())
+}
+
+/// The primary entry of a list of references/configurations (or the first one).
+fn primary(list: Option<&Value>) -> Option<&Value> {
+    let list = list?.as_array()?;
+    list.iter()
+        .find(|v| v.pointer("/properties/primary").and_then(Value::as_bool) == Some(true))
+        .or_else(|| list.first())
+}
+
+impl InventoryProvider for AzureVms {
+    /// Tags can't be filtered server-side for VMs; the shared filter does it.
+    fn list(&self, _filter: &BTreeMap<String, String>) -> Result<Vec<DiscoveredHost>, String> {
+        let token = self.token()?;
+        let vms = self.list_all(&token, "Microsoft.Compute/virtualMachines", COMPUTE_API_VERSION)?;
+        let nics: HashMap<String, Value> = self
+            .list_all(&token, "Microsoft.Network/networkInterfaces", NETWORK_API_VERSION)?
+            .into_iter()
+            .filter_map(|n| Some((id_of(&n)?, n)))
+            .collect();
+        let public_ips: HashMap<String, Value> = self
+            .list_all(&token, "Microsoft.Network/publicIPAddresses", NETWORK_API_VERSION)?
+            .into_iter()
+            .filter_map(|p| Some((id_of(&p)?, p)))
+            .collect();
+
+        Ok(vms
+            .iter()
+            .filter_map(|vm| {
+                let nic = primary(vm.pointer("/properties/networkProfile/networkInterfaces"))
+                    .and_then(id_of)
+                    .and_then(|id| nics.get(&id));
+                let ip_config = nic.and_then(|n| primary(n.pointer("/properties/ipConfigurations")));
+                let public_ip = ip_config
+                    .and_then(|c| str_at(c, "/properties/publicIPAddress/id"))
+                    .and_then(|id| public_ips.get(&id.to_lowercase()));
+                Some(DiscoveredHost {
+                    name: str_at(vm, "/name")?,
+                    location: str_at(vm, "/location").unwrap_or_default(),
+                    private_ip: ip_config.and_then(|c| str_at(c, "/properties/privateIPAddress")),
+                    public_ip: public_ip.and_then(|p| str_at(p, "/properties/ipAddress")),
+                    dns_name: public_ip.and_then(|p| str_at(p, "/properties/dnsSettings/fqdn")),
+                    username: str_at(vm, "/properties/osProfile/adminUsername"),
+                    labels: vm
+                        .get("tags")
+                        .and_then(Value::as_object)
+                        .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,94 @@
+//! Compute Engine instances, through the `aggregated/instances` list (every zone in one listing).
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::{get_json, run_cli, DiscoveredHost, InventoryProvider};
+
+const API: &str = "https://compute.googleapis.com/compute/v1";
+
+pub(super) struct ComputeEngine {
+    pub project: String,
+    pub account: Option<String>,
+    pub impersonate_service_account: Option<String>,
+}
+
+impl ComputeEngine {
+    fn token(&self) -> Result<String, String> {
+        let mut args = vec!["auth".to_string(), "print-access-token".to_string()];
+        if let Some(account) = &self.account {
+            args.push(account.clone());
+        }
+        if let Some(sa) = &self.impersonate_service_account {
+            args.push(format!("--impersonate-service-account={sa}"));
+        }
+        let token = run_cli("gcloud", &args)?.trim().to_string();
+        if token.is_empty() {
+            return Err("gcloud returned no access token; run `gcloud auth login`".to_string());
+        }
+        Ok(token)
+    }
+}
+
+/// The list API's filter syntax: `(labels.env = "prod") AND (labels.team = "ops")`.
+fn api_filter(filter: &BTreeMap<String, String>) -> String {
+    filter
+        .iter()
+        .map(|(k, v)| format!("(labels.{k} = \"{}\")", v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn last_segment(url: &str) -> String {
+    url.rsplit('/').next().unwrap_or(url).to_string()
+}
+
+fn instance(item: &Value) -> Option<DiscoveredHost> {
+    let nic = item.pointer("/networkInterfaces/0");
+    let str_at = |v: Option<&Value>, ptr: &str| v.and_then(|v| v.pointer(ptr)).and_then(Value::as_str).map(str::to_string);
+    Some(DiscoveredHost {
+        name: item.get("name")?.as_str()?.to_string(),
+        location: item.get("zone").and_then(Value::as_str).map(last_segment).unwrap_or_default(),
+        private_ip: str_at(nic, "/networkIP"),
+        public_ip: str_at(nic, "/accessConfigs/0/natIP"),
+        dns_name: None,
+        username: None,
+        labels: item
+            .get("labels")
+            .and_then(Value::as_object)
+            .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+            .unwrap_or_default(),
+    })
+}
+
+impl InventoryProvider for ComputeEngine {
+    fn list(&self, filter: &BTreeMap<String, String>) -> Result<Vec<DiscoveredHost>, String> {
+        let token = self.token()?;
+        let url = format!("{API}/projects/{}/aggregated/instances", self.project);
+        let filter = api_filter(filter);
+        let mut hosts = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![("maxResults", "500")];
+            if !filter.is_empty() {
+                query.push(("filter", filter.as_str()));
+            }
+            if let Some(page) = &page_token {
+                query.push(("pageToken", page.as_str()));
+            }
+            let reply = get_json(&url, &token, &query)?;
+            if let Some(zones) = reply.get("items").and_then(Value::as_object) {
+                for zone in zones.values() {
+                    let instances = zone.get("instances").and_then(Value::as_array);
+                    hosts.extend(instances.into_iter().flatten().filter_map(instance));
+                }
+            }
+            page_token = reply.get("nextPageToken").and_then(Value::as_str).map(str::to_string);
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(hosts)
+    }
+}
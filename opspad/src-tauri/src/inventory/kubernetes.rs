@@ -0,0 +1,64 @@
+//! A Kubernetes cluster's nodes, as `kubectl get nodes -o json` lists them for one context.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::{run_cli, DiscoveredHost, InventoryProvider};
+
+pub(super) struct KubernetesNodes {
+    pub context: String,
+    pub kubeconfig: Option<String>,
+}
+
+/// The first address of `kind` in a node's `status.addresses`.
+fn address(node: &Value, kind: &str) -> Option<String> {
+    node.pointer("/status/addresses")?
+        .as_array()?
+        .iter()
+        .find(|a| a.get("type").and_then(Value::as_str) == Some(kind))
+        .and_then(|a| a.get("address")?.as_str())
+        .map(str::to_string)
+}
+
+fn node(item: &Value) -> Option<DiscoveredHost> {
+    let labels: BTreeMap<String, String> = item
+        .pointer("/metadata/labels")
+        .and_then(Value::as_object)
+        .map(|m| m.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+        .unwrap_or_default();
+    let location = ["topology.kubernetes.io/zone", "topology.kubernetes.io/region"]
+        .iter()
+        .find_map(|l| labels.get(*l).cloned())
+        .unwrap_or_default();
+    Some(DiscoveredHost {
+        name: item.pointer("/metadata/name")?.as_str()?.to_string(),
+        location,
+        private_ip: address(item, "InternalIP"),
+        public_ip: address(item, "ExternalIP"),
+        dns_name: address(item, "ExternalDNS").or_else(|| address(item, "InternalDNS")),
+        username: None,
+        labels,
+    })
+}
+
+impl InventoryProvider for KubernetesNodes {
+    fn list(&self, filter: &BTreeMap<String, String>) -> Result<Vec<DiscoveredHost>, String> {
+        let mut args = vec![
+            "get".to_string(),
+            "nodes".to_string(),
+            "--output=json".to_string(),
+            format!("--context={}", self.context),
+        ];
+        if let Some(path) = &self.kubeconfig {
+            args.push(format!("--kubeconfig={path}"));
+        }
+        if !filter.is_empty() {
+            let selector: Vec<String> = filter.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            args.push(format!("--selector={}", selector.join(",")));
+        }
+        let reply: Value = serde_json::from_str(&run_cli("kubectl", &args)?).map_err(|e| format!("unexpected kubectl output: {e}"))?;
+        let items = reply.get("items").and_then(Value::as_array);
+        Ok(items.into_iter().flatten().filter_map(node).collect())
+    }
+}
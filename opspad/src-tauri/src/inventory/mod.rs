@@ -0,0 +1,478 @@
+//! Cloud inventory: hosts discovered from a cloud provider's VM list (or a Kubernetes cluster's
+//! nodes) instead of typed in.
+//!
+//! Each source (settings key `inventory`) names a provider and how to authenticate to it, which
+//! instances to take (labels on Compute Engine and Kubernetes, tags on Azure, all called labels
+//! here), and how an
+//! instance becomes a host: which label holds the environment, the group, the login user, and
+//! which address to connect to. Providers sit behind [`InventoryProvider`]; they only list
+//! instances, and the mapping and storing are shared.
+//!
+//! A sync creates hosts for new instances (through the host import path, so the same validation
+//! and group handling apply) and updates the address and environment of hosts it created before,
+//! matched by label. Nothing is deleted: an instance that disappeared is left for the user.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::arch::vault::VaultProvider;
+use crate::db::{Db, HostUpdate};
+use crate::host_import::{self, HostImportReport, ImportNote, ImportedHost};
+use crate::{exec, validate};
+
+mod azure;
+mod gcp;
+mod kubernetes;
+
+const SETTINGS_KEY: &str = "inventory";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CLI_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_RESPONSE_BYTES: u64 = 32 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ProviderConfig {
+    /// Compute Engine instances of one project. Authenticates with the gcloud CLI's credentials
+    /// (`gcloud auth print-access-token`).
+    Gcp {
+        project: String,
+        /// A gcloud account other than the active one.
+        #[serde(default)]
+        account: Option<String>,
+        /// Act as this service account (the gcloud account needs the Token Creator role on it).
+        #[serde(default)]
+        impersonate_service_account: Option<String>,
+    },
+    /// Azure VMs of one subscription, optionally one resource group.
+    Azure {
+        subscription_id: String,
+        #[serde(default)]
+        resource_group: Option<String>,
+        #[serde(default)]
+        auth: AzureAuth,
+    },
+    /// The nodes of the cluster behind a kubectl context, with kubectl's own credentials.
+    Kubernetes {
+        context: String,
+        /// A kubeconfig other than kubectl's default (`$KUBECONFIG`, `~/.kube/config`).
+        #[serde(default)]
+        kubeconfig: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum AzureAuth {
+    /// The Azure CLI's signed-in account (`az account get-access-token`).
+    #[default]
+    Cli,
+    /// An app registration; its client secret is kept in the vault (`inventory_secret_set`).
+    ServicePrincipal { tenant_id: String, client_id: String },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddressPreference {
+    /// The primary private IP (reached over VPN, peering or a bastion); a node's InternalIP.
+    #[default]
+    PrivateIp,
+    PublicIp,
+    /// The DNS name where the provider has one (Azure, a node's ExternalDNS/InternalDNS), else the
+    /// public IP.
+    DnsName,
+}
+
+/// How an instance becomes a host.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMapping {
+    /// Label whose value is the environment tag (upper-cased).
+    #[serde(default)]
+    pub environment_label: Option<String>,
+    /// For instances without that label.
+    #[serde(default = "default_environment")]
+    pub default_environment: String,
+    /// Label whose value is the host group (created if needed).
+    #[serde(default)]
+    pub group_label: Option<String>,
+    /// Label whose value is the login user.
+    #[serde(default)]
+    pub username_label: Option<String>,
+    /// For instances without that label (Azure's admin user is used before this).
+    #[serde(default)]
+    pub default_username: Option<String>,
+    #[serde(default)]
+    pub address: AddressPreference,
+    /// Prepended to instance names, to keep sources apart ("gcp-").
+    #[serde(default)]
+    pub label_prefix: Option<String>,
+}
+
+fn default_environment() -> String {
+    "UNKNOWN".to_string()
+}
+
+impl Default for HostMapping {
+    fn default() -> Self {
+        Self {
+            environment_label: None,
+            default_environment: default_environment(),
+            group_label: None,
+            username_label: None,
+            default_username: None,
+            address: AddressPreference::default(),
+            label_prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventorySource {
+    /// Assigned on first save.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub provider: ProviderConfig,
+    /// Only instances carrying all of these labels with these values.
+    #[serde(default)]
+    pub filter: BTreeMap<String, String>,
+    #[serde(default)]
+    pub mapping: HostMapping,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryConfig {
+    #[serde(default)]
+    pub sources: Vec<InventorySource>,
+}
+
+impl InventoryConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// An instance as the provider reports it.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredHost {
+    pub name: String,
+    /// Zone or region.
+    pub location: String,
+    pub private_ip: Option<String>,
+    pub public_ip: Option<String>,
+    pub dns_name: Option<String>,
+    /// The provider's own idea of the login user, if it has one.
+    pub username: Option<String>,
+    /// GCP or Kubernetes labels, or Azure tags.
+    pub labels: BTreeMap<String, String>,
+}
+
+pub trait InventoryProvider {
+    /// Instances carrying all of `filter`'s labels. Providers that can filter server-side do;
+    /// the result is checked again either way.
+    fn list(&self, filter: &BTreeMap<String, String>) -> Result<Vec<DiscoveredHost>, String>;
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventorySyncReport {
+    /// Instances the provider returned that passed the filter.
+    pub discovered: usize,
+    /// Hosts created (or, for a dry run, that would be).
+    pub created: Vec<ImportedHost>,
+    /// Existing hosts whose address or environment changed.
+    pub updated: Vec<ImportedHost>,
+    pub unchanged: usize,
+    pub skipped: Vec<ImportNote>,
+    pub notes: Vec<ImportNote>,
+}
+
+/// Vault key of a source's client secret (Azure service principals).
+pub fn secret_key(source_id: &str) -> String {
+    format!("inventory:{source_id}:client_secret")
+}
+
+fn clean(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+pub fn validate_source(source: &InventorySource) -> Result<(), String> {
+    if source.name.trim().is_empty() {
+        return Err("give the source a name".to_string());
+    }
+    match &source.provider {
+        ProviderConfig::Gcp { project, .. } if project.trim().is_empty() => Err("the GCP project is required".to_string()),
+        ProviderConfig::Kubernetes { context, .. } if context.trim().is_empty() => {
+            Err("choose the kubectl context".to_string())
+        }
+        ProviderConfig::Azure { subscription_id, .. } if subscription_id.trim().is_empty() => {
+            Err("the Azure subscription id is required".to_string())
+        }
+        ProviderConfig::Azure {
+            auth: AzureAuth::ServicePrincipal { tenant_id, client_id },
+            ..
+        } if tenant_id.trim().is_empty() || client_id.trim().is_empty() => {
+            Err("a service principal needs its tenant id and client id".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Create (empty id) or replace a source.
+pub fn save_source(db: &Db, mut source: InventorySource) -> Result<InventorySource, String> {
+    validate_source(&source)?;
+    let mut config = InventoryConfig::load(db);
+    if source.id.trim().is_empty() {
+        source.id = Uuid::new_v4().to_string();
+        config.sources.push(source.clone());
+    } else {
+        let existing = config
+            .sources
+            .iter_mut()
+            .find(|s| s.id == source.id)
+            .ok_or_else(|| "inventory source not found".to_string())?;
+        *existing = source.clone();
+    }
+    config.save(db)?;
+    Ok(source)
+}
+
+pub fn delete_source(db: &Db, vault: &dyn VaultProvider, id: &str) -> Result<(), String> {
+    let mut config = InventoryConfig::load(db);
+    config.sources.retain(|s| s.id != id);
+    config.save(db)?;
+    vault.delete_secret(&secret_key(id)).map_err(|e| e.to_string())
+}
+
+fn provider(source: &InventorySource, vault: &dyn VaultProvider) -> Result<Box<dyn InventoryProvider>, String> {
+    Ok(match &source.provider {
+        ProviderConfig::Gcp {
+            project,
+            account,
+            impersonate_service_account,
+        } => Box::new(gcp::ComputeEngine {
+            project: project.trim().to_string(),
+            account: clean(account),
+            impersonate_service_account: clean(impersonate_service_account),
+        }),
+        ProviderConfig::Azure {
+            subscription_id,
+            resource_group,
+            auth,
+        } => {
+            let credentials = match auth {
+                AzureAuth::Cli => None,
+                AzureAuth::ServicePrincipal { tenant_id, client_id } => {
+                    let secret = vault
+                        .get_secret(&secret_key(&source.id))
+                        .map_err(|e| e.to_string())?
+                        .ok_or_else(|| format!("no client secret is stored for {}", source.name))?;
+                    let secret = String::from_utf8(secret).map_err(|_| "the client secret isn't text".to_string())?;
+                    Some(azure::ServicePrincipal {
+                        tenant_id: tenant_id.trim().to_string(),
+                        client_id: client_id.trim().to_string(),
+                        client_secret: secret.trim().to_string(),
+                    })
+                }
+            };
+            Box::new(azure::AzureVms {
+                subscription_id: subscription_id.trim().to_string(),
+                resource_group: clean(resource_group),
+                credentials,
+            })
+        }
+        ProviderConfig::Kubernetes { context, kubeconfig } => Box::new(kubernetes::KubernetesNodes {
+            context: context.trim().to_string(),
+            kubeconfig: clean(kubeconfig),
+        }),
+    })
+}
+
+/// GET a provider API with a bearer token; errors carry the provider's message (both clouds
+/// answer `{"error": {"message": ...}}`).
+fn get_json(url: &str, token: &str, query: &[(&str, &str)]) -> Result<Value, String> {
+    let mut req = ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .get(url)
+        .set("Authorization", &format!("Bearer {token}"));
+    for (name, value) in query {
+        req = req.query(name, value);
+    }
+    let resp = match req.call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(code, resp)) => {
+            let reply: Value = serde_json::from_str(&resp.into_string().unwrap_or_default()).unwrap_or_default();
+            let message = reply.pointer("/error/message").and_then(Value::as_str).unwrap_or("no details");
+            return Err(format!("HTTP {code}: {message}"));
+        }
+        Err(ureq::Error::Transport(e)) => return Err(e.kind().to_string()),
+    };
+    let mut text = String::new();
+    resp.into_reader()
+        .take(MAX_RESPONSE_BYTES)
+        .read_to_string(&mut text)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("unexpected response: {e}"))
+}
+
+/// Run a cloud CLI (found on PATH, so Windows' `.cmd` shims work) and return its stdout.
+fn run_cli(program: &str, args: &[String]) -> Result<String, String> {
+    let path = which::which(program).map_err(|_| format!("{program} isn't installed or isn't on PATH"))?;
+    let out = exec::run(&path.to_string_lossy(), args, CLI_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.timed_out {
+        return Err(format!("{program} didn't finish within {} s", CLI_TIMEOUT.as_secs()));
+    }
+    if out.exit_code != Some(0) {
+        return Err(format!("{program}: {}", out.stderr.trim()));
+    }
+    Ok(out.stdout)
+}
+
+fn matches_filter(host: &DiscoveredHost, filter: &BTreeMap<String, String>) -> bool {
+    filter.iter().all(|(k, v)| host.labels.get(k).is_some_and(|h| h == v))
+}
+
+/// The host an instance maps to, or why it can't be one.
+fn map_host(host: &DiscoveredHost, mapping: &HostMapping) -> Result<ImportedHost, String> {
+    let label_value = |label: &Option<String>| {
+        clean(label)
+            .and_then(|l| host.labels.get(&l).cloned())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let address = match mapping.address {
+        AddressPreference::PrivateIp => host.private_ip.clone(),
+        AddressPreference::PublicIp => host.public_ip.clone(),
+        AddressPreference::DnsName => host.dns_name.clone().or_else(|| host.public_ip.clone()),
+    }
+    .ok_or_else(|| "the instance has no address of the configured kind".to_string())?;
+    let username = label_value(&mapping.username_label)
+        .or_else(|| host.username.clone())
+        .or_else(|| clean(&mapping.default_username))
+        .ok_or_else(|| "no login user: set a username label or a default username".to_string())?;
+    Ok(ImportedHost {
+        label: format!("{}{}", clean(&mapping.label_prefix).unwrap_or_default(), host.name),
+        hostname: address,
+        port: 22,
+        username,
+        identity_file: None,
+        group: label_value(&mapping.group_label),
+        environment_tag: Some(
+            label_value(&mapping.environment_label)
+                .unwrap_or_else(|| mapping.default_environment.trim().to_string())
+                .to_uppercase(),
+        ),
+    })
+}
+
+/// List the source's instances and create or update their hosts. With `dry_run` nothing is stored.
+pub fn sync(db: &Db, vault: &dyn VaultProvider, source_id: &str, dry_run: bool) -> Result<InventorySyncReport, String> {
+    let config = InventoryConfig::load(db);
+    let source = config
+        .sources
+        .iter()
+        .find(|s| s.id == source_id)
+        .ok_or_else(|| "inventory source not found".to_string())?;
+    let instances: Vec<DiscoveredHost> = provider(source, vault)?
+        .list(&source.filter)?
+        .into_iter()
+        .filter(|h| matches_filter(h, &source.filter))
+        .collect();
+
+    let existing: HashMap<String, _> = db
+        .hosts_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|h| (h.label.to_lowercase(), h))
+        .collect();
+    let mut report = InventorySyncReport {
+        discovered: instances.len(),
+        ..Default::default()
+    };
+    let mut new_hosts = Vec::new();
+    for instance in &instances {
+        let mapped = match map_host(instance, &source.mapping) {
+            Ok(mapped) => mapped,
+            Err(e) => {
+                report.skipped.push(ImportNote {
+                    entry: instance.name.clone(),
+                    message: e,
+                });
+                continue;
+            }
+        };
+        let Some(host) = existing.get(&mapped.label.to_lowercase()) else {
+            new_hosts.push(mapped);
+            continue;
+        };
+        let environment_tag = mapped.environment_tag.clone().unwrap_or_default();
+        if host.hostname == mapped.hostname && host.environment_tag == environment_tag {
+            report.unchanged += 1;
+            continue;
+        }
+        if host.subscription_id.is_some() {
+            report.skipped.push(ImportNote {
+                entry: mapped.label.clone(),
+                message: "a subscription host has this name".to_string(),
+            });
+            continue;
+        }
+        if let Err(e) = validate::host(
+            db,
+            Some(&host.id),
+            &host.label,
+            &mapped.hostname,
+            host.port,
+            &host.username,
+            &environment_tag,
+        ) {
+            report.skipped.push(ImportNote {
+                entry: mapped.label.clone(),
+                message: e.replace('\n', "; "),
+            });
+            continue;
+        }
+        if !dry_run {
+            db.hosts_update(HostUpdate {
+                id: host.id.clone(),
+                label: host.label.clone(),
+                hostname: mapped.hostname.clone(),
+                port: host.port,
+                username: host.username.clone(),
+                environment_tag,
+                identity_file: host.identity_file.clone(),
+                color: host.color.clone(),
+                connection_type: None,
+                dns_expansion: None,
+                dns_selection: None,
+            })
+            .map_err(|e| e.to_string())?;
+        }
+        report.updated.push(mapped);
+    }
+
+    let mut created = HostImportReport::default();
+    host_import::store(db, new_hosts, &source.mapping.default_environment, dry_run, &mut created)?;
+    report.created = created.imported;
+    report.skipped.extend(created.skipped);
+    report.notes.extend(created.notes);
+    Ok(report)
+}
@@ -0,0 +1,379 @@
+//! Headless jobs: non-interactive commands run locally or over ssh without opening a PTY tab.
+//!
+//! Each job is persisted in the `jobs` table (status, exit code, captured output). While a job
+//! runs, output chunks are streamed as `job:output` events and state changes as `job:status`.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::alerts;
+use crate::arch::{shell, ssh};
+use crate::db::{Confirmation, Job, JobFinish, PolicyApproval, ScopeContext};
+use crate::exec::{self, OutputSink, OutputStream};
+use crate::notifications;
+use crate::queue::{Attempt, TaskOutcome};
+use crate::{policy, AppState};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const MAX_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+/// ssh exits with 255 when the connection itself failed, but so may the remote command.
+const SSH_CONNECT_FAILURE: i32 = 255;
+/// What ssh prints when it gives up before running anything on the remote side.
+const SSH_SETUP_ERRORS: &[&str] = &[
+    "ssh: connect to host",
+    "ssh: Could not resolve hostname",
+    "Connection timed out during banner exchange",
+    "kex_exchange_identification",
+    // Not "Connection to <host> closed by remote host", which ssh prints when a session drops.
+    "Connection closed by",
+    "Permission denied (",
+    "Host key verification failed",
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JobOutputEvent {
+    pub job_id: String,
+    pub stream: OutputStream,
+    pub data: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatusEvent {
+    pub job_id: String,
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// Cancel flags for jobs that haven't finished yet, keyed by job id.
+#[derive(Default)]
+pub struct Jobs {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl Jobs {
+    pub fn cancel(&self, job_id: &str) -> bool {
+        match self.active.lock().expect("poisoned jobs lock").get(job_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Resolved program + args for a job target.
+struct Invocation {
+    label: String,
+    /// `None` for local jobs.
+    host_id: Option<String>,
+    program: String,
+    args: Vec<String>,
+}
+
+fn resolve_target(state: &AppState, target: &str, command: &str) -> Result<Invocation, String> {
+    if target == "local" {
+        let cmd = shell::exec_command(command);
+        return Ok(Invocation {
+            label: "Local".to_string(),
+            host_id: None,
+            program: cmd.program,
+            args: cmd.args,
+        });
+    }
+    let host_id = target
+        .strip_prefix("ssh:")
+        .ok_or_else(|| format!("unsupported job target: {target}"))?;
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    Ok(Invocation {
+        label: host.label,
+        host_id: Some(host.id.clone()),
+        program: ssh::ssh_program_checked()?,
+        args: ssh::exec_args(
+            &host.username,
+            &host.hostname,
+            Some(host.port),
+            host.identity_file.as_deref(),
+            command,
+        ),
+    })
+}
+
+/// A job's command after the checks a CommandDock run gets; see [`check`].
+pub struct CheckedCommand {
+    context: ScopeContext,
+    command: String,
+    approval: Option<PolicyApproval>,
+}
+
+/// Check `command` for `target` ("local" or "ssh:<host_id>") the way a CommandDock run is checked:
+/// `confirmed_hash` must match when a `confirmation` is given, `before_command_run` plugins may
+/// rewrite or refuse it, and the target environment's destructive-command policy (four-eyes
+/// included) applies to the result.
+pub fn check(
+    state: &AppState,
+    target: &str,
+    command: &str,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<CheckedCommand, String> {
+    if command.trim().is_empty() {
+        return Err("command must not be empty".to_string());
+    }
+    policy::check_confirmed(command, confirmation, confirmed_hash)?;
+    let context = state.db.scope_context(target.trim()).map_err(|e| e.to_string())?;
+    let command = crate::command_plugins(
+        state,
+        command,
+        None,
+        Some(&context.scope),
+        Some(&context.environment_tag),
+        None,
+    )?;
+    let approval = policy::check_command(&state.db, &context.environment_tag, &command, confirmation, approval)?;
+    Ok(CheckedCommand {
+        context,
+        command,
+        approval,
+    })
+}
+
+/// Lint `command` and decide how the policy of `target`'s environment treats it, for the
+/// confirmation dialog; its hash goes back to [`check`] as `confirmed_hash`.
+pub fn preview(state: &AppState, target: &str, command: &str) -> Result<policy::CommandPreview, String> {
+    let context = state.db.scope_context(target.trim()).map_err(|e| e.to_string())?;
+    policy::assess(&state.db, &context.environment_tag, command.to_string(), false)
+}
+
+/// Queue a [`check`]ed command on the shared work queue, recording how it got past the policy.
+pub fn start(
+    app: AppHandle,
+    state: Arc<AppState>,
+    checked: CheckedCommand,
+    timeout_secs: Option<u64>,
+) -> Result<Job, String> {
+    let CheckedCommand {
+        context,
+        command,
+        approval,
+    } = checked;
+    let target = context.scope.as_str();
+    let invocation = resolve_target(&state, target, &command)?;
+    let timeout = timeout_secs
+        .map(|s| Duration::from_secs(s.clamp(1, MAX_TIMEOUT_SECS)))
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    let job = state
+        .db
+        .jobs_create(target, &invocation.label, &command)
+        .map_err(|e| e.to_string())?;
+    if let Some(approval) = approval {
+        let confirmation = Confirmation {
+            scope: Some(context.scope.clone()),
+            host_id: context.host_id.clone(),
+            ..policy::confirmation(&approval, &context.environment_tag, &command)
+        };
+        if let Err(e) = state.db.confirmations_add(confirmation) {
+            log::warn!("job {}: confirmation not recorded: {e}", job.id);
+        }
+    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    state
+        .jobs
+        .active
+        .lock()
+        .expect("poisoned jobs lock")
+        .insert(job.id.clone(), cancel.clone());
+
+    // Only remote jobs are serialized per host and retried; local commands may not be idempotent
+    // and a failing local shell won't fix itself.
+    let remote = target.starts_with("ssh:");
+    let key = remote.then(|| target.to_string());
+    let job_id = job.id.clone();
+    let queue = state.queue.clone();
+    queue.submit(key, move |attempt| {
+        // The queue outlives a panicking task, but the job would be left "running" until restart.
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            run(&app, &state, &job_id, &invocation, timeout, &cancel, remote, attempt)
+        }))
+        .unwrap_or_else(|_| {
+            abandon(&app, &state, &job_id);
+            TaskOutcome::Done
+        })
+    });
+    Ok(job)
+}
+
+/// ssh failed before the remote command started, so running it again can't repeat it.
+pub(crate) fn never_ran(exit_code: Option<i32>, stdout: &str, stderr: &str) -> bool {
+    exit_code == Some(SSH_CONNECT_FAILURE)
+        && stdout.is_empty()
+        && stderr.lines().any(|line| SSH_SETUP_ERRORS.iter().any(|e| line.contains(e)))
+}
+
+/// Record a job whose task panicked as failed.
+fn abandon(app: &AppHandle, state: &AppState, job_id: &str) {
+    let error = "internal error while running the job";
+    log::error!("job {job_id} panicked");
+    let _ = state.db.jobs_finish(
+        job_id,
+        &JobFinish {
+            status: "failed",
+            exit_code: None,
+            stdout: "",
+            stderr: "",
+            truncated: false,
+            error: Some(error),
+            duration_ms: None,
+        },
+    );
+    state.jobs.active.lock().unwrap_or_else(|e| e.into_inner()).remove(job_id);
+    let _ = app.emit(
+        "job:status",
+        JobStatusEvent {
+            job_id: job_id.to_string(),
+            status: "failed".to_string(),
+            exit_code: None,
+            error: Some(error.to_string()),
+        },
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run(
+    app: &AppHandle,
+    state: &AppState,
+    job_id: &str,
+    invocation: &Invocation,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    retryable: bool,
+    attempt: Attempt,
+) -> TaskOutcome {
+    let emit_status = |status: &str, exit_code: Option<i32>, error: Option<String>| {
+        let _ = app.emit(
+            "job:status",
+            JobStatusEvent {
+                job_id: job_id.to_string(),
+                status: status.to_string(),
+                exit_code,
+                error,
+            },
+        );
+    };
+
+    let finished = |finish: &JobFinish<'_>| {
+        let _ = state.db.jobs_finish(job_id, finish);
+        state.jobs.active.lock().expect("poisoned jobs lock").remove(job_id);
+        emit_status(finish.status, finish.exit_code, finish.error.map(str::to_string));
+        // A cancel came from the user; nothing to tell them.
+        if finish.status != "cancelled" {
+            let outcome = match (finish.status, finish.exit_code) {
+                ("succeeded", _) => "succeeded".to_string(),
+                ("timedOut", _) => "timed out".to_string(),
+                (_, Some(code)) => format!("failed (exit {code})"),
+                _ => "failed".to_string(),
+            };
+            notifications::notify(
+                app,
+                notifications::Kind::Job {
+                    failed: finish.status != "succeeded",
+                },
+                &format!("Job {outcome}"),
+                &match finish.error {
+                    Some(error) => format!("{}: {error}", invocation.label),
+                    None => invocation.label.clone(),
+                },
+            );
+            let output = format!("{}\n{}", finish.stdout, finish.stderr);
+            alerts::on_output(app, state, invocation.host_id.as_deref(), &invocation.label, &output);
+        }
+        TaskOutcome::Done
+    };
+
+    // Cancelled while waiting in the queue (or between retries).
+    if cancel.load(Ordering::SeqCst) {
+        return finished(&JobFinish {
+            status: "cancelled",
+            exit_code: None,
+            stdout: "",
+            stderr: "",
+            truncated: false,
+            error: None,
+            duration_ms: None,
+        });
+    }
+
+    let _ = state.db.jobs_mark_running(job_id);
+    emit_status("running", None, None);
+
+    let sink: OutputSink = {
+        let app = app.clone();
+        let job_id = job_id.to_string();
+        Arc::new(move |stream, data| {
+            let _ = app.emit(
+                "job:output",
+                JobOutputEvent {
+                    job_id: job_id.clone(),
+                    stream,
+                    data: data.to_string(),
+                },
+            );
+        })
+    };
+
+    let result = exec::run_with(&invocation.program, &invocation.args, timeout, Some(cancel), Some(sink));
+    let transient = match &result {
+        Ok(out) => !out.cancelled && never_ran(out.exit_code, &out.stdout, &out.stderr),
+        Err(_) => true,
+    };
+    if retryable && transient && !attempt.is_last {
+        let note = format!("connection failed (attempt {}); retrying", attempt.number);
+        let _ = state.db.jobs_requeue(job_id, &note);
+        emit_status("queued", None, Some(note));
+        return TaskOutcome::Retry;
+    }
+
+    let (status, exit_code, error) = match &result {
+        Ok(out) if out.cancelled => ("cancelled", None, None),
+        Ok(out) if out.timed_out => ("timedOut", None, None),
+        Ok(out) if out.exit_code == Some(0) => ("succeeded", out.exit_code, None),
+        Ok(out) => ("failed", out.exit_code, None),
+        Err(e) => ("failed", None, Some(format!("failed to start: {e}"))),
+    };
+    let finish = match &result {
+        Ok(out) => JobFinish {
+            status,
+            exit_code,
+            stdout: &out.stdout,
+            stderr: &out.stderr,
+            truncated: out.truncated,
+            error: error.as_deref(),
+            duration_ms: Some(out.duration_ms),
+        },
+        Err(_) => JobFinish {
+            status,
+            exit_code,
+            stdout: "",
+            stderr: "",
+            truncated: false,
+            error: error.as_deref(),
+            duration_ms: None,
+        },
+    };
+    finished(&finish)
+}
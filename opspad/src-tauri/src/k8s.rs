@@ -0,0 +1,263 @@
+//! Kubernetes contexts for CommandDock: which kubeconfig context (and namespace) a scope's
+//! kubectl commands should talk to.
+//!
+//! Contexts are read from the local kubeconfig (`$KUBECONFIG`, else `~/.kube/config`) so the UI
+//! can offer them; a preference is saved per scope ("local", "ssh:<host_id>" or "env:<TAG>", a
+//! host's own preference winning over its environment's). When a dock command is rendered for a
+//! scope with a preference, each `kubectl` invocation in it gets `--context` and, unless it picks
+//! its own, `--namespace`, and runs with `KUBECONFIG` pointing at the preferred file (passed as
+//! `--kubeconfig` under sudo, which drops the environment). Flags the command already sets are
+//! left alone.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Db, K8sPref};
+use crate::terminal::highlight;
+
+const MAX_NAME_LEN: usize = 253;
+
+#[derive(Deserialize)]
+struct KubeConfigFile {
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default, rename = "current-context")]
+    current_context: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NamedContext {
+    name: String,
+    #[serde(default)]
+    context: Option<ContextSpec>,
+}
+
+#[derive(Default, Deserialize)]
+struct ContextSpec {
+    #[serde(default)]
+    cluster: Option<String>,
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeContext {
+    pub name: String,
+    pub cluster: Option<String>,
+    pub user: Option<String>,
+    pub namespace: Option<String>,
+    /// The kubeconfig's `current-context`.
+    pub current: bool,
+    /// The file the context was read from.
+    pub kubeconfig: String,
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(path), |home| home.join(rest)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The files kubectl would merge: `$KUBECONFIG`'s list, else `~/.kube/config`.
+fn default_files() -> Vec<PathBuf> {
+    if let Some(list) = std::env::var_os("KUBECONFIG").filter(|v| !v.is_empty()) {
+        return std::env::split_paths(&list).filter(|p| !p.as_os_str().is_empty()).collect();
+    }
+    dirs::home_dir().map(|home| vec![home.join(".kube").join("config")]).unwrap_or_default()
+}
+
+fn read_file(path: &Path) -> Result<KubeConfigFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    serde_yaml::from_str(&text).map_err(|e| format!("{} isn't a readable kubeconfig: {e}", path.display()))
+}
+
+/// Contexts in `kubeconfig` (or kubectl's default files), merged the way kubectl merges them: the
+/// first file to define a name, or set `current-context`, wins.
+pub fn contexts(kubeconfig: Option<&str>) -> Result<Vec<KubeContext>, String> {
+    let files = match kubeconfig.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => vec![expand_home(path)],
+        None => default_files(),
+    };
+    let explicit = kubeconfig.is_some_and(|p| !p.trim().is_empty());
+    let mut current: Option<String> = None;
+    let mut out: Vec<KubeContext> = Vec::new();
+    for path in &files {
+        // Missing files in the default list are skipped, as kubectl does.
+        if !explicit && !path.exists() {
+            continue;
+        }
+        let file = read_file(path)?;
+        if current.is_none() {
+            current = file.current_context.filter(|c| !c.is_empty());
+        }
+        for named in file.contexts {
+            if out.iter().any(|c| c.name == named.name) {
+                continue;
+            }
+            let spec = named.context.unwrap_or_default();
+            out.push(KubeContext {
+                name: named.name,
+                cluster: spec.cluster,
+                user: spec.user,
+                namespace: spec.namespace,
+                current: false,
+                kubeconfig: path.display().to_string(),
+            });
+        }
+    }
+    for context in out.iter_mut() {
+        context.current = current.as_deref() == Some(context.name.as_str());
+    }
+    Ok(out)
+}
+
+fn check_name(what: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() || value.len() > MAX_NAME_LEN || value.chars().any(char::is_control) {
+        return Err(format!("a {what} is 1 to {MAX_NAME_LEN} characters, without control characters"));
+    }
+    Ok(())
+}
+
+pub fn set_pref(
+    db: &Db,
+    scope: &str,
+    kubeconfig: Option<&str>,
+    context: &str,
+    namespace: Option<&str>,
+) -> Result<K8sPref, String> {
+    let scope = scope.trim();
+    if scope.is_empty() {
+        return Err("a scope is required".to_string());
+    }
+    highlight::validate_scope(Some(scope))?;
+    let context = context.trim();
+    check_name("context name", context)?;
+    let kubeconfig = kubeconfig.map(str::trim).filter(|p| !p.is_empty());
+    if let Some(path) = kubeconfig {
+        check_name("kubeconfig path", path)?;
+    }
+    let namespace = namespace.map(str::trim).filter(|n| !n.is_empty());
+    if let Some(ns) = namespace {
+        let label = ns.len() <= 63
+            && ns.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !ns.starts_with('-')
+            && !ns.ends_with('-');
+        if !label {
+            return Err(format!("{ns:?} isn't a valid namespace name"));
+        }
+    }
+    db.k8s_prefs_set(scope, kubeconfig, context, namespace)
+        .map_err(|e| e.to_string())
+}
+
+/// The preference for `scope`: its own, else its environment's.
+pub fn resolve(db: &Db, scope: &str) -> Result<Option<K8sPref>, String> {
+    let prefs = db.k8s_prefs_list().map_err(|e| e.to_string())?;
+    if let Some(pref) = prefs.iter().find(|p| p.scope == scope) {
+        return Ok(Some(pref.clone()));
+    }
+    if scope.starts_with("env:") {
+        return Ok(None);
+    }
+    let env = format!("env:{}", db.scope_context(scope).map_err(|e| e.to_string())?.environment_tag);
+    Ok(prefs.into_iter().find(|p| p.scope == env))
+}
+
+/// `value` as one shell word: bare when that's safe, a leading `~/` left bare so it still expands.
+fn word(value: &str) -> String {
+    let (home, rest) = match value.strip_prefix("~/") {
+        Some(rest) => ("~/", rest),
+        None => ("", value),
+    };
+    let bare = !rest.is_empty()
+        && rest
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | '@' | '+' | ','));
+    if bare {
+        format!("{home}{rest}")
+    } else {
+        format!("{home}{}", crate::arch::ssh::shell_quote(rest))
+    }
+}
+
+fn has_flag(args: &[&str], long: &str, short: Option<&str>) -> bool {
+    args.iter().any(|a| {
+        *a == long
+            || a.strip_prefix(long).is_some_and(|r| r.starts_with('='))
+            || short.is_some_and(|s| a.starts_with(s) && !a.starts_with("--"))
+    })
+}
+
+/// `command` with `pref` applied to each kubectl invocation in it.
+pub fn apply(command: &str, pref: &K8sPref) -> String {
+    static KUBECTL: OnceLock<Regex> = OnceLock::new();
+    let re = KUBECTL.get_or_init(|| {
+        // An invocation starts a command: after a separator, optionally behind variable
+        // assignments and sudo (with its options).
+        Regex::new(
+            r"(?m)(?:^|[;&|(\n])[ \t]*(?P<env>(?:[A-Za-z_][A-Za-z0-9_]*=\S*[ \t]+)*)(?P<sudo>sudo(?:[ \t]+(?:-[ugh][ \t]+\S+|-\S+))*[ \t]+)?(?P<kubectl>kubectl)(?:[ \t]|$)",
+        )
+        .expect("valid regex")
+    });
+    let mut inserts: Vec<(usize, String)> = Vec::new();
+    for caps in re.captures_iter(command) {
+        let kubectl = caps.name("kubectl").expect("group always matches");
+        let rest = &command[kubectl.end()..];
+        let end = rest.find([';', '&', '|', '\n', ')']).unwrap_or(rest.len());
+        let args: Vec<&str> = rest[..end].split_whitespace().collect();
+        let sudo = caps.name("sudo").is_some();
+        let env_set = caps.name("env").is_some_and(|m| m.as_str().contains("KUBECONFIG="));
+
+        let mut flags = String::new();
+        if let Some(path) = pref.kubeconfig.as_deref() {
+            if !env_set && !has_flag(&args, "--kubeconfig", None) {
+                if sudo {
+                    flags.push_str(&format!(" --kubeconfig {}", word(path)));
+                } else {
+                    let at = caps.name("env").map_or(kubectl.start(), |m| m.start());
+                    inserts.push((at, format!("KUBECONFIG={} ", word(path))));
+                }
+            }
+        }
+        if !has_flag(&args, "--context", None) {
+            flags.push_str(&format!(" --context {}", word(&pref.context)));
+        }
+        if let Some(ns) = pref.namespace.as_deref() {
+            let picked = has_flag(&args, "--namespace", Some("-n"))
+                || has_flag(&args, "--all-namespaces", Some("-A"));
+            if !picked {
+                flags.push_str(&format!(" --namespace {}", word(ns)));
+            }
+        }
+        if !flags.is_empty() {
+            inserts.push((kubectl.end(), flags));
+        }
+    }
+    let mut out = command.to_string();
+    inserts.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    for (at, text) in inserts {
+        out.insert_str(at, &text);
+    }
+    out
+}
+
+/// A rendered dock command with the scope's preference applied; unchanged without one.
+pub fn for_scope(db: &Db, scope: Option<&str>, command: String) -> Result<String, String> {
+    let Some(scope) = scope else {
+        return Ok(command);
+    };
+    if !command.contains("kubectl") {
+        return Ok(command);
+    }
+    Ok(match resolve(db, scope)? {
+        Some(pref) => apply(&command, &pref),
+        None => command,
+    })
+}
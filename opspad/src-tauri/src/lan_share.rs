@@ -0,0 +1,359 @@
+//! Read-only sharing of one terminal session over the LAN, so a teammate can watch in a browser
+//! without a screen share.
+//!
+//! Off until enabled in settings. Starting a share opens a small HTTP server on all interfaces
+//! that serves a viewer page and streams the session's output to it (server-sent events) from
+//! then on; both require the share's random token, which is part of the URL handed out. There is
+//! no route that writes to the session: the server ignores request bodies and answers only GET.
+//! Stopping the share (or the session ending) closes the port and ends every viewer's stream.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Db;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "lan_share";
+/// How often the accept loop checks for a stop or the session ending.
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+const KEEPALIVE: Duration = Duration::from_secs(15);
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+const MAX_VIEWERS: usize = 16;
+/// Connections handled at once, viewers included; more are closed unanswered, so a host on the
+/// LAN can't tie up a thread per connection before its token is checked.
+const MAX_CONNECTIONS: usize = MAX_VIEWERS + 16;
+
+const VIEWER_PAGE: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><meta name="referrer" content="no-referrer">
+<title>OpsPad shared session</title>
+<style>
+body{margin:0;background:#0b0f14;color:#d6dde6;font:13px/1.4 ui-monospace,Menlo,Consolas,monospace}
+header{position:sticky;top:0;padding:6px 10px;background:#131a22;border-bottom:1px solid #233;font-family:system-ui,sans-serif}
+pre{margin:0;padding:10px;white-space:pre-wrap;word-break:break-all}
+</style></head>
+<body><header id="status">Connecting… (read-only)</header><pre id="out"></pre>
+<script>
+const out = document.getElementById("out");
+const status = document.getElementById("status");
+const ansi = /\x1b\[[0-?]*[ -\/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)|\x1b[@-_]/g;
+const MAX = 500000;
+let text = "";
+function append(chunk) {
+  const follow = window.innerHeight + window.scrollY >= document.body.scrollHeight - 40;
+  for (const part of chunk.replace(ansi, "").split(/(\r\n|\r|\n|\x08)/)) {
+    if (part === "\r\n" || part === "\n") text += "\n";
+    else if (part === "\r") text = text.slice(0, text.lastIndexOf("\n") + 1);
+    else if (part === "\x08") text = text.slice(0, -1);
+    else text += part;
+  }
+  if (text.length > MAX) text = text.slice(text.length - MAX);
+  out.textContent = text;
+  if (follow) window.scrollTo(0, document.body.scrollHeight);
+}
+const es = new EventSource("/events" + location.search);
+es.onopen = () => { status.textContent = "Live (read-only)"; };
+es.onmessage = (e) => append(JSON.parse(e.data));
+es.addEventListener("end", (e) => {
+  status.textContent = e.data === "ended" ? "The session ended." : "Sharing was stopped.";
+  es.close();
+});
+es.onerror = () => { if (es.readyState === EventSource.CLOSED) status.textContent = "Disconnected."; };
+</script></body></html>
+"#;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanShareConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Fixed port (e.g. one opened in a firewall); `None` picks a free one.
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+impl LanShareConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// The active share; also the `lan-share:changed` payload (`null` once stopped).
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    pub session_id: String,
+    /// Viewer URL, token included; whoever has it can watch.
+    pub url: String,
+    pub viewers: usize,
+    /// Epoch seconds.
+    pub started_at: i64,
+}
+
+struct Active {
+    info: ShareInfo,
+    stop: Arc<AtomicBool>,
+    viewers: Arc<AtomicUsize>,
+}
+
+/// At most one session is shared at a time.
+#[derive(Default)]
+pub struct LanShare {
+    active: Mutex<Option<Active>>,
+}
+
+impl LanShare {
+    pub fn status(&self) -> Option<ShareInfo> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|a| ShareInfo {
+            viewers: a.viewers.load(Ordering::SeqCst),
+            ..a.info.clone()
+        })
+    }
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 24];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compare without returning early, so response timing doesn't leak how much of a guess matched.
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The address other machines reach us on: the source address the OS would use for an outbound
+/// route (connecting a UDP socket sends nothing).
+fn lan_ip() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|s| s.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).map(|_| s))
+        .and_then(|s| s.local_addr())
+        .map(|a| a.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn emit_changed(app: &AppHandle) {
+    let state = app.state::<Arc<AppState>>();
+    let _ = app.emit("lan-share:changed", state.lan_share.status());
+}
+
+/// Start sharing `session_id`. Fails if sharing is off in settings or another session is shared.
+pub fn start(app: &AppHandle, state: &AppState, session_id: &str) -> Result<ShareInfo, String> {
+    let config = LanShareConfig::load(&state.db);
+    if !config.enabled {
+        return Err("LAN sharing is turned off in settings".to_string());
+    }
+    state.terminal.window(session_id).map_err(|e| e.to_string())?;
+    let mut active = state.lan_share.active.lock().unwrap_or_else(|e| e.into_inner());
+    if active.is_some() {
+        return Err("a session is already being shared; stop it first".to_string());
+    }
+
+    let listener =
+        TcpListener::bind((Ipv4Addr::UNSPECIFIED, config.port.unwrap_or(0))).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let token = new_token()?;
+    let info = ShareInfo {
+        session_id: session_id.to_string(),
+        url: format!("http://{}:{port}/?t={token}", lan_ip()),
+        viewers: 0,
+        started_at: now_epoch_secs(),
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let viewers = Arc::new(AtomicUsize::new(0));
+    *active = Some(Active {
+        info: info.clone(),
+        stop: stop.clone(),
+        viewers: viewers.clone(),
+    });
+    drop(active);
+
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    thread::spawn(move || {
+        let shared = Shared {
+            app,
+            session_id,
+            token,
+            stop,
+            viewers,
+            connections: AtomicUsize::new(0),
+        };
+        shared.accept_loop(listener);
+    });
+    log::info!("lan share started on port {port}");
+    Ok(info)
+}
+
+/// Stop the active share, if any; viewers see "Sharing was stopped".
+pub fn stop(app: &AppHandle, state: &AppState) {
+    let Some(active) = state.lan_share.active.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    active.stop.store(true, Ordering::SeqCst);
+    log::info!("lan share stopped");
+    let _ = app.emit("lan-share:changed", None::<ShareInfo>);
+}
+
+struct Shared {
+    app: AppHandle,
+    session_id: String,
+    token: String,
+    stop: Arc<AtomicBool>,
+    viewers: Arc<AtomicUsize>,
+    /// Accepted connections still being served.
+    connections: AtomicUsize,
+}
+
+impl Shared {
+    fn accept_loop(self, listener: TcpListener) {
+        let shared = Arc::new(self);
+        loop {
+            if shared.stop.load(Ordering::SeqCst) {
+                break;
+            }
+            let state = shared.app.state::<Arc<AppState>>();
+            if state.terminal.window(&shared.session_id).is_err() {
+                // The session ended; viewers get "ended" from their own streams.
+                stop(&shared.app, &state);
+                break;
+            }
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    if shared.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        shared.connections.fetch_sub(1, Ordering::SeqCst);
+                        log::debug!("lan share connection from {peer} refused: too many connections");
+                        continue;
+                    }
+                    let shared = shared.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = shared.serve(stream) {
+                            log::debug!("lan share request failed: {e}");
+                        }
+                        shared.connections.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(e) => {
+                    log::warn!("lan share accept failed: {e}");
+                    thread::sleep(ACCEPT_POLL);
+                }
+            }
+        }
+        // Dropping the listener here closes the port.
+    }
+
+    fn serve(&self, stream: TcpStream) -> std::io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Drain the headers; nothing in them is used.
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && !line.trim().is_empty() {
+            line.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        if method != "GET" {
+            return respond(&stream, "405 Method Not Allowed", "text/plain", "read-only");
+        }
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let given = query
+            .split('&')
+            .find_map(|kv| kv.strip_prefix("t="))
+            .unwrap_or("");
+        if !token_matches(given, &self.token) {
+            return respond(&stream, "403 Forbidden", "text/plain", "forbidden");
+        }
+        match path {
+            "/" => respond(&stream, "200 OK", "text/html; charset=utf-8", VIEWER_PAGE),
+            "/events" => self.stream_events(&stream),
+            _ => respond(&stream, "404 Not Found", "text/plain", "not found"),
+        }
+    }
+
+    fn stream_events(&self, mut stream: &TcpStream) -> std::io::Result<()> {
+        if self.viewers.fetch_add(1, Ordering::SeqCst) >= MAX_VIEWERS {
+            self.viewers.fetch_sub(1, Ordering::SeqCst);
+            return respond(stream, "503 Service Unavailable", "text/plain", "too many viewers");
+        }
+        emit_changed(&self.app);
+        let result = self.pump(&mut stream);
+        self.viewers.fetch_sub(1, Ordering::SeqCst);
+        emit_changed(&self.app);
+        result
+    }
+
+    fn pump(&self, stream: &mut &TcpStream) -> std::io::Result<()> {
+        let state = self.app.state::<Arc<AppState>>();
+        let output = state
+            .terminal
+            .subscribe_output(&self.session_id)
+            .map_err(std::io::Error::other)?;
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\
+              X-Content-Type-Options: nosniff\r\nConnection: close\r\n\r\n",
+        )?;
+        let mut last_write = Instant::now();
+        loop {
+            if self.stop.load(Ordering::SeqCst) {
+                return stream.write_all(b"event: end\ndata: stopped\n\n");
+            }
+            match output.recv_timeout(Duration::from_secs(1)) {
+                Ok(chunk) => {
+                    let data = serde_json::to_string(&chunk).map_err(std::io::Error::other)?;
+                    stream.write_all(format!("data: {data}\n\n").as_bytes())?;
+                    last_write = Instant::now();
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if last_write.elapsed() >= KEEPALIVE {
+                        stream.write_all(b": keepalive\n\n")?;
+                        last_write = Instant::now();
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return stream.write_all(b"event: end\ndata: ended\n\n"),
+            }
+        }
+    }
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+         Referrer-Policy: no-referrer\r\nX-Content-Type-Options: nosniff\r\n\
+         Content-Security-Policy: default-src 'self'; script-src 'unsafe-inline'; style-src 'unsafe-inline'\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())
+}
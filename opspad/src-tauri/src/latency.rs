@@ -0,0 +1,254 @@
+//! Round-trip latency of ssh sessions, measured in the background while they're open.
+//!
+//! Each measurement has two parts: a TCP connect to the host's ssh port, which is the network
+//! alone, and a no-op `ssh host true` over a multiplexed connection, which adds the remote sshd
+//! and login shell. A session whose remote time is far above its network time is waiting on the
+//! host, not the link. The multiplexed connection is brought up by an untimed call first, so the
+//! timed one doesn't pay for the handshake. Windows' OpenSSH can't multiplex, so there (and for
+//! hosts that log in with a security key, which would ask for a touch each time) only the network
+//! part is measured. Results go on the session summary and out as `terminal:latency` events.
+//!
+//! Background measuring is off until enabled, since it runs commands on every connected host;
+//! `terminal_latency_probe` measures a session on demand either way.
+
+use std::collections::HashMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::ssh;
+use crate::db::Db;
+use crate::server_info::{self, Target};
+use crate::terminal::session_manager::SessionLatency;
+use crate::{exec, AppState};
+
+const SETTINGS_KEY: &str = "terminal.latency";
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const MIN_INTERVAL_SECS: u64 = 5;
+const MAX_INTERVAL_SECS: u64 = 3600;
+const TICK: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    DEFAULT_INTERVAL_SECS
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: DEFAULT_INTERVAL_SECS,
+        }
+    }
+}
+
+impl LatencyConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&self.interval_secs) {
+            return Err(format!(
+                "interval must be between {MIN_INTERVAL_SECS} and {MAX_INTERVAL_SECS} seconds"
+            ));
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// Payload of `terminal:latency`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LatencyEvent {
+    session_id: String,
+    latency: SessionLatency,
+}
+
+struct Tracked {
+    target: Target,
+    next_at: Instant,
+    /// A measurement is under way; a slow host isn't probed again until it finishes.
+    busy: Arc<AtomicBool>,
+}
+
+/// Open ssh sessions and where they go, keyed by session id.
+#[derive(Default)]
+pub struct SessionLatencies {
+    tracked: Mutex<HashMap<String, Tracked>>,
+}
+
+impl SessionLatencies {
+    /// Start measuring a just-opened ssh session; the first measurement is taken right away.
+    pub fn register(&self, session_id: &str, target: Target) {
+        self.tracked.lock().unwrap_or_else(|e| e.into_inner()).insert(
+            session_id.to_string(),
+            Tracked {
+                target,
+                next_at: Instant::now(),
+                busy: Arc::new(AtomicBool::new(false)),
+            },
+        );
+    }
+}
+
+/// The route picks a jump host or proxy, so the session's packets don't go to `address` directly.
+fn proxied(target: &Target) -> bool {
+    let route = server_info::route_args(&target.extra_args);
+    route.iter().any(|a| {
+        let a = a.to_ascii_lowercase();
+        a.starts_with("-j") || a.contains("proxyjump") || a.contains("proxycommand")
+    })
+}
+
+fn network_ms(target: &Target) -> Result<Option<u64>, String> {
+    if proxied(target) {
+        return Ok(None);
+    }
+    let addr = (target.address.as_str(), target.port.unwrap_or(22))
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve failed: {e}"))?
+        .next()
+        .ok_or_else(|| "no address".to_string())?;
+    let started = Instant::now();
+    TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| e.to_string())?;
+    Ok(Some(started.elapsed().as_millis() as u64))
+}
+
+fn remote_ms(target: &Target) -> Result<Option<u64>, String> {
+    let multiplex = ssh::multiplex_args();
+    if multiplex.is_empty() || target.needs_touch() {
+        return Ok(None);
+    }
+    let program = ssh::ssh_program_checked()?;
+    let mut args = multiplex;
+    args.extend(server_info::route_args(&target.extra_args));
+    args.extend(ssh::exec_args(
+        &target.user,
+        &target.address,
+        target.port,
+        target.identity_file.as_deref(),
+        "true",
+    ));
+    let run = || -> Result<u64, String> {
+        let out = exec::run(&program, &args, PROBE_TIMEOUT).map_err(|e| e.to_string())?;
+        if out.timed_out {
+            return Err("timed out".to_string());
+        }
+        if out.exit_code != Some(0) {
+            return Err(out.stderr.lines().last().unwrap_or("ssh failed").trim().to_string());
+        }
+        Ok(out.duration_ms)
+    };
+    // Opens (or reuses) the shared connection; only the second call is timed.
+    run()?;
+    run().map(Some)
+}
+
+fn measure(target: &Target) -> SessionLatency {
+    let (network_ms, network_err) = match network_ms(target) {
+        Ok(ms) => (ms, None),
+        Err(e) => (None, Some(format!("network: {e}"))),
+    };
+    let (remote_ms, remote_err) = match remote_ms(target) {
+        Ok(ms) => (ms, None),
+        Err(e) => (None, Some(format!("remote: {e}"))),
+    };
+    let error = match (network_err, remote_err) {
+        (Some(a), Some(b)) => Some(format!("{a}; {b}")),
+        (a, b) => a.or(b),
+    };
+    SessionLatency {
+        network_ms,
+        remote_ms,
+        measured_at: crate::timestamp::now_millis() / 1000,
+        error,
+    }
+}
+
+fn publish(app: &AppHandle, state: &AppState, session_id: &str, latency: &SessionLatency) -> bool {
+    // The session may have closed while the measurement ran.
+    if state.terminal.set_latency(session_id, latency.clone()).is_err() {
+        return false;
+    }
+    let _ = app.emit(
+        "terminal:latency",
+        LatencyEvent {
+            session_id: session_id.to_string(),
+            latency: latency.clone(),
+        },
+    );
+    true
+}
+
+/// Measure `session_id` now, outside the schedule (e.g. when the user clicks the ping badge).
+pub fn measure_now(app: &AppHandle, state: &AppState, session_id: &str) -> Result<SessionLatency, String> {
+    let target = state
+        .latencies
+        .tracked
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(session_id)
+        .map(|t| t.target.clone())
+        .ok_or_else(|| "latency is only measured for ssh sessions".to_string())?;
+    let latency = measure(&target);
+    if !publish(app, state, session_id, &latency) {
+        return Err("session not found".to_string());
+    }
+    Ok(latency)
+}
+
+/// Measure every registered session every `interval_secs`, each on its own thread, and forget
+/// sessions that have closed.
+pub fn spawn_prober(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+        let state = app.state::<Arc<AppState>>();
+        let config = LatencyConfig::load(&state.db);
+        let live: Vec<String> = state.terminal.list().into_iter().map(|s| s.session_id).collect();
+        let now = Instant::now();
+        let mut due = Vec::new();
+        {
+            let mut tracked = state.latencies.tracked.lock().unwrap_or_else(|e| e.into_inner());
+            tracked.retain(|id, _| live.contains(id));
+            if !config.enabled {
+                continue;
+            }
+            for (id, t) in tracked.iter_mut() {
+                if now < t.next_at || t.busy.swap(true, Ordering::SeqCst) {
+                    continue;
+                }
+                t.next_at = now + Duration::from_secs(config.interval_secs);
+                due.push((id.clone(), t.target.clone(), t.busy.clone()));
+            }
+        }
+        for (session_id, target, busy) in due {
+            let (app, state) = (app.clone(), state.inner().clone());
+            thread::spawn(move || {
+                let latency = measure(&target);
+                publish(&app, &state, &session_id, &latency);
+                busy.store(false, Ordering::SeqCst);
+            });
+        }
+    });
+}
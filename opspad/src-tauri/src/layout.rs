@@ -0,0 +1,209 @@
+//! How terminals are arranged, per workspace: tab order, tab groups, and each tab's split panes.
+//!
+//! The UI saves its arrangement whenever it changes (`layout_set`) and reads it back on start.
+//! Panes name the scope their session was opened for ("local", "ssh:<host_id>"), the same key
+//! `session_restore` saves sessions under, so reopened sessions can be put back where they were.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::Db;
+use crate::terminal::highlight;
+
+/// The main window's workspace.
+pub const DEFAULT_WORKSPACE: &str = "main";
+const MAX_WORKSPACE_LEN: usize = 64;
+const MAX_TABS: usize = 200;
+/// Splits nested deeper than this are refused.
+const MAX_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SplitDirection {
+    /// Children side by side.
+    Horizontal,
+    /// Children stacked.
+    Vertical,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Pane {
+    Session {
+        scope: String,
+        #[serde(default)]
+        title: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Split {
+        direction: SplitDirection,
+        /// Each child's share of the space; normalized to sum to 1 (empty: equal shares).
+        #[serde(default)]
+        sizes: Vec<f64>,
+        children: Vec<Pane>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutTab {
+    pub id: String,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub pane: Pane,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabGroup {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceLayout {
+    /// In display order.
+    #[serde(default)]
+    pub tabs: Vec<LayoutTab>,
+    #[serde(default)]
+    pub groups: Vec<TabGroup>,
+    #[serde(default)]
+    pub active_tab: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedLayout {
+    pub workspace: String,
+    pub layout: WorkspaceLayout,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSummary {
+    pub workspace: String,
+    pub tabs: usize,
+    #[serde(with = "crate::timestamp::iso")]
+    pub updated_at: i64,
+}
+
+fn workspace_name(workspace: Option<&str>) -> Result<String, String> {
+    let name = workspace.map(str::trim).filter(|w| !w.is_empty()).unwrap_or(DEFAULT_WORKSPACE);
+    if name.len() > MAX_WORKSPACE_LEN || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!(
+            "a workspace name is up to {MAX_WORKSPACE_LEN} letters, digits, '-' or '_'"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Check a pane tree and normalize its split sizes.
+fn check_pane(pane: &mut Pane, depth: usize) -> Result<(), String> {
+    match pane {
+        Pane::Session { scope, .. } => {
+            if scope.starts_with("env:") || scope.trim().is_empty() {
+                return Err(format!("a pane's scope must be local or ssh:<host>, not {scope:?}"));
+            }
+            highlight::validate_scope(Some(scope))
+        }
+        Pane::Split { sizes, children, .. } => {
+            if depth >= MAX_DEPTH {
+                return Err(format!("splits can be nested at most {MAX_DEPTH} deep"));
+            }
+            if children.len() < 2 {
+                return Err("a split needs at least two panes".to_string());
+            }
+            if sizes.is_empty() {
+                *sizes = vec![1.0; children.len()];
+            }
+            if sizes.len() != children.len() {
+                return Err("a split needs one size per pane".to_string());
+            }
+            if sizes.iter().any(|s| !s.is_finite() || *s <= 0.0) {
+                return Err("pane sizes must be positive".to_string());
+            }
+            let total: f64 = sizes.iter().sum();
+            sizes.iter_mut().for_each(|s| *s /= total);
+            children.iter_mut().try_for_each(|child| check_pane(child, depth + 1))
+        }
+    }
+}
+
+fn validate(layout: &mut WorkspaceLayout) -> Result<(), String> {
+    if layout.tabs.len() > MAX_TABS {
+        return Err(format!("a workspace can have at most {MAX_TABS} tabs"));
+    }
+    let mut groups = HashSet::new();
+    for group in &layout.groups {
+        if group.id.trim().is_empty() || !groups.insert(group.id.as_str()) {
+            return Err(format!("group ids must be unique and non-empty ({:?})", group.id));
+        }
+    }
+    let mut tabs = HashSet::new();
+    for tab in layout.tabs.iter_mut() {
+        if tab.id.trim().is_empty() || !tabs.insert(tab.id.clone()) {
+            return Err(format!("tab ids must be unique and non-empty ({:?})", tab.id));
+        }
+        if let Some(group) = tab.group.as_deref().filter(|g| !groups.contains(g)) {
+            return Err(format!("tab {} is in group {group}, which isn't in the layout", tab.id));
+        }
+        check_pane(&mut tab.pane, 0)?;
+    }
+    if layout.active_tab.as_ref().is_some_and(|id| !tabs.contains(id)) {
+        layout.active_tab = None;
+    }
+    Ok(())
+}
+
+pub fn get(db: &Db, workspace: Option<&str>) -> Result<Option<SavedLayout>, String> {
+    let workspace = workspace_name(workspace)?;
+    let Some((json, updated_at)) = db.layouts_get(&workspace).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let layout = serde_json::from_str(&json).map_err(|e| format!("the saved layout is unreadable: {e}"))?;
+    Ok(Some(SavedLayout {
+        workspace,
+        layout,
+        updated_at,
+    }))
+}
+
+pub fn set(db: &Db, workspace: Option<&str>, mut layout: WorkspaceLayout) -> Result<SavedLayout, String> {
+    let workspace = workspace_name(workspace)?;
+    validate(&mut layout)?;
+    let json = serde_json::to_string(&layout).map_err(|e| e.to_string())?;
+    let updated_at = db.layouts_set(&workspace, &json).map_err(|e| e.to_string())?;
+    Ok(SavedLayout {
+        workspace,
+        layout,
+        updated_at,
+    })
+}
+
+pub fn list(db: &Db) -> Result<Vec<WorkspaceSummary>, String> {
+    let rows = db.layouts_list().map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .map(|(workspace, updated_at)| {
+            let tabs = get(db, Some(&workspace)).ok().flatten().map_or(0, |s| s.layout.tabs.len());
+            WorkspaceSummary {
+                workspace,
+                tabs,
+                updated_at,
+            }
+        })
+        .collect())
+}
+
+pub fn delete(db: &Db, workspace: &str) -> Result<(), String> {
+    let workspace = workspace_name(Some(workspace))?;
+    db.layouts_delete(&workspace).map_err(|e| e.to_string())
+}
@@ -1,18 +1,99 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+mod alerts;
+mod app_lock;
 #[allow(dead_code)]
 mod arch;
+mod askpass;
+mod automation_api;
+mod broadcast;
+mod cli;
+mod clipboard;
+mod command_timing;
+mod config_sync;
+mod crash;
 mod db;
+mod db_changes;
+mod dir_sync;
+mod dock_watch;
+mod deep_link;
+mod detach;
+mod diagnostics;
+mod exec;
+mod fanout;
+mod four_eyes;
+mod global_hotkeys;
+mod host_dns;
+mod host_expiry;
+mod host_import;
+mod http_checks;
+mod integrations;
+mod inventory;
+mod jobs;
+mod k8s;
+mod layout;
+mod lan_share;
+mod latency;
+mod lint;
+mod macros;
+mod log_tail;
+mod logging;
+mod monitor;
+mod network_watch;
+mod notifications;
+mod packs;
+mod palette;
+mod plugins;
+mod policy;
+mod preflight;
+mod queue;
+mod rdp;
+mod recording;
+mod redact;
+mod retention;
+mod runbook_sync;
+mod runbooks;
+mod sampler;
+mod secrets;
+mod security_key;
+mod sequence;
+mod server_info;
+mod session_restore;
+mod shell_integration;
+mod shortcuts;
+mod snippets;
+mod ssh_login;
+mod subscriptions;
+mod sudo_assist;
+mod template;
 mod terminal;
+mod timeline;
+mod timestamp;
+mod totp;
+mod transcript;
+mod transfers;
+mod tray;
+mod usage_report;
+mod validate;
+mod vault_autolock;
+mod window_state;
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-use tauri::{Manager, State};
+use tauri::{Emitter, Manager, State};
 
 use crate::arch::vault;
-use crate::db::{Db, DockCommand, DockCommandCreate, HostCreate, HostUpdate};
+use crate::db::{
+    Alert, AlertRule, AlertRuleCreate, CommandVisibility, Db, DockCommand, DockCommandCreate, Environment,
+    FanoutRun, HighlightRule, HighlightRuleCreate, HostCreate, HostGroup, HostQuery, HostSort, HostUpdate, HttpCheck, HttpCheckCreate, Job, LintRule, LintRuleCreate, Macro, MacroInput, RedactionRule, RedactionRuleCreate, Runbook,
+    RunbookCreate, RunbookVersion, Sequence, SequenceCreate,
+    Shortcut, ShortcutTarget, Snippet, SnippetInput, Subscription, SyncProfile, SyncProfileCreate, TerminalMark, Transfer,
+    TransferCreate,
+};
+use crate::terminal::highlight::{self, Highlighter};
+use crate::terminal::perf;
 use crate::terminal::TerminalManager;
 use base64::Engine as _;
-use serde::Serialize;
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -21,334 +102,4056 @@ fn greet(name: &str) -> String {
 
 pub struct AppState {
     terminal: TerminalManager,
-    db: Db,
-    #[allow(dead_code)]
+    db: Arc<Db>,
     vault: Box<dyn vault::VaultProvider>,
+    vault_lock: Arc<vault::VaultLock>,
+    app_lock: app_lock::AppLock,
+    sequences: sequence::SequenceRuns,
+    recordings: recording::Recordings,
+    watches: dock_watch::DockWatches,
+    macros: macros::Macros,
+    latencies: latency::SessionLatencies,
+    jobs: jobs::Jobs,
+    queue: queue::WorkQueue,
+    /// The last CLI/deep-link action, parked until the UI takes it.
+    cli_action: Mutex<Option<cli::CliAction>>,
+    detached: detach::DetachedWindows,
+    monitor: monitor::Monitor,
+    http_checks: http_checks::HttpChecks,
+    lan_share: lan_share::LanShare,
+    broadcasts: broadcast::Broadcasts,
+    clipboard: clipboard::ClipboardHistory,
+    command_timings: command_timing::CommandTimings,
+    transfers: transfers::Transfers,
+    log_tails: log_tail::LogTails,
+    plugins: plugins::Plugins,
+    automation_api: automation_api::AutomationApi,
+    rdp: rdp::RdpSessions,
+    auth_prompts: askpass::AuthPrompts,
 }
 
+/// All hosts, or with `query` a filtered, sorted page of them.
 #[tauri::command]
-fn hosts_list(state: State<'_, Arc<AppState>>) -> Result<Vec<db::Host>, String> {
-    state.db.hosts_list().map_err(|e| e.to_string())
+fn hosts_list(state: State<'_, Arc<AppState>>, query: Option<HostQuery>) -> Result<Vec<db::Host>, String> {
+    let Some(query) = query else {
+        return state.db.hosts_list().map_err(|e| e.to_string());
+    };
+    if query.sort == HostSort::Manual {
+        return state.db.hosts_query(&query).map_err(|e| e.to_string());
+    }
+    // Status order isn't known to the database, so sort everything that matches, then page.
+    let mut hosts = state
+        .db
+        .hosts_query(&HostQuery {
+            limit: None,
+            offset: None,
+            ..query.clone()
+        })
+        .map_err(|e| e.to_string())?;
+    state.monitor.sort_hosts(&mut hosts, query.sort);
+    let offset = query.offset.unwrap_or(0).max(0) as usize;
+    let limit = query.limit.map_or(usize::MAX, |l| l.max(0) as usize);
+    Ok(hosts.into_iter().skip(offset).take(limit).collect())
+}
+
+/// Hosts matching `query`'s filters, for paging through `hosts_list`.
+#[tauri::command]
+fn hosts_count(state: State<'_, Arc<AppState>>, query: Option<HostQuery>) -> Result<i64, String> {
+    state
+        .db
+        .hosts_count(&query.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn hosts_create(state: State<'_, Arc<AppState>>, input: HostCreate) -> Result<db::Host, String> {
+    validate::host(
+        &state.db,
+        None,
+        &input.label,
+        &input.hostname,
+        input.port.unwrap_or(match input.connection_type {
+            db::ConnectionType::Ssh => 22,
+            db::ConnectionType::Rdp => 3389,
+        }),
+        &input.username,
+        &input.environment_tag,
+    )?;
+    host_expiry::check_expiry(input.expires_at)?;
+    state.db.hosts_create(input).map_err(|e| e.to_string())
+}
+
+/// Import saved sessions from PuTTY, Termius or SecureCRT into `environment_tag`. `path` is the
+/// exported file (optional for PuTTY on Windows, which reads the registry). With `dry_run` only
+/// the report is returned.
+#[tauri::command]
+async fn hosts_import(
+    state: State<'_, Arc<AppState>>,
+    source: host_import::ImportSource,
+    path: Option<String>,
+    environment_tag: String,
+    dry_run: Option<bool>,
+) -> Result<host_import::HostImportReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = path.as_deref().map(str::trim).filter(|p| !p.is_empty()).map(std::path::Path::new);
+        host_import::import(&state.db, source, path, &environment_tag, dry_run.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn inventory_sources_list(state: State<'_, Arc<AppState>>) -> Vec<inventory::InventorySource> {
+    inventory::InventoryConfig::load(&state.db).sources
+}
+
+/// Create (no id) or replace a cloud inventory source.
+#[tauri::command]
+fn inventory_source_save(
+    state: State<'_, Arc<AppState>>,
+    source: inventory::InventorySource,
+) -> Result<inventory::InventorySource, String> {
+    inventory::save_source(&state.db, source)
+}
+
+/// Also deletes the source's stored client secret. Hosts it created stay.
+#[tauri::command]
+fn inventory_source_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    inventory::delete_source(&state.db, state.vault.as_ref(), &id)
+}
+
+/// Store (or, when empty, delete) the client secret of a source that signs in as a service principal.
+#[tauri::command]
+fn inventory_secret_set(state: State<'_, Arc<AppState>>, source_id: String, secret: String) -> Result<(), String> {
+    let key = inventory::secret_key(&source_id);
+    if secret.trim().is_empty() {
+        return state.vault.delete_secret(&key).map_err(|e| e.to_string());
+    }
+    state
+        .vault
+        .set_secret(&key, secret.trim().as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// List a source's instances and create or update their hosts; with `dry_run` only the report.
+#[tauri::command]
+async fn inventory_sync(
+    state: State<'_, Arc<AppState>>,
+    source_id: String,
+    dry_run: Option<bool>,
+) -> Result<inventory::InventorySyncReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        inventory::sync(&state.db, state.vault.as_ref(), &source_id, dry_run.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Refuses while a terminal to the host is open unless `force`, which closes those sessions first.
+/// `delete_history` also drops the host's CommandDock history.
+#[tauri::command]
+fn hosts_delete(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    force: Option<bool>,
+    delete_history: Option<bool>,
+) -> Result<(), String> {
+    subscriptions::ensure_host_local(&state.db, &id)?;
+    let scope = format!("ssh:{id}");
+    let live: Vec<String> = state
+        .terminal
+        .list()
+        .into_iter()
+        .map(|s| s.session_id)
+        .filter(|sid| state.db.terminal_session_scope_get(sid).ok().flatten().as_deref() == Some(scope.as_str()))
+        .collect();
+    if !live.is_empty() {
+        if !force.unwrap_or(false) {
+            return Err(format!(
+                "{} open terminal session(s) to this host; close them first or force the delete",
+                live.len()
+            ));
+        }
+        for session_id in &live {
+            let _ = state.terminal.close(session_id);
+        }
+    }
+    transfers::stop_for_host(&state, &id);
+    state
+        .db
+        .hosts_delete(&id, delete_history.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Bind vault keys that are typed automatically when ssh asks for the password or key passphrase.
+#[tauri::command]
+fn hosts_set_credentials(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    password_vault_key: Option<String>,
+    passphrase_vault_key: Option<String>,
+) -> Result<db::Host, String> {
+    let clean = |k: Option<String>| k.map(|k| k.trim().to_string()).filter(|k| !k.is_empty());
+    state
+        .db
+        .hosts_set_credentials(
+            &id,
+            clean(password_vault_key).as_deref(),
+            clean(passphrase_vault_key).as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
+    state
+        .db
+        .hosts_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("host not found: {id}"))
+}
+
+/// Set the ssh options used with hardware security keys: `IdentityAgent` and `SecurityKeyProvider`.
+#[tauri::command]
+fn hosts_set_security_key(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    identity_agent: Option<String>,
+    security_key_provider: Option<String>,
+) -> Result<db::Host, String> {
+    let clean = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    state
+        .db
+        .hosts_set_security_key(
+            &id,
+            clean(identity_agent).as_deref(),
+            clean(security_key_provider).as_deref(),
+        )
+        .map_err(|e| e.to_string())?;
+    state
+        .db
+        .hosts_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("host not found: {id}"))
+}
+
+/// Whether this machine's ssh can use FIDO2 security keys (`sk-` identities), and what's missing.
+#[tauri::command]
+async fn security_key_support() -> Result<security_key::SecurityKeySupport, String> {
+    tauri::async_runtime::spawn_blocking(security_key::support)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn hosts_update(state: State<'_, Arc<AppState>>, input: HostUpdate) -> Result<db::Host, String> {
+    subscriptions::ensure_host_local(&state.db, &input.id)?;
+    validate::host(
+        &state.db,
+        Some(&input.id),
+        &input.label,
+        &input.hostname,
+        input.port,
+        &input.username,
+        &input.environment_tag,
+    )?;
+    state.db.hosts_update(input).map_err(|e| e.to_string())
+}
+
+/// Pinned hosts are listed first in the tray's quick connect menu.
+/// Make a host ephemeral, archived once `expires_at` (epoch seconds) passes; None makes it an
+/// ordinary host again.
+#[tauri::command]
+fn hosts_set_expiry(state: State<'_, Arc<AppState>>, id: String, expires_at: Option<i64>) -> Result<(), String> {
+    host_expiry::check_expiry(expires_at)?;
+    state.db.hosts_set_expiry(&id, expires_at).map_err(|e| e.to_string())
+}
+
+/// Ephemeral hosts archived after their expiry, most recent first.
+#[tauri::command]
+fn hosts_archived_list(state: State<'_, Arc<AppState>>) -> Result<Vec<db::Host>, String> {
+    state.db.hosts_archived_list().map_err(|e| e.to_string())
+}
+
+/// Restore an archived host as an ordinary, non-expiring one.
+#[tauri::command]
+fn hosts_unarchive(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.hosts_unarchive(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn hosts_set_pinned(state: State<'_, Arc<AppState>>, id: String, pinned: bool) -> Result<(), String> {
+    state.db.hosts_set_pinned(&id, pinned).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn hosts_reorder(state: State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<(), String> {
+    state.db.hosts_reorder(&ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn hosts_set_group(state: State<'_, Arc<AppState>>, id: String, group_id: Option<String>) -> Result<(), String> {
+    state.db.hosts_set_group(&id, group_id.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn host_groups_list(state: State<'_, Arc<AppState>>) -> Result<Vec<HostGroup>, String> {
+    state.db.host_groups_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn host_groups_create(state: State<'_, Arc<AppState>>, name: String) -> Result<HostGroup, String> {
+    state.db.host_groups_create(name.trim()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn host_groups_update(state: State<'_, Arc<AppState>>, input: HostGroup) -> Result<HostGroup, String> {
+    state.db.host_groups_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn host_groups_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.host_groups_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn environments_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Environment>, String> {
+    state.db.environments_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn environments_upsert(state: State<'_, Arc<AppState>>, input: Environment) -> Result<Environment, String> {
+    if input.name.trim().is_empty() {
+        return Err("environment name must not be empty".to_string());
+    }
+    state.db.environments_upsert(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn environments_delete(state: State<'_, Arc<AppState>>, name: String) -> Result<(), String> {
+    state.db.environments_delete(&name).map_err(|e| e.to_string())
+}
+
+/// This installation's approver name and public key, for teammates to trust.
+#[tauri::command]
+fn four_eyes_identity(state: State<'_, Arc<AppState>>) -> Result<four_eyes::ApproverIdentity, String> {
+    four_eyes::identity(state.vault.as_ref())
+}
+
+#[tauri::command]
+fn four_eyes_approvers_list(state: State<'_, Arc<AppState>>) -> Result<Vec<four_eyes::TrustedApprover>, String> {
+    Ok(four_eyes::approvers(&state.db))
+}
+
+#[tauri::command]
+fn four_eyes_approver_add(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    public_key: String,
+) -> Result<four_eyes::TrustedApprover, String> {
+    four_eyes::approver_add(&state.db, state.vault.as_ref(), &name, &public_key)
+}
+
+#[tauri::command]
+fn four_eyes_approver_remove(state: State<'_, Arc<AppState>>, fingerprint: String) -> Result<bool, String> {
+    four_eyes::approver_remove(&state.db, &fingerprint)
+}
+
+/// A request code asking a teammate to approve `command` in `environment`.
+#[tauri::command]
+fn four_eyes_request(environment: String, command: String) -> Result<String, String> {
+    four_eyes::request(&environment, &command)
+}
+
+#[tauri::command]
+fn four_eyes_request_inspect(code: String) -> Result<four_eyes::ApprovalRequest, String> {
+    four_eyes::inspect_request(&code)
+}
+
+/// Approve a teammate's request code; the token goes back to them to run the command with.
+#[tauri::command]
+fn four_eyes_approve(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+    valid_minutes: Option<u32>,
+) -> Result<String, String> {
+    four_eyes::approve(state.vault.as_ref(), &code, valid_minutes)
+}
+
+#[tauri::command]
+fn dock_commands_list(state: State<'_, Arc<AppState>>) -> Result<Vec<db::DockCommand>, String> {
+    state.db.dock_commands_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_create(
+    state: State<'_, Arc<AppState>>,
+    input: DockCommandCreate,
+) -> Result<db::DockCommand, String> {
+    validate::dock_command(&input.title, &input.command)?;
+    if let Some(params) = input.params.as_deref() {
+        template::validate_schema(&input.command, params)?;
+    }
+    state.db.dock_commands_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_update(state: State<'_, Arc<AppState>>, input: DockCommand) -> Result<db::DockCommand, String> {
+    subscriptions::ensure_command_local(&state.db, &input.id)?;
+    validate::dock_command(&input.title, &input.command)?;
+    // The stored parameter schema stays; it must still fit the new text.
+    if let Some(stored) = state.db.dock_commands_get(&input.id).map_err(|e| e.to_string())? {
+        template::validate_schema(&input.command, &stored.params)
+            .map_err(|e| format!("{e}; remove the parameter before removing it from the command"))?;
+    }
+    state.db.dock_commands_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_set_params(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    params: Vec<template::ParamSpec>,
+) -> Result<db::DockCommand, String> {
+    let cmd = state
+        .db
+        .dock_commands_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "dock command not found".to_string())?;
+    subscriptions::ensure_command_local(&state.db, &id)?;
+    template::validate_schema(&cmd.command, &params)?;
+    state.db.dock_commands_set_params(&id, &params).map_err(|e| e.to_string())?;
+    Ok(db::DockCommand { params, ..cmd })
+}
+
+#[tauri::command]
+fn dock_commands_set_visibility(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    visibility: CommandVisibility,
+) -> Result<(), String> {
+    subscriptions::ensure_command_local(&state.db, &id)?;
+    state
+        .db
+        .dock_commands_set_visibility(&id, &visibility)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_set_category(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    category: Option<String>,
+) -> Result<(), String> {
+    subscriptions::ensure_command_local(&state.db, &id)?;
+    state
+        .db
+        .dock_commands_set_category(&id, category.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Interrupt the command when it runs longer than `timeout_secs` (None removes the timeout);
+/// `escalate` follows up with Ctrl-\ if Ctrl-C doesn't stop it.
+#[tauri::command]
+fn dock_commands_set_timeout(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    timeout_secs: Option<u32>,
+    escalate: bool,
+) -> Result<(), String> {
+    subscriptions::ensure_command_local(&state.db, &id)?;
+    state
+        .db
+        .dock_commands_set_timeout(&id, timeout_secs, escalate)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn shortcuts_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Shortcut>, String> {
+    state.db.shortcuts_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn shortcuts_set(state: State<'_, Arc<AppState>>, chord: String, target: ShortcutTarget) -> Result<Shortcut, String> {
+    let chord = shortcuts::validate(&state.db, &chord, &target)?;
+    state.db.shortcuts_set(&chord, &target).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn global_hotkeys_get(state: State<'_, Arc<AppState>>) -> global_hotkeys::GlobalHotkeys {
+    global_hotkeys::GlobalHotkeys::load(&state.db)
+}
+
+/// Register system-wide hotkeys; fails (keeping the old ones) if a chord is taken or invalid.
+#[tauri::command]
+fn global_hotkeys_set(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    config: global_hotkeys::GlobalHotkeys,
+) -> Result<global_hotkeys::GlobalHotkeys, String> {
+    global_hotkeys::save(&app, &state.db, &config)
+}
+
+#[tauri::command]
+fn shortcuts_clear(state: State<'_, Arc<AppState>>, target: ShortcutTarget) -> Result<(), String> {
+    state.db.shortcuts_clear(&target).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn command_pack_export(state: State<'_, Arc<AppState>>, input: packs::PackExportInput) -> Result<String, String> {
+    packs::export(&state.db, state.vault.as_ref(), input)
+}
+
+#[tauri::command]
+fn command_pack_inspect(state: State<'_, Arc<AppState>>, json: String) -> Result<packs::PackPreview, String> {
+    packs::inspect(&state.db, state.vault.as_ref(), &json)
+}
+
+#[tauri::command]
+fn command_pack_import(
+    state: State<'_, Arc<AppState>>,
+    json: String,
+    strategy: Option<packs::ConflictStrategy>,
+) -> Result<packs::PackImportResult, String> {
+    packs::import(&state.db, &json, strategy.unwrap_or_default())
+}
+
+#[tauri::command]
+fn dock_commands_for_scope(state: State<'_, Arc<AppState>>, scope: String) -> Result<Vec<DockCommand>, String> {
+    state.db.dock_commands_for_scope(&scope).map_err(|e| e.to_string())
+}
+
+/// Explicit `values` layered over the values last used in `scope` (empty values don't count).
+fn dock_command_values(
+    state: &AppState,
+    scope: Option<&str>,
+    values: HashMap<String, String>,
+) -> Result<HashMap<String, String>, String> {
+    let mut merged = match scope {
+        Some(sc) => state.db.dock_param_values_get(sc).map_err(|e| e.to_string())?,
+        None => HashMap::new(),
+    };
+    merged.extend(values.into_iter().filter(|(_, v)| !v.is_empty()));
+    Ok(merged)
+}
+
+/// Validate `values` against the command's parameter schema and return the interpolated text.
+///
+/// When `scope` is given, values remembered for that scope fill in anything not supplied, and the
+/// values used are remembered again on success (except parameters marked sensitive).
+#[tauri::command]
+fn dock_command_render(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    values: HashMap<String, String>,
+    scope: Option<String>,
+) -> Result<String, String> {
+    let cmd = state
+        .db
+        .dock_commands_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "dock command not found".to_string())?;
+    let scope = scope.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+    let mut merged = dock_command_values(&state, scope, values)?;
+    let rendered = template::render(&cmd.command, &cmd.params, &merged)?;
+    let rendered = k8s::for_scope(&state.db, scope, rendered)?;
+
+    if let Some(sc) = scope {
+        let placeholders = template::placeholders(&cmd.command);
+        merged.retain(|name, _| {
+            placeholders.contains(name) && !cmd.params.iter().any(|p| &p.name == name && p.sensitive)
+        });
+        let _ = state.db.dock_param_values_remember(sc, &merged);
+    }
+    Ok(rendered)
+}
+
+/// Render a dock command exactly as `dock_command_render` would (without remembering values) and
+/// report lint findings plus the environment policy decision for `scope`.
+#[tauri::command]
+fn dock_command_preview(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    values: HashMap<String, String>,
+    scope: Option<String>,
+) -> Result<policy::CommandPreview, String> {
+    let cmd = state
+        .db
+        .dock_commands_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "dock command not found".to_string())?;
+    let scope = scope.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+    let merged = dock_command_values(&state, scope, values)?;
+    let rendered = template::render(&cmd.command, &cmd.params, &merged)?;
+    let rendered = k8s::for_scope(&state.db, scope, rendered)?;
+    let environment = match scope {
+        Some(sc) => state.db.scope_context(sc).map_err(|e| e.to_string())?.environment_tag,
+        None => "UNKNOWN".to_string(),
+    };
+    policy::preview(&state.db, &environment, &cmd, rendered)
+}
+
+#[tauri::command]
+fn dock_param_values_get(state: State<'_, Arc<AppState>>, scope: String) -> Result<HashMap<String, String>, String> {
+    state.db.dock_param_values_get(&scope).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    subscriptions::ensure_command_local(&state.db, &id)?;
+    state.db.dock_commands_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_commands_reorder(state: State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<(), String> {
+    state.db.dock_commands_reorder(&ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_runbook_get(state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    state.db.dock_runbook_get().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_runbook_set(state: State<'_, Arc<AppState>>, markdown: String) -> Result<(), String> {
+    state.db.dock_runbook_set(&markdown).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Runbook>, String> {
+    state.db.runbooks_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_get(state: State<'_, Arc<AppState>>, id: String) -> Result<Option<Runbook>, String> {
+    state.db.runbooks_get(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_create(state: State<'_, Arc<AppState>>, input: RunbookCreate) -> Result<Runbook, String> {
+    if input.title.trim().is_empty() {
+        return Err("runbook title must not be empty".to_string());
+    }
+    state.db.runbooks_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_update(state: State<'_, Arc<AppState>>, input: Runbook) -> Result<Runbook, String> {
+    if input.title.trim().is_empty() {
+        return Err("runbook title must not be empty".to_string());
+    }
+    subscriptions::ensure_runbook_local(&state.db, &input.id)?;
+    state.db.runbooks_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_set_params(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    params: Vec<template::ParamSpec>,
+) -> Result<Runbook, String> {
+    let rb = state
+        .db
+        .runbooks_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook not found".to_string())?;
+    subscriptions::ensure_runbook_local(&state.db, &id)?;
+    template::validate_schema(&rb.markdown, &params)?;
+    state.db.runbooks_set_params(&id, &params).map_err(|e| e.to_string())?;
+    Ok(Runbook { params, ..rb })
+}
+
+/// Render a runbook's `{name}` placeholders from `values` and resolve its `opspad://` links
+/// (see `runbooks::render_with_links`).
+#[tauri::command]
+fn runbook_render(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    values: HashMap<String, String>,
+) -> Result<runbooks::RenderedRunbook, String> {
+    let rb = state
+        .db
+        .runbooks_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook not found".to_string())?;
+    runbooks::render_with_links(&rb, &values, |url| deep_link::resolve_from_runbook(&state, url))
+}
+
+/// Follow an `opspad://` link clicked in a runbook. It's handed to the UI like any other link
+/// (`cli:action`), so a command runs through CommandDock with its parameters and policy checks.
+#[tauri::command]
+fn runbook_link_open(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, url: String) -> Result<String, String> {
+    let action = deep_link::resolve_from_runbook(&state, &url)?;
+    Ok(cli::deliver(&app, &state, action))
+}
+
+/// Export a runbook to `path` as standalone HTML or PDF (see `runbooks::export`).
+#[tauri::command]
+async fn runbook_export(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    format: runbooks::ExportFormat,
+    path: String,
+) -> Result<(), String> {
+    if !std::path::Path::new(&path).is_absolute() {
+        return Err("export path must be absolute".to_string());
+    }
+    let rb = state
+        .db
+        .runbooks_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook not found".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || runbooks::export(&rb, format, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn runbooks_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    subscriptions::ensure_runbook_local(&state.db, &id)?;
+    if let Some(rb) = state.db.runbooks_get(&id).map_err(|e| e.to_string())? {
+        runbook_sync::delete_file_for(&state.db, &rb);
+    }
+    state.db.runbooks_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbooks_reorder(state: State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<(), String> {
+    state.db.runbooks_reorder(&ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbook_sync_config_get(state: State<'_, Arc<AppState>>) -> runbook_sync::FolderSyncConfig {
+    runbook_sync::FolderSyncConfig::load(&state.db)
+}
+
+#[tauri::command]
+async fn runbook_sync_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: runbook_sync::FolderSyncConfig,
+) -> Result<runbook_sync::SyncReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        config.save(&state.db)?;
+        runbook_sync::sync_now(&state.db)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn runbook_sync_now(state: State<'_, Arc<AppState>>) -> Result<runbook_sync::SyncReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || runbook_sync::sync_now(&state.db))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn runbook_sync_resolve(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    keep: runbook_sync::ConflictSide,
+) -> Result<(), String> {
+    runbook_sync::resolve(&state.db, &id, keep)
+}
+
+#[tauri::command]
+fn sync_config_get(state: State<'_, Arc<AppState>>) -> config_sync::GitSyncConfig {
+    config_sync::GitSyncConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn sync_config_set(state: State<'_, Arc<AppState>>, config: config_sync::GitSyncConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+#[tauri::command]
+async fn sync_status(state: State<'_, Arc<AppState>>) -> Result<config_sync::GitSyncStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || config_sync::status(&state.db))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Merge with the sync repository's remote, commit, and push (see `config_sync`).
+#[tauri::command]
+async fn sync_now(state: State<'_, Arc<AppState>>) -> Result<config_sync::GitSyncReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || config_sync::sync_now(&state.db))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn subscriptions_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Subscription>, String> {
+    state.db.subscriptions_list().map_err(|e| e.to_string())
+}
+
+/// Subscribe to a signed config bundle at `url`, pinning `signer` (fingerprint) if given.
+#[tauri::command]
+async fn subscription_add(
+    state: State<'_, Arc<AppState>>,
+    url: String,
+    signer: Option<String>,
+) -> Result<Subscription, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || subscriptions::add(&state.db, &url, signer.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn subscription_refresh(state: State<'_, Arc<AppState>>, id: String) -> Result<Subscription, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        subscriptions::refresh(&state.db, &id)?;
+        state
+            .db
+            .subscriptions_get(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "subscription not found".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Unsubscribe, removing every host, command, and runbook the subscription provided.
+#[tauri::command]
+fn subscriptions_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.subscriptions_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Sign a JSON or YAML config bundle for publishing; returns the signed bundle as JSON.
+#[tauri::command]
+fn config_bundle_sign(state: State<'_, Arc<AppState>>, content: String) -> Result<String, String> {
+    subscriptions::sign(state.vault.as_ref(), &content)
+}
+
+/// Start a run of the runbook's checkbox items, filed under `incident_id` or else the active
+/// incident (if any).
+#[tauri::command]
+fn runbook_checklist_start(
+    state: State<'_, Arc<AppState>>,
+    runbook_id: String,
+    incident_id: Option<String>,
+) -> Result<db::ChecklistRun, String> {
+    let rb = state
+        .db
+        .runbooks_get(&runbook_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook not found".to_string())?;
+    let items = runbooks::checklist_items(&rb.markdown);
+    if items.is_empty() {
+        return Err(format!("{} has no checkbox items (`- [ ] step`)", rb.title));
+    }
+    let incident_id = match incident_id {
+        Some(id) => {
+            state
+                .db
+                .incidents_get(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "incident not found".to_string())?;
+            Some(id)
+        }
+        None => state.db.incidents_active().map_err(|e| e.to_string())?.map(|i| i.id),
+    };
+    state
+        .db
+        .checklist_runs_start(&rb.id, &rb.title, incident_id.as_deref(), &items)
+        .map_err(|e| e.to_string())
+}
+
+/// Check or uncheck item `index` of a checklist run; returns the run with its completion.
+#[tauri::command]
+fn runbook_checklist_toggle(
+    state: State<'_, Arc<AppState>>,
+    run_id: String,
+    index: i64,
+    checked: bool,
+) -> Result<db::ChecklistRun, String> {
+    state
+        .db
+        .checklist_items_set(&run_id, index, checked, &policy::os_user())
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "checklist item not found".to_string())
+}
+
+#[tauri::command]
+fn runbook_checklist_get(state: State<'_, Arc<AppState>>, run_id: String) -> Result<Option<db::ChecklistRun>, String> {
+    state.db.checklist_runs_get(&run_id).map_err(|e| e.to_string())
+}
+
+/// Newest first, without items.
+#[tauri::command]
+fn runbook_checklists_list(
+    state: State<'_, Arc<AppState>>,
+    runbook_id: Option<String>,
+    incident_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<db::ChecklistRun>, String> {
+    state
+        .db
+        .checklist_runs_list(runbook_id.as_deref(), incident_id.as_deref(), limit.unwrap_or(50).clamp(1, 500))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbook_checklist_delete(state: State<'_, Arc<AppState>>, run_id: String) -> Result<(), String> {
+    state.db.checklist_runs_delete(&run_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbook_versions_list(state: State<'_, Arc<AppState>>, runbook_id: String) -> Result<Vec<RunbookVersion>, String> {
+    state.db.runbook_versions_list(&runbook_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn runbook_versions_get(state: State<'_, Arc<AppState>>, id: String) -> Result<Option<RunbookVersion>, String> {
+    state.db.runbook_versions_get(&id).map_err(|e| e.to_string())
+}
+
+/// Diff version `from_id` against version `to_id`, or against the runbook's current content.
+#[tauri::command]
+fn runbook_versions_diff(
+    state: State<'_, Arc<AppState>>,
+    from_id: String,
+    to_id: Option<String>,
+) -> Result<runbooks::RunbookDiff, String> {
+    let from = state
+        .db
+        .runbook_versions_get(&from_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook version not found".to_string())?;
+    let (title, markdown) = match to_id {
+        Some(id) => {
+            let to = state
+                .db
+                .runbook_versions_get(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "runbook version not found".to_string())?;
+            (to.title, to.markdown)
+        }
+        None => {
+            let current = state
+                .db
+                .runbooks_get(&from.runbook_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "runbook not found".to_string())?;
+            (current.title, current.markdown)
+        }
+    };
+    Ok(runbooks::diff((&from.title, &from.markdown), (&title, &markdown)))
+}
+
+#[tauri::command]
+fn runbook_versions_restore(state: State<'_, Arc<AppState>>, id: String) -> Result<Runbook, String> {
+    if let Some(version) = state.db.runbook_versions_get(&id).map_err(|e| e.to_string())? {
+        subscriptions::ensure_runbook_local(&state.db, &version.runbook_id)?;
+    }
+    state
+        .db
+        .runbook_versions_restore(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook version not found".to_string())
+}
+
+#[tauri::command]
+fn dock_history_list(
+    state: State<'_, Arc<AppState>>,
+    limit: Option<i64>,
+    pinned_only: Option<bool>,
+    cursor: Option<String>,
+) -> Result<db::Page<db::DockHistoryEntry>, String> {
+    let lim = limit.unwrap_or(200).clamp(1, 500);
+    let cursor = cursor.as_deref().map(db::Cursor::parse).transpose()?;
+    state
+        .db
+        .dock_history_list(lim, pinned_only.unwrap_or(false), cursor.as_ref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_history_for_scope(
+    state: State<'_, Arc<AppState>>,
+    scope: String,
+    limit: Option<i64>,
+) -> Result<Vec<db::DockHistoryEntry>, String> {
+    let lim = limit.unwrap_or(50).clamp(1, 500);
+    state.db.dock_history_for_scope(&scope, lim).map_err(|e| e.to_string())
+}
+
+/// History with identical commands collapsed per scope: run counts and first/last run times.
+#[tauri::command]
+fn dock_history_aggregate(
+    state: State<'_, Arc<AppState>>,
+    scope: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<db::DockHistoryAggregate>, String> {
+    let lim = limit.unwrap_or(200).clamp(1, 500);
+    let scope = scope.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    state.db.dock_history_aggregate(scope, lim).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_history_pin(state: State<'_, Arc<AppState>>, id: String, pinned: bool) -> Result<(), String> {
+    state.db.dock_history_pin(&id, pinned).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_history_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.dock_history_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn dock_history_clear(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    state.db.dock_history_clear().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sequences_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Sequence>, String> {
+    state.db.sequences_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sequences_create(state: State<'_, Arc<AppState>>, input: SequenceCreate) -> Result<Sequence, String> {
+    sequence::validate_steps(&input.steps)?;
+    state.db.sequences_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sequences_update(state: State<'_, Arc<AppState>>, input: Sequence) -> Result<Sequence, String> {
+    sequence::validate_steps(&input.steps)?;
+    state.db.sequences_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sequences_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.sequences_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Render every step of a sequence for `session_id` and report how its environment treats them.
+#[tauri::command]
+fn sequence_preview(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    session_id: Option<String>,
+) -> Result<sequence::SequencePreview, String> {
+    let seq = state
+        .db
+        .sequences_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "sequence not found".to_string())?;
+    sequence::preview(&state, &seq, session_id.as_deref())
+}
+
+/// Start running a sequence into a live session. Progress arrives as `sequence:progress` events.
+#[tauri::command]
+fn sequence_run(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    session_id: String,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<String, String> {
+    let seq = state
+        .db
+        .sequences_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "sequence not found".to_string())?;
+    sequence::start(
+        app,
+        state.inner().clone(),
+        seq,
+        session_id,
+        confirmation.as_deref(),
+        approval.as_deref(),
+        confirmed_hash.as_deref(),
+    )
+}
+
+#[tauri::command]
+fn sequence_abort(state: State<'_, Arc<AppState>>, run_id: String) -> Result<bool, String> {
+    Ok(state.sequences.abort(&run_id))
+}
+
+/// Re-run a dock command into a live session every `interval_secs` until stopped. Each run is
+/// announced as a `dock:watch` event.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn dock_watch_start(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    dock_command_id: String,
+    values: HashMap<String, String>,
+    interval_secs: u64,
+    max_minutes: Option<u64>,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<dock_watch::DockWatch, String> {
+    dock_watch::start(
+        app,
+        state.inner().clone(),
+        session_id,
+        &dock_command_id,
+        values,
+        interval_secs,
+        max_minutes,
+        confirmation.as_deref(),
+        approval.as_deref(),
+        confirmed_hash.as_deref(),
+    )
+}
+
+#[tauri::command]
+fn dock_watch_stop(state: State<'_, Arc<AppState>>, session_id: String) -> Result<bool, String> {
+    Ok(state.watches.stop(&session_id))
+}
+
+#[tauri::command]
+fn dock_watch_list(state: State<'_, Arc<AppState>>) -> Result<Vec<dock_watch::DockWatch>, String> {
+    Ok(state.watches.list())
+}
+
+#[tauri::command]
+fn macros_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Macro>, String> {
+    state.db.macros_list().map_err(|e| e.to_string())
+}
+
+fn validate_macro(input: &MacroInput) -> Result<(), String> {
+    if input.title.trim().is_empty() {
+        return Err("macro title must not be empty".to_string());
+    }
+    macros::validate_steps(&input.steps)
+}
+
+#[tauri::command]
+fn macros_create(state: State<'_, Arc<AppState>>, input: MacroInput) -> Result<Macro, String> {
+    validate_macro(&input)?;
+    state.db.macros_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn macros_update(state: State<'_, Arc<AppState>>, id: String, input: MacroInput) -> Result<Macro, String> {
+    validate_macro(&input)?;
+    state
+        .db
+        .macros_update(&id, input)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "macro not found".to_string())
+}
+
+#[tauri::command]
+fn macros_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.macros_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Start capturing what's typed into the session; `macro_record_stop` hands back the steps.
+#[tauri::command]
+fn macro_record_start(window: tauri::Window, state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
+    detach::check_owner(&state, &session_id, window.label())?;
+    macros::start_recording(&state, &session_id)
+}
+
+#[tauri::command]
+fn macro_record_stop(state: State<'_, Arc<AppState>>, session_id: String) -> Result<macros::MacroRecording, String> {
+    state
+        .macros
+        .stop_recording(&session_id)
+        .ok_or_else(|| "this session isn't being recorded".to_string())
+}
+
+/// Session ids being recorded.
+#[tauri::command]
+fn macro_record_list(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, String> {
+    Ok(state.macros.recording())
+}
+
+/// Dry run of a replay: the steps, their timing, and which lines the session's policy treats as
+/// destructive.
+#[tauri::command]
+fn macro_preview(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    session_id: Option<String>,
+    speed: Option<f64>,
+) -> Result<macros::MacroPreview, String> {
+    let m = state
+        .db
+        .macros_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "macro not found".to_string())?;
+    macros::preview(&state, &m, session_id.as_deref(), speed)
+}
+
+/// Replay a macro into a live session. Progress arrives as `macro:progress` events.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn macro_replay(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    session_id: String,
+    speed: Option<f64>,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<String, String> {
+    let m = state
+        .db
+        .macros_get(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "macro not found".to_string())?;
+    macros::replay(
+        app,
+        state.inner().clone(),
+        m,
+        session_id,
+        speed,
+        confirmation.as_deref(),
+        approval.as_deref(),
+        confirmed_hash.as_deref(),
+    )
+}
+
+#[tauri::command]
+fn macro_replay_abort(state: State<'_, Arc<AppState>>, run_id: String) -> Result<bool, String> {
+    Ok(state.macros.abort(&run_id))
+}
+
+// Runs ssh on every host and can take minutes; keep it off the main thread.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn command_fanout(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    command_id: String,
+    host_ids: Vec<String>,
+    values: Option<HashMap<String, String>>,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<FanoutRun, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let run = fanout::run(
+            &state,
+            &command_id,
+            &host_ids,
+            values.unwrap_or_default(),
+            confirmation.as_deref(),
+            approval.as_deref(),
+            confirmed_hash.as_deref(),
+        )?;
+        notifications::notify(
+            &app,
+            notifications::Kind::Fanout,
+            &format!("Fan-out finished: {}", run.command_title),
+            &format!("{} ok, {} failed of {} hosts", run.ok_count, run.failed_count, run.host_count),
+        );
+        Ok(run)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn fanout_runs_list(state: State<'_, Arc<AppState>>, limit: Option<i64>) -> Result<Vec<FanoutRun>, String> {
+    state
+        .db
+        .fanout_runs_list(limit.unwrap_or(20).clamp(1, 50))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn fanout_run_get(state: State<'_, Arc<AppState>>, id: String) -> Result<Option<FanoutRun>, String> {
+    state.db.fanout_run_get(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn fanout_run_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.fanout_run_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn job_preview(state: State<'_, Arc<AppState>>, target: String, command: String) -> Result<policy::CommandPreview, String> {
+    jobs::preview(&state, &target, &command)
+}
+
+/// Destructive commands need the target environment's confirmation, as in CommandDock.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn job_start(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    target: String,
+    command: String,
+    timeout_secs: Option<u64>,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<Job, String> {
+    let checked = jobs::check(
+        &state,
+        &target,
+        &command,
+        confirmation.as_deref(),
+        approval.as_deref(),
+        confirmed_hash.as_deref(),
+    )?;
+    jobs::start(app, state.inner().clone(), checked, timeout_secs)
+}
+
+#[tauri::command]
+fn jobs_list(
+    state: State<'_, Arc<AppState>>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+) -> Result<db::Page<Job>, String> {
+    let cursor = cursor.as_deref().map(db::Cursor::parse).transpose()?;
+    state
+        .db
+        .jobs_list(limit.unwrap_or(50).clamp(1, 200), cursor.as_ref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn job_get(state: State<'_, Arc<AppState>>, id: String) -> Result<Option<Job>, String> {
+    state.db.jobs_get(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn job_cancel(state: State<'_, Arc<AppState>>, id: String) -> Result<bool, String> {
+    Ok(state.jobs.cancel(&id))
+}
+
+#[tauri::command]
+fn job_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.jobs_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn queue_config_get(state: State<'_, Arc<AppState>>) -> Result<queue::QueueConfig, String> {
+    Ok(state.queue.config())
+}
+
+#[tauri::command]
+fn queue_config_set(state: State<'_, Arc<AppState>>, config: queue::QueueConfig) -> Result<queue::QueueConfig, String> {
+    state.queue.set_config(&state.db, config)
+}
+
+#[tauri::command]
+fn queue_status(state: State<'_, Arc<AppState>>) -> Result<queue::QueueStatus, String> {
+    Ok(state.queue.status())
+}
+
+#[tauri::command]
+fn redaction_rules_list(state: State<'_, Arc<AppState>>) -> Result<Vec<RedactionRule>, String> {
+    state.db.redaction_rules_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn redaction_rules_create(
+    state: State<'_, Arc<AppState>>,
+    input: RedactionRuleCreate,
+) -> Result<RedactionRule, String> {
+    redact::validate_pattern(&input.pattern)?;
+    state.db.redaction_rules_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn redaction_rules_update(state: State<'_, Arc<AppState>>, input: RedactionRule) -> Result<RedactionRule, String> {
+    redact::validate_pattern(&input.pattern)?;
+    state.db.redaction_rules_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn redaction_rules_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.redaction_rules_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn command_lint(state: State<'_, Arc<AppState>>, text: String) -> Result<Vec<lint::LintFinding>, String> {
+    Ok(state.db.linter().map_err(|e| e.to_string())?.lint(&text))
+}
+
+#[tauri::command]
+fn highlight_rules_list(state: State<'_, Arc<AppState>>) -> Result<Vec<HighlightRule>, String> {
+    state.db.highlight_rules_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn highlight_rules_create(state: State<'_, Arc<AppState>>, input: HighlightRuleCreate) -> Result<HighlightRule, String> {
+    highlight::validate_rule(&input.pattern, input.scope.as_deref(), input.color.as_deref())?;
+    let rule = state.db.highlight_rules_create(input).map_err(|e| e.to_string())?;
+    refresh_highlights(&state);
+    Ok(rule)
+}
+
+#[tauri::command]
+fn highlight_rules_update(state: State<'_, Arc<AppState>>, input: HighlightRule) -> Result<HighlightRule, String> {
+    highlight::validate_rule(&input.pattern, input.scope.as_deref(), input.color.as_deref())?;
+    let rule = state.db.highlight_rules_update(input).map_err(|e| e.to_string())?;
+    refresh_highlights(&state);
+    Ok(rule)
+}
+
+#[tauri::command]
+fn highlight_rules_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.highlight_rules_delete(&id).map_err(|e| e.to_string())?;
+    refresh_highlights(&state);
+    Ok(())
+}
+
+#[tauri::command]
+fn snippets_list(state: State<'_, Arc<AppState>>) -> Result<Vec<Snippet>, String> {
+    state.db.snippets_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn snippets_create(state: State<'_, Arc<AppState>>, input: SnippetInput) -> Result<Snippet, String> {
+    snippets::validate(&state.db, None, &input)?;
+    state.db.snippets_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn snippets_update(state: State<'_, Arc<AppState>>, id: String, input: SnippetInput) -> Result<Snippet, String> {
+    snippets::validate(&state.db, Some(&id), &input)?;
+    state
+        .db
+        .snippets_update(&id, input)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "snippet not found".to_string())
+}
+
+#[tauri::command]
+fn snippets_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.snippets_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Expand snippet triggers in typed `text` for a session in `scope` (see `snippets::expand`).
+/// The input path calls this when a word is finished and writes back the returned text.
+#[tauri::command]
+fn terminal_expand(
+    state: State<'_, Arc<AppState>>,
+    text: String,
+    scope: Option<String>,
+) -> Result<snippets::SnippetExpansion, String> {
+    snippets::expand(&state.db, &text, scope.as_deref())
+}
+
+#[tauri::command]
+fn lint_rules_list(state: State<'_, Arc<AppState>>) -> Result<Vec<LintRule>, String> {
+    state.db.lint_rules_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn lint_rules_create(state: State<'_, Arc<AppState>>, input: LintRuleCreate) -> Result<LintRule, String> {
+    lint::validate_rule(&input.pattern, input.unless.as_deref())?;
+    state.db.lint_rules_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn lint_rules_update(state: State<'_, Arc<AppState>>, input: LintRule) -> Result<LintRule, String> {
+    lint::validate_rule(&input.pattern, input.unless.as_deref())?;
+    state.db.lint_rules_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn lint_rules_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.lint_rules_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn terminal_open_local(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    environment_tag: Option<String>,
+) -> Result<String, String> {
+    open_local_session(&app, &state, environment_tag)
+}
+
+pub(crate) fn open_local_session(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    environment_tag: Option<String>,
+) -> Result<String, String> {
+    let env = environment_tag.unwrap_or_else(|| "LOCAL".to_string());
+    let (initial_cols, initial_rows) = state
+        .db
+        .terminal_prefs_get_size("local")
+        .map_err(|e| e.to_string())?
+        .map(|(c, r)| (Some(c), Some(r)))
+        .unwrap_or((None, None));
+    let sid = state
+        .terminal
+        .open_local(
+            app.clone(),
+            Some(env.clone()),
+            initial_cols,
+            initial_rows,
+            session_banner(state, &env, "local shell"),
+        )
+        .map(|id| id.0)
+        .map_err(|e| e.to_string())?;
+    sudo_assist::spawn_watch(app, state, &sid)?;
+
+    // Persist non-secret per-scope prefs and map the runtime session id -> scope.
+    state.db.terminal_session_scope_set(&sid, "local").map_err(|e| e.to_string())?;
+    state.db.terminal_prefs_touch("local", &env).map_err(|e| e.to_string())?;
+    apply_highlights(state, &sid, &env);
+    plugins::session_opened(app, state, &sid, &env);
+    Ok(sid)
+}
+
+/// Async because the automatic pre-flight (if on) waits on the network.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn terminal_open_ssh(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    user: String,
+    host: String,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    extra_args: Vec<String>,
+    environment_tag: Option<String>,
+    host_id: Option<String>,
+) -> Result<String, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        open_ssh_session(
+            app,
+            &state,
+            user,
+            host,
+            port,
+            identity_file,
+            extra_args,
+            environment_tag,
+            host_id,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn open_ssh_session(
+    app: tauri::AppHandle,
+    state: &Arc<AppState>,
+    user: String,
+    host: String,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    extra_args: Vec<String>,
+    environment_tag: Option<String>,
+    host_id: Option<String>,
+) -> Result<String, String> {
+    let host_record = match host_id.as_deref() {
+        Some(id) => state.db.hosts_get(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    if let Some(h) = host_record.as_ref().filter(|h| !h.connection_type.is_ssh()) {
+        return Err(format!("{} is a remote desktop host; open it with RDP", h.label));
+    }
+    // A DNS-expanded host's name stands for its targets. Callers that picked one (from
+    // `host_resolve_targets`) pass it instead of the name.
+    let (host, port) = match host_record.as_ref().filter(|h| !h.dns_expansion.is_off() && h.hostname.trim() == host.trim()) {
+        Some(h) => {
+            let target = host_dns::choose(h)?;
+            (target.address, Some(target.port))
+        }
+        None => (host, port),
+    };
+    // After the caller's own arguments, which win (ssh keeps the first value of an option).
+    let mut extra_args = extra_args;
+    if let Some(h) = host_record.as_ref() {
+        extra_args.extend(security_key::ssh_options(h));
+    }
+    let preflight_config = preflight::PreflightConfig::load(&state.db);
+    if preflight_config.auto {
+        let target = preflight::Target {
+            user: &user,
+            host: &host,
+            port,
+            identity_file: identity_file.as_deref(),
+            extra_args: &extra_args,
+        };
+        if let Some(message) = preflight::run(&target, &preflight_config).failure_message() {
+            return Err(message);
+        }
+    }
+
+    let env = environment_tag.unwrap_or_else(|| "UNKNOWN".to_string());
+    let scope = if let Some(hid) = host_id.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        format!("ssh:{hid}")
+    } else {
+        let p = port.unwrap_or(22);
+        format!("ssh:{user}@{host}:{p}")
+    };
+
+    let (initial_cols, initial_rows) = state
+        .db
+        .terminal_prefs_get_size(&scope)
+        .map_err(|e| e.to_string())?
+        .map(|(c, r)| (Some(c), Some(r)))
+        .unwrap_or((None, None));
+
+    let banner = session_banner(state, &env, &format!("{user}@{host}:{}", port.unwrap_or(22)));
+    let (askpass_channel, askpass_env) = match askpass::session_env(&app, state, host_record.clone())? {
+        Some(bridge) => (Some(bridge.channel), bridge.env),
+        None => (None, Vec::new()),
+    };
+    let address = host.clone();
+    let probe_target = server_info::Target {
+        user: user.clone(),
+        address: host.clone(),
+        port,
+        identity_file: identity_file.clone(),
+        extra_args: extra_args.clone(),
+        host: host_record.clone(),
+    };
+    let sid = state
+        .terminal
+        .open_ssh(
+            app.clone(),
+            user.clone(),
+            host,
+            port,
+            identity_file,
+            extra_args,
+            Some(env.clone()),
+            initial_cols,
+            initial_rows,
+            banner,
+            askpass_env,
+        )
+        .map(|id| id.0)
+        .map_err(|e| {
+            if let Some(channel) = &askpass_channel {
+                state.auth_prompts.discard(channel);
+            }
+            e.to_string()
+        })?;
+    sudo_assist::spawn_watch(&app, state, &sid)?;
+
+    if let Some(host) = host_record {
+        state.db.hosts_touch_connected(&host.id).map_err(|e| e.to_string())?;
+        state
+            .db
+            .host_connections_add(&host.id, &sid, &host.hostname, &address, port.unwrap_or(22))
+            .map_err(|e| e.to_string())?;
+        // Type the host's bound password/passphrase when ssh asks for them in the terminal (with
+        // the askpass bridge, ssh asks the bridge instead, which answers from the same secrets).
+        if askpass_channel.is_none() {
+            ssh_login::spawn_autofill(state.clone(), sid.clone(), host)?;
+        }
+    }
+    if askpass_channel.is_none() {
+        security_key::spawn_touch_watch(&app, state, &sid)?;
+    }
+    if let Some(channel) = &askpass_channel {
+        state.auth_prompts.bind(channel, &sid);
+    }
+
+    state.db.terminal_session_scope_set(&sid, &scope).map_err(|e| e.to_string())?;
+    state.db.terminal_prefs_touch(&scope, &env).map_err(|e| e.to_string())?;
+    apply_highlights(state, &sid, &env);
+    plugins::session_opened(&app, state, &sid, &env);
+    state.latencies.register(&sid, probe_target.clone());
+    server_info::spawn_probe(&app, state, &sid, probe_target);
+    Ok(sid)
+}
+
+#[tauri::command]
+fn auth_prompt_config_get(state: State<'_, Arc<AppState>>) -> askpass::AuthPromptConfig {
+    askpass::AuthPromptConfig::load(&state.db)
+}
+
+/// Applies to ssh sessions opened from now on.
+#[tauri::command]
+fn auth_prompt_config_set(state: State<'_, Arc<AppState>>, config: askpass::AuthPromptConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// ssh prompts waiting for an answer (for a UI that missed the `auth:prompt` events).
+#[tauri::command]
+fn auth_prompts_pending(state: State<'_, Arc<AppState>>) -> Vec<askpass::AuthPrompt> {
+    state.auth_prompts.list()
+}
+
+/// Answer an `auth:prompt`; no answer cancels it.
+#[tauri::command]
+fn auth_prompt_answer(state: State<'_, Arc<AppState>>, id: String, answer: Option<String>) -> Result<(), String> {
+    state.auth_prompts.answer(&id, answer)
+}
+
+/// Launch the remote desktop client for an RDP host. `inject_credentials` hands it the host's
+/// bound password instead of letting it prompt.
+#[tauri::command]
+async fn rdp_open(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    host_id: String,
+    inject_credentials: Option<bool>,
+) -> Result<rdp::RdpSession, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        rdp::open(&app, &state, &host_id, inject_credentials.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn rdp_sessions_list(state: State<'_, Arc<AppState>>) -> Vec<rdp::RdpSession> {
+    state.rdp.list()
+}
+
+#[tauri::command]
+fn rdp_close(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.rdp.close(&id)
+}
+
+/// Open a session to every host in a group and register them as a broadcast group.
+#[tauri::command]
+async fn terminal_open_group(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    group_id: String,
+) -> Result<broadcast::GroupOpen, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || broadcast::open_group(&app, &state, &group_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Broadcast groups that still have live sessions.
+#[tauri::command]
+fn terminal_broadcast_list(state: State<'_, Arc<AppState>>) -> Vec<broadcast::BroadcastGroup> {
+    state.broadcasts.list(&state)
+}
+
+/// Type `data` into every session of a broadcast group. Returns how many sessions got it.
+#[tauri::command]
+fn terminal_broadcast_write(
+    window: tauri::Window,
+    state: State<'_, Arc<AppState>>,
+    broadcast_id: String,
+    data: String,
+) -> Result<usize, String> {
+    state.vault_lock.touch();
+    state.app_lock.touch();
+    state.broadcasts.write(&state, window.label(), &broadcast_id, &data)
+}
+
+/// Forget a broadcast group; its sessions stay open.
+#[tauri::command]
+fn terminal_broadcast_remove(state: State<'_, Arc<AppState>>, broadcast_id: String) -> bool {
+    state.broadcasts.remove(&broadcast_id)
+}
+
+/// Give a session the highlight rules for its scope and environment. Best effort: without them its
+/// output is just sent unmarked.
+/// The warning banner for a new session, if its environment has one turned on.
+fn session_banner(state: &AppState, environment_tag: &str, target: &str) -> Option<String> {
+    let env = state.db.environment_get(environment_tag).ok()?;
+    env.banner.then(|| terminal::banner::render(&env, target))
+}
+
+fn apply_highlights(state: &AppState, session_id: &str, environment_tag: &str) {
+    let rules = match state.db.highlight_rules_list() {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::warn!("highlight rules unavailable: {e}");
+            return;
+        }
+    };
+    let scope = state.db.terminal_session_scope_get(session_id).ok().flatten();
+    let highlighter = Highlighter::for_session(&rules, scope.as_deref(), environment_tag);
+    let _ = state.terminal.set_highlighter(session_id, highlighter);
+}
+
+/// After the rules change, re-apply them to every live session.
+fn refresh_highlights(state: &AppState) {
+    for session in state.terminal.list() {
+        apply_highlights(state, &session.session_id, &session.environment_tag);
+    }
+}
+
+/// Persist "last command" and history for a CommandDock-origin write. Returns the history entry's
+/// id, for timing the command.
+///
+/// This records only CommandDock "Run" actions (and backend-driven equivalents like sequences),
+/// never typed keystrokes.
+fn record_commanddock_write(
+    state: &AppState,
+    session_id: &str,
+    data: &str,
+    dock_command_id: Option<&str>,
+    dock_command_title: Option<&str>,
+    dock_command_template: Option<&str>,
+    approval: Option<&db::PolicyApproval>,
+) -> Option<String> {
+    if let Some(id) = dock_command_id {
+        let _ = state.db.dock_commands_record_run(id);
+    }
+
+    let Ok(Some(scope)) = state.db.terminal_session_scope_get(session_id) else {
+        return None;
+    };
+    let _ = state.db.terminal_prefs_update_last_command(
+        &scope,
+        dock_command_id,
+        dock_command_title,
+        dock_command_template,
+    );
+
+    // Also append to CommandDock history (local-only).
+    let cmd_text = data.replace('\r', "").trim().to_string();
+    if cmd_text.is_empty() {
+        return None;
+    }
+    let env = state
+        .db
+        .terminal_prefs_get_env(&scope)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "UNKNOWN".to_string());
+    let id = state
+        .db
+        .dock_history_add(
+            Some(&scope),
+            &env,
+            &cmd_text,
+            dock_command_id,
+            dock_command_title,
+            dock_command_template,
+            approval,
+        )
+        .ok()?;
+    if let Some(approval) = approval {
+        let confirmation = db::Confirmation {
+            session_id: Some(session_id.to_string()),
+            host_id: state.db.scope_context(&scope).ok().and_then(|c| c.host_id),
+            scope: Some(scope.clone()),
+            dock_command_id: dock_command_id.map(str::to_string),
+            dock_command_title: dock_command_title.map(str::to_string),
+            history_id: Some(id.clone()),
+            ..policy::confirmation(approval, &env, &cmd_text)
+        };
+        if let Err(e) = state.db.confirmations_add(confirmation) {
+            log::warn!("couldn't record the confirmation of a destructive command: {e}");
+        }
+    }
+    state.plugins.notify(
+        plugins::ON_HISTORY_ADD,
+        serde_json::json!({
+            "id": id,
+            "sessionId": session_id,
+            "scope": scope,
+            "environmentTag": env,
+            "command": cmd_text,
+            "dockCommandId": dock_command_id,
+            "dockCommandTitle": dock_command_title,
+        }),
+    );
+    Some(id)
+}
+
+/// Pass `data`, about to run in `scope` (in the terminal `session_id`, if any), through the
+/// `before_command_run` plugins, which may rewrite or refuse it. Every path that runs commands
+/// calls this before its policy check, so the policy judges what is actually sent.
+pub(crate) fn command_plugins(
+    state: &AppState,
+    data: &str,
+    session_id: Option<&str>,
+    scope: Option<&str>,
+    environment_tag: Option<&str>,
+    dock_command_id: Option<&str>,
+) -> Result<String, String> {
+    let context = serde_json::json!({
+        "sessionId": session_id,
+        "environmentTag": environment_tag,
+        "scope": scope,
+        "dockCommandId": dock_command_id,
+    });
+    state.plugins.before_command_run(data, context)
+}
+
+/// Everything a CommandDock run goes through before it's written: plugins (which may rewrite it),
+/// the environment's policy, history and timing. Returns the text to write.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prepare_dock_run(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    session_id: &str,
+    data: &str,
+    dock_command_id: Option<&str>,
+    dock_command_title: Option<&str>,
+    dock_command_template: Option<&str>,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<String, String> {
+    state.terminal.check_writable(session_id).map_err(|e| e.to_string())?;
+    // Checked before plugins get to rewrite it: the hash is of what the user saw.
+    policy::check_confirmed(data, confirmation, confirmed_hash)?;
+    let scope = state.db.terminal_session_scope_get(session_id).ok().flatten();
+    let environment = scope.as_deref().and_then(|s| state.db.terminal_prefs_get_env(s).ok().flatten());
+    let data = command_plugins(
+        state,
+        data,
+        Some(session_id),
+        scope.as_deref(),
+        environment.as_deref(),
+        dock_command_id,
+    )?;
+    let approval = policy::check_session_write(&state.db, session_id, dock_command_id, &data, confirmation, approval)?;
+    let history_id = record_commanddock_write(
+        state,
+        session_id,
+        &data,
+        dock_command_id,
+        dock_command_title,
+        dock_command_template,
+        approval.as_ref(),
+    );
+    if let Some(history_id) = history_id {
+        let timeout = dock_command_id
+            .and_then(|id| state.db.dock_commands_get(id).ok().flatten())
+            .and_then(|cmd| command_timing::Timeout::of(&cmd));
+        command_timing::watch(app, state, session_id, &history_id, &data, timeout);
+    }
+    Ok(data)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn terminal_write(
+    window: tauri::Window,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    data: String,
+    origin: Option<String>,
+    dock_command_id: Option<String>,
+    dock_command_title: Option<String>,
+    dock_command_template: Option<String>,
+    confirmation: Option<String>,
+    approval: Option<String>,
+    confirmed_hash: Option<String>,
+) -> Result<(), String> {
+    let _timer = perf::time(perf::Stage::WriteCommand, data.len());
+    detach::check_owner(&state, &session_id, window.label())?;
+    // Typing in a terminal counts as activity for the vault's and the app lock's idle timers.
+    state.vault_lock.touch();
+    state.app_lock.touch();
+
+    // Update persisted "last command" only for CommandDock-origin runs.
+    let data = if origin.as_deref() == Some("commanddock") {
+        prepare_dock_run(
+            window.app_handle(),
+            &state,
+            &session_id,
+            &data,
+            dock_command_id.as_deref(),
+            dock_command_title.as_deref(),
+            dock_command_template.as_deref(),
+            confirmation.as_deref(),
+            approval.as_deref(),
+            confirmed_hash.as_deref(),
+        )?
+    } else {
+        data
+    };
+
+    if origin.is_some() {
+        state
+            .terminal
+            .write_with_meta(
+                &session_id,
+                &data,
+                crate::terminal::session_manager::WriteMeta { origin },
+            )
+            .map_err(|e| e.to_string())?;
+    } else {
+        state.terminal.write(&session_id, &data).map_err(|e| e.to_string())?;
+    }
+    state.macros.capture(&session_id, &data);
+    Ok(())
+}
+
+#[tauri::command]
+fn terminal_resize(
+    window: tauri::Window,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    detach::check_owner(&state, &session_id, window.label())?;
+    state
+        .terminal
+        .resize(&session_id, cols, rows)
+        .map_err(|e| e.to_string())?;
+
+    if let Ok(Some(scope)) = state.db.terminal_session_scope_get(&session_id) {
+        let _ = state.db.terminal_prefs_update_size(&scope, cols, rows);
+    }
+    Ok(())
+}
+
+const MAX_SESSION_TITLE_CHARS: usize = 80;
+
+/// Give a session a title shown in place of the host or shell name; an empty one clears it.
+#[tauri::command]
+fn terminal_rename(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    title: String,
+) -> Result<(), String> {
+    let title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+    if title.as_ref().is_some_and(|t| t.chars().count() > MAX_SESSION_TITLE_CHARS) {
+        return Err(format!("a title can be at most {MAX_SESSION_TITLE_CHARS} characters"));
+    }
+    if title.as_ref().is_some_and(|t| t.chars().any(char::is_control)) {
+        return Err("a title can't contain control characters".to_string());
+    }
+    state
+        .terminal
+        .rename(&session_id, title.clone())
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("terminal:renamed", terminal::TerminalRenamedEvent { session_id, title });
+    Ok(())
+}
+
+/// Make the session read-only (every write refused, in the backend) or writable again.
+#[tauri::command]
+fn terminal_set_readonly(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    readonly: bool,
+) -> Result<(), String> {
+    state
+        .terminal
+        .set_readonly(&session_id, readonly)
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("terminal:readonly", terminal::TerminalReadonlyEvent { session_id, readonly });
+    Ok(())
+}
+
+/// Timing and throughput of the terminal write path and output loop (see `terminal::perf`).
+#[tauri::command]
+fn perf_metrics() -> perf::PerfMetrics {
+    perf::snapshot()
+}
+
+#[tauri::command]
+fn perf_metrics_reset() -> perf::PerfMetrics {
+    perf::reset();
+    perf::snapshot()
+}
+
+/// Push `bytes` of synthetic output through a PTY session and measure the throughput (see
+/// `terminal::benchmark`). Not offered in the UI.
+#[tauri::command]
+async fn terminal_benchmark(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    bytes: Option<u64>,
+) -> Result<terminal::benchmark::BenchmarkReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || terminal::benchmark::run(&state.terminal, app, bytes))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Turn the once-a-second `perf:metrics` event on or off (for a debug overlay; not persisted).
+#[tauri::command]
+fn perf_overlay_set(app: tauri::AppHandle, enabled: bool) -> perf::PerfMetrics {
+    perf::set_overlay(&app, enabled);
+    perf::snapshot()
+}
+
+#[tauri::command]
+fn terminal_close(state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
+    state
+        .terminal
+        .close(&session_id)
+        .map_err(|e| e.to_string())?;
+    let _ = state.db.terminal_session_scope_delete(&session_id);
+    state.auth_prompts.release(&session_id);
+    Ok(())
+}
+
+/// Move a live session into its own window; the session keeps running throughout.
+#[tauri::command]
+async fn terminal_detach(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    title: String,
+    environment_tag: String,
+) -> Result<(), String> {
+    detach::detach(
+        &app,
+        &state,
+        detach::DetachedSession {
+            session_id,
+            title,
+            environment_tag,
+        },
+    )
+}
+
+/// The session shown by the calling (detached) window.
+#[tauri::command]
+fn terminal_detached_get(window: tauri::Window, state: State<'_, Arc<AppState>>) -> Result<detach::DetachedSession, String> {
+    detach::get(&state, window.label())
+}
+
+/// Called by a detached window once it's listening for `terminal:data`.
+#[tauri::command]
+fn terminal_attach(app: tauri::AppHandle, window: tauri::Window, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    detach::attach(&app, &state, window.label())
+}
+
+#[tauri::command]
+fn terminal_mark_exited(state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
+    state.auth_prompts.release(&session_id);
+    state.db.terminal_session_scope_delete(&session_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn vault_set_secret(state: State<'_, Arc<AppState>>, key: String, secret_b64: String) -> Result<(), String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(secret_b64.as_bytes())
+        .map_err(|e| e.to_string())?;
+    state
+        .vault
+        .set_secret(&key, &bytes)
+        .map_err(|e| e.to_string())
+}
+
+/// Secrets marked as requiring presence show the platform verification prompt first.
+#[tauri::command]
+async fn vault_get_secret(state: State<'_, Arc<AppState>>, key: String) -> Result<Option<String>, String> {
+    let state = state.inner().clone();
+    let bytes = tauri::async_runtime::spawn_blocking(move || secrets::get_verified(state.vault.as_ref(), &key))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(bytes.map(|b| base64::engine::general_purpose::STANDARD.encode(b)))
+}
+
+/// Whether a secret is stored, without returning it (or prompting for it).
+#[tauri::command]
+fn vault_has_secret(state: State<'_, Arc<AppState>>, key: String) -> Result<bool, String> {
+    let bytes = state.vault.get_secret(&key).map_err(|e| e.to_string())?;
+    Ok(bytes.is_some_and(|b| !b.is_empty()))
+}
+
+#[tauri::command]
+fn vault_delete_secret(state: State<'_, Arc<AppState>>, key: String) -> Result<(), String> {
+    state
+        .vault
+        .delete_secret(&key)
+        .map_err(|e| e.to_string())
+}
+
+/// Store a TOTP seed: a base32 secret or an `otpauth://totp/` URI (stored normalized).
+#[tauri::command]
+fn vault_totp_set(state: State<'_, Arc<AppState>>, key: String, seed: String) -> Result<(), String> {
+    let seed = totp::Totp::parse(&seed)?;
+    state
+        .vault
+        .set_secret(&key, seed.to_uri().as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// The current code for a stored TOTP seed.
+#[tauri::command]
+async fn vault_totp_code(state: State<'_, Arc<AppState>>, key: String) -> Result<totp::TotpCode, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let seed = secrets::get_verified(state.vault.as_ref(), &key)?
+            .ok_or_else(|| format!("no secret stored under \"{key}\""))?;
+        totp::code(&seed)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stored secrets (metadata only, never values), with orphans flagged.
+#[tauri::command]
+fn vault_list_keys(state: State<'_, Arc<AppState>>) -> Result<Vec<secrets::SecretInfo>, String> {
+    secrets::list(&state.db, state.vault.as_ref())
+}
+
+#[tauri::command]
+fn vault_set_label(state: State<'_, Arc<AppState>>, key: String, label: Option<String>) -> Result<(), String> {
+    state
+        .vault
+        .set_label(&key, label.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn vault_set_require_presence(
+    state: State<'_, Arc<AppState>>,
+    key: String,
+    required: bool,
+) -> Result<(), String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        secrets::set_require_presence(state.vault.as_ref(), &key, required)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Set or clear (`rotate_by` = null) the date, in epoch seconds, by which a secret should be rotated.
+#[tauri::command]
+fn vault_set_rotate_by(state: State<'_, Arc<AppState>>, key: String, rotate_by: Option<i64>) -> Result<(), String> {
+    state
+        .vault
+        .set_rotate_by(&key, rotate_by)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn vault_rotation_due(state: State<'_, Arc<AppState>>) -> Result<Vec<secrets::RotationDue>, String> {
+    secrets::rotation_due(state.vault.as_ref())
+}
+
+/// Delete secrets whose owner (e.g. a host) no longer exists. Returns the deleted keys.
+#[tauri::command]
+fn vault_prune_orphans(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, String> {
+    secrets::prune_orphans(&state.db, state.vault.as_ref())
+}
+
+#[tauri::command]
+fn vault_status(state: State<'_, Arc<AppState>>) -> vault_autolock::VaultStatus {
+    vault_autolock::status(&state.db, &state.vault_lock)
+}
+
+#[tauri::command]
+fn vault_lock(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<vault_autolock::VaultStatus, String> {
+    vault_autolock::lock(&app, &state.db, &state.vault_lock)?;
+    Ok(vault_autolock::status(&state.db, &state.vault_lock))
+}
+
+#[tauri::command]
+async fn vault_unlock(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    passphrase: String,
+) -> Result<vault_autolock::VaultStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        vault_autolock::unlock(&app, &state.db, &state.vault_lock, &passphrase)?;
+        // The automation API can't start without its token; it may have been waiting on the vault.
+        if state.automation_api.status().is_none() {
+            if let Err(e) = automation_api::apply(&app, &state) {
+                log::warn!("automation api unavailable: {e}");
+            }
+        }
+        Ok(vault_autolock::status(&state.db, &state.vault_lock))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn vault_lock_config_get(state: State<'_, Arc<AppState>>) -> vault_autolock::VaultLockConfig {
+    vault_autolock::VaultLockConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn vault_lock_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: vault_autolock::VaultLockConfig,
+) -> Result<vault_autolock::VaultStatus, String> {
+    config.save(&state.db)?;
+    // Don't lock the moment the idle timeout is shortened.
+    state.vault_lock.touch();
+    Ok(vault_autolock::status(&state.db, &state.vault_lock))
+}
+
+/// Set, change, or remove (`new` = null) the vault unlock passphrase.
+#[tauri::command]
+async fn vault_set_passphrase(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    current: Option<String>,
+    new: Option<String>,
+) -> Result<vault_autolock::VaultStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        vault_autolock::set_passphrase(&app, &state.db, &state.vault_lock, current.as_deref(), new.as_deref())?;
+        Ok(vault_autolock::status(&state.db, &state.vault_lock))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn app_lock_status(state: State<'_, Arc<AppState>>) -> app_lock::AppLockStatus {
+    app_lock::status(&state)
+}
+
+#[tauri::command]
+fn app_lock_engage(app: tauri::AppHandle, state: State<'_, Arc<AppState>>) -> Result<app_lock::AppLockStatus, String> {
+    app_lock::lock(&app, &state)?;
+    Ok(app_lock::status(&state))
+}
+
+/// Unlock with the PIN, or without one through the platform verification prompt (if allowed).
+#[tauri::command]
+async fn app_unlock(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    pin: Option<String>,
+) -> Result<app_lock::AppLockStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        app_lock::unlock(&app, &state, pin.as_deref())?;
+        Ok(app_lock::status(&state))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn app_lock_config_get(state: State<'_, Arc<AppState>>) -> app_lock::AppLockConfig {
+    app_lock::AppLockConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn app_lock_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: app_lock::AppLockConfig,
+) -> Result<app_lock::AppLockStatus, String> {
+    config.save(&state.db)?;
+    // Don't lock the moment the idle timeout is shortened.
+    state.app_lock.touch();
+    Ok(app_lock::status(&state))
+}
+
+/// Set, change, or remove (`new` = null) the app lock PIN.
+#[tauri::command]
+async fn app_lock_set_pin(
+    state: State<'_, Arc<AppState>>,
+    current: Option<String>,
+    new: Option<String>,
+) -> Result<app_lock::AppLockStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        app_lock::set_pin(&state, current.as_deref(), new.as_deref())?;
+        Ok(app_lock::status(&state))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The last `n` lines (default 200) of the application log.
+#[tauri::command]
+fn logs_tail(app: tauri::AppHandle, n: Option<usize>) -> Result<Vec<String>, String> {
+    logging::tail(&app, n.unwrap_or(200))
+}
+
+#[tauri::command]
+fn log_config_get(state: State<'_, Arc<AppState>>) -> logging::LogConfig {
+    logging::LogConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn log_config_set(state: State<'_, Arc<AppState>>, config: logging::LogConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Write a support zip (versions, tool resolution, schema, session summaries, redacted logs).
+#[tauri::command]
+async fn diagnostics_export(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    if !std::path::Path::new(&path).is_absolute() {
+        return Err("export path must be absolute".to_string());
+    }
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || diagnostics::export(&app, &state, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn crash_config_get(state: State<'_, Arc<AppState>>) -> crash::CrashConfig {
+    crash::CrashConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn crash_config_set(state: State<'_, Arc<AppState>>, config: crash::CrashConfig) -> Result<(), String> {
+    config.save(&state.db)?;
+    crash::spawn_upload();
+    Ok(())
+}
+
+/// Crash reports kept on this machine, newest first.
+#[tauri::command]
+fn crash_reports_list() -> Vec<crash::CrashReport> {
+    crash::list()
+}
+
+#[tauri::command]
+fn crash_reports_clear() -> Result<(), String> {
+    crash::clear()
+}
+
+#[tauri::command]
+fn notification_config_get(state: State<'_, Arc<AppState>>) -> notifications::NotificationConfig {
+    notifications::NotificationConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn notification_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: notifications::NotificationConfig,
+) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Show a sample notification (and trigger the OS permission prompt on first use).
+#[tauri::command]
+fn notification_test(app: tauri::AppHandle) -> Result<(), String> {
+    notifications::test(&app)
+}
+
+#[tauri::command]
+fn monitor_config_get(state: State<'_, Arc<AppState>>) -> monitor::MonitorConfig {
+    monitor::MonitorConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn monitor_config_set(state: State<'_, Arc<AppState>>, config: monitor::MonitorConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Latest status of every checked host (empty while the monitor is off).
+#[tauri::command]
+fn host_status_list(state: State<'_, Arc<AppState>>) -> Vec<monitor::HostStatus> {
+    state.monitor.list()
+}
+
+/// A host's status transitions, newest first.
+#[tauri::command]
+fn host_status_history(
+    state: State<'_, Arc<AppState>>,
+    host_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<db::HostStatusEvent>, String> {
+    state
+        .db
+        .host_status_history(&host_id, limit.unwrap_or(50).clamp(1, 200))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn host_check_now(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    host_id: String,
+) -> Result<monitor::HostStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || monitor::check_now(&app, &state, &host_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn http_checks_list(state: State<'_, Arc<AppState>>) -> Result<Vec<HttpCheck>, String> {
+    state.db.http_checks_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn http_checks_create(state: State<'_, Arc<AppState>>, input: HttpCheckCreate) -> Result<HttpCheck, String> {
+    http_checks::validate(&input.name, &input.url, input.expected_status, &input.headers)?;
+    state.db.http_checks_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn http_checks_update(state: State<'_, Arc<AppState>>, input: HttpCheck) -> Result<HttpCheck, String> {
+    http_checks::validate(&input.name, &input.url, Some(input.expected_status), &input.headers)?;
+    state.db.http_checks_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn http_checks_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.http_checks_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Latest status of every enabled HTTP check (empty while the monitor is off).
+#[tauri::command]
+fn http_check_status_list(state: State<'_, Arc<AppState>>) -> Vec<http_checks::HttpCheckStatus> {
+    state.http_checks.list()
+}
+
+/// A check's status transitions, newest first.
+#[tauri::command]
+fn http_check_history(
+    state: State<'_, Arc<AppState>>,
+    check_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<db::HttpCheckEvent>, String> {
+    state
+        .db
+        .http_check_history(&check_id, limit.unwrap_or(50).clamp(1, 200))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn http_check_now(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    check_id: String,
+) -> Result<http_checks::HttpCheckStatus, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || http_checks::check_now(&app, &state, &check_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn sampler_config_get(state: State<'_, Arc<AppState>>) -> sampler::SamplerConfig {
+    sampler::SamplerConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn sampler_config_set(state: State<'_, Arc<AppState>>, config: sampler::SamplerConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// The most recent resource sample for a host, if it has been sampled.
+#[tauri::command]
+fn host_metrics_latest(state: State<'_, Arc<AppState>>, id: String) -> Result<Option<sampler::HostMetrics>, String> {
+    sampler::latest(&state.db, &id)
+}
+
+#[tauri::command]
+async fn host_metrics_sample_now(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<sampler::HostMetrics, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || sampler::sample_now(&app, &state, &id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn alert_rules_list(state: State<'_, Arc<AppState>>) -> Result<Vec<AlertRule>, String> {
+    state.db.alert_rules_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn alert_rules_create(state: State<'_, Arc<AppState>>, input: AlertRuleCreate) -> Result<AlertRule, String> {
+    alerts::validate_rule(&input.name, &input.condition)?;
+    state.db.alert_rules_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn alert_rules_update(state: State<'_, Arc<AppState>>, input: AlertRule) -> Result<AlertRule, String> {
+    alerts::validate_rule(&input.name, &input.condition)?;
+    state.db.alert_rules_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn alert_rules_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.alert_rules_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Alerts newest first; `status` ("open" | "acknowledged" | "resolved") filters to one status.
+#[tauri::command]
+fn alerts_list(
+    state: State<'_, Arc<AppState>>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<Alert>, String> {
+    state
+        .db
+        .alerts_list(status.as_deref(), limit.unwrap_or(100).clamp(1, 500))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn alert_acknowledge(state: State<'_, Arc<AppState>>, id: String) -> Result<Alert, String> {
+    state.db.alerts_acknowledge(&id).map_err(|e| e.to_string())?;
+    alerts::get(&state, &id)
+}
+
+#[tauri::command]
+fn alert_resolve(state: State<'_, Arc<AppState>>, id: String) -> Result<Alert, String> {
+    state.db.alerts_resolve(&id).map_err(|e| e.to_string())?;
+    alerts::get(&state, &id)
+}
+
+/// Store the Slack incoming webhook URL in the vault; an empty URL removes it.
+#[tauri::command]
+fn slack_webhook_set(state: State<'_, Arc<AppState>>, url: String) -> Result<(), String> {
+    integrations::slack::set_webhook(state.vault.as_ref(), &url)
+}
+
+#[tauri::command]
+fn slack_webhook_configured(state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    integrations::slack::webhook_configured(state.vault.as_ref())
+}
+
+#[tauri::command]
+async fn share_to_slack(
+    state: State<'_, Arc<AppState>>,
+    share: integrations::slack::SlackShare,
+) -> Result<(), String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        integrations::slack::share(&state.db, state.vault.as_ref(), &share)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn oncall_config_get(state: State<'_, Arc<AppState>>) -> integrations::oncall::OncallConfig {
+    integrations::oncall::OncallConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn oncall_config_set(state: State<'_, Arc<AppState>>, config: integrations::oncall::OncallConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Store the PagerDuty/Opsgenie API key in the vault; an empty key removes it.
+#[tauri::command]
+fn oncall_api_key_set(state: State<'_, Arc<AppState>>, key: String) -> Result<(), String> {
+    integrations::oncall::set_api_key(state.vault.as_ref(), &key)
+}
+
+#[tauri::command]
+fn oncall_api_key_configured(state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    integrations::oncall::api_key_configured(state.vault.as_ref())
+}
+
+#[tauri::command]
+async fn oncall_incidents_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<integrations::oncall::OncallIncident>, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || integrations::oncall::incidents(&state.db, state.vault.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Start an incident (ending the active one). Confirmations of destructive commands are filed
+/// under the active incident.
+#[tauri::command]
+fn incident_start(
+    state: State<'_, Arc<AppState>>,
+    title: String,
+    reference: Option<String>,
+) -> Result<db::Incident, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("title: must not be empty".to_string());
+    }
+    let reference = reference.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    state.db.incidents_start(title, reference).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn incident_end(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    state.db.incidents_end().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn incident_active(state: State<'_, Arc<AppState>>) -> Result<Option<db::Incident>, String> {
+    state.db.incidents_active().map_err(|e| e.to_string())
+}
+
+/// Newest first.
+#[tauri::command]
+fn incidents_list(state: State<'_, Arc<AppState>>, limit: Option<i64>) -> Result<Vec<db::Incident>, String> {
+    state.db.incidents_list(limit.unwrap_or(50).clamp(1, 500)).map_err(|e| e.to_string())
+}
+
+/// The audit trail of confirmed destructive commands, oldest first: per incident, scope or
+/// time range, a page (`query.limit`) at a time.
+#[tauri::command]
+fn confirmations_list(
+    state: State<'_, Arc<AppState>>,
+    query: Option<db::ConfirmationQuery>,
+    cursor: Option<String>,
+) -> Result<db::Page<db::Confirmation>, String> {
+    let cursor = cursor.as_deref().map(db::Cursor::parse).transpose()?;
+    state
+        .db
+        .confirmations_list(&query.unwrap_or_default(), cursor.as_ref())
+        .map_err(|e| e.to_string())
+}
+
+/// History, session opens and closes, marks, alerts and confirmations from `since` to `until`
+/// (or over an incident), oldest first, as one JSON or Markdown document.
+#[tauri::command]
+async fn timeline_export(
+    state: State<'_, Arc<AppState>>,
+    since: Option<i64>,
+    until: Option<i64>,
+    incident_id: Option<String>,
+    format: timeline::TimelineFormat,
+) -> Result<String, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        timeline::export(&state, since, until, incident_id.as_deref(), format)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Hours connected and commands run from `since` to `until`, per host, environment and incident.
+#[tauri::command]
+async fn usage_report(
+    state: State<'_, Arc<AppState>>,
+    since: i64,
+    until: Option<i64>,
+) -> Result<usage_report::UsageReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || usage_report::report(&state, since, until))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// [`usage_report`] as CSV.
+#[tauri::command]
+async fn usage_report_csv(state: State<'_, Arc<AppState>>, since: i64, until: Option<i64>) -> Result<String, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        usage_report::report(&state, since, until).map(|r| usage_report::csv(&r))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn suggest_config_get(state: State<'_, Arc<AppState>>) -> integrations::suggest::SuggestConfig {
+    integrations::suggest::SuggestConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn suggest_config_set(state: State<'_, Arc<AppState>>, config: integrations::suggest::SuggestConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Store the command suggestion provider's API key in the vault; an empty key removes it.
+#[tauri::command]
+fn suggest_api_key_set(state: State<'_, Arc<AppState>>, key: String) -> Result<(), String> {
+    integrations::suggest::set_api_key(state.vault.as_ref(), &key)
+}
+
+#[tauri::command]
+fn suggest_api_key_configured(state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    integrations::suggest::api_key_configured(state.vault.as_ref())
+}
+
+/// Candidate commands for a plain-language `prompt`, for a session in `scope` ("local",
+/// "ssh:<host id>"). Nothing is run: a chosen candidate is sent like any other CommandDock line.
+#[tauri::command]
+async fn command_suggest(
+    state: State<'_, Arc<AppState>>,
+    prompt: String,
+    scope: String,
+) -> Result<Vec<integrations::suggest::CommandSuggestion>, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let context = integrations::suggest::SuggestContext::for_scope(&state.db, scope.trim())?;
+        integrations::suggest::suggest(&state.db, state.vault.as_ref(), &prompt, &context)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn automation_api_config_get(state: State<'_, Arc<AppState>>) -> automation_api::AutomationApiConfig {
+    automation_api::AutomationApiConfig::load(&state.db, state.vault.as_ref())
+}
+
+/// Saves and restarts (or stops) the server to match. Returns where it's listening, if on.
+#[tauri::command]
+fn automation_api_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    config: automation_api::AutomationApiConfig,
+) -> Result<Option<automation_api::AutomationApiStatus>, String> {
+    config.save(&state.db, state.vault.as_ref())?;
+    automation_api::apply(&app, &state)
+}
+
+#[tauri::command]
+fn automation_api_status(state: State<'_, Arc<AppState>>) -> Option<automation_api::AutomationApiStatus> {
+    state.automation_api.status()
+}
+
+/// Replace the token; clients holding the old one are refused from now on.
+#[tauri::command]
+fn automation_api_rotate_token(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<automation_api::AutomationApiConfig, String> {
+    let config = automation_api::rotate_token(&state.db, state.vault.as_ref())?;
+    automation_api::apply(&app, &state)?;
+    Ok(config)
+}
+
+#[tauri::command]
+fn lan_share_config_get(state: State<'_, Arc<AppState>>) -> lan_share::LanShareConfig {
+    lan_share::LanShareConfig::load(&state.db)
+}
+
+/// Turning sharing off also stops the active share.
+#[tauri::command]
+fn lan_share_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    config: lan_share::LanShareConfig,
+) -> Result<(), String> {
+    config.save(&state.db)?;
+    if !config.enabled {
+        lan_share::stop(&app, &state);
+    }
+    Ok(())
+}
+
+/// Start streaming a session's output read-only to browsers on the LAN.
+#[tauri::command]
+fn session_share_start(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<lan_share::ShareInfo, String> {
+    lan_share::start(&app, &state, &session_id)
+}
+
+#[tauri::command]
+fn session_share_stop(app: tauri::AppHandle, state: State<'_, Arc<AppState>>) {
+    lan_share::stop(&app, &state);
+}
+
+#[tauri::command]
+fn session_share_status(state: State<'_, Arc<AppState>>) -> Option<lan_share::ShareInfo> {
+    state.lan_share.status()
+}
+
+/// A live session's output as Markdown: ANSI stripped, split at each CommandDock command and mark,
+/// redacted.
+#[tauri::command]
+fn session_export_markdown(state: State<'_, Arc<AppState>>, session_id: String) -> Result<String, String> {
+    transcript::export_markdown(&state, &session_id)
+}
+
+/// The output of the session's last finished command, as plain redacted text (needs shell
+/// integration).
+#[tauri::command]
+fn terminal_last_output(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Option<transcript::LastOutput>, String> {
+    transcript::last_output(&state, &session_id)
+}
+
+/// Start recording the session to an asciicast file (masked; see `recording.rs`).
+#[tauri::command]
+fn recording_start(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<recording::RecordingInfo, String> {
+    recording::start(&app, &state, &session_id)
+}
+
+#[tauri::command]
+fn recording_pause(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Option<recording::RecordingInfo>, String> {
+    Ok(state.recordings.set_paused(&app, &session_id, true))
+}
+
+#[tauri::command]
+fn recording_resume(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Option<recording::RecordingInfo>, String> {
+    Ok(state.recordings.set_paused(&app, &session_id, false))
+}
+
+/// Stop the session's recording; returns the file's path.
+#[tauri::command]
+fn recording_stop(state: State<'_, Arc<AppState>>, session_id: String) -> Result<Option<String>, String> {
+    Ok(state.recordings.stop(&session_id))
+}
+
+#[tauri::command]
+fn recording_list(state: State<'_, Arc<AppState>>) -> Result<Vec<recording::RecordingInfo>, String> {
+    Ok(state.recordings.list())
+}
+
+#[tauri::command]
+fn scrollback_config_get(state: State<'_, Arc<AppState>>) -> terminal::scrollback::ScrollbackConfig {
+    terminal::scrollback::ScrollbackConfig::load(&state.db)
+}
+
+/// Applies to sessions opened from now on.
+#[tauri::command]
+fn scrollback_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    config: terminal::scrollback::ScrollbackConfig,
+) -> Result<terminal::scrollback::ScrollbackConfig, String> {
+    config.save(&state.db)?;
+    terminal::scrollback::apply(&app, &state.db, &state.terminal);
+    Ok(config)
+}
+
+/// Live and ended sessions with scrollback pages on disk, newest first.
+#[tauri::command]
+fn scrollback_sessions_list(app: tauri::AppHandle) -> Result<Vec<terminal::scrollback::SpooledSession>, String> {
+    Ok(terminal::scrollback::list(&terminal::scrollback::root(&app)?))
+}
+
+#[tauri::command]
+fn retention_config_get(state: State<'_, Arc<AppState>>) -> retention::RetentionConfig {
+    retention::RetentionConfig::load(&state.db)
+}
+
+/// Save the retention limits and enforce them right away.
+#[tauri::command]
+async fn retention_config_set(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    config: retention::RetentionConfig,
+) -> Result<retention::PurgeReport, String> {
+    config.save(&state.db)?;
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || retention::purge(&app, &state))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// What OpsPad holds on disk: database, recordings, scrollback, crash reports and logs.
+#[tauri::command]
+async fn storage_usage(app: tauri::AppHandle) -> Result<retention::StorageUsage, String> {
+    tauri::async_runtime::spawn_blocking(move || retention::storage_usage(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// One page of a session's spilled scrollback (0 is the oldest kept). For a live session the
+/// last page is followed by `session_export_markdown`'s transcript.
+#[tauri::command]
+async fn scrollback_page(
+    app: tauri::AppHandle,
+    session_id: String,
+    page: usize,
+) -> Result<terminal::scrollback::ScrollbackPage, String> {
+    let root = terminal::scrollback::root(&app)?;
+    tauri::async_runtime::spawn_blocking(move || terminal::scrollback::read_page(&root, &session_id, page))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn scrollback_delete(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
+    if state.terminal.list().iter().any(|s| s.session_id == session_id) {
+        return Err("the session is still open".to_string());
+    }
+    terminal::scrollback::delete(&terminal::scrollback::root(&app)?, &session_id)
+}
+
+/// Search a live or ended session's kept output (spilled pages, then memory), oldest match first.
+#[tauri::command]
+async fn terminal_search(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    pattern: String,
+    opts: Option<terminal::search::SearchOptions>,
+) -> Result<terminal::search::SearchResult, String> {
+    let root = terminal::scrollback::root(&app)?;
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        terminal::search::search(&state.terminal, &root, &session_id, &pattern, opts.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Commands the session's shell has marked as finished, oldest first (empty without shell
+/// integration).
+#[tauri::command]
+fn terminal_commands(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<terminal::boundaries::CommandRecord>, String> {
+    state.terminal.commands(&session_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn terminal_watchdog_config_get(state: State<'_, Arc<AppState>>) -> terminal::watchdog::WatchdogConfig {
+    terminal::watchdog::WatchdogConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn terminal_watchdog_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: terminal::watchdog::WatchdogConfig,
+) -> Result<terminal::watchdog::WatchdogConfig, String> {
+    config.save(&state.db)?;
+    Ok(config)
+}
+
+/// Type a newline (or an `echo`) into a session suspected hung and wait a few seconds for a reply.
+#[tauri::command]
+async fn terminal_probe(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    mode: Option<terminal::watchdog::ProbeMode>,
+) -> Result<terminal::watchdog::ProbeResult, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        terminal::watchdog::probe(&app, &state.terminal, &session_id, mode.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn network_watch_config_get(state: State<'_, Arc<AppState>>) -> network_watch::NetworkWatchConfig {
+    network_watch::NetworkWatchConfig::load(&state.db)
+}
+
+/// Which shells have OpsPad's integration in their profile, locally ("local") or on a saved host
+/// ("ssh:<host id>").
+#[tauri::command]
+async fn shell_integration_status(
+    state: State<'_, Arc<AppState>>,
+    scope: String,
+) -> Result<Vec<shell_integration::ShellIntegrationStatus>, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || shell_integration::status(&state, &scope))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn shell_integration_install(
+    state: State<'_, Arc<AppState>>,
+    scope: String,
+    shell: shell_integration::ShellKind,
+) -> Result<shell_integration::InstallOutcome, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || shell_integration::install(&state, &scope, shell))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn shell_integration_uninstall(
+    state: State<'_, Arc<AppState>>,
+    scope: String,
+    shell: shell_integration::ShellKind,
+) -> Result<shell_integration::InstallOutcome, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || shell_integration::uninstall(&state, &scope, shell))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The integration script for a shell, for installing by hand.
+#[tauri::command]
+fn shell_integration_snippet(shell: shell_integration::ShellKind) -> String {
+    shell.snippet().to_string()
+}
+
+#[tauri::command]
+fn idle_policy_get(state: State<'_, Arc<AppState>>) -> terminal::idle::IdlePolicyConfig {
+    terminal::idle::IdlePolicyConfig::load(&state.db)
+}
+
+/// Applies to open sessions too, from the next check on.
+#[tauri::command]
+fn idle_policy_set(
+    state: State<'_, Arc<AppState>>,
+    config: terminal::idle::IdlePolicyConfig,
+) -> Result<terminal::idle::IdlePolicyConfig, String> {
+    config.save(&state.db)
+}
+
+#[tauri::command]
+fn server_info_config_get(state: State<'_, Arc<AppState>>) -> server_info::ServerInfoConfig {
+    server_info::ServerInfoConfig::load(&state.db)
+}
+
+/// Applies to ssh sessions opened from now on.
+#[tauri::command]
+fn server_info_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: server_info::ServerInfoConfig,
+) -> Result<server_info::ServerInfoConfig, String> {
+    config.save(&state.db)?;
+    Ok(config)
+}
+
+#[tauri::command]
+fn latency_config_get(state: State<'_, Arc<AppState>>) -> latency::LatencyConfig {
+    latency::LatencyConfig::load(&state.db)
 }
 
 #[tauri::command]
-fn hosts_create(state: State<'_, Arc<AppState>>, input: HostCreate) -> Result<db::Host, String> {
-    state.db.hosts_create(input).map_err(|e| e.to_string())
+fn latency_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: latency::LatencyConfig,
+) -> Result<latency::LatencyConfig, String> {
+    config.save(&state.db)?;
+    Ok(config)
 }
 
+/// Measure an ssh session's latency now rather than at its next scheduled probe.
 #[tauri::command]
-fn hosts_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.hosts_delete(&id).map_err(|e| e.to_string())
+async fn terminal_latency_probe(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<terminal::session_manager::SessionLatency, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || latency::measure_now(&app, &state, &session_id))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-fn hosts_update(state: State<'_, Arc<AppState>>, input: HostUpdate) -> Result<db::Host, String> {
-    state.db.hosts_update(input).map_err(|e| e.to_string())
+fn network_watch_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: network_watch::NetworkWatchConfig,
+) -> Result<network_watch::NetworkWatchConfig, String> {
+    config.save(&state.db)?;
+    Ok(config)
 }
 
 #[tauri::command]
-fn hosts_reorder(state: State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<(), String> {
-    state.db.hosts_reorder(&ids).map_err(|e| e.to_string())
+fn clipboard_history_config_get(state: State<'_, Arc<AppState>>) -> clipboard::ClipboardConfig {
+    clipboard::ClipboardConfig::load(&state.db)
 }
 
+/// Turning history off also drops what it collected.
 #[tauri::command]
-fn dock_commands_list(state: State<'_, Arc<AppState>>) -> Result<Vec<db::DockCommand>, String> {
-    state.db.dock_commands_list().map_err(|e| e.to_string())
+fn clipboard_history_config_set(
+    state: State<'_, Arc<AppState>>,
+    config: clipboard::ClipboardConfig,
+) -> Result<clipboard::ClipboardConfig, String> {
+    clipboard::set_config(&state, config)
 }
 
+/// Record text copied from a terminal (ignored unless clipboard history is on).
 #[tauri::command]
-fn dock_commands_create(
+fn clipboard_history_add(
     state: State<'_, Arc<AppState>>,
-    input: DockCommandCreate,
-) -> Result<db::DockCommand, String> {
-    state.db.dock_commands_create(input).map_err(|e| e.to_string())
+    text: String,
+    session_id: Option<String>,
+) -> Result<Option<clipboard::ClipboardEntry>, String> {
+    clipboard::add(&state, &text, session_id.as_deref())
 }
 
+/// Newest first.
 #[tauri::command]
-fn dock_commands_update(state: State<'_, Arc<AppState>>, input: DockCommand) -> Result<db::DockCommand, String> {
-    state.db.dock_commands_update(input).map_err(|e| e.to_string())
+fn clipboard_history_list(state: State<'_, Arc<AppState>>) -> Vec<clipboard::ClipboardEntry> {
+    state.clipboard.list()
 }
 
 #[tauri::command]
-fn dock_commands_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.dock_commands_delete(&id).map_err(|e| e.to_string())
+fn clipboard_history_clear(state: State<'_, Arc<AppState>>) {
+    state.clipboard.clear();
 }
 
+/// Command palette search over sessions, hosts, dock commands, runbooks and history, best first.
 #[tauri::command]
-fn dock_commands_reorder(state: State<'_, Arc<AppState>>, ids: Vec<String>) -> Result<(), String> {
-    state.db.dock_commands_reorder(&ids).map_err(|e| e.to_string())
+fn palette_query(
+    state: State<'_, Arc<AppState>>,
+    text: String,
+    limit: Option<usize>,
+) -> Result<Vec<palette::PaletteItem>, String> {
+    palette::query(&state, &text, limit)
 }
 
+/// Record a labelled point in a live session's timeline. Marks are kept with the session's scope,
+/// appear as headings in its exported transcript and as markers in its recording, if one is
+/// running, and are announced as `terminal:mark`.
 #[tauri::command]
-fn dock_runbook_get(state: State<'_, Arc<AppState>>) -> Result<String, String> {
-    state.db.dock_runbook_get().map_err(|e| e.to_string())
+fn terminal_mark(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    label: String,
+) -> Result<TerminalMark, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err("label: must not be empty".to_string());
+    }
+    if label.chars().count() > 200 {
+        return Err("label: must be at most 200 characters".to_string());
+    }
+    if !state.terminal.list().iter().any(|s| s.session_id == session_id) {
+        return Err("session not found".to_string());
+    }
+    let scope = state
+        .db
+        .terminal_session_scope_get(&session_id)
+        .map_err(|e| e.to_string())?;
+    let mark = state
+        .db
+        .terminal_marks_add(&session_id, scope.as_deref(), label)
+        .map_err(|e| e.to_string())?;
+    state.recordings.mark(&session_id, label);
+    let _ = app.emit("terminal:mark", &mark);
+    Ok(mark)
 }
 
+/// A session's marks, oldest first.
 #[tauri::command]
-fn dock_runbook_set(state: State<'_, Arc<AppState>>, markdown: String) -> Result<(), String> {
-    state.db.dock_runbook_set(&markdown).map_err(|e| e.to_string())
+fn terminal_marks_list(state: State<'_, Arc<AppState>>, session_id: String) -> Result<Vec<TerminalMark>, String> {
+    state
+        .db
+        .terminal_marks_for_session(&session_id)
+        .map_err(|e| e.to_string())
 }
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct DockHistoryItem {
-    id: String,
-    created_at: i64,
-    environment_tag: String,
-    command_text: String,
+/// Check that a host can be reached the way a session would connect (DNS, TCP or jump host,
+/// identity file) without opening one.
+#[tauri::command]
+async fn host_preflight(state: State<'_, Arc<AppState>>, id: String) -> Result<preflight::Preflight, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = state
+            .db
+            .hosts_get(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "host not found".to_string())?;
+        let target = preflight::Target {
+            user: &host.username,
+            host: &host.hostname,
+            port: Some(host.port),
+            identity_file: host.identity_file.as_deref(),
+            extra_args: &[],
+        };
+        Ok(preflight::run(&target, &preflight::PreflightConfig::load(&state.db)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
+/// Resolve a DNS-expanded host's name into the targets a session can go to, for a picker. Pass
+/// the chosen target's address and port to `terminal_open_ssh`.
 #[tauri::command]
-fn dock_history_list(state: State<'_, Arc<AppState>>, limit: Option<i64>) -> Result<Vec<DockHistoryItem>, String> {
-    let lim = limit.unwrap_or(200).clamp(1, 500);
-    let rows = state.db.dock_history_list(lim).map_err(|e| e.to_string())?;
-    Ok(rows
-        .into_iter()
-        .map(|(id, created_at, environment_tag, command_text)| DockHistoryItem {
-            id,
-            created_at,
-            environment_tag,
-            command_text,
-        })
-        .collect())
+async fn host_resolve_targets(state: State<'_, Arc<AppState>>, id: String) -> Result<host_dns::ResolvedTargets, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = state
+            .db
+            .hosts_get(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "host not found".to_string())?;
+        host_dns::resolve(&host)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
+/// ssh sessions opened to a host and the address each went to, newest first.
 #[tauri::command]
-fn dock_history_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.dock_history_delete(&id).map_err(|e| e.to_string())
+fn hosts_connections(
+    state: State<'_, Arc<AppState>>,
+    host_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<db::HostConnection>, String> {
+    state
+        .db
+        .host_connections_list(&host_id, limit.unwrap_or(50).clamp(1, 200))
+        .map_err(|e| e.to_string())
 }
 
+/// Log in to each hop of a host's jump chain (`ProxyJump`) in turn, then the host, and report the
+/// first hop that fails and why.
 #[tauri::command]
-fn dock_history_clear(state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    state.db.dock_history_clear().map_err(|e| e.to_string())
+async fn host_verify_path(state: State<'_, Arc<AppState>>, id: String) -> Result<preflight::PathReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = state
+            .db
+            .hosts_get(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "host not found".to_string())?;
+        if !host.connection_type.is_ssh() {
+            return Err(format!("{} is a remote desktop host", host.label));
+        }
+        let extra_args = security_key::ssh_options(&host);
+        let target = preflight::Target {
+            user: &host.username,
+            host: &host.hostname,
+            port: Some(host.port),
+            identity_file: host.identity_file.as_deref(),
+            extra_args: &extra_args,
+        };
+        Ok(preflight::verify_path(&target))
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-fn terminal_open_local(
-    app: tauri::AppHandle,
-    state: State<'_, Arc<AppState>>,
-    environment_tag: Option<String>,
-) -> Result<String, String> {
-    let env = environment_tag.unwrap_or_else(|| "LOCAL".to_string());
-    let (initial_cols, initial_rows) = state
+fn sudo_assist_config_get(state: State<'_, Arc<AppState>>) -> sudo_assist::SudoAssistConfig {
+    sudo_assist::SudoAssistConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn sudo_assist_config_set(state: State<'_, Arc<AppState>>, config: sudo_assist::SudoAssistConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+#[tauri::command]
+fn transfer_enqueue(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, input: TransferCreate) -> Result<Transfer, String> {
+    transfers::enqueue(&app, state.inner(), input)
+}
+
+#[tauri::command]
+fn transfers_list(state: State<'_, Arc<AppState>>, limit: Option<i64>) -> Result<Vec<Transfer>, String> {
+    state
         .db
-        .terminal_prefs_get_size("local")
-        .map_err(|e| e.to_string())?
-        .map(|(c, r)| (Some(c), Some(r)))
-        .unwrap_or((None, None));
-    let sid = state
-        .terminal
-        .open_local(app, Some(env.clone()), initial_cols, initial_rows)
-        .map(|id| id.0)
-        .map_err(|e| e.to_string())?;
+        .transfers_list(limit.unwrap_or(50).clamp(1, 200))
+        .map_err(|e| e.to_string())
+}
 
-    // Persist non-secret per-scope prefs and map the runtime session id -> scope.
-    state.db.terminal_session_scope_set(&sid, "local").map_err(|e| e.to_string())?;
-    state.db.terminal_prefs_touch("local", &env).map_err(|e| e.to_string())?;
-    Ok(sid)
+#[tauri::command]
+fn transfer_pause(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, id: String) -> Result<Transfer, String> {
+    transfers::pause(&app, &state, &id)
+}
+
+#[tauri::command]
+fn transfer_resume(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, id: String) -> Result<Transfer, String> {
+    transfers::resume(&app, state.inner(), &id)
+}
+
+#[tauri::command]
+fn transfer_cancel(app: tauri::AppHandle, state: State<'_, Arc<AppState>>, id: String) -> Result<Transfer, String> {
+    transfers::cancel(&app, &state, &id)
+}
+
+/// Finished, paused or failed transfers only.
+#[tauri::command]
+fn transfer_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.transfers_delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sync_profiles_list(state: State<'_, Arc<AppState>>) -> Result<Vec<SyncProfile>, String> {
+    state.db.sync_profiles_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sync_profiles_create(state: State<'_, Arc<AppState>>, input: SyncProfileCreate) -> Result<SyncProfile, String> {
+    dir_sync::validate(&input.name, &input.local_path, &input.remote_path, &input.direction)?;
+    if state.db.hosts_get(&input.host_id).map_err(|e| e.to_string())?.is_none() {
+        return Err("host not found".to_string());
+    }
+    state.db.sync_profiles_create(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sync_profiles_update(state: State<'_, Arc<AppState>>, input: SyncProfile) -> Result<SyncProfile, String> {
+    dir_sync::validate(&input.name, &input.local_path, &input.remote_path, &input.direction)?;
+    state.db.sync_profiles_update(input).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sync_profiles_delete(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state.db.sync_profiles_delete(&id).map_err(|e| e.to_string())
+}
+
+/// Mirror a profile's folders; with `dry_run`, only report what would be copied.
+#[tauri::command]
+async fn sync_run(
+    state: State<'_, Arc<AppState>>,
+    profile_id: String,
+    dry_run: Option<bool>,
+) -> Result<dir_sync::SyncReport, String> {
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || dir_sync::run(&state, &profile_id, dry_run.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
+/// Follow a log file on a host; lines arrive as `log:lines` events for the returned tail id.
 #[tauri::command]
-fn terminal_open_ssh(
+fn log_tail_start(
     app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
-    user: String,
-    host: String,
-    port: Option<u16>,
-    identity_file: Option<String>,
-    extra_args: Vec<String>,
-    environment_tag: Option<String>,
-    host_id: Option<String>,
+    host_id: String,
+    path: String,
+    filters: Option<log_tail::LogFilters>,
+    backlog_lines: Option<u32>,
 ) -> Result<String, String> {
-    let env = environment_tag.unwrap_or_else(|| "UNKNOWN".to_string());
-    let scope = if let Some(hid) = host_id.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
-        format!("ssh:{hid}")
-    } else {
-        let p = port.unwrap_or(22);
-        format!("ssh:{user}@{host}:{p}")
+    log_tail::start(&app, state.inner(), &host_id, &path, &filters.unwrap_or_default(), backlog_lines)
+}
+
+#[tauri::command]
+fn log_tail_stop(state: State<'_, Arc<AppState>>, tail_id: String) -> Result<bool, String> {
+    Ok(state.log_tails.stop(&tail_id))
+}
+
+fn load_plugins(app: &tauri::AppHandle, state: &AppState) {
+    let dir = match arch::paths::app_data_dir(app) {
+        Ok(dir) => dir.join(plugins::DIR_NAME),
+        Err(e) => {
+            log::warn!("plugins unavailable: {e}");
+            return;
+        }
     };
+    // Created up front so there's somewhere obvious to drop plugins.
+    let _ = std::fs::create_dir_all(&dir);
+    state.plugins.load(&dir, &state.db);
+}
 
-    let (initial_cols, initial_rows) = state
-        .db
-        .terminal_prefs_get_size(&scope)
-        .map_err(|e| e.to_string())?
-        .map(|(c, r)| (Some(c), Some(r)))
-        .unwrap_or((None, None));
+#[tauri::command]
+fn plugins_list(state: State<'_, Arc<AppState>>) -> Vec<plugins::PluginInfo> {
+    state.plugins.list()
+}
 
-    let sid = state
-        .terminal
-        .open_ssh(
-            app,
-            user.clone(),
-            host.clone(),
-            port,
-            identity_file,
-            extra_args,
-            Some(env.clone()),
-            initial_cols,
-            initial_rows,
-        )
-        .map(|id| id.0)
-        .map_err(|e| e.to_string())?;
+/// Load the plugins folder again, picking up added, changed and removed files.
+#[tauri::command]
+fn plugins_reload(app: tauri::AppHandle, state: State<'_, Arc<AppState>>) -> Vec<plugins::PluginInfo> {
+    load_plugins(&app, &state);
+    state.plugins.list()
+}
 
-    state.db.terminal_session_scope_set(&sid, &scope).map_err(|e| e.to_string())?;
-    state.db.terminal_prefs_touch(&scope, &env).map_err(|e| e.to_string())?;
-    Ok(sid)
+#[tauri::command]
+fn plugins_set_enabled(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    enabled: bool,
+) -> Result<Vec<plugins::PluginInfo>, String> {
+    let mut config = plugins::PluginsConfig::load(&state.db);
+    config.disabled.retain(|n| *n != name);
+    if !enabled {
+        config.disabled.push(name);
+    }
+    config.save(&state.db)?;
+    load_plugins(&app, &state);
+    Ok(state.plugins.list())
 }
 
 #[tauri::command]
-fn terminal_write(
+fn preflight_config_get(state: State<'_, Arc<AppState>>) -> preflight::PreflightConfig {
+    preflight::PreflightConfig::load(&state.db)
+}
+
+#[tauri::command]
+fn preflight_config_set(state: State<'_, Arc<AppState>>, config: preflight::PreflightConfig) -> Result<(), String> {
+    config.save(&state.db)
+}
+
+/// Type a vault secret into a session, followed by Enter. The secret is read and written here so
+/// it never passes through the webview; nothing is added to history, and an echo of it in the
+/// session's output is masked.
+#[tauri::command]
+async fn terminal_type_secret(
+    window: tauri::Window,
     state: State<'_, Arc<AppState>>,
     session_id: String,
-    data: String,
-    origin: Option<String>,
-    dock_command_id: Option<String>,
-    dock_command_title: Option<String>,
-    dock_command_template: Option<String>,
+    vault_key: String,
 ) -> Result<(), String> {
-    // Update persisted "last command" only for CommandDock-origin runs.
-    if origin.as_deref() == Some("commanddock") {
-        if let Ok(Some(scope)) = state.db.terminal_session_scope_get(&session_id) {
-            let _ = state.db.terminal_prefs_update_last_command(
-                &scope,
-                dock_command_id.as_deref(),
-                dock_command_title.as_deref(),
-                dock_command_template.as_deref(),
-            );
-
-            // Also append to CommandDock history (local-only). This records only CommandDock "Run"
-            // actions (not typed keystrokes).
-            let mut cmd_text = data.clone();
-            cmd_text = cmd_text.replace('\r', "").trim().to_string();
-            if !cmd_text.is_empty() {
-                let env = state
-                    .db
-                    .terminal_prefs_get_env(&scope)
-                    .ok()
-                    .flatten()
-                    .unwrap_or_else(|| "UNKNOWN".to_string());
-                let _ = state.db.dock_history_add(
-                    Some(&scope),
-                    &env,
-                    &cmd_text,
-                    dock_command_id.as_deref(),
-                    dock_command_title.as_deref(),
-                    dock_command_template.as_deref(),
-                );
-            }
+    detach::check_owner(&state, &session_id, window.label())?;
+    state.vault_lock.touch();
+    state.app_lock.touch();
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let secret = secrets::get_verified(state.vault.as_ref(), &vault_key)?
+            .ok_or_else(|| format!("no secret stored under \"{vault_key}\""))?;
+        let secret = String::from_utf8(secret).map_err(|_| "the secret isn't text, so it can't be typed".to_string())?;
+        // A stored trailing newline would press Enter twice.
+        let secret = secret.trim_end_matches(['\r', '\n']);
+        if secret.contains(['\r', '\n']) {
+            return Err("the secret spans several lines, so it can't be typed".to_string());
         }
-    }
+        state.terminal.write_secret(&session_id, secret).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    if origin.is_some() {
-        state
-            .terminal
-            .write_with_meta(
-                &session_id,
-                &data,
-                crate::terminal::session_manager::WriteMeta { origin },
-            )
-            .map_err(|e| e.to_string())?;
-        return Ok(());
-    }
+/// Type the current code of a stored TOTP seed into a session, then Enter. Neither the seed nor the
+/// code goes through the frontend.
+#[tauri::command]
+async fn terminal_type_totp(
+    window: tauri::Window,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    vault_key: String,
+) -> Result<(), String> {
+    detach::check_owner(&state, &session_id, window.label())?;
+    state.vault_lock.touch();
+    state.app_lock.touch();
+    let state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let seed = secrets::get_verified(state.vault.as_ref(), &vault_key)?
+            .ok_or_else(|| format!("no secret stored under \"{vault_key}\""))?;
+        let code = totp::code(&seed)?;
+        state.terminal.write_secret(&session_id, &code.code).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    state.terminal.write(&session_id, &data).map_err(|e| e.to_string())
+/// The sessions open at the last exit, for offering to reopen them; `None` if there were none.
+#[tauri::command]
+fn session_restore_plan(state: State<'_, Arc<AppState>>) -> Result<Option<session_restore::RestorePlan>, String> {
+    session_restore::plan(&state)
 }
 
+/// Forget the saved sessions once they've been reopened or declined.
 #[tauri::command]
-fn terminal_resize(state: State<'_, Arc<AppState>>, session_id: String, cols: u16, rows: u16) -> Result<(), String> {
-    state
-        .terminal
-        .resize(&session_id, cols, rows)
-        .map_err(|e| e.to_string())?;
+fn session_restore_dismiss(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    session_restore::dismiss(&state.db)
+}
 
-    if let Ok(Some(scope)) = state.db.terminal_session_scope_get(&session_id) {
-        let _ = state.db.terminal_prefs_update_size(&scope, cols, rows);
-    }
-    Ok(())
+/// A workspace's saved tab and pane arrangement (default workspace: "main").
+#[tauri::command]
+fn layout_get(state: State<'_, Arc<AppState>>, workspace: Option<String>) -> Result<Option<layout::SavedLayout>, String> {
+    layout::get(&state.db, workspace.as_deref())
 }
 
 #[tauri::command]
-fn terminal_close(state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
-    state
-        .terminal
-        .close(&session_id)
-        .map_err(|e| e.to_string())?;
-    let _ = state.db.terminal_session_scope_delete(&session_id);
-    Ok(())
+fn layout_set(
+    state: State<'_, Arc<AppState>>,
+    workspace: Option<String>,
+    layout: layout::WorkspaceLayout,
+) -> Result<layout::SavedLayout, String> {
+    layout::set(&state.db, workspace.as_deref(), layout)
 }
 
 #[tauri::command]
-fn terminal_mark_exited(state: State<'_, Arc<AppState>>, session_id: String) -> Result<(), String> {
-    state.db.terminal_session_scope_delete(&session_id).map_err(|e| e.to_string())
+fn layout_list(state: State<'_, Arc<AppState>>) -> Result<Vec<layout::WorkspaceSummary>, String> {
+    layout::list(&state.db)
 }
 
 #[tauri::command]
-fn vault_set_secret(state: State<'_, Arc<AppState>>, key: String, secret_b64: String) -> Result<(), String> {
-    let bytes = base64::engine::general_purpose::STANDARD
-        .decode(secret_b64.as_bytes())
-        .map_err(|e| e.to_string())?;
-    state
-        .vault
-        .set_secret(&key, &bytes)
-        .map_err(|e| e.to_string())
+fn layout_delete(state: State<'_, Arc<AppState>>, workspace: String) -> Result<(), String> {
+    layout::delete(&state.db, &workspace)
 }
 
+/// Contexts in a kubeconfig on this machine (`None`: kubectl's default files).
 #[tauri::command]
-fn vault_get_secret(state: State<'_, Arc<AppState>>, key: String) -> Result<Option<String>, String> {
-    let bytes = state.vault.get_secret(&key).map_err(|e| e.to_string())?;
-    Ok(bytes.map(|b| base64::engine::general_purpose::STANDARD.encode(b)))
+fn k8s_contexts_list(kubeconfig: Option<String>) -> Result<Vec<k8s::KubeContext>, String> {
+    k8s::contexts(kubeconfig.as_deref())
 }
 
 #[tauri::command]
-fn vault_delete_secret(state: State<'_, Arc<AppState>>, key: String) -> Result<(), String> {
-    state
-        .vault
-        .delete_secret(&key)
-        .map_err(|e| e.to_string())
+fn k8s_prefs_list(state: State<'_, Arc<AppState>>) -> Result<Vec<db::K8sPref>, String> {
+    state.db.k8s_prefs_list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn k8s_prefs_set(
+    state: State<'_, Arc<AppState>>,
+    scope: String,
+    kubeconfig: Option<String>,
+    context: String,
+    namespace: Option<String>,
+) -> Result<db::K8sPref, String> {
+    k8s::set_pref(&state.db, &scope, kubeconfig.as_deref(), &context, namespace.as_deref())
+}
+
+#[tauri::command]
+fn k8s_prefs_delete(state: State<'_, Arc<AppState>>, scope: String) -> Result<(), String> {
+    state.db.k8s_prefs_delete(scope.trim()).map_err(|e| e.to_string())
+}
+
+/// Take the pending CLI action (if any); the UI calls this on load and on each `cli:action` event.
+#[tauri::command]
+fn cli_take_action(state: State<'_, Arc<AppState>>) -> Option<cli::CliAction> {
+    state.cli_action.lock().expect("poisoned cli action lock").take()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if let Some(code) = askpass::handle_invocation() {
+        std::process::exit(code);
+    }
+    if let Some(code) = cli::handle_args() {
+        std::process::exit(code);
+    }
     tauri::Builder::default()
+        // Must be registered first: a second copy exits here, before it opens the database.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            cli::handle_second_launch(app, &argv);
+        }))
         .setup(|app| {
-            let (db, _path) = Db::open(&app.handle()).map_err(|e| e.to_string())?;
-            let vault = vault::default_vault_provider();
+            crash::install(app.handle())?;
+            let (db, path) = Db::open(&app.handle()).map_err(|e| e.to_string())?;
+            logging::LogConfig::load(&db).apply();
+            log::info!("OpsPad {} starting (db: {})", app.package_info().version, path.display());
+            let db = Arc::new(db);
+            let vault_lock = Arc::new(vault::VaultLock::new(vault_autolock::locked_at_startup(&db)));
+            let vault = vault::default_vault_provider(db.clone(), vault_lock.clone());
+            let queue = queue::WorkQueue::default();
+            queue.load_config(&db);
             let state = Arc::new(AppState {
                 terminal: TerminalManager::new(),
                 db,
                 vault,
+                vault_lock,
+                app_lock: app_lock::AppLock::default(),
+                sequences: sequence::SequenceRuns::default(),
+                recordings: recording::Recordings::default(),
+                watches: dock_watch::DockWatches::default(),
+                macros: macros::Macros::default(),
+                latencies: latency::SessionLatencies::default(),
+                jobs: jobs::Jobs::default(),
+                queue,
+                cli_action: Mutex::new(None),
+                detached: detach::DetachedWindows::default(),
+                monitor: monitor::Monitor::default(),
+                http_checks: http_checks::HttpChecks::default(),
+                lan_share: lan_share::LanShare::default(),
+                broadcasts: broadcast::Broadcasts::default(),
+                clipboard: clipboard::ClipboardHistory::default(),
+                command_timings: command_timing::CommandTimings::default(),
+                transfers: transfers::Transfers::default(),
+                log_tails: log_tail::LogTails::default(),
+                plugins: plugins::Plugins::default(),
+                automation_api: automation_api::AutomationApi::default(),
+                rdp: rdp::RdpSessions::default(),
+                auth_prompts: askpass::AuthPrompts::default(),
             });
+            load_plugins(app.handle(), &state);
+            terminal::scrollback::apply(app.handle(), &state.db, &state.terminal);
+            if let Ok(root) = terminal::scrollback::root(app.handle()) {
+                terminal::scrollback::prune(&root, terminal::scrollback::ScrollbackConfig::load(&state.db).retention_days);
+            }
             app.manage(state);
+            if let Some(window) = app.get_webview_window("main") {
+                window_state::restore(&window, &app.state::<Arc<AppState>>().db, "main");
+                window_state::track(&window, "main");
+                let _ = window.show();
+            }
+            runbook_sync::spawn_poller(app.handle().clone());
+            subscriptions::spawn_poller(app.handle().clone());
+            vault_autolock::spawn_watcher(app.handle().clone());
+            app_lock::spawn_watcher(app.handle().clone());
+            terminal::watchdog::spawn_watcher(app.handle().clone());
+            terminal::idle::spawn_watcher(app.handle().clone());
+            network_watch::spawn_watcher(app.handle().clone());
+            latency::spawn_prober(app.handle().clone());
+            secrets::spawn_rotation_reminder(app.handle().clone());
+            host_expiry::spawn_sweeper(app.handle().clone());
+            retention::spawn_purger(app.handle().clone());
+            monitor::spawn_scheduler(app.handle().clone());
+            transfers::resume_queued(app.handle());
+            sampler::spawn_scheduler(app.handle().clone());
+            // Reports left by earlier runs, if the user opted in to uploading.
+            crash::spawn_upload();
+            if let Err(e) = cli::spawn_server(app.handle().clone()) {
+                log::warn!("cli endpoint unavailable: {e}");
+            }
+            if let Err(e) = automation_api::apply(app.handle(), &app.state::<Arc<AppState>>()) {
+                log::warn!("automation api unavailable: {e}");
+            }
+            db_changes::spawn_notifier(app.handle().clone());
+            global_hotkeys::apply_saved(app.handle(), &app.state::<Arc<AppState>>().db);
+            if let Err(e) = tray::install(app.handle()) {
+                log::warn!("tray icon unavailable: {e}");
+            }
+            deep_link::listen(app.handle());
+            if let Some(request) = cli::launch_request() {
+                let state = app.state::<Arc<AppState>>();
+                if let Err(e) = cli::dispatch(app.handle(), &state, &request) {
+                    log::warn!("launch arguments ignored: {e}");
+                }
+            }
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
+        .plugin(logging::plugin())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(global_hotkeys::on_shortcut)
+                .build(),
+        )
         .invoke_handler(tauri::generate_handler![
             greet,
             hosts_list,
+            hosts_count,
             hosts_create,
+            hosts_import,
+            inventory_sources_list,
+            inventory_source_save,
+            inventory_source_delete,
+            inventory_secret_set,
+            inventory_sync,
             hosts_delete,
             hosts_update,
+            hosts_set_credentials,
+            hosts_set_security_key,
+            security_key_support,
             hosts_reorder,
+            hosts_set_expiry,
+            hosts_archived_list,
+            hosts_unarchive,
+            hosts_set_pinned,
+            hosts_set_group,
+            host_groups_list,
+            host_groups_create,
+            host_groups_update,
+            host_groups_delete,
+            environments_list,
+            environments_upsert,
+            environments_delete,
+            four_eyes_identity,
+            four_eyes_approvers_list,
+            four_eyes_approver_add,
+            four_eyes_approver_remove,
+            four_eyes_request,
+            four_eyes_request_inspect,
+            four_eyes_approve,
             dock_commands_list,
             dock_commands_create,
             dock_commands_update,
+            dock_commands_set_params,
+            dock_commands_set_visibility,
+            dock_commands_set_category,
+            dock_commands_set_timeout,
+            shortcuts_list,
+            shortcuts_set,
+            shortcuts_clear,
+            global_hotkeys_get,
+            global_hotkeys_set,
+            command_pack_export,
+            command_pack_inspect,
+            command_pack_import,
+            dock_commands_for_scope,
+            dock_command_render,
+            dock_command_preview,
+            dock_param_values_get,
             dock_commands_delete,
             dock_commands_reorder,
             dock_runbook_get,
             dock_runbook_set,
+            runbooks_list,
+            runbooks_get,
+            runbooks_create,
+            runbooks_update,
+            runbooks_set_params,
+            runbook_render,
+            runbook_link_open,
+            runbook_export,
+            runbooks_delete,
+            runbooks_reorder,
+            runbook_sync_config_get,
+            runbook_sync_config_set,
+            runbook_sync_now,
+            runbook_sync_resolve,
+            sync_config_get,
+            sync_config_set,
+            sync_status,
+            sync_now,
+            subscriptions_list,
+            subscription_add,
+            subscription_refresh,
+            subscriptions_delete,
+            config_bundle_sign,
+            runbook_versions_list,
+            runbook_versions_get,
+            runbook_versions_diff,
+            runbook_versions_restore,
+            runbook_checklist_start,
+            runbook_checklist_toggle,
+            runbook_checklist_get,
+            runbook_checklists_list,
+            runbook_checklist_delete,
             dock_history_list,
+            dock_history_for_scope,
+            dock_history_aggregate,
+            dock_history_pin,
             dock_history_delete,
             dock_history_clear,
+            sequences_list,
+            sequences_create,
+            sequences_update,
+            sequences_delete,
+            sequence_preview,
+            sequence_run,
+            sequence_abort,
+            dock_watch_start,
+            dock_watch_stop,
+            dock_watch_list,
+            macros_list,
+            macros_create,
+            macros_update,
+            macros_delete,
+            macro_record_start,
+            macro_record_stop,
+            macro_record_list,
+            macro_preview,
+            macro_replay,
+            macro_replay_abort,
+            command_fanout,
+            fanout_runs_list,
+            fanout_run_get,
+            fanout_run_delete,
+            job_preview,
+            job_start,
+            jobs_list,
+            job_get,
+            job_cancel,
+            job_delete,
+            queue_config_get,
+            queue_config_set,
+            queue_status,
+            redaction_rules_list,
+            redaction_rules_create,
+            redaction_rules_update,
+            redaction_rules_delete,
+            command_lint,
+            lint_rules_list,
+            lint_rules_create,
+            lint_rules_update,
+            lint_rules_delete,
             terminal_open_local,
             terminal_open_ssh,
             terminal_write,
             terminal_resize,
+            terminal_rename,
+            terminal_set_readonly,
+            perf_metrics,
+            perf_metrics_reset,
+            perf_overlay_set,
+            terminal_benchmark,
             terminal_close,
+            terminal_detach,
+            terminal_detached_get,
+            terminal_attach,
             terminal_mark_exited,
             vault_set_secret,
             vault_get_secret,
+            vault_has_secret,
             vault_delete_secret,
+            vault_totp_set,
+            vault_totp_code,
+            vault_list_keys,
+            vault_set_label,
+            vault_set_require_presence,
+            vault_set_rotate_by,
+            vault_rotation_due,
+            vault_prune_orphans,
+            vault_status,
+            vault_lock,
+            vault_unlock,
+            vault_lock_config_get,
+            vault_lock_config_set,
+            vault_set_passphrase,
+            app_lock_status,
+            app_lock_engage,
+            app_unlock,
+            app_lock_config_get,
+            app_lock_config_set,
+            app_lock_set_pin,
+            logs_tail,
+            log_config_get,
+            log_config_set,
+            diagnostics_export,
+            crash_config_get,
+            crash_config_set,
+            crash_reports_list,
+            crash_reports_clear,
+            notification_config_get,
+            notification_config_set,
+            notification_test,
+            monitor_config_get,
+            monitor_config_set,
+            host_status_list,
+            host_status_history,
+            host_check_now,
+            http_checks_list,
+            http_checks_create,
+            http_checks_update,
+            http_checks_delete,
+            http_check_status_list,
+            http_check_history,
+            http_check_now,
+            sampler_config_get,
+            sampler_config_set,
+            host_metrics_latest,
+            host_metrics_sample_now,
+            alert_rules_list,
+            alert_rules_create,
+            alert_rules_update,
+            alert_rules_delete,
+            alerts_list,
+            alert_acknowledge,
+            alert_resolve,
+            slack_webhook_set,
+            slack_webhook_configured,
+            share_to_slack,
+            oncall_config_get,
+            oncall_config_set,
+            oncall_api_key_set,
+            oncall_api_key_configured,
+            oncall_incidents_list,
+            incident_start,
+            incident_end,
+            incident_active,
+            incidents_list,
+            confirmations_list,
+            timeline_export,
+            usage_report,
+            usage_report_csv,
+            suggest_config_get,
+            suggest_config_set,
+            suggest_api_key_set,
+            suggest_api_key_configured,
+            command_suggest,
+            lan_share_config_get,
+            lan_share_config_set,
+            session_share_start,
+            session_share_stop,
+            session_share_status,
+            session_export_markdown,
+            terminal_last_output,
+            recording_start,
+            recording_pause,
+            recording_resume,
+            recording_stop,
+            recording_list,
+            scrollback_config_get,
+            scrollback_config_set,
+            scrollback_sessions_list,
+            scrollback_page,
+            scrollback_delete,
+            retention_config_get,
+            retention_config_set,
+            storage_usage,
+            terminal_search,
+            terminal_commands,
+            terminal_watchdog_config_get,
+            terminal_watchdog_config_set,
+            idle_policy_get,
+            idle_policy_set,
+            terminal_probe,
+            network_watch_config_get,
+            network_watch_config_set,
+            server_info_config_get,
+            server_info_config_set,
+            latency_config_get,
+            latency_config_set,
+            terminal_latency_probe,
+            shell_integration_status,
+            shell_integration_install,
+            shell_integration_uninstall,
+            shell_integration_snippet,
+            auth_prompt_config_get,
+            auth_prompt_config_set,
+            auth_prompts_pending,
+            auth_prompt_answer,
+            rdp_open,
+            rdp_sessions_list,
+            rdp_close,
+            terminal_open_group,
+            terminal_broadcast_list,
+            terminal_broadcast_write,
+            terminal_broadcast_remove,
+            session_restore_plan,
+            session_restore_dismiss,
+            layout_get,
+            layout_set,
+            layout_list,
+            layout_delete,
+            k8s_contexts_list,
+            k8s_prefs_list,
+            k8s_prefs_set,
+            k8s_prefs_delete,
+            clipboard_history_config_get,
+            clipboard_history_config_set,
+            clipboard_history_add,
+            clipboard_history_list,
+            clipboard_history_clear,
+            palette_query,
+            terminal_mark,
+            terminal_marks_list,
+            terminal_type_secret,
+            terminal_type_totp,
+            host_preflight,
+            host_resolve_targets,
+            hosts_connections,
+            host_verify_path,
+            preflight_config_get,
+            preflight_config_set,
+            sudo_assist_config_get,
+            sudo_assist_config_set,
+            transfer_enqueue,
+            transfers_list,
+            transfer_pause,
+            transfer_resume,
+            transfer_cancel,
+            transfer_delete,
+            sync_profiles_list,
+            sync_profiles_create,
+            sync_profiles_update,
+            sync_profiles_delete,
+            sync_run,
+            log_tail_start,
+            log_tail_stop,
+            highlight_rules_list,
+            highlight_rules_create,
+            highlight_rules_update,
+            highlight_rules_delete,
+            snippets_list,
+            snippets_create,
+            snippets_update,
+            snippets_delete,
+            terminal_expand,
+            plugins_list,
+            plugins_reload,
+            plugins_set_enabled,
+            automation_api_config_get,
+            automation_api_config_set,
+            automation_api_status,
+            automation_api_rotate_token,
+            cli_take_action,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<Arc<AppState>>() {
+                    session_restore::save(&state);
+                }
+            }
+        });
 }
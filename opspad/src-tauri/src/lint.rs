@@ -0,0 +1,206 @@
+//! Regex-based linting of commands before CommandDock writes them to a terminal.
+//!
+//! Rules are user-managed and stored in SQLite (`lint_rules`). A rule matches when `pattern`
+//! matches and `unless` (if set) does not, which covers "missing `--dry-run`"-style checks.
+//! "danger" findings make a command count as destructive for environment policies.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::db::LintRule;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintSeverity {
+    #[default]
+    Warning,
+    Danger,
+}
+
+impl LintSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Danger => "danger",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "danger" => Self::Danger,
+            _ => Self::Warning,
+        }
+    }
+}
+
+/// Built-in rule seeded on first run.
+pub struct DefaultRule {
+    pub name: &'static str,
+    pub pattern: &'static str,
+    pub unless: Option<&'static str>,
+    pub severity: LintSeverity,
+    pub message: &'static str,
+}
+
+pub const DEFAULT_RULES: &[DefaultRule] = &[
+    DefaultRule {
+        name: "Recursive delete of /",
+        pattern: r"\brm\s+(?:-\S+\s+)*-[a-zA-Z]*[rR][a-zA-Z]*\s+(?:-\S+\s+)*/(?:\*|\s|$)",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Recursively deletes from the filesystem root.",
+    },
+    DefaultRule {
+        name: "Recursive force delete",
+        pattern: r"\brm\s+(?:-\S+\s+)*-[a-zA-Z]*(?:rf|fr|Rf|fR)",
+        unless: None,
+        severity: LintSeverity::Warning,
+        message: "Recursive forced delete; double-check the path.",
+    },
+    DefaultRule {
+        name: "SQL DROP",
+        pattern: r"(?i)\bdrop\s+(?:table|database|schema)\b",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Drops a table/database/schema.",
+    },
+    DefaultRule {
+        name: "SQL TRUNCATE",
+        pattern: r"(?i)\btruncate\s+(?:table\s+)?\w",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Removes all rows from a table.",
+    },
+    DefaultRule {
+        name: "DELETE without WHERE",
+        pattern: r"(?i)\bdelete\s+from\s+\S+\s*(?:;|$)",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "DELETE without a WHERE clause removes every row.",
+    },
+    DefaultRule {
+        name: "kubectl delete namespace",
+        pattern: r"\bkubectl\b.*\bdelete\s+(?:ns|namespaces?)\b",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Deletes a namespace and everything in it.",
+    },
+    DefaultRule {
+        name: "kubectl change without --dry-run",
+        pattern: r"\bkubectl\b.*\b(?:apply|delete|replace|patch|scale|drain)\b",
+        unless: Some(r"--dry-run"),
+        severity: LintSeverity::Warning,
+        message: "Consider a --dry-run first.",
+    },
+    DefaultRule {
+        name: "Terraform destroy / auto-approve",
+        pattern: r"\bterraform\s+(?:destroy\b|apply\b.*-auto-approve)",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Applies infrastructure changes without review.",
+    },
+    DefaultRule {
+        name: "Disk wipe",
+        pattern: r"\b(?:mkfs(?:\.\w+)?\s|dd\s+.*\bof=/dev/)",
+        unless: None,
+        severity: LintSeverity::Danger,
+        message: "Overwrites a block device.",
+    },
+    DefaultRule {
+        name: "git force push",
+        pattern: r"\bgit\s+push\b.*(?:--force\b|\s-f\b)",
+        unless: Some(r"--force-with-lease"),
+        severity: LintSeverity::Warning,
+        message: "Force push rewrites remote history; prefer --force-with-lease.",
+    },
+    DefaultRule {
+        name: "Recursive chmod 777",
+        pattern: r"\bchmod\s+-R\s+0?777\b",
+        unless: None,
+        severity: LintSeverity::Warning,
+        message: "Makes a whole tree world-writable.",
+    },
+    DefaultRule {
+        name: "Shutdown / reboot",
+        pattern: r"\b(?:shutdown|reboot|halt|poweroff)\b",
+        unless: None,
+        severity: LintSeverity::Warning,
+        message: "Takes the machine down.",
+    },
+];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintFinding {
+    pub rule_id: String,
+    pub name: String,
+    pub message: String,
+    pub severity: LintSeverity,
+    /// The text the rule matched.
+    pub matched: String,
+}
+
+struct CompiledRule {
+    rule: LintRule,
+    pattern: Regex,
+    unless: Option<Regex>,
+}
+
+/// Compiled set of enabled lint rules.
+pub struct Linter {
+    rules: Vec<CompiledRule>,
+}
+
+impl Linter {
+    pub fn new(rules: &[LintRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| {
+                let pattern = Regex::new(&r.pattern).ok()?;
+                let unless = match r.unless.as_deref().filter(|u| !u.trim().is_empty()) {
+                    Some(u) => Some(Regex::new(u).ok()?),
+                    None => None,
+                };
+                Some(CompiledRule {
+                    rule: r.clone(),
+                    pattern,
+                    unless,
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn lint(&self, text: &str) -> Vec<LintFinding> {
+        let mut out = Vec::new();
+        for c in &self.rules {
+            let Some(m) = c.pattern.find(text) else {
+                continue;
+            };
+            if c.unless.as_ref().is_some_and(|u| u.is_match(text)) {
+                continue;
+            }
+            out.push(LintFinding {
+                rule_id: c.rule.id.clone(),
+                name: c.rule.name.clone(),
+                message: c.rule.message.clone(),
+                severity: c.rule.severity,
+                matched: m.as_str().to_string(),
+            });
+        }
+        out
+    }
+}
+
+/// Validate user-supplied rule patterns before they are persisted.
+pub fn validate_rule(pattern: &str, unless: Option<&str>) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("lint pattern must not be empty".to_string());
+    }
+    Regex::new(pattern).map_err(|e| format!("invalid lint pattern: {e}"))?;
+    if let Some(u) = unless.filter(|u| !u.trim().is_empty()) {
+        Regex::new(u).map_err(|e| format!("invalid 'unless' pattern: {e}"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Following a log file on a host without a terminal: `tail -F` runs over ssh in the background
+//! and its output comes back as parsed lines.
+//!
+//! Each line gets the timestamp and level found in it, if any, and is dropped here when the tail's
+//! filters say so, so a noisy log doesn't flood the webview. Lines arrive in batches as
+//! `log:lines`; `log:ended` follows when the tail stops for any reason.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::arch::ssh;
+use crate::exec::{self, OutputSink, OutputStream};
+use crate::AppState;
+
+const DEFAULT_BACKLOG_LINES: u32 = 100;
+const MAX_BACKLOG_LINES: u32 = 10_000;
+/// A tail left running is stopped after this long.
+const MAX_TAIL_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+/// Longer lines are cut; a runaway line mustn't hold the whole buffer.
+const MAX_LINE_CHARS: usize = 8 * 1024;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilters {
+    /// Regexes; a line must match at least one (when any are given).
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Regexes; a line matching any is dropped.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Drop lines whose detected level is below this ("debug", "info", "warn", ...). Lines with no
+    /// level (stack traces, continuations) are kept.
+    #[serde(default)]
+    pub min_level: Option<String>,
+}
+
+struct CompiledFilters {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    min_level: Option<u8>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    pub text: String,
+    /// As written in the line, in whichever common format it uses.
+    pub timestamp: Option<String>,
+    /// "trace" | "debug" | "info" | "warn" | "error" | "fatal"
+    pub level: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLinesEvent {
+    pub tail_id: String,
+    pub lines: Vec<LogLine>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEndedEvent {
+    pub tail_id: String,
+    /// Why it stopped, unless it was stopped on request.
+    pub error: Option<String>,
+}
+
+/// Stop flags for running tails, keyed by tail id.
+#[derive(Default)]
+pub struct LogTails {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl LogTails {
+    pub fn stop(&self, tail_id: &str) -> bool {
+        match self.active.lock().unwrap_or_else(|e| e.into_inner()).get(tail_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "fatal"];
+
+fn level_rank(level: &str) -> Option<u8> {
+    LEVELS.iter().position(|l| *l == level).map(|i| i as u8)
+}
+
+fn timestamp_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(concat!(
+            // ISO 8601 / RFC 3339: 2024-05-01T12:00:00.123Z, 2024-05-01 12:00:00,123
+            r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?",
+            // Common log format: 01/May/2024:12:00:00 +0000
+            r"|\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4}",
+            // syslog: May  1 12:00:00
+            r"|[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}",
+        ))
+        .expect("valid timestamp regex")
+    })
+}
+
+fn level_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        RegexBuilder::new(r"\b(trace|debug|info|notice|warn|warning|err|error|crit|critical|alert|emerg|fatal|panic)\b")
+            .case_insensitive(true)
+            .build()
+            .expect("valid level regex")
+    })
+}
+
+fn detect_level(line: &str) -> Option<&'static str> {
+    let word = level_re().find(line)?.as_str().to_ascii_lowercase();
+    Some(match word.as_str() {
+        "trace" => "trace",
+        "debug" => "debug",
+        "info" | "notice" => "info",
+        "warn" | "warning" => "warn",
+        "err" | "error" => "error",
+        _ => "fatal",
+    })
+}
+
+fn parse_line(text: &str) -> LogLine {
+    let text: String = text.chars().take(MAX_LINE_CHARS).collect();
+    LogLine {
+        timestamp: timestamp_re().find(&text).map(|m| m.as_str().to_string()),
+        level: detect_level(&text),
+        text,
+    }
+}
+
+fn compile(filters: &LogFilters) -> Result<CompiledFilters, String> {
+    let compile_all = |patterns: &[String]| -> Result<Vec<Regex>, String> {
+        patterns
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| Regex::new(p).map_err(|e| format!("invalid filter {p:?}: {e}")))
+            .collect()
+    };
+    let min_level = match filters.min_level.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        Some(level) => Some(level_rank(&level.to_ascii_lowercase()).ok_or_else(|| format!("unknown level: {level}"))?),
+        None => None,
+    };
+    Ok(CompiledFilters {
+        include: compile_all(&filters.include)?,
+        exclude: compile_all(&filters.exclude)?,
+        min_level,
+    })
+}
+
+impl CompiledFilters {
+    fn keep(&self, line: &LogLine) -> bool {
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(&line.text)) {
+            return false;
+        }
+        if self.exclude.iter().any(|re| re.is_match(&line.text)) {
+            return false;
+        }
+        match (self.min_level, line.level.and_then(level_rank)) {
+            (Some(min), Some(rank)) => rank >= min,
+            _ => true,
+        }
+    }
+}
+
+/// Complete lines out of `buffer`, leaving a trailing partial one for the next chunk.
+fn take_lines(buffer: &mut String) -> Vec<String> {
+    let Some(end) = buffer.rfind('\n') else {
+        return Vec::new();
+    };
+    let complete: String = buffer.drain(..=end).collect();
+    complete.lines().map(|l| l.trim_end_matches('\r').to_string()).collect()
+}
+
+/// Start following `path` on a host. Returns the tail id that events carry.
+pub fn start(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    host_id: &str,
+    path: &str,
+    filters: &LogFilters,
+    backlog_lines: Option<u32>,
+) -> Result<String, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("path is required".to_string());
+    }
+    let filters = Arc::new(compile(filters)?);
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    let backlog = backlog_lines.unwrap_or(DEFAULT_BACKLOG_LINES).min(MAX_BACKLOG_LINES);
+    let command = format!("exec tail -n {backlog} -F -- {}", ssh::shell_quote(path));
+    let program = ssh::ssh_program_checked()?;
+    let args = ssh::exec_args(
+        &host.username,
+        &host.hostname,
+        Some(host.port),
+        host.identity_file.as_deref(),
+        &command,
+    );
+
+    let tail_id = Uuid::new_v4().to_string();
+    let stop = Arc::new(AtomicBool::new(false));
+    state
+        .log_tails
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(tail_id.clone(), stop.clone());
+
+    let buffer = Arc::new(Mutex::new(String::new()));
+    let emit_lines = {
+        let app = app.clone();
+        let tail_id = tail_id.clone();
+        let filters = filters.clone();
+        move |raw: Vec<String>| {
+            let lines: Vec<LogLine> = raw.iter().map(|l| parse_line(l)).filter(|l| filters.keep(l)).collect();
+            if !lines.is_empty() {
+                let _ = app.emit(
+                    "log:lines",
+                    LogLinesEvent {
+                        tail_id: tail_id.clone(),
+                        lines,
+                    },
+                );
+            }
+        }
+    };
+    let sink: OutputSink = {
+        let buffer = buffer.clone();
+        let emit_lines = emit_lines.clone();
+        Arc::new(move |stream, data| {
+            if matches!(stream, OutputStream::Stderr) {
+                return;
+            }
+            let lines = {
+                let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                buffer.push_str(data);
+                let lines = take_lines(&mut buffer);
+                if buffer.len() > MAX_LINE_CHARS * 4 {
+                    buffer.clear();
+                }
+                lines
+            };
+            emit_lines(lines);
+        })
+    };
+
+    let app = app.clone();
+    let state = state.clone();
+    let id = tail_id.clone();
+    thread::spawn(move || {
+        let result = exec::run_with(&program, &args, MAX_TAIL_DURATION, Some(&stop), Some(sink));
+        let rest = std::mem::take(&mut *buffer.lock().unwrap_or_else(|e| e.into_inner()));
+        if !rest.is_empty() {
+            emit_lines(vec![rest]);
+        }
+        state.log_tails.active.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        let error = match result {
+            Ok(out) if out.cancelled => None,
+            Ok(out) if out.timed_out => Some("stopped after 24 hours".to_string()),
+            Ok(out) => Some(match out.stderr.lines().map(str::trim).rfind(|l| !l.is_empty()) {
+                Some(line) => line.to_string(),
+                None => format!("tail exited ({})", out.exit_code.map_or("killed".to_string(), |c| c.to_string())),
+            }),
+            Err(e) => Some(format!("failed to start ssh: {e}")),
+        };
+        let _ = app.emit(
+            "log:ended",
+            LogEndedEvent {
+                tail_id: id,
+                error,
+            },
+        );
+    });
+    Ok(tail_id)
+}
@@ -0,0 +1,123 @@
+//! Application log: `log` records go to stdout and to a size-rotated file in the app log dir.
+//!
+//! The logger is installed at the most verbose level; the level the user picks in settings is
+//! applied on top with `log::set_max_level`, so it can change without a restart.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::db::Db;
+
+const SETTINGS_KEY: &str = "logging";
+const LOG_FILE_NAME: &str = "opspad";
+const MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+const KEEP_FILES: usize = 5;
+/// `logs_tail` reads at most this much from the end of the file.
+const TAIL_READ_BYTES: u64 = 1024 * 1024;
+pub const MAX_TAIL_LINES: usize = 5000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogConfig {
+    #[serde(default)]
+    pub level: LogLevel,
+}
+
+impl LogConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())?;
+        self.apply();
+        Ok(())
+    }
+
+    pub fn apply(&self) {
+        log::set_max_level(self.level.filter());
+    }
+}
+
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_log::Builder::new()
+        .clear_targets()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_FILE_NAME.to_string()),
+            }),
+        ])
+        .level(LevelFilter::Trace)
+        // Dependencies are chatty at debug level; keep them at info unless something's wrong.
+        .level_for("tao", LevelFilter::Info)
+        .level_for("ureq", LevelFilter::Info)
+        .level_for("rustls", LevelFilter::Info)
+        .max_file_size(MAX_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_FILES))
+        .build()
+}
+
+pub fn log_file(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(format!("{LOG_FILE_NAME}.log")))
+}
+
+/// The last `n` lines of the current log file (older rotated files aren't read).
+pub fn tail(app: &AppHandle, n: usize) -> Result<Vec<String>, String> {
+    let n = n.min(MAX_TAIL_LINES);
+    let mut file = match File::open(log_file(app)?) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let start = len.saturating_sub(TAIL_READ_BYTES);
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if start > 0 && !lines.is_empty() {
+        // The first line was cut mid-way by the read window.
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
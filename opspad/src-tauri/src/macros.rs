@@ -0,0 +1,420 @@
+//! Input macros: record what's typed into one session and replay it into another.
+//!
+//! While a session is being recorded, every terminal write from the UI (keystrokes and
+//! CommandDock runs alike) is captured. Input is grouped into steps that end at Enter or a control
+//! key like Ctrl-C, each remembering the pause before it so a replay keeps the original pacing
+//! (scaled by a speed factor). Input typed at a password prompt is never captured. Before a replay
+//! sends anything, every line is checked against the target session's environment policy like an
+//! ad-hoc CommandDock command; a dry run shows the steps and what the policy makes of them without
+//! writing. Progress is reported as `macro:progress` events.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::db::{Macro, MacroStep};
+use crate::terminal::session_manager::WriteMeta;
+use crate::{policy, recording, AppState};
+
+const MAX_STEPS: usize = 1000;
+/// Longest pause before a step.
+const MAX_DELAY_MS: u64 = 24 * 60 * 60 * 1000;
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 10.0;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a recording captured, for saving as a macro.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroRecording {
+    pub session_id: String,
+    pub steps: Vec<MacroStep>,
+    /// Lines typed at a password prompt, which were left out.
+    pub skipped_secret_inputs: usize,
+}
+
+struct Recorder {
+    steps: Vec<MacroStep>,
+    /// Input since the last step ended, and when its first key came in.
+    current: String,
+    current_delay: u64,
+    last_input: Instant,
+    /// The session's last output line is a password prompt.
+    at_secret_prompt: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    skipped_secret_inputs: usize,
+}
+
+impl Recorder {
+    fn capture(&mut self, data: &str) {
+        let now = Instant::now();
+        if self.at_secret_prompt.load(Ordering::SeqCst) {
+            if data.contains(['\r', '\n']) {
+                self.skipped_secret_inputs += 1;
+                self.at_secret_prompt.store(false, Ordering::SeqCst);
+            }
+            self.last_input = now;
+            return;
+        }
+        if self.steps.len() >= MAX_STEPS {
+            return;
+        }
+        if self.current.is_empty() {
+            self.current_delay = (now.duration_since(self.last_input).as_millis() as u64).min(MAX_DELAY_MS);
+        }
+        self.last_input = now;
+        self.current.push_str(data);
+        // Enter and control keys (other than Tab, Backspace and Escape sequences) end a step.
+        if data.chars().any(|c| c.is_control() && !matches!(c, '\t' | '\x08' | '\x7f' | '\x1b')) {
+            self.end_step();
+        }
+    }
+
+    fn end_step(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        self.steps.push(MacroStep {
+            text: std::mem::take(&mut self.current),
+            delay_ms: self.current_delay,
+        });
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroProgressEvent {
+    pub run_id: String,
+    pub macro_id: String,
+    pub session_id: String,
+    /// Zero-based step index (equal to `total` once the run has finished).
+    pub step: usize,
+    pub total: usize,
+    /// "running" | "completed" | "failed" | "aborted"
+    pub status: String,
+    pub message: Option<String>,
+}
+
+/// Sessions being recorded (by session id) and replays in flight (by run id).
+#[derive(Default)]
+pub struct Macros {
+    recording: Mutex<HashMap<String, Recorder>>,
+    runs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl Macros {
+    /// Capture a UI write into `session_id`, if it's being recorded.
+    pub fn capture(&self, session_id: &str, data: &str) {
+        if let Some(recorder) = self.recording.lock().unwrap_or_else(|e| e.into_inner()).get_mut(session_id) {
+            recorder.capture(data);
+        }
+    }
+
+    pub fn recording(&self) -> Vec<String> {
+        self.recording.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect()
+    }
+
+    /// Stop recording `session_id` and hand back what was captured; `None` if it wasn't recording.
+    pub fn stop_recording(&self, session_id: &str) -> Option<MacroRecording> {
+        let mut recorder = self.recording.lock().unwrap_or_else(|e| e.into_inner()).remove(session_id)?;
+        recorder.stop.store(true, Ordering::SeqCst);
+        recorder.end_step();
+        Some(MacroRecording {
+            session_id: session_id.to_string(),
+            steps: recorder.steps,
+            skipped_secret_inputs: recorder.skipped_secret_inputs,
+        })
+    }
+
+    pub fn abort(&self, run_id: &str) -> bool {
+        match self.runs.lock().unwrap_or_else(|e| e.into_inner()).get(run_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Start capturing what's typed into `session_id`.
+pub fn start_recording(state: &AppState, session_id: &str) -> Result<(), String> {
+    let output = state.terminal.subscribe_output(session_id).map_err(|e| e.to_string())?;
+    let at_secret_prompt = Arc::new(AtomicBool::new(false));
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut recording = state.macros.recording.lock().unwrap_or_else(|e| e.into_inner());
+        if recording.contains_key(session_id) {
+            return Err("this session is already being recorded".to_string());
+        }
+        recording.insert(
+            session_id.to_string(),
+            Recorder {
+                steps: Vec::new(),
+                current: String::new(),
+                current_delay: 0,
+                last_input: Instant::now(),
+                at_secret_prompt: at_secret_prompt.clone(),
+                stop: stop.clone(),
+                skipped_secret_inputs: 0,
+            },
+        );
+    }
+
+    // Follows the output only to notice password prompts.
+    thread::spawn(move || {
+        while let Ok(chunk) = output.recv() {
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            let last_line = chunk.rsplit(['\n', '\r']).next().unwrap_or(&chunk);
+            let plain = crate::transcript::clean(last_line);
+            if !plain.is_empty() || chunk.contains(['\n', '\r']) {
+                at_secret_prompt.store(recording::prompt_re().is_match(plain.trim_end_matches(' ')), Ordering::SeqCst);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// `text` with control characters made visible (`⏎` for Enter, `^C` and the like).
+fn visible(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\r' | '\n' => out.push('⏎'),
+            '\t' => out.push('⇥'),
+            '\x7f' => out.push_str("^?"),
+            c if (c as u32) < 0x20 => {
+                out.push('^');
+                out.push((b'@' + c as u8) as char);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The line a step submits, if it ends with Enter.
+fn submitted_line(step: &MacroStep) -> Option<&str> {
+    step.text
+        .strip_suffix(['\r', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+}
+
+/// Steps as saved or replayed: at most [`MAX_STEPS`] of them, none waiting over a day.
+pub fn validate_steps(steps: &[MacroStep]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Err("macro has no steps".to_string());
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(format!("a macro can have at most {MAX_STEPS} steps"));
+    }
+    if let Some(i) = steps.iter().position(|s| s.delay_ms > MAX_DELAY_MS) {
+        return Err(format!("step {}: the pause must be at most 24 hours", i + 1));
+    }
+    Ok(())
+}
+
+fn check_speed(speed: Option<f64>) -> Result<f64, String> {
+    let speed = speed.unwrap_or(1.0);
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(format!("speed must be between {MIN_SPEED} and {MAX_SPEED}"));
+    }
+    Ok(speed)
+}
+
+fn scaled(delay_ms: u64, speed: f64) -> u64 {
+    (delay_ms as f64 / speed).round() as u64
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroPreviewStep {
+    /// The step's input with control keys made visible.
+    pub display: String,
+    /// Pause before it, at the chosen speed.
+    pub delay_ms: u64,
+    /// When it's sent, counted from the start of the replay.
+    pub at_ms: u64,
+    /// The line counts as destructive in the target session's environment.
+    pub destructive: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroPreview {
+    pub environment: String,
+    pub steps: Vec<MacroPreviewStep>,
+    pub total_ms: u64,
+    /// Hash of the submitted lines together; sent back with the replay's confirmation.
+    pub command_hash: String,
+}
+
+/// The lines a replay of `m` submits, as one text for its confirmation.
+fn submitted_text(m: &Macro) -> String {
+    policy::joined_commands(m.steps.iter().filter_map(submitted_line))
+}
+
+/// Dry run: what replaying `m` into `session_id` at `speed` would send, and when.
+pub fn preview(state: &AppState, m: &Macro, session_id: Option<&str>, speed: Option<f64>) -> Result<MacroPreview, String> {
+    let speed = check_speed(speed)?;
+    let environment = match session_id {
+        Some(id) => policy::session_environment(&state.db, id)?,
+        None => "UNKNOWN".to_string(),
+    };
+    let mut at_ms = 0;
+    let mut steps = Vec::with_capacity(m.steps.len());
+    for step in &m.steps {
+        let delay_ms = scaled(step.delay_ms, speed);
+        at_ms += delay_ms;
+        let destructive = match submitted_line(step) {
+            Some(line) => policy::assess(&state.db, &environment, line.to_string(), false)?.decision.destructive,
+            None => false,
+        };
+        steps.push(MacroPreviewStep {
+            display: visible(&step.text),
+            delay_ms,
+            at_ms,
+            destructive,
+        });
+    }
+    Ok(MacroPreview {
+        environment,
+        steps,
+        total_ms: at_ms,
+        command_hash: policy::command_hash(&submitted_text(m)),
+    })
+}
+
+/// Replay `m` into `session_id` in the background. Returns the run id.
+///
+/// Every submitted line is checked against the session's environment policy first;
+/// `confirmation` and `approval` stand for the whole run, as for a sequence, and a confirmation
+/// comes with the [`preview`]'s hash as `confirmed_hash`.
+#[allow(clippy::too_many_arguments)]
+pub fn replay(
+    app: AppHandle,
+    state: Arc<AppState>,
+    mut m: Macro,
+    session_id: String,
+    speed: Option<f64>,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<String, String> {
+    validate_steps(&m.steps)?;
+    let speed = check_speed(speed)?;
+    state.terminal.check_writable(&session_id).map_err(|e| e.to_string())?;
+    policy::check_confirmed(&submitted_text(&m), confirmation, confirmed_hash)?;
+
+    let environment = policy::session_environment(&state.db, &session_id)?;
+    let scope = state.db.terminal_session_scope_get(&session_id).map_err(|e| e.to_string())?;
+    let mut confirmations = Vec::new();
+    for (i, step) in m.steps.iter_mut().enumerate() {
+        if submitted_line(step).is_none() {
+            continue;
+        }
+        step.text = crate::command_plugins(
+            &state,
+            &step.text,
+            Some(&session_id),
+            scope.as_deref(),
+            Some(&environment),
+            None,
+        )
+        .map_err(|e| format!("step {}: {e}", i + 1))?;
+        let Some(line) = submitted_line(step) else {
+            continue;
+        };
+        let approved = policy::check_session_write(&state.db, &session_id, None, line, confirmation, approval)
+            .map_err(|e| format!("step {}: {e}", i + 1))?;
+        if let Some(approved) = approved {
+            confirmations.push(crate::db::Confirmation {
+                session_id: Some(session_id.clone()),
+                scope: scope.clone(),
+                ..policy::confirmation(&approved, &environment, line)
+            });
+        }
+    }
+    for c in confirmations {
+        state.db.confirmations_add(c).map_err(|e| e.to_string())?;
+    }
+
+    let run_id = Uuid::new_v4().to_string();
+    let abort = Arc::new(AtomicBool::new(false));
+    state
+        .macros
+        .runs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(run_id.clone(), abort.clone());
+
+    let run_id2 = run_id.clone();
+    thread::spawn(move || {
+        let total = m.steps.len();
+        let emit = |step: usize, status: &str, message: Option<String>| {
+            let _ = app.emit(
+                "macro:progress",
+                MacroProgressEvent {
+                    run_id: run_id2.clone(),
+                    macro_id: m.id.clone(),
+                    session_id: session_id.clone(),
+                    step,
+                    total,
+                    status: status.to_string(),
+                    message,
+                },
+            );
+        };
+
+        let mut outcome = Ok(());
+        'steps: for (i, step) in m.steps.iter().enumerate() {
+            let until = Instant::now() + Duration::from_millis(scaled(step.delay_ms, speed));
+            loop {
+                if abort.load(Ordering::SeqCst) {
+                    outcome = Err((i, None));
+                    break 'steps;
+                }
+                let now = Instant::now();
+                if now >= until {
+                    break;
+                }
+                thread::sleep(POLL_INTERVAL.min(until - now));
+            }
+            emit(i, "running", Some(visible(&step.text)));
+            let written = state.terminal.write_with_meta(
+                &session_id,
+                &step.text,
+                WriteMeta {
+                    origin: Some("macro".to_string()),
+                },
+            );
+            if let Err(e) = written {
+                outcome = Err((i, Some(e.to_string())));
+                break;
+            }
+        }
+
+        state
+            .macros
+            .runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&run_id2);
+        match outcome {
+            Ok(()) => emit(total, "completed", None),
+            Err((i, None)) => emit(i, "aborted", None),
+            Err((i, Some(msg))) => emit(i, "failed", Some(msg)),
+        }
+    });
+
+    Ok(run_id)
+}
@@ -0,0 +1,388 @@
+//! Host health monitor: a background scheduler that checks every host on an interval (TCP connect
+//! to the ssh port, ssh banner, or ICMP ping) and reports up/slow/down.
+//!
+//! Current statuses live in memory; only transitions are stored (`host_status_events`) and emitted
+//! as `host:status`, so the host list can badge hosts without polling. Off until enabled in
+//! settings, since probing production hosts unannounced can trip intrusion detection.
+//!
+//! Each round also runs the HTTP service checks ([`http_checks`]).
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::ping;
+use crate::db::{Db, Host, HostSort};
+use crate::{alerts, exec, http_checks, AppState};
+
+const SETTINGS_KEY: &str = "monitor";
+/// How often the scheduler wakes to see whether a round is due.
+const TICK: Duration = Duration::from_secs(5);
+const MIN_INTERVAL_SECS: u64 = 15;
+const MAX_INTERVAL_SECS: u64 = 3600;
+/// Hosts checked at once.
+const PARALLEL_CHECKS: usize = 16;
+const MAX_BANNER_BYTES: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckKind {
+    /// TCP connect to the host's ssh port.
+    Tcp,
+    /// TCP connect plus an `SSH-` banner, so a port that accepts but never answers is down.
+    SshBanner,
+    Icmp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Up,
+    Slow,
+    Down,
+}
+
+impl Status {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Status::Up => "up",
+            Status::Slow => "slow",
+            Status::Down => "down",
+        }
+    }
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+fn default_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_slow_ms() -> u64 {
+    500
+}
+
+fn default_check() -> CheckKind {
+    CheckKind::Tcp
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Answers slower than this count as "slow".
+    #[serde(default = "default_slow_ms")]
+    pub slow_ms: u64,
+    #[serde(default = "default_check")]
+    pub check: CheckKind,
+    /// Per-host check kind, keyed by host id.
+    #[serde(default)]
+    pub overrides: HashMap<String, CheckKind>,
+    /// Host ids never checked (e.g. behind a bastion the app can't reach directly).
+    #[serde(default)]
+    pub skip: Vec<String>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval(),
+            timeout_ms: default_timeout_ms(),
+            slow_ms: default_slow_ms(),
+            check: default_check(),
+            overrides: HashMap::new(),
+            skip: Vec::new(),
+        }
+    }
+}
+
+impl MonitorConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS))
+    }
+
+    pub(crate) fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.clamp(100, 30_000))
+    }
+
+    fn check_for(&self, host_id: &str) -> CheckKind {
+        self.overrides.get(host_id).copied().unwrap_or(self.check)
+    }
+}
+
+/// Latest check result for a host; also the `host:status` payload.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostStatus {
+    pub host_id: String,
+    pub status: Status,
+    pub latency_ms: Option<u64>,
+    /// Why it's down, or the ssh banner.
+    pub detail: Option<String>,
+    /// Epoch seconds.
+    pub checked_at: i64,
+    /// When the current status began (epoch seconds).
+    pub since: i64,
+    /// Failed checks in a row (0 while up or slow).
+    pub failures: u32,
+}
+
+/// Current statuses keyed by host id.
+#[derive(Default)]
+pub struct Monitor {
+    statuses: Mutex<HashMap<String, HostStatus>>,
+}
+
+impl Monitor {
+    pub fn list(&self) -> Vec<HostStatus> {
+        let mut out: Vec<HostStatus> = self
+            .statuses
+            .lock()
+            .expect("poisoned monitor lock")
+            .values()
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| a.host_id.cmp(&b.host_id));
+        out
+    }
+
+    /// Reorder hosts by their last check. Stable, so each status keeps the stored order.
+    pub fn sort_hosts(&self, hosts: &mut [Host], sort: HostSort) {
+        if sort == HostSort::Manual {
+            return;
+        }
+        let statuses = self.statuses.lock().expect("poisoned monitor lock");
+        let rank = |host: &Host| {
+            let status = statuses.get(&host.id).map(|s| s.status);
+            match (sort, status) {
+                (HostSort::ReachableFirst, Some(Status::Up)) => 0,
+                (HostSort::ReachableFirst, Some(Status::Slow)) => 1,
+                (HostSort::ReachableFirst, None) => 2,
+                (HostSort::ReachableFirst, Some(Status::Down)) => 3,
+                (_, Some(Status::Down)) => 0,
+                (_, Some(Status::Slow)) => 1,
+                (_, Some(Status::Up)) => 2,
+                (_, None) => 3,
+            }
+        };
+        hosts.sort_by_key(rank);
+    }
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn resolve(host: &Host) -> Result<SocketAddr, String> {
+    (host.hostname.as_str(), host.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve failed: {e}"))?
+        .next()
+        .ok_or_else(|| "no address".to_string())
+}
+
+/// Returns (latency, detail) on success.
+fn check_tcp(host: &Host, timeout: Duration) -> Result<(Duration, Option<String>), String> {
+    let addr = resolve(host)?;
+    let started = Instant::now();
+    TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    Ok((started.elapsed(), None))
+}
+
+fn check_ssh_banner(host: &Host, timeout: Duration) -> Result<(Duration, Option<String>), String> {
+    let addr = resolve(host)?;
+    let started = Instant::now();
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(timeout.saturating_sub(started.elapsed()).max(Duration::from_millis(100))))
+        .map_err(|e| e.to_string())?;
+    let mut banner = Vec::new();
+    let mut buf = [0u8; 64];
+    while !banner.contains(&b'\n') && banner.len() < MAX_BANNER_BYTES {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => banner.extend_from_slice(&buf[..n]),
+            Err(e) => return Err(format!("no ssh banner: {e}")),
+        }
+    }
+    let latency = started.elapsed();
+    let banner = String::from_utf8_lossy(&banner);
+    let line = banner.lines().next().unwrap_or("").trim();
+    if !line.starts_with("SSH-") {
+        return Err("port open but not an ssh server".to_string());
+    }
+    Ok((latency, Some(line.to_string())))
+}
+
+fn check_icmp(host: &Host, timeout: Duration) -> Result<(Duration, Option<String>), String> {
+    let cmd = ping::ping_command(&host.hostname, timeout);
+    // Allow for process startup on top of the ping's own timeout.
+    let out = exec::run(&cmd.program, &cmd.args, timeout + Duration::from_secs(2)).map_err(|e| e.to_string())?;
+    if out.exit_code != Some(0) {
+        return Err("no ping reply".to_string());
+    }
+    // Prefer the round trip ping reports; our own timing includes spawning the process.
+    let reported = Regex::new(r"time[=<]([0-9.]+)\s*ms")
+        .ok()
+        .and_then(|re| re.captures(&out.stdout).and_then(|c| c[1].parse::<f64>().ok()));
+    let latency = reported
+        .map(|ms| Duration::from_micros((ms * 1000.0) as u64))
+        .unwrap_or(Duration::from_millis(out.duration_ms));
+    Ok((latency, None))
+}
+
+fn check(host: &Host, kind: CheckKind, config: &MonitorConfig) -> (Status, Option<u64>, Option<String>) {
+    let timeout = config.timeout();
+    let result = match kind {
+        CheckKind::Tcp => check_tcp(host, timeout),
+        CheckKind::SshBanner => check_ssh_banner(host, timeout),
+        CheckKind::Icmp => check_icmp(host, timeout),
+    };
+    match result {
+        Ok((latency, detail)) => {
+            let ms = latency.as_millis() as u64;
+            let status = if ms > config.slow_ms { Status::Slow } else { Status::Up };
+            (status, Some(ms), detail)
+        }
+        Err(e) => (Status::Down, None, Some(e)),
+    }
+}
+
+/// Fold a check result into the host's status; stores and emits it if the status changed.
+fn update(app: &AppHandle, state: &AppState, host_id: &str, result: (Status, Option<u64>, Option<String>)) -> HostStatus {
+    let (status, latency_ms, detail) = result;
+    let now = now_epoch_secs();
+    let (current, changed) = {
+        let mut statuses = state.monitor.statuses.lock().expect("poisoned monitor lock");
+        let previous = statuses.get(host_id);
+        let changed = previous.is_none_or(|p| p.status != status);
+        let current = HostStatus {
+            host_id: host_id.to_string(),
+            status,
+            latency_ms,
+            detail,
+            checked_at: now,
+            since: if changed { now } else { previous.map_or(now, |p| p.since) },
+            failures: match status {
+                Status::Down => previous.map_or(0, |p| p.failures) + 1,
+                _ => 0,
+            },
+        };
+        statuses.insert(host_id.to_string(), current.clone());
+        (current, changed)
+    };
+    if changed {
+        if let Err(e) = state.db.host_status_record(
+            host_id,
+            status.as_str(),
+            current.latency_ms,
+            current.detail.as_deref(),
+        ) {
+            log::warn!("host status not recorded: {e}");
+        }
+        let _ = app.emit("host:status", current.clone());
+    }
+    alerts::on_host_status(app, state, &current);
+    current
+}
+
+/// Check one host now, regardless of the schedule (but still honoring its check kind).
+pub fn check_now(app: &AppHandle, state: &AppState, host_id: &str) -> Result<HostStatus, String> {
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    let config = MonitorConfig::load(&state.db);
+    let result = check(&host, config.check_for(&host.id), &config);
+    Ok(update(app, state, &host.id, result))
+}
+
+fn run_round(app: &AppHandle, state: &AppState, config: &MonitorConfig) {
+    let hosts: Vec<Host> = match state.db.hosts_list() {
+        Ok(hosts) => hosts.into_iter().filter(|h| !config.skip.contains(&h.id)).collect(),
+        Err(e) => {
+            log::warn!("monitor: couldn't list hosts: {e}");
+            return;
+        }
+    };
+    // Forget hosts that were deleted or are no longer checked.
+    state
+        .monitor
+        .statuses
+        .lock()
+        .expect("poisoned monitor lock")
+        .retain(|id, _| hosts.iter().any(|h| &h.id == id));
+
+    for chunk in hosts.chunks(PARALLEL_CHECKS) {
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|host| scope.spawn(move || check(host, config.check_for(&host.id), config)))
+                .collect();
+            handles.into_iter().map(|h| h.join()).collect()
+        });
+        for (host, result) in chunk.iter().zip(results) {
+            if let Ok(result) = result {
+                update(app, state, &host.id, result);
+            }
+        }
+    }
+    http_checks::run_round(app, state, config);
+}
+
+/// Run a check round whenever one is due; the interval is re-read each tick so changes apply
+/// without a restart.
+pub fn spawn_scheduler(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_round: Option<Instant> = None;
+        loop {
+            thread::sleep(TICK);
+            let state = app.state::<Arc<AppState>>();
+            let config = MonitorConfig::load(&state.db);
+            if !config.enabled {
+                if last_round.take().is_some() {
+                    state.monitor.statuses.lock().expect("poisoned monitor lock").clear();
+                    state.http_checks.clear();
+                }
+                continue;
+            }
+            if last_round.is_some_and(|t| t.elapsed() < config.interval()) {
+                continue;
+            }
+            last_round = Some(Instant::now());
+            run_round(&app, &state, &config);
+        }
+    });
+}
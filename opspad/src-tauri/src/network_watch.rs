@@ -0,0 +1,312 @@
+//! Network change awareness: after the machine wakes from sleep or its network changes, live SSH
+//! sessions are checked and each one's status is announced (`terminal:connection`), so a dead
+//! shell shows as dead before anyone types into it.
+//!
+//! Neither signal needs OS hooks. Sleep shows up as the wall clock jumping past the watch interval
+//! (monotonic time stops while asleep), and a network change as the local address the default
+//! route uses changing. A session is then dead when the local address it was opened from no
+//! longer exists (its TCP connection went with it) or its host can't be reached any more; when
+//! neither is the case it's reported alive if it has printed anything since, and otherwise as
+//! unconfirmed, for `terminal_probe` to settle. With `auto_reconnect`, a dead session to a saved
+//! host that logs in on its own (key file or bound password) is replaced by a new one.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Db;
+use crate::session_restore::{self, Login};
+use crate::terminal::session_manager::TerminalKind;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "network_watch";
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+/// A wall-clock jump this far past the watch interval means the machine was asleep.
+const SLEEP_GAP: Duration = Duration::from_secs(30);
+/// Let DHCP, Wi-Fi and VPNs come back before checking.
+const SETTLE: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Any routable address works: connecting a UDP socket only looks up the route, sending nothing.
+const ROUTE_PROBE: &str = "192.0.2.1:9";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkWatchConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Replace dead sessions to saved hosts that can log in without the user.
+    #[serde(default)]
+    pub auto_reconnect: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for NetworkWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auto_reconnect: false,
+        }
+    }
+}
+
+impl NetworkWatchConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkChange {
+    Resumed,
+    AddressChanged,
+}
+
+/// Payload of `network:changed`, sent before the sessions are checked.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkChangedEvent {
+    reason: NetworkChange,
+    /// The local address now used for the default route; `None` when offline.
+    address: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionStatus {
+    Checking,
+    Alive,
+    /// Nothing says it's dead, but it hasn't printed anything to prove otherwise.
+    Unconfirmed,
+    Dead,
+    Reconnecting,
+}
+
+/// Payload of `terminal:connection`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionEvent {
+    pub session_id: String,
+    pub status: ConnectionStatus,
+    pub detail: Option<String>,
+    /// Reconnecting: the session opened to replace this one, which is then closed.
+    pub replacement_id: Option<String>,
+    /// Dead: a new session would log in without the user.
+    pub reconnectable: bool,
+}
+
+/// The local address the default route goes out from; `None` without a route.
+fn route_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect(ROUTE_PROBE).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+/// Whether `addr` is still assigned to this machine.
+fn address_present(addr: IpAddr) -> bool {
+    UdpSocket::bind(SocketAddr::new(addr, 0)).is_ok()
+}
+
+fn reachable(host: &str, port: u16) -> Result<(), String> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{host} doesn't resolve: {e}"))?
+        .next()
+        .ok_or_else(|| format!("{host} doesn't resolve"))?;
+    TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map(drop)
+        .map_err(|e| format!("{host}:{port} is unreachable: {e}"))
+}
+
+/// Where an SSH session connected to, and the saved host if it's one.
+struct Target {
+    address: String,
+    port: u16,
+    host: Option<crate::db::Host>,
+}
+
+fn target(state: &AppState, session_id: &str) -> Option<Target> {
+    let scope = state.db.terminal_session_scope_get(session_id).ok().flatten()?;
+    let rest = scope.strip_prefix("ssh:")?;
+    if let Some(host) = state.db.hosts_get(rest).ok().flatten() {
+        // A DNS-expanded host's session went to one of its targets.
+        let connection = state
+            .db
+            .host_connections_list(&host.id, 50)
+            .ok()
+            .and_then(|list| list.into_iter().find(|c| c.session_id == session_id));
+        let (address, port) = match connection {
+            Some(c) => (c.address, c.port),
+            None => (host.hostname.clone(), host.port),
+        };
+        return Some(Target {
+            address,
+            port,
+            host: Some(host),
+        });
+    }
+    // An ad-hoc connection: "ssh:user@host:port".
+    let (_, host_port) = rest.split_once('@')?;
+    let (address, port) = host_port.rsplit_once(':')?;
+    Some(Target {
+        address: address.trim_matches(['[', ']']).to_string(),
+        port: port.parse().ok()?,
+        host: None,
+    })
+}
+
+fn announce(app: &AppHandle, event: ConnectionEvent) {
+    let _ = app.emit("terminal:connection", event);
+}
+
+/// Check one session after a change at `changed_at`; `from` is the local address it was opened from.
+fn validate(app: &AppHandle, state: &Arc<AppState>, config: &NetworkWatchConfig, session_id: String, from: Option<IpAddr>, changed_at: i64) {
+    let event = |status, detail: Option<String>| ConnectionEvent {
+        session_id: session_id.clone(),
+        status,
+        detail,
+        replacement_id: None,
+        reconnectable: false,
+    };
+    announce(app, event(ConnectionStatus::Checking, None));
+    let Some(target) = target(state, &session_id) else {
+        announce(app, event(ConnectionStatus::Unconfirmed, None));
+        return;
+    };
+    let dead = match from.filter(|addr| !address_present(*addr)) {
+        Some(addr) => Some(format!("this machine no longer has the address {addr} it connected from")),
+        None => reachable(&target.address, target.port).err(),
+    };
+    let Some(detail) = dead else {
+        let printed = state
+            .terminal
+            .list()
+            .into_iter()
+            .find(|s| s.session_id == session_id)
+            .and_then(|s| s.last_output_at)
+            .is_some_and(|at| at >= changed_at);
+        let status = if printed { ConnectionStatus::Alive } else { ConnectionStatus::Unconfirmed };
+        announce(app, event(status, None));
+        return;
+    };
+
+    let host = target.host.filter(|h| session_restore::login(state, h) != Login::Interactive);
+    let reconnectable = host.is_some();
+    let Some(host) = host.filter(|_| config.auto_reconnect) else {
+        announce(
+            app,
+            ConnectionEvent {
+                reconnectable,
+                ..event(ConnectionStatus::Dead, Some(detail))
+            },
+        );
+        return;
+    };
+    let opened = crate::open_ssh_session(
+        app.clone(),
+        state,
+        host.username.clone(),
+        host.hostname.clone(),
+        Some(host.port),
+        host.identity_file.clone(),
+        Vec::new(),
+        Some(host.environment_tag.clone()),
+        Some(host.id.clone()),
+    );
+    match opened {
+        Ok(replacement) => {
+            announce(
+                app,
+                ConnectionEvent {
+                    replacement_id: Some(replacement),
+                    reconnectable: true,
+                    ..event(ConnectionStatus::Reconnecting, Some(detail))
+                },
+            );
+            let _ = state.terminal.close(&session_id);
+        }
+        Err(e) => announce(
+            app,
+            ConnectionEvent {
+                reconnectable: true,
+                ..event(ConnectionStatus::Dead, Some(format!("{detail}; reconnecting failed: {e}")))
+            },
+        ),
+    }
+}
+
+/// Watch for wake-from-sleep and route changes; check live SSH sessions after each.
+pub fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_tick = SystemTime::now();
+        let mut address = route_address();
+        // SSH session id -> the route address when it was first seen.
+        let mut opened_from: HashMap<String, Option<IpAddr>> = HashMap::new();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let now = SystemTime::now();
+            let gap = now.duration_since(last_tick).unwrap_or_default();
+            last_tick = now;
+
+            let state = app.state::<Arc<AppState>>().inner().clone();
+            let current = route_address();
+            let change = if gap > WATCH_INTERVAL + SLEEP_GAP {
+                Some(NetworkChange::Resumed)
+            } else if current != address {
+                Some(NetworkChange::AddressChanged)
+            } else {
+                None
+            };
+            address = current;
+            let sessions: Vec<String> = state
+                .terminal
+                .list()
+                .into_iter()
+                .filter(|s| s.kind == TerminalKind::Ssh)
+                .map(|s| s.session_id)
+                .collect();
+            opened_from.retain(|id, _| sessions.contains(id));
+            for id in &sessions {
+                // One first seen right after a change may have connected from either address;
+                // assuming the current one only risks missing it going dead, not a false alarm.
+                opened_from.entry(id.clone()).or_insert(current);
+            }
+
+            let config = NetworkWatchConfig::load(&state.db);
+            let Some(reason) = change.filter(|_| config.enabled && !sessions.is_empty()) else {
+                continue;
+            };
+            let _ = app.emit(
+                "network:changed",
+                NetworkChangedEvent {
+                    reason,
+                    address: current.map(|a| a.to_string()),
+                },
+            );
+            let changed_at = crate::timestamp::now_millis() / 1000;
+            thread::sleep(SETTLE);
+            for id in sessions {
+                let from = opened_from.get(&id).copied().flatten();
+                let (app, state, config) = (app.clone(), state.clone(), config.clone());
+                thread::spawn(move || validate(&app, &state, &config, id, from, changed_at));
+            }
+        }
+    });
+}
@@ -0,0 +1,134 @@
+//! OS notifications for things that finish while you're looking elsewhere: a terminal session
+//! exiting, a long CommandDock command finishing, a headless job finishing or failing, a fan-out
+//! run completing, a sequence stopping, an alert rule firing.
+//!
+//! Off until enabled in settings; by default only shown while the OpsPad window isn't focused
+//! (when it is, the UI already shows the same thing).
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::db::Db;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "notifications";
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Skip notifications while the main window has focus.
+    #[serde(default = "default_true")]
+    pub only_when_unfocused: bool,
+    #[serde(default = "default_true")]
+    pub session_exit: bool,
+    /// Long-running CommandDock commands finishing (needs shell integration to see the end).
+    #[serde(default = "default_true")]
+    pub commands: bool,
+    /// Headless jobs; `jobs_failed_only` limits these to failures and timeouts.
+    #[serde(default = "default_true")]
+    pub jobs: bool,
+    #[serde(default)]
+    pub jobs_failed_only: bool,
+    #[serde(default = "default_true")]
+    pub fanout: bool,
+    #[serde(default = "default_true")]
+    pub sequences: bool,
+    #[serde(default = "default_true")]
+    pub alerts: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            only_when_unfocused: true,
+            session_exit: true,
+            commands: true,
+            jobs: true,
+            jobs_failed_only: false,
+            fanout: true,
+            sequences: true,
+            alerts: true,
+        }
+    }
+}
+
+impl NotificationConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    SessionExit,
+    Command,
+    Job { failed: bool },
+    Fanout,
+    Sequence,
+    Alert,
+}
+
+impl Kind {
+    fn allowed(self, config: &NotificationConfig) -> bool {
+        match self {
+            Kind::SessionExit => config.session_exit,
+            Kind::Command => config.commands,
+            Kind::Job { failed } => config.jobs && (failed || !config.jobs_failed_only),
+            Kind::Fanout => config.fanout,
+            Kind::Sequence => config.sequences,
+            Kind::Alert => config.alerts,
+        }
+    }
+}
+
+fn window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Show a notification if settings allow it. Never fails: a notification is best effort.
+pub fn notify(app: &AppHandle, kind: Kind, title: &str, body: &str) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let config = NotificationConfig::load(&state.db);
+    if !config.enabled || !kind.allowed(&config) {
+        return;
+    }
+    if config.only_when_unfocused && window_focused(app) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("notification failed: {e}");
+    }
+}
+
+/// Send a test notification regardless of the focus setting, so the OS permission prompt shows.
+pub fn test(app: &AppHandle) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title("OpsPad")
+        .body("Notifications are working.")
+        .show()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,342 @@
+//! Shareable command packs: versioned JSON bundles of dock commands, signed with ed25519.
+//!
+//! The signing key is generated on first export and kept in the OS vault. Packs embed the
+//! signer's public key; import verifies the signature (rejecting modified packs) and reports a
+//! short signer fingerprint so users can tell whose pack they are looking at.
+
+use base64::Engine as _;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::arch::vault::VaultProvider;
+use crate::db::{Db, DockCommand, DockCommandCreate};
+use crate::template::{self, ParamSpec};
+
+const PACK_FORMAT: &str = "opspad-command-pack";
+/// Highest pack format version this build understands.
+const FORMAT_VERSION: u32 = 1;
+const SIGNING_KEY_NAME: &str = "opspad.command-pack-signing-key";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackCommand {
+    pub title: String,
+    pub command: String,
+    #[serde(default)]
+    pub requires_confirm: bool,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub params: Vec<ParamSpec>,
+}
+
+/// The signed part of a pack. Field order is the canonical serialization order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackBody {
+    format: String,
+    format_version: u32,
+    name: String,
+    /// The pack author's own version string (e.g. "1.2.0").
+    version: String,
+    #[serde(default)]
+    description: Option<String>,
+    created_at: i64,
+    commands: Vec<PackCommand>,
+}
+
+/// Detached ed25519 signature over the canonical JSON of a body (also used by config bundles).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PackSignature {
+    algorithm: String,
+    public_key: String,
+    value: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommandPack {
+    #[serde(flatten)]
+    body: PackBody,
+    #[serde(default)]
+    signature: Option<PackSignature>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackExportInput {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub command_ids: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    /// Keep the existing command, ignore the pack's.
+    #[default]
+    Skip,
+    /// Replace the existing command's text, flags, category, and parameters (commands from a
+    /// team subscription are skipped instead).
+    Overwrite,
+    /// Import alongside the existing command under a suffixed title.
+    Duplicate,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackPreviewCommand {
+    pub title: String,
+    pub category: Option<String>,
+    /// Id of an existing dock command with the same title, if any.
+    pub conflict_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackPreview {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub created_at: i64,
+    pub signed: bool,
+    /// Short SHA-256 fingerprint of the signer's public key.
+    pub signer: Option<String>,
+    /// True when the pack was signed with this installation's own key.
+    pub signed_by_me: bool,
+    pub commands: Vec<PackPreviewCommand>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+pub(crate) fn fingerprint(key: &VerifyingKey) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    digest[..8].iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(":")
+}
+
+fn signing_key(vault: &dyn VaultProvider, create: bool) -> Result<Option<SigningKey>, String> {
+    if let Some(bytes) = vault.get_secret(SIGNING_KEY_NAME).map_err(|e| e.to_string())? {
+        let bytes: [u8; 32] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| "stored pack signing key is malformed".to_string())?;
+        return Ok(Some(SigningKey::from_bytes(&bytes)));
+    }
+    if !create {
+        return Ok(None);
+    }
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    vault
+        .set_secret(SIGNING_KEY_NAME, &bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(SigningKey::from_bytes(&bytes)))
+}
+
+fn canonical(body: &PackBody) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(body).map_err(|e| e.to_string())
+}
+
+/// Sign `message` with this installation's key (created on first use).
+pub(crate) fn sign(vault: &dyn VaultProvider, message: &[u8]) -> Result<PackSignature, String> {
+    let key = signing_key(vault, true)?.expect("signing key created on demand");
+    Ok(PackSignature {
+        algorithm: "ed25519".to_string(),
+        public_key: b64().encode(key.verifying_key().as_bytes()),
+        value: b64().encode(key.sign(message).to_bytes()),
+    })
+}
+
+/// This installation's public key (created on first use).
+pub(crate) fn public_key(vault: &dyn VaultProvider) -> Result<VerifyingKey, String> {
+    Ok(signing_key(vault, true)?.expect("signing key created on demand").verifying_key())
+}
+
+/// Check `sig` against `message`, returning the signer's key.
+pub(crate) fn verify(sig: &PackSignature, message: &[u8]) -> Result<VerifyingKey, String> {
+    if sig.algorithm != "ed25519" {
+        return Err(format!("unsupported signature algorithm: {}", sig.algorithm));
+    }
+    let key_bytes: [u8; 32] = b64()
+        .decode(&sig.public_key)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| "signature has a malformed public key".to_string())?;
+    let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| "signature has an invalid public key".to_string())?;
+    let sig_bytes: [u8; 64] = b64()
+        .decode(&sig.value)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| "signature is malformed".to_string())?;
+    key.verify(message, &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| "signature does not match the contents (modified after signing?)".to_string())?;
+    Ok(key)
+}
+
+/// Export the selected dock commands (in the given order) as a signed pack.
+pub fn export(db: &Db, vault: &dyn VaultProvider, input: PackExportInput) -> Result<String, String> {
+    if input.name.trim().is_empty() {
+        return Err("pack name must not be empty".to_string());
+    }
+    if input.command_ids.is_empty() {
+        return Err("select at least one command".to_string());
+    }
+    let mut commands = Vec::with_capacity(input.command_ids.len());
+    for id in &input.command_ids {
+        let cmd = db
+            .dock_commands_get(id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("dock command not found: {id}"))?;
+        commands.push(PackCommand {
+            title: cmd.title,
+            command: cmd.command,
+            requires_confirm: cmd.requires_confirm,
+            color: cmd.color,
+            category: cmd.category,
+            params: cmd.params,
+        });
+    }
+    let body = PackBody {
+        format: PACK_FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        name: input.name.trim().to_string(),
+        version: input.version.trim().to_string(),
+        description: input.description.filter(|d| !d.trim().is_empty()),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        commands,
+    };
+
+    let signature = sign(vault, &canonical(&body)?)?;
+    let pack = CommandPack {
+        body,
+        signature: Some(signature),
+    };
+    serde_json::to_string_pretty(&pack).map_err(|e| e.to_string())
+}
+
+/// Parse a pack and verify its signature. Unsigned packs are accepted; tampered ones are not.
+fn parse(json: &str) -> Result<(PackBody, Option<VerifyingKey>), String> {
+    let pack: CommandPack = serde_json::from_str(json).map_err(|e| format!("not a valid command pack: {e}"))?;
+    if pack.body.format != PACK_FORMAT {
+        return Err("not an OpsPad command pack".to_string());
+    }
+    if pack.body.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "pack format version {} is newer than this OpsPad supports ({FORMAT_VERSION})",
+            pack.body.format_version
+        ));
+    }
+    for (i, c) in pack.body.commands.iter().enumerate() {
+        if c.title.trim().is_empty() || c.command.trim().is_empty() {
+            return Err(format!("command {}: title and command are required", i + 1));
+        }
+        template::validate_schema(&c.command, &c.params).map_err(|e| format!("{}: {e}", c.title))?;
+    }
+
+    let Some(sig) = pack.signature else {
+        return Ok((pack.body, None));
+    };
+    let key = verify(&sig, &canonical(&pack.body)?).map_err(|e| format!("pack {e}"))?;
+    Ok((pack.body, Some(key)))
+}
+
+fn find_conflict<'a>(existing: &'a [DockCommand], title: &str) -> Option<&'a DockCommand> {
+    existing.iter().find(|c| c.title.trim().eq_ignore_ascii_case(title.trim()))
+}
+
+pub fn inspect(db: &Db, vault: &dyn VaultProvider, json: &str) -> Result<PackPreview, String> {
+    let (body, key) = parse(json)?;
+    let existing = db.dock_commands_list().map_err(|e| e.to_string())?;
+    let mine = signing_key(vault, false)?.map(|k| k.verifying_key());
+    Ok(PackPreview {
+        signed: key.is_some(),
+        signer: key.as_ref().map(fingerprint),
+        signed_by_me: key.is_some() && key == mine,
+        commands: body
+            .commands
+            .iter()
+            .map(|c| PackPreviewCommand {
+                title: c.title.clone(),
+                category: c.category.clone(),
+                conflict_id: find_conflict(&existing, &c.title).map(|e| e.id.clone()),
+            })
+            .collect(),
+        name: body.name,
+        version: body.version,
+        description: body.description,
+        created_at: body.created_at,
+    })
+}
+
+fn unique_title(existing: &[DockCommand], title: &str) -> String {
+    (2..)
+        .map(|n| format!("{title} ({n})"))
+        .find(|t| find_conflict(existing, t).is_none())
+        .expect("unbounded suffix search")
+}
+
+pub fn import(db: &Db, json: &str, strategy: ConflictStrategy) -> Result<PackImportResult, String> {
+    let (body, _) = parse(json)?;
+    let mut existing = db.dock_commands_list().map_err(|e| e.to_string())?;
+    let mut result = PackImportResult::default();
+
+    for c in body.commands {
+        let conflict = find_conflict(&existing, &c.title).cloned();
+        let title = match (&conflict, strategy) {
+            (Some(current), ConflictStrategy::Overwrite) if current.subscription_id.is_none() => {
+                db.dock_commands_update(DockCommand {
+                    command: c.command.clone(),
+                    requires_confirm: c.requires_confirm,
+                    color: c.color.clone(),
+                    ..current.clone()
+                })
+                .map_err(|e| e.to_string())?;
+                db.dock_commands_set_params(&current.id, &c.params).map_err(|e| e.to_string())?;
+                db.dock_commands_set_category(&current.id, c.category.as_deref())
+                    .map_err(|e| e.to_string())?;
+                result.updated += 1;
+                continue;
+            }
+            // Commands from a team subscription are read-only, so they are never overwritten.
+            (Some(_), ConflictStrategy::Skip | ConflictStrategy::Overwrite) => {
+                result.skipped += 1;
+                continue;
+            }
+            (Some(_), ConflictStrategy::Duplicate) => unique_title(&existing, &c.title),
+            (None, _) => c.title.clone(),
+        };
+
+        let created = db
+            .dock_commands_create(DockCommandCreate {
+                title,
+                command: c.command,
+                requires_confirm: Some(c.requires_confirm),
+                color: c.color,
+                params: Some(c.params),
+            })
+            .map_err(|e| e.to_string())?;
+        db.dock_commands_set_category(&created.id, c.category.as_deref())
+            .map_err(|e| e.to_string())?;
+        existing.push(created);
+        result.created += 1;
+    }
+    Ok(result)
+}
@@ -0,0 +1,238 @@
+//! The command palette's search: one query over hosts, CommandDock commands, runbooks, recent
+//! history and live sessions, so the palette fills in with a single round trip.
+//!
+//! Matching is case-insensitive and ranks an exact match above a prefix, a prefix above the start
+//! of a word, that above a plain substring, and a scattered in-order match ("prdb" for
+//! "prod-db") last. Each kind contributes at most [`PER_KIND`] results so one long list can't
+//! crowd out the others.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::terminal::session_manager::TerminalKind;
+use crate::AppState;
+
+const DEFAULT_LIMIT: usize = 30;
+const MAX_LIMIT: usize = 100;
+const PER_KIND: usize = 10;
+/// History entries searched (newest first).
+const HISTORY_LOOKBACK: i64 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaletteKind {
+    Session,
+    Host,
+    Command,
+    Runbook,
+    History,
+}
+
+impl PaletteKind {
+    /// Tie-break between kinds on equal match quality: things already open first.
+    fn weight(self) -> i64 {
+        match self {
+            PaletteKind::Session => 40,
+            PaletteKind::Host => 30,
+            PaletteKind::Command => 20,
+            PaletteKind::Runbook => 10,
+            PaletteKind::History => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteItem {
+    pub kind: PaletteKind,
+    /// Session id, host id, dock command id, runbook id, or history entry id, by `kind`.
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub environment_tag: Option<String>,
+    /// Higher is better; only meaningful within one response.
+    pub score: i64,
+}
+
+/// How well `query` (already lowercased) matches `text`, or `None` if it doesn't.
+fn match_score(query: &str, text: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text = text.to_lowercase();
+    if text == query {
+        return Some(1000);
+    }
+    if text.starts_with(query) {
+        return Some(800);
+    }
+    if let Some(pos) = text.find(query) {
+        let word_start = text[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_alphanumeric());
+        return Some(if word_start { 600 } else { 400 });
+    }
+    // In-order but scattered; fewer skipped characters is better.
+    let mut wanted = query.chars().peekable();
+    let mut skipped = 0i64;
+    for c in text.chars() {
+        match wanted.peek() {
+            Some(&w) if w == c => {
+                wanted.next();
+            }
+            Some(_) => skipped += 1,
+            None => break,
+        }
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    Some((200 - skipped).max(1))
+}
+
+/// Best score over a title and its secondary fields; the title counts for more.
+fn best_score(query: &str, title: &str, others: &[&str]) -> Option<i64> {
+    let title_score = match_score(query, title).map(|s| s + 50);
+    others
+        .iter()
+        .filter_map(|t| match_score(query, t))
+        .chain(title_score)
+        .max()
+}
+
+fn push(out: &mut Vec<PaletteItem>, query: &str, mut item: PaletteItem, others: &[&str]) {
+    if let Some(score) = best_score(query, &item.title, others) {
+        item.score = score + item.kind.weight();
+        out.push(item);
+    }
+}
+
+/// Keep the best [`PER_KIND`] of one kind.
+fn top(mut items: Vec<PaletteItem>) -> Vec<PaletteItem> {
+    items.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+    items.truncate(PER_KIND);
+    items
+}
+
+pub fn query(state: &AppState, text: &str, limit: Option<usize>) -> Result<Vec<PaletteItem>, String> {
+    let q = text.trim().to_lowercase();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let hosts = state.db.hosts_list().map_err(|e| e.to_string())?;
+    let mut results = Vec::new();
+
+    let mut sessions = Vec::new();
+    for s in state.terminal.list() {
+        let scope = state
+            .db
+            .terminal_session_scope_get(&s.session_id)
+            .map_err(|e| e.to_string())?;
+        let host = scope
+            .as_deref()
+            .and_then(|s| s.strip_prefix("ssh:"))
+            .and_then(|id| hosts.iter().find(|h| h.id == id));
+        let (title, subtitle) = match (host, s.kind) {
+            (Some(h), _) => (h.label.clone(), format!("{}@{}", h.username, h.hostname)),
+            (None, TerminalKind::Ssh) => (
+                scope
+                    .as_deref()
+                    .and_then(|s| s.strip_prefix("ssh:"))
+                    .unwrap_or("SSH session")
+                    .to_string(),
+                "ssh".to_string(),
+            ),
+            (None, TerminalKind::Local) => ("Terminal".to_string(), "local".to_string()),
+        };
+        let item = PaletteItem {
+            kind: PaletteKind::Session,
+            id: s.session_id,
+            title,
+            subtitle: Some(subtitle.clone()),
+            environment_tag: Some(s.environment_tag),
+            score: 0,
+        };
+        push(&mut sessions, &q, item, &[&subtitle]);
+    }
+    results.extend(top(sessions));
+
+    let mut host_items = Vec::new();
+    for h in &hosts {
+        let subtitle = format!("{}@{}:{}", h.username, h.hostname, h.port);
+        let item = PaletteItem {
+            kind: PaletteKind::Host,
+            id: h.id.clone(),
+            title: h.label.clone(),
+            subtitle: Some(subtitle.clone()),
+            environment_tag: Some(h.environment_tag.clone()),
+            score: 0,
+        };
+        push(&mut host_items, &q, item, &[&h.hostname, &subtitle, &h.environment_tag]);
+    }
+    results.extend(top(host_items));
+
+    let mut commands = Vec::new();
+    for c in state.db.dock_commands_list().map_err(|e| e.to_string())? {
+        let item = PaletteItem {
+            kind: PaletteKind::Command,
+            id: c.id,
+            title: c.title,
+            subtitle: Some(c.command.clone()),
+            environment_tag: None,
+            score: 0,
+        };
+        let category = c.category.unwrap_or_default();
+        push(&mut commands, &q, item, &[&c.command, &category]);
+    }
+    results.extend(top(commands));
+
+    let mut runbooks = Vec::new();
+    for r in state.db.runbooks_list().map_err(|e| e.to_string())? {
+        let tags = r.tags.join(" ");
+        let item = PaletteItem {
+            kind: PaletteKind::Runbook,
+            id: r.id,
+            title: r.title,
+            subtitle: (!tags.is_empty()).then(|| tags.clone()),
+            environment_tag: None,
+            score: 0,
+        };
+        push(&mut runbooks, &q, item, &[&tags]);
+    }
+    results.extend(top(runbooks));
+
+    let mut history = Vec::new();
+    let mut seen = HashSet::new();
+    let entries = state
+        .db
+        .dock_history_list(HISTORY_LOOKBACK, false, None)
+        .map_err(|e| e.to_string())?
+        .items;
+    // Newest first, so each command keeps its most recent run.
+    for h in entries {
+        if !seen.insert(h.command_text.clone()) {
+            continue;
+        }
+        let item = PaletteItem {
+            kind: PaletteKind::History,
+            id: h.id,
+            title: h.command_text,
+            subtitle: h.scope,
+            environment_tag: Some(h.environment_tag),
+            score: 0,
+        };
+        push(&mut history, &q, item, &[]);
+    }
+    // Without a query, recency is the only ranking history has; keep it.
+    if q.is_empty() {
+        history.truncate(PER_KIND);
+        results.extend(history);
+    } else {
+        results.extend(top(history));
+    }
+
+    // Stable, so kinds keep their order on equal scores.
+    results.sort_by_key(|r| std::cmp::Reverse(r.score));
+    results.truncate(limit);
+    Ok(results)
+}
@@ -0,0 +1,438 @@
+//! Team guardrails as WebAssembly plugins ("append --context to every kubectl"), without forking
+//! the app.
+//!
+//! A plugin is a `.wasm` file in `<app data>/plugins`, loaded at startup and by `plugins_reload`.
+//! It runs sandboxed: no WASI, no filesystem or network, a fuel budget per call and a memory cap.
+//! Each call gets a fresh instance, so plugins keep no state between calls.
+//!
+//! ABI (everything is JSON; a "packed" `i64` is `ptr << 32 | len` into the plugin's memory, `0`
+//! for nothing):
+//! - exports `memory` and `opspad_alloc(len: i32) -> i32`, which the host uses to pass input;
+//! - optionally exports `opspad_manifest() -> i64`: `{"outputPatterns": ["regex", ...]}`;
+//! - exports any of the hooks, each `(ptr: i32, len: i32) -> i64`:
+//!   - `before_command_run`: a command about to run (from the CommandDock, a watch, sequence,
+//!     fan-out, macro replay or job), before it's sent; `sessionId` is null off a terminal. Returns
+//!     `{"action": "allow" | "rewrite" | "deny", "command": ..., "reason": ...}`. Plugins run in
+//!     name order, each seeing the previous one's rewrite.
+//!   - `after_session_open`: may return `{"write": "..."}` to type into the new session.
+//!   - `on_output_match`: a line of session output matched one of the manifest's patterns.
+//!   - `on_history_add`: a command was added to CommandDock history.
+//! - may import `opspad.log(ptr: i32, len: i32)` to write to the app log.
+//!
+//! A plugin that fails (traps, runs out of fuel, returns bad JSON) is logged and skipped; it never
+//! blocks a command by failing.
+
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::thread;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::db::Db;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "plugins";
+pub const DIR_NAME: &str = "plugins";
+/// Instructions per hook call.
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Larger results are refused.
+const MAX_RESULT_BYTES: usize = 1024 * 1024;
+const MAX_LOG_CHARS: usize = 2000;
+/// Enough output to hold a whole line split across reads; longer lines are checked in pieces.
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+pub const BEFORE_COMMAND_RUN: &str = "before_command_run";
+pub const AFTER_SESSION_OPEN: &str = "after_session_open";
+pub const ON_OUTPUT_MATCH: &str = "on_output_match";
+pub const ON_HISTORY_ADD: &str = "on_history_add";
+const HOOKS: [&str; 4] = [BEFORE_COMMAND_RUN, AFTER_SESSION_OPEN, ON_OUTPUT_MATCH, ON_HISTORY_ADD];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginsConfig {
+    /// Plugin names (file stems) that are installed but turned off.
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+impl PluginsConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    #[serde(default)]
+    output_patterns: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    pub name: String,
+    pub file: String,
+    pub enabled: bool,
+    /// The hooks it exports.
+    pub hooks: Vec<&'static str>,
+    pub output_patterns: Vec<String>,
+    /// Why it couldn't be loaded.
+    pub error: Option<String>,
+}
+
+struct Plugin {
+    name: String,
+    module: Module,
+    hooks: Vec<&'static str>,
+    patterns: Vec<Regex>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "action")]
+enum CommandVerdict {
+    Allow,
+    Rewrite { command: String },
+    Deny { reason: Option<String> },
+}
+
+struct HostState {
+    plugin: String,
+    limits: StoreLimits,
+}
+
+pub struct Plugins {
+    engine: Engine,
+    /// Enabled plugins that loaded, in name order.
+    loaded: RwLock<Vec<Arc<Plugin>>>,
+    /// Every plugin found, for `plugins_list`.
+    found: RwLock<Vec<PluginInfo>>,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+            loaded: RwLock::new(Vec::new()),
+            found: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+fn escape_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]")
+            .expect("valid escape regex")
+    })
+}
+
+impl Plugins {
+    /// (Re)load every plugin in `dir`.
+    pub fn load(&self, dir: &Path, db: &Db) {
+        let config = PluginsConfig::load(db);
+        let mut files: Vec<_> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "wasm"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+
+        let mut loaded = Vec::new();
+        let mut found = Vec::new();
+        for path in files {
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let enabled = !config.disabled.contains(&name);
+            let mut info = PluginInfo {
+                name: name.clone(),
+                file: path.display().to_string(),
+                enabled,
+                hooks: Vec::new(),
+                output_patterns: Vec::new(),
+                error: None,
+            };
+            match self.compile(&name, &path) {
+                Ok((plugin, manifest)) => {
+                    info.hooks = plugin.hooks.clone();
+                    info.output_patterns = manifest.output_patterns;
+                    if enabled {
+                        loaded.push(Arc::new(plugin));
+                    }
+                }
+                Err(e) => {
+                    log::warn!("plugin {name} not loaded: {e}");
+                    info.error = Some(e);
+                }
+            }
+            found.push(info);
+        }
+        log::info!("{} plugin(s) loaded from {}", loaded.len(), dir.display());
+        *self.loaded.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+        *self.found.write().unwrap_or_else(|e| e.into_inner()) = found;
+    }
+
+    fn compile(&self, name: &str, path: &Path) -> Result<(Plugin, Manifest), String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let module = Module::new(&self.engine, &bytes).map_err(|e| e.to_string())?;
+        if module.get_export("opspad_alloc").is_none() || module.get_export("memory").is_none() {
+            return Err("must export memory and opspad_alloc".to_string());
+        }
+        let hooks: Vec<&'static str> = HOOKS.into_iter().filter(|h| module.get_export(h).is_some()).collect();
+        let mut plugin = Plugin {
+            name: name.to_string(),
+            module,
+            hooks,
+            patterns: Vec::new(),
+        };
+        let manifest: Manifest = match plugin.module.get_export("opspad_manifest") {
+            Some(_) => match call(&self.engine, &plugin, "opspad_manifest", None)? {
+                Some(value) => serde_json::from_value(value).map_err(|e| format!("bad manifest: {e}"))?,
+                None => Manifest::default(),
+            },
+            None => Manifest::default(),
+        };
+        plugin.patterns = manifest
+            .output_patterns
+            .iter()
+            .map(|p| Regex::new(p).map_err(|e| format!("bad output pattern {p:?}: {e}")))
+            .collect::<Result<_, _>>()?;
+        Ok((plugin, manifest))
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.found.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn with_hook(&self, hook: &str) -> Vec<Arc<Plugin>> {
+        self.loaded
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|p| p.hooks.contains(&hook))
+            .cloned()
+            .collect()
+    }
+
+    /// The command to write after every `before_command_run` hook has seen it, or why it's refused.
+    /// `data` keeps its line ending.
+    pub fn before_command_run(&self, data: &str, context: Value) -> Result<String, String> {
+        let plugins = self.with_hook(BEFORE_COMMAND_RUN);
+        if plugins.is_empty() {
+            return Ok(data.to_string());
+        }
+        let body = data.trim_end_matches(['\r', '\n']);
+        let ending = &data[body.len()..];
+        let mut command = body.to_string();
+        for plugin in plugins {
+            let mut input = context.clone();
+            input["command"] = Value::String(command.clone());
+            let verdict = match call(&self.engine, &plugin, BEFORE_COMMAND_RUN, Some(&input)) {
+                Ok(Some(value)) => serde_json::from_value(value).map_err(|e| e.to_string()),
+                Ok(None) => Ok(CommandVerdict::Allow),
+                Err(e) => Err(e),
+            };
+            match verdict {
+                Ok(CommandVerdict::Allow) => {}
+                Ok(CommandVerdict::Rewrite { command: rewritten }) => {
+                    log::info!("plugin {} rewrote a command", plugin.name);
+                    command = rewritten;
+                }
+                Ok(CommandVerdict::Deny { reason }) => {
+                    return Err(match reason.filter(|r| !r.trim().is_empty()) {
+                        Some(reason) => format!("blocked by plugin {}: {reason}", plugin.name),
+                        None => format!("blocked by plugin {}", plugin.name),
+                    });
+                }
+                Err(e) => log::warn!("plugin {} {BEFORE_COMMAND_RUN} failed: {e}", plugin.name),
+            }
+        }
+        Ok(format!("{command}{ending}"))
+    }
+
+    /// Call `hook` on every plugin that has it, in the background; results are ignored.
+    pub fn notify(&self, hook: &'static str, input: Value) {
+        let plugins = self.with_hook(hook);
+        if plugins.is_empty() {
+            return;
+        }
+        let engine = self.engine.clone();
+        thread::spawn(move || {
+            for plugin in plugins {
+                if let Err(e) = call(&engine, &plugin, hook, Some(&input)) {
+                    log::warn!("plugin {} {hook} failed: {e}", plugin.name);
+                }
+            }
+        });
+    }
+}
+
+/// Run `after_session_open` for a new session and, if any plugin watches output, start watching
+/// it for `on_output_match`.
+pub fn session_opened(app: &AppHandle, state: &AppState, session_id: &str, environment_tag: &str) {
+    let openers = state.plugins.with_hook(AFTER_SESSION_OPEN);
+    let watchers: Vec<Arc<Plugin>> = state
+        .plugins
+        .with_hook(ON_OUTPUT_MATCH)
+        .into_iter()
+        .filter(|p| !p.patterns.is_empty())
+        .collect();
+    if openers.is_empty() && watchers.is_empty() {
+        return;
+    }
+    let output = if watchers.is_empty() {
+        None
+    } else {
+        state.terminal.subscribe_output(session_id).ok()
+    };
+    let input = json!({
+        "sessionId": session_id,
+        "environmentTag": environment_tag,
+        "scope": state.db.terminal_session_scope_get(session_id).ok().flatten(),
+    });
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    thread::spawn(move || {
+        let Some(state) = app.try_state::<Arc<AppState>>().map(|s| s.inner().clone()) else {
+            return;
+        };
+        for plugin in openers {
+            match call(&state.plugins.engine, &plugin, AFTER_SESSION_OPEN, Some(&input)) {
+                Ok(Some(result)) => {
+                    if let Some(text) = result.get("write").and_then(Value::as_str) {
+                        let _ = state.terminal.write(&session_id, text);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("plugin {} {AFTER_SESSION_OPEN} failed: {e}", plugin.name),
+            }
+        }
+        if let Some(output) = output {
+            watch_output(&state, &session_id, &watchers, &output);
+        }
+    });
+}
+
+fn watch_output(state: &AppState, session_id: &str, watchers: &[Arc<Plugin>], output: &Receiver<String>) {
+    let mut line = String::new();
+    while let Ok(chunk) = output.recv() {
+        line.push_str(&chunk);
+        let mut complete: Vec<String> = Vec::new();
+        while let Some(end) = line.find('\n') {
+            complete.push(line.drain(..=end).collect());
+        }
+        if line.len() > MAX_LINE_BYTES {
+            complete.push(std::mem::take(&mut line));
+        }
+        for raw in complete {
+            let text = escape_re().replace_all(&raw, "");
+            let text = text.trim_end_matches(['\r', '\n']);
+            for plugin in watchers {
+                // One call per plugin and line, with its first matching pattern.
+                let Some((re, m)) = plugin.patterns.iter().find_map(|re| re.find(text).map(|m| (re, m))) else {
+                    continue;
+                };
+                let input = json!({
+                    "sessionId": session_id,
+                    "pattern": re.as_str(),
+                    "match": m.as_str(),
+                    "line": text,
+                });
+                if let Err(e) = call(&state.plugins.engine, plugin, ON_OUTPUT_MATCH, Some(&input)) {
+                    log::warn!("plugin {} {ON_OUTPUT_MATCH} failed: {e}", plugin.name);
+                }
+            }
+        }
+    }
+}
+
+/// Run one export in a fresh instance. `input` is passed as JSON; `None` calls a
+/// no-argument export.
+fn call(engine: &Engine, plugin: &Plugin, export: &str, input: Option<&Value>) -> Result<Option<Value>, String> {
+    let mut store = Store::new(
+        engine,
+        HostState {
+            plugin: plugin.name.clone(),
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+
+    let mut linker = Linker::<HostState>::new(engine);
+    linker
+        .func_wrap("opspad", "log", |caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+            let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+                return;
+            };
+            let data = memory.data(&caller);
+            let (start, len) = (ptr as u32 as usize, len as u32 as usize);
+            if let Some(bytes) = data.get(start..start.saturating_add(len)) {
+                let text: String = String::from_utf8_lossy(bytes).chars().take(MAX_LOG_CHARS).collect();
+                log::info!("plugin {}: {text}", caller.data().plugin);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    let instance = linker
+        .instantiate(&mut store, &plugin.module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| e.to_string())?;
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or_else(|| "no memory export".to_string())?;
+
+    let packed = match input {
+        Some(input) => {
+            let bytes = serde_json::to_vec(input).map_err(|e| e.to_string())?;
+            let len = i32::try_from(bytes.len()).map_err(|_| "input too large".to_string())?;
+            let alloc = instance
+                .get_typed_func::<i32, i32>(&store, "opspad_alloc")
+                .map_err(|e| e.to_string())?;
+            let ptr = alloc.call(&mut store, len).map_err(|e| e.to_string())?;
+            memory
+                .write(&mut store, ptr as u32 as usize, &bytes)
+                .map_err(|e| e.to_string())?;
+            let hook = instance
+                .get_typed_func::<(i32, i32), i64>(&store, export)
+                .map_err(|e| e.to_string())?;
+            hook.call(&mut store, (ptr, len)).map_err(|e| e.to_string())?
+        }
+        None => {
+            let func = instance
+                .get_typed_func::<(), i64>(&store, export)
+                .map_err(|e| e.to_string())?;
+            func.call(&mut store, ()).map_err(|e| e.to_string())?
+        }
+    };
+    if packed == 0 {
+        return Ok(None);
+    }
+    let (ptr, len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+    if len > MAX_RESULT_BYTES {
+        return Err(format!("result of {len} bytes is too large"));
+    }
+    let mut out = vec![0u8; len];
+    memory.read(&store, ptr, &mut out).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&out)
+        .map(Some)
+        .map_err(|e| format!("bad result from {export}: {e}"))
+}
@@ -0,0 +1,256 @@
+//! Backend enforcement of per-environment execution policies for CommandDock writes.
+//!
+//! The frontend still asks for confirmation, but the decision is made here so a bypassed or
+//! broken dialog can't send a destructive command into PROD. A command is destructive when its
+//! dock command has `requires_confirm`, or when the linter reports a "danger" finding for the
+//! text actually being written. The preview a confirmation dialog shows carries a hash of the
+//! command, and a confirmed write must still hash the same, so what gets sent is what was
+//! confirmed. In a four-eyes environment a destructive command also needs a
+//! teammate's signed approval (see [`crate::four_eyes`]). How a destructive command was let
+//! through is returned as a [`PolicyApproval`] for the command history.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::db::{Confirmation, Db, DestructivePolicy, DockCommand, PolicyApproval};
+use crate::four_eyes;
+use crate::lint::{LintFinding, LintSeverity};
+
+/// Shortest reason accepted under [`DestructivePolicy::Reason`].
+const MIN_REASON_CHARS: usize = 8;
+
+/// What the policy would do with a write, computed without performing it.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyDecision {
+    pub environment: String,
+    /// Whether the command counts as destructive (`requires_confirm` or a "danger" finding).
+    pub destructive: bool,
+    /// The policy that applies; always `Allow` for non-destructive commands.
+    pub policy: DestructivePolicy,
+    /// A second person's approval token is needed as well.
+    pub four_eyes: bool,
+}
+
+/// Exactly what a dock command would write, for confirmation dialogs.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandPreview {
+    pub command: String,
+    /// [`command_hash`] of `command`; sent back with the confirmed write.
+    pub command_hash: String,
+    pub findings: Vec<LintFinding>,
+    pub decision: PolicyDecision,
+}
+
+/// Apply the destructive-command policy of `environment` to `what` (used in error messages), the
+/// command `text`.
+///
+/// `confirmation` is what the confirmation dialog sent: any text for a click confirm, the typed
+/// environment name, or the reason. `approval` is the teammate's token in a four-eyes environment.
+fn check_destructive(
+    db: &Db,
+    environment: &str,
+    what: &str,
+    text: &str,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+) -> Result<PolicyApproval, String> {
+    let env = db.environment_get(environment).map_err(|e| e.to_string())?;
+    let typed = confirmation.map(str::trim).unwrap_or_default();
+    let passed = |reason: Option<String>| PolicyApproval {
+        policy: env.destructive_policy,
+        reason,
+        confirmation: confirmation.map(str::to_string),
+        approved_by: None,
+    };
+    let passed = match env.destructive_policy {
+        DestructivePolicy::Allow => passed(None),
+        DestructivePolicy::Block => {
+            return Err(format!("{} policy blocks destructive commands ({what})", env.name))
+        }
+        DestructivePolicy::Confirm if confirmation.is_some() => passed(None),
+        DestructivePolicy::Confirm => return Err(format!("{} policy: confirm before running {what}", env.name)),
+        DestructivePolicy::TypedName if typed.eq_ignore_ascii_case(&env.name) => passed(None),
+        DestructivePolicy::TypedName => {
+            return Err(format!("{} policy: type the environment name to run {what}", env.name))
+        }
+        DestructivePolicy::Reason if typed.chars().count() >= MIN_REASON_CHARS => passed(Some(typed.to_string())),
+        DestructivePolicy::Reason => {
+            return Err(format!(
+                "{} policy: give a reason (at least {MIN_REASON_CHARS} characters) to run {what}",
+                env.name
+            ))
+        }
+    };
+    if !env.four_eyes {
+        return Ok(passed);
+    }
+    let token = approval
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| format!("{} needs a second person's approval to run {what}", env.name))?;
+    let approved_by = four_eyes::verify(db, &env.name, text, token).map_err(|e| format!("{what}: {e}"))?;
+    Ok(PolicyApproval {
+        approved_by: Some(approved_by),
+        ..passed
+    })
+}
+
+/// First "danger" lint finding for `text`, if any.
+fn danger_finding(db: &Db, text: &str) -> Result<Option<String>, String> {
+    let linter = db.linter().map_err(|e| e.to_string())?;
+    Ok(linter
+        .lint(text)
+        .into_iter()
+        .find(|f| f.severity == LintSeverity::Danger)
+        .map(|f| f.name))
+}
+
+/// Lint `text` and decide how the policy of `environment` treats it.
+pub fn preview(db: &Db, environment: &str, cmd: &DockCommand, text: String) -> Result<CommandPreview, String> {
+    assess(db, environment, text, cmd.requires_confirm)
+}
+
+/// [`preview`] for a command line that isn't a dock command (`requires_confirm` marks it
+/// destructive regardless of lint).
+pub fn assess(db: &Db, environment: &str, text: String, requires_confirm: bool) -> Result<CommandPreview, String> {
+    let findings = db.linter().map_err(|e| e.to_string())?.lint(&text);
+    let destructive = requires_confirm || findings.iter().any(|f| f.severity == LintSeverity::Danger);
+    let env = db.environment_get(environment).map_err(|e| e.to_string())?;
+    Ok(CommandPreview {
+        command_hash: command_hash(&text),
+        command: text,
+        findings,
+        decision: PolicyDecision {
+            environment: env.name,
+            destructive,
+            policy: if destructive { env.destructive_policy } else { DestructivePolicy::Allow },
+            four_eyes: destructive && env.four_eyes,
+        },
+    })
+}
+
+/// SHA-256 (hex) of a command, ignoring the line ending a run adds to it.
+pub fn command_hash(text: &str) -> String {
+    Sha256::digest(text.trim_end_matches(['\r', '\n']).as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Several commands run as one (a sequence's steps, a macro's lines), one per line: the text
+/// their confirmation is checked against.
+pub fn joined_commands<'a>(texts: impl IntoIterator<Item = &'a str>) -> String {
+    let lines: Vec<&str> = texts.into_iter().map(|t| t.trim_end_matches(['\r', '\n'])).collect();
+    lines.join("\n")
+}
+
+/// Reject `text` unless it's the command whose hash was confirmed. A confirmation is only
+/// accepted together with that hash, so it can't be replayed for a different command.
+pub fn check_confirmed(text: &str, confirmation: Option<&str>, confirmed_hash: Option<&str>) -> Result<(), String> {
+    match (confirmation, confirmed_hash) {
+        (Some(_), None) => Err("the confirmed command's hash is missing; preview and confirm it again".to_string()),
+        (_, Some(hash)) if !hash.trim().eq_ignore_ascii_case(&command_hash(text)) => {
+            log::warn!("refused a write that differs from the command that was confirmed");
+            Err("the command changed after it was confirmed; review and confirm it again".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check a dock command (rendered as `text`) against the policy of `environment`. `None` when
+/// the command isn't destructive.
+pub fn check_dock_command(
+    db: &Db,
+    environment: &str,
+    cmd: &DockCommand,
+    text: &str,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+) -> Result<Option<PolicyApproval>, String> {
+    let what = if cmd.requires_confirm {
+        format!("\"{}\"", cmd.title)
+    } else if let Some(name) = danger_finding(db, text)? {
+        format!("\"{}\" ({name})", cmd.title)
+    } else {
+        return Ok(None);
+    };
+    check_destructive(db, environment, &what, text, confirmation, approval).map(Some)
+}
+
+/// Environment tag of the terminal session `session_id` ("UNKNOWN" if it has no scope).
+pub fn session_environment(db: &Db, session_id: &str) -> Result<String, String> {
+    match db.terminal_session_scope_get(session_id).map_err(|e| e.to_string())? {
+        Some(scope) => Ok(db.scope_context(&scope).map_err(|e| e.to_string())?.environment_tag),
+        None => Ok("UNKNOWN".to_string()),
+    }
+}
+
+/// Check a CommandDock-origin terminal write of `text`.
+pub fn check_session_write(
+    db: &Db,
+    session_id: &str,
+    dock_command_id: Option<&str>,
+    text: &str,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+) -> Result<Option<PolicyApproval>, String> {
+    let cmd = match dock_command_id {
+        Some(id) => db.dock_commands_get(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let env = session_environment(db, session_id)?;
+    match cmd {
+        Some(cmd) => check_dock_command(db, &env, &cmd, text, confirmation, approval),
+        // Ad-hoc CommandDock writes (e.g. re-running history) are judged by the linter alone.
+        None => check_command(db, &env, text, confirmation, approval),
+    }
+}
+
+/// Check a command that isn't a dock command (judged by the linter alone) against the policy of
+/// `environment`. `None` when it isn't destructive.
+pub fn check_command(
+    db: &Db,
+    environment: &str,
+    text: &str,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+) -> Result<Option<PolicyApproval>, String> {
+    match danger_finding(db, text)? {
+        Some(name) => {
+            check_destructive(db, environment, &format!("this command ({name})"), text, confirmation, approval).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// The OS account OpsPad runs under, as the confirming user.
+pub(crate) fn os_user() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().map(|u| u.trim().to_string()).filter(|u| !u.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// An audit record of `approval` for `command_text` run in `environment`; the caller fills in
+/// where it ran and stores it with `Db::confirmations_add`.
+pub fn confirmation(approval: &PolicyApproval, environment: &str, command_text: &str) -> Confirmation {
+    Confirmation {
+        id: String::new(),
+        confirmed_at: 0,
+        os_user: os_user(),
+        incident_id: None,
+        session_id: None,
+        scope: None,
+        host_id: None,
+        environment_tag: environment.to_string(),
+        dock_command_id: None,
+        dock_command_title: None,
+        command_text: command_text.to_string(),
+        policy: approval.policy,
+        confirmation_text: approval.confirmation.clone(),
+        history_id: None,
+        approved_by: approval.approved_by.clone(),
+    }
+}
@@ -0,0 +1,545 @@
+//! Quick checks before an ssh session is opened: does the name resolve, does the port (or the jump
+//! host's) take a connection, is the identity file there and private enough for ssh to use. The
+//! failures come back as data instead of ssh printing them into a tab that then dies.
+//!
+//! `host_preflight` runs them on demand. With the automatic mode on (settings key
+//! `ssh_preflight`), opening a session runs them first and refuses if any fail.
+//!
+//! Targets are taken as ssh sees them (`ssh -G`), so `~/.ssh/config` aliases, ports and
+//! `ProxyJump` are honored.
+//!
+//! `host_verify_path` goes further for hosts behind jump hosts ([`verify_path`]): it logs in to
+//! each hop in turn, through the hops before it, so a failure names the hop where the chain broke
+//! and whether it was the connection, the host key or authentication.
+
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arch::ssh;
+use crate::db::Db;
+use crate::{exec, security_key};
+
+const SETTINGS_KEY: &str = "ssh_preflight";
+const SSH_CONFIG_TIMEOUT: Duration = Duration::from_secs(3);
+const MIN_TIMEOUT_MS: u64 = 500;
+const MAX_TIMEOUT_MS: u64 = 15_000;
+/// ssh's `ConnectTimeout` for each hop of a path check.
+const HOP_CONNECT_TIMEOUT_SECS: u64 = 10;
+/// A whole hop probe, including authentication through the hops before it.
+const HOP_PROBE_TIMEOUT: Duration = Duration::from_secs(45);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightConfig {
+    /// Run the checks before every ssh session and refuse to open on a failure.
+    #[serde(default)]
+    pub auto: bool,
+    /// Per network check (resolve, connect).
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    3000
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+}
+
+impl PreflightConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.clamp(MIN_TIMEOUT_MS, MAX_TIMEOUT_MS))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not applicable here, e.g. the target is only reached through a proxy.
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    /// `sshConfig`, `dns`, `proxy`, `tcp`, `identityFile` or `securityKey`.
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preflight {
+    /// `user@host:port` as ssh will connect to it.
+    pub target: String,
+    /// No check failed.
+    pub ok: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl Preflight {
+    /// One line per failed check, for refusing to open a session.
+    pub fn failure_message(&self) -> Option<String> {
+        let failed: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .map(|c| format!("{}: {}", c.name, c.detail))
+            .collect();
+        (!failed.is_empty()).then(|| format!("ssh pre-flight failed for {}\n{}", self.target, failed.join("\n")))
+    }
+}
+
+/// What to connect to, as the session would be opened.
+pub struct Target<'a> {
+    pub user: &'a str,
+    pub host: &'a str,
+    pub port: Option<u16>,
+    pub identity_file: Option<&'a str>,
+    pub extra_args: &'a [String],
+}
+
+/// The parts of ssh's effective configuration the checks need.
+struct Effective {
+    hostname: String,
+    port: u16,
+    proxy_jump: Option<String>,
+    proxy_command: Option<String>,
+    /// Set to something other than ssh's built-in FIDO middleware.
+    security_key_provider: Option<String>,
+}
+
+fn effective(target: &Target<'_>) -> Result<Effective, String> {
+    let program = ssh::ssh_program_checked()?;
+    let mut args = vec!["-G".to_string()];
+    if let Some(p) = target.port {
+        args.push("-p".to_string());
+        args.push(p.to_string());
+    }
+    args.extend(target.extra_args.iter().cloned());
+    args.push(format!("{}@{}", target.user, target.host));
+    let out = exec::run(&program, &args, SSH_CONFIG_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.exit_code != Some(0) {
+        return Err(out.stderr.trim().to_string());
+    }
+    let mut config = Effective {
+        hostname: target.host.to_string(),
+        port: target.port.unwrap_or(22),
+        proxy_jump: None,
+        proxy_command: None,
+        security_key_provider: None,
+    };
+    for line in out.stdout.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let value = value.trim();
+        let set = (!value.is_empty() && value != "none").then(|| value.to_string());
+        match key {
+            "hostname" => config.hostname = value.to_string(),
+            "port" => config.port = value.parse().unwrap_or(config.port),
+            "proxyjump" => config.proxy_jump = set,
+            "proxycommand" => config.proxy_command = set,
+            "securitykeyprovider" => config.security_key_provider = set.filter(|v| v != "internal"),
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+/// Name resolution can block well past any timeout we'd like, so it runs on its own thread.
+fn resolve(host: &str, port: u16, timeout: Duration) -> Result<Vec<SocketAddr>, String> {
+    let (tx, rx) = mpsc::channel();
+    let host = host.to_string();
+    thread::spawn(move || {
+        let _ = tx.send((host.as_str(), port).to_socket_addrs().map(|a| a.collect::<Vec<_>>()));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(addrs)) if !addrs.is_empty() => Ok(addrs),
+        Ok(Ok(_)) => Err("no addresses".to_string()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {} ms", timeout.as_millis())),
+    }
+}
+
+/// The first address that takes a connection, and how long it took.
+fn connect(addrs: &[SocketAddr], timeout: Duration) -> Result<(SocketAddr, Duration), String> {
+    let mut last = String::new();
+    for addr in addrs {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(_) => return Ok((*addr, started.elapsed())),
+            Err(e) => last = format!("{addr}: {e}"),
+        }
+    }
+    Err(last)
+}
+
+fn check(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> PreflightCheck {
+    PreflightCheck {
+        name,
+        status,
+        detail: detail.into(),
+    }
+}
+
+/// One hop of a `ProxyJump` spec: `[ssh://][user@]host[:port]`.
+struct Hop {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+}
+
+fn parse_hop(hop: &str) -> Hop {
+    let hop = hop.trim();
+    let hop = hop.strip_prefix("ssh://").unwrap_or(hop);
+    let (user, hop) = match hop.rsplit_once('@') {
+        Some((user, host)) => (Some(user.to_string()), host),
+        None => (None, hop),
+    };
+    if let Some(rest) = hop.strip_prefix('[') {
+        if let Some((host, tail)) = rest.split_once(']') {
+            let port = tail.strip_prefix(':').and_then(|p| p.parse().ok());
+            return Hop { user, host: host.to_string(), port };
+        }
+    }
+    match hop.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => Hop {
+            user,
+            host: host.to_string(),
+            port: port.parse().ok(),
+        },
+        _ => Hop { user, host: hop.to_string(), port: None },
+    }
+}
+
+/// `host:port` of the first hop of a `ProxyJump` spec (`[user@]host[:port],...`).
+fn first_hop(spec: &str) -> (String, u16) {
+    let hop = parse_hop(spec.split(',').next().unwrap_or(spec));
+    (hop.host, hop.port.unwrap_or(22))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(path), |home| home.join(rest)),
+        None => PathBuf::from(path),
+    }
+}
+
+fn check_identity_file(path: &str) -> PreflightCheck {
+    let resolved = expand_home(path);
+    let meta = match std::fs::metadata(&resolved) {
+        Ok(meta) => meta,
+        Err(e) => return check("identityFile", CheckStatus::Fail, format!("{path}: {e}")),
+    };
+    if !meta.is_file() {
+        return check("identityFile", CheckStatus::Fail, format!("{path} is not a file"));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = meta.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return check(
+                "identityFile",
+                CheckStatus::Fail,
+                format!("{path} is readable by others (mode {mode:o}); ssh will refuse it. Run chmod 600 on it."),
+            );
+        }
+    }
+    check("identityFile", CheckStatus::Pass, path.to_string())
+}
+
+/// For an `sk-` identity: can ssh reach the key?
+fn check_security_key(provider: Option<&str>) -> PreflightCheck {
+    let support = security_key::support();
+    if !support.key_types {
+        return check("securityKey", CheckStatus::Fail, support.notes.join("; "));
+    }
+    match provider {
+        Some(provider) => check("securityKey", CheckStatus::Pass, format!("SecurityKeyProvider {provider}")),
+        None if support.builtin_provider => check("securityKey", CheckStatus::Pass, "built-in FIDO support"),
+        None => check("securityKey", CheckStatus::Warn, support.notes.join("; ")),
+    }
+}
+
+pub fn run(target: &Target<'_>, config: &PreflightConfig) -> Preflight {
+    let timeout = config.timeout();
+    let mut checks = Vec::new();
+    let effective = match effective(target) {
+        Ok(e) => e,
+        Err(e) => {
+            checks.push(check(
+                "sshConfig",
+                CheckStatus::Warn,
+                format!("couldn't read ssh's configuration, checking the host as entered: {e}"),
+            ));
+            Effective {
+                hostname: target.host.to_string(),
+                port: target.port.unwrap_or(22),
+                proxy_jump: None,
+                proxy_command: None,
+                security_key_provider: None,
+            }
+        }
+    };
+
+    if let Some(command) = &effective.proxy_command {
+        checks.push(check("proxy", CheckStatus::Skipped, format!("reached through ProxyCommand {command}")));
+        checks.push(check("dns", CheckStatus::Skipped, "resolved by the proxy command"));
+        checks.push(check("tcp", CheckStatus::Skipped, "connected by the proxy command"));
+    } else if let Some(jump) = &effective.proxy_jump {
+        let (host, port) = first_hop(jump);
+        let reached = resolve(&host, port, timeout).and_then(|addrs| connect(&addrs, timeout));
+        checks.push(match reached {
+            Ok((addr, took)) => check(
+                "proxy",
+                CheckStatus::Pass,
+                format!("jump host {host}:{port} ({addr}) answered in {} ms", took.as_millis()),
+            ),
+            Err(e) => check("proxy", CheckStatus::Fail, format!("jump host {host}:{port}: {e}")),
+        });
+        checks.push(check("dns", CheckStatus::Skipped, "resolved by the jump host"));
+        checks.push(check("tcp", CheckStatus::Skipped, "connected from the jump host"));
+    } else {
+        match resolve(&effective.hostname, effective.port, timeout) {
+            Ok(addrs) => {
+                let shown: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+                checks.push(check("dns", CheckStatus::Pass, shown.join(", ")));
+                checks.push(match connect(&addrs, timeout) {
+                    Ok((addr, took)) => check(
+                        "tcp",
+                        CheckStatus::Pass,
+                        format!("{addr} answered in {} ms", took.as_millis()),
+                    ),
+                    Err(e) => check("tcp", CheckStatus::Fail, e),
+                });
+            }
+            Err(e) => {
+                checks.push(check("dns", CheckStatus::Fail, format!("{}: {e}", effective.hostname)));
+                checks.push(check("tcp", CheckStatus::Skipped, "nothing to connect to"));
+            }
+        }
+    }
+
+    checks.push(match target.identity_file.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(path) => check_identity_file(path),
+        None => check("identityFile", CheckStatus::Skipped, "none set; ssh tries its defaults and the agent"),
+    });
+    if let Some(path) = target.identity_file.map(str::trim).filter(|s| !s.is_empty()) {
+        if security_key::is_security_key_identity(&expand_home(path).to_string_lossy()) {
+            checks.push(check_security_key(effective.security_key_provider.as_deref()));
+        }
+    }
+
+    Preflight {
+        target: format!("{}@{}:{}", target.user, effective.hostname, effective.port),
+        ok: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+/// Where a hop probe stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HopStage {
+    /// Resolving, connecting, or the hop before it couldn't forward the connection.
+    Connect,
+    HostKey,
+    Auth,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathHop {
+    /// The hop as written in `ProxyJump`, or the target itself for the last one.
+    pub hop: String,
+    pub is_target: bool,
+    pub status: CheckStatus,
+    /// Where it failed, for a failed hop.
+    pub stage: Option<HopStage>,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathReport {
+    /// `user@host:port` as ssh will connect to it.
+    pub target: String,
+    pub ok: bool,
+    /// Index into `hops` of the first hop that failed.
+    pub failed_hop: Option<usize>,
+    pub hops: Vec<PathHop>,
+}
+
+/// Which part of the login ssh's error came from, judging by its message.
+fn failure_stage(stderr: &str) -> HopStage {
+    let lower = stderr.to_lowercase();
+    if lower.contains("host key verification failed") || lower.contains("remote host identification has changed") {
+        HopStage::HostKey
+    } else if lower.contains("permission denied") || lower.contains("too many authentication failures") {
+        HopStage::Auth
+    } else {
+        HopStage::Connect
+    }
+}
+
+/// The last line ssh printed that says what went wrong (it prints warnings and banners too).
+fn failure_detail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines
+        .iter()
+        .rev()
+        .find(|l| !l.starts_with("Warning:"))
+        .or(lines.last())
+        .map_or_else(|| "ssh failed without saying why".to_string(), |l| l.to_string())
+}
+
+/// Run ssh with `args` (ending in the destination) and a no-op command: any exit but ssh's own 255
+/// means the connection and authentication went through.
+fn probe(mut args: Vec<String>, hop: String, is_target: bool) -> PathHop {
+    args.push("exit".to_string());
+    let started = Instant::now();
+    let result = ssh::ssh_program_checked().and_then(|program| {
+        exec::run(&program, &args, HOP_PROBE_TIMEOUT).map_err(|e| e.to_string())
+    });
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let (status, stage, detail) = match result {
+        Err(e) => (CheckStatus::Fail, Some(HopStage::Connect), e),
+        Ok(out) if out.timed_out => (
+            CheckStatus::Fail,
+            Some(HopStage::Connect),
+            format!("no answer within {} s", HOP_PROBE_TIMEOUT.as_secs()),
+        ),
+        Ok(out) if out.exit_code == Some(255) => {
+            (CheckStatus::Fail, Some(failure_stage(&out.stderr)), failure_detail(&out.stderr))
+        }
+        Ok(_) => (CheckStatus::Pass, None, format!("logged in in {duration_ms} ms")),
+    };
+    PathHop {
+        hop,
+        is_target,
+        status,
+        stage,
+        detail,
+        duration_ms,
+    }
+}
+
+/// Options shared by every hop probe: no prompts (there's no one to answer them), and a fresh
+/// connection even where ssh would reuse a multiplexed one.
+fn probe_args() -> Vec<String> {
+    vec![
+        "-T".to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        format!("ConnectTimeout={HOP_CONNECT_TIMEOUT_SECS}"),
+        "-o".to_string(),
+        "ControlMaster=no".to_string(),
+        "-o".to_string(),
+        "ControlPath=none".to_string(),
+    ]
+}
+
+/// Log in to each hop of the target's jump chain through the hops before it, then to the target
+/// through all of them, stopping at the first failure.
+pub fn verify_path(target: &Target<'_>) -> PathReport {
+    let effective = effective(target).ok();
+    let chain: Vec<String> = effective
+        .as_ref()
+        .and_then(|e| e.proxy_jump.as_deref())
+        .map(|spec| spec.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+        .unwrap_or_default();
+
+    let mut hops = Vec::new();
+    for (i, spec) in chain.iter().enumerate() {
+        let hop = parse_hop(spec);
+        let mut args = probe_args();
+        if i > 0 {
+            args.push("-J".to_string());
+            args.push(chain[..i].join(","));
+        }
+        if let Some(port) = hop.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        args.push(match &hop.user {
+            Some(user) => format!("{user}@{}", hop.host),
+            None => hop.host.clone(),
+        });
+        hops.push(probe(args, spec.clone(), false));
+        if hops.last().is_some_and(|h| h.status == CheckStatus::Fail) {
+            break;
+        }
+    }
+
+    let reached = hops.iter().all(|h| h.status != CheckStatus::Fail);
+    let target_name = format!("{}@{}", target.user, target.host);
+    if reached {
+        let mut args = probe_args();
+        if let Some(p) = target.port {
+            args.push("-p".to_string());
+            args.push(p.to_string());
+        }
+        if let Some(id) = target.identity_file.map(str::trim).filter(|s| !s.is_empty()) {
+            args.push("-i".to_string());
+            args.push(id.to_string());
+        }
+        args.extend(target.extra_args.iter().cloned());
+        args.push(target_name.clone());
+        hops.push(probe(args, target_name, true));
+    } else {
+        let skipped = chain[hops.len()..].iter().map(|h| (h.clone(), false));
+        for (hop, is_target) in skipped.chain(std::iter::once((target_name, true))) {
+            hops.push(PathHop {
+                hop,
+                is_target,
+                status: CheckStatus::Skipped,
+                stage: None,
+                detail: "not reached".to_string(),
+                duration_ms: 0,
+            });
+        }
+    }
+
+    let failed_hop = hops.iter().position(|h| h.status == CheckStatus::Fail);
+    PathReport {
+        target: match &effective {
+            Some(e) => format!("{}@{}:{}", target.user, e.hostname, e.port),
+            None => format!("{}@{}:{}", target.user, target.host, target.port.unwrap_or(22)),
+        },
+        ok: failed_hop.is_none(),
+        failed_hop,
+        hops,
+    }
+}
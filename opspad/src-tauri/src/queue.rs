@@ -0,0 +1,222 @@
+//! Worker-pool queue for headless work (jobs, fan-out runs).
+//!
+//! Bounds how many ssh/shell processes run at once, serializes tasks that share a key (one
+//! task per host at a time), and retries transient failures with exponential backoff.
+
+use std::collections::{HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::Db;
+
+const SETTINGS_KEY: &str = "queue.config";
+const MAX_PARALLEL_CAP: usize = 64;
+const MAX_RETRIES_CAP: u32 = 10;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueConfig {
+    pub max_parallel: usize,
+    /// Extra attempts after the first one, for failures a task reports as retryable.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on each further attempt.
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            max_parallel: 8,
+            max_retries: 2,
+            retry_backoff_ms: 2000,
+        }
+    }
+}
+
+impl QueueConfig {
+    fn normalized(mut self) -> Self {
+        self.max_parallel = self.max_parallel.clamp(1, MAX_PARALLEL_CAP);
+        self.max_retries = self.max_retries.min(MAX_RETRIES_CAP);
+        self.retry_backoff_ms = self.retry_backoff_ms.min(MAX_BACKOFF.as_millis() as u64);
+        self
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ms = self
+            .retry_backoff_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(16));
+        Duration::from_millis(ms).min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub running: usize,
+    pub pending: usize,
+}
+
+/// Passed to a task on every attempt (1-based).
+#[derive(Clone, Copy, Debug)]
+pub struct Attempt {
+    pub number: u32,
+    /// No retry will follow this attempt, so the task must record its final outcome.
+    pub is_last: bool,
+}
+
+pub enum TaskOutcome {
+    Done,
+    /// Transient failure; requeued with backoff unless this was the last attempt.
+    Retry,
+}
+
+type Work = Box<dyn FnMut(Attempt) -> TaskOutcome + Send>;
+
+struct Task {
+    /// Tasks with the same key never run concurrently (e.g. "ssh:<host_id>").
+    key: Option<String>,
+    attempt: u32,
+    not_before: Instant,
+    work: Work,
+}
+
+#[derive(Default)]
+struct Inner {
+    pending: VecDeque<Task>,
+    busy_keys: HashSet<String>,
+    running: usize,
+    workers: usize,
+    config: QueueConfig,
+}
+
+/// Cheap to clone; clones share the same pool.
+#[derive(Clone, Default)]
+pub struct WorkQueue {
+    inner: Arc<(Mutex<Inner>, Condvar)>,
+}
+
+impl WorkQueue {
+    /// Load the persisted configuration (defaults if unset or unreadable).
+    pub fn load_config(&self, db: &Db) {
+        let config = db
+            .settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str::<QueueConfig>(&v).ok())
+            .unwrap_or_default();
+        self.inner.0.lock().expect("poisoned queue lock").config = config.normalized();
+    }
+
+    pub fn config(&self) -> QueueConfig {
+        self.inner.0.lock().expect("poisoned queue lock").config.clone()
+    }
+
+    pub fn set_config(&self, db: &Db, config: QueueConfig) -> Result<QueueConfig, String> {
+        let config = config.normalized();
+        let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())?;
+        let (lock, cvar) = &*self.inner;
+        lock.lock().expect("poisoned queue lock").config = config.clone();
+        // A higher limit may let waiting tasks start now.
+        cvar.notify_all();
+        self.ensure_workers();
+        Ok(config)
+    }
+
+    pub fn status(&self) -> QueueStatus {
+        let inner = self.inner.0.lock().expect("poisoned queue lock");
+        QueueStatus {
+            running: inner.running,
+            pending: inner.pending.len(),
+        }
+    }
+
+    pub fn submit(&self, key: Option<String>, work: impl FnMut(Attempt) -> TaskOutcome + Send + 'static) {
+        let (lock, cvar) = &*self.inner;
+        lock.lock().expect("poisoned queue lock").pending.push_back(Task {
+            key,
+            attempt: 1,
+            not_before: Instant::now(),
+            work: Box::new(work),
+        });
+        cvar.notify_one();
+        self.ensure_workers();
+    }
+
+    fn ensure_workers(&self) {
+        let mut inner = self.inner.0.lock().expect("poisoned queue lock");
+        let wanted = inner.config.max_parallel.min(inner.pending.len() + inner.running);
+        while inner.workers < wanted {
+            inner.workers += 1;
+            let shared = self.inner.clone();
+            thread::spawn(move || worker(shared));
+        }
+    }
+}
+
+fn worker(shared: Arc<(Mutex<Inner>, Condvar)>) {
+    let (lock, cvar) = &*shared;
+    let mut inner = lock.lock().expect("poisoned queue lock");
+    loop {
+        let now = Instant::now();
+        let next = if inner.running < inner.config.max_parallel {
+            inner.pending.iter().position(|t| {
+                t.not_before <= now && t.key.as_ref().is_none_or(|k| !inner.busy_keys.contains(k))
+            })
+        } else {
+            None
+        };
+
+        let Some(idx) = next else {
+            if inner.pending.is_empty() {
+                // Nothing left to pick up: let this thread go; `submit` spawns workers on demand.
+                inner.workers -= 1;
+                return;
+            }
+            // Wake for new work, a finished task, or the earliest backoff deadline.
+            let wait = inner
+                .pending
+                .iter()
+                .map(|t| t.not_before.saturating_duration_since(now))
+                .filter(|d| !d.is_zero())
+                .min()
+                .unwrap_or(Duration::from_secs(1));
+            inner = cvar
+                .wait_timeout(inner, wait)
+                .expect("poisoned queue lock")
+                .0;
+            continue;
+        };
+
+        let mut task = inner.pending.remove(idx).expect("valid pending index");
+        if let Some(k) = &task.key {
+            inner.busy_keys.insert(k.clone());
+        }
+        inner.running += 1;
+        let attempt = Attempt {
+            number: task.attempt,
+            is_last: task.attempt > inner.config.max_retries,
+        };
+        drop(inner);
+
+        // A panicking task must not take the worker (and its running/busy-key bookkeeping) down.
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| (task.work)(attempt))).unwrap_or(TaskOutcome::Done);
+
+        inner = lock.lock().expect("poisoned queue lock");
+        inner.running -= 1;
+        if let Some(k) = &task.key {
+            inner.busy_keys.remove(k);
+        }
+        if matches!(outcome, TaskOutcome::Retry) && !attempt.is_last {
+            task.not_before = Instant::now() + inner.config.backoff(task.attempt);
+            task.attempt += 1;
+            inner.pending.push_back(task);
+        }
+        cvar.notify_all();
+    }
+}
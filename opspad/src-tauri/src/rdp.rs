@@ -0,0 +1,304 @@
+//! Remote desktop sessions to hosts whose connection type is RDP.
+//!
+//! OpsPad doesn't draw the desktop itself: it writes a temporary `.rdp` file and hands it to
+//! mstsc on Windows (or the Microsoft Remote Desktop app on macOS), or runs FreeRDP elsewhere.
+//! The client process is tracked as a session with no PTY: `rdp:opened` when it starts,
+//! `rdp:closed` when it exits (or is closed from here), and the temporary files go with it.
+//!
+//! With `inject_credentials`, the host's bound password (`hosts_set_credentials`) is handed to
+//! the client so it doesn't prompt: FreeRDP reads it from stdin, mstsc finds it in the Windows
+//! credential store, where `cmdkey` puts it for the length of the session. A credential the user
+//! had already saved for the host is used as-is and left in place.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::db::{ConnectionType, Host};
+use crate::{secrets, AppState};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+#[cfg(not(any(windows, target_os = "macos")))]
+const FREERDP_PROGRAMS: [&str; 5] = ["xfreerdp3", "sdl-freerdp3", "xfreerdp", "sdl-freerdp", "wlfreerdp"];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RdpSession {
+    pub id: String,
+    pub host_id: String,
+    pub label: String,
+    /// The client that was launched ("mstsc", "xfreerdp", ...).
+    pub client: String,
+    /// Whether the host's stored password was handed to the client.
+    pub credentials_injected: bool,
+    pub started_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RdpClosedEvent {
+    pub id: String,
+    pub host_id: String,
+    /// The client's exit code, when it exited by itself.
+    pub exit_code: Option<i32>,
+    pub duration_secs: u64,
+}
+
+struct Running {
+    session: RdpSession,
+    child: Child,
+    started: Instant,
+    rdp_file: Option<PathBuf>,
+    /// `cmdkey` target to delete when the session ends.
+    credential_target: Option<String>,
+}
+
+/// Client processes of open RDP sessions, keyed by session id.
+#[derive(Default)]
+pub struct RdpSessions {
+    running: Mutex<HashMap<String, Running>>,
+}
+
+impl RdpSessions {
+    pub fn list(&self) -> Vec<RdpSession> {
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<RdpSession> = running.values().map(|r| r.session.clone()).collect();
+        list.sort_by_key(|s| s.started_at);
+        list
+    }
+
+    /// Close the client; `rdp:closed` follows once it has exited.
+    pub fn close(&self, id: &str) -> Result<(), String> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let r = running.get_mut(id).ok_or_else(|| "no such RDP session".to_string())?;
+        r.child.kill().map_err(|e| e.to_string())
+    }
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// The host's bound password, if any (asking for verification when the secret requires it).
+fn password(state: &AppState, host: &Host) -> Result<Option<String>, String> {
+    let Some(key) = host.password_vault_key.as_deref() else {
+        return Ok(None);
+    };
+    let bytes = secrets::get_verified(state.vault.as_ref(), key)?;
+    Ok(bytes.and_then(|b| String::from_utf8(b).ok()).filter(|p| !p.is_empty()))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn rdp_file(host: &Host) -> Result<PathBuf, String> {
+    let path = std::env::temp_dir().join(format!("opspad-rdp-{}.rdp", Uuid::new_v4()));
+    let contents = format!(
+        "full address:s:{}:{}\r\nusername:s:{}\r\n",
+        host.hostname.trim(),
+        host.port,
+        host.username.trim()
+    );
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// A launched client, with whatever has to be cleaned up after it.
+struct Launch {
+    client: String,
+    child: Child,
+    rdp_file: Option<PathBuf>,
+    credential_target: Option<String>,
+    credentials_injected: bool,
+}
+
+#[cfg(windows)]
+fn forget_credential(target: &str) {
+    let args = vec![format!("/delete:{target}")];
+    let _ = crate::exec::run("cmdkey", &args, Duration::from_secs(15));
+}
+
+#[cfg(not(windows))]
+fn forget_credential(_target: &str) {}
+
+#[cfg(windows)]
+fn launch(host: &Host, password: Option<String>) -> Result<Launch, String> {
+    let target = format!("TERMSRV/{}", host.hostname.trim());
+    let timeout = Duration::from_secs(15);
+    let run = |args: Vec<String>| crate::exec::run("cmdkey", &args, timeout).map_err(|e| e.to_string());
+    let mut credential_target = None;
+    let mut credentials_injected = false;
+    if let Some(password) = password {
+        let listed = run(vec![format!("/list:{target}")])?;
+        if listed.stdout.contains(&target) {
+            // The user's own saved credential: mstsc will use it, and it isn't ours to remove.
+            credentials_injected = true;
+        } else {
+            let out = run(vec![
+                format!("/generic:{target}"),
+                format!("/user:{}", host.username.trim()),
+                format!("/pass:{password}"),
+            ])?;
+            if out.exit_code != Some(0) {
+                return Err("couldn't store the credential for mstsc".to_string());
+            }
+            credential_target = Some(target);
+            credentials_injected = true;
+        }
+    }
+    let path = rdp_file(host)?;
+    let child = Command::new("mstsc").arg(&path).stdin(Stdio::null()).spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            if let Some(target) = &credential_target {
+                forget_credential(target);
+            }
+            return Err(format!("couldn't start mstsc: {e}"));
+        }
+    };
+    Ok(Launch {
+        client: "mstsc".to_string(),
+        child,
+        rdp_file: Some(path),
+        credential_target,
+        credentials_injected,
+    })
+}
+
+/// The Microsoft Remote Desktop app opens `.rdp` files; `open -W` returns when the app quits.
+/// It has no way to be handed a password.
+#[cfg(target_os = "macos")]
+fn launch(host: &Host, _password: Option<String>) -> Result<Launch, String> {
+    let path = rdp_file(host)?;
+    let child = Command::new("open").arg("-W").arg(&path).stdin(Stdio::null()).spawn();
+    let child = child.map_err(|e| {
+        let _ = std::fs::remove_file(&path);
+        format!("couldn't open the remote desktop app: {e}")
+    })?;
+    Ok(Launch {
+        client: "Microsoft Remote Desktop".to_string(),
+        child,
+        rdp_file: Some(path),
+        credential_target: None,
+        credentials_injected: false,
+    })
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn launch(host: &Host, password: Option<String>) -> Result<Launch, String> {
+    let program = FREERDP_PROGRAMS
+        .iter()
+        .find_map(|p| which::which(p).ok())
+        .ok_or_else(|| "FreeRDP isn't installed (xfreerdp or sdl-freerdp)".to_string())?;
+    let client = program
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "freerdp".to_string());
+    let mut cmd = Command::new(&program);
+    cmd.arg(format!("/v:{}:{}", host.hostname.trim(), host.port))
+        .arg(format!("/u:{}", host.username.trim()))
+        .arg(format!("/t:{}", host.label.trim()))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if password.is_some() {
+        // FreeRDP prompts on stdin for anything not given on the command line.
+        cmd.arg("/from-stdin").stdin(Stdio::piped());
+    } else {
+        cmd.stdin(Stdio::null());
+    }
+    let mut child = cmd.spawn().map_err(|e| format!("couldn't start {client}: {e}"))?;
+    if let (Some(password), Some(mut stdin)) = (password.as_deref(), child.stdin.take()) {
+        use std::io::Write as _;
+        let _ = stdin.write_all(format!("{password}\n").as_bytes());
+    }
+    Ok(Launch {
+        client,
+        credentials_injected: password.is_some(),
+        child,
+        rdp_file: None,
+        credential_target: None,
+    })
+}
+
+/// Launch a remote desktop client for `host_id`.
+pub fn open(app: &AppHandle, state: &Arc<AppState>, host_id: &str, inject_credentials: bool) -> Result<RdpSession, String> {
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("host not found: {host_id}"))?;
+    if host.connection_type != ConnectionType::Rdp {
+        return Err(format!("{} is an SSH host", host.label));
+    }
+    let password = if inject_credentials {
+        Some(password(state, &host)?.ok_or_else(|| format!("no password is bound to {}", host.label))?)
+    } else {
+        None
+    };
+    let launch = launch(&host, password)?;
+    let session = RdpSession {
+        id: Uuid::new_v4().to_string(),
+        host_id: host.id.clone(),
+        label: host.label.clone(),
+        client: launch.client,
+        credentials_injected: launch.credentials_injected,
+        started_at: now_epoch_secs(),
+    };
+    state.db.hosts_touch_connected(&host.id).map_err(|e| e.to_string())?;
+    state.rdp.running.lock().unwrap_or_else(|e| e.into_inner()).insert(
+        session.id.clone(),
+        Running {
+            session: session.clone(),
+            child: launch.child,
+            started: Instant::now(),
+            rdp_file: launch.rdp_file,
+            credential_target: launch.credential_target,
+        },
+    );
+    log::info!("rdp session {} to {} opened with {}", session.id, host.label, session.client);
+    let _ = app.emit("rdp:opened", &session);
+
+    let (app, state, id) = (app.clone(), state.clone(), session.id.clone());
+    thread::spawn(move || watch(&app, &state, &id));
+    Ok(session)
+}
+
+fn watch(app: &AppHandle, state: &AppState, id: &str) {
+    let finished = loop {
+        thread::sleep(POLL_INTERVAL);
+        let mut running = state.rdp.running.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(r) = running.get_mut(id) else { return };
+        match r.child.try_wait() {
+            Ok(None) => continue,
+            Ok(Some(status)) => break running.remove(id).map(|r| (r, status.code())),
+            Err(_) => break running.remove(id).map(|r| (r, None)),
+        }
+    };
+    let Some((r, exit_code)) = finished else { return };
+    if let Some(path) = &r.rdp_file {
+        let _ = std::fs::remove_file(path);
+    }
+    if let Some(target) = &r.credential_target {
+        forget_credential(target);
+    }
+    log::info!("rdp session {id} to {} closed", r.session.label);
+    let _ = app.emit(
+        "rdp:closed",
+        RdpClosedEvent {
+            id: id.to_string(),
+            host_id: r.session.host_id.clone(),
+            exit_code,
+            duration_secs: r.started.elapsed().as_secs(),
+        },
+    );
+}
@@ -0,0 +1,397 @@
+//! Session recording to asciicast v2 (`.cast`) files, with pause/resume and masking.
+//!
+//! A recording follows the session's output from the moment it starts. Nothing reaches the file
+//! unmasked:
+//! - secrets typed by the backend (`write_secret`) are masked by the terminal backend before any
+//!   consumer sees the output;
+//! - the redaction rules are applied to each line, so output is held until its line ends (or
+//!   briefly stalls, as at a prompt) rather than matched in fragments;
+//! - after a password-style prompt, whatever the session prints up to the next line break is
+//!   replaced with a placeholder, in case the program echoes the input.
+//!
+//! While paused nothing is written, and the paused time is cut from the recording's clock so
+//! playback doesn't stall; a placeholder line marks the gap. Changes are announced as
+//! `recording:state` events. Timeline marks (`terminal_mark`) are written as asciicast marker
+//! events, so players can jump to them.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::arch::paths;
+use crate::redact::Redactor;
+use crate::AppState;
+
+const DIR_NAME: &str = "recordings";
+/// An unterminated line is written after this long without more output.
+const FLUSH_AFTER: Duration = Duration::from_millis(250);
+/// An unterminated line longer than this is written without waiting for its end.
+const MAX_HELD_BYTES: usize = 16 * 1024;
+const HIDDEN_INPUT: &str = "[input hidden]";
+const PAUSED: &str = "\r\n\x1b[2m[recording paused]\x1b[0m\r\n";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub session_id: String,
+    pub path: String,
+    #[serde(with = "crate::timestamp::iso")]
+    pub started_at: i64,
+    pub paused: bool,
+}
+
+/// Payload of `recording:state`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingStateEvent {
+    #[serde(flatten)]
+    recording: RecordingInfo,
+    /// "recording" | "paused" | "stopped"
+    status: &'static str,
+}
+
+struct Active {
+    info: RecordingInfo,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    /// Mark labels waiting to be written.
+    marks: Arc<Mutex<Vec<String>>>,
+}
+
+/// Recordings in progress, at most one per session (keyed by session id).
+#[derive(Default)]
+pub struct Recordings {
+    active: Mutex<HashMap<String, Active>>,
+}
+
+impl Recordings {
+    pub fn list(&self) -> Vec<RecordingInfo> {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<RecordingInfo> = active
+            .values()
+            .map(|a| RecordingInfo {
+                paused: a.paused.load(Ordering::SeqCst),
+                ..a.info.clone()
+            })
+            .collect();
+        out.sort_by_key(|r| r.started_at);
+        out
+    }
+
+    /// Pause or resume the session's recording; `None` if it isn't being recorded.
+    pub fn set_paused(&self, app: &AppHandle, session_id: &str, paused: bool) -> Option<RecordingInfo> {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let a = active.get(session_id)?;
+        a.paused.store(paused, Ordering::SeqCst);
+        let recording = RecordingInfo {
+            paused,
+            ..a.info.clone()
+        };
+        let status = if paused { "paused" } else { "recording" };
+        let _ = app.emit("recording:state", RecordingStateEvent { recording: recording.clone(), status });
+        Some(recording)
+    }
+
+    /// Put a marker in the session's recording, if it's being recorded.
+    pub fn mark(&self, session_id: &str, label: &str) {
+        if let Some(a) = self.active.lock().unwrap_or_else(|e| e.into_inner()).get(session_id) {
+            a.marks.lock().unwrap_or_else(|e| e.into_inner()).push(label.to_string());
+        }
+    }
+
+    /// Stop the session's recording. Returns the file it went to.
+    pub fn stop(&self, session_id: &str) -> Option<String> {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let a = active.get(session_id)?;
+        a.stop.store(true, Ordering::SeqCst);
+        Some(a.info.path.clone())
+    }
+}
+
+pub fn root(app: &AppHandle) -> Result<PathBuf, String> {
+    paths::app_data_dir(app)
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| format!("no app data directory: {e}"))
+}
+
+pub(crate) fn prompt_re() -> &'static Regex {
+    // ssh ("alice@host's password: "), sudo, passphrase and one-time code prompts.
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)(password|passphrase|passcode|verification code|one-time code|\bpin)[^\n\r]*: ?$")
+            .expect("valid password prompt regex")
+    })
+}
+
+/// Applies the masking described above to output on its way to the file.
+struct Masker {
+    redactor: Redactor,
+    /// A password prompt was the last thing written; output up to the next line break is hidden.
+    hiding: bool,
+}
+
+impl Masker {
+    fn mask(&mut self, text: &str) -> String {
+        let mut rest = text;
+        let mut out = String::new();
+        if self.hiding {
+            match rest.find(['\r', '\n']) {
+                Some(end) => {
+                    if !rest[..end].trim().is_empty() {
+                        out.push_str(HIDDEN_INPUT);
+                    }
+                    rest = &rest[end..];
+                    self.hiding = false;
+                }
+                None => {
+                    return if rest.trim().is_empty() { String::new() } else { HIDDEN_INPUT.to_string() };
+                }
+            }
+        }
+        out.push_str(&self.redactor.redact(rest));
+        let last_line = out.rsplit(['\n', '\r']).next().unwrap_or(&out);
+        let plain = crate::transcript::clean(last_line);
+        self.hiding = prompt_re().is_match(plain.trim_end_matches(' '));
+        out
+    }
+}
+
+struct CastWriter {
+    file: BufWriter<File>,
+    started: Instant,
+    /// Time spent paused, left out of event times.
+    paused_for: Duration,
+    paused_since: Option<Instant>,
+}
+
+impl CastWriter {
+    fn event(&mut self, text: &str) -> std::io::Result<()> {
+        self.write("o", text)
+    }
+
+    /// An asciicast marker, which players list as a chapter.
+    fn marker(&mut self, label: &str) -> std::io::Result<()> {
+        self.write("m", label)
+    }
+
+    fn write(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let elapsed = match self.paused_since {
+            Some(since) => since.duration_since(self.started),
+            None => self.started.elapsed(),
+        };
+        let at = elapsed.saturating_sub(self.paused_for).as_secs_f64();
+        let line = serde_json::json!([(at * 1_000_000.0).round() / 1_000_000.0, code, data]);
+        writeln!(self.file, "{line}")
+    }
+
+    /// Follow the pause flag: mark the gap once, and don't count it on resume.
+    fn sync_pause(&mut self, paused: bool) -> std::io::Result<()> {
+        match (paused, self.paused_since) {
+            (true, None) => {
+                self.event(PAUSED)?;
+                self.paused_since = Some(Instant::now());
+            }
+            (false, Some(since)) => {
+                self.paused_for += since.elapsed();
+                self.paused_since = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Start recording `session_id` into a new file under the recordings directory.
+pub fn start(app: &AppHandle, state: &Arc<AppState>, session_id: &str) -> Result<RecordingInfo, String> {
+    if state.recordings.active.lock().unwrap_or_else(|e| e.into_inner()).contains_key(session_id) {
+        return Err("the session is already being recorded".to_string());
+    }
+    let session = state
+        .terminal
+        .list()
+        .into_iter()
+        .find(|s| s.session_id == session_id)
+        .ok_or_else(|| "terminal session not found".to_string())?;
+    let rules = state.db.redaction_rules_list().map_err(|e| e.to_string())?;
+
+    let dir = root(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("couldn't create {}: {e}", dir.display()))?;
+    let started_at = crate::timestamp::now_millis();
+    let path = dir.join(format!("{started_at}-{session_id}.cast"));
+    let mut file = BufWriter::new(File::create(&path).map_err(|e| format!("couldn't create {}: {e}", path.display()))?);
+    let header = serde_json::json!({
+        "version": 2,
+        "width": session.cols,
+        "height": session.rows,
+        "timestamp": started_at / 1000,
+        "title": session.title,
+        "env": { "TERM": "xterm-256color" },
+    });
+    writeln!(file, "{header}").map_err(|e| e.to_string())?;
+
+    // Subscribe last, so a failure above leaves nothing behind but the file.
+    let output = state.terminal.subscribe_output(session_id).map_err(|e| e.to_string())?;
+    let info = RecordingInfo {
+        session_id: session_id.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        started_at,
+        paused: false,
+    };
+    let paused = Arc::new(AtomicBool::new(false));
+    let stop = Arc::new(AtomicBool::new(false));
+    let marks = Arc::new(Mutex::new(Vec::new()));
+    {
+        let mut active = state.recordings.active.lock().unwrap_or_else(|e| e.into_inner());
+        if active.contains_key(session_id) {
+            return Err("the session is already being recorded".to_string());
+        }
+        active.insert(
+            session_id.to_string(),
+            Active {
+                info: info.clone(),
+                paused: paused.clone(),
+                stop: stop.clone(),
+                marks: marks.clone(),
+            },
+        );
+    }
+
+    let writer = CastWriter {
+        file,
+        started: Instant::now(),
+        paused_for: Duration::ZERO,
+        paused_since: None,
+    };
+    let masker = Masker {
+        redactor: Redactor::new(&rules),
+        hiding: false,
+    };
+    let _ = app.emit(
+        "recording:state",
+        RecordingStateEvent {
+            recording: info.clone(),
+            status: "recording",
+        },
+    );
+    let (app, state, session_id) = (app.clone(), state.clone(), session_id.to_string());
+    let recording = info.clone();
+    thread::spawn(move || {
+        if let Err(e) = record(&output, writer, masker, &paused, &stop, &marks) {
+            log::warn!("recording of {session_id} ended early: {e}");
+        }
+        state.recordings.active.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+        let _ = app.emit(
+            "recording:state",
+            RecordingStateEvent {
+                recording,
+                status: "stopped",
+            },
+        );
+    });
+
+    Ok(info)
+}
+
+fn record(
+    output: &Receiver<String>,
+    mut writer: CastWriter,
+    mut masker: Masker,
+    paused: &AtomicBool,
+    stop: &AtomicBool,
+    marks: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+    let mut held = String::new();
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let is_paused = paused.load(Ordering::SeqCst);
+        let pending = std::mem::take(&mut *marks.lock().unwrap_or_else(|e| e.into_inner()));
+        if (is_paused && writer.paused_since.is_none()) || !pending.is_empty() {
+            // What came in before the pause (or the mark) still belongs before it.
+            writer.event(&masker.mask(&std::mem::take(&mut held)))?;
+        }
+        for label in pending {
+            writer.marker(&masker.redactor.redact(&label))?;
+        }
+        writer.sync_pause(is_paused)?;
+        let ready = match output.recv_timeout(FLUSH_AFTER) {
+            Ok(chunk) => {
+                if is_paused {
+                    continue;
+                }
+                held.push_str(&chunk);
+                match held.rfind('\n') {
+                    _ if held.len() > MAX_HELD_BYTES => std::mem::take(&mut held),
+                    Some(end) => {
+                        let tail = held.split_off(end + 1);
+                        std::mem::replace(&mut held, tail)
+                    }
+                    None => continue,
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => std::mem::take(&mut held),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        writer.event(&masker.mask(&ready))?;
+        writer.file.flush()?;
+    }
+    if !paused.load(Ordering::SeqCst) {
+        writer.event(&masker.mask(&held))?;
+    }
+    writer.file.flush()
+}
+
+/// Recording files on disk, oldest first: (path, last written in epoch ms, bytes).
+fn files(root: &Path) -> Vec<(PathBuf, i64, u64)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(PathBuf, i64, u64)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "cast"))
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+            Some((e.path(), modified, meta.len()))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified, _)| *modified);
+    files
+}
+
+/// Delete recordings last written more than `max_days` ago, then the oldest until the rest fit in
+/// `max_bytes`. Recordings still in progress are kept (and counted). Returns (files, bytes)
+/// deleted.
+pub fn enforce(root: &Path, max_days: Option<u32>, max_bytes: Option<u64>, active: &[String]) -> (usize, u64) {
+    let files = files(root);
+    let mut total: u64 = files.iter().map(|(_, _, bytes)| bytes).sum();
+    let cutoff_ms = max_days.map(|days| crate::timestamp::now_millis() - i64::from(days) * 86_400_000);
+    let mut deleted = (0, 0);
+    for (path, modified, bytes) in files {
+        if active.iter().any(|p| Path::new(p) == path) {
+            continue;
+        }
+        let expired = cutoff_ms.is_some_and(|cutoff| modified < cutoff);
+        let over = max_bytes.is_some_and(|max| total > max);
+        if (expired || over) && fs::remove_file(&path).is_ok() {
+            total -= bytes;
+            deleted.0 += 1;
+            deleted.1 += bytes;
+        }
+    }
+    deleted
+}
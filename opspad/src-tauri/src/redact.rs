@@ -0,0 +1,65 @@
+//! Regex-based redaction of secrets in text that OpsPad persists (currently CommandDock history).
+//!
+//! Rules are user-managed and stored in SQLite (`redaction_rules`). Invalid or disabled rules are
+//! skipped rather than failing the write that is being redacted.
+
+use regex::Regex;
+
+use crate::db::RedactionRule;
+
+/// Built-in rules seeded on first run: (name, pattern, replacement).
+pub const DEFAULT_RULES: &[(&str, &str, &str)] = &[
+    (
+        "Bearer tokens",
+        r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]+",
+        "${1}****",
+    ),
+    (
+        "Password/token flags",
+        r"(?i)(--?(?:password|passwd|pass|pwd|token|secret|api[-_]?key)[= ])\S+",
+        "${1}****",
+    ),
+    (
+        "Secret env assignments",
+        r"(?i)\b([a-z0-9_]*(?:password|passwd|secret|token|api_?key)[a-z0-9_]*=)\S+",
+        "${1}****",
+    ),
+    (
+        "Connection string credentials",
+        r"([a-zA-Z][a-zA-Z0-9+.-]*://[^:/\s@]+:)[^@\s]+@",
+        "${1}****@",
+    ),
+    ("AWS access key ids", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", "****"),
+];
+
+/// Compiled set of enabled redaction rules, applied in order.
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    pub fn new(rules: &[RedactionRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| Regex::new(&r.pattern).ok().map(|re| (re, r.replacement.clone())))
+            .collect();
+        Self { rules }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (re, replacement) in &self.rules {
+            out = re.replace_all(&out, replacement.as_str()).into_owned();
+        }
+        out
+    }
+}
+
+/// Validate a user-supplied rule pattern before it is persisted.
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("redaction pattern must not be empty".to_string());
+    }
+    Regex::new(pattern).map(|_| ()).map_err(|e| format!("invalid redaction pattern: {e}"))
+}
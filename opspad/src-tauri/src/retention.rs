@@ -0,0 +1,217 @@
+//! How long OpsPad keeps session output on disk, and how much of it there is.
+//!
+//! Session recordings (`.cast` files) and spilled scrollback (the on-disk part of each session's
+//! transcript) are capped by age and by total size (settings key `retention`); the scrollback age
+//! limit is the scrollback setting's own `retentionDays`. A background job enforces the caps at
+//! startup and then hourly, oldest first, never touching a live session or a recording in
+//! progress, and announces what it deleted as `storage:purged`. `storage_usage` reports what's on
+//! disk, including the database, crash reports and logs, which aren't purged here.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::paths;
+use crate::db::Db;
+use crate::terminal::scrollback::{self, ScrollbackConfig};
+use crate::{recording, AppState};
+
+const SETTINGS_KEY: &str = "retention";
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MB: u64 = 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionConfig {
+    /// Recordings last written longer ago than this are deleted (`None`: kept however old).
+    #[serde(default = "default_recording_max_days")]
+    pub recording_max_days: Option<u32>,
+    /// The oldest recordings are deleted past this total (`None`: no cap).
+    #[serde(default = "default_recording_max_mb")]
+    pub recording_max_mb: Option<u64>,
+    /// The oldest ended sessions' scrollback is deleted past this total (`None`: no cap).
+    #[serde(default = "default_scrollback_max_mb")]
+    pub scrollback_max_mb: Option<u64>,
+}
+
+fn default_recording_max_days() -> Option<u32> {
+    Some(30)
+}
+
+fn default_recording_max_mb() -> Option<u64> {
+    Some(2048)
+}
+
+fn default_scrollback_max_mb() -> Option<u64> {
+    Some(2048)
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            recording_max_days: default_recording_max_days(),
+            recording_max_mb: default_recording_max_mb(),
+            scrollback_max_mb: default_scrollback_max_mb(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if self.recording_max_days == Some(0) {
+            return Err("keep recordings for at least a day".to_string());
+        }
+        if self.recording_max_mb == Some(0) || self.scrollback_max_mb == Some(0) {
+            return Err("a size limit must be at least 1 MB".to_string());
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// Payload of `storage:purged`.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeReport {
+    pub recordings_deleted: usize,
+    pub scrollback_sessions_deleted: usize,
+    pub bytes_freed: u64,
+}
+
+/// Enforce the retention settings now.
+pub fn purge(app: &AppHandle, state: &AppState) -> PurgeReport {
+    let config = RetentionConfig::load(&state.db);
+    let mut report = PurgeReport::default();
+
+    if let Ok(root) = recording::root(app) {
+        let active: Vec<String> = state.recordings.list().into_iter().map(|r| r.path).collect();
+        let (files, bytes) =
+            recording::enforce(&root, config.recording_max_days, config.recording_max_mb.map(|mb| mb * MB), &active);
+        report.recordings_deleted = files;
+        report.bytes_freed += bytes;
+    }
+    if let Ok(root) = scrollback::root(app) {
+        let live: Vec<String> = state.terminal.list().into_iter().map(|s| s.session_id).collect();
+        let retention_days = ScrollbackConfig::load(&state.db).retention_days;
+        let (sessions, bytes) =
+            scrollback::enforce(&root, retention_days, config.scrollback_max_mb.map(|mb| mb * MB), &live);
+        report.scrollback_sessions_deleted = sessions;
+        report.bytes_freed += bytes;
+    }
+
+    if report.recordings_deleted + report.scrollback_sessions_deleted > 0 {
+        log::info!(
+            "retention: deleted {} recording(s) and {} session(s) of scrollback, {} bytes",
+            report.recordings_deleted,
+            report.scrollback_sessions_deleted,
+            report.bytes_freed
+        );
+        let _ = app.emit("storage:purged", report.clone());
+    }
+    report
+}
+
+/// Purge now and then every hour.
+pub fn spawn_purger(app: AppHandle) {
+    thread::spawn(move || loop {
+        let state = app.state::<Arc<AppState>>();
+        purge(&app, &state);
+        thread::sleep(PURGE_INTERVAL);
+    });
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageArea {
+    /// "database" | "recordings" | "scrollback" | "crashReports" | "logs"
+    pub name: &'static str,
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub areas: Vec<StorageArea>,
+    pub total_bytes: u64,
+}
+
+/// (bytes, files) under `path`, recursively.
+fn usage(path: &Path) -> (u64, u64) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return (0, 0);
+    };
+    let mut total = (0, 0);
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let (bytes, files) = usage(&entry.path());
+            total.0 += bytes;
+            total.1 += files;
+        } else {
+            total.0 += meta.len();
+            total.1 += 1;
+        }
+    }
+    total
+}
+
+/// What OpsPad is holding on disk, by area.
+pub fn storage_usage(app: &AppHandle) -> Result<StorageUsage, String> {
+    let data = paths::app_data_dir(app).map_err(|e| format!("no app data directory: {e}"))?;
+    let mut dirs = vec![
+        ("recordings", recording::root(app)?),
+        ("scrollback", scrollback::root(app)?),
+        ("crashReports", data.join("crashes")),
+    ];
+    if let Ok(logs) = paths::app_log_dir(app) {
+        dirs.push(("logs", logs));
+    }
+
+    let mut areas = Vec::with_capacity(dirs.len() + 1);
+    // The database and its journal files, not the subdirectories beside it.
+    let (bytes, files) = fs::read_dir(&data)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with("opspad.db"))
+                .filter_map(|e| e.metadata().ok())
+                .fold((0, 0), |(bytes, files), m| (bytes + m.len(), files + 1))
+        })
+        .unwrap_or((0, 0));
+    areas.push(StorageArea {
+        name: "database",
+        path: data.join("opspad.db").to_string_lossy().into_owned(),
+        bytes,
+        files,
+    });
+    for (name, dir) in dirs {
+        let (bytes, files) = usage(&dir);
+        areas.push(StorageArea {
+            name,
+            path: dir.to_string_lossy().into_owned(),
+            bytes,
+            files,
+        });
+    }
+    Ok(StorageUsage {
+        total_bytes: areas.iter().map(|a| a.bytes).sum(),
+        areas,
+    })
+}
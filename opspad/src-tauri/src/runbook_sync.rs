@@ -0,0 +1,379 @@
+//! Two-way sync between runbooks and a folder of `.md` files (typically a Git checkout).
+//!
+//! Each synced runbook remembers its file name and a hash of the content at the last sync. On
+//! every pass, a side whose content no longer matches that hash has changed: one-sided changes are
+//! copied across, and changes on both sides are flagged as conflicts and left alone until the
+//! user picks a side. A background thread polls the folder so edits made outside OpsPad show up
+//! without a restart.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::{Db, Runbook, RunbookCreate};
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "runbooks.folder";
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Serializes sync passes (background poll vs. "sync now" vs. conflict resolution).
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSyncConfig {
+    pub enabled: bool,
+    pub path: Option<String>,
+}
+
+impl FolderSyncConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if self.enabled {
+            let dir = self.dir().ok_or_else(|| "choose a folder to sync runbooks with".to_string())?;
+            if !dir.is_dir() {
+                return Err(format!("not a folder: {}", dir.display()));
+            }
+        }
+        let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // Links to files in another folder mean nothing here; start over so the first pass
+        // pushes every runbook instead of treating their files as deleted.
+        if Self::load(db).dir() != self.dir() {
+            db.runbooks_clear_sync_state().map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn dir(&self) -> Option<PathBuf> {
+        self.path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Runbooks created from new files.
+    pub imported: Vec<String>,
+    /// Runbooks updated from changed files.
+    pub pulled: Vec<String>,
+    /// Files written from runbooks changed (or created) in the app.
+    pub pushed: Vec<String>,
+    /// Runbooks removed because their file was deleted.
+    pub removed: Vec<String>,
+    /// Runbooks changed on both sides; left untouched until resolved.
+    pub conflicts: Vec<String>,
+    /// A conflict was detected in this pass (not just carried over from an earlier one).
+    #[serde(skip)]
+    new_conflict: bool,
+}
+
+impl SyncReport {
+    /// Whether this pass changed anything the UI should refresh for.
+    pub fn changed(&self) -> bool {
+        !self.imported.is_empty()
+            || !self.pulled.is_empty()
+            || !self.pushed.is_empty()
+            || !self.removed.is_empty()
+            || self.new_conflict
+    }
+
+    fn conflict(&mut self, rb: &Runbook) {
+        self.conflicts.push(rb.title.clone());
+        self.new_conflict |= !rb.sync_conflict;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+    /// Keep the app's content and overwrite the file.
+    App,
+    /// Keep the file's content (or its deletion) and overwrite the app.
+    File,
+}
+
+fn hash(markdown: &str) -> String {
+    Sha256::digest(markdown.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Title for a runbook imported from `file_name`: its first `# ` heading, else the file stem.
+fn title_from(file_name: &str, markdown: &str) -> String {
+    markdown
+        .lines()
+        .find_map(|l| l.strip_prefix("# ").map(str::trim).filter(|t| !t.is_empty()))
+        .map(str::to_string)
+        .unwrap_or_else(|| file_name.trim_end_matches(".md").to_string())
+}
+
+/// A file name for a runbook that has none yet, unique within `taken`.
+fn file_name_for(title: &str, taken: &HashMap<String, String>) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() { "runbook".to_string() } else { slug };
+    let mut name = format!("{slug}.md");
+    let mut n = 2;
+    while taken.contains_key(&name) {
+        name = format!("{slug}-{n}.md");
+        n += 1;
+    }
+    name
+}
+
+/// `.md` files directly inside `dir`, keyed by file name.
+fn read_folder(dir: &Path) -> Result<HashMap<String, String>, String> {
+    let mut out = HashMap::new();
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+            continue;
+        };
+        if !path.is_file() || !name.to_ascii_lowercase().ends_with(".md") {
+            continue;
+        }
+        // Skip files that aren't valid UTF-8 rather than failing the whole pass.
+        if let Ok(markdown) = fs::read_to_string(&path) {
+            out.insert(name, markdown);
+        }
+    }
+    Ok(out)
+}
+
+fn write_file(dir: &Path, file_name: &str, markdown: &str) -> Result<(), String> {
+    let path = dir.join(file_name);
+    // Write-then-rename so editors and Git never see a half-written file.
+    let tmp = dir.join(format!(".{file_name}.opspad-tmp"));
+    fs::write(&tmp, markdown).map_err(|e| format!("{}: {e}", path.display()))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn sync_runbook(
+    db: &Db,
+    dir: &Path,
+    rb: &Runbook,
+    file_name: &str,
+    files: &HashMap<String, String>,
+    report: &mut SyncReport,
+) -> Result<(), String> {
+    let base = rb.synced_hash.as_deref();
+    let app_hash = hash(&rb.markdown);
+    let app_changed = base != Some(app_hash.as_str());
+    let Some(file_markdown) = files.get(file_name) else {
+        if app_changed {
+            report.conflict(rb);
+            return db
+                .runbooks_set_sync_state(&rb.id, Some(file_name), base, true)
+                .map_err(|e| e.to_string());
+        }
+        report.removed.push(rb.title.clone());
+        return db.runbooks_delete(&rb.id).map_err(|e| e.to_string());
+    };
+    let file_hash = hash(file_markdown);
+    let file_changed = base != Some(file_hash.as_str());
+
+    if app_hash == file_hash {
+        if app_changed || rb.sync_conflict {
+            db.runbooks_set_sync_state(&rb.id, Some(file_name), Some(&app_hash), false)
+                .map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+    match (app_changed, file_changed) {
+        (true, true) => {
+            if !rb.sync_conflict {
+                db.runbooks_set_sync_state(&rb.id, Some(file_name), base, true)
+                    .map_err(|e| e.to_string())?;
+            }
+            report.conflict(rb);
+        }
+        (true, false) => {
+            write_file(dir, file_name, &rb.markdown)?;
+            db.runbooks_set_sync_state(&rb.id, Some(file_name), Some(&app_hash), false)
+                .map_err(|e| e.to_string())?;
+            report.pushed.push(rb.title.clone());
+        }
+        (false, _) => {
+            db.runbooks_update(Runbook {
+                markdown: file_markdown.clone(),
+                ..rb.clone()
+            })
+            .map_err(|e| e.to_string())?;
+            db.runbooks_set_sync_state(&rb.id, Some(file_name), Some(&file_hash), false)
+                .map_err(|e| e.to_string())?;
+            report.pulled.push(rb.title.clone());
+        }
+    }
+    Ok(())
+}
+
+/// One sync pass over `dir`.
+fn sync_dir(db: &Db, dir: &Path) -> Result<SyncReport, String> {
+    let mut files = read_folder(dir)?;
+    // Subscribed runbooks are read-only copies; they don't belong in the folder.
+    let runbooks: Vec<Runbook> = db
+        .runbooks_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|rb| rb.subscription_id.is_none())
+        .collect();
+    let mut report = SyncReport::default();
+    let mut linked: HashSet<String> = runbooks.iter().filter_map(|rb| rb.file_name.clone()).collect();
+    if files.is_empty() && !linked.is_empty() {
+        // More likely an unmounted drive or a half-done checkout than a deliberate wipe.
+        return Err(format!(
+            "{} has no .md files; not removing {} synced runbooks",
+            dir.display(),
+            linked.len()
+        ));
+    }
+
+    for rb in &runbooks {
+        match rb.file_name.as_deref() {
+            Some(file_name) => sync_runbook(db, dir, rb, file_name, &files, &mut report)?,
+            None => {
+                let file_name = file_name_for(&rb.title, &files);
+                write_file(dir, &file_name, &rb.markdown)?;
+                db.runbooks_set_sync_state(&rb.id, Some(&file_name), Some(&hash(&rb.markdown)), false)
+                    .map_err(|e| e.to_string())?;
+                report.pushed.push(rb.title.clone());
+                linked.insert(file_name.clone());
+                files.insert(file_name, rb.markdown.clone());
+            }
+        }
+    }
+
+    let mut new_files: Vec<(&String, &String)> = files.iter().filter(|(name, _)| !linked.contains(*name)).collect();
+    new_files.sort();
+    for (file_name, markdown) in new_files {
+        let created = db
+            .runbooks_create(RunbookCreate {
+                title: title_from(file_name, markdown),
+                markdown: Some(markdown.clone()),
+                tags: None,
+            })
+            .map_err(|e| e.to_string())?;
+        db.runbooks_set_sync_state(&created.id, Some(file_name), Some(&hash(markdown)), false)
+            .map_err(|e| e.to_string())?;
+        report.imported.push(created.title);
+    }
+    Ok(report)
+}
+
+/// Run a sync pass if folder sync is enabled (an empty report otherwise).
+pub fn sync_now(db: &Db) -> Result<SyncReport, String> {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let config = FolderSyncConfig::load(db);
+    match config.dir().filter(|_| config.enabled) {
+        Some(dir) => sync_dir(db, &dir),
+        None => Ok(SyncReport::default()),
+    }
+}
+
+/// Settle a conflicted runbook by keeping one side.
+pub fn resolve(db: &Db, id: &str, keep: ConflictSide) -> Result<(), String> {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let config = FolderSyncConfig::load(db);
+    let dir = config
+        .dir()
+        .filter(|_| config.enabled)
+        .ok_or_else(|| "runbook folder sync is not enabled".to_string())?;
+    let rb = db
+        .runbooks_get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "runbook not found".to_string())?;
+    let file_name = rb
+        .file_name
+        .clone()
+        .ok_or_else(|| "runbook is not linked to a file".to_string())?;
+    let path = dir.join(&file_name);
+
+    match keep {
+        ConflictSide::App => {
+            write_file(&dir, &file_name, &rb.markdown)?;
+            db.runbooks_set_sync_state(id, Some(&file_name), Some(&hash(&rb.markdown)), false)
+                .map_err(|e| e.to_string())
+        }
+        ConflictSide::File => match fs::read_to_string(&path) {
+            Ok(markdown) => {
+                let file_hash = hash(&markdown);
+                db.runbooks_update(Runbook { markdown, ..rb })
+                    .map_err(|e| e.to_string())?;
+                db.runbooks_set_sync_state(id, Some(&file_name), Some(&file_hash), false)
+                    .map_err(|e| e.to_string())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => db.runbooks_delete(id).map_err(|e| e.to_string()),
+            Err(e) => Err(format!("{}: {e}", path.display())),
+        },
+    }
+}
+
+/// Delete the file behind a runbook that is being deleted in the app, unless it changed on disk
+/// since the last sync (then the next pass re-imports it rather than losing those edits).
+pub fn delete_file_for(db: &Db, rb: &Runbook) {
+    let _guard = SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let config = FolderSyncConfig::load(db);
+    let (Some(dir), Some(file_name)) = (config.dir().filter(|_| config.enabled), rb.file_name.as_deref()) else {
+        return;
+    };
+    let path = dir.join(file_name);
+    if let Ok(markdown) = fs::read_to_string(&path) {
+        if rb.synced_hash.as_deref() == Some(hash(&markdown).as_str()) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+/// Poll the sync folder in the background, emitting `runbooks:synced` when a pass changed anything.
+pub fn spawn_poller(app: AppHandle) {
+    thread::spawn(move || {
+        // Polls every few seconds; only log an error when it first appears or changes.
+        let mut last_error: Option<String> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let state = app.state::<Arc<AppState>>();
+            match sync_now(&state.db) {
+                Ok(report) => {
+                    if report.changed() {
+                        let _ = app.emit("runbooks:synced", &report);
+                    }
+                    last_error = None;
+                }
+                Err(e) => {
+                    if last_error.as_deref() != Some(e.as_str()) {
+                        log::warn!("runbook folder sync failed: {e}");
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+    });
+}
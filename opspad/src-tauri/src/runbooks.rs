@@ -0,0 +1,290 @@
+//! Runbook helpers that sit on top of the `runbooks` tables: version diffs, rendering, and export.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::cli::CliAction;
+use crate::db::Runbook;
+use crate::arch::pdf;
+use crate::deep_link;
+use crate::exec;
+use crate::template;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 1-based line number in the old text (none for inserted lines).
+    pub old_line: Option<usize>,
+    /// 1-based line number in the new text (none for deleted lines).
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunbookDiff {
+    pub title_changed: bool,
+    pub inserted: usize,
+    pub deleted: usize,
+    pub lines: Vec<DiffLine>,
+    /// The same diff in unified format, for copying.
+    pub unified: String,
+}
+
+/// Line diff between two (title, markdown) states of a runbook.
+pub fn diff(old: (&str, &str), new: (&str, &str)) -> RunbookDiff {
+    let text_diff = TextDiff::from_lines(old.1, new.1);
+    let mut lines = Vec::new();
+    let (mut inserted, mut deleted) = (0, 0);
+    for change in text_diff.iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => DiffLineKind::Equal,
+            ChangeTag::Insert => {
+                inserted += 1;
+                DiffLineKind::Insert
+            }
+            ChangeTag::Delete => {
+                deleted += 1;
+                DiffLineKind::Delete
+            }
+        };
+        lines.push(DiffLine {
+            kind,
+            old_line: change.old_index().map(|i| i + 1),
+            new_line: change.new_index().map(|i| i + 1),
+            text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+        });
+    }
+    let unified = text_diff
+        .unified_diff()
+        .context_radius(3)
+        .header(old.0, new.0)
+        .to_string();
+    RunbookDiff {
+        title_changed: old.0 != new.0,
+        inserted,
+        deleted,
+        lines,
+        unified,
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedRunbook {
+    pub markdown: String,
+    /// Every placeholder in the runbook, in order of first appearance.
+    pub placeholders: Vec<String>,
+    /// Placeholders left as `{name}` because no value (or default) was given.
+    pub missing: Vec<String>,
+    /// The `opspad://` links in the rendered markdown, in order.
+    pub links: Vec<RunbookLink>,
+}
+
+/// An `opspad://` link in a runbook, resolved to what opening it (`runbook_link_open`) does.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunbookLink {
+    pub url: String,
+    /// The link text, e.g. "restart the ingester".
+    pub text: String,
+    /// The host, command or runbook it points at; `None` when it doesn't resolve.
+    pub target: Option<CliAction>,
+    pub error: Option<String>,
+}
+
+/// `(url, text)` of each `opspad://` link (inline or `<autolink>`), in order.
+fn opspad_links(markdown: &str) -> Vec<(String, String)> {
+    let prefix = format!("{}://", deep_link::SCHEME);
+    let mut links = Vec::new();
+    let mut open: Option<(String, String)> = None;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) if dest_url.starts_with(&prefix) => {
+                open = Some((dest_url.to_string(), String::new()));
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, text)) = &mut open {
+                    text.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Link) => links.extend(open.take()),
+            _ => {}
+        }
+    }
+    links
+}
+
+/// Substitute `values` into a runbook with the same rules as dock commands, except that
+/// placeholders without a value stay in place so a partly filled-in runbook is still readable.
+/// `links` is left empty; `render_with_links` fills it.
+pub fn render(rb: &Runbook, values: &HashMap<String, String>) -> Result<RenderedRunbook, String> {
+    let (markdown, missing) = template::render_partial(&rb.markdown, &rb.params, values)?;
+    Ok(RenderedRunbook {
+        markdown,
+        placeholders: template::placeholders(&rb.markdown),
+        missing,
+        links: Vec::new(),
+    })
+}
+
+/// `render`, plus the rendered text's `opspad://` links checked with `resolve`.
+pub fn render_with_links(
+    rb: &Runbook,
+    values: &HashMap<String, String>,
+    resolve: impl Fn(&str) -> Result<CliAction, String>,
+) -> Result<RenderedRunbook, String> {
+    let mut rendered = render(rb, values)?;
+    rendered.links = opspad_links(&rendered.markdown)
+        .into_iter()
+        .map(|(url, text)| {
+            let (target, error) = match resolve(&url) {
+                Ok(action) => (Some(action), None),
+                Err(e) => (None, Some(e)),
+            };
+            RunbookLink { url, text, target, error }
+        })
+        .collect();
+    Ok(rendered)
+}
+
+/// The text of each checkbox item (`- [ ] step`) in the markdown, in order. Checkboxes inside
+/// code blocks don't count, and whether an item is ticked in the markdown doesn't matter: a
+/// checklist run starts with everything unchecked.
+pub fn checklist_items(markdown: &str) -> Vec<String> {
+    Parser::new_ext(markdown, Options::ENABLE_TASKLISTS)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::TaskListMarker(_) => {
+                let rest = &markdown[range.end..];
+                let text = rest.lines().next().unwrap_or("").trim();
+                Some(if text.is_empty() { "(untitled step)".to_string() } else { text.to_string() })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+}
+
+/// How long the headless renderer gets to print a PDF.
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+
+const EXPORT_CSS: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,\"Segoe UI\",Roboto,Helvetica,Arial,sans-serif;\
+max-width:820px;margin:2rem auto;padding:0 1.5rem;color:#1f2328;line-height:1.55;font-size:15px}\
+h1,h2,h3{line-height:1.25;margin-top:1.6em}h1{border-bottom:1px solid #d0d7de;padding-bottom:.3em}\
+code{font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;font-size:.9em;\
+background:#f6f8fa;padding:.15em .35em;border-radius:4px}\
+pre{background:#f6f8fa;padding:.9em 1em;border-radius:6px;overflow-x:auto}pre code{background:none;padding:0}\
+table{border-collapse:collapse}th,td{border:1px solid #d0d7de;padding:.35em .7em}\
+blockquote{margin:0;padding:0 1em;color:#59636e;border-left:.25em solid #d0d7de}\
+footer{margin-top:3rem;color:#59636e;font-size:.8em;border-top:1px solid #d0d7de;padding-top:.6em}\
+@media print{body{margin:0;max-width:none}pre{white-space:pre-wrap}}";
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A self-contained HTML document (inline styles, no scripts or external assets).
+///
+/// Raw HTML in the markdown is shown as text rather than passed through (and `javascript:` links
+/// are dropped), so an exported runbook can't carry scripts to whoever opens it.
+pub fn export_html(rb: &Runbook) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(&rb.markdown, options).map(|e| match e {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link { link_type, dest_url, title, id })
+            if dest_url.trim_start().to_ascii_lowercase().starts_with("javascript:") =>
+        {
+            Event::Start(Tag::Link { link_type, dest_url: CowStr::Borrowed("#"), title, id })
+        }
+        other => other,
+    });
+    let mut body = String::with_capacity(rb.markdown.len() * 2);
+    html::push_html(&mut body, events);
+
+    let title = escape_html(&rb.title);
+    let updated = if rb.updated_at > 0 {
+        format!(" &middot; last updated {}", format_epoch(rb.updated_at / 1000))
+    } else {
+        String::new()
+    };
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <meta name=\"generator\" content=\"OpsPad\">\n<title>{title}</title>\n<style>{EXPORT_CSS}</style>\n\
+         </head>\n<body>\n<h1>{title}</h1>\n{body}<footer>Exported from OpsPad{updated}</footer>\n</body>\n</html>\n"
+    )
+}
+
+/// `YYYY-MM-DD HH:MM UTC` for an epoch-seconds timestamp.
+fn format_epoch(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    // Civil-from-days (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02} UTC", rem / 3600, rem % 3600 / 60)
+}
+
+/// Write the runbook to `path` as standalone HTML, or as PDF via a headless browser.
+pub fn export(rb: &Runbook, format: ExportFormat, path: &Path) -> Result<(), String> {
+    let html = export_html(rb);
+    match format {
+        ExportFormat::Html => std::fs::write(path, html).map_err(|e| e.to_string()),
+        ExportFormat::Pdf => {
+            let tmp = std::env::temp_dir().join(format!("opspad-runbook-{}.html", uuid::Uuid::new_v4()));
+            std::fs::write(&tmp, html).map_err(|e| e.to_string())?;
+            let result = print_pdf(&tmp, path);
+            let _ = std::fs::remove_file(&tmp);
+            result
+        }
+    }
+}
+
+fn print_pdf(input: &Path, output: &Path) -> Result<(), String> {
+    let cmd = pdf::html_to_pdf_command(input, output).ok_or_else(|| {
+        "PDF export needs Chrome, Chromium, Edge, or wkhtmltopdf installed; export as HTML instead".to_string()
+    })?;
+    // Don't report a stale file from an earlier export as success.
+    let _ = std::fs::remove_file(output);
+    let out = exec::run(&cmd.program, &cmd.args, PDF_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.timed_out {
+        return Err("PDF renderer timed out".to_string());
+    }
+    if !output.exists() {
+        let detail = out.stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output");
+        return Err(format!("PDF renderer failed: {detail}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Opt-in resource sampling: every few minutes, run `uptime`, `df -hP` and `free -m` on chosen
+//! hosts over ssh and keep the parsed numbers, for a small per-host dashboard without installing
+//! an agent.
+//!
+//! Calls share one multiplexed ssh connection per host (see [`ssh::multiplex_args`]) so a sample
+//! doesn't cost a full handshake. Samples are stored in `host_metrics` and emitted as
+//! `host:metrics`.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::ssh;
+use crate::db::{Db, Host};
+use crate::{alerts, exec, AppState};
+
+const SETTINGS_KEY: &str = "sampler";
+const TICK: Duration = Duration::from_secs(15);
+const MIN_INTERVAL_SECS: u64 = 60;
+const MAX_INTERVAL_SECS: u64 = 3600;
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(20);
+const PARALLEL_SAMPLES: usize = 8;
+
+const SPLIT: &str = "__OPSPAD_SPLIT__";
+/// POSIX sh; `free` is missing on macOS/BSD, which leaves memory empty rather than failing.
+const BUNDLE: &str = "LC_ALL=C uptime; echo __OPSPAD_SPLIT__; LC_ALL=C df -hP; echo __OPSPAD_SPLIT__; LC_ALL=C free -m 2>/dev/null";
+
+/// Pseudo filesystems that would only clutter the disk list.
+const SKIP_FILESYSTEMS: &[&str] = &["tmpfs", "devtmpfs", "udev", "overlay", "shm", "none", "devfs", "map"];
+
+fn default_interval() -> u64 {
+    300
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    /// Host ids to sample; nothing is sampled unless listed here.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval(),
+            hosts: Vec::new(),
+        }
+    }
+}
+
+impl SamplerConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS))
+    }
+}
+
+/// One row of `df -hP`; sizes are as printed (e.g. "40G").
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsage {
+    pub filesystem: String,
+    pub mount: String,
+    pub size: String,
+    pub used: String,
+    pub avail: String,
+    pub use_percent: u8,
+}
+
+/// Parsed sample; also the `host:metrics` payload. Fields a host didn't report are `None`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMetrics {
+    pub host_id: String,
+    /// Epoch seconds.
+    pub sampled_at: i64,
+    /// `uptime` as printed, e.g. "up 12 days, 3:04".
+    pub uptime: Option<String>,
+    pub load1: Option<f64>,
+    pub load5: Option<f64>,
+    pub load15: Option<f64>,
+    pub mem_total_mb: Option<u64>,
+    pub mem_used_mb: Option<u64>,
+    pub mem_available_mb: Option<u64>,
+    pub swap_total_mb: Option<u64>,
+    pub swap_used_mb: Option<u64>,
+    #[serde(default)]
+    pub disks: Vec<DiskUsage>,
+    /// Set when the sample failed (ssh error, timeout); the other fields are then empty.
+    pub error: Option<String>,
+}
+
+fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// `... up 12 days,  3:04,  2 users,  load average: 0.15, 0.10, 0.05` (Linux) or
+/// `... load averages: 1.52 1.61 1.70` (macOS).
+fn parse_uptime(out: &str, metrics: &mut HostMetrics) {
+    let line = out.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+    if let Some(start) = line.find("up ") {
+        let rest = &line[start..];
+        let end = rest.find(" user").and_then(|i| rest[..i].rfind(',')).or_else(|| rest.find(", load"));
+        let text = end.map_or(rest, |i| &rest[..i]);
+        metrics.uptime = Some(text.trim().trim_end_matches(',').to_string());
+    }
+    if let Some(i) = line.find("load average") {
+        let values = line[i..].split_once(':').map_or("", |(_, v)| v);
+        let loads: Vec<f64> = values
+            .split([',', ' '])
+            .filter_map(|v| v.trim().parse().ok())
+            .collect();
+        metrics.load1 = loads.first().copied();
+        metrics.load5 = loads.get(1).copied();
+        metrics.load15 = loads.get(2).copied();
+    }
+}
+
+fn parse_df(out: &str, metrics: &mut HostMetrics) {
+    for line in out.lines().skip(1) {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 6 {
+            continue;
+        }
+        let filesystem = cols[0];
+        if SKIP_FILESYSTEMS.contains(&filesystem) || filesystem.starts_with("/dev/loop") {
+            continue;
+        }
+        let Ok(use_percent) = cols[4].trim_end_matches('%').parse::<u8>() else {
+            continue;
+        };
+        metrics.disks.push(DiskUsage {
+            filesystem: filesystem.to_string(),
+            // Mount points may contain spaces.
+            mount: cols[5..].join(" "),
+            size: cols[1].to_string(),
+            used: cols[2].to_string(),
+            avail: cols[3].to_string(),
+            use_percent,
+        });
+    }
+}
+
+/// `free -m`: `Mem: total used free shared buff/cache available` and `Swap: total used free`.
+fn parse_free(out: &str, metrics: &mut HostMetrics) {
+    for line in out.lines() {
+        let mut cols = line.split_whitespace();
+        let label = cols.next().unwrap_or("");
+        let nums: Vec<u64> = cols.filter_map(|c| c.parse().ok()).collect();
+        match label {
+            "Mem:" => {
+                metrics.mem_total_mb = nums.first().copied();
+                metrics.mem_used_mb = nums.get(1).copied();
+                metrics.mem_available_mb = nums.get(5).copied();
+            }
+            "Swap:" => {
+                metrics.swap_total_mb = nums.first().copied();
+                metrics.swap_used_mb = nums.get(1).copied();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse(host_id: &str, out: &str) -> HostMetrics {
+    let mut metrics = HostMetrics {
+        host_id: host_id.to_string(),
+        sampled_at: now_epoch_secs(),
+        ..Default::default()
+    };
+    let mut parts = out.split(SPLIT);
+    parse_uptime(parts.next().unwrap_or(""), &mut metrics);
+    parse_df(parts.next().unwrap_or(""), &mut metrics);
+    parse_free(parts.next().unwrap_or(""), &mut metrics);
+    metrics
+}
+
+fn sample(host: &Host) -> HostMetrics {
+    let failed = |error: String| HostMetrics {
+        host_id: host.id.clone(),
+        sampled_at: now_epoch_secs(),
+        error: Some(error),
+        ..Default::default()
+    };
+    let program = match ssh::ssh_program_checked() {
+        Ok(p) => p,
+        Err(e) => return failed(e),
+    };
+    let mut args = ssh::multiplex_args();
+    args.extend(ssh::exec_args(
+        &host.username,
+        &host.hostname,
+        Some(host.port),
+        host.identity_file.as_deref(),
+        BUNDLE,
+    ));
+    match exec::run(&program, &args, SAMPLE_TIMEOUT) {
+        Ok(out) if out.timed_out => failed("timed out".to_string()),
+        Ok(out) if !out.stdout.contains(SPLIT) => {
+            let err = out.stderr.lines().last().unwrap_or("no output").trim().to_string();
+            failed(err)
+        }
+        Ok(out) => parse(&host.id, &out.stdout),
+        Err(e) => failed(e.to_string()),
+    }
+}
+
+fn store(app: &AppHandle, state: &AppState, metrics: &HostMetrics) {
+    match serde_json::to_string(metrics) {
+        Ok(json) => {
+            if let Err(e) = state.db.host_metrics_add(&metrics.host_id, metrics.sampled_at, &json) {
+                log::warn!("host metrics not stored: {e}");
+            }
+        }
+        Err(e) => log::warn!("host metrics not serialized: {e}"),
+    }
+    let _ = app.emit("host:metrics", metrics.clone());
+    alerts::on_metrics(app, state, metrics);
+}
+
+pub fn latest(db: &Db, host_id: &str) -> Result<Option<HostMetrics>, String> {
+    let json = db.host_metrics_latest(host_id).map_err(|e| e.to_string())?;
+    Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+}
+
+/// Sample one host now (whether or not it's in the sampled list).
+pub fn sample_now(app: &AppHandle, state: &AppState, host_id: &str) -> Result<HostMetrics, String> {
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    let metrics = sample(&host);
+    store(app, state, &metrics);
+    Ok(metrics)
+}
+
+fn run_round(app: &AppHandle, state: &AppState, config: &SamplerConfig) {
+    let hosts: Vec<Host> = config
+        .hosts
+        .iter()
+        .filter_map(|id| state.db.hosts_get(id).ok().flatten())
+        .collect();
+    for chunk in hosts.chunks(PARALLEL_SAMPLES) {
+        let samples: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = chunk.iter().map(|host| scope.spawn(move || sample(host))).collect();
+            handles.into_iter().filter_map(|h| h.join().ok()).collect()
+        });
+        for metrics in &samples {
+            store(app, state, metrics);
+        }
+    }
+}
+
+pub fn spawn_scheduler(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_round: Option<Instant> = None;
+        loop {
+            thread::sleep(TICK);
+            let state = app.state::<Arc<AppState>>();
+            let config = SamplerConfig::load(&state.db);
+            if !config.enabled || config.hosts.is_empty() {
+                last_round = None;
+                continue;
+            }
+            if last_round.is_some_and(|t| t.elapsed() < config.interval()) {
+                continue;
+            }
+            last_round = Some(Instant::now());
+            run_round(&app, &state, &config);
+        }
+    });
+}
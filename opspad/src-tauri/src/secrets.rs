@@ -0,0 +1,172 @@
+//! App-level view of the vault: which secrets exist, whether anything still uses them, which are
+//! due for rotation, and handing values to the user behind a presence check where a secret asks
+//! for one.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::user_presence::{self, Presence};
+use crate::arch::vault::{SecretMetadata, VaultProvider, ROTATION_WINDOW_SECS};
+use crate::db::Db;
+use crate::exec;
+use crate::AppState;
+
+/// How long the platform prompt may sit unanswered before we count it as denied.
+const PRESENCE_TIMEOUT: Duration = Duration::from_secs(120);
+/// First rotation check shortly after launch, then once a day.
+const ROTATION_FIRST_CHECK: Duration = Duration::from_secs(60);
+const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    #[serde(flatten)]
+    pub metadata: SecretMetadata,
+    /// Belongs to a host that no longer exists.
+    pub orphaned: bool,
+}
+
+/// A secret whose `rotate_by` date is near or past.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationDue {
+    #[serde(flatten)]
+    pub metadata: SecretMetadata,
+    pub overdue: bool,
+    /// Whole days until `rotate_by`; negative once overdue.
+    pub days_left: i64,
+}
+
+/// Host id for per-host keys (`host:<id>:<name>`).
+fn owner_host(key: &str) -> Option<&str> {
+    key.strip_prefix("host:")?.split_once(':').map(|(id, _)| id)
+}
+
+fn is_orphaned(db: &Db, key: &str) -> Result<bool, String> {
+    match owner_host(key) {
+        Some(id) => Ok(db.hosts_get(id).map_err(|e| e.to_string())?.is_none()),
+        None => Ok(false),
+    }
+}
+
+pub fn list(db: &Db, vault: &dyn VaultProvider) -> Result<Vec<SecretInfo>, String> {
+    let keys = vault.list_keys().map_err(|e| e.to_string())?;
+    keys.into_iter()
+        .map(|metadata| {
+            Ok(SecretInfo {
+                orphaned: is_orphaned(db, &metadata.key)?,
+                metadata,
+            })
+        })
+        .collect()
+}
+
+/// Delete every orphaned secret. Returns the deleted keys.
+pub fn prune_orphans(db: &Db, vault: &dyn VaultProvider) -> Result<Vec<String>, String> {
+    let mut deleted = Vec::new();
+    for info in list(db, vault)? {
+        if info.orphaned {
+            vault.delete_secret(&info.metadata.key).map_err(|e| e.to_string())?;
+            deleted.push(info.metadata.key);
+        }
+    }
+    Ok(deleted)
+}
+
+fn requires_presence(vault: &dyn VaultProvider, key: &str) -> bool {
+    vault
+        .metadata(key)
+        .ok()
+        .flatten()
+        .is_some_and(|m| m.require_presence)
+}
+
+fn describe(vault: &dyn VaultProvider, key: &str) -> String {
+    let label = vault.metadata(key).ok().flatten().and_then(|m| m.label);
+    label.unwrap_or_else(|| key.to_string())
+}
+
+/// Show the platform verification prompt (Windows Hello, Touch ID, polkit) and wait for it.
+pub(crate) fn verify_presence(reason: &str) -> Result<(), String> {
+    let check = user_presence::presence_check(reason)
+        .ok_or_else(|| "user verification isn't available on this system".to_string())?;
+    let out = exec::run(&check.command.program, &check.command.args, PRESENCE_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.timed_out {
+        return Err("user verification timed out".to_string());
+    }
+    match check.result(out.exit_code) {
+        Presence::Verified => Ok(()),
+        Presence::Denied => Err("user verification was cancelled or failed".to_string()),
+        Presence::Unavailable => Err("no biometric or credential prompt is set up on this system".to_string()),
+    }
+}
+
+/// Read a secret on the user's behalf, verifying their presence first if the secret asks for it.
+pub fn get_verified(vault: &dyn VaultProvider, key: &str) -> Result<Option<Vec<u8>>, String> {
+    // Read first so a locked vault or missing key fails without prompting.
+    let secret = vault.get_secret(key).map_err(|e| e.to_string())?;
+    if secret.is_some() && requires_presence(vault, key) {
+        verify_presence(&format!("OpsPad wants to use the secret \"{}\"", describe(vault, key)))?;
+    }
+    Ok(secret)
+}
+
+/// Turn the presence check on or off for a secret. Turning it off needs the check itself,
+/// otherwise it would be a one-call bypass.
+pub fn set_require_presence(vault: &dyn VaultProvider, key: &str, required: bool) -> Result<(), String> {
+    if required {
+        // Fail now rather than lock the user out of the secret later.
+        if user_presence::presence_check("").is_none() {
+            return Err("user verification isn't available on this system".to_string());
+        }
+    } else if requires_presence(vault, key) {
+        verify_presence(&format!("OpsPad wants to stop protecting \"{}\"", describe(vault, key)))?;
+    }
+    vault.set_require_presence(key, required).map_err(|e| e.to_string())
+}
+
+/// Secrets due for rotation within the reminder window, most overdue first.
+pub fn rotation_due(vault: &dyn VaultProvider) -> Result<Vec<RotationDue>, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let mut due: Vec<RotationDue> = vault
+        .list_keys()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|metadata| {
+            let rotate_by = metadata.rotate_by?;
+            (rotate_by - now <= ROTATION_WINDOW_SECS).then(|| RotationDue {
+                overdue: rotate_by <= now,
+                days_left: (rotate_by - now).div_euclid(24 * 60 * 60),
+                metadata,
+            })
+        })
+        .collect();
+    due.sort_by_key(|d| d.metadata.rotate_by);
+    Ok(due)
+}
+
+/// Remind about secrets due for rotation; emits `vault:rotation-due` when there are any.
+pub fn spawn_rotation_reminder(app: AppHandle) {
+    thread::spawn(move || {
+        thread::sleep(ROTATION_FIRST_CHECK);
+        loop {
+            let state = app.state::<Arc<AppState>>();
+            match rotation_due(state.vault.as_ref()) {
+                Ok(due) if !due.is_empty() => {
+                    log::info!("{} secret(s) due for rotation", due.len());
+                    let _ = app.emit("vault:rotation-due", due);
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("rotation check failed: {e}"),
+            }
+            thread::sleep(ROTATION_CHECK_INTERVAL);
+        }
+    });
+}
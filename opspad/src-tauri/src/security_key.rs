@@ -0,0 +1,232 @@
+//! Hardware security keys (FIDO2) for ssh: `sk-ssh-ed25519` / `sk-ecdsa-sha2-nistp256` identities.
+//!
+//! ssh does the FIDO work itself; OpsPad checks that it can ([`support`]), passes a host's
+//! `IdentityAgent` and `SecurityKeyProvider` options (`hosts_set_security_key`), and surfaces
+//! "Confirm user presence for key ..." as an `auth:prompt` of kind `touch`, followed by
+//! `auth:prompt_closed` once the key was touched. With the askpass bridge ssh sends that notice
+//! through the bridge (`askpass`); otherwise it prints it in the terminal, where
+//! [`spawn_touch_watch`] looks for it. A PIN prompt is an ordinary login prompt either way.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::arch::ssh;
+use crate::askpass::{AuthPrompt, AuthPromptClosedEvent, AuthPromptKind};
+use crate::db::Host;
+use crate::AppState;
+
+/// ssh asks for the touch while authenticating; stop watching the session after that.
+const WATCH_FOR: Duration = Duration::from_secs(120);
+const TAIL_BYTES: usize = 512;
+pub(crate) const TOUCH_MARKER: &str = "Confirm user presence for key";
+const SK_KEY_TYPE: &str = "sk-ssh-ed25519@openssh.com";
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityKeySupport {
+    /// `ssh -V`, e.g. "OpenSSH_9.6p1, OpenSSL 3.0.13 30 Jan 2024".
+    pub ssh_version: Option<String>,
+    /// ssh knows the `sk-` key types (OpenSSH 8.2+).
+    pub key_types: bool,
+    /// The FIDO middleware ssh uses when no `SecurityKeyProvider` is set (libfido2, or Windows
+    /// Hello on Windows) is available.
+    pub builtin_provider: bool,
+    /// `ssh-sk-helper`, which OpenSSH runs to talk to the key, where one was found.
+    pub sk_helper: Option<String>,
+    /// What's missing, in words.
+    pub notes: Vec<String>,
+}
+
+/// "OpenSSH_9.6p1 ..." or "OpenSSH_for_Windows_9.5p1 ..." as (9, 6).
+fn openssh_version(version: &str) -> Option<(u32, u32)> {
+    let rest = version.split_once("OpenSSH_")?.1;
+    let rest = rest.strip_prefix("for_Windows_").unwrap_or(rest);
+    let (major, rest) = rest.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+fn find_in(dirs: &[&str], matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    dirs.iter().find_map(|dir| {
+        std::fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.file_name().is_some_and(|n| matches(&n.to_string_lossy())))
+    })
+}
+
+#[cfg(not(windows))]
+fn libfido2() -> Option<PathBuf> {
+    const LIB_DIRS: [&str; 8] = [
+        "/usr/lib",
+        "/usr/lib64",
+        "/usr/lib/x86_64-linux-gnu",
+        "/usr/lib/aarch64-linux-gnu",
+        "/usr/local/lib",
+        "/opt/homebrew/lib",
+        "/lib/x86_64-linux-gnu",
+        "/lib64",
+    ];
+    find_in(&LIB_DIRS, |n| n.starts_with("libfido2.so") || n.starts_with("libfido2.") && n.ends_with(".dylib"))
+}
+
+fn sk_helper() -> Option<PathBuf> {
+    const HELPER_DIRS: [&str; 5] = [
+        "/usr/lib/openssh",
+        "/usr/libexec/openssh",
+        "/usr/libexec",
+        "/usr/local/libexec",
+        "/opt/homebrew/libexec",
+    ];
+    find_in(&HELPER_DIRS, |n| n == "ssh-sk-helper")
+}
+
+/// Whether this machine's ssh can use security keys, and what it lacks if not.
+pub fn support() -> SecurityKeySupport {
+    let timeout = Duration::from_secs(10);
+    let program = ssh::ssh_program_checked();
+    let run = |args: &[&str]| {
+        let program = program.as_ref().ok()?;
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        crate::exec::run(program, &args, timeout).ok()
+    };
+    let ssh_version = run(&["-V"]).map(|out| format!("{}{}", out.stderr, out.stdout).trim().to_string());
+    let key_types = run(&["-Q", "key"]).is_some_and(|out| out.stdout.lines().any(|l| l.trim() == SK_KEY_TYPE));
+    let version = ssh_version.as_deref().and_then(openssh_version);
+    let sk_helper = sk_helper();
+    let mut notes = Vec::new();
+
+    if program.is_err() {
+        notes.push("ssh isn't installed".to_string());
+    } else if !key_types {
+        notes.push("this ssh doesn't know security key types; OpenSSH 8.2 or later is needed".to_string());
+    }
+
+    #[cfg(windows)]
+    let builtin_provider = {
+        // Windows' OpenSSH talks to keys through Windows Hello from 8.9 on.
+        let ok = version.is_some_and(|v| v >= (8, 9));
+        if key_types && !ok {
+            notes.push("update Windows' OpenSSH client to 8.9 or later for Windows Hello support".to_string());
+        }
+        ok
+    };
+    #[cfg(not(windows))]
+    let builtin_provider = {
+        let _ = version;
+        let apple_ssh = program.as_deref().is_ok_and(|p| cfg!(target_os = "macos") && p == "/usr/bin/ssh");
+        let ok = !apple_ssh && libfido2().is_some();
+        if key_types && apple_ssh {
+            notes.push(
+                "macOS's own ssh has no FIDO support built in: set a SecurityKeyProvider for the host or install OpenSSH from Homebrew"
+                    .to_string(),
+            );
+        } else if key_types && !ok {
+            notes.push("libfido2 wasn't found; install it (e.g. the libfido2 package) or set a SecurityKeyProvider".to_string());
+        }
+        ok
+    };
+
+    SecurityKeySupport {
+        ssh_version,
+        key_types,
+        builtin_provider,
+        sk_helper: sk_helper.map(|p| p.to_string_lossy().to_string()),
+        notes,
+    }
+}
+
+/// Whether an identity file is a security key handle, judging by its public half.
+pub fn is_security_key_identity(identity_file: &str) -> bool {
+    let public = format!("{}.pub", identity_file.trim());
+    std::fs::read_to_string(Path::new(&public))
+        .ok()
+        .and_then(|text| text.split_whitespace().next().map(|t| t.starts_with("sk-")))
+        .unwrap_or(false)
+}
+
+/// ssh options for the host's security key settings.
+pub fn ssh_options(host: &Host) -> Vec<String> {
+    let clean = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+    let mut args = Vec::new();
+    if let Some(agent) = clean(&host.identity_agent) {
+        args.push("-o".to_string());
+        args.push(format!("IdentityAgent={agent}"));
+    }
+    if let Some(provider) = clean(&host.security_key_provider) {
+        args.push("-o".to_string());
+        args.push(format!("SecurityKeyProvider={provider}"));
+    }
+    args
+}
+
+/// Watch a session's output for ssh's touch request (sessions without the askpass bridge).
+pub fn spawn_touch_watch(app: &AppHandle, state: &Arc<AppState>, session_id: &str) -> Result<(), String> {
+    let output = state.terminal.subscribe_output(session_id).map_err(|e| e.to_string())?;
+    let (app, session_id) = (app.clone(), session_id.to_string());
+    thread::spawn(move || watch(&app, &session_id, &output));
+    Ok(())
+}
+
+fn touch_prompt(session_id: &str, text: &str) -> AuthPrompt {
+    AuthPrompt {
+        id: Uuid::new_v4().to_string(),
+        session_id: Some(session_id.to_string()),
+        kind: AuthPromptKind::Touch,
+        prompt: text.to_string(),
+        echo: true,
+    }
+}
+
+fn emit_closed(app: &AppHandle, id: String, answered: bool) {
+    let _ = app.emit("auth:prompt_closed", AuthPromptClosedEvent { id, answered });
+}
+
+fn watch(app: &AppHandle, session_id: &str, output: &Receiver<String>) {
+    let deadline = Instant::now() + WATCH_FOR;
+    let mut tail = String::new();
+    // The touch prompt shown, until ssh moves on.
+    let mut waiting: Option<String> = None;
+    while let Ok(chunk) = output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        tail.push_str(&chunk);
+        if tail.len() > TAIL_BYTES {
+            let mut cut = tail.len() - TAIL_BYTES;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
+        }
+
+        if let Some(pos) = tail.rfind(TOUCH_MARKER) {
+            // Wait for the rest of the line.
+            let Some(end) = tail[pos..].find(['\r', '\n']).map(|i| pos + i) else { continue };
+            if let Some(id) = waiting.take() {
+                emit_closed(app, id, true);
+            }
+            let prompt = touch_prompt(session_id, tail[pos..end].trim());
+            let _ = app.emit("auth:prompt", &prompt);
+            // Output after the line means the key was touched already.
+            if tail[end..].trim().is_empty() {
+                waiting = Some(prompt.id);
+            } else {
+                emit_closed(app, prompt.id, true);
+            }
+            tail.clear();
+        } else if !chunk.trim().is_empty() {
+            if let Some(id) = waiting.take() {
+                emit_closed(app, id, true);
+            }
+        }
+    }
+    if let Some(id) = waiting {
+        emit_closed(app, id, false);
+    }
+}
@@ -0,0 +1,359 @@
+//! Backend execution of multi-step CommandDock sequences into a live terminal session.
+//!
+//! Every step is rendered up front (so a bad value fails before anything is sent), then written
+//! through the same CommandDock path as a manual "Run". Progress is reported via
+//! `sequence:progress` events; runs can be aborted between (and during) steps.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::db::{DockCommand, PolicyApproval, Sequence, SequenceStep};
+use crate::terminal::session_manager::WriteMeta;
+use crate::{command_timing, notifications, policy, template, AppState};
+
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Longest delay or wait-for timeout a step may have.
+const MAX_STEP_MS: u64 = 24 * 60 * 60 * 1000;
+/// How much trailing output is kept for wait-for matching.
+const MATCH_WINDOW_BYTES: usize = 64 * 1024;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceProgressEvent {
+    pub run_id: String,
+    pub sequence_id: String,
+    pub session_id: String,
+    /// Zero-based step index (equal to `total` once the run has finished).
+    pub step: usize,
+    pub total: usize,
+    /// "running" | "waiting" | "completed" | "failed" | "aborted"
+    pub status: String,
+    pub message: Option<String>,
+}
+
+/// In-flight runs keyed by run id, holding their abort flags.
+#[derive(Default)]
+pub struct SequenceRuns {
+    runs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl SequenceRuns {
+    pub fn abort(&self, run_id: &str) -> bool {
+        match self.runs.lock().expect("poisoned sequence runs lock").get(run_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct PreparedStep {
+    command_id: String,
+    title: String,
+    template: String,
+    text: String,
+    /// How the step got past the environment policy, for history.
+    approval: Option<PolicyApproval>,
+    wait_for: Option<Regex>,
+    timeout: Duration,
+    delay: Option<Duration>,
+    /// The dock command's own run timeout.
+    run_timeout: Option<command_timing::Timeout>,
+}
+
+enum StepError {
+    Aborted,
+    Failed(String),
+}
+
+/// Validate step definitions before they are persisted.
+pub fn validate_steps(steps: &[SequenceStep]) -> Result<(), String> {
+    for (i, step) in steps.iter().enumerate() {
+        if step.dock_command_id.trim().is_empty() {
+            return Err(format!("step {}: a dock command is required", i + 1));
+        }
+        if step.delay_ms.is_some_and(|ms| ms > MAX_STEP_MS) || step.timeout_ms.is_some_and(|ms| ms > MAX_STEP_MS) {
+            return Err(format!("step {}: delays and timeouts must be at most 24 hours", i + 1));
+        }
+        if let Some(p) = step.wait_for.as_deref().filter(|p| !p.trim().is_empty()) {
+            Regex::new(p).map_err(|e| format!("step {}: invalid wait-for pattern: {e}", i + 1))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequencePreview {
+    pub environment: String,
+    /// Each step's rendered command, lint findings and policy decision.
+    pub steps: Vec<policy::CommandPreview>,
+    /// Hash of all the steps together; sent back with the run's confirmation.
+    pub command_hash: String,
+}
+
+/// Each step's dock command and its rendered text.
+fn render(state: &AppState, seq: &Sequence) -> Result<Vec<(DockCommand, String)>, String> {
+    if seq.steps.is_empty() {
+        return Err("sequence has no steps".to_string());
+    }
+    seq.steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let cmd = state
+                .db
+                .dock_commands_get(&step.dock_command_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("step {}: dock command not found", i + 1))?;
+            let text = template::render(&cmd.command, &cmd.params, &step.values)
+                .map_err(|e| format!("step {}: {e}", i + 1))?;
+            Ok((cmd, text))
+        })
+        .collect()
+}
+
+/// What running `seq` in `session_id` would send, and how the environment policy treats it.
+pub fn preview(state: &AppState, seq: &Sequence, session_id: Option<&str>) -> Result<SequencePreview, String> {
+    let environment = match session_id {
+        Some(id) => policy::session_environment(&state.db, id)?,
+        None => "UNKNOWN".to_string(),
+    };
+    let rendered = render(state, seq)?;
+    let command_hash = policy::command_hash(&policy::joined_commands(rendered.iter().map(|(_, t)| t.as_str())));
+    let steps = rendered
+        .into_iter()
+        .map(|(cmd, text)| policy::preview(&state.db, &environment, &cmd, text))
+        .collect::<Result<_, _>>()?;
+    Ok(SequencePreview {
+        environment,
+        steps,
+        command_hash,
+    })
+}
+
+/// Validate and start a sequence run in the background. Returns the run id.
+///
+/// Every step is checked against the session's environment policy up front; `confirmation` is
+/// the typed confirmation for the whole run, given with the [`preview`]'s hash as
+/// `confirmed_hash`, and `approval` a four-eyes approval token (which only covers a step whose
+/// command it was issued for).
+pub fn start(
+    app: AppHandle,
+    state: Arc<AppState>,
+    seq: Sequence,
+    session_id: String,
+    confirmation: Option<&str>,
+    approval: Option<&str>,
+    confirmed_hash: Option<&str>,
+) -> Result<String, String> {
+    state.terminal.check_writable(&session_id).map_err(|e| e.to_string())?;
+    // Sequences saved before the limits existed may not satisfy them.
+    validate_steps(&seq.steps)?;
+    let environment = policy::session_environment(&state.db, &session_id)?;
+    let rendered = render(&state, &seq)?;
+    let all = policy::joined_commands(rendered.iter().map(|(_, t)| t.as_str()));
+    policy::check_confirmed(&all, confirmation, confirmed_hash)?;
+
+    let scope = state.db.terminal_session_scope_get(&session_id).map_err(|e| e.to_string())?;
+    let mut steps = Vec::with_capacity(rendered.len());
+    for (i, (step, (cmd, text))) in seq.steps.iter().zip(rendered).enumerate() {
+        let text = crate::command_plugins(
+            &state,
+            &text,
+            Some(&session_id),
+            scope.as_deref(),
+            Some(&environment),
+            Some(&cmd.id),
+        )
+        .map_err(|e| format!("step {}: {e}", i + 1))?;
+        let approval = policy::check_dock_command(&state.db, &environment, &cmd, &text, confirmation, approval)
+            .map_err(|e| format!("step {}: {e}", i + 1))?;
+        let wait_for = step
+            .wait_for
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("step {}: invalid wait-for pattern: {e}", i + 1))?;
+        steps.push(PreparedStep {
+            run_timeout: command_timing::Timeout::of(&cmd),
+            command_id: cmd.id,
+            title: cmd.title,
+            template: cmd.command,
+            text,
+            approval,
+            wait_for,
+            timeout: step.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_WAIT_TIMEOUT),
+            delay: step.delay_ms.filter(|ms| *ms > 0).map(Duration::from_millis),
+        });
+    }
+
+    // Subscribe before the first write so no output is missed.
+    let output = state.terminal.subscribe_output(&session_id).map_err(|e| e.to_string())?;
+
+    let run_id = Uuid::new_v4().to_string();
+    let abort = Arc::new(AtomicBool::new(false));
+    state
+        .sequences
+        .runs
+        .lock()
+        .expect("poisoned sequence runs lock")
+        .insert(run_id.clone(), abort.clone());
+
+    let run_id2 = run_id.clone();
+    thread::spawn(move || {
+        let total = steps.len();
+        let emit = |step: usize, status: &str, message: Option<String>| {
+            let _ = app.emit(
+                "sequence:progress",
+                SequenceProgressEvent {
+                    run_id: run_id2.clone(),
+                    sequence_id: seq.id.clone(),
+                    session_id: session_id.clone(),
+                    step,
+                    total,
+                    status: status.to_string(),
+                    message,
+                },
+            );
+        };
+
+        let mut outcome = Ok(());
+        for (i, step) in steps.iter().enumerate() {
+            emit(i, "running", Some(step.title.clone()));
+            if let Err(e) = run_step(&app, &state, &session_id, step, &output, &abort, |waiting| {
+                emit(i, "waiting", Some(waiting));
+            }) {
+                outcome = Err((i, e));
+                break;
+            }
+        }
+
+        state
+            .sequences
+            .runs
+            .lock()
+            .expect("poisoned sequence runs lock")
+            .remove(&run_id2);
+
+        let (title, body) = match outcome {
+            Ok(()) => {
+                emit(total, "completed", None);
+                ("Sequence completed".to_string(), format!("{total} steps"))
+            }
+            Err((i, StepError::Aborted)) => {
+                emit(i, "aborted", None);
+                ("Sequence aborted".to_string(), format!("at step {} of {total}", i + 1))
+            }
+            Err((i, StepError::Failed(msg))) => {
+                let body = format!("step {} of {total}: {msg}", i + 1);
+                emit(i, "failed", Some(msg));
+                ("Sequence failed".to_string(), body)
+            }
+        };
+        notifications::notify(&app, notifications::Kind::Sequence, &format!("{title}: {}", seq.title), &body);
+    });
+
+    Ok(run_id)
+}
+
+fn run_step(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    step: &PreparedStep,
+    output: &Receiver<String>,
+    abort: &AtomicBool,
+    on_wait: impl Fn(String),
+) -> Result<(), StepError> {
+    if abort.load(Ordering::SeqCst) {
+        return Err(StepError::Aborted);
+    }
+
+    // Only output produced after this step's command counts toward its wait condition.
+    while output.try_recv().is_ok() {}
+
+    let data = format!("{}\r", step.text);
+    let history_id = crate::record_commanddock_write(
+        state,
+        session_id,
+        &data,
+        Some(&step.command_id),
+        Some(&step.title),
+        Some(&step.template),
+        step.approval.as_ref(),
+    );
+    if let Some(history_id) = history_id {
+        command_timing::watch(app, state, session_id, &history_id, &data, step.run_timeout);
+    }
+    state
+        .terminal
+        .write_with_meta(
+            session_id,
+            &data,
+            WriteMeta {
+                origin: Some("commanddock".to_string()),
+            },
+        )
+        .map_err(|e| StepError::Failed(e.to_string()))?;
+
+    if let Some(re) = &step.wait_for {
+        on_wait(re.as_str().to_string());
+        let deadline = Instant::now() + step.timeout;
+        let mut window = String::new();
+        loop {
+            if abort.load(Ordering::SeqCst) {
+                return Err(StepError::Aborted);
+            }
+            if Instant::now() >= deadline {
+                return Err(StepError::Failed(format!(
+                    "timed out waiting for output matching `{}`",
+                    re.as_str()
+                )));
+            }
+            match output.recv_timeout(POLL_INTERVAL) {
+                Ok(chunk) => {
+                    window.push_str(&chunk);
+                    if window.len() > MATCH_WINDOW_BYTES {
+                        let mut cut = window.len() - MATCH_WINDOW_BYTES;
+                        while !window.is_char_boundary(cut) {
+                            cut += 1;
+                        }
+                        window.drain(..cut);
+                    }
+                    if re.is_match(&window) {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(StepError::Failed("terminal session ended".to_string()));
+                }
+            }
+        }
+    }
+
+    if let Some(delay) = step.delay {
+        let deadline = Instant::now() + delay;
+        while Instant::now() < deadline {
+            if abort.load(Ordering::SeqCst) {
+                return Err(StepError::Aborted);
+            }
+            thread::sleep(POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Post-connect server summary: when an ssh session opens, a separate non-interactive ssh call
+//! runs `uname -a`, `uptime` and a distro lookup, and the result is attached to the session (in
+//! `terminal_list`) and, for saved hosts, to the host record, then announced as
+//! `terminal:server_info`.
+//!
+//! The side call is in batch mode, so it only gets through when the host logs in with a key or
+//! agent; a host that needs a password (or a security key touch, which would ask twice) records
+//! why it wasn't probed instead.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::arch::ssh;
+use crate::db::{Db, Host, ServerInfo};
+use crate::{exec, security_key, AppState};
+
+const SETTINGS_KEY: &str = "server_info";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+const SPLIT: &str = "__OPSPAD_SPLIT__";
+/// POSIX sh; os-release is read in a subshell so its variables don't leak into the next lines.
+const BUNDLE: &str = "LC_ALL=C; export LC_ALL; hostname 2>/dev/null; echo __OPSPAD_SPLIT__; uname -a; \
+echo __OPSPAD_SPLIT__; uptime; echo __OPSPAD_SPLIT__; \
+if [ -r /etc/os-release ]; then (. /etc/os-release; echo \"${PRETTY_NAME:-$NAME $VERSION}\"); \
+elif command -v sw_vers >/dev/null 2>&1; then echo \"$(sw_vers -productName) $(sw_vers -productVersion)\"; fi";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfoConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for ServerInfoConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl ServerInfoConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+/// Payload of `terminal:server_info`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerInfoEvent {
+    session_id: String,
+    host_id: Option<String>,
+    info: ServerInfo,
+}
+
+/// Where the session went, as it was opened.
+#[derive(Clone)]
+pub struct Target {
+    pub user: String,
+    pub address: String,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    /// The session's own ssh arguments; only the ones that pick a route or config are reused.
+    pub extra_args: Vec<String>,
+    pub host: Option<Host>,
+}
+
+impl Target {
+    /// Logging in needs a security key touch, which an unattended ssh call would ask for again.
+    pub fn needs_touch(&self) -> bool {
+        self.host.as_ref().is_some_and(|host| {
+            host.security_key_provider.as_deref().is_some_and(|p| !p.trim().is_empty())
+                || host.identity_file.as_deref().is_some_and(security_key::is_security_key_identity)
+        })
+    }
+}
+
+fn now_epoch_secs() -> i64 {
+    crate::timestamp::now_millis() / 1000
+}
+
+/// `-o`, `-J` and `-F` with their values: enough to reach the host the same way, without the
+/// session's forwards (which would collide with its own).
+pub(crate) fn route_args(extra_args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut args = extra_args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "-J" | "-F" => {
+                if let Some(value) = args.next() {
+                    out.push(arg.clone());
+                    out.push(value.clone());
+                }
+            }
+            a if a.starts_with("-o") || a.starts_with("-J") || a.starts_with("-F") => out.push(arg.clone()),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn field(part: Option<&str>) -> Option<String> {
+    part.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+fn parse(out: &str) -> ServerInfo {
+    let mut parts = out.split(SPLIT);
+    ServerInfo {
+        hostname: field(parts.next()),
+        kernel: field(parts.next()),
+        uptime: field(parts.next()),
+        distro: field(parts.next()),
+        probed_at: now_epoch_secs(),
+        error: None,
+    }
+}
+
+fn probe(target: &Target) -> ServerInfo {
+    let failed = |error: String| ServerInfo {
+        probed_at: now_epoch_secs(),
+        error: Some(error),
+        ..Default::default()
+    };
+    if target.needs_touch() {
+        return failed("not probed: the host logs in with a security key".to_string());
+    }
+    let program = match ssh::ssh_program_checked() {
+        Ok(p) => p,
+        Err(e) => return failed(e),
+    };
+    let mut args = route_args(&target.extra_args);
+    args.extend(ssh::exec_args(
+        &target.user,
+        &target.address,
+        target.port,
+        target.identity_file.as_deref(),
+        BUNDLE,
+    ));
+    match exec::run(&program, &args, PROBE_TIMEOUT) {
+        Ok(out) if out.timed_out => failed("timed out".to_string()),
+        Ok(out) if !out.stdout.contains(SPLIT) => {
+            let err = out.stderr.lines().last().unwrap_or("no output").trim().to_string();
+            failed(err)
+        }
+        Ok(out) => parse(&out.stdout),
+        Err(e) => failed(e.to_string()),
+    }
+}
+
+/// Probe the server behind a just-opened ssh session in the background, if enabled.
+pub fn spawn_probe(app: &AppHandle, state: &Arc<AppState>, session_id: &str, target: Target) {
+    if !ServerInfoConfig::load(&state.db).enabled {
+        return;
+    }
+    let (app, state, session_id) = (app.clone(), state.clone(), session_id.to_string());
+    thread::spawn(move || {
+        let info = probe(&target);
+        let host_id = target.host.as_ref().map(|h| h.id.clone());
+        if let (Some(id), None) = (&host_id, &info.error) {
+            if let Err(e) = state.db.hosts_set_server_info(id, &info) {
+                log::warn!("server info not stored for host {id}: {e}");
+            }
+        }
+        // The session may have closed while the probe ran.
+        if state.terminal.set_server_info(&session_id, info.clone()).is_ok() {
+            let _ = app.emit(
+                "terminal:server_info",
+                ServerInfoEvent {
+                    session_id,
+                    host_id,
+                    info,
+                },
+            );
+        }
+    });
+}
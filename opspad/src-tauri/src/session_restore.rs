@@ -0,0 +1,175 @@
+//! Reopening the terminals that were open when the app last quit.
+//!
+//! On exit the open sessions' scopes, environments and sizes are saved to settings
+//! (`session_restore`). On the next start the UI asks for a [`RestorePlan`] and offers to reopen
+//! them; a session to a host counts as reconnectable when it can log in without the user, i.e.
+//! the host has a key file or a password bound in the vault.
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Db, Host};
+use crate::layout::{self, WorkspaceLayout};
+use crate::terminal::session_manager::TerminalKind;
+use crate::AppState;
+
+const SETTINGS_KEY: &str = "session_restore";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedSession {
+    scope: String,
+    kind: TerminalKind,
+    environment_tag: String,
+    cols: u16,
+    rows: u16,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    /// Epoch seconds.
+    saved_at: i64,
+    sessions: Vec<SavedSession>,
+}
+
+/// How a restored ssh session would log in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Login {
+    /// A local shell; nothing to log in to.
+    None,
+    KeyFile,
+    /// A password bound to the host in the vault.
+    Password,
+    /// No bound credentials: ssh may use an agent or prompt.
+    Interactive,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSession {
+    pub scope: String,
+    pub kind: TerminalKind,
+    pub environment_tag: String,
+    pub cols: u16,
+    pub rows: u16,
+    /// The saved host, if the scope is one and it still exists.
+    pub host_id: Option<String>,
+    pub label: String,
+    pub login: Login,
+    /// Whether it can be reopened without the user (local, or a host with bound credentials).
+    pub reconnect: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorePlan {
+    /// When the sessions were saved (epoch seconds).
+    pub saved_at: i64,
+    pub sessions: Vec<RestoreSession>,
+    /// How the main window's tabs and panes were arranged; panes name sessions by scope.
+    pub layout: Option<WorkspaceLayout>,
+}
+
+/// Remember the open sessions; called as the app exits. Replaces any earlier snapshot, so quitting
+/// with nothing open leaves nothing to restore.
+pub fn save(state: &AppState) {
+    let sessions: Vec<SavedSession> = state
+        .terminal
+        .list()
+        .into_iter()
+        .filter_map(|s| {
+            let scope = state.db.terminal_session_scope_get(&s.session_id).ok().flatten()?;
+            Some(SavedSession {
+                scope,
+                kind: s.kind,
+                environment_tag: s.environment_tag,
+                cols: s.cols,
+                rows: s.rows,
+            })
+        })
+        .collect();
+    let snapshot = Snapshot {
+        saved_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        sessions,
+    };
+    let Ok(json) = serde_json::to_string(&snapshot) else {
+        return;
+    };
+    if let Err(e) = state.db.settings_set(SETTINGS_KEY, &json) {
+        log::warn!("open sessions not saved for restore: {e}");
+    }
+}
+
+fn load(db: &Db) -> Option<Snapshot> {
+    db.settings_get(SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+}
+
+/// How a new ssh session to `host` would log in.
+pub fn login(state: &AppState, host: &Host) -> Login {
+    if host.identity_file.as_deref().is_some_and(|f| !f.trim().is_empty()) {
+        Login::KeyFile
+    } else if host
+        .password_vault_key
+        .as_deref()
+        .is_some_and(|key| state.vault.metadata(key).ok().flatten().is_some())
+    {
+        Login::Password
+    } else {
+        Login::Interactive
+    }
+}
+
+/// What was open at the last exit; `None` if nothing was (or it was dismissed).
+pub fn plan(state: &AppState) -> Result<Option<RestorePlan>, String> {
+    let Some(snapshot) = load(&state.db).filter(|s| !s.sessions.is_empty()) else {
+        return Ok(None);
+    };
+    let mut sessions = Vec::with_capacity(snapshot.sessions.len());
+    for saved in snapshot.sessions {
+        let host = match saved.scope.strip_prefix("ssh:") {
+            Some(id) => state.db.hosts_get(id).map_err(|e| e.to_string())?,
+            None => None,
+        };
+        let (label, login, reconnect) = match (&host, saved.kind) {
+            (_, TerminalKind::Local) => ("Terminal".to_string(), Login::None, true),
+            (Some(h), TerminalKind::Ssh) => {
+                let login = login(state, h);
+                (h.label.clone(), login, login != Login::Interactive)
+            }
+            // An ad-hoc connection ("ssh:user@host:port") or a host deleted since.
+            (None, TerminalKind::Ssh) => {
+                let target = saved.scope.strip_prefix("ssh:").unwrap_or(&saved.scope).to_string();
+                (target, Login::Interactive, false)
+            }
+        };
+        sessions.push(RestoreSession {
+            scope: saved.scope,
+            kind: saved.kind,
+            environment_tag: saved.environment_tag,
+            cols: saved.cols,
+            rows: saved.rows,
+            host_id: host.map(|h| h.id),
+            label,
+            login,
+            reconnect,
+        });
+    }
+    Ok(Some(RestorePlan {
+        saved_at: snapshot.saved_at,
+        sessions,
+        layout: layout::get(&state.db, None).ok().flatten().map(|saved| saved.layout),
+    }))
+}
+
+/// Forget the snapshot once the user has restored or declined it.
+pub fn dismiss(db: &Db) -> Result<(), String> {
+    let json = serde_json::to_string(&Snapshot::default()).map_err(|e| e.to_string())?;
+    db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+}
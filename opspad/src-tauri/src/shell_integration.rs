@@ -0,0 +1,454 @@
+//! Installs OpsPad's shell integration into a shell's profile, on this machine or a saved host.
+//!
+//! The integration is a small script per shell that reports the working directory (OSC 7) and
+//! brackets each command with prompt marks (OSC 133, see `terminal/prompt_marks.rs`), the last one
+//! carrying the exit status. Installing writes the script to `~/.config/opspad/` and adds a block
+//! that sources it to the shell's profile between marker comments; installing again rewrites the
+//! script and leaves the block, uninstalling removes both. Remote hosts are changed over a
+//! non-interactive ssh call, so they need to log in with a key or agent.
+//!
+//! Whether a session actually has it running shows in `terminal_list` (`shellIntegration`), set
+//! once its shell sends a prompt mark.
+
+use serde::{Deserialize, Serialize};
+
+use crate::arch::ssh;
+use crate::db::Host;
+use crate::{exec, security_key, AppState};
+
+const BLOCK_START: &str = "# >>> opspad shell integration >>>";
+const BLOCK_END: &str = "# <<< opspad shell integration <<<";
+/// Under the home directory, here and on remote hosts.
+const SCRIPT_DIR: &str = ".config/opspad";
+const REMOTE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+const HEREDOC_END: &str = "__OPSPAD_EOF__";
+
+const BASH: &str = r##"# OpsPad shell integration for bash: working directory (OSC 7) and prompt marks (OSC 133).
+if [[ $- == *i* && -z ${__opspad_integration-} ]]; then
+  __opspad_integration=1
+  __opspad_ran=0
+  __opspad_prompt() {
+    local status=$?
+    if [[ $__opspad_ran == 1 ]]; then printf '\e]133;D;%s\a' "$status"; fi
+    __opspad_ran=1
+    printf '\e]7;file://%s%s\a' "${HOSTNAME-}" "$PWD"
+    printf '\e]133;A;uid=%s\a' "$EUID"
+    [[ $PS1 == *'133;B'* ]] || PS1+='\[\e]133;B\a\]'
+    return $status
+  }
+  PS0+='\e]133;C\a'
+  PROMPT_COMMAND="__opspad_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"##;
+
+const ZSH: &str = r##"# OpsPad shell integration for zsh: working directory (OSC 7) and prompt marks (OSC 133).
+if [[ -o interactive && -z ${__opspad_integration-} ]]; then
+  typeset -g __opspad_integration=1 __opspad_ran=0
+  __opspad_precmd() {
+    local status_=$?
+    (( __opspad_ran )) && printf '\e]133;D;%s\a' "$status_"
+    __opspad_ran=0
+    printf '\e]7;file://%s%s\a' "$HOST" "$PWD"
+    printf '\e]133;A;uid=%s\a' "$EUID"
+    [[ $PS1 == *'133;B'* ]] || PS1="$PS1"$'%{\e]133;B\a%}'
+  }
+  __opspad_preexec() {
+    __opspad_ran=1
+    printf '\e]133;C\a'
+  }
+  autoload -Uz add-zsh-hook
+  add-zsh-hook precmd __opspad_precmd
+  add-zsh-hook preexec __opspad_preexec
+fi
+"##;
+
+const FISH: &str = r##"# OpsPad shell integration for fish: working directory (OSC 7) and prompt marks (OSC 133).
+if status is-interactive; and not set -q __opspad_integration
+    set -g __opspad_integration 1
+    set -g __opspad_uid (id -u)
+    function __opspad_prompt --on-event fish_prompt
+        printf '\e]7;file://%s%s\a' $hostname $PWD
+        printf '\e]133;A;uid=%s\a' $__opspad_uid
+    end
+    function __opspad_preexec --on-event fish_preexec
+        printf '\e]133;C\a'
+    end
+    function __opspad_postexec --on-event fish_postexec
+        printf '\e]133;D;%s\a' $status
+    end
+end
+"##;
+
+/// PowerShell has no hook before a command runs, so there's no `C` mark; commands are timed from
+/// when they're sent instead.
+const POWERSHELL: &str = r##"# OpsPad shell integration for PowerShell: working directory (OSC 7) and prompt marks (OSC 133).
+if (-not $global:__OpsPadIntegration -and $Host.Name -eq 'ConsoleHost') {
+    $global:__OpsPadIntegration = $true
+    $global:__OpsPadRan = $false
+    $global:__OpsPadPrompt = $function:prompt
+    function global:prompt {
+        $ok = $?
+        $code = $global:LASTEXITCODE
+        $esc = [char]27
+        $bel = [char]7
+        $out = ''
+        if ($global:__OpsPadRan) {
+            $status = if ($ok) { 0 } elseif ($code) { $code } else { 1 }
+            $out += "$esc]133;D;$status$bel"
+        }
+        $global:__OpsPadRan = $true
+        $loc = $executionContext.SessionState.Path.CurrentLocation
+        if ($loc.Provider.Name -eq 'FileSystem') {
+            $path = $loc.ProviderPath -replace '\\', '/'
+            if (-not $path.StartsWith('/')) { $path = '/' + $path }
+            $out += "$esc]7;file://$([Environment]::MachineName)$path$bel"
+        }
+        $out += "$esc]133;A$bel"
+        $out + (& $global:__OpsPadPrompt) + "$esc]133;B$bel"
+    }
+}
+"##;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl ShellKind {
+    const ALL: [ShellKind; 4] = [ShellKind::Bash, ShellKind::Zsh, ShellKind::Fish, ShellKind::PowerShell];
+
+    fn name(self) -> &'static str {
+        match self {
+            ShellKind::Bash => "bash",
+            ShellKind::Zsh => "zsh",
+            ShellKind::Fish => "fish",
+            ShellKind::PowerShell => "powershell",
+        }
+    }
+
+    pub fn snippet(self) -> &'static str {
+        match self {
+            ShellKind::Bash => BASH,
+            ShellKind::Zsh => ZSH,
+            ShellKind::Fish => FISH,
+            ShellKind::PowerShell => POWERSHELL,
+        }
+    }
+
+    fn script_name(self) -> &'static str {
+        match self {
+            ShellKind::Bash => "shell-integration.bash",
+            ShellKind::Zsh => "shell-integration.zsh",
+            ShellKind::Fish => "shell-integration.fish",
+            ShellKind::PowerShell => "shell-integration.ps1",
+        }
+    }
+
+    /// Programs that run this shell; the first found counts.
+    fn programs(self) -> &'static [&'static str] {
+        match self {
+            ShellKind::Bash => &["bash"],
+            ShellKind::Zsh => &["zsh"],
+            ShellKind::Fish => &["fish"],
+            ShellKind::PowerShell => &["pwsh", "powershell"],
+        }
+    }
+
+    /// The profile line that loads the script.
+    fn source_line(self) -> String {
+        let script = format!("{SCRIPT_DIR}/{}", self.script_name());
+        match self {
+            ShellKind::Bash | ShellKind::Zsh => format!("[ -f \"$HOME/{script}\" ] && . \"$HOME/{script}\""),
+            ShellKind::Fish => format!("test -f ~/{script}; and source ~/{script}"),
+            ShellKind::PowerShell => format!("if (Test-Path \"$HOME/{script}\") {{ . \"$HOME/{script}\" }}"),
+        }
+    }
+
+    fn block(self) -> String {
+        format!("{BLOCK_START}\n{}\n{BLOCK_END}\n", self.source_line())
+    }
+
+    /// The profile on a POSIX host, as a shell expression.
+    fn remote_profile(self) -> &'static str {
+        match self {
+            ShellKind::Bash => "$HOME/.bashrc",
+            ShellKind::Zsh => "${ZDOTDIR:-$HOME}/.zshrc",
+            // fish runs everything in conf.d, so the block gets a file of its own.
+            ShellKind::Fish => "$HOME/.config/fish/conf.d/opspad.fish",
+            ShellKind::PowerShell => "$HOME/.config/powershell/Microsoft.PowerShell_profile.ps1",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellIntegrationStatus {
+    pub shell: ShellKind,
+    pub profile: String,
+    /// The shell is on the PATH.
+    pub available: bool,
+    pub installed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallOutcome {
+    pub shell: ShellKind,
+    pub profile: String,
+    /// False when the profile already had (or, uninstalling, didn't have) the block.
+    pub changed: bool,
+}
+
+enum Target {
+    Local,
+    Remote(Box<Host>),
+}
+
+fn target(state: &AppState, scope: &str) -> Result<Target, String> {
+    let scope = scope.trim();
+    if scope == "local" {
+        return Ok(Target::Local);
+    }
+    let host_id = scope
+        .strip_prefix("ssh:")
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| format!("shell integration is installed locally or on a saved host (ssh:<host id>), not {scope}"))?;
+    let host = state
+        .db
+        .hosts_get(host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    if !host.connection_type.is_ssh() {
+        return Err(format!("{} is a remote desktop host", host.label));
+    }
+    Ok(Target::Remote(Box::new(host)))
+}
+
+fn home() -> Result<std::path::PathBuf, String> {
+    dirs::home_dir().ok_or_else(|| "no home directory".to_string())
+}
+
+fn local_profile(shell: ShellKind) -> Result<std::path::PathBuf, String> {
+    let home = home()?;
+    Ok(match shell {
+        ShellKind::Bash => home.join(".bashrc"),
+        ShellKind::Zsh => std::env::var_os("ZDOTDIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or(home)
+            .join(".zshrc"),
+        ShellKind::Fish => home.join(".config/fish/conf.d/opspad.fish"),
+        ShellKind::PowerShell if cfg!(windows) => dirs::document_dir()
+            .unwrap_or(home)
+            .join("PowerShell")
+            .join("Microsoft.PowerShell_profile.ps1"),
+        ShellKind::PowerShell => home.join(".config/powershell/Microsoft.PowerShell_profile.ps1"),
+    })
+}
+
+/// `text` without the marker block (and the blank line put before it).
+fn strip_block(text: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    let mut inside = false;
+    for line in text.lines() {
+        match line.trim_end() {
+            BLOCK_START => {
+                inside = true;
+                if out.last().is_some_and(|l| l.trim().is_empty()) {
+                    out.pop();
+                }
+            }
+            BLOCK_END if inside => inside = false,
+            _ if inside => {}
+            _ => out.push(line),
+        }
+    }
+    let mut joined = out.join("\n");
+    if !joined.is_empty() {
+        joined.push('\n');
+    }
+    joined
+}
+
+fn install_local(shell: ShellKind) -> Result<InstallOutcome, String> {
+    let script_dir = home()?.join(SCRIPT_DIR);
+    std::fs::create_dir_all(&script_dir).map_err(|e| format!("{}: {e}", script_dir.display()))?;
+    let script = script_dir.join(shell.script_name());
+    std::fs::write(&script, shell.snippet()).map_err(|e| format!("{}: {e}", script.display()))?;
+
+    let profile = local_profile(shell)?;
+    let current = std::fs::read_to_string(&profile).unwrap_or_default();
+    let changed = !current.lines().any(|l| l.trim_end() == BLOCK_START);
+    if changed {
+        if let Some(dir) = profile.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        let mut text = current;
+        if !text.is_empty() {
+            text.push_str(if text.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        text.push_str(&shell.block());
+        std::fs::write(&profile, text).map_err(|e| format!("{}: {e}", profile.display()))?;
+    }
+    Ok(InstallOutcome {
+        shell,
+        profile: profile.display().to_string(),
+        changed,
+    })
+}
+
+fn uninstall_local(shell: ShellKind) -> Result<InstallOutcome, String> {
+    let profile = local_profile(shell)?;
+    let current = std::fs::read_to_string(&profile).unwrap_or_default();
+    let stripped = strip_block(&current);
+    let changed = current.lines().any(|l| l.trim_end() == BLOCK_START);
+    if changed {
+        std::fs::write(&profile, stripped).map_err(|e| format!("{}: {e}", profile.display()))?;
+    }
+    let _ = std::fs::remove_file(home()?.join(SCRIPT_DIR).join(shell.script_name()));
+    Ok(InstallOutcome {
+        shell,
+        profile: profile.display().to_string(),
+        changed,
+    })
+}
+
+fn status_local() -> Result<Vec<ShellIntegrationStatus>, String> {
+    ShellKind::ALL
+        .iter()
+        .map(|&shell| {
+            let profile = local_profile(shell)?;
+            let installed = std::fs::read_to_string(&profile)
+                .is_ok_and(|text| text.lines().any(|l| l.trim_end() == BLOCK_START));
+            Ok(ShellIntegrationStatus {
+                shell,
+                profile: profile.display().to_string(),
+                available: shell.programs().iter().any(|p| which::which(p).is_ok()),
+                installed,
+            })
+        })
+        .collect()
+}
+
+/// Run a POSIX sh script on the host; its stdout.
+fn run_remote(host: &Host, script: &str) -> Result<String, String> {
+    let program = ssh::ssh_program_checked()?;
+    // The login shell may not be POSIX (fish, csh).
+    let command = format!("sh -c {}", ssh::shell_quote(script));
+    let mut args = security_key::ssh_options(host);
+    args.extend(ssh::exec_args(
+        &host.username,
+        &host.hostname,
+        Some(host.port),
+        host.identity_file.as_deref(),
+        &command,
+    ));
+    let out = exec::run(&program, &args, REMOTE_TIMEOUT).map_err(|e| e.to_string())?;
+    if out.timed_out {
+        return Err(format!("{} didn't answer in time", host.label));
+    }
+    if out.exit_code != Some(0) {
+        let err = out.stderr.lines().last().unwrap_or("failed").trim().to_string();
+        return Err(format!("{}: {err}", host.label));
+    }
+    Ok(out.stdout)
+}
+
+/// The last two lines a remote script prints: "installed"/"removed"/"unchanged", then the profile.
+fn remote_outcome(shell: ShellKind, stdout: &str) -> InstallOutcome {
+    let mut lines = stdout.lines().rev();
+    let profile = lines.next().unwrap_or_default().trim().to_string();
+    let changed = lines.next().is_some_and(|l| l.trim() != "unchanged");
+    InstallOutcome { shell, profile, changed }
+}
+
+fn install_remote(host: &Host, shell: ShellKind) -> Result<InstallOutcome, String> {
+    let script = format!(
+        "set -e\nd=\"$HOME/{SCRIPT_DIR}\"\nmkdir -p \"$d\"\ncat > \"$d/{name}\" <<'{HEREDOC_END}'\n{snippet}{HEREDOC_END}\n\
+         p=\"{profile}\"\nmkdir -p \"$(dirname \"$p\")\"\ntouch \"$p\"\n\
+         if grep -qxF {start} \"$p\"; then echo unchanged; else printf '\\n%s' {block} >> \"$p\"; echo installed; fi\n\
+         echo \"$p\"\n",
+        name = shell.script_name(),
+        snippet = shell.snippet(),
+        profile = shell.remote_profile(),
+        start = ssh::shell_quote(BLOCK_START),
+        block = ssh::shell_quote(&shell.block()),
+    );
+    run_remote(host, &script).map(|out| remote_outcome(shell, &out))
+}
+
+fn uninstall_remote(host: &Host, shell: ShellKind) -> Result<InstallOutcome, String> {
+    // Rewritten in place (not moved over) so the profile keeps its owner, mode and links.
+    let script = format!(
+        "set -e\np=\"{profile}\"\n\
+         if [ -f \"$p\" ] && grep -qxF {start} \"$p\"; then\n\
+         sed '/^# >>> opspad shell integration >>>$/,/^# <<< opspad shell integration <<<$/d' \"$p\" > \"$p.opspad-tmp\"\n\
+         cat \"$p.opspad-tmp\" > \"$p\"\nrm -f \"$p.opspad-tmp\"\necho removed\nelse echo unchanged; fi\n\
+         rm -f \"$HOME/{SCRIPT_DIR}/{name}\"\necho \"$p\"\n",
+        profile = shell.remote_profile(),
+        start = ssh::shell_quote(BLOCK_START),
+        name = shell.script_name(),
+    );
+    run_remote(host, &script).map(|out| remote_outcome(shell, &out))
+}
+
+fn status_remote(host: &Host) -> Result<Vec<ShellIntegrationStatus>, String> {
+    // One line per shell: name, available, installed, profile.
+    let mut script = String::new();
+    for shell in ShellKind::ALL {
+        let available = shell
+            .programs()
+            .iter()
+            .map(|p| format!("command -v {p} >/dev/null 2>&1"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        script.push_str(&format!(
+            "p=\"{profile}\"; a=0; i=0; if {available}; then a=1; fi; \
+             if grep -qxF {start} \"$p\" 2>/dev/null; then i=1; fi; echo \"{name} $a $i $p\"\n",
+            profile = shell.remote_profile(),
+            start = ssh::shell_quote(BLOCK_START),
+            name = shell.name(),
+        ));
+    }
+    let out = run_remote(host, &script)?;
+    let statuses = out
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, ' ');
+            let name = parts.next()?;
+            let shell = ShellKind::ALL.into_iter().find(|s| s.name() == name)?;
+            Some(ShellIntegrationStatus {
+                shell,
+                available: parts.next()? == "1",
+                installed: parts.next()? == "1",
+                profile: parts.next()?.to_string(),
+            })
+        })
+        .collect();
+    Ok(statuses)
+}
+
+/// Which shells have the integration in their profile, in `scope` ("local" or "ssh:<host id>").
+pub fn status(state: &AppState, scope: &str) -> Result<Vec<ShellIntegrationStatus>, String> {
+    match target(state, scope)? {
+        Target::Local => status_local(),
+        Target::Remote(host) => status_remote(&host),
+    }
+}
+
+pub fn install(state: &AppState, scope: &str, shell: ShellKind) -> Result<InstallOutcome, String> {
+    match target(state, scope)? {
+        Target::Local => install_local(shell),
+        Target::Remote(host) => install_remote(&host, shell),
+    }
+}
+
+pub fn uninstall(state: &AppState, scope: &str, shell: ShellKind) -> Result<InstallOutcome, String> {
+    match target(state, scope)? {
+        Target::Local => uninstall_local(shell),
+        Target::Remote(host) => uninstall_remote(&host, shell),
+    }
+}
@@ -0,0 +1,125 @@
+//! Keyboard shortcut chords for dock commands and app actions.
+//!
+//! Chords are normalized here ("shift+ctrl+k" -> "Ctrl+Shift+K") so conflict checks compare like
+//! with like, whatever order or spelling the frontend sends.
+
+use crate::db::{Db, Shortcut, ShortcutTarget};
+use crate::global_hotkeys::GlobalHotkeys;
+
+/// App actions a shortcut can trigger besides running a dock command.
+pub const ACTIONS: &[&str] = &["dock.search", "dock.newCommand", "dock.history", "dock.editRunbook"];
+
+/// Chords the terminal needs for itself; binding them would break copy/paste or interrupts.
+pub const RESERVED: &[&str] = &["Ctrl+C", "Ctrl+D", "Ctrl+V", "Ctrl+Z", "Ctrl+Shift+C", "Ctrl+Shift+V"];
+
+const MODIFIERS: &[(&str, &[&str])] = &[
+    ("Ctrl", &["ctrl", "control"]),
+    ("Alt", &["alt", "option"]),
+    ("Shift", &["shift"]),
+    ("Meta", &["meta", "cmd", "command", "super", "win"]),
+];
+
+fn normalize_key(key: &str) -> Option<String> {
+    let lower = key.to_ascii_lowercase();
+    let named = match lower.as_str() {
+        "esc" | "escape" => "Escape",
+        "enter" | "return" => "Enter",
+        "space" => "Space",
+        "tab" => "Tab",
+        "backspace" => "Backspace",
+        "delete" | "del" => "Delete",
+        "up" | "arrowup" => "ArrowUp",
+        "down" | "arrowdown" => "ArrowDown",
+        "left" | "arrowleft" => "ArrowLeft",
+        "right" | "arrowright" => "ArrowRight",
+        "home" => "Home",
+        "end" => "End",
+        "pageup" => "PageUp",
+        "pagedown" => "PageDown",
+        _ => "",
+    };
+    if !named.is_empty() {
+        return Some(named.to_string());
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=24).contains(&n).then(|| format!("F{n}"));
+    }
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_graphic() => Some(c.to_ascii_uppercase().to_string()),
+        _ => None,
+    }
+}
+
+/// Normalize a chord like "shift+ctrl+k" to "Ctrl+Shift+K".
+///
+/// Every chord needs Ctrl, Alt, or Meta (Shift alone would swallow typing), except F-keys.
+pub fn normalize_chord(chord: &str) -> Result<String, String> {
+    let parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+    let Some((key, mods)) = parts.split_last() else {
+        return Err("shortcut must not be empty".to_string());
+    };
+    let mut present = [false; 4];
+    for m in mods {
+        let lower = m.to_ascii_lowercase();
+        let idx = MODIFIERS
+            .iter()
+            .position(|(_, aliases)| aliases.contains(&lower.as_str()))
+            .ok_or_else(|| format!("unknown modifier in shortcut: {m}"))?;
+        present[idx] = true;
+    }
+    let key = normalize_key(key).ok_or_else(|| format!("unsupported key in shortcut: {chord}"))?;
+    let has_command_modifier = present[0] || present[1] || present[3];
+    let is_function_key = key.len() > 1 && key.starts_with('F') && key[1..].chars().all(|c| c.is_ascii_digit());
+    if !has_command_modifier && !is_function_key {
+        return Err(format!("shortcut {chord} needs Ctrl, Alt, or Meta"));
+    }
+
+    let mut out: Vec<String> = MODIFIERS
+        .iter()
+        .zip(present)
+        .filter(|(_, on)| *on)
+        .map(|((name, _), _)| name.to_string())
+        .collect();
+    out.push(key);
+    Ok(out.join("+"))
+}
+
+fn describe(db: &Db, target: &ShortcutTarget) -> String {
+    match target {
+        ShortcutTarget::DockCommand { id } => match db.dock_commands_get(id) {
+            Ok(Some(cmd)) => format!("\"{}\"", cmd.title),
+            _ => "a dock command".to_string(),
+        },
+        ShortcutTarget::Action { action } => format!("action {action}"),
+    }
+}
+
+/// Validate and normalize a binding, rejecting chords already bound to something else.
+pub fn validate(db: &Db, chord: &str, target: &ShortcutTarget) -> Result<String, String> {
+    let chord = normalize_chord(chord)?;
+    if RESERVED.contains(&chord.as_str()) {
+        return Err(format!("{chord} is reserved for the terminal"));
+    }
+    match target {
+        ShortcutTarget::DockCommand { id } => {
+            if db.dock_commands_get(id).map_err(|e| e.to_string())?.is_none() {
+                return Err(format!("dock command not found: {id}"));
+            }
+        }
+        ShortcutTarget::Action { action } => {
+            if !ACTIONS.contains(&action.as_str()) {
+                return Err(format!("unknown shortcut action: {action}"));
+            }
+        }
+    }
+    let existing: Vec<Shortcut> = db.shortcuts_list().map_err(|e| e.to_string())?;
+    if let Some(other) = existing.iter().find(|s| s.chord == chord && &s.target != target) {
+        return Err(format!("{chord} is already bound to {}", describe(db, &other.target)));
+    }
+    // The OS would deliver the chord to the global hotkey, never to the window.
+    if let Some(action) = GlobalHotkeys::load(db).action_for(&chord) {
+        return Err(format!("{chord} is the global hotkey for {action}"));
+    }
+    Ok(chord)
+}
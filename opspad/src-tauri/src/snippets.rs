@@ -0,0 +1,149 @@
+//! Snippets: short triggers typed in a terminal (";k8slogs") that expand to longer text.
+//!
+//! Expansion is done here rather than in the UI so every window, and every machine the snippets
+//! reach through Git sync, expands a trigger the same way. A trigger is one whitespace-delimited
+//! word; when several snippets share it, the one scoped most narrowly to the session wins (the
+//! session's own scope, then its environment, then unscoped).
+//!
+//! Expansions may use `{name}` variables. `{host}` (label), `{hostname}`, `{user}`, `{port}` and
+//! `{env}` come from the session, `{date}` and `{time}` are UTC; any other name takes the value
+//! last used for it with a dock command in the same scope, and one with no value stays as
+//! `{name}` and is reported as missing.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::db::{Db, Snippet, SnippetInput};
+use crate::terminal::highlight;
+use crate::{template, timestamp};
+
+const MAX_TRIGGER_LEN: usize = 64;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetExpansion {
+    /// The input with each trigger replaced; unchanged when nothing matched.
+    pub text: String,
+    /// Triggers expanded, in order.
+    pub expanded: Vec<String>,
+    /// Variables left as `{name}`.
+    pub missing: Vec<String>,
+}
+
+pub fn validate(db: &Db, id: Option<&str>, input: &SnippetInput) -> Result<(), String> {
+    let trigger = input.trigger.trim();
+    if trigger.is_empty() {
+        return Err("trigger is required".to_string());
+    }
+    if trigger.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("a trigger is a single word, without spaces".to_string());
+    }
+    if trigger.chars().count() > MAX_TRIGGER_LEN {
+        return Err(format!("a trigger can be at most {MAX_TRIGGER_LEN} characters"));
+    }
+    if input.expansion.trim().is_empty() {
+        return Err("expansion is required".to_string());
+    }
+    highlight::validate_scope(input.scope.as_deref())?;
+    let scope = input.scope.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let taken = db
+        .snippets_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .any(|s| s.trigger == trigger && s.scope.as_deref() == scope && Some(s.id.as_str()) != id);
+    if taken {
+        return Err(format!("{trigger} is already a snippet in this scope"));
+    }
+    Ok(())
+}
+
+fn word_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\S+").expect("valid word regex"))
+}
+
+/// How narrowly a snippet's scope covers the session; `None` when it doesn't.
+fn specificity(snippet_scope: Option<&str>, scope: Option<&str>, environment_tag: &str) -> Option<u8> {
+    match snippet_scope {
+        None => Some(0),
+        Some(s) => match s.strip_prefix("env:") {
+            Some(tag) => tag.eq_ignore_ascii_case(environment_tag).then_some(1),
+            None => (Some(s) == scope).then_some(2),
+        },
+    }
+}
+
+/// Variable values for a session scope: remembered dock command values under the built-ins.
+fn variables(db: &Db, scope: Option<&str>, environment_tag: &str) -> Result<HashMap<String, String>, String> {
+    let Some(scope) = scope else {
+        return Ok(HashMap::new());
+    };
+    let mut values = db.dock_param_values_get(scope).map_err(|e| e.to_string())?;
+    let utc = timestamp::format_utc(timestamp::now_millis() / 1000);
+    values.insert("env".to_string(), environment_tag.to_string());
+    values.insert("date".to_string(), utc[..10].to_string());
+    values.insert("time".to_string(), utc[11..].to_string());
+    let host_id = scope.strip_prefix("ssh:");
+    if let Some(host) = host_id.and_then(|id| db.hosts_get(id).ok().flatten()) {
+        values.insert("host".to_string(), host.label);
+        values.insert("hostname".to_string(), host.hostname);
+        values.insert("user".to_string(), host.username);
+        values.insert("port".to_string(), host.port.to_string());
+    }
+    Ok(values)
+}
+
+/// Expand every trigger in `text` for a session in `scope` ("local", "ssh:<host_id>"; `None`
+/// outside a session, where only unscoped snippets apply).
+pub fn expand(db: &Db, text: &str, scope: Option<&str>) -> Result<SnippetExpansion, String> {
+    let scope = scope.map(str::trim).filter(|s| !s.is_empty());
+    let environment_tag = match scope {
+        Some(sc) => db.scope_context(sc).map_err(|e| e.to_string())?.environment_tag,
+        None => String::new(),
+    };
+    let mut best: HashMap<String, (u8, Snippet)> = HashMap::new();
+    for snippet in db.snippets_list().map_err(|e| e.to_string())? {
+        if !snippet.enabled {
+            continue;
+        }
+        let Some(rank) = specificity(snippet.scope.as_deref(), scope, &environment_tag) else {
+            continue;
+        };
+        if best.get(&snippet.trigger).is_none_or(|(r, _)| rank > *r) {
+            best.insert(snippet.trigger.clone(), (rank, snippet));
+        }
+    }
+
+    let mut out = SnippetExpansion {
+        text: String::with_capacity(text.len()),
+        expanded: Vec::new(),
+        missing: Vec::new(),
+    };
+    let mut values: Option<HashMap<String, String>> = None;
+    let mut last = 0;
+    for word in word_re().find_iter(text) {
+        let Some((_, snippet)) = best.get(word.as_str()) else {
+            continue;
+        };
+        let values = match &values {
+            Some(v) => v,
+            None => values.insert(variables(db, scope, &environment_tag)?),
+        };
+        let (expansion, missing) = template::render_partial(&snippet.expansion, &[], values)
+            .map_err(|e| format!("{}: {e}", snippet.trigger))?;
+        out.text.push_str(&text[last..word.start()]);
+        out.text.push_str(&expansion);
+        last = word.end();
+        out.expanded.push(snippet.trigger.clone());
+        for name in missing {
+            if !out.missing.contains(&name) {
+                out.missing.push(name);
+            }
+        }
+    }
+    out.text.push_str(&text[last..]);
+    Ok(out)
+}
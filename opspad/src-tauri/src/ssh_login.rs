@@ -0,0 +1,149 @@
+//! Answering ssh's login prompts with the credentials bound to a host.
+//!
+//! ssh reads passwords and key passphrases straight from the terminal, so we watch a new
+//! session's output for ssh's own prompt formats and type the bound secret, once per prompt kind.
+//! A verification code prompt is answered with the current code from the host's TOTP seed
+//! (`totp`), when one is stored. Anything else (host key confirmation, a repeat prompt after a
+//! wrong password) is left to the user, and so is every prompt when the vault is locked, the user declines verification, or no
+//! secret is stored: ssh simply keeps waiting for the user to type.
+
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::db::Host;
+use crate::{secrets, totp, AppState};
+
+/// ssh prompts right after connecting; don't keep typing into the session beyond that.
+const WATCH_FOR: Duration = Duration::from_secs(60);
+/// Enough trailing output to hold a whole prompt line split across reads.
+const TAIL_BYTES: usize = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Prompt {
+    Password,
+    Passphrase,
+    Otp,
+}
+
+fn password_re() -> &'static Regex {
+    // "user@host's password: " (password auth) and "(user@host) Password: " (keyboard-interactive).
+    // A bare "Password: " is deliberately not matched: `su` prints the same thing.
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^(?:[^\s@]+@\S+'s password|\([^\s@]+@\S+\) Password): $").expect("valid password prompt regex")
+    })
+}
+
+fn passphrase_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^Enter passphrase for key .+: $").expect("valid passphrase prompt regex"))
+}
+
+fn otp_re() -> &'static Regex {
+    // Keyboard-interactive 2FA, as PAM modules word it ("Verification code: " from
+    // google-authenticator, "One-time password (OATH) for `user': " from pam_oath, ...).
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)^(?:\([^\s@]+@\S+\) )?(?:verification code|one-time password|otp|authenticator code|token code)[^:]*: $")
+            .expect("valid otp prompt regex")
+    })
+}
+
+/// The prompt ssh is waiting at, judging by the last (unterminated) line of output.
+fn pending_prompt(tail: &str) -> Option<Prompt> {
+    let line = tail.rsplit(['\n', '\r']).next().unwrap_or(tail);
+    if password_re().is_match(line) {
+        Some(Prompt::Password)
+    } else if passphrase_re().is_match(line) {
+        Some(Prompt::Passphrase)
+    } else if otp_re().is_match(line) {
+        Some(Prompt::Otp)
+    } else {
+        None
+    }
+}
+
+fn vault_key(host: &Host, prompt: Prompt) -> Option<String> {
+    match prompt {
+        Prompt::Password => host.password_vault_key.clone(),
+        Prompt::Passphrase => Some(
+            host.passphrase_vault_key
+                .clone()
+                .unwrap_or_else(|| format!("host:{}:ssh_key_passphrase", host.id)),
+        ),
+        Prompt::Otp => Some(totp::host_key(&host.id)),
+    }
+}
+
+/// Look up the secret for a prompt; `None` means the user has to type it.
+fn secret_for(state: &AppState, host: &Host, prompt: Prompt) -> Option<String> {
+    let key = vault_key(host, prompt)?;
+    let bytes = secrets::get_verified(state.vault.as_ref(), &key).ok().flatten()?;
+    if prompt == Prompt::Otp {
+        return totp::code(&bytes).ok().map(|c| c.code);
+    }
+    String::from_utf8(bytes).ok().filter(|s| !s.is_empty())
+}
+
+/// The bound secret for a prompt that came through the askpass bridge (`askpass`), with the same
+/// rule as the terminal watcher: each kind is answered once, and a repeat is left to the user.
+pub(crate) fn answer_for(state: &AppState, host: &Host, prompt: &str, answered: &mut Vec<Prompt>) -> Option<String> {
+    let prompt = pending_prompt(prompt)?;
+    if answered.contains(&prompt) {
+        return None;
+    }
+    answered.push(prompt);
+    secret_for(state, host, prompt)
+}
+
+/// Start answering `session_id`'s login prompts with `host`'s bound credentials.
+pub fn spawn_autofill(state: Arc<AppState>, session_id: String, host: Host) -> Result<(), String> {
+    // Subscribe before ssh gets a chance to prompt.
+    let output = state.terminal.subscribe_output(&session_id).map_err(|e| e.to_string())?;
+    thread::spawn(move || watch(&state, &session_id, &host, &output));
+    Ok(())
+}
+
+fn watch(state: &AppState, session_id: &str, host: &Host, output: &Receiver<String>) {
+    let deadline = Instant::now() + WATCH_FOR;
+    let mut tail = String::new();
+    let mut answered: Vec<Prompt> = Vec::new();
+    loop {
+        let chunk = match output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(chunk) => chunk,
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return,
+        };
+        tail.push_str(&chunk);
+        if tail.len() > TAIL_BYTES {
+            let mut cut = tail.len() - TAIL_BYTES;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
+        }
+
+        match pending_prompt(&tail) {
+            Some(prompt) if answered.contains(&prompt) => {
+                // Asked again: the stored secret was wrong. Leave it to the user from here.
+                return;
+            }
+            Some(prompt) => {
+                answered.push(prompt);
+                tail.clear();
+                let Some(secret) = secret_for(state, host, prompt) else {
+                    continue;
+                };
+                if state.terminal.write(session_id, &format!("{secret}\r")).is_err() {
+                    return;
+                }
+            }
+            // Real output after we answered means the login went through.
+            None if !answered.is_empty() && !chunk.trim().is_empty() => return,
+            None => {}
+        }
+    }
+}
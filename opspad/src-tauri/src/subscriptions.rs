@@ -0,0 +1,438 @@
+//! Team configuration subscriptions: signed bundles of hosts, dock commands, and runbooks served
+//! from an HTTPS URL.
+//!
+//! A platform team publishes one bundle (JSON or YAML) signed with `config_bundle_sign`.
+//! Subscribers refresh it with `If-None-Match`, so an unchanged bundle costs a 304. The signer's
+//! fingerprint is pinned on the first refresh (or given up front) and bundles signed by anyone
+//! else are rejected. Bundle items are stored as read-only copies tagged with their subscription.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::vault::VaultProvider;
+use crate::db::{CommandVisibility, ConnectionType, Db, DnsExpansion, DnsSelection, DockCommand, Host, Runbook};
+use crate::packs::{self, PackSignature};
+use crate::template::{self, ParamSpec};
+use crate::AppState;
+
+const BUNDLE_FORMAT: &str = "opspad-config-bundle";
+/// Highest bundle format version this build understands.
+const FORMAT_VERSION: u32 = 1;
+const MAX_BUNDLE_BYTES: u64 = 8 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleHost {
+    id: String,
+    label: String,
+    hostname: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    username: String,
+    environment_tag: String,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    connection_type: ConnectionType,
+    #[serde(default)]
+    dns_expansion: DnsExpansion,
+    #[serde(default)]
+    dns_selection: DnsSelection,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleCommand {
+    id: String,
+    title: String,
+    command: String,
+    #[serde(default)]
+    requires_confirm: bool,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    params: Vec<ParamSpec>,
+    #[serde(default)]
+    visibility: CommandVisibility,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleRunbook {
+    id: String,
+    title: String,
+    markdown: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    params: Vec<ParamSpec>,
+}
+
+/// The signed part of a bundle. Field order is the canonical serialization order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleBody {
+    format: String,
+    format_version: u32,
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    hosts: Vec<BundleHost>,
+    #[serde(default)]
+    commands: Vec<BundleCommand>,
+    #[serde(default)]
+    runbooks: Vec<BundleRunbook>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    #[serde(flatten)]
+    body: BundleBody,
+    #[serde(default)]
+    signature: Option<PackSignature>,
+}
+
+fn canonical(body: &BundleBody) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(body).map_err(|e| e.to_string())
+}
+
+fn check_ids<'a>(kind: &str, ids: impl Iterator<Item = &'a str>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if id.trim().is_empty() {
+            return Err(format!("every {kind} needs an id"));
+        }
+        if !seen.insert(id) {
+            return Err(format!("duplicate {kind} id: {id}"));
+        }
+    }
+    Ok(())
+}
+
+/// Parse a JSON or YAML bundle and check it is well-formed (the signature is checked separately).
+fn parse(text: &str) -> Result<Bundle, String> {
+    let bundle: Bundle = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|e| format!("not a valid config bundle: {e}"))?
+    } else {
+        serde_yaml::from_str(text).map_err(|e| format!("not a valid config bundle: {e}"))?
+    };
+    let body = &bundle.body;
+    if body.format != BUNDLE_FORMAT {
+        return Err("not an OpsPad config bundle".to_string());
+    }
+    if body.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "bundle format version {} is newer than this OpsPad supports ({FORMAT_VERSION})",
+            body.format_version
+        ));
+    }
+    if body.name.trim().is_empty() {
+        return Err("bundle name must not be empty".to_string());
+    }
+    check_ids("host", body.hosts.iter().map(|h| h.id.as_str()))?;
+    check_ids("command", body.commands.iter().map(|c| c.id.as_str()))?;
+    check_ids("runbook", body.runbooks.iter().map(|r| r.id.as_str()))?;
+    for h in &body.hosts {
+        if h.label.trim().is_empty() || h.hostname.trim().is_empty() {
+            return Err(format!("host {}: label and hostname are required", h.id));
+        }
+    }
+    for c in &body.commands {
+        if c.title.trim().is_empty() || c.command.trim().is_empty() {
+            return Err(format!("command {}: title and command are required", c.id));
+        }
+        template::validate_schema(&c.command, &c.params).map_err(|e| format!("{}: {e}", c.title))?;
+    }
+    for r in &body.runbooks {
+        if r.title.trim().is_empty() {
+            return Err(format!("runbook {}: title is required", r.id));
+        }
+        template::validate_schema(&r.markdown, &r.params).map_err(|e| format!("{}: {e}", r.title))?;
+    }
+    Ok(bundle)
+}
+
+/// Sign a bundle with this installation's key so it can be published. Returns signed JSON.
+pub fn sign(vault: &dyn VaultProvider, text: &str) -> Result<String, String> {
+    let bundle = parse(text)?;
+    let signature = packs::sign(vault, &canonical(&bundle.body)?)?;
+    serde_json::to_string_pretty(&Bundle {
+        body: bundle.body,
+        signature: Some(signature),
+    })
+    .map_err(|e| e.to_string())
+}
+
+enum Fetched {
+    NotModified,
+    Bundle { text: String, etag: Option<String> },
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    let lower = url.trim().to_ascii_lowercase();
+    let loopback = ["http://localhost", "http://127.0.0.1", "http://[::1]"]
+        .iter()
+        .any(|p| lower.starts_with(p));
+    if !lower.starts_with("https://") && !loopback {
+        return Err("subscriptions must use an https:// URL".to_string());
+    }
+    Ok(())
+}
+
+fn fetch(url: &str, etag: Option<&str>) -> Result<Fetched, String> {
+    let agent = ureq::AgentBuilder::new().timeout(FETCH_TIMEOUT).build();
+    let mut req = agent
+        .get(url)
+        .set("Accept", "application/json, application/yaml;q=0.9, */*;q=0.5");
+    if let Some(etag) = etag {
+        req = req.set("If-None-Match", etag);
+    }
+    let resp = match req.call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(code, _)) => return Err(format!("{url}: HTTP {code}")),
+        Err(e) => return Err(format!("{url}: {e}")),
+    };
+    if resp.status() == 304 {
+        return Ok(Fetched::NotModified);
+    }
+    let etag = resp.header("ETag").map(str::to_string);
+    let mut text = String::new();
+    resp.into_reader()
+        .take(MAX_BUNDLE_BYTES + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| format!("{url}: {e}"))?;
+    if text.len() as u64 > MAX_BUNDLE_BYTES {
+        return Err(format!("{url}: bundle is larger than {} MiB", MAX_BUNDLE_BYTES / (1024 * 1024)));
+    }
+    Ok(Fetched::Bundle { text, etag })
+}
+
+fn refresh_inner(db: &Db, id: &str) -> Result<bool, String> {
+    let sub = db
+        .subscriptions_get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "subscription not found".to_string())?;
+    let etag = db.subscriptions_etag(id).map_err(|e| e.to_string())?;
+    let (text, etag) = match fetch(&sub.url, etag.as_deref())? {
+        Fetched::NotModified => {
+            db.subscriptions_mark_checked(id, None).map_err(|e| e.to_string())?;
+            return Ok(false);
+        }
+        Fetched::Bundle { text, etag } => (text, etag),
+    };
+
+    let Bundle { body, signature } = parse(&text)?;
+    let sig = signature.ok_or_else(|| "bundle is not signed; publish it signed with OpsPad".to_string())?;
+    let key = packs::verify(&sig, &canonical(&body)?).map_err(|e| format!("bundle {e}"))?;
+    let signer = packs::fingerprint(&key);
+    if let Some(pinned) = sub.signer.as_deref().filter(|p| !p.eq_ignore_ascii_case(&signer)) {
+        return Err(format!("bundle is signed by {signer}, but this subscription trusts {pinned}"));
+    }
+
+    // Namespace ids so bundles can't collide with local items or with each other.
+    let local_id = |item: &str| format!("{id}:{item}");
+    let host_ids: HashSet<String> = body.hosts.iter().map(|h| h.id.clone()).collect();
+    let hosts: Vec<Host> = body
+        .hosts
+        .iter()
+        .cloned()
+        .map(|h| Host {
+            id: local_id(&h.id),
+            label: h.label,
+            hostname: h.hostname,
+            port: h.port,
+            username: h.username,
+            environment_tag: h.environment_tag.to_uppercase(),
+            identity_file: None,
+            color: h.color,
+            group_id: None,
+            subscription_id: Some(id.to_string()),
+            password_vault_key: None,
+            passphrase_vault_key: None,
+            pinned: false,
+            last_connected_at: None,
+            connection_type: h.connection_type,
+            identity_agent: None,
+            security_key_provider: None,
+            dns_expansion: h.dns_expansion,
+            dns_selection: h.dns_selection,
+            expires_at: None,
+            archived_at: None,
+            server_info: None,
+            effective_color: None,
+        })
+        .collect();
+    let commands: Vec<DockCommand> = body
+        .commands
+        .iter()
+        .cloned()
+        .map(|c| {
+            let mut visibility = c.visibility;
+            // Visibility may name the bundle's own hosts; point those at the local copies.
+            for h in visibility.host_ids.iter_mut() {
+                if host_ids.contains(h) {
+                    *h = local_id(h);
+                }
+            }
+            DockCommand {
+                id: local_id(&c.id),
+                title: c.title,
+                command: c.command,
+                requires_confirm: c.requires_confirm,
+                color: c.color,
+                params: c.params,
+                visibility,
+                category: c.category,
+                run_count: 0,
+                last_run_at: None,
+                shortcut: None,
+                subscription_id: Some(id.to_string()),
+                effective_color: None,
+                timeout_secs: None,
+                timeout_escalate: false,
+            }
+        })
+        .collect();
+    let runbooks: Vec<Runbook> = body
+        .runbooks
+        .iter()
+        .cloned()
+        .map(|r| Runbook {
+            id: local_id(&r.id),
+            title: r.title,
+            markdown: r.markdown,
+            tags: r.tags,
+            params: r.params,
+            created_at: 0,
+            updated_at: 0,
+            file_name: None,
+            sync_conflict: false,
+            synced_hash: None,
+            subscription_id: Some(id.to_string()),
+        })
+        .collect();
+
+    db.subscriptions_apply(
+        id,
+        &body.name,
+        &body.version,
+        &signer,
+        etag.as_deref(),
+        &hosts,
+        &commands,
+        &runbooks,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Fetch the subscription's bundle and, if it changed, replace its items. Returns whether it
+/// changed. Failures are recorded on the subscription and leave its items as they were.
+pub fn refresh(db: &Db, id: &str) -> Result<bool, String> {
+    let result = refresh_inner(db, id);
+    if let Err(e) = &result {
+        let _ = db.subscriptions_mark_checked(id, Some(e));
+    }
+    result
+}
+
+/// Subscribe to `url`. The first fetch must succeed (and match `signer`, if given) or nothing is
+/// saved.
+pub fn add(db: &Db, url: &str, signer: Option<&str>) -> Result<crate::db::Subscription, String> {
+    let url = url.trim();
+    check_url(url)?;
+    if db
+        .subscriptions_list()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .any(|s| s.url == url)
+    {
+        return Err("already subscribed to this URL".to_string());
+    }
+    let signer = signer.map(str::trim).filter(|s| !s.is_empty());
+    let sub = db.subscriptions_create(url, signer).map_err(|e| e.to_string())?;
+    if let Err(e) = refresh_inner(db, &sub.id) {
+        let _ = db.subscriptions_delete(&sub.id);
+        return Err(e);
+    }
+    db.subscriptions_get(&sub.id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "subscription not found".to_string())
+}
+
+fn ensure_local(db: &Db, subscription_id: Option<&str>, what: &str) -> Result<(), String> {
+    let Some(sub_id) = subscription_id else {
+        return Ok(());
+    };
+    let name = db
+        .subscriptions_get(sub_id)
+        .ok()
+        .flatten()
+        .map(|s| s.name.unwrap_or(s.url))
+        .unwrap_or_else(|| "a team subscription".to_string());
+    Err(format!("\"{what}\" comes from {name} and is read-only"))
+}
+
+/// Refuse edits to a host that comes from a subscription.
+pub fn ensure_host_local(db: &Db, id: &str) -> Result<(), String> {
+    match db.hosts_get(id).map_err(|e| e.to_string())? {
+        Some(h) => ensure_local(db, h.subscription_id.as_deref(), &h.label),
+        None => Ok(()),
+    }
+}
+
+/// Refuse edits to a dock command that comes from a subscription.
+pub fn ensure_command_local(db: &Db, id: &str) -> Result<(), String> {
+    match db.dock_commands_get(id).map_err(|e| e.to_string())? {
+        Some(c) => ensure_local(db, c.subscription_id.as_deref(), &c.title),
+        None => Ok(()),
+    }
+}
+
+/// Refuse edits to a runbook that comes from a subscription.
+pub fn ensure_runbook_local(db: &Db, id: &str) -> Result<(), String> {
+    match db.runbooks_get(id).map_err(|e| e.to_string())? {
+        Some(rb) => ensure_local(db, rb.subscription_id.as_deref(), &rb.title),
+        None => Ok(()),
+    }
+}
+
+/// Refresh every subscription periodically; emits `subscriptions:refreshed` when items change.
+pub fn spawn_poller(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(REFRESH_INTERVAL);
+        let state = app.state::<Arc<AppState>>();
+        let Ok(subs) = state.db.subscriptions_list() else {
+            continue;
+        };
+        let changed = subs
+            .iter()
+            .filter(|s| match refresh(&state.db, &s.id) {
+                Ok(changed) => changed,
+                Err(e) => {
+                    log::warn!("subscription {} refresh failed: {e}", s.url);
+                    false
+                }
+            })
+            .count();
+        if changed > 0 {
+            let _ = app.emit("subscriptions:refreshed", changed);
+        }
+    });
+}
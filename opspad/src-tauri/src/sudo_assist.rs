@@ -0,0 +1,177 @@
+//! Spotting sudo's password prompt in a session, and optionally answering it from the vault.
+//!
+//! Every session's output is watched for sudo's prompt (`[sudo] password for alice:`). Each one is
+//! announced as `terminal:sudo_prompt`, with the vault key that would answer it, so the UI can
+//! offer a one-click answer through `terminal_type_secret`. With `autoRespond` on (settings key
+//! `sudo_assist`) the backend types the secret itself, the same backend-only way. If sudo asks
+//! again right after an automatic answer, the stored password was wrong: the session stops being
+//! answered automatically and the prompt is left to the user.
+
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Db;
+use crate::{secrets, AppState};
+
+const SETTINGS_KEY: &str = "sudo_assist";
+/// Enough trailing output to hold a whole prompt line split across reads.
+const TAIL_BYTES: usize = 512;
+/// A prompt this soon after an automatic answer means the answer was rejected.
+const REJECTED_WITHIN: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SudoAssistConfig {
+    /// Type the password without asking. Off by default.
+    #[serde(default)]
+    pub auto_respond: bool,
+    /// For ssh sessions without a `host:<id>:sudo_password` secret, try the host's bound login
+    /// password.
+    #[serde(default)]
+    pub use_login_password: bool,
+    /// Last resort for any session, including local ones.
+    #[serde(default)]
+    pub vault_key: Option<String>,
+}
+
+impl SudoAssistConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SudoPromptEvent {
+    pub session_id: String,
+    /// The account sudo wants the password of.
+    pub user: Option<String>,
+    /// Vault key holding the answer, if one is stored.
+    pub vault_key: Option<String>,
+    pub auto_answered: bool,
+    /// Why it wasn't answered automatically while auto-respond is on.
+    pub note: Option<String>,
+}
+
+fn prompt_re() -> &'static Regex {
+    // sudo ("[sudo] password for alice: ") and sudo-rs ("[sudo: authenticate] Password: ").
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\[sudo\] password for ([^:\s]+): ?$|^\[sudo: authenticate\] Password: ?$")
+            .expect("valid sudo prompt regex")
+    })
+}
+
+/// The sudo prompt the session is waiting at, judging by the last (unterminated) line of output:
+/// `Some(user)` for sudo's own prompt (`user` may be unknown).
+fn pending_prompt(tail: &str) -> Option<Option<String>> {
+    let line = tail.rsplit(['\n', '\r']).next().unwrap_or(tail);
+    let caps = prompt_re().captures(line)?;
+    Some(caps.get(1).map(|m| m.as_str().to_string()))
+}
+
+/// The first candidate vault key that holds a secret.
+fn answer_key(state: &AppState, session_id: &str, config: &SudoAssistConfig) -> Option<String> {
+    let scope = state.db.terminal_session_scope_get(session_id).ok().flatten();
+    let host = match scope.as_deref().and_then(|s| s.strip_prefix("ssh:")) {
+        Some(id) => state.db.hosts_get(id).ok().flatten(),
+        None => None,
+    };
+    let mut keys = Vec::new();
+    if let Some(host) = &host {
+        keys.push(format!("host:{}:sudo_password", host.id));
+        if config.use_login_password {
+            keys.extend(host.password_vault_key.clone());
+        }
+    }
+    keys.extend(config.vault_key.clone().filter(|k| !k.trim().is_empty()));
+    keys.into_iter().find(|key| state.vault.metadata(key).ok().flatten().is_some())
+}
+
+fn answer(state: &AppState, session_id: &str, key: &str) -> Result<(), String> {
+    let secret = secrets::get_verified(state.vault.as_ref(), key)?.ok_or_else(|| "secret is gone".to_string())?;
+    let secret = String::from_utf8(secret).map_err(|_| "secret isn't text".to_string())?;
+    state
+        .terminal
+        .write_secret(session_id, secret.trim_end_matches(['\r', '\n']))
+        .map_err(|e| e.to_string())
+}
+
+/// Watch `session_id` for sudo prompts for as long as it lives.
+pub fn spawn_watch(app: &AppHandle, state: &AppState, session_id: &str) -> Result<(), String> {
+    let output = state.terminal.subscribe_output(session_id).map_err(|e| e.to_string())?;
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    thread::spawn(move || {
+        if let Some(state) = app.try_state::<Arc<AppState>>().map(|s| s.inner().clone()) {
+            watch(&app, &state, &session_id, &output);
+        }
+    });
+    Ok(())
+}
+
+fn watch(app: &AppHandle, state: &AppState, session_id: &str, output: &Receiver<String>) {
+    let mut tail = String::new();
+    let mut answered_at: Option<Instant> = None;
+    let mut auto_disabled = false;
+    while let Ok(chunk) = output.recv() {
+        tail.push_str(&chunk);
+        if tail.len() > TAIL_BYTES {
+            let mut cut = tail.len() - TAIL_BYTES;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
+        }
+        let Some(user) = pending_prompt(&tail) else {
+            continue;
+        };
+        tail.clear();
+
+        let config = SudoAssistConfig::load(&state.db);
+        let vault_key = answer_key(state, session_id, &config);
+        if answered_at.is_some_and(|at| at.elapsed() < REJECTED_WITHIN) {
+            auto_disabled = true;
+        }
+        let (auto_answered, note) = match (&vault_key, config.auto_respond, auto_disabled) {
+            (_, false, _) => (false, None),
+            (_, true, true) => (false, Some("the stored password was rejected; type it yourself")),
+            (None, true, false) => (false, Some("no sudo password is stored for this session")),
+            (Some(key), true, false) => match answer(state, session_id, key) {
+                Ok(()) => {
+                    answered_at = Some(Instant::now());
+                    (true, None)
+                }
+                Err(e) => {
+                    log::warn!("sudo prompt in {session_id} not answered: {e}");
+                    (false, Some("the stored password couldn't be read or typed"))
+                }
+            },
+        };
+        let _ = app.emit(
+            "terminal:sudo_prompt",
+            SudoPromptEvent {
+                session_id: session_id.to_string(),
+                user,
+                vault_key,
+                auto_answered,
+                note: note.map(str::to_string),
+            },
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! `{name}` placeholder templates used by CommandDock commands.
+//!
+//! Placeholders are validated against an optional per-command parameter schema and interpolated
+//! server-side, so what gets written to a terminal never contains half-substituted braces.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParamType {
+    #[default]
+    String,
+    Integer,
+    Boolean,
+    Enum,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamSpec {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub param_type: ParamType,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Sensitive values are never remembered per scope.
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+fn placeholder_re() -> &'static Regex {
+    // Keep in sync with the frontend's `extractParams`.
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{([a-zA-Z0-9_:-]+)\}").expect("valid placeholder regex"))
+}
+
+/// Unique placeholder names in order of first appearance.
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for cap in placeholder_re().captures_iter(template) {
+        let name = &cap[1];
+        if !out.iter().any(|n| n == name) {
+            out.push(name.to_string());
+        }
+    }
+    out
+}
+
+/// Validate a parameter schema before it is persisted alongside `template`.
+pub fn validate_schema(template: &str, specs: &[ParamSpec]) -> Result<(), String> {
+    let names = placeholders(template);
+    let mut seen: Vec<&str> = Vec::new();
+    for spec in specs {
+        if !names.iter().any(|n| n == &spec.name) {
+            return Err(format!("parameter '{}' does not appear in the command template", spec.name));
+        }
+        if seen.contains(&spec.name.as_str()) {
+            return Err(format!("parameter '{}' is declared more than once", spec.name));
+        }
+        seen.push(&spec.name);
+        if spec.param_type == ParamType::Enum && spec.allowed_values.is_empty() {
+            return Err(format!("enum parameter '{}' needs at least one allowed value", spec.name));
+        }
+        if let Some(d) = spec.default.as_deref() {
+            check_value(spec, d).map_err(|e| format!("default for '{}': {e}", spec.name))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_value(spec: &ParamSpec, value: &str) -> Result<(), String> {
+    if value.chars().any(char::is_control) {
+        return Err("control characters (including newlines) are not allowed".to_string());
+    }
+    match spec.param_type {
+        ParamType::String | ParamType::Enum => {}
+        ParamType::Integer => {
+            value
+                .parse::<i64>()
+                .map_err(|_| "expected an integer".to_string())?;
+        }
+        ParamType::Boolean => {
+            if value != "true" && value != "false" {
+                return Err("expected true or false".to_string());
+            }
+        }
+    }
+    if !spec.allowed_values.is_empty() && !spec.allowed_values.iter().any(|v| v == value) {
+        return Err(format!("must be one of: {}", spec.allowed_values.join(", ")));
+    }
+    Ok(())
+}
+
+/// Interpolate `values` into `template`, validating each placeholder against its spec.
+///
+/// Placeholders without a spec are treated as required free-form strings. Empty values count as
+/// missing so the declared default (if any) applies. All problems are reported at once.
+pub fn render(template: &str, specs: &[ParamSpec], values: &HashMap<String, String>) -> Result<String, String> {
+    interpolate(template, specs, values, false).map(|(rendered, _)| rendered)
+}
+
+/// Like [`render`], but placeholders still missing a value are left in place (and returned)
+/// instead of failing. Used for runbooks, which stay readable while partly filled in.
+pub fn render_partial(
+    template: &str,
+    specs: &[ParamSpec],
+    values: &HashMap<String, String>,
+) -> Result<(String, Vec<String>), String> {
+    interpolate(template, specs, values, true)
+}
+
+fn interpolate(
+    template: &str,
+    specs: &[ParamSpec],
+    values: &HashMap<String, String>,
+    keep_missing: bool,
+) -> Result<(String, Vec<String>), String> {
+    let mut resolved: HashMap<String, String> = HashMap::new();
+    let mut missing: Vec<String> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for name in placeholders(template) {
+        let fallback = ParamSpec {
+            name: name.clone(),
+            param_type: ParamType::String,
+            default: None,
+            allowed_values: Vec::new(),
+            required: true,
+            sensitive: false,
+        };
+        let spec = specs.iter().find(|s| s.name == name).unwrap_or(&fallback);
+
+        let value = values
+            .get(&name)
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .or_else(|| spec.default.clone());
+        let value = match value {
+            Some(v) => v,
+            None if spec.required && keep_missing => {
+                missing.push(name);
+                continue;
+            }
+            None if spec.required => {
+                errors.push(format!("{name}: a value is required"));
+                continue;
+            }
+            None => String::new(),
+        };
+        if !value.is_empty() {
+            if let Err(e) = check_value(spec, &value) {
+                errors.push(format!("{name}: {e}"));
+                continue;
+            }
+        }
+        resolved.insert(name, value);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let rendered = placeholder_re()
+        .replace_all(template, |cap: &regex::Captures<'_>| {
+            resolved
+                .get(&cap[1])
+                .cloned()
+                .unwrap_or_else(|| cap[0].to_string())
+        })
+        .into_owned();
+    Ok((rendered, missing))
+}
@@ -0,0 +1,50 @@
+//! The warning written at the top of sessions in environments that ask for it
+//! ([`Environment::banner`]): a speed bump before working on production.
+//!
+//! It's session output as far as the UI is concerned (and kept in the transcript), but nothing is
+//! sent to the shell.
+
+use crate::db::{DestructivePolicy, Environment};
+
+const RED: &str = "\x1b[1;31m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+/// Longer banner text is cut.
+const MAX_TEXT_CHARS: usize = 200;
+
+/// "WARNING" in 5-row block letters.
+const ART: [&str; 5] = [
+    "#   #   ###   ####   #   #  #####  #   #   ####",
+    "#   #  #   #  #   #  ##  #    #    ##  #  #    ",
+    "# # #  #####  ####   # # #    #    # # #  #  ##",
+    "## ##  #   #  #  #   #  ##    #    #  ##  #   #",
+    "#   #  #   #  #   #  #   #  #####  #   #   ####",
+];
+
+fn policy_line(policy: DestructivePolicy) -> &'static str {
+    match policy {
+        DestructivePolicy::Allow => "Destructive commands are allowed.",
+        DestructivePolicy::Confirm => "Destructive commands need confirming.",
+        DestructivePolicy::TypedName => "Destructive commands need the environment name typed.",
+        DestructivePolicy::Reason => "Destructive commands need a reason, kept in history.",
+        DestructivePolicy::Block => "Destructive commands are blocked.",
+    }
+}
+
+/// The banner for a session in `env`; `target` says where it's connected ("user@host:22",
+/// "local shell").
+pub fn render(env: &Environment, target: &str) -> String {
+    let mut lines: Vec<String> = ART.iter().map(|row| format!("{RED}  {row}{RESET}")).collect();
+    lines.push(String::new());
+    lines.push(format!("{RED}  {} environment{RESET} {BOLD}{target}{RESET}", env.name));
+    lines.push(format!("  {}", policy_line(env.destructive_policy)));
+    if let Some(text) = env.banner_text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        // The text is shown as-is: no escape sequences of its own.
+        let text: String = text.chars().filter(|c| !c.is_control()).take(MAX_TEXT_CHARS).collect();
+        lines.push(format!("  {text}"));
+    }
+    lines.push(String::new());
+    let mut banner = lines.join("\r\n");
+    banner.push_str("\r\n");
+    banner
+}
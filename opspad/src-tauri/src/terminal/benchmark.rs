@@ -0,0 +1,127 @@
+//! Throughput benchmark for the PTY pipeline (`terminal_benchmark`, not shown in the UI).
+//!
+//! A synthetic process prints fixed-width lines as fast as it can through a real PTY session, so
+//! its output takes the same path as a shell's: read loop, masking, transcript, highlighting and
+//! `terminal:data` events. Bytes and events are counted from an output tap. The clock starts at
+//! the first byte, since the process waits a moment before printing so the tap is in place.
+
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::terminal::perf::{self, PerfMetrics};
+use crate::terminal::session_manager::{SpawnSpec, TerminalKind};
+use crate::terminal::TerminalManager;
+
+const DEFAULT_BYTES: u64 = 64 * 1024 * 1024;
+const MAX_BYTES: u64 = 1024 * 1024 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(120);
+/// Each line is this many characters plus a newline.
+const LINE_WIDTH: u64 = 79;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub bytes_requested: u64,
+    /// More than requested: the PTY turns each `\n` into `\r\n`.
+    pub bytes_received: u64,
+    /// `terminal:data` events (one per PTY read).
+    pub events: u64,
+    /// From spawning to the first byte.
+    pub first_byte_ms: u64,
+    /// From the first byte to the process exiting (or the timeout).
+    pub elapsed_ms: u64,
+    pub bytes_per_sec: u64,
+    pub events_per_sec: u64,
+    pub mean_event_bytes: u64,
+    pub timed_out: bool,
+    /// The pipeline counters after the run (`perf_metrics`); they include any other sessions'
+    /// traffic during it.
+    pub pipeline: PerfMetrics,
+}
+
+/// The program printing `lines` lines of `LINE_WIDTH` characters after a short pause.
+#[cfg(not(windows))]
+fn generator(lines: u64) -> (String, Vec<String>) {
+    let line = "x".repeat(LINE_WIDTH as usize);
+    (
+        "sh".to_string(),
+        vec!["-c".to_string(), format!("sleep 0.5; yes {line} | head -n {lines}")],
+    )
+}
+
+#[cfg(windows)]
+fn generator(lines: u64) -> (String, Vec<String>) {
+    let script = format!(
+        "Start-Sleep -Milliseconds 500; $l = 'x' * {LINE_WIDTH}; $o = [Console]::Out; for ($i = 0; $i -lt {lines}; $i++) {{ $o.WriteLine($l) }}"
+    );
+    (
+        "powershell.exe".to_string(),
+        vec!["-NoProfile".to_string(), "-NonInteractive".to_string(), "-Command".to_string(), script],
+    )
+}
+
+pub fn run(terminal: &TerminalManager, app: AppHandle, bytes: Option<u64>) -> Result<BenchmarkReport, String> {
+    let bytes_requested = bytes.unwrap_or(DEFAULT_BYTES).clamp(LINE_WIDTH + 1, MAX_BYTES);
+    let (program, args) = generator(bytes_requested / (LINE_WIDTH + 1));
+    let spawned = Instant::now();
+    let session_id = terminal
+        .spawn_process(
+            app,
+            SpawnSpec {
+                kind: TerminalKind::Local,
+                environment_tag: "LOCAL".to_string(),
+                initial_cols: Some(LINE_WIDTH as u16 + 1),
+                initial_rows: Some(24),
+                program,
+                args,
+                banner: None,
+                env: Vec::new(),
+                quiet: true,
+            },
+        )
+        .map_err(|e| e.to_string())?
+        .0;
+    let output = match terminal.subscribe_output(&session_id) {
+        Ok(rx) => rx,
+        Err(e) => {
+            let _ = terminal.close(&session_id);
+            return Err(e.to_string());
+        }
+    };
+
+    let deadline = spawned + TIMEOUT;
+    let mut first_byte: Option<Instant> = None;
+    let (mut bytes_received, mut events) = (0u64, 0u64);
+    let timed_out = loop {
+        match output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(chunk) => {
+                first_byte.get_or_insert_with(Instant::now);
+                bytes_received += chunk.len() as u64;
+                events += 1;
+            }
+            Err(RecvTimeoutError::Disconnected) => break false,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = terminal.close(&session_id);
+                break true;
+            }
+        }
+    };
+
+    let first_byte = first_byte.ok_or_else(|| "the benchmark process printed nothing".to_string())?;
+    let elapsed_ms = first_byte.elapsed().as_millis().max(1) as u64;
+    Ok(BenchmarkReport {
+        bytes_requested,
+        bytes_received,
+        events,
+        first_byte_ms: first_byte.duration_since(spawned).as_millis() as u64,
+        elapsed_ms,
+        bytes_per_sec: bytes_received * 1000 / elapsed_ms,
+        events_per_sec: events * 1000 / elapsed_ms,
+        mean_event_bytes: bytes_received.checked_div(events).unwrap_or(0),
+        timed_out,
+        pipeline: perf::snapshot(),
+    })
+}
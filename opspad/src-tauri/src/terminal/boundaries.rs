@@ -0,0 +1,178 @@
+//! Command boundaries from shell-integration marks (see `prompt_marks.rs`).
+//!
+//! Each session's output is followed from prompt to prompt: the text between the command-start
+//! and output-start marks is the command line as the shell echoed it, and the end mark carries its
+//! exit status. Finished commands are kept (the last [`MAX_COMMANDS`]) with where in the output
+//! their prompt and output began, for jumping between commands and lining them up with history.
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::terminal::prompt_marks::{PromptMark, PromptMarkParser, Segment};
+
+const MAX_COMMANDS: usize = 500;
+/// Command lines longer than this are cut.
+const MAX_COMMAND_CHARS: usize = 2000;
+/// Echo collected while looking for the command line.
+const MAX_TYPED_BYTES: usize = 16 * 1024;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandRecord {
+    /// Counts up from 1 within the session.
+    pub id: u64,
+    /// As echoed by the shell; `None` when the shell doesn't mark where the command line starts.
+    pub command: Option<String>,
+    /// Epoch ms.
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub exit_status: Option<i32>,
+    /// Bytes of output (not counting marks) before the command's prompt, and before its output
+    /// (for shells that don't mark where output starts, before the next prompt).
+    pub prompt_offset: u64,
+    pub output_offset: u64,
+    /// The CommandDock history entry that sent it.
+    pub history_id: Option<String>,
+}
+
+pub enum Boundary {
+    Started(CommandRecord),
+    Ended(CommandRecord),
+}
+
+enum Phase {
+    Idle,
+    /// After the prompt mark; `typed` collects the command line once the shell marks its start.
+    Prompt { prompt_offset: u64, typed: Option<String> },
+    Running(CommandRecord),
+}
+
+pub struct CommandTracker {
+    parser: PromptMarkParser,
+    phase: Phase,
+    next_id: u64,
+    offset: u64,
+    /// History entry for the next command to start.
+    tagged: Option<String>,
+    log: VecDeque<CommandRecord>,
+    /// A mark has been seen.
+    pub integrated: bool,
+}
+
+impl Default for CommandTracker {
+    fn default() -> Self {
+        Self {
+            parser: PromptMarkParser::default(),
+            phase: Phase::Idle,
+            next_id: 1,
+            offset: 0,
+            tagged: None,
+            log: VecDeque::new(),
+            integrated: false,
+        }
+    }
+}
+
+/// The first line of what the shell echoed after the command-start mark. Without an
+/// output-start mark that's followed by the command's output, hence only the first line.
+fn command_line(typed: Option<String>) -> Option<String> {
+    let text = crate::transcript::clean(&typed?);
+    let mut line = text.lines().map(str::trim).find(|l| !l.is_empty())?.to_string();
+    if let Some((cut, _)) = line.char_indices().nth(MAX_COMMAND_CHARS) {
+        line.truncate(cut);
+    }
+    Some(line)
+}
+
+impl CommandTracker {
+    /// The next command to start was sent for this CommandDock history entry.
+    pub fn tag_next(&mut self, history_id: String) {
+        self.tagged = Some(history_id);
+    }
+
+    /// Finished commands, oldest first.
+    pub fn commands(&self) -> Vec<CommandRecord> {
+        self.log.iter().cloned().collect()
+    }
+
+    fn start(&mut self, prompt_offset: u64, typed: Option<String>, now: i64) -> CommandRecord {
+        let record = CommandRecord {
+            id: self.next_id,
+            command: command_line(typed),
+            started_at: now,
+            ended_at: None,
+            exit_status: None,
+            prompt_offset,
+            output_offset: self.offset,
+            history_id: self.tagged.take(),
+        };
+        self.next_id += 1;
+        record
+    }
+
+    fn finish(&mut self, mut record: CommandRecord, exit: Option<i32>, now: i64) -> CommandRecord {
+        record.ended_at = Some(now);
+        record.exit_status = exit;
+        if self.log.len() >= MAX_COMMANDS {
+            self.log.pop_front();
+        }
+        self.log.push_back(record.clone());
+        record
+    }
+
+    pub fn feed(&mut self, chunk: &str) -> Vec<Boundary> {
+        let now = crate::timestamp::now_millis();
+        let mut out = Vec::new();
+        for segment in self.parser.feed_segments(chunk) {
+            let mark = match segment {
+                Segment::Text(text) => {
+                    self.offset += text.len() as u64;
+                    if let Phase::Prompt { typed: Some(typed), .. } = &mut self.phase {
+                        if typed.len() < MAX_TYPED_BYTES {
+                            typed.push_str(&text);
+                        }
+                    }
+                    continue;
+                }
+                Segment::Mark(mark) => mark,
+            };
+            self.integrated = true;
+            match (std::mem::replace(&mut self.phase, Phase::Idle), mark) {
+                // A new prompt while one is running: the shell didn't report its end.
+                (Phase::Running(record), PromptMark::PromptStart { .. }) => {
+                    out.push(Boundary::Ended(self.finish(record, None, now)));
+                    self.phase = Phase::Prompt { prompt_offset: self.offset, typed: None };
+                }
+                (_, PromptMark::PromptStart { .. }) => {
+                    self.phase = Phase::Prompt { prompt_offset: self.offset, typed: None };
+                }
+                (Phase::Prompt { prompt_offset, .. }, PromptMark::CommandStart) => {
+                    self.phase = Phase::Prompt {
+                        prompt_offset,
+                        typed: Some(String::new()),
+                    };
+                }
+                (Phase::Prompt { prompt_offset, typed }, PromptMark::OutputStart) => {
+                    let record = self.start(prompt_offset, typed, now);
+                    out.push(Boundary::Started(record.clone()));
+                    self.phase = Phase::Running(record);
+                }
+                (Phase::Running(record), PromptMark::CommandEnd { exit }) => {
+                    out.push(Boundary::Ended(self.finish(record, exit, now)));
+                }
+                // No output-start mark (PowerShell): the command ran between the prompt and now.
+                // An empty line is just Enter at the prompt.
+                (Phase::Prompt { prompt_offset, typed }, PromptMark::CommandEnd { exit }) => {
+                    if command_line(typed.clone()).is_some() {
+                        let record = self.start(prompt_offset, typed, now);
+                        out.push(Boundary::Started(record.clone()));
+                        out.push(Boundary::Ended(self.finish(record, exit, now)));
+                    }
+                }
+                (phase, _) => self.phase = phase,
+            }
+        }
+        out
+    }
+}
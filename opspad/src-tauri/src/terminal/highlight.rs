@@ -0,0 +1,117 @@
+//! Highlight rules applied to session output before it's emitted.
+//!
+//! Each `terminal:data` event carries the spans the session's rules matched in it, so the UI
+//! marks errors, addresses and ids the same way in every session without re-running the rules.
+//! Matching is per read: a match split across two reads, or interrupted by an escape sequence, is
+//! missed.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::db::{HighlightRule, HighlightSeverity};
+
+/// Spans beyond this in one read are dropped (a rule matching every character, say).
+const MAX_SPANS: usize = 256;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightSpan {
+    /// UTF-16 offsets into the event's `data`, as JavaScript indexes strings.
+    pub start: usize,
+    pub end: usize,
+    pub rule_id: String,
+    pub severity: HighlightSeverity,
+    pub color: Option<String>,
+    pub annotation: Option<String>,
+}
+
+struct CompiledRule {
+    re: Regex,
+    rule: HighlightRule,
+}
+
+/// The rules that apply to one session, compiled.
+pub struct Highlighter {
+    rules: Vec<CompiledRule>,
+}
+
+pub fn validate_rule(pattern: &str, scope: Option<&str>, color: Option<&str>) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("pattern is required".to_string());
+    }
+    Regex::new(pattern).map_err(|e| format!("invalid pattern: {e}"))?;
+    validate_scope(scope)?;
+    if color.is_some_and(|c| c.len() > 64) {
+        return Err("color is too long".to_string());
+    }
+    Ok(())
+}
+
+/// A rule scope: none, "local", "ssh:<host id>" or "env:<tag>". Snippets are scoped the same way.
+pub fn validate_scope(scope: Option<&str>) -> Result<(), String> {
+    if let Some(scope) = scope.map(str::trim).filter(|s| !s.is_empty()) {
+        let valid = scope == "local"
+            || scope.strip_prefix("ssh:").is_some_and(|id| !id.is_empty())
+            || scope.strip_prefix("env:").is_some_and(|tag| !tag.is_empty());
+        if !valid {
+            return Err(format!("scope must be local, ssh:<host id> or env:<tag>, not {scope}"));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a rule's scope covers a session (`scope` as stored for the session, e.g. "local" or
+/// "ssh:<host_id>").
+fn in_scope(rule_scope: Option<&str>, scope: Option<&str>, environment_tag: &str) -> bool {
+    match rule_scope.map(str::trim).filter(|s| !s.is_empty()) {
+        None => true,
+        Some(s) => match s.strip_prefix("env:") {
+            Some(tag) => tag.eq_ignore_ascii_case(environment_tag),
+            None => Some(s) == scope,
+        },
+    }
+}
+
+impl Highlighter {
+    /// `None` when no enabled rule applies to the session.
+    pub fn for_session(rules: &[HighlightRule], scope: Option<&str>, environment_tag: &str) -> Option<Self> {
+        let rules: Vec<CompiledRule> = rules
+            .iter()
+            .filter(|r| r.enabled && in_scope(r.scope.as_deref(), scope, environment_tag))
+            .filter_map(|r| {
+                Some(CompiledRule {
+                    re: Regex::new(&r.pattern).ok()?,
+                    rule: r.clone(),
+                })
+            })
+            .collect();
+        (!rules.is_empty()).then_some(Self { rules })
+    }
+
+    pub fn spans(&self, text: &str) -> Vec<HighlightSpan> {
+        let utf16 = |byte: usize| {
+            if text.is_ascii() {
+                byte
+            } else {
+                text[..byte].encode_utf16().count()
+            }
+        };
+        let mut spans = Vec::new();
+        for CompiledRule { re, rule } in &self.rules {
+            for m in re.find_iter(text).filter(|m| !m.is_empty()) {
+                if spans.len() >= MAX_SPANS {
+                    return spans;
+                }
+                spans.push(HighlightSpan {
+                    start: utf16(m.start()),
+                    end: utf16(m.end()),
+                    rule_id: rule.id.clone(),
+                    severity: rule.severity,
+                    color: rule.color.clone(),
+                    annotation: rule.annotation.clone(),
+                });
+            }
+        }
+        spans
+    }
+}
@@ -0,0 +1,252 @@
+//! Idle session policy.
+//!
+//! A session that hasn't been typed into for `idle_minutes` is closed, or made read-only,
+//! depending on the policy; environments can have their own threshold and action (or be exempt),
+//! so a prod shell can be closed after 30 minutes while a local one stays open all day.
+//! `warn_minutes` before acting, a `terminal:idle_warning` event lets the UI ask whether the
+//! session is still in use; any input resets the clock. The action itself is announced as
+//! `terminal:idle_action`.
+//!
+//! Output doesn't count as activity by default: a forgotten shell left running `tail -f` or `top`
+//! prints forever. A rule can opt in with `output_counts`, where cutting off a long-running command
+//! would be worse than leaving a shell open.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Db;
+use crate::terminal::TerminalReadonlyEvent;
+use crate::{timestamp, AppState};
+
+const SETTINGS_KEY: &str = "terminal.idle_policy";
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_IDLE_MINUTES: u32 = 7 * 24 * 60;
+const MAX_WARN_MINUTES: u32 = 60;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdleAction {
+    /// End the session.
+    Close,
+    /// Refuse input (see `terminal_set_readonly`); the session and its output stay.
+    #[default]
+    Lock,
+}
+
+/// When and how an idle session is dealt with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleRule {
+    /// 0 leaves idle sessions alone.
+    pub idle_minutes: u32,
+    #[serde(default)]
+    pub action: IdleAction,
+    /// Output resets the clock too, not just input.
+    #[serde(default)]
+    pub output_counts: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlePolicyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_rule")]
+    pub rule: IdleRule,
+    /// Rules for particular environments (by tag), in place of `rule`.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, IdleRule>,
+    /// How long before acting `terminal:idle_warning` is sent; 0 acts without warning.
+    #[serde(default = "default_warn_minutes")]
+    pub warn_minutes: u32,
+}
+
+fn default_rule() -> IdleRule {
+    IdleRule {
+        idle_minutes: 60,
+        action: IdleAction::Lock,
+        output_counts: false,
+    }
+}
+
+fn default_warn_minutes() -> u32 {
+    5
+}
+
+impl Default for IdlePolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rule: default_rule(),
+            overrides: BTreeMap::new(),
+            warn_minutes: default_warn_minutes(),
+        }
+    }
+}
+
+impl IdlePolicyConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Environment tags are stored upper-case, like the environments themselves.
+    pub fn save(mut self, db: &Db) -> Result<Self, String> {
+        self.overrides = self
+            .overrides
+            .into_iter()
+            .map(|(env, rule)| (env.trim().to_uppercase(), rule))
+            .filter(|(env, _)| !env.is_empty())
+            .collect();
+        let rules = std::iter::once(("default", &self.rule))
+            .chain(self.overrides.iter().map(|(env, rule)| (env.as_str(), rule)));
+        for (env, rule) in rules {
+            if rule.idle_minutes > MAX_IDLE_MINUTES {
+                return Err(format!("{env}: idle minutes must be at most {MAX_IDLE_MINUTES}"));
+            }
+            if rule.idle_minutes != 0 && rule.idle_minutes <= self.warn_minutes {
+                return Err(format!(
+                    "{env}: idle minutes must be more than the warning ({} minutes)",
+                    self.warn_minutes
+                ));
+            }
+        }
+        if self.warn_minutes > MAX_WARN_MINUTES {
+            return Err(format!("warning minutes must be at most {MAX_WARN_MINUTES}"));
+        }
+        let json = serde_json::to_string(&self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())?;
+        Ok(self)
+    }
+
+    fn rule_for(&self, environment_tag: &str) -> &IdleRule {
+        self.overrides.get(&environment_tag.to_uppercase()).unwrap_or(&self.rule)
+    }
+}
+
+/// Payload of `terminal:idle_warning`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleWarningEvent {
+    pub session_id: String,
+    pub environment_tag: String,
+    pub action: IdleAction,
+    /// Epoch seconds of the last input (or output, where it counts).
+    pub idle_since: i64,
+    /// Epoch seconds at which `action` is taken unless the session is used.
+    pub action_at: i64,
+}
+
+/// Payload of `terminal:idle_action`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleActionEvent {
+    pub session_id: String,
+    pub environment_tag: String,
+    pub action: IdleAction,
+    pub idle_since: i64,
+}
+
+/// What has been done about a session's current idle stretch, keyed by when it started.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Warned,
+    Acted,
+}
+
+/// Warn about, then close or lock, sessions idle beyond their environment's threshold.
+pub fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        // Session id -> (idle since, what was done about it).
+        let mut handled: HashMap<String, (i64, Stage)> = HashMap::new();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let state = app.state::<Arc<AppState>>();
+            let config = IdlePolicyConfig::load(&state.db);
+            if !config.enabled {
+                handled.clear();
+                continue;
+            }
+            let now = timestamp::now_millis() / 1000;
+            let sessions = state.terminal.list();
+            handled.retain(|id, _| sessions.iter().any(|s| &s.session_id == id));
+            for s in sessions {
+                let rule = config.rule_for(&s.environment_tag);
+                if rule.idle_minutes == 0 || (rule.action == IdleAction::Lock && s.readonly) {
+                    continue;
+                }
+                let output_at = s.last_output_at.filter(|_| rule.output_counts);
+                let idle_since = [Some(s.started_at), s.last_input_at, output_at]
+                    .into_iter()
+                    .flatten()
+                    .max()
+                    .unwrap_or(s.started_at);
+                let action_at = idle_since + i64::from(rule.idle_minutes) * 60;
+                let warn_at = action_at - i64::from(config.warn_minutes) * 60;
+                let stage = handled.get(&s.session_id).filter(|(since, _)| *since == idle_since).map(|(_, st)| *st);
+
+                if now >= action_at && stage != Some(Stage::Acted) {
+                    handled.insert(s.session_id.clone(), (idle_since, Stage::Acted));
+                    act(&app, &state, &s.session_id, rule.action);
+                    log::info!(
+                        "idle session in {} {} after {} minutes",
+                        s.environment_tag,
+                        if rule.action == IdleAction::Close { "closed" } else { "locked" },
+                        rule.idle_minutes
+                    );
+                    let _ = app.emit(
+                        "terminal:idle_action",
+                        IdleActionEvent {
+                            session_id: s.session_id,
+                            environment_tag: s.environment_tag,
+                            action: rule.action,
+                            idle_since,
+                        },
+                    );
+                } else if config.warn_minutes > 0 && now >= warn_at && now < action_at && stage.is_none() {
+                    handled.insert(s.session_id.clone(), (idle_since, Stage::Warned));
+                    let _ = app.emit(
+                        "terminal:idle_warning",
+                        IdleWarningEvent {
+                            session_id: s.session_id,
+                            environment_tag: s.environment_tag,
+                            action: rule.action,
+                            idle_since,
+                            action_at,
+                        },
+                    );
+                }
+            }
+        }
+    });
+}
+
+fn act(app: &AppHandle, state: &AppState, session_id: &str, action: IdleAction) {
+    match action {
+        IdleAction::Close => {
+            if state.terminal.close(session_id).is_ok() {
+                let _ = state.db.terminal_session_scope_delete(session_id);
+                state.auth_prompts.release(session_id);
+            }
+        }
+        IdleAction::Lock => {
+            if state.terminal.set_readonly(session_id, true).is_ok() {
+                let _ = app.emit(
+                    "terminal:readonly",
+                    TerminalReadonlyEvent {
+                        session_id: session_id.to_string(),
+                        readonly: true,
+                    },
+                );
+            }
+        }
+    }
+}
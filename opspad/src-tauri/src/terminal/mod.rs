@@ -1,14 +1,32 @@
+pub mod banner;
+pub mod benchmark;
+pub mod boundaries;
+pub mod highlight;
+pub mod idle;
+pub mod perf;
 mod portable_pty_backend;
+pub mod privilege;
+pub mod prompt_marks;
+pub mod scrollback;
+pub mod search;
 pub mod session_manager;
+pub mod watchdog;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 
 use serde::Serialize;
 use tauri::AppHandle;
 
 use crate::arch::{shell, ssh};
+use crate::terminal::boundaries::CommandRecord;
+use crate::terminal::highlight::{HighlightSpan, Highlighter};
 use crate::terminal::portable_pty_backend::PortablePtySessionManager;
-use crate::terminal::session_manager::{SpawnSpec, TerminalKind, TerminalSessionManager, WriteMeta};
+use crate::terminal::scrollback::SpillTarget;
+use crate::terminal::session_manager::{
+    SessionLatency, SessionSummary, SpawnSpec, TerminalKind, TerminalSessionManager, Transcript, WriteMeta,
+};
 
 #[derive(Clone, Debug)]
 pub struct SessionId(pub String);
@@ -16,6 +34,10 @@ pub struct SessionId(pub String);
 #[derive(Debug)]
 pub enum TerminalError {
     NotFound,
+    /// Input is refused while the app lock is engaged.
+    Locked,
+    /// The session was made read-only (`terminal_set_readonly`).
+    ReadOnly,
     Backend(String),
 }
 
@@ -23,6 +45,8 @@ impl std::fmt::Display for TerminalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TerminalError::NotFound => write!(f, "terminal session not found"),
+            TerminalError::Locked => write!(f, "OpsPad is locked"),
+            TerminalError::ReadOnly => write!(f, "the session is read-only"),
             TerminalError::Backend(msg) => write!(f, "terminal backend error: {msg}"),
         }
     }
@@ -35,22 +59,74 @@ impl std::error::Error for TerminalError {}
 pub struct TerminalDataEvent {
     pub session_id: String,
     pub data: String,
+    /// What the session's highlight rules matched in `data`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<HighlightSpan>,
 }
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalExitEvent {
     pub session_id: String,
+    pub title: Option<String>,
+}
+
+/// The session was given a title, or had it cleared (`terminal:renamed`).
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalRenamedEvent {
+    pub session_id: String,
+    pub title: Option<String>,
+}
+
+/// The session was made read-only or writable again (`terminal:readonly`).
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalReadonlyEvent {
+    pub session_id: String,
+    pub readonly: bool,
+}
+
+/// The session's shell went in or out of root (`terminal:privileged`).
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalPrivilegedEvent {
+    pub session_id: String,
+    pub privileged: bool,
+    /// `shellIntegration` (the shell said) or `prompt` (guessed from the prompt).
+    pub source: &'static str,
+    pub environment_tag: String,
+}
+
+/// A command started or finished in the session, by its shell's marks (`terminal:command_start`,
+/// `terminal:command_end`).
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalCommandEvent {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub command: CommandRecord,
+}
+
+/// The session's shell sent its first prompt mark: shell integration is running
+/// (`terminal:shell_integration`).
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalShellIntegrationEvent {
+    pub session_id: String,
 }
 
 pub struct TerminalManager {
     backend: Arc<dyn TerminalSessionManager>,
+    /// The app lock is engaged: writes fail and output is held back.
+    locked: AtomicBool,
 }
 
 impl TerminalManager {
     pub fn new() -> Self {
         Self {
             backend: Arc::new(PortablePtySessionManager::new()),
+            locked: AtomicBool::new(false),
         }
     }
 
@@ -63,6 +139,7 @@ impl TerminalManager {
         environment_tag: Option<String>,
         initial_cols: Option<u16>,
         initial_rows: Option<u16>,
+        banner: Option<String>,
     ) -> Result<SessionId, TerminalError> {
         let sh = shell::default_shell_command();
         self.spawn_process(
@@ -74,6 +151,9 @@ impl TerminalManager {
                 initial_rows,
                 program: sh.program,
                 args: sh.args,
+                banner,
+                env: Vec::new(),
+                quiet: false,
             },
         )
     }
@@ -81,6 +161,7 @@ impl TerminalManager {
     /// Spawn an SSH interactive session (system `ssh`).
     ///
     /// SSH is treated like any other spawned process: `spawn(program, args)`.
+    #[allow(clippy::too_many_arguments)]
     pub fn open_ssh(
         &self,
         app: AppHandle,
@@ -92,6 +173,8 @@ impl TerminalManager {
         environment_tag: Option<String>,
         initial_cols: Option<u16>,
         initial_rows: Option<u16>,
+        banner: Option<String>,
+        env: Vec<(String, String)>,
     ) -> Result<SessionId, TerminalError> {
         let program = ssh::ssh_program_checked().map_err(TerminalError::Backend)?;
         let mut args = Vec::<String>::new();
@@ -125,15 +208,19 @@ impl TerminalManager {
                 initial_rows,
                 program,
                 args,
+                banner,
+                env,
+                quiet: false,
             },
         )
     }
 
-    fn spawn_process(&self, app: AppHandle, spec: SpawnSpec) -> Result<SessionId, TerminalError> {
+    pub fn spawn_process(&self, app: AppHandle, spec: SpawnSpec) -> Result<SessionId, TerminalError> {
         self.backend.spawn(app, spec).map(SessionId)
     }
 
     pub fn write(&self, session_id: &str, data: &str) -> Result<(), TerminalError> {
+        self.check_unlocked()?;
         self.backend.write(session_id, data, WriteMeta::default())
     }
 
@@ -143,9 +230,74 @@ impl TerminalManager {
         data: &str,
         meta: WriteMeta,
     ) -> Result<(), TerminalError> {
+        self.check_unlocked()?;
         self.backend.write(session_id, data, meta)
     }
 
+    pub fn write_secret(&self, session_id: &str, secret: &str) -> Result<(), TerminalError> {
+        self.check_unlocked()?;
+        self.backend.write_secret(session_id, secret)
+    }
+
+    fn check_unlocked(&self) -> Result<(), TerminalError> {
+        match self.locked.load(Ordering::SeqCst) {
+            true => Err(TerminalError::Locked),
+            false => Ok(()),
+        }
+    }
+
+    /// Fail the way a write to the session would, without writing: for checking before a write
+    /// has side effects of its own (history, policy confirmations).
+    pub fn check_writable(&self, session_id: &str) -> Result<(), TerminalError> {
+        self.check_unlocked()?;
+        match self.backend.list().into_iter().find(|s| s.session_id == session_id) {
+            Some(s) if s.readonly => Err(TerminalError::ReadOnly),
+            Some(_) => Ok(()),
+            None => Err(TerminalError::NotFound),
+        }
+    }
+
+    /// Engage or release the app lock's hold on every session: while locked, input is refused
+    /// and output is kept back from the UI (still recorded), then delivered on unlock.
+    pub fn set_locked(&self, app: &AppHandle, locked: bool) {
+        self.locked.store(locked, Ordering::SeqCst);
+        self.backend.set_output_hidden(app, locked);
+    }
+
+    /// Spill new sessions' evicted scrollback to disk (`None`: keep it in memory only).
+    pub fn set_spill(&self, target: Option<SpillTarget>) {
+        self.backend.set_spill(target)
+    }
+
+    pub fn rename(&self, session_id: &str, title: Option<String>) -> Result<(), TerminalError> {
+        self.backend.rename(session_id, title)
+    }
+
+    pub fn set_readonly(&self, session_id: &str, readonly: bool) -> Result<(), TerminalError> {
+        self.backend.set_readonly(session_id, readonly)
+    }
+
+    /// The next command the session's shell marks as started was sent for this history entry.
+    pub fn tag_next_command(&self, session_id: &str, history_id: &str) -> Result<(), TerminalError> {
+        self.backend.tag_next_command(session_id, history_id)
+    }
+
+    pub fn commands(&self, session_id: &str) -> Result<Vec<CommandRecord>, TerminalError> {
+        self.backend.commands(session_id)
+    }
+
+    pub fn set_server_info(&self, session_id: &str, info: crate::db::ServerInfo) -> Result<(), TerminalError> {
+        self.backend.set_server_info(session_id, info)
+    }
+
+    pub fn set_latency(&self, session_id: &str, latency: SessionLatency) -> Result<(), TerminalError> {
+        self.backend.set_latency(session_id, latency)
+    }
+
+    pub fn set_highlighter(&self, session_id: &str, highlighter: Option<Highlighter>) -> Result<(), TerminalError> {
+        self.backend.set_highlighter(session_id, highlighter.map(Arc::new))
+    }
+
     pub fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), TerminalError> {
         self.backend.resize(session_id, cols, rows)
     }
@@ -153,4 +305,31 @@ impl TerminalManager {
     pub fn close(&self, session_id: &str) -> Result<(), TerminalError> {
         self.backend.close(session_id)
     }
+
+    pub fn subscribe_output(&self, session_id: &str) -> Result<Receiver<String>, TerminalError> {
+        self.backend.subscribe_output(session_id)
+    }
+
+    pub fn set_window(&self, session_id: &str, window: Option<String>) -> Result<(), TerminalError> {
+        self.backend.set_window(session_id, window)
+    }
+
+    pub fn attach(&self, app: &AppHandle, session_id: &str, window: &str) -> Result<(), TerminalError> {
+        self.backend.attach(app, session_id, window)
+    }
+
+    pub fn window(&self, session_id: &str) -> Result<Option<String>, TerminalError> {
+        self.backend.window(session_id)
+    }
+
+    pub fn transcript(&self, session_id: &str) -> Result<Transcript, TerminalError> {
+        self.backend.transcript(session_id)
+    }
+
+    /// Live sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionSummary> {
+        let mut sessions = self.backend.list();
+        sessions.sort_by_key(|s| s.started_at);
+        sessions
+    }
 }
@@ -0,0 +1,217 @@
+//! Timing and throughput counters for the terminal pipeline, to put numbers on "typing feels
+//! laggy" (`perf_metrics`).
+//!
+//! Four stages are measured: the `terminal_write` command (keystroke IPC in, up to the bytes
+//! being handed to the PTY), the PTY write itself (including waiting on the session's writer),
+//! each PTY read's processing up to delivery, and the `terminal:data` emit (highlighting,
+//! serializing, sending to the webview). Counters are process-wide atomics, cheap enough to stay
+//! on; latencies go into power-of-two microsecond buckets, so percentiles are upper bounds.
+//!
+//! With the overlay on (`perf_overlay_set`), a `perf:metrics` event carries a snapshot every
+//! second.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::timestamp;
+
+/// Bucket `i` holds latencies below 2^i µs; the last one everything from ~33 s up.
+const BUCKETS: usize = 26;
+const OVERLAY_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug)]
+pub enum Stage {
+    WriteCommand,
+    PtyWrite,
+    Output,
+    Emit,
+}
+
+struct Counters {
+    count: AtomicU64,
+    bytes: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+    buckets: [AtomicU64; BUCKETS],
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+            buckets: [const { AtomicU64::new(0) }; BUCKETS],
+        }
+    }
+
+    fn record(&self, elapsed: Duration, bytes: usize) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+        let bucket = (u64::BITS - us.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for counter in [&self.count, &self.bytes, &self.total_us, &self.max_us]
+            .into_iter()
+            .chain(self.buckets.iter())
+        {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self, elapsed_ms: i64) -> StageMetrics {
+        let count = self.count.load(Ordering::Relaxed);
+        let bytes = self.bytes.load(Ordering::Relaxed);
+        let buckets: Vec<u64> = self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect();
+        let percentile = |p: u64| -> u64 {
+            if count == 0 {
+                return 0;
+            }
+            let target = (count * p).div_ceil(100);
+            let mut seen = 0;
+            for (i, n) in buckets.iter().enumerate() {
+                seen += n;
+                if seen >= target {
+                    return 1 << i;
+                }
+            }
+            1 << (BUCKETS - 1)
+        };
+        StageMetrics {
+            count,
+            bytes,
+            mean_us: self.total_us.load(Ordering::Relaxed).checked_div(count).unwrap_or(0),
+            max_us: self.max_us.load(Ordering::Relaxed),
+            p50_us: percentile(50),
+            p95_us: percentile(95),
+            p99_us: percentile(99),
+            bytes_per_sec: if elapsed_ms > 0 { bytes * 1000 / elapsed_ms as u64 } else { 0 },
+        }
+    }
+}
+
+static WRITE_COMMAND: Counters = Counters::new();
+static PTY_WRITE: Counters = Counters::new();
+static OUTPUT: Counters = Counters::new();
+static EMIT: Counters = Counters::new();
+/// Epoch ms the counters last started from; 0 until the first `record`.
+static SINCE: AtomicI64 = AtomicI64::new(0);
+static OVERLAY: AtomicBool = AtomicBool::new(false);
+/// Bumped on each start, so a quick off/on doesn't leave two emitters running.
+static OVERLAY_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn counters(stage: Stage) -> &'static Counters {
+    match stage {
+        Stage::WriteCommand => &WRITE_COMMAND,
+        Stage::PtyWrite => &PTY_WRITE,
+        Stage::Output => &OUTPUT,
+        Stage::Emit => &EMIT,
+    }
+}
+
+pub fn record(stage: Stage, elapsed: Duration, bytes: usize) {
+    let _ = SINCE.compare_exchange(0, timestamp::now_millis(), Ordering::Relaxed, Ordering::Relaxed);
+    counters(stage).record(elapsed, bytes);
+}
+
+/// Records the time until it's dropped, so every return path of a stage is counted.
+pub struct Timer {
+    stage: Stage,
+    bytes: usize,
+    started: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        record(self.stage, self.started.elapsed(), self.bytes);
+    }
+}
+
+pub fn time(stage: Stage, bytes: usize) -> Timer {
+    Timer {
+        stage,
+        bytes,
+        started: Instant::now(),
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageMetrics {
+    pub count: u64,
+    pub bytes: u64,
+    pub mean_us: u64,
+    pub max_us: u64,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    /// Averaged since the counters started.
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerfMetrics {
+    /// When counting started (first activity after launch or a reset); `None` before any.
+    pub since: Option<String>,
+    pub elapsed_ms: i64,
+    /// The `terminal_write` command, from entry to the bytes reaching the PTY.
+    pub write_command: StageMetrics,
+    /// Writing to the PTY, including waiting for the session's writer.
+    pub pty_write: StageMetrics,
+    /// A PTY read's processing (masking, taps, transcript) until it's delivered.
+    pub output: StageMetrics,
+    /// Highlighting and emitting `terminal:data`.
+    pub emit: StageMetrics,
+    pub overlay: bool,
+}
+
+pub fn snapshot() -> PerfMetrics {
+    let since = SINCE.load(Ordering::Relaxed);
+    let elapsed_ms = if since > 0 { timestamp::now_millis() - since } else { 0 };
+    PerfMetrics {
+        since: (since > 0).then(|| timestamp::to_iso(since)),
+        elapsed_ms,
+        write_command: WRITE_COMMAND.snapshot(elapsed_ms),
+        pty_write: PTY_WRITE.snapshot(elapsed_ms),
+        output: OUTPUT.snapshot(elapsed_ms),
+        emit: EMIT.snapshot(elapsed_ms),
+        overlay: OVERLAY.load(Ordering::Relaxed),
+    }
+}
+
+pub fn reset() {
+    for stage in [Stage::WriteCommand, Stage::PtyWrite, Stage::Output, Stage::Emit] {
+        counters(stage).reset();
+    }
+    SINCE.store(0, Ordering::Relaxed);
+}
+
+/// Start or stop the `perf:metrics` events.
+pub fn set_overlay(app: &AppHandle, enabled: bool) {
+    if OVERLAY.swap(enabled, Ordering::Relaxed) || !enabled {
+        return;
+    }
+    let generation = OVERLAY_GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    let app = app.clone();
+    thread::Builder::new()
+        .name("perf-overlay".to_string())
+        .spawn(move || {
+            while OVERLAY.load(Ordering::Relaxed) && OVERLAY_GENERATION.load(Ordering::Relaxed) == generation {
+                let _ = app.emit("perf:metrics", snapshot());
+                thread::sleep(OVERLAY_INTERVAL);
+            }
+        })
+        .map(drop)
+        .unwrap_or_else(|e| log::warn!("couldn't start the perf overlay: {e}"));
+}
@@ -1,25 +1,53 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     io::{Read, Write},
-    sync::{Arc, Mutex},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
     thread,
-    time::SystemTime,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, EventTarget};
 use uuid::Uuid;
 
-use crate::terminal::{TerminalDataEvent, TerminalError, TerminalExitEvent};
-use crate::terminal::session_manager::{SpawnSpec, TerminalSessionManager, WriteMeta};
+use crate::db::ServerInfo;
+use crate::terminal::boundaries::{Boundary, CommandRecord, CommandTracker};
+use crate::terminal::highlight::Highlighter;
+use crate::terminal::perf::{self, Stage};
+use crate::terminal::privilege::PrivilegeDetector;
+use crate::terminal::scrollback::{SpillTarget, Spool};
+use crate::terminal::{
+    TerminalCommandEvent, TerminalDataEvent, TerminalError, TerminalExitEvent, TerminalPrivilegedEvent,
+    TerminalShellIntegrationEvent,
+};
+use crate::terminal::session_manager::{
+    SessionLatency, SessionSummary, SpawnSpec, TerminalKind, TerminalSessionManager, Transcript, TranscriptChunk,
+    WriteMeta,
+};
 
 #[derive(Debug)]
 struct SessionMeta {
+    kind: TerminalKind,
+    started_at: SystemTime,
     environment_tag: String,
     cols: u16,
     rows: u16,
     last_commanddock_command: Option<String>,
     last_commanddock_at: Option<SystemTime>,
+    privileged: bool,
+    quiet: bool,
+    last_output_at: Option<SystemTime>,
+    last_input_at: Option<SystemTime>,
+    awaiting_output_since: Option<SystemTime>,
+    /// Set with `rename`.
+    title: Option<String>,
+    server_info: Option<ServerInfo>,
+    latency: Option<SessionLatency>,
+    readonly: bool,
 }
 
 struct Session {
@@ -27,25 +55,257 @@ struct Session {
     master: Mutex<Box<dyn portable_pty::MasterPty + Send>>,
     killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
     meta: Mutex<SessionMeta>,
+    /// Backend-side output consumers (e.g. sequence wait-for-prompt).
+    output_taps: Mutex<Vec<Sender<String>>>,
+    route: Mutex<Route>,
+    transcript: Mutex<TranscriptBuffer>,
+    /// Receives what the transcript evicts, when spilling is on. Locked after `transcript`.
+    spool: Mutex<Option<Spool>>,
+    /// Secrets typed with `write_secret`, masked wherever they show up in output.
+    masked: Mutex<Vec<String>>,
+    privilege: Mutex<PrivilegeDetector>,
+    boundaries: Mutex<CommandTracker>,
+    highlighter: RwLock<Option<Arc<Highlighter>>>,
+}
+
+/// Secrets remembered per session for masking; the oldest is forgotten past this.
+const MAX_MASKED: usize = 8;
+/// Shorter secrets aren't masked: they'd blank out ordinary output.
+const MIN_MASKED_CHARS: usize = 4;
+const MASK: &str = "********";
+
+impl Session {
+    fn mask(&self, mut text: String) -> String {
+        for secret in self.masked.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            if text.contains(secret.as_str()) {
+                text = text.replace(secret.as_str(), MASK);
+            }
+        }
+        text
+    }
+
+    /// Keep `text` for the transcript and send it to the UI (or hold it during a window handoff).
+    fn deliver(&self, app: &AppHandle, session_id: &str, text: String) {
+        {
+            let mut transcript = self.transcript.lock().unwrap_or_else(|e| e.into_inner());
+            let evicted = transcript.push(&text);
+            if !evicted.is_empty() {
+                if let Some(spool) = self.spool.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+                    evicted.into_iter().for_each(|chunk| spool.push(chunk));
+                }
+            }
+        }
+        let mut guard = self.route.lock().unwrap_or_else(|e| e.into_inner());
+        let route = &mut *guard;
+        match route.hidden.as_mut().or(route.handoff.as_mut()) {
+            Some(held) => hold(held, &text),
+            None => emit_data(app, self, route, session_id, text),
+        }
+    }
+
+    /// Write what's left of the transcript to the spool and close it (once; later calls no-op).
+    fn finish_spool(&self) {
+        let (spool, remaining) = {
+            let transcript = self.transcript.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(spool) = self.spool.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+                return;
+            };
+            (spool, transcript.chunks.clone())
+        };
+        spool.finish(remaining);
+    }
+
+    fn check_writable(&self) -> Result<(), TerminalError> {
+        match self.meta.lock().unwrap_or_else(|e| e.into_inner()).readonly {
+            true => Err(TerminalError::ReadOnly),
+            false => Ok(()),
+        }
+    }
+
+    fn title(&self) -> Option<String> {
+        self.meta.lock().unwrap_or_else(|e| e.into_inner()).title.clone()
+    }
+
+    /// Input is on its way to the PTY; the hang watchdog waits for output answering it.
+    fn note_input(&self) {
+        let mut m = self.meta.lock().unwrap_or_else(|e| e.into_inner());
+        let now = SystemTime::now();
+        m.last_input_at = Some(now);
+        m.awaiting_output_since.get_or_insert(now);
+    }
+
+    fn note_output(&self) {
+        let mut m = self.meta.lock().unwrap_or_else(|e| e.into_inner());
+        m.last_output_at = Some(SystemTime::now());
+        m.awaiting_output_since = None;
+    }
+
+    /// Track whether the shell is root; announce it when that changes.
+    fn watch_privilege(&self, app: &AppHandle, session_id: &str, output: &str) {
+        let change = self.privilege.lock().unwrap_or_else(|e| e.into_inner()).feed(output);
+        let Some((privileged, source)) = change else {
+            return;
+        };
+        let environment_tag = {
+            let mut m = self.meta.lock().unwrap_or_else(|e| e.into_inner());
+            m.privileged = privileged;
+            m.environment_tag.clone()
+        };
+        let _ = app.emit(
+            "terminal:privileged",
+            TerminalPrivilegedEvent {
+                session_id: session_id.to_string(),
+                privileged,
+                source: source.as_str(),
+                environment_tag,
+            },
+        );
+    }
+
+    /// Follow the shell's prompt marks; announce the first one, and each command's start and end.
+    fn watch_commands(&self, app: &AppHandle, session_id: &str, output: &str) {
+        let (first, mut boundaries) = {
+            let mut tracker = self.boundaries.lock().unwrap_or_else(|e| e.into_inner());
+            let before = tracker.integrated;
+            let boundaries = tracker.feed(output);
+            (!before && tracker.integrated, boundaries)
+        };
+        // They carry command lines; the tracker still has them for `commands`.
+        if self.route.lock().unwrap_or_else(|e| e.into_inner()).hidden.is_some() {
+            boundaries.clear();
+        }
+        if first {
+            let _ = app.emit(
+                "terminal:shell_integration",
+                TerminalShellIntegrationEvent {
+                    session_id: session_id.to_string(),
+                },
+            );
+        }
+        for boundary in boundaries {
+            let (event, command) = match boundary {
+                Boundary::Started(command) => ("terminal:command_start", command),
+                Boundary::Ended(command) => ("terminal:command_end", command),
+            };
+            let _ = app.emit(
+                event,
+                TerminalCommandEvent {
+                    session_id: session_id.to_string(),
+                    command,
+                },
+            );
+        }
+    }
 }
 
+/// Output kept for transcript export, oldest dropped past [`MAX_TRANSCRIPT_BYTES`].
+#[derive(Default)]
+struct TranscriptBuffer {
+    chunks: VecDeque<TranscriptChunk>,
+    bytes: usize,
+    truncated: bool,
+}
+
+const MAX_TRANSCRIPT_BYTES: usize = 2 * 1024 * 1024;
+
+impl TranscriptBuffer {
+    /// Returns the chunks dropped to make room, oldest first.
+    fn push(&mut self, text: &str) -> Vec<TranscriptChunk> {
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        match self.chunks.back_mut() {
+            // Output arrives in small reads; keep one chunk per second.
+            Some(last) if last.at == at => last.text.push_str(text),
+            _ => self.chunks.push_back(TranscriptChunk {
+                at,
+                text: text.to_string(),
+            }),
+        }
+        self.bytes += text.len();
+        let mut evicted = Vec::new();
+        while self.bytes > MAX_TRANSCRIPT_BYTES && self.chunks.len() > 1 {
+            if let Some(old) = self.chunks.pop_front() {
+                self.bytes -= old.text.len();
+                self.truncated = true;
+                evicted.push(old);
+            }
+        }
+        evicted
+    }
+}
+
+/// Where `terminal:data` goes. Sessions start out broadcast; a detached session is sent only to
+/// its own window.
+#[derive(Default)]
+struct Route {
+    window: Option<String>,
+    /// Output held back while a new window loads, flushed by `attach`.
+    handoff: Option<String>,
+    /// Output held back while the app is locked, flushed by `set_output_hidden(false)`.
+    hidden: Option<String>,
+}
+
+/// Cap on output held during a handoff or lock; older output is dropped past this.
+const MAX_HANDOFF_BUFFER: usize = 1024 * 1024;
+
+fn hold(held: &mut String, text: &str) {
+    held.push_str(text);
+    if held.len() > MAX_HANDOFF_BUFFER {
+        let mut cut = held.len() - MAX_HANDOFF_BUFFER;
+        while !held.is_char_boundary(cut) {
+            cut += 1;
+        }
+        held.drain(..cut);
+    }
+}
+
+fn emit_data(app: &AppHandle, session: &Session, route: &Route, session_id: &str, data: String) {
+    let _timer = perf::time(Stage::Emit, data.len());
+    let spans = match session.highlighter.read().unwrap_or_else(|e| e.into_inner()).as_deref() {
+        Some(highlighter) => highlighter.spans(&data),
+        None => Vec::new(),
+    };
+    let event = TerminalDataEvent {
+        session_id: session_id.to_string(),
+        data,
+        spans,
+    };
+    let _ = match &route.window {
+        Some(label) => app.emit_to(EventTarget::webview_window(label.as_str()), "terminal:data", event),
+        None => app.emit("terminal:data", event),
+    };
+}
+
+/// The map is only locked long enough to look a session up (or add/remove one); everything else
+/// locks just the session's own fields, so a write stuck on one stalled PTY holds up that
+/// session only.
 #[derive(Default)]
 pub struct PortablePtySessionManager {
-    sessions: Arc<Mutex<HashMap<String, Arc<Session>>>>,
+    sessions: Arc<RwLock<HashMap<String, Arc<Session>>>>,
+    spill: RwLock<Option<SpillTarget>>,
+    /// Output is held back from the UI (app lock).
+    hidden: AtomicBool,
 }
 
 impl PortablePtySessionManager {
     pub fn new() -> Self {
         Self::default()
     }
+
+    fn session(&self, session_id: &str) -> Result<Arc<Session>, TerminalError> {
+        self.sessions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_id)
+            .cloned()
+            .ok_or(TerminalError::NotFound)
+    }
 }
 
 impl TerminalSessionManager for PortablePtySessionManager {
     fn spawn(&self, app: AppHandle, spec: SpawnSpec) -> Result<String, TerminalError> {
-        // Kind is currently used only by callers for UI/session bookkeeping.
-        // Keep it in the API to avoid special-casing session types later.
-        let _ = spec.kind;
-
         let rows = spec.initial_rows.unwrap_or(30);
         let cols = spec.initial_cols.unwrap_or(120);
 
@@ -78,6 +338,9 @@ impl TerminalSessionManager for PortablePtySessionManager {
         }
         // Stable identifier (best-effort).
         cmd.env("TERM_PROGRAM", "OpsPad");
+        for (key, value) in &spec.env {
+            cmd.env(key, value);
+        }
 
         let mut child = pair
             .slave
@@ -97,93 +360,158 @@ impl TerminalSessionManager for PortablePtySessionManager {
         let master = pair.master;
 
         let session_id = Uuid::new_v4().to_string();
+        let spool = self
+            .spill
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .and_then(|target| match Spool::create(target, &session_id, spec.kind, &spec.environment_tag) {
+                Ok(spool) => Some(spool),
+                Err(e) => {
+                    log::warn!("terminal {session_id}: scrollback won't be kept on disk: {e}");
+                    None
+                }
+            });
         let session = Arc::new(Session {
             writer: Mutex::new(writer),
             master: Mutex::new(master),
             killer: Mutex::new(killer),
             meta: Mutex::new(SessionMeta {
+                kind: spec.kind,
+                started_at: SystemTime::now(),
                 environment_tag: spec.environment_tag,
                 cols,
                 rows,
                 last_commanddock_command: None,
                 last_commanddock_at: None,
+                privileged: false,
+                quiet: spec.quiet,
+                last_output_at: None,
+                last_input_at: None,
+                awaiting_output_since: None,
+                title: None,
+                server_info: None,
+                latency: None,
+                readonly: false,
             }),
+            output_taps: Mutex::new(Vec::new()),
+            route: Mutex::new(Route::default()),
+            transcript: Mutex::new(TranscriptBuffer::default()),
+            spool: Mutex::new(spool),
+            masked: Mutex::new(Vec::new()),
+            privilege: Mutex::new(PrivilegeDetector::default()),
+            boundaries: Mutex::new(CommandTracker::default()),
+            highlighter: RwLock::new(None),
         });
 
         self.sessions
-            .lock()
-            .expect("poisoned terminal sessions lock")
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
             .insert(session_id.clone(), session.clone());
+        // Checked after the insert, so a lock engaged meanwhile can't miss the session.
+        if self.hidden.load(Ordering::SeqCst) {
+            session.route.lock().unwrap_or_else(|e| e.into_inner()).hidden.get_or_insert_with(String::new);
+        }
 
         // Read loop: PTY -> tauri event.
         let app2 = app.clone();
         let session_id2 = session_id.clone();
         let sessions2 = self.sessions.clone();
-        thread::spawn(move || {
-            let mut buf = [0u8; 8192];
-            loop {
-                let n = match reader.read(&mut buf) {
-                    Ok(0) => break,
-                    Ok(n) => n,
-                    Err(_) => break,
-                };
-                let s = String::from_utf8_lossy(&buf[..n]).to_string();
-                let _ = app2.emit(
-                    "terminal:data",
-                    TerminalDataEvent {
-                        session_id: session_id2.clone(),
-                        data: s,
-                    },
-                );
-            }
+        let session2 = session.clone();
+        let mut banner = spec.banner;
+        // Named so crash reports say which session's thread died.
+        thread::Builder::new()
+            .name(format!("pty-read-{session_id}"))
+            .spawn(move || {
+                let mut buf = [0u8; 8192];
+                loop {
+                    let n = match reader.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(n) => n,
+                        Err(e) => {
+                            log::debug!("terminal {session_id2}: read ended: {e}");
+                            break;
+                        }
+                    };
+                    let _timer = perf::time(Stage::Output, n);
+                    session2.note_output();
+                    // Sent with the first output rather than at spawn, when the UI may not know
+                    // the session id yet.
+                    if let Some(banner) = banner.take() {
+                        session2.deliver(&app2, &session_id2, banner);
+                    }
+                    let s = session2.mask(String::from_utf8_lossy(&buf[..n]).to_string());
+                    session2.watch_privilege(&app2, &session_id2, &s);
+                    session2.watch_commands(&app2, &session_id2, &s);
+                    {
+                        let mut taps = session2.output_taps.lock().unwrap_or_else(|e| e.into_inner());
+                        if !taps.is_empty() {
+                            taps.retain(|tx| tx.send(s.clone()).is_ok());
+                        }
+                    }
+                    session2.deliver(&app2, &session_id2, s);
+                }
 
-            // On EOF/error: best-effort finalize. On Windows, PTY EOF isn't a reliable signal,
-            // so we also finalize via a child wait thread below.
-            let removed = {
-                let mut map = sessions2.lock().expect("poisoned terminal sessions lock");
-                map.remove(&session_id2).is_some()
-            };
-            if removed {
-                let _ = app2.emit(
-                    "terminal:exit",
-                    TerminalExitEvent {
-                        session_id: session_id2.clone(),
-                    },
-                );
-            }
-        });
+                // On EOF/error: best-effort finalize. On Windows, PTY EOF isn't a reliable signal,
+                // so we also finalize via a child wait thread below.
+                // Disconnect output subscribers.
+                session2.output_taps.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                session2.finish_spool();
+                let removed = {
+                    let mut map = sessions2.write().unwrap_or_else(|e| e.into_inner());
+                    map.remove(&session_id2)
+                };
+                if let Some(session) = removed {
+                    let _ = app2.emit(
+                        "terminal:exit",
+                        TerminalExitEvent {
+                            session_id: session_id2.clone(),
+                            title: session.title(),
+                        },
+                    );
+                    notify_exit(&app2, &session);
+                }
+            })
+            .expect("spawn pty read thread");
 
         // Finalize on child exit (more reliable than PTY EOF on Windows).
         let app3 = app.clone();
         let session_id3 = session_id.clone();
         let sessions3 = self.sessions.clone();
-        thread::spawn(move || {
-            let _ = child.wait();
-            let removed = {
-                let mut map = sessions3.lock().expect("poisoned terminal sessions lock");
-                map.remove(&session_id3).is_some()
-            };
-            if removed {
-                let _ = app3.emit(
-                    "terminal:exit",
-                    TerminalExitEvent {
-                        session_id: session_id3.clone(),
-                    },
-                );
-            }
-        });
+        thread::Builder::new()
+            .name(format!("pty-wait-{session_id}"))
+            .spawn(move || {
+                if let Err(e) = child.wait() {
+                    crate::crash::capture_error(&format!("waiting on terminal {session_id3}"), &e.to_string());
+                }
+                let removed = {
+                    let mut map = sessions3.write().unwrap_or_else(|e| e.into_inner());
+                    map.remove(&session_id3)
+                };
+                if let Some(session) = removed {
+                    // The read loop may never see EOF on Windows; elsewhere it finishes the spool
+                    // after the last output.
+                    if cfg!(windows) {
+                        session.finish_spool();
+                    }
+                    let _ = app3.emit(
+                        "terminal:exit",
+                        TerminalExitEvent {
+                            session_id: session_id3.clone(),
+                            title: session.title(),
+                        },
+                    );
+                    notify_exit(&app3, &session);
+                }
+            })
+            .expect("spawn pty wait thread");
 
         Ok(session_id)
     }
 
     fn write(&self, session_id: &str, data: &str, meta: WriteMeta) -> Result<(), TerminalError> {
-        let session = self
-            .sessions
-            .lock()
-            .expect("poisoned terminal sessions lock")
-            .get(session_id)
-            .cloned()
-            .ok_or(TerminalError::NotFound)?;
+        let session = self.session(session_id)?;
+        session.check_writable()?;
 
         // Track "last command" only for structured CommandDock runs.
         // We do not attempt to infer typed commands from raw keystrokes to avoid capturing secrets.
@@ -196,35 +524,105 @@ impl TerminalSessionManager for PortablePtySessionManager {
                 cmd.truncate(512);
             }
             if !cmd.is_empty() {
-                let mut m = session.meta.lock().expect("poisoned session meta lock");
+                let mut m = session.meta.lock().unwrap_or_else(|e| e.into_inner());
                 m.last_commanddock_command = Some(cmd);
                 m.last_commanddock_at = Some(SystemTime::now());
             }
         }
 
-        let mut w = session.writer.lock().expect("poisoned pty writer lock");
+        let _timer = perf::time(Stage::PtyWrite, data.len());
+        session.note_input();
+        let mut w = session.writer.lock().unwrap_or_else(|e| e.into_inner());
         w.write_all(data.as_bytes())
             .map_err(|e| TerminalError::Backend(e.to_string()))?;
         w.flush().ok();
         Ok(())
     }
 
-    fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), TerminalError> {
-        let session = self
-            .sessions
+    fn write_secret(&self, session_id: &str, secret: &str) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session.check_writable()?;
+        if secret.chars().count() >= MIN_MASKED_CHARS {
+            let mut masked = session.masked.lock().unwrap_or_else(|e| e.into_inner());
+            if !masked.iter().any(|s| s == secret) {
+                if masked.len() >= MAX_MASKED {
+                    masked.remove(0);
+                }
+                masked.push(secret.to_string());
+            }
+        }
+        session.note_input();
+        let mut w = session.writer.lock().unwrap_or_else(|e| e.into_inner());
+        w.write_all(secret.as_bytes())
+            .and_then(|_| w.write_all(b"\r"))
+            .map_err(|e| TerminalError::Backend(e.to_string()))?;
+        w.flush().ok();
+        Ok(())
+    }
+
+    fn set_readonly(&self, session_id: &str, readonly: bool) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session.meta.lock().unwrap_or_else(|e| e.into_inner()).readonly = readonly;
+        Ok(())
+    }
+
+    fn rename(&self, session_id: &str, title: Option<String>) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session.meta.lock().unwrap_or_else(|e| e.into_inner()).title = title.clone();
+        if let Some(spool) = session.spool.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            spool.set_title(title);
+        }
+        Ok(())
+    }
+
+    fn tag_next_command(&self, session_id: &str, history_id: &str) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session
+            .boundaries
             .lock()
-            .expect("poisoned terminal sessions lock")
-            .get(session_id)
-            .cloned()
-            .ok_or(TerminalError::NotFound)?;
+            .unwrap_or_else(|e| e.into_inner())
+            .tag_next(history_id.to_string());
+        Ok(())
+    }
+
+    fn commands(&self, session_id: &str) -> Result<Vec<CommandRecord>, TerminalError> {
+        let session = self.session(session_id)?;
+        let commands = session.boundaries.lock().unwrap_or_else(|e| e.into_inner()).commands();
+        Ok(commands)
+    }
+
+    fn set_server_info(&self, session_id: &str, info: ServerInfo) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session.meta.lock().unwrap_or_else(|e| e.into_inner()).server_info = Some(info);
+        Ok(())
+    }
+
+    fn set_latency(&self, session_id: &str, latency: SessionLatency) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        session.meta.lock().unwrap_or_else(|e| e.into_inner()).latency = Some(latency);
+        Ok(())
+    }
+
+    fn set_spill(&self, target: Option<SpillTarget>) {
+        *self.spill.write().unwrap_or_else(|e| e.into_inner()) = target;
+    }
+
+    fn set_highlighter(&self, session_id: &str, highlighter: Option<Arc<Highlighter>>) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        *session.highlighter.write().unwrap_or_else(|e| e.into_inner()) = highlighter;
+        Ok(())
+    }
+
+    fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
 
         {
-            let mut m = session.meta.lock().expect("poisoned session meta lock");
+            let mut m = session.meta.lock().unwrap_or_else(|e| e.into_inner());
             m.cols = cols;
             m.rows = rows;
         }
 
-        let master = session.master.lock().expect("poisoned pty master lock");
+        let master = session.master.lock().unwrap_or_else(|e| e.into_inner());
         master
             .resize(PtySize {
                 rows,
@@ -237,7 +635,7 @@ impl TerminalSessionManager for PortablePtySessionManager {
 
     fn close(&self, session_id: &str) -> Result<(), TerminalError> {
         let session = {
-            let mut map = self.sessions.lock().expect("poisoned terminal sessions lock");
+            let mut map = self.sessions.write().unwrap_or_else(|e| e.into_inner());
             map.remove(session_id)
         };
 
@@ -247,8 +645,147 @@ impl TerminalSessionManager for PortablePtySessionManager {
 
         // Best-effort terminate, but never block the UI thread on it.
         thread::spawn(move || {
-            let _ = session.killer.lock().expect("poisoned killer lock").kill();
+            let _ = session.killer.lock().unwrap_or_else(|e| e.into_inner()).kill();
         });
         Ok(())
     }
+
+    fn subscribe_output(&self, session_id: &str) -> Result<Receiver<String>, TerminalError> {
+        let session = self.session(session_id)?;
+        let (tx, rx) = mpsc::channel();
+        session
+            .output_taps
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(tx);
+        Ok(rx)
+    }
+
+    fn set_window(&self, session_id: &str, window: Option<String>) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        let mut route = session.route.lock().unwrap_or_else(|e| e.into_inner());
+        route.handoff = window.as_ref().map(|_| String::new());
+        route.window = window;
+        Ok(())
+    }
+
+    fn set_output_hidden(&self, app: &AppHandle, hidden: bool) {
+        self.hidden.store(hidden, Ordering::SeqCst);
+        let sessions: Vec<(String, Arc<Session>)> = self
+            .sessions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect();
+        for (id, session) in sessions {
+            let mut guard = session.route.lock().unwrap_or_else(|e| e.into_inner());
+            let route = &mut *guard;
+            if hidden {
+                route.hidden.get_or_insert_with(String::new);
+                continue;
+            }
+            let Some(held) = route.hidden.take().filter(|h| !h.is_empty()) else {
+                continue;
+            };
+            match route.handoff.as_mut() {
+                Some(handoff) => hold(handoff, &held),
+                None => emit_data(app, &session, route, &id, held),
+            }
+        }
+    }
+
+    fn attach(&self, app: &AppHandle, session_id: &str, window: &str) -> Result<(), TerminalError> {
+        let session = self.session(session_id)?;
+        let mut route = session.route.lock().unwrap_or_else(|e| e.into_inner());
+        if route.window.as_deref() != Some(window) {
+            return Err(TerminalError::Backend("session belongs to another window".to_string()));
+        }
+        if let Some(held) = route.handoff.take() {
+            match route.hidden.as_mut() {
+                // Locked: the window gets it on unlock instead.
+                Some(hidden) => hold(hidden, &held),
+                None if !held.is_empty() => emit_data(app, &session, &route, session_id, held),
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn window(&self, session_id: &str) -> Result<Option<String>, TerminalError> {
+        let session = self.session(session_id)?;
+        let window = session.route.lock().unwrap_or_else(|e| e.into_inner()).window.clone();
+        Ok(window)
+    }
+
+    fn transcript(&self, session_id: &str) -> Result<Transcript, TerminalError> {
+        let session = self.session(session_id)?;
+        let buffer = session.transcript.lock().unwrap_or_else(|e| e.into_inner());
+        let spool = session.spool.lock().unwrap_or_else(|e| e.into_inner());
+        // Evicted output not yet paged out comes first, so pages and transcript meet without a gap.
+        let pending = spool.as_ref().map(|s| s.pending()).unwrap_or_default();
+        Ok(Transcript {
+            chunks: pending.iter().chain(buffer.chunks.iter()).cloned().collect(),
+            truncated: buffer.truncated,
+            spilled_through: spool.as_ref().map(|s| s.last_page()),
+        })
+    }
+
+    fn list(&self) -> Vec<SessionSummary> {
+        let epoch = |t: SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        // Snapshot first so the map isn't held while each session's metadata is locked.
+        let sessions: Vec<(String, Arc<Session>)> = self
+            .sessions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect();
+        sessions
+            .iter()
+            .map(|(id, session)| {
+                let m = session.meta.lock().unwrap_or_else(|e| e.into_inner());
+                SessionSummary {
+                    session_id: id.clone(),
+                    kind: m.kind,
+                    environment_tag: m.environment_tag.clone(),
+                    cols: m.cols,
+                    rows: m.rows,
+                    started_at: epoch(m.started_at),
+                    last_commanddock_at: m.last_commanddock_at.map(epoch),
+                    privileged: m.privileged,
+                    last_output_at: m.last_output_at.map(epoch),
+                    last_input_at: m.last_input_at.map(epoch),
+                    awaiting_output_since: m.awaiting_output_since.map(epoch),
+                    title: m.title.clone(),
+                    server_info: m.server_info.clone(),
+                    latency: m.latency.clone(),
+                    readonly: m.readonly,
+                    shell_integration: session.boundaries.lock().unwrap_or_else(|e| e.into_inner()).integrated,
+                }
+            })
+            .collect()
+    }
+}
+
+fn notify_exit(app: &AppHandle, session: &Session) {
+    let (kind, env, started_at, title) = {
+        let m = session.meta.lock().unwrap_or_else(|e| e.into_inner());
+        if m.quiet {
+            return;
+        }
+        (m.kind, m.environment_tag.clone(), m.started_at, m.title.clone())
+    };
+    let what = match (title, kind) {
+        (Some(title), _) => format!("\"{title}\""),
+        (None, TerminalKind::Local) => "Local shell".to_string(),
+        (None, TerminalKind::Ssh) => "SSH session".to_string(),
+    };
+    let minutes = started_at.elapsed().map(|d| d.as_secs() / 60).unwrap_or(0);
+    crate::notifications::notify(
+        app,
+        crate::notifications::Kind::SessionExit,
+        &format!("{what} ended"),
+        &format!("{env} session closed after {minutes} min"),
+    );
 }
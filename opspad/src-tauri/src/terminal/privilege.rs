@@ -0,0 +1,94 @@
+//! Whether a session's shell is running as root.
+//!
+//! A shell with integration can say so itself: `ESC ] 133 ; A ; uid=0` at the start of its prompt.
+//! Otherwise the prompt is guessed at: an idle last line ending in `# ` is root's (`root@db1:~# `),
+//! one ending in `$ `, `% ` or `> ` isn't. Whatever the shell reports in a read wins over the
+//! guess for that read; a root shell started without integration (`sudo -i`) is still caught by
+//! its prompt.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::terminal::prompt_marks::{PromptMark, PromptMarkParser};
+
+/// A prompt line longer than this is taken to be output, not a prompt.
+const MAX_PROMPT_CHARS: usize = 160;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrivilegeSource {
+    ShellIntegration,
+    Prompt,
+}
+
+impl PrivilegeSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrivilegeSource::ShellIntegration => "shellIntegration",
+            PrivilegeSource::Prompt => "prompt",
+        }
+    }
+}
+
+fn escape_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]")
+            .expect("valid escape sequence regex")
+    })
+}
+
+/// What an idle last line of output says about the shell, if it looks like a prompt at all.
+fn prompt_is_root(line: &str) -> Option<bool> {
+    let line = escape_re().replace_all(line, "");
+    let line = line.trim_start();
+    if line.is_empty() || line.chars().count() > MAX_PROMPT_CHARS {
+        return None;
+    }
+    let body = line.trim_end();
+    // A bare `>` is the continuation prompt, which root shells show too.
+    if body == ">" {
+        return None;
+    }
+    match body.chars().last()? {
+        '#' if body == "#" || !body.starts_with('#') => Some(true),
+        '$' | '%' | '>' => Some(false),
+        _ => None,
+    }
+}
+
+/// Follows a session's output and reports when it goes in or out of a root shell.
+#[derive(Default)]
+pub struct PrivilegeDetector {
+    marks: PromptMarkParser,
+    /// Output since the last line break.
+    line: String,
+    privileged: bool,
+}
+
+impl PrivilegeDetector {
+    /// `Some` when this output changed the answer.
+    pub fn feed(&mut self, chunk: &str) -> Option<(bool, PrivilegeSource)> {
+        let reported = self.marks.feed(chunk).into_iter().rev().find_map(|mark| match mark {
+            PromptMark::PromptStart { uid } => uid,
+            _ => None,
+        });
+        match chunk.rfind(['\n', '\r']) {
+            Some(pos) => self.line = chunk[pos + 1..].to_string(),
+            None => self.line.push_str(chunk),
+        }
+        if self.line.len() > MAX_PROMPT_CHARS * 4 {
+            self.line.clear();
+        }
+
+        let (privileged, source) = match reported {
+            Some(uid) => (uid == 0, PrivilegeSource::ShellIntegration),
+            None => (prompt_is_root(&self.line)?, PrivilegeSource::Prompt),
+        };
+        if privileged == self.privileged {
+            return None;
+        }
+        self.privileged = privileged;
+        Some((privileged, source))
+    }
+}
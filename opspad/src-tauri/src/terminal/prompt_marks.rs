@@ -0,0 +1,98 @@
+//! Shell-integration prompt marks (OSC 133) in terminal output.
+//!
+//! A shell with integration set up brackets each command: `ESC ] 133 ; A` where the prompt starts,
+//! `B` where the typed command starts, `C` when it starts running and `D ; <exit status>` when it
+//! finishes. Each sequence ends with BEL or `ESC \`. OpsPad's own integration also puts the
+//! shell's user id on the prompt mark (`A ; uid=0`).
+
+/// Longest mark kept across reads while waiting for its terminator; anything longer isn't one.
+const MAX_MARK_BYTES: usize = 256;
+const INTRODUCER: &str = "\x1b]133;";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptMark {
+    /// The shell's user id, if it said.
+    PromptStart { uid: Option<u32> },
+    CommandStart,
+    OutputStart,
+    /// The exit status, if the shell reported one.
+    CommandEnd { exit: Option<i32> },
+}
+
+fn parse(params: &str) -> Option<PromptMark> {
+    let mut parts = params.split(';');
+    match parts.next()? {
+        "A" => Some(PromptMark::PromptStart {
+            uid: parts.find_map(|p| p.trim().strip_prefix("uid=")?.parse().ok()),
+        }),
+        "B" => Some(PromptMark::CommandStart),
+        "C" => Some(PromptMark::OutputStart),
+        "D" => Some(PromptMark::CommandEnd {
+            exit: parts.next().and_then(|s| s.trim().parse().ok()),
+        }),
+        _ => None,
+    }
+}
+
+/// Output split at its marks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Mark(PromptMark),
+}
+
+/// Finds marks in a session's output as it arrives; a mark split across reads is still found.
+#[derive(Default)]
+pub struct PromptMarkParser {
+    carry: String,
+}
+
+impl PromptMarkParser {
+    pub fn feed(&mut self, chunk: &str) -> Vec<PromptMark> {
+        self.feed_segments(chunk)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Mark(mark) => Some(mark),
+                Segment::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Like [`feed`](Self::feed), with the text around the marks. Text held back because it may
+    /// be the start of a mark comes with the next read; a `BEL`-less or unknown mark is dropped.
+    pub fn feed_segments(&mut self, chunk: &str) -> Vec<Segment> {
+        let mut text = std::mem::take(&mut self.carry);
+        text.push_str(chunk);
+        let mut segments = Vec::new();
+        let push_text = |segments: &mut Vec<Segment>, s: &str| {
+            if !s.is_empty() {
+                segments.push(Segment::Text(s.to_string()));
+            }
+        };
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(INTRODUCER) {
+            push_text(&mut segments, &rest[..start]);
+            let body = &rest[start + INTRODUCER.len()..];
+            let Some(end) = body.find(['\x07', '\x1b']) else {
+                if body.len() <= MAX_MARK_BYTES {
+                    self.carry = rest[start..].to_string();
+                } else {
+                    push_text(&mut segments, &rest[start..]);
+                }
+                return segments;
+            };
+            segments.extend(parse(&body[..end]).map(Segment::Mark));
+            let terminator = if body[end..].starts_with("\x1b\\") { 2 } else { 1 };
+            rest = &body[end + terminator..];
+        }
+        // The read may have stopped partway into an introducer.
+        if let Some(pos) = rest.rfind('\x1b') {
+            if INTRODUCER.starts_with(&rest[pos..]) {
+                self.carry = rest[pos..].to_string();
+                rest = &rest[..pos];
+            }
+        }
+        push_text(&mut segments, rest);
+        segments
+    }
+}
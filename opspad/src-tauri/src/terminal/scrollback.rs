@@ -0,0 +1,524 @@
+//! Scrollback spilled to disk: output that falls out of a session's in-memory transcript is kept
+//! in gzip-compressed pages under `<app data>/scrollback/<session id>/`, so a long session can
+//! be scrolled back past the memory cap, reattached with its history, and read after it ends.
+//!
+//! Off by default (settings key `scrollback`). Each session keeps at most `max_mb_per_session`
+//! of compressed pages, dropping its oldest past that; when the session ends, what was still in
+//! memory is written out too. Ended sessions are deleted after `retention_days`, and the oldest of
+//! them once all sessions together pass the retention settings' cap (see `retention.rs`). Output
+//! on disk is what the UI was shown, with `write_secret` echoes already masked.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::arch::paths;
+use crate::db::Db;
+use crate::terminal::session_manager::{TerminalKind, TranscriptChunk};
+use crate::terminal::TerminalManager;
+use crate::timestamp;
+
+const SETTINGS_KEY: &str = "scrollback";
+const DIR_NAME: &str = "scrollback";
+const META_FILE: &str = "meta.json";
+const PAGE_SUFFIX: &str = ".json.gz";
+/// Uncompressed output gathered before a page is written.
+const PAGE_BYTES: usize = 256 * 1024;
+const MIN_MB_PER_SESSION: u64 = 1;
+const MAX_MB_PER_SESSION: u64 = 4096;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Compressed size kept per session.
+    #[serde(default = "default_max_mb")]
+    pub max_mb_per_session: u64,
+    /// Ended sessions' scrollback is deleted after this many days.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_max_mb() -> u64 {
+    64
+}
+
+fn default_retention_days() -> u32 {
+    7
+}
+
+impl Default for ScrollbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_mb_per_session: default_max_mb(),
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+impl ScrollbackConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if !(MIN_MB_PER_SESSION..=MAX_MB_PER_SESSION).contains(&self.max_mb_per_session) {
+            return Err(format!(
+                "the per-session limit must be {MIN_MB_PER_SESSION}-{MAX_MB_PER_SESSION} MB"
+            ));
+        }
+        if self.retention_days == 0 {
+            return Err("keep scrollback for at least a day".to_string());
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+pub fn root(app: &AppHandle) -> Result<PathBuf, String> {
+    paths::app_data_dir(app)
+        .map(|dir| dir.join(DIR_NAME))
+        .map_err(|e| format!("no app data directory: {e}"))
+}
+
+/// Point the terminal backend at the scrollback directory, or away from it, per the saved config.
+/// Sessions already open keep spilling (or not) as they started.
+pub fn apply(app: &AppHandle, db: &Db, terminal: &TerminalManager) {
+    let config = ScrollbackConfig::load(db);
+    let target = match (config.enabled, root(app)) {
+        (true, Ok(root)) => Some(SpillTarget {
+            root,
+            max_bytes: config.max_mb_per_session * 1024 * 1024,
+        }),
+        (true, Err(e)) => {
+            log::warn!("scrollback stays in memory: {e}");
+            None
+        }
+        (false, _) => None,
+    };
+    terminal.set_spill(target);
+}
+
+/// Where new sessions spill, handed to the terminal backend while spilling is on.
+#[derive(Clone, Debug)]
+pub struct SpillTarget {
+    pub root: PathBuf,
+    pub max_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpoolMeta {
+    session_id: String,
+    kind: TerminalKind,
+    environment_tag: String,
+    /// Epoch milliseconds.
+    started_at: i64,
+    ended_at: Option<i64>,
+    /// Older pages were deleted to stay within the limit.
+    #[serde(default)]
+    dropped: bool,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+/// Session ids become directory names.
+fn safe_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn page_name(seq: u64) -> String {
+    format!("{seq:08}{PAGE_SUFFIX}")
+}
+
+/// Page sequence numbers in a session directory, oldest first.
+fn page_seqs(dir: &Path) -> Vec<u64> {
+    let mut seqs: Vec<u64> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str()?.strip_suffix(PAGE_SUFFIX)?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    seqs.sort_unstable();
+    seqs
+}
+
+/// One session's pages being written.
+pub struct Spool {
+    dir: PathBuf,
+    meta: SpoolMeta,
+    max_bytes: u64,
+    /// Evicted from memory, not yet in a page.
+    pending: Vec<TranscriptChunk>,
+    pending_bytes: usize,
+    /// (sequence number, compressed size), oldest first.
+    pages: VecDeque<(u64, u64)>,
+    disk_bytes: u64,
+    failed: bool,
+}
+
+impl Spool {
+    pub fn create(
+        target: &SpillTarget,
+        session_id: &str,
+        kind: TerminalKind,
+        environment_tag: &str,
+    ) -> io::Result<Self> {
+        let dir = target.root.join(session_id);
+        fs::create_dir_all(&dir)?;
+        let spool = Self {
+            dir,
+            meta: SpoolMeta {
+                session_id: session_id.to_string(),
+                kind,
+                environment_tag: environment_tag.to_string(),
+                started_at: timestamp::now_millis(),
+                ended_at: None,
+                dropped: false,
+                title: None,
+            },
+            max_bytes: target.max_bytes,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            pages: VecDeque::new(),
+            disk_bytes: 0,
+            failed: false,
+        };
+        spool.write_meta()?;
+        Ok(spool)
+    }
+
+    fn write_meta(&self) -> io::Result<()> {
+        let json = serde_json::to_vec(&self.meta).map_err(io::Error::other)?;
+        fs::write(self.dir.join(META_FILE), json)
+    }
+
+    /// Output evicted from the in-memory transcript, oldest first.
+    pub fn push(&mut self, chunk: TranscriptChunk) {
+        if self.failed {
+            return;
+        }
+        self.pending_bytes += chunk.text.len();
+        self.pending.push(chunk);
+        if self.pending_bytes >= PAGE_BYTES {
+            self.flush();
+        }
+    }
+
+    /// Evicted output not yet in a page; it sits between the pages and the in-memory transcript.
+    pub fn pending(&self) -> &[TranscriptChunk] {
+        &self.pending
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.meta.title = title;
+        if let Err(e) = self.write_meta() {
+            log::warn!("scrollback for {}: {e}", self.meta.session_id);
+        }
+    }
+
+    /// Sequence number of the newest page written; 0 before the first.
+    pub fn last_page(&self) -> u64 {
+        self.pages.back().map_or(0, |(seq, _)| *seq)
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() || self.failed {
+            return;
+        }
+        let seq = self.pages.back().map_or(1, |(s, _)| s + 1);
+        let path = self.dir.join(page_name(seq));
+        match write_page(&path, &self.pending) {
+            Ok(size) => {
+                self.pages.push_back((seq, size));
+                self.disk_bytes += size;
+                self.pending.clear();
+                self.pending_bytes = 0;
+            }
+            Err(e) => {
+                // Stop spilling rather than retry on every read; memory still holds the tail.
+                log::warn!("scrollback for {}: {e}", self.meta.session_id);
+                self.failed = true;
+                return;
+            }
+        }
+        let dropped_before = self.meta.dropped;
+        while self.disk_bytes > self.max_bytes && self.pages.len() > 1 {
+            if let Some((old, size)) = self.pages.pop_front() {
+                let _ = fs::remove_file(self.dir.join(page_name(old)));
+                self.disk_bytes -= size;
+                self.meta.dropped = true;
+            }
+        }
+        if self.meta.dropped != dropped_before {
+            let _ = self.write_meta();
+        }
+    }
+
+    /// The session ended: write out `remaining` (what was still in memory) and close the spool.
+    pub fn finish(mut self, remaining: impl IntoIterator<Item = TranscriptChunk>) {
+        for chunk in remaining {
+            self.pending_bytes += chunk.text.len();
+            self.pending.push(chunk);
+            if self.pending_bytes >= PAGE_BYTES {
+                self.flush();
+            }
+        }
+        self.flush();
+        self.meta.ended_at = Some(timestamp::now_millis());
+        if let Err(e) = self.write_meta() {
+            log::warn!("scrollback for {}: {e}", self.meta.session_id);
+        }
+    }
+}
+
+/// Write `chunks` as a gzip-compressed JSON array of `[at, text]`; returns the file size.
+fn write_page(path: &Path, chunks: &[TranscriptChunk]) -> io::Result<u64> {
+    let rows: Vec<(i64, &str)> = chunks.iter().map(|c| (c.at, c.text.as_str())).collect();
+    let mut encoder = GzEncoder::new(fs::File::create(path)?, Compression::fast());
+    serde_json::to_writer(&mut encoder, &rows).map_err(io::Error::other)?;
+    encoder.finish()?.flush()?;
+    Ok(fs::metadata(path)?.len())
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpooledSession {
+    pub session_id: String,
+    pub kind: TerminalKind,
+    pub environment_tag: String,
+    pub title: Option<String>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub started_at: i64,
+    /// `None` while the session is live (or if the app quit before it ended).
+    pub ended_at: Option<String>,
+    pub pages: usize,
+    /// Compressed size on disk.
+    pub bytes: u64,
+    /// The oldest output was deleted to stay within the limit.
+    pub dropped: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackPage {
+    pub session_id: String,
+    /// 0 is the oldest page still kept.
+    pub page: usize,
+    pub pages: usize,
+    pub text: String,
+    /// Epoch seconds of the page's first and last output.
+    pub from_at: i64,
+    pub to_at: i64,
+}
+
+fn read_meta(dir: &Path) -> Option<SpoolMeta> {
+    serde_json::from_slice(&fs::read(dir.join(META_FILE)).ok()?).ok()
+}
+
+/// Sessions with scrollback on disk, newest first.
+pub fn list(root: &Path) -> Vec<SpooledSession> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut sessions: Vec<SpooledSession> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let meta = read_meta(&dir)?;
+            let seqs = page_seqs(&dir);
+            let bytes = seqs
+                .iter()
+                .filter_map(|s| fs::metadata(dir.join(page_name(*s))).ok())
+                .map(|m| m.len())
+                .sum();
+            Some(SpooledSession {
+                session_id: meta.session_id,
+                kind: meta.kind,
+                environment_tag: meta.environment_tag,
+                title: meta.title,
+                started_at: meta.started_at,
+                ended_at: meta.ended_at.map(timestamp::to_iso),
+                pages: seqs.len(),
+                bytes,
+                dropped: meta.dropped,
+            })
+        })
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+    sessions
+}
+
+fn read_rows(path: &Path) -> io::Result<Vec<TranscriptChunk>> {
+    let mut json = Vec::new();
+    GzDecoder::new(fs::File::open(path)?).read_to_end(&mut json)?;
+    let rows: Vec<(i64, String)> = serde_json::from_slice(&json).map_err(io::Error::other)?;
+    Ok(rows.into_iter().map(|(at, text)| TranscriptChunk { at, text }).collect())
+}
+
+pub fn read_page(root: &Path, session_id: &str, page: usize) -> Result<ScrollbackPage, String> {
+    if !safe_id(session_id) {
+        return Err("invalid session id".to_string());
+    }
+    let dir = root.join(session_id);
+    let seqs = page_seqs(&dir);
+    let seq = seqs
+        .get(page)
+        .ok_or_else(|| format!("no page {page} (this session has {})", seqs.len()))?;
+    let rows = read_rows(&dir.join(page_name(*seq))).map_err(|e| format!("page {page} is damaged: {e}"))?;
+    Ok(ScrollbackPage {
+        session_id: session_id.to_string(),
+        page,
+        pages: seqs.len(),
+        from_at: rows.first().map_or(0, |r| r.at),
+        to_at: rows.last().map_or(0, |r| r.at),
+        text: rows.into_iter().map(|r| r.text).collect(),
+    })
+}
+
+/// A session's pages on disk, for reading them in order.
+pub struct Spilled {
+    dir: PathBuf,
+    /// Sequence numbers, oldest first.
+    seqs: Vec<u64>,
+    /// The session has ended and everything it kept is in the pages.
+    pub ended: bool,
+    /// Older pages were deleted to stay within the limit.
+    pub dropped: bool,
+}
+
+impl Spilled {
+    /// `None` when the session never spilled.
+    pub fn open(root: &Path, session_id: &str) -> Option<Self> {
+        if !safe_id(session_id) {
+            return None;
+        }
+        let dir = root.join(session_id);
+        let meta = read_meta(&dir)?;
+        Some(Self {
+            seqs: page_seqs(&dir),
+            dir,
+            ended: meta.ended_at.is_some(),
+            dropped: meta.dropped,
+        })
+    }
+
+    /// Keep only pages up to sequence number `last`.
+    pub fn through(mut self, last: u64) -> Self {
+        self.seqs.retain(|seq| *seq <= last);
+        self
+    }
+
+    pub fn pages(&self) -> usize {
+        self.seqs.len()
+    }
+
+    /// Page `page`'s output (0 is the oldest); `None` if it's been dropped since it was listed.
+    pub fn read(&self, page: usize) -> Result<Option<Vec<TranscriptChunk>>, String> {
+        let Some(seq) = self.seqs.get(page) else {
+            return Ok(None);
+        };
+        match read_rows(&self.dir.join(page_name(*seq))) {
+            Ok(rows) => Ok(Some(rows)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("page {page} is damaged: {e}")),
+        }
+    }
+}
+
+pub fn delete(root: &Path, session_id: &str) -> Result<(), String> {
+    if !safe_id(session_id) {
+        return Err("invalid session id".to_string());
+    }
+    match fs::remove_dir_all(root.join(session_id)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Delete scrollback of sessions that ended more than `retention_days` ago. Run at startup, when
+/// no session is live, so one that never recorded an end (the app quit) counts as ended when its
+/// files were last written.
+pub fn prune(root: &Path, retention_days: u32) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let cutoff = SystemTime::now() - Duration::from_secs(u64::from(retention_days) * 86_400);
+    let cutoff_ms = timestamp::now_millis() - i64::from(retention_days) * 86_400_000;
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let expired = match read_meta(&dir).and_then(|m| m.ended_at) {
+            Some(ended_at) => ended_at < cutoff_ms,
+            None => fs::metadata(&dir).and_then(|m| m.modified()).is_ok_and(|t| t < cutoff),
+        };
+        if expired {
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+}
+
+fn modified_millis(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_millis() as i64)
+}
+
+/// While sessions may be live: delete scrollback of sessions that ended more than
+/// `retention_days` ago, then of the oldest ended sessions until the whole directory fits in
+/// `max_bytes`. Sessions in `live` are never touched; one that isn't live and never recorded an
+/// end (the app quit) counts as ended when its files were last written. Returns (sessions, bytes)
+/// deleted.
+pub fn enforce(root: &Path, retention_days: u32, max_bytes: Option<u64>, live: &[String]) -> (usize, u64) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return (0, 0);
+    };
+    let cutoff_ms = timestamp::now_millis() - i64::from(retention_days) * 86_400_000;
+    let mut total = 0;
+    // (started_at, ended_at, bytes, dir) of sessions that may go.
+    let mut ended = Vec::new();
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let Some(meta) = read_meta(&dir) else {
+            continue;
+        };
+        let bytes: u64 = fs::read_dir(&dir)
+            .map(|files| files.flatten().filter_map(|f| f.metadata().ok()).map(|m| m.len()).sum())
+            .unwrap_or(0);
+        total += bytes;
+        if live.contains(&meta.session_id) {
+            continue;
+        }
+        let ended_at = meta.ended_at.or_else(|| modified_millis(&dir)).unwrap_or(0);
+        ended.push((meta.started_at, ended_at, bytes, dir));
+    }
+    ended.sort_by_key(|(started_at, ..)| *started_at);
+
+    let mut purged = (0, 0);
+    for (_, ended_at, bytes, dir) in ended {
+        let over = max_bytes.is_some_and(|max| total > max);
+        if ended_at >= cutoff_ms && !over {
+            continue;
+        }
+        if fs::remove_dir_all(&dir).is_ok() {
+            total -= bytes;
+            purged.0 += 1;
+            purged.1 += bytes;
+        }
+    }
+    purged
+}
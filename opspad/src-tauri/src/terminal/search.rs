@@ -0,0 +1,241 @@
+//! Search through everything kept of a session's output (`terminal_search`): the pages spilled
+//! to disk, then the in-memory transcript, so output long gone from xterm.js's buffer can still
+//! be found. Ended sessions are searchable for as long as their pages are kept.
+//!
+//! Output is searched line by line, as `session_export_markdown` shows it (ANSI stripped,
+//! carriage returns replayed). Each matching line comes back with a few lines around it, where
+//! its output arrived and its match ranges.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::terminal::scrollback::Spilled;
+use crate::terminal::session_manager::TranscriptChunk;
+use crate::terminal::{TerminalError, TerminalManager};
+use crate::transcript;
+
+const DEFAULT_CONTEXT: usize = 2;
+const MAX_CONTEXT: usize = 20;
+const DEFAULT_MAX_RESULTS: usize = 200;
+const MAX_RESULTS: usize = 2000;
+/// Compiled size limit for patterns, so a pathological one fails instead of eating memory.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    /// Treat the pattern as a regular expression rather than literal text.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Lines shown before and after each match (default 2, at most 20).
+    pub context: Option<usize>,
+    /// Matching lines returned (default 200, at most 2000), oldest first.
+    pub max_results: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// Line number in the output searched, from 0 at the oldest line kept.
+    pub line: u64,
+    /// The spilled page the line starts in (`scrollback_page`); `None` when it's still in memory.
+    pub page: Option<usize>,
+    /// Epoch seconds the line's output arrived.
+    pub at: i64,
+    pub text: String,
+    /// `[start, end)` of each match in `text`, in UTF-16 units as JavaScript indexes strings.
+    pub ranges: Vec<[usize; 2]>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub matches: Vec<SearchMatch>,
+    pub lines_searched: u64,
+    /// Stopped at `max_results`; later output wasn't searched.
+    pub limited: bool,
+    /// The session's oldest output is gone (spilling was off, or pages were dropped at the limit).
+    pub incomplete: bool,
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// Collects matches while lines are fed in order, filling in context as later lines arrive.
+struct Searcher {
+    re: Regex,
+    context: usize,
+    max_results: usize,
+    recent: VecDeque<String>,
+    /// Matches still waiting for lines after them.
+    open: Vec<usize>,
+    result: SearchResult,
+}
+
+impl Searcher {
+    fn done(&self) -> bool {
+        self.result.limited && self.open.is_empty()
+    }
+
+    fn line(&mut self, raw: &str, at: i64, page: Option<usize>) {
+        let text = transcript::clean(raw);
+        let context = self.context;
+        let matches = &mut self.result.matches;
+        self.open.retain(|i| {
+            matches[*i].after.push(text.clone());
+            matches[*i].after.len() < context
+        });
+        let number = self.result.lines_searched;
+        self.result.lines_searched += 1;
+        if !self.result.limited {
+            let ranges: Vec<[usize; 2]> = self
+                .re
+                .find_iter(&text)
+                .filter(|m| !m.is_empty())
+                .map(|m| {
+                    let start = utf16_len(&text[..m.start()]);
+                    [start, start + utf16_len(m.as_str())]
+                })
+                .collect();
+            if !ranges.is_empty() {
+                matches.push(SearchMatch {
+                    line: number,
+                    page,
+                    at,
+                    text: text.clone(),
+                    ranges,
+                    before: self.recent.iter().cloned().collect(),
+                    after: Vec::new(),
+                });
+                if context > 0 {
+                    self.open.push(matches.len() - 1);
+                }
+                self.result.limited = matches.len() >= self.max_results;
+            }
+        }
+        if context > 0 {
+            if self.recent.len() == context {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(text);
+        }
+    }
+}
+
+/// Splits chunks into lines, carrying a partial line over to the next chunk (and page).
+struct Lines {
+    partial: String,
+    /// When and where the partial line started.
+    started: Option<(i64, Option<usize>)>,
+}
+
+impl Lines {
+    fn feed(&mut self, searcher: &mut Searcher, chunks: &[TranscriptChunk], page: Option<usize>) {
+        for chunk in chunks {
+            let mut rest = chunk.text.as_str();
+            while let Some(end) = rest.find('\n') {
+                if searcher.done() {
+                    return;
+                }
+                self.partial.push_str(&rest[..end]);
+                let (at, page) = self.started.take().unwrap_or((chunk.at, page));
+                searcher.line(&self.partial, at, page);
+                self.partial.clear();
+                rest = &rest[end + 1..];
+            }
+            if !rest.is_empty() {
+                self.started.get_or_insert((chunk.at, page));
+                self.partial.push_str(rest);
+            }
+        }
+    }
+
+    fn finish(self, searcher: &mut Searcher) {
+        if let Some((at, page)) = self.started {
+            if !searcher.done() {
+                searcher.line(&self.partial, at, page);
+            }
+        }
+    }
+}
+
+pub fn search(
+    terminal: &TerminalManager,
+    scrollback_root: &Path,
+    session_id: &str,
+    pattern: &str,
+    opts: SearchOptions,
+) -> Result<SearchResult, String> {
+    if pattern.is_empty() {
+        return Err("pattern is required".to_string());
+    }
+    let source = if opts.regex { pattern.to_string() } else { regex::escape(pattern) };
+    let re = RegexBuilder::new(&source)
+        .case_insensitive(!opts.case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("invalid pattern: {e}"))?;
+
+    let live = match terminal.transcript(session_id) {
+        Ok(t) => Some(t),
+        Err(TerminalError::NotFound) => None,
+        Err(e) => return Err(e.to_string()),
+    };
+    let spilled = Spilled::open(scrollback_root, session_id);
+    // A live session whose spool has been closed has just ended: its pages hold everything.
+    let (spilled, live) = match (spilled, live) {
+        (None, None) => return Err("no output is kept for this session".to_string()),
+        (Some(s), Some(t)) => match t.spilled_through {
+            Some(last) => (Some(s.through(last)), Some(t)),
+            None if s.ended => (Some(s), None),
+            None => (None, Some(t)),
+        },
+        other => other,
+    };
+    let incomplete = match (&spilled, &live) {
+        (Some(s), _) => s.dropped,
+        (None, Some(t)) => t.truncated,
+        (None, None) => false,
+    };
+
+    let mut searcher = Searcher {
+        re,
+        context: opts.context.unwrap_or(DEFAULT_CONTEXT).min(MAX_CONTEXT),
+        max_results: opts.max_results.unwrap_or(DEFAULT_MAX_RESULTS).clamp(1, MAX_RESULTS),
+        recent: VecDeque::new(),
+        open: Vec::new(),
+        result: SearchResult {
+            matches: Vec::new(),
+            lines_searched: 0,
+            limited: false,
+            incomplete,
+        },
+    };
+    let mut lines = Lines {
+        partial: String::new(),
+        started: None,
+    };
+    if let Some(spilled) = &spilled {
+        for page in 0..spilled.pages() {
+            if searcher.done() {
+                break;
+            }
+            if let Some(chunks) = spilled.read(page)? {
+                lines.feed(&mut searcher, &chunks, Some(page));
+            }
+        }
+    }
+    if let Some(live) = &live {
+        lines.feed(&mut searcher, &live.chunks, None);
+    }
+    lines.finish(&mut searcher);
+    Ok(searcher.result)
+}
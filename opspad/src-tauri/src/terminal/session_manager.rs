@@ -1,8 +1,17 @@
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
+use crate::db::ServerInfo;
+use crate::terminal::boundaries::CommandRecord;
+use crate::terminal::highlight::Highlighter;
+use crate::terminal::scrollback::SpillTarget;
 use crate::terminal::TerminalError;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum TerminalKind {
     Local,
     Ssh,
@@ -16,6 +25,12 @@ pub struct SpawnSpec {
     pub initial_rows: Option<u16>,
     pub program: String,
     pub args: Vec<String>,
+    /// Written to the terminal ahead of the process's own output (see `terminal::banner`).
+    pub banner: Option<String>,
+    /// Extra environment for the process (on top of the app's own).
+    pub env: Vec<(String, String)>,
+    /// No desktop notification when it ends (benchmark sessions).
+    pub quiet: bool,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -26,9 +41,112 @@ pub struct WriteMeta {
     pub origin: Option<String>,
 }
 
+/// Non-secret facts about a live session (no command text or output).
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub kind: TerminalKind,
+    pub environment_tag: String,
+    pub cols: u16,
+    pub rows: u16,
+    /// Epoch seconds.
+    pub started_at: i64,
+    pub last_commanddock_at: Option<i64>,
+    /// The shell is running as root, as far as can be told (see `terminal:privileged`).
+    pub privileged: bool,
+    pub last_output_at: Option<i64>,
+    pub last_input_at: Option<i64>,
+    /// When input was first written after the last output; `None` once output follows it.
+    pub awaiting_output_since: Option<i64>,
+    /// Given by the user (`terminal_rename`).
+    pub title: Option<String>,
+    /// For ssh sessions, once the post-connect probe has run.
+    pub server_info: Option<ServerInfo>,
+    /// The shell has sent prompt marks (OSC 133), so it runs OpsPad's or a compatible integration.
+    pub shell_integration: bool,
+    /// Writes are refused until `terminal_set_readonly` turns it off.
+    pub readonly: bool,
+    /// For ssh sessions, the latest round-trip measurement (see `terminal:latency`).
+    pub latency: Option<SessionLatency>,
+}
+
+/// One round-trip measurement of an ssh session, split so a slow session can be put down to the
+/// network or to the remote host.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLatency {
+    /// TCP connect time to the host's ssh port; `None` when the session goes through a jump host
+    /// or proxy, where a direct connect says nothing about its route.
+    pub network_ms: Option<u64>,
+    /// Time for the server to run a no-op command over an established connection; covers the
+    /// network plus the remote sshd and login shell.
+    pub remote_ms: Option<u64>,
+    /// Epoch seconds.
+    pub measured_at: i64,
+    /// Why a measurement is missing.
+    pub error: Option<String>,
+}
+
+/// A piece of session output and when it arrived (epoch seconds).
+#[derive(Clone, Debug)]
+pub struct TranscriptChunk {
+    pub at: i64,
+    pub text: String,
+}
+
+/// The session's recent output, oldest first, as kept in memory for export.
+#[derive(Clone, Debug, Default)]
+pub struct Transcript {
+    pub chunks: Vec<TranscriptChunk>,
+    /// Older output was dropped to stay within the size cap.
+    pub truncated: bool,
+    /// The newest page the session had spilled to disk when this was taken (0 before the first);
+    /// `None` when it isn't spilling. Pages after it repeat output included here.
+    pub spilled_through: Option<u64>,
+}
+
 pub trait TerminalSessionManager: Send + Sync {
     fn spawn(&self, app: AppHandle, spec: SpawnSpec) -> Result<String, TerminalError>;
     fn write(&self, session_id: &str, data: &str, meta: WriteMeta) -> Result<(), TerminalError>;
+    /// Type `secret` followed by Enter. Later output that echoes it back is masked before it's
+    /// kept or sent anywhere (best effort: an echo split across reads gets through).
+    fn write_secret(&self, session_id: &str, secret: &str) -> Result<(), TerminalError>;
+    /// Refuse (or allow again) every write to the session, typed, CommandDock or secret.
+    fn set_readonly(&self, session_id: &str, readonly: bool) -> Result<(), TerminalError>;
+    /// Set (or with `None`, clear) the user's title for the session.
+    fn rename(&self, session_id: &str, title: Option<String>) -> Result<(), TerminalError>;
+    /// Credit the next command the shell marks as started to a CommandDock history entry.
+    fn tag_next_command(&self, session_id: &str, history_id: &str) -> Result<(), TerminalError>;
+    /// Commands the shell has marked as finished, oldest first (needs shell integration).
+    fn commands(&self, session_id: &str) -> Result<Vec<CommandRecord>, TerminalError>;
+    /// Attach what the post-connect probe found on the session's server.
+    fn set_server_info(&self, session_id: &str, info: ServerInfo) -> Result<(), TerminalError>;
+    /// Attach the latest latency measurement of the session.
+    fn set_latency(&self, session_id: &str, latency: SessionLatency) -> Result<(), TerminalError>;
+    /// Where sessions spawned from now on spill output evicted from their transcript.
+    fn set_spill(&self, target: Option<SpillTarget>);
+    /// Replace the rules whose matches are sent along with the session's output.
+    fn set_highlighter(&self, session_id: &str, highlighter: Option<Arc<Highlighter>>) -> Result<(), TerminalError>;
     fn resize(&self, session_id: &str, cols: u16, rows: u16) -> Result<(), TerminalError>;
     fn close(&self, session_id: &str) -> Result<(), TerminalError>;
+    /// Receive a copy of the session's output (lossy UTF-8 chunks) from now on.
+    ///
+    /// The channel disconnects when the session ends.
+    fn subscribe_output(&self, session_id: &str) -> Result<Receiver<String>, TerminalError>;
+    /// Send the session's output only to `window` (`None`: every window). Output is held back
+    /// until that window calls [`attach`](Self::attach), so nothing is lost while it loads.
+    fn set_window(&self, session_id: &str, window: Option<String>) -> Result<(), TerminalError>;
+    /// Hold every session's output back from the UI (including sessions spawned meanwhile), or
+    /// send what was held and stream live again. Command boundary events are skipped while
+    /// hidden.
+    fn set_output_hidden(&self, app: &AppHandle, hidden: bool);
+    /// Flush output held since [`set_window`](Self::set_window) to `window`, then stream live.
+    fn attach(&self, app: &AppHandle, session_id: &str, window: &str) -> Result<(), TerminalError>;
+    /// The window the session is routed to, if it's been moved out of the main one.
+    fn window(&self, session_id: &str) -> Result<Option<String>, TerminalError>;
+    fn list(&self) -> Vec<SessionSummary>;
+    /// The session's output so far (capped; see [`Transcript::truncated`]). With spilling on,
+    /// output already in scrollback pages isn't included.
+    fn transcript(&self, session_id: &str) -> Result<Transcript, TerminalError>;
 }
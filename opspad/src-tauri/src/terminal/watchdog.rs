@@ -0,0 +1,206 @@
+//! Hung session watchdog.
+//!
+//! A session that was sent input and has printed nothing since for `quiet_minutes` is flagged
+//! with a `terminal:suspect_hung` event: even a busy command's terminal echoes keystrokes, so
+//! silence after typing usually means the connection or the remote side has stalled. The UI
+//! then offers `terminal_probe`, which types a newline (or a harmless `echo`) and waits briefly
+//! for any reply; a probe that gets none flags the session again and suggests reconnecting.
+//!
+//! Programs that read keys without echoing them (a pager, say) can be flagged falsely, which is
+//! why the watchdog only ever suggests.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::db::Db;
+use crate::terminal::TerminalManager;
+use crate::{timestamp, AppState};
+
+const SETTINGS_KEY: &str = "terminal.watchdog";
+const WATCH_INTERVAL: Duration = Duration::from_secs(15);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_QUIET_MINUTES: u32 = 120;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Minutes without output after input before a session is flagged.
+    #[serde(default = "default_quiet_minutes")]
+    pub quiet_minutes: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_quiet_minutes() -> u32 {
+    2
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            quiet_minutes: default_quiet_minutes(),
+        }
+    }
+}
+
+impl WatchdogConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if !(1..=MAX_QUIET_MINUTES).contains(&self.quiet_minutes) {
+            return Err(format!("quiet minutes must be between 1 and {MAX_QUIET_MINUTES}"));
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(SETTINGS_KEY, &json).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HangReason {
+    /// Input went unanswered for `quiet_minutes`.
+    NoOutput,
+    /// `terminal_probe` got no reply.
+    ProbeFailed,
+}
+
+/// Payload of `terminal:suspect_hung`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspectHungEvent {
+    pub session_id: String,
+    pub reason: HangReason,
+    /// Epoch seconds of the unanswered input (or the probe).
+    pub since: i64,
+    pub last_output_at: Option<i64>,
+    pub environment_tag: String,
+}
+
+/// Flag sessions whose input has gone unanswered for too long; each stall is flagged once.
+pub fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        // Session id -> the `awaiting_output_since` already flagged.
+        let mut flagged: HashMap<String, i64> = HashMap::new();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let state = app.state::<Arc<AppState>>();
+            let config = WatchdogConfig::load(&state.db);
+            if !config.enabled {
+                flagged.clear();
+                continue;
+            }
+            let now = timestamp::now_millis() / 1000;
+            let quiet_secs = i64::from(config.quiet_minutes) * 60;
+            let sessions = state.terminal.list();
+            flagged.retain(|id, since| sessions.iter().any(|s| &s.session_id == id && s.awaiting_output_since == Some(*since)));
+            for s in sessions {
+                let Some(since) = s.awaiting_output_since else {
+                    continue;
+                };
+                if now - since < quiet_secs || flagged.contains_key(&s.session_id) {
+                    continue;
+                }
+                flagged.insert(s.session_id.clone(), since);
+                let _ = app.emit(
+                    "terminal:suspect_hung",
+                    SuspectHungEvent {
+                        session_id: s.session_id,
+                        reason: HangReason::NoOutput,
+                        since,
+                        last_output_at: s.last_output_at,
+                        environment_tag: s.environment_tag,
+                    },
+                );
+            }
+        }
+    });
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeMode {
+    /// Press Enter: any shell or program reprints something.
+    #[default]
+    Newline,
+    /// Type `echo <token>`: tells a shell that runs it from a connection that merely echoes keys.
+    Echo,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    /// Any output came back.
+    pub responded: bool,
+    /// Echo probes only: the token was printed by the command, not just echoed as typed.
+    pub executed: Option<bool>,
+    /// Until the first output.
+    pub latency_ms: Option<u64>,
+    pub suggest_reconnect: bool,
+}
+
+/// Probe a session once. No reply flags it again (`ProbeFailed`).
+pub fn probe(app: &AppHandle, terminal: &TerminalManager, session_id: &str, mode: ProbeMode) -> Result<ProbeResult, String> {
+    let output = terminal.subscribe_output(session_id).map_err(|e| e.to_string())?;
+    let token = format!("opspad-probe-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    // The leading space keeps the echo out of history where the shell ignores such lines.
+    let input = match mode {
+        ProbeMode::Newline => "\r".to_string(),
+        ProbeMode::Echo => format!(" echo {token}\r"),
+    };
+    let sent = Instant::now();
+    terminal.write(session_id, &input).map_err(|e| e.to_string())?;
+
+    let deadline = sent + PROBE_TIMEOUT;
+    let mut latency_ms = None;
+    let mut seen = String::new();
+    // Stops at the deadline, or when the session ends.
+    while let Ok(chunk) = output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        latency_ms.get_or_insert(sent.elapsed().as_millis() as u64);
+        if matches!(mode, ProbeMode::Newline) {
+            break;
+        }
+        seen.push_str(&chunk);
+        // Once as typed, once as printed.
+        if seen.matches(&token).count() >= 2 {
+            break;
+        }
+    }
+
+    let responded = latency_ms.is_some();
+    if !responded {
+        let summary = terminal.list().into_iter().find(|s| s.session_id == session_id);
+        let _ = app.emit(
+            "terminal:suspect_hung",
+            SuspectHungEvent {
+                session_id: session_id.to_string(),
+                reason: HangReason::ProbeFailed,
+                since: timestamp::now_millis() / 1000,
+                last_output_at: summary.as_ref().and_then(|s| s.last_output_at),
+                environment_tag: summary.map(|s| s.environment_tag).unwrap_or_default(),
+            },
+        );
+    }
+    Ok(ProbeResult {
+        responded,
+        executed: matches!(mode, ProbeMode::Echo).then(|| seen.matches(&token).count() >= 2),
+        latency_ms,
+        suggest_reconnect: !responded,
+    })
+}
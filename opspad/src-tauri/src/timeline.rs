@@ -0,0 +1,339 @@
+//! One chronological record of what happened over a time range or during an incident, for
+//! postmortems: CommandDock history, terminal sessions opening and closing, timeline marks,
+//! alerts firing and resolving, and confirmed destructive commands, merged by time. Exported as
+//! JSON or Markdown, redacted like anything else that leaves the app.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{ConfirmationQuery, DestructivePolicy, Incident};
+use crate::redact::Redactor;
+use crate::timestamp::{self, format_time, format_utc};
+use crate::AppState;
+
+/// Rows read from each source; a timeline that hits it is marked truncated.
+const SOURCE_LIMIT: i64 = 10_000;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineEventKind {
+    Command,
+    SessionOpened,
+    SessionClosed,
+    Mark,
+    AlertFired,
+    AlertResolved,
+    Confirmation,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEvent {
+    #[serde(with = "crate::timestamp::iso")]
+    pub at: i64,
+    pub kind: TimelineEventKind,
+    /// The terminal scope ("ssh:<host_id>", "local") or, for alerts, the host's.
+    pub scope: Option<String>,
+    /// The host's label, when the event belongs to a saved host.
+    pub host_label: Option<String>,
+    pub environment_tag: Option<String>,
+    pub summary: String,
+    pub detail: Option<String>,
+    /// The id of the history entry, mark, alert or confirmation (the session's, for session events).
+    pub source_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    #[serde(with = "crate::timestamp::iso")]
+    pub since: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub until: i64,
+    pub incident: Option<Incident>,
+    #[serde(with = "crate::timestamp::iso")]
+    pub exported_at: i64,
+    /// Some source had more events in the range than were read.
+    pub truncated: bool,
+    pub events: Vec<TimelineEvent>,
+}
+
+/// Host labels by id, looked up once each.
+struct Hosts<'a> {
+    state: &'a AppState,
+    labels: BTreeMap<String, Option<String>>,
+}
+
+impl Hosts<'_> {
+    fn label(&mut self, host_id: &str) -> Option<String> {
+        let state = self.state;
+        self.labels
+            .entry(host_id.to_string())
+            .or_insert_with(|| state.db.hosts_get(host_id).ok().flatten().map(|h| h.label))
+            .clone()
+    }
+
+    fn for_scope(&mut self, scope: Option<&str>) -> Option<String> {
+        self.label(scope?.strip_prefix("ssh:")?)
+    }
+}
+
+/// `since` and `until` are inclusive epoch seconds; events are stamped in milliseconds.
+fn collect(state: &AppState, since: i64, until: i64, incident: Option<Incident>) -> Result<Timeline, String> {
+    let db = &state.db;
+    let (from, to) = (since * 1000, until * 1000 + 999);
+    let mut hosts = Hosts { state, labels: BTreeMap::new() };
+    let mut events = Vec::new();
+    let mut truncated = false;
+
+    let history = db.dock_history_between(from, to, SOURCE_LIMIT).map_err(|e| e.to_string())?;
+    truncated |= history.len() as i64 >= SOURCE_LIMIT;
+    for h in history {
+        let mut outcome = Vec::new();
+        if let Some(status) = h.exit_status {
+            outcome.push(format!("exit {status}"));
+        }
+        if let Some(ms) = h.duration_ms {
+            outcome.push(format!("{:.1}s", ms as f64 / 1000.0));
+        }
+        if let Some(reason) = &h.policy_reason {
+            outcome.push(format!("reason: {reason}"));
+        }
+        events.push(TimelineEvent {
+            at: h.created_at,
+            kind: TimelineEventKind::Command,
+            host_label: hosts.for_scope(h.scope.as_deref()),
+            scope: h.scope,
+            environment_tag: Some(h.environment_tag),
+            summary: h.command_text,
+            detail: (!outcome.is_empty()).then(|| outcome.join(", ")),
+            source_id: h.id,
+        });
+    }
+
+    let sessions = db.session_events_between(from, to, SOURCE_LIMIT).map_err(|e| e.to_string())?;
+    truncated |= sessions.len() as i64 >= SOURCE_LIMIT;
+    for s in sessions {
+        let (kind, summary) = match s.kind.as_str() {
+            "open" => (TimelineEventKind::SessionOpened, "Session opened"),
+            _ => (TimelineEventKind::SessionClosed, "Session closed"),
+        };
+        events.push(TimelineEvent {
+            at: s.at,
+            kind,
+            host_label: hosts.for_scope(Some(&s.scope)),
+            scope: Some(s.scope),
+            environment_tag: s.environment_tag,
+            summary: summary.to_string(),
+            detail: None,
+            source_id: s.session_id,
+        });
+    }
+
+    let marks = db.terminal_marks_between(from, to, SOURCE_LIMIT).map_err(|e| e.to_string())?;
+    truncated |= marks.len() as i64 >= SOURCE_LIMIT;
+    for m in marks {
+        events.push(TimelineEvent {
+            at: m.created_at,
+            kind: TimelineEventKind::Mark,
+            host_label: hosts.for_scope(m.scope.as_deref()),
+            scope: m.scope,
+            environment_tag: None,
+            summary: m.label,
+            detail: None,
+            source_id: m.id,
+        });
+    }
+
+    let alerts = db.alerts_between(from, to, SOURCE_LIMIT).map_err(|e| e.to_string())?;
+    truncated |= alerts.len() as i64 >= SOURCE_LIMIT;
+    for a in alerts {
+        let host_label = a.host_id.as_deref().and_then(|id| hosts.label(id));
+        let scope = a.host_id.as_ref().map(|id| format!("ssh:{id}"));
+        let subject = if a.subject.is_empty() { String::new() } else { format!(" ({})", a.subject) };
+        if (from..=to).contains(&a.created_at) {
+            events.push(TimelineEvent {
+                at: a.created_at,
+                kind: TimelineEventKind::AlertFired,
+                scope: scope.clone(),
+                host_label: host_label.clone(),
+                environment_tag: None,
+                summary: format!("{}{subject}", a.rule_name),
+                detail: Some(a.message.clone()),
+                source_id: a.id.clone(),
+            });
+        }
+        if let Some(resolved_at) = a.resolved_at.filter(|at| (since..=until).contains(at)) {
+            events.push(TimelineEvent {
+                at: resolved_at * 1000,
+                kind: TimelineEventKind::AlertResolved,
+                scope,
+                host_label,
+                environment_tag: None,
+                summary: format!("{}{subject}", a.rule_name),
+                detail: None,
+                source_id: a.id,
+            });
+        }
+    }
+
+    let query = ConfirmationQuery {
+        since: Some(since),
+        until: Some(until),
+        limit: Some(SOURCE_LIMIT),
+        ..Default::default()
+    };
+    let confirmations = db.confirmations_list(&query, None).map_err(|e| e.to_string())?.items;
+    truncated |= confirmations.len() as i64 >= SOURCE_LIMIT;
+    for c in confirmations {
+        let how = match c.policy {
+            DestructivePolicy::TypedName => "typed the environment name",
+            DestructivePolicy::Reason => "gave a reason",
+            _ => "confirmed",
+        };
+        let mut detail = format!("{} {how}", c.os_user);
+        if let Some(text) = c.confirmation_text.as_deref().filter(|t| !t.is_empty()) {
+            detail.push_str(&format!(": {text}"));
+        }
+        let host_label = match c.host_id.as_deref() {
+            Some(id) => hosts.label(id),
+            None => hosts.for_scope(c.scope.as_deref()),
+        };
+        events.push(TimelineEvent {
+            at: c.confirmed_at * 1000,
+            kind: TimelineEventKind::Confirmation,
+            scope: c.scope,
+            host_label,
+            environment_tag: Some(c.environment_tag),
+            summary: c.dock_command_title.unwrap_or(c.command_text),
+            detail: Some(detail),
+            source_id: c.id,
+        });
+    }
+
+    // Stable, so events from the same second keep the order of the sources above.
+    events.sort_by_key(|e| e.at);
+    Ok(Timeline {
+        since: from,
+        until: to,
+        incident,
+        exported_at: timestamp::now_millis(),
+        truncated,
+        events,
+    })
+}
+
+fn kind_label(kind: TimelineEventKind) -> &'static str {
+    match kind {
+        TimelineEventKind::Command => "Command",
+        TimelineEventKind::SessionOpened => "Session opened",
+        TimelineEventKind::SessionClosed => "Session closed",
+        TimelineEventKind::Mark => "Mark",
+        TimelineEventKind::AlertFired => "Alert fired",
+        TimelineEventKind::AlertResolved => "Alert resolved",
+        TimelineEventKind::Confirmation => "Confirmed",
+    }
+}
+
+fn inline_code(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    let quote = if text.contains('`') { "``" } else { "`" };
+    format!("{quote} {text} {quote}")
+}
+
+fn markdown(timeline: &Timeline) -> String {
+    let mut md = match &timeline.incident {
+        Some(incident) => format!("# Incident timeline: {}\n\n", incident.title),
+        None => "# Timeline\n\n".to_string(),
+    };
+    if let Some(reference) = timeline.incident.as_ref().and_then(|i| i.reference.as_deref()) {
+        md.push_str(&format!("- **Reference:** {reference}\n"));
+    }
+    md.push_str(&format!("- **From:** {} UTC\n", format_utc(timeline.since / 1000)));
+    md.push_str(&format!("- **To:** {} UTC\n", format_utc(timeline.until / 1000)));
+    md.push_str(&format!("- **Exported:** {} UTC\n", format_utc(timeline.exported_at / 1000)));
+    if timeline.truncated {
+        md.push_str("- _Some events were left out; export a shorter range to see them all._\n");
+    }
+    if timeline.events.is_empty() {
+        md.push_str("\n_Nothing was recorded in this range._\n");
+        return md;
+    }
+
+    let mut day = String::new();
+    for event in &timeline.events {
+        let date = format_utc(event.at / 1000)[..10].to_string();
+        if date != day {
+            md.push_str(&format!("\n## {date}\n\n"));
+            day = date;
+        }
+        let mut line = format!("- **{}** {}", format_time(event.at / 1000), kind_label(event.kind));
+        let place = event.host_label.as_deref().or(event.scope.as_deref());
+        match (place, event.environment_tag.as_deref()) {
+            (Some(place), Some(env)) => line.push_str(&format!(" on {place} ({env})")),
+            (Some(place), None) => line.push_str(&format!(" on {place}")),
+            (None, Some(env)) => line.push_str(&format!(" ({env})")),
+            (None, None) => {}
+        }
+        match event.kind {
+            TimelineEventKind::SessionOpened | TimelineEventKind::SessionClosed => {}
+            TimelineEventKind::Command | TimelineEventKind::Confirmation => {
+                line.push_str(&format!(": {}", inline_code(&event.summary)));
+            }
+            _ => line.push_str(&format!(": {}", event.summary.replace('\n', " "))),
+        }
+        if let Some(detail) = &event.detail {
+            line.push_str(&format!(" — {}", detail.replace('\n', " ")));
+        }
+        md.push_str(&line);
+        md.push('\n');
+    }
+    md
+}
+
+/// The timeline of `since..=until`, or of an incident (through now while it's active), as a
+/// document in `format`.
+pub fn export(
+    state: &AppState,
+    since: Option<i64>,
+    until: Option<i64>,
+    incident_id: Option<&str>,
+    format: TimelineFormat,
+) -> Result<String, String> {
+    let (since, until, incident) = match incident_id {
+        Some(id) => {
+            if since.is_some() || until.is_some() {
+                return Err("give either an incident or a time range, not both".to_string());
+            }
+            let incident = state
+                .db
+                .incidents_get(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "incident not found".to_string())?;
+            (incident.started_at, incident.ended_at.unwrap_or_else(|| timestamp::now_millis() / 1000), Some(incident))
+        }
+        None => {
+            let since = since.ok_or_else(|| "since: required without an incident".to_string())?;
+            (since, until.unwrap_or_else(|| timestamp::now_millis() / 1000), None)
+        }
+    };
+    if until < since {
+        return Err("until: must not be before since".to_string());
+    }
+
+    let timeline = collect(state, since, until, incident)?;
+    let doc = match format {
+        TimelineFormat::Json => serde_json::to_string_pretty(&timeline).map_err(|e| e.to_string())?,
+        TimelineFormat::Markdown => markdown(&timeline),
+    };
+    let redactor = Redactor::new(&state.db.redaction_rules_list().map_err(|e| e.to_string())?);
+    Ok(redactor.redact(doc.trim_end()) + "\n")
+}
@@ -0,0 +1,130 @@
+//! Timestamps. Rows record `created_at`/`updated_at` in epoch milliseconds, and models serialize
+//! them as ISO-8601 UTC strings ("2026-10-16T02:11:05.123Z") so they line up with external logs
+//! without anyone guessing the unit or zone.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Below this, an epoch number is in seconds (a millisecond value this small would be 1973).
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Days since 1970-01-01 -> (year, month, day) (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// The inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_utc(epoch_secs: i64) -> String {
+    let (year, month, day) = civil_from_days(epoch_secs.div_euclid(86_400));
+    let secs = epoch_secs.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// `HH:MM:SS` in UTC.
+pub fn format_time(epoch_secs: i64) -> String {
+    format_utc(epoch_secs)[11..].to_string()
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+pub fn to_iso(epoch_millis: i64) -> String {
+    let utc = format_utc(epoch_millis.div_euclid(1000));
+    format!("{}T{}.{:03}Z", &utc[..10], &utc[11..], epoch_millis.rem_euclid(1000))
+}
+
+/// Epoch milliseconds from an ISO-8601 date-time with a zone: `Z` or `±HH:MM`, fraction optional.
+pub fn parse_iso(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (date, rest) = text.split_once(['T', 't', ' '])?;
+    let mut ymd = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (ymd.next()??, ymd.next()??, ymd.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (time, offset_secs) = if let Some(time) = rest.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let at = rest.rfind(['+', '-'])?;
+        let (hours, minutes) = rest[at + 1..].split_once(':').unwrap_or((&rest[at + 1..], "0"));
+        let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (&rest[..at], if rest.as_bytes()[at] == b'-' { -offset } else { offset })
+    };
+    let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut hms = clock.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute) = (hms.next()??, hms.next()??);
+    let second = hms.next().flatten().unwrap_or(0);
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let millis = match fraction {
+        "" => 0,
+        f if f.bytes().all(|b| b.is_ascii_digit()) => format!("{f:0<3}")[..3].parse::<i64>().ok()?,
+        _ => return None,
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset_secs;
+    Some(secs * 1000 + millis)
+}
+
+/// Epoch milliseconds from a number that may still be in seconds (written before the switch).
+pub fn millis_from_legacy(epoch: i64) -> i64 {
+    if epoch.abs() < MILLIS_THRESHOLD {
+        epoch * 1000
+    } else {
+        epoch
+    }
+}
+
+/// `#[serde(with = "crate::timestamp::iso")]` on an epoch-milliseconds field: written as an ISO
+/// string; read from one, or from a number in seconds or milliseconds.
+pub mod iso {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Number(i64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(epoch_millis: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_iso(*epoch_millis))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(n) => Ok(millis_from_legacy(n)),
+            Raw::Text(s) => parse_iso(&s).ok_or_else(|| serde::de::Error::custom(format!("not an ISO-8601 timestamp: {s}"))),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! One-time codes (TOTP, RFC 6238) from seeds kept in the vault.
+//!
+//! A seed is stored as an `otpauth://totp/...` URI, whatever form it was entered in (the base32
+//! secret shown next to a QR code, or the URI the QR code holds). Codes are computed here, so the
+//! seed itself never goes to the frontend; a code can even be typed into a session without
+//! passing through it (`terminal_type_totp`).
+//!
+//! A host's seed lives under `host:<id>:totp` by convention, which is what the login autofill
+//! (`ssh_login`, and the askpass bridge) uses to answer verification code prompts.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+
+    fn mac(self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => hmac_sha1_compact::HMAC::mac(message, key).to_vec(),
+            Self::Sha256 => hmac_sha256::HMAC::mac(message, key).to_vec(),
+            Self::Sha512 => hmac_sha512::HMAC::mac(message, key).to_vec(),
+        }
+    }
+}
+
+/// A parsed seed.
+#[derive(Clone, Debug)]
+pub struct Totp {
+    secret: Vec<u8>,
+    algorithm: Algorithm,
+    digits: u32,
+    period: u64,
+}
+
+/// The current code, for display or for typing.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCode {
+    pub code: String,
+    /// Seconds until the code changes.
+    pub remaining_secs: u64,
+    pub period: u64,
+}
+
+fn base32_decode(input: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u64, 0u32);
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '-' && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("'{c}' isn't a base32 character"))?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(out)
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u64, 0u32);
+    for b in bytes {
+        buffer = (buffer << 8) | *b as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+impl Totp {
+    /// A base32 secret, or an `otpauth://totp/` URI (secret, algorithm, digits and period are used;
+    /// the label and issuer are not).
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let mut totp = Totp {
+            secret: Vec::new(),
+            algorithm: Algorithm::Sha1,
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+        };
+        let Some(rest) = input.strip_prefix("otpauth://") else {
+            totp.secret = base32_decode(input)?;
+            return totp.checked();
+        };
+        let (kind, query) = rest.split_once('?').ok_or("the otpauth URI has no parameters")?;
+        if !kind.to_lowercase().starts_with("totp/") {
+            return Err("only time-based (totp) codes are supported".to_string());
+        }
+        for (name, value) in query.split('&').filter_map(|p| p.split_once('=')) {
+            match name.to_lowercase().as_str() {
+                "secret" => totp.secret = base32_decode(&value.replace("%3D", "="))?,
+                "algorithm" => {
+                    totp.algorithm = match value.to_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        other => return Err(format!("unsupported algorithm {other}")),
+                    }
+                }
+                "digits" => totp.digits = value.parse().map_err(|_| format!("bad digits: {value}"))?,
+                "period" => totp.period = value.parse().map_err(|_| format!("bad period: {value}"))?,
+                _ => {}
+            }
+        }
+        totp.checked()
+    }
+
+    fn checked(self) -> Result<Self, String> {
+        if self.secret.len() < 10 {
+            return Err("the secret is too short to be a TOTP seed".to_string());
+        }
+        if !(6..=8).contains(&self.digits) {
+            return Err("codes must have 6 to 8 digits".to_string());
+        }
+        if self.period == 0 || self.period > 300 {
+            return Err("the period must be 1 to 300 seconds".to_string());
+        }
+        Ok(self)
+    }
+
+    /// The form stored in the vault.
+    pub fn to_uri(&self) -> String {
+        format!(
+            "otpauth://totp/OpsPad?secret={}&algorithm={}&digits={}&period={}",
+            base32_encode(&self.secret),
+            self.algorithm.as_str(),
+            self.digits,
+            self.period
+        )
+    }
+
+    fn code_at(&self, unix_secs: u64) -> String {
+        let counter = unix_secs / self.period;
+        let mac = self.algorithm.mac(&self.secret, &counter.to_be_bytes());
+        // Dynamic truncation (RFC 4226 section 5.3).
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([mac[offset] & 0x7f, mac[offset + 1], mac[offset + 2], mac[offset + 3]]);
+        format!("{:0width$}", value % 10u32.pow(self.digits), width = self.digits as usize)
+    }
+
+    pub fn now(&self) -> TotpCode {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        TotpCode {
+            code: self.code_at(now),
+            remaining_secs: self.period - now % self.period,
+            period: self.period,
+        }
+    }
+}
+
+/// The current code for a stored seed (the vault value).
+pub fn code(stored: &[u8]) -> Result<TotpCode, String> {
+    let text = std::str::from_utf8(stored).map_err(|_| "the secret isn't a TOTP seed".to_string())?;
+    Ok(Totp::parse(text)?.now())
+}
+
+/// The vault key of a host's seed.
+pub fn host_key(host_id: &str) -> String {
+    format!("host:{host_id}:totp")
+}
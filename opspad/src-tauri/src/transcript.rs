@@ -0,0 +1,309 @@
+//! Markdown export of a live session's output, for pasting into tickets and wikis.
+//!
+//! The terminal backend keeps the last couple of MiB of each session's output with arrival
+//! times. Export strips ANSI sequences and replays carriage returns and backspaces the way a
+//! terminal would, then splits the output at each command CommandDock recorded for the session's
+//! scope and at each timeline mark (`terminal_mark`), so every command or mark becomes a heading
+//! over the output that followed it. The result goes through the redaction rules like anything
+//! else that leaves the app.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use serde::Serialize;
+
+use crate::db::DockHistoryEntry;
+use crate::redact::Redactor;
+use crate::terminal::prompt_marks::{PromptMark, PromptMarkParser, Segment};
+use crate::terminal::session_manager::{TerminalKind, TranscriptChunk};
+use crate::timestamp::{format_time, format_utc};
+use crate::AppState;
+
+/// History entries considered when correlating commands.
+const HISTORY_LOOKBACK: i64 = 500;
+
+/// Plain text as a terminal would have shown it: no escape sequences, and `\r` and backspace move
+/// the cursor back so later characters overwrite.
+pub fn clean(raw: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]").expect("valid regex")
+    });
+    let text = ansi.replace_all(raw, "");
+    let mut out = String::with_capacity(text.len());
+    // The current line and the cursor's column in it.
+    let mut line: Vec<char> = Vec::new();
+    let mut col: usize = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' => col = 0,
+            '\n' => {
+                out.push_str(line.iter().collect::<String>().trim_end());
+                out.push('\n');
+                line.clear();
+                col = 0;
+            }
+            '\x08' => col = col.saturating_sub(1),
+            c if c.is_control() && c != '\t' => {}
+            c => {
+                if col < line.len() {
+                    line[col] = c;
+                } else {
+                    line.push(c);
+                }
+                col += 1;
+            }
+        }
+    }
+    out.push_str(line.iter().collect::<String>().trim_end());
+    out.trim_matches('\n').to_string()
+}
+
+/// A code fence longer than any run of backticks in `text`.
+fn fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_block(md: &mut String, heading: &str, chunks: &[&TranscriptChunk]) {
+    let raw: String = chunks.iter().map(|c| c.text.as_str()).collect();
+    let text = clean(&raw);
+    md.push_str(heading);
+    md.push_str("\n\n");
+    if text.is_empty() {
+        md.push_str("_(no output)_\n\n");
+        return;
+    }
+    let fence = fence(&text);
+    md.push_str(&format!("{fence}text\n{text}\n{fence}\n\n"));
+}
+
+/// The output of a session's last finished command.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastOutput {
+    /// As echoed by the shell, when it marks where the command line starts.
+    pub command: Option<String>,
+    /// Plain text, redacted.
+    pub output: String,
+    pub exit_status: Option<i32>,
+    /// Epoch seconds.
+    pub started_at: i64,
+    pub ended_at: i64,
+    /// The command started before the kept output does; its beginning is missing.
+    pub truncated: bool,
+}
+
+/// A command being read out of the transcript.
+#[derive(Default)]
+struct Reading {
+    /// Echo after the command-start mark.
+    typed: Option<String>,
+    /// The shell marked output as started; text goes to `output` from then on.
+    running: bool,
+    output: String,
+    started_at: i64,
+    truncated: bool,
+}
+
+impl Reading {
+    fn push(&mut self, text: &str) {
+        match self.typed.as_mut().filter(|_| !self.running) {
+            Some(typed) => typed.push_str(text),
+            None => self.output.push_str(text),
+        }
+    }
+
+    fn finish(self, exit_status: Option<i32>, ended_at: i64) -> Option<LastOutput> {
+        let typed = self.typed.map(|t| clean(&t)).unwrap_or_default();
+        let (command, output) = if self.running || self.truncated {
+            (typed.trim().to_string(), clean(&self.output))
+        } else {
+            // No output-start mark (PowerShell): the echo is the command line, then its output.
+            match typed.split_once('\n') {
+                Some((line, rest)) => (line.trim().to_string(), rest.to_string()),
+                None => (typed.trim().to_string(), String::new()),
+            }
+        };
+        // Enter at an empty prompt.
+        if command.is_empty() && output.trim().is_empty() && !self.truncated {
+            return None;
+        }
+        Some(LastOutput {
+            command: Some(command).filter(|c| !c.is_empty()),
+            output,
+            exit_status,
+            started_at: self.started_at,
+            ended_at,
+            truncated: self.truncated,
+        })
+    }
+}
+
+/// The output between the last command-start and command-end marks in the session's kept
+/// output (needs shell integration); `None` before a command has finished.
+pub fn last_output(state: &AppState, session_id: &str) -> Result<Option<LastOutput>, String> {
+    let transcript = state.terminal.transcript(session_id).map_err(|e| e.to_string())?;
+    let mut parser = PromptMarkParser::default();
+    let mut last = None;
+    // Output kept from partway through a command counts toward it.
+    let mut reading = transcript.chunks.first().filter(|_| transcript.truncated).map(|c| Reading {
+        started_at: c.at,
+        truncated: true,
+        ..Default::default()
+    });
+    for chunk in &transcript.chunks {
+        for segment in parser.feed_segments(&chunk.text) {
+            let finished = match segment {
+                Segment::Text(text) => {
+                    if let Some(r) = reading.as_mut() {
+                        r.push(&text);
+                    }
+                    None
+                }
+                Segment::Mark(PromptMark::CommandStart) => {
+                    reading = Some(Reading {
+                        typed: Some(String::new()),
+                        started_at: chunk.at,
+                        ..Default::default()
+                    });
+                    None
+                }
+                Segment::Mark(PromptMark::OutputStart) => {
+                    let r = reading.get_or_insert_with(Reading::default);
+                    r.running = true;
+                    r.started_at = chunk.at;
+                    r.output.clear();
+                    None
+                }
+                Segment::Mark(PromptMark::CommandEnd { exit }) => reading.take().and_then(|r| r.finish(exit, chunk.at)),
+                // A new prompt without an end mark: whatever was running ended unreported.
+                Segment::Mark(PromptMark::PromptStart { .. }) => {
+                    reading.take().and_then(|r| r.finish(None, chunk.at))
+                }
+            };
+            if finished.is_some() {
+                last = finished;
+            }
+        }
+    }
+    let Some(mut last) = last else {
+        return Ok(None);
+    };
+    let redactor = Redactor::new(&state.db.redaction_rules_list().map_err(|e| e.to_string())?);
+    last.output = redactor.redact(&last.output);
+    last.command = last.command.map(|c| redactor.redact(&c));
+    Ok(Some(last))
+}
+
+pub fn export_markdown(state: &AppState, session_id: &str) -> Result<String, String> {
+    let summary = state
+        .terminal
+        .list()
+        .into_iter()
+        .find(|s| s.session_id == session_id)
+        .ok_or_else(|| "session not found (only live sessions can be exported)".to_string())?;
+    let transcript = state.terminal.transcript(session_id).map_err(|e| e.to_string())?;
+    let scope = state
+        .db
+        .terminal_session_scope_get(session_id)
+        .map_err(|e| e.to_string())?;
+    let host = match scope.as_deref().and_then(|s| s.strip_prefix("ssh:")) {
+        Some(id) => state.db.hosts_get(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+
+    // CommandDock commands sent in this session's scope since it started, oldest first.
+    let mut commands: Vec<DockHistoryEntry> = match scope.as_deref() {
+        Some(scope) => state
+            .db
+            .dock_history_for_scope(scope, HISTORY_LOOKBACK)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|h| h.created_at >= summary.started_at * 1000)
+            .collect(),
+        None => Vec::new(),
+    };
+    commands.sort_by_key(|h| h.created_at);
+
+    // Where the output is split, oldest first: each command, then each mark, with its heading.
+    let mut anchors: Vec<(i64, String)> = commands
+        .iter()
+        .map(|cmd| {
+            let command = cmd.command_text.replace('\n', " ");
+            let quote = if command.contains('`') { "``" } else { "`" };
+            let at = cmd.created_at / 1000;
+            let heading = format!("## {} UTC · {quote} {command} {quote}", format_time(at));
+            (at, heading)
+        })
+        .collect();
+    for mark in state
+        .db
+        .terminal_marks_for_session(session_id)
+        .map_err(|e| e.to_string())?
+    {
+        let label = mark.label.replace('\n', " ");
+        let at = mark.created_at / 1000;
+        anchors.push((at, format!("## {} UTC · —— {label} ——", format_time(at))));
+    }
+    // Stable, so a mark placed in the same second as a command comes after it.
+    anchors.sort_by_key(|(at, _)| *at);
+
+    let title = match (&summary.title, &host, summary.kind) {
+        (Some(title), _, _) => title.clone(),
+        (None, Some(h), _) => h.label.clone(),
+        (None, None, TerminalKind::Ssh) => "SSH session".to_string(),
+        (None, None, TerminalKind::Local) => "Local terminal".to_string(),
+    };
+    let mut md = format!("# Session transcript: {title}\n\n");
+    if let Some(h) = &host {
+        md.push_str(&format!("- **Host:** `{}@{}:{}`\n", h.username, h.hostname, h.port));
+    }
+    md.push_str(&format!("- **Environment:** {}\n", summary.environment_tag));
+    md.push_str(&format!("- **Started:** {} UTC\n", format_utc(summary.started_at)));
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    md.push_str(&format!("- **Exported:** {} UTC\n", format_utc(exported_at)));
+    if transcript.truncated {
+        let from = transcript.chunks.first().map_or(exported_at, |c| c.at);
+        md.push_str(&format!(
+            "- _Earlier output was dropped; this transcript starts at {} UTC._\n",
+            format_time(from)
+        ));
+    }
+    md.push('\n');
+
+    let before: Vec<&TranscriptChunk> = transcript
+        .chunks
+        .iter()
+        .filter(|c| anchors.first().is_none_or(|(at, _)| c.at < *at))
+        .collect();
+    if !before.is_empty() || anchors.is_empty() {
+        let heading = match transcript.chunks.first() {
+            Some(c) => format!("## {} UTC", format_time(c.at)),
+            None => "## Output".to_string(),
+        };
+        push_block(&mut md, &heading, &before);
+    }
+    for (i, (at, heading)) in anchors.iter().enumerate() {
+        let until = anchors.get(i + 1).map(|(u, _)| *u);
+        let chunks: Vec<&TranscriptChunk> = transcript
+            .chunks
+            .iter()
+            .filter(|c| c.at >= *at && until.is_none_or(|u| c.at < u))
+            .collect();
+        push_block(&mut md, heading, &chunks);
+    }
+
+    let redactor = Redactor::new(&state.db.redaction_rules_list().map_err(|e| e.to_string())?);
+    Ok(redactor.redact(md.trim_end()) + "\n")
+}
@@ -0,0 +1,380 @@
+//! Queued file transfers to and from hosts, over the system `sftp`.
+//!
+//! Each transfer is persisted in the `transfers` table and run on the shared work queue, one per
+//! host at a time. A transfer that is paused, fails or is cut off by the app exiting is later
+//! continued from its partial destination file (`sftp`'s `-a`) instead of starting over. Failures
+//! are retried per the transfer's own policy, and whatever was queued or running when the app
+//! exited is picked up again on the next start. State changes are emitted as `transfer:status`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::ssh;
+use crate::db::{Host, Transfer, TransferCreate};
+use crate::exec;
+use crate::notifications;
+use crate::queue::TaskOutcome;
+use crate::AppState;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const MAX_RETRIES_CAP: u32 = 10;
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 5000;
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+/// Large files take a while; a dead connection is noticed by the keepalives instead.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferStatusEvent {
+    pub transfer_id: String,
+    pub status: String,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Stop flags for transfers whose `sftp` is running, keyed by transfer id.
+#[derive(Default)]
+pub struct Transfers {
+    active: Mutex<HashMap<String, Arc<Stop>>>,
+}
+
+#[derive(Default)]
+struct Stop {
+    requested: AtomicBool,
+    /// Keep the transfer for resuming rather than cancel it.
+    pause: AtomicBool,
+}
+
+impl Transfers {
+    fn stop(&self, id: &str, pause: bool) -> bool {
+        match self.active.lock().unwrap_or_else(|e| e.into_inner()).get(id) {
+            Some(stop) => {
+                stop.pause.store(pause, Ordering::SeqCst);
+                stop.requested.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn emit_status(app: &AppHandle, transfer: &Transfer) {
+    let _ = app.emit(
+        "transfer:status",
+        TransferStatusEvent {
+            transfer_id: transfer.id.clone(),
+            status: transfer.status.clone(),
+            attempts: transfer.attempts,
+            error: transfer.error.clone(),
+        },
+    );
+}
+
+/// Re-read a transfer after a change and announce it.
+fn changed(app: &AppHandle, state: &AppState, id: &str) -> Result<Transfer, String> {
+    let transfer = state
+        .db
+        .transfers_get(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "transfer not found".to_string())?;
+    emit_status(app, &transfer);
+    Ok(transfer)
+}
+
+fn submit(app: &AppHandle, state: &Arc<AppState>, transfer: &Transfer) {
+    let app = app.clone();
+    let state2 = state.clone();
+    let id = transfer.id.clone();
+    // Not the jobs' "ssh:<host_id>" key: a long upload shouldn't hold up commands on the host.
+    let key = format!("transfer:{}", transfer.host_id);
+    state.queue.submit(Some(key), move |_attempt| {
+        run(&app, &state2, &id);
+        TaskOutcome::Done
+    });
+}
+
+pub fn enqueue(app: &AppHandle, state: &Arc<AppState>, input: TransferCreate) -> Result<Transfer, String> {
+    if input.local_path.trim().is_empty() || input.remote_path.trim().is_empty() {
+        return Err("local and remote paths are required".to_string());
+    }
+    match input.direction.as_str() {
+        "upload" => {
+            if !Path::new(&input.local_path).is_file() {
+                return Err(format!("{} is not a file", input.local_path));
+            }
+        }
+        "download" => {
+            let dir = Path::new(&input.local_path).parent().filter(|p| !p.as_os_str().is_empty());
+            if dir.is_some_and(|d| !d.is_dir()) {
+                return Err(format!("{} is not a folder", dir.map(|d| d.display().to_string()).unwrap_or_default()));
+            }
+        }
+        other => return Err(format!("unknown transfer direction: {other}")),
+    }
+    let host = state
+        .db
+        .hosts_get(&input.host_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "host not found".to_string())?;
+    let max_retries = input.max_retries.unwrap_or(DEFAULT_MAX_RETRIES).min(MAX_RETRIES_CAP);
+    let backoff_ms = input
+        .retry_backoff_ms
+        .unwrap_or(DEFAULT_RETRY_BACKOFF_MS)
+        .min(MAX_BACKOFF.as_millis() as u64);
+    let transfer = state
+        .db
+        .transfers_create(&input, &host.label, max_retries, backoff_ms)
+        .map_err(|e| e.to_string())?;
+    emit_status(app, &transfer);
+    submit(app, state, &transfer);
+    Ok(transfer)
+}
+
+/// A queued transfer is held back; a running one is stopped, keeping what has arrived so far.
+pub fn pause(app: &AppHandle, state: &AppState, id: &str) -> Result<Transfer, String> {
+    if state
+        .db
+        .transfers_set_status(id, &["queued"], "paused", None)
+        .map_err(|e| e.to_string())?
+    {
+        return changed(app, state, id);
+    }
+    if !state.transfers.stop(id, true) {
+        return Err("only queued or running transfers can be paused".to_string());
+    }
+    state.db.transfers_get(id).map_err(|e| e.to_string())?.ok_or_else(|| "transfer not found".to_string())
+}
+
+/// Queue a paused or failed transfer again; it continues from its partial file.
+pub fn resume(app: &AppHandle, state: &Arc<AppState>, id: &str) -> Result<Transfer, String> {
+    if !state
+        .db
+        .transfers_set_status(id, &["paused", "failed"], "queued", None)
+        .map_err(|e| e.to_string())?
+    {
+        return Err("only paused or failed transfers can be resumed".to_string());
+    }
+    let transfer = changed(app, state, id)?;
+    submit(app, state, &transfer);
+    Ok(transfer)
+}
+
+/// The partial file, if any, is left where it is.
+pub fn cancel(app: &AppHandle, state: &AppState, id: &str) -> Result<Transfer, String> {
+    if state
+        .db
+        .transfers_set_status(id, &["queued", "paused", "failed"], "cancelled", None)
+        .map_err(|e| e.to_string())?
+    {
+        return changed(app, state, id);
+    }
+    if !state.transfers.stop(id, false) {
+        return Err("the transfer has already finished".to_string());
+    }
+    state.db.transfers_get(id).map_err(|e| e.to_string())?.ok_or_else(|| "transfer not found".to_string())
+}
+
+/// Stop the host's running transfers, before the host is deleted (which cancels them).
+pub fn stop_for_host(state: &AppState, host_id: &str) {
+    let active: Vec<String> =
+        state.transfers.active.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+    for id in active {
+        if state.db.transfers_get(&id).ok().flatten().is_some_and(|t| t.host_id == host_id) {
+            state.transfers.stop(&id, false);
+        }
+    }
+}
+
+/// Queue again whatever a previous run of the app left unfinished.
+pub fn resume_queued(app: &AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>().map(|s| s.inner().clone()) else {
+        return;
+    };
+    match state.db.transfers_queued() {
+        Ok(queued) => {
+            for transfer in &queued {
+                submit(app, &state, transfer);
+            }
+        }
+        Err(e) => log::warn!("queued transfers not resumed: {e}"),
+    }
+}
+
+fn run(app: &AppHandle, state: &Arc<AppState>, id: &str) {
+    let Ok(Some(transfer)) = state.db.transfers_get(id) else {
+        return;
+    };
+    // Paused or cancelled while waiting in the queue.
+    if !state.db.transfers_start_attempt(id).unwrap_or(false) {
+        return;
+    }
+    let continuing = transfer.attempts > 0;
+    let attempt = transfer.attempts + 1;
+    let _ = changed(app, state, id);
+
+    let stop = Arc::new(Stop::default());
+    state
+        .transfers
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id.to_string(), stop.clone());
+    let result = match state.db.hosts_get(&transfer.host_id) {
+        Ok(Some(host)) => sftp(&host, &transfer, continuing, &stop.requested),
+        Ok(None) => Err(Failure::Permanent("the host was deleted".to_string())),
+        Err(e) => Err(Failure::Permanent(e.to_string())),
+    };
+    state.transfers.active.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+
+    let (status, error) = match result {
+        Ok(()) => ("succeeded", None),
+        Err(Failure::Stopped) if stop.pause.load(Ordering::SeqCst) => ("paused", None),
+        Err(Failure::Stopped) => ("cancelled", None),
+        Err(Failure::Transient(e)) if attempt <= transfer.max_retries => {
+            let backoff = Duration::from_millis(
+                transfer
+                    .retry_backoff_ms
+                    .saturating_mul(1u64 << (attempt - 1).min(16)),
+            )
+            .min(MAX_BACKOFF);
+            let note = format!("attempt {attempt} failed: {e}; retrying in {} s", backoff.as_secs());
+            if state
+                .db
+                .transfers_set_status(id, &["running"], "queued", Some(&note))
+                .unwrap_or(false)
+            {
+                let _ = changed(app, state, id);
+                let app = app.clone();
+                let state = state.clone();
+                let id = id.to_string();
+                thread::spawn(move || {
+                    thread::sleep(backoff);
+                    // Only if nobody paused or cancelled it meanwhile.
+                    if let Ok(Some(transfer)) = state.db.transfers_get(&id) {
+                        if transfer.status == "queued" {
+                            submit(&app, &state, &transfer);
+                        }
+                    }
+                });
+            }
+            return;
+        }
+        Err(Failure::Transient(e) | Failure::Permanent(e)) => ("failed", Some(e)),
+    };
+    let _ = state.db.transfers_set_status(id, &["running"], status, error.as_deref());
+    let _ = changed(app, state, id);
+    if matches!(status, "succeeded" | "failed") {
+        let name = Path::new(&transfer.remote_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| transfer.remote_path.clone());
+        notifications::notify(
+            app,
+            notifications::Kind::Job {
+                failed: status == "failed",
+            },
+            &format!("Transfer {status}"),
+            &match error {
+                Some(error) => format!("{name} ({}): {error}", transfer.host_label),
+                None => format!("{name} ({})", transfer.host_label),
+            },
+        );
+    }
+}
+
+enum Failure {
+    /// Paused or cancelled.
+    Stopped,
+    /// Worth another attempt (connection dropped, remote busy, ...).
+    Transient(String),
+    Permanent(String),
+}
+
+/// A path as one argument of an `sftp` batch command.
+pub fn quote(path: &str) -> String {
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn sftp(host: &Host, transfer: &Transfer, continuing: bool, stop: &AtomicBool) -> Result<(), Failure> {
+    let program = ssh::sftp_program_checked().map_err(Failure::Permanent)?;
+    let result = sftp_once(&program, host, transfer, continuing, stop);
+    match result {
+        // `put -a` needs the remote partial file; if the first run never created it, start over.
+        Err(Failure::Transient(e)) if continuing && transfer.direction == "upload" && e.contains("No such file") => {
+            sftp_once(&program, host, transfer, false, stop)
+        }
+        other => other,
+    }
+}
+
+fn sftp_once(
+    program: &str,
+    host: &Host,
+    transfer: &Transfer,
+    continuing: bool,
+    stop: &AtomicBool,
+) -> Result<(), Failure> {
+    let flag = if continuing { " -a" } else { "" };
+    let command = match transfer.direction.as_str() {
+        "upload" => format!("put{flag} {} {}\n", quote(&transfer.local_path), quote(&transfer.remote_path)),
+        _ => format!("get{flag} {} {}\n", quote(&transfer.remote_path), quote(&transfer.local_path)),
+    };
+    let batch = std::env::temp_dir().join(format!("opspad-transfer-{}.sftp", transfer.id));
+    std::fs::write(&batch, command).map_err(|e| Failure::Permanent(format!("batch file: {e}")))?;
+    let result = exec::run_with(program, &sftp_args(&batch, host), TRANSFER_TIMEOUT, Some(stop), None);
+    let _ = std::fs::remove_file(&batch);
+
+    let out = result.map_err(|e| Failure::Permanent(format!("failed to start sftp: {e}")))?;
+    if out.cancelled {
+        return Err(Failure::Stopped);
+    }
+    if out.timed_out {
+        return Err(Failure::Transient("timed out".to_string()));
+    }
+    if out.exit_code == Some(0) {
+        return Ok(());
+    }
+    Err(Failure::Transient(last_error(&out.stderr)))
+}
+
+/// The line of `sftp`'s stderr that says what went wrong.
+pub fn last_error(stderr: &str) -> String {
+    stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .unwrap_or("sftp failed")
+        .to_string()
+}
+
+/// Arguments for running the `sftp` commands in `batch` against `host`. `sftp` stops at the first
+/// failing command (unless it's prefixed with `-`) and exits non-zero.
+pub fn sftp_args(batch: &Path, host: &Host) -> Vec<String> {
+    let mut args = vec![
+        "-b".to_string(),
+        batch.to_string_lossy().to_string(),
+        "-S".to_string(),
+        ssh::ssh_program(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+        "-o".to_string(),
+        "ServerAliveInterval=15".to_string(),
+        "-o".to_string(),
+        "ServerAliveCountMax=4".to_string(),
+        "-P".to_string(),
+        host.port.to_string(),
+    ];
+    if let Some(id) = host.identity_file.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        args.push("-i".to_string());
+        args.push(id.to_string());
+    }
+    args.push(format!("{}@{}", host.username, host.hostname));
+    args
+}
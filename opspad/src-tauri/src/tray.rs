@@ -0,0 +1,170 @@
+//! Tray icon with quick connect: pinned and recently used hosts, plus global dock commands, so
+//! a host is one click away without focusing the main window first.
+//!
+//! Items are routed like CLI requests (see [`crate::cli`]); the menu is rebuilt whenever hosts or
+//! dock commands change (`db:changed`).
+
+use std::sync::Arc;
+
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Listener, Manager};
+
+use crate::cli::{self, CliAction};
+use crate::db::Host;
+use crate::db_changes::DbChangedEvent;
+use crate::AppState;
+
+const TRAY_ID: &str = "main";
+const MAX_PINNED: usize = 15;
+const MAX_RECENT: usize = 5;
+const MAX_COMMANDS: usize = 15;
+
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+const HOST_PREFIX: &str = "host:";
+const COMMAND_PREFIX: &str = "command:";
+
+/// Pinned hosts (in sidebar order) and the most recently connected unpinned ones.
+fn quick_connect_hosts(hosts: Vec<Host>) -> (Vec<Host>, Vec<Host>) {
+    let (pinned, rest): (Vec<Host>, Vec<Host>) = hosts.into_iter().partition(|h| h.pinned);
+    let mut recent: Vec<Host> = rest.into_iter().filter(|h| h.last_connected_at.is_some()).collect();
+    recent.sort_by_key(|h| std::cmp::Reverse(h.last_connected_at));
+    recent.truncate(MAX_RECENT);
+    (pinned.into_iter().take(MAX_PINNED).collect(), recent)
+}
+
+fn host_item(app: &AppHandle, host: &Host) -> tauri::Result<MenuItem<tauri::Wry>> {
+    let text = format!("{}  ({})", host.label, host.environment_tag);
+    MenuItem::with_id(app, format!("{HOST_PREFIX}{}", host.id), text, true, None::<&str>)
+}
+
+fn build_menu(app: &AppHandle, state: &AppState) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(app, SHOW_ID, "Show OpsPad", true, None::<&str>)?)?;
+
+    let (pinned, recent) = quick_connect_hosts(state.db.hosts_list().unwrap_or_default());
+    if !pinned.is_empty() || !recent.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for host in &pinned {
+        menu.append(&host_item(app, host)?)?;
+    }
+    if !recent.is_empty() {
+        let submenu = Submenu::new(app, "Recent", true)?;
+        for host in &recent {
+            submenu.append(&host_item(app, host)?)?;
+        }
+        menu.append(&submenu)?;
+    }
+
+    // Scoped commands only make sense next to their hosts; the tray offers the global ones.
+    let commands: Vec<_> = state
+        .db
+        .dock_commands_list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.visibility.is_global())
+        .take(MAX_COMMANDS)
+        .collect();
+    if !commands.is_empty() {
+        let submenu = Submenu::new(app, "Run in active terminal", true)?;
+        for c in &commands {
+            submenu.append(&MenuItem::with_id(
+                app,
+                format!("{COMMAND_PREFIX}{}", c.id),
+                &c.title,
+                true,
+                None::<&str>,
+            )?)?;
+        }
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+        menu.append(&submenu)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, QUIT_ID, "Quit OpsPad", true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+fn on_menu_item(app: &AppHandle, id: &str) {
+    let state = app.state::<Arc<AppState>>();
+    let action = if let Some(host_id) = id.strip_prefix(HOST_PREFIX) {
+        state
+            .db
+            .hosts_get(host_id)
+            .ok()
+            .flatten()
+            .map(|host| CliAction::Connect { host })
+    } else if let Some(command_id) = id.strip_prefix(COMMAND_PREFIX) {
+        state
+            .db
+            .dock_commands_get(command_id)
+            .ok()
+            .flatten()
+            .map(|command| CliAction::Command {
+                command: Box::new(command),
+            })
+    } else {
+        match id {
+            SHOW_ID => cli::focus_main_window(app),
+            QUIT_ID => app.exit(0),
+            _ => {}
+        }
+        return;
+    };
+    match action {
+        Some(action) => {
+            cli::deliver(app, &state, action);
+        }
+        // Deleted since the menu was built.
+        None => rebuild(app),
+    }
+}
+
+pub fn rebuild(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app.state::<Arc<AppState>>();
+    match build_menu(app, &state) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log::warn!("tray menu rebuild failed: {e}"),
+    }
+}
+
+pub fn install(app: &AppHandle) -> tauri::Result<()> {
+    let state = app.state::<Arc<AppState>>();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("OpsPad")
+        .menu(&build_menu(app, &state)?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_item(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                cli::focus_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let handle = app.clone();
+    app.listen("db:changed", move |event| {
+        let Ok(changed) = serde_json::from_str::<DbChangedEvent>(event.payload()) else {
+            return;
+        };
+        if changed.tables.iter().any(|t| t == "hosts" || t == "dock_commands") {
+            rebuild(&handle);
+        }
+    });
+    Ok(())
+}
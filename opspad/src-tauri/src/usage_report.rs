@@ -0,0 +1,289 @@
+//! How long terminals were connected and how many commands were run over a time range, per
+//! host, per environment and per incident, for on-call retros and capacity discussions.
+//!
+//! Connected time comes from the session open/close events the timeline uses, clipped to the
+//! range (and, for incidents, to the incident). A session still open counts up to now; one that
+//! never recorded a close (the app quit or crashed under it) is counted but adds no time, since
+//! when it really ended isn't known. Commands are CommandDock history entries, with a watch
+//! counting each of its runs.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::db::Incident;
+use crate::timestamp;
+use crate::AppState;
+
+/// Rows read from each source; a report that hits it is marked truncated.
+const SOURCE_LIMIT: i64 = 20_000;
+/// How far before the range a session may have opened and still be counted.
+const OPEN_LOOKBACK_MS: i64 = 7 * 24 * 3600 * 1000;
+const INCIDENT_LIMIT: i64 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UsageGroup {
+    Host,
+    Environment,
+    Incident,
+}
+
+impl UsageGroup {
+    fn label(self) -> &'static str {
+        match self {
+            UsageGroup::Host => "host",
+            UsageGroup::Environment => "environment",
+            UsageGroup::Incident => "incident",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRow {
+    pub group: UsageGroup,
+    /// The terminal scope ("ssh:<host_id>", "local"), environment tag or incident id.
+    pub key: String,
+    /// The host's label, the environment tag or the incident's title.
+    pub label: String,
+    pub sessions: u64,
+    pub connected_secs: i64,
+    pub commands: i64,
+    /// Sessions with no recorded close, which add no connected time.
+    pub unclosed_sessions: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    #[serde(with = "crate::timestamp::iso")]
+    pub since: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub until: i64,
+    #[serde(with = "crate::timestamp::iso")]
+    pub generated_at: i64,
+    /// Some source had more rows in the range than were read.
+    pub truncated: bool,
+    /// Most connected first, within each group: hosts, then environments, then incidents.
+    pub rows: Vec<UsageRow>,
+}
+
+/// A session as reconstructed from its open and close events (epoch milliseconds).
+struct Session {
+    scope: String,
+    environment_tag: Option<String>,
+    opened_at: i64,
+    /// `None` when no close was recorded and the session isn't live.
+    closed_at: Option<i64>,
+}
+
+/// Start and end, inclusive epoch milliseconds.
+type Window = (i64, i64);
+
+fn overlap(a: Window, b: Window) -> i64 {
+    (a.1.min(b.1) - a.0.max(b.0)).max(0)
+}
+
+#[derive(Default)]
+struct Totals {
+    label: String,
+    sessions: u64,
+    connected_ms: i64,
+    commands: i64,
+    unclosed: u64,
+}
+
+impl Totals {
+    fn of<'a>(
+        groups: &'a mut BTreeMap<(UsageGroup, String), Totals>,
+        group: UsageGroup,
+        key: &str,
+        label: impl FnOnce() -> String,
+    ) -> &'a mut Totals {
+        groups.entry((group, key.to_string())).or_insert_with(|| Totals {
+            label: label(),
+            ..Default::default()
+        })
+    }
+
+    fn add_session(&mut self, session: &Session, window: Window) {
+        self.sessions += 1;
+        match session.closed_at {
+            Some(closed_at) => self.connected_ms += overlap((session.opened_at, closed_at), window),
+            None => self.unclosed += 1,
+        }
+    }
+}
+
+/// The session was connected at some point in `window`.
+fn touches(session: &Session, window: Window) -> bool {
+    let end = session.closed_at.unwrap_or(session.opened_at);
+    session.opened_at <= window.1 && end >= window.0
+}
+
+fn sessions(state: &AppState, from: i64, to: i64) -> Result<(Vec<Session>, bool), String> {
+    let events = state
+        .db
+        .session_events_between(from - OPEN_LOOKBACK_MS, to, SOURCE_LIMIT)
+        .map_err(|e| e.to_string())?;
+    let truncated = events.len() as i64 >= SOURCE_LIMIT;
+    let live: HashSet<String> = state.terminal.list().into_iter().map(|s| s.session_id).collect();
+    let now = timestamp::now_millis();
+
+    let mut open: HashMap<String, Session> = HashMap::new();
+    let mut out = Vec::new();
+    for e in events {
+        match e.kind.as_str() {
+            "open" => {
+                open.insert(
+                    e.session_id,
+                    Session {
+                        scope: e.scope,
+                        environment_tag: e.environment_tag,
+                        opened_at: e.at,
+                        closed_at: None,
+                    },
+                );
+            }
+            _ => {
+                if let Some(mut s) = open.remove(&e.session_id) {
+                    s.closed_at = Some(e.at);
+                    out.push(s);
+                }
+            }
+        }
+    }
+    for (id, mut s) in open {
+        if live.contains(&id) {
+            s.closed_at = Some(now);
+        }
+        out.push(s);
+    }
+    out.retain(|s| touches(s, (from, to)));
+    Ok((out, truncated))
+}
+
+/// Incidents that overlap `from..=to`, with their windows in epoch milliseconds.
+fn incidents(state: &AppState, from: i64, to: i64) -> Result<Vec<(Incident, Window)>, String> {
+    let now = timestamp::now_millis();
+    let incidents = state.db.incidents_list(INCIDENT_LIMIT).map_err(|e| e.to_string())?;
+    Ok(incidents
+        .into_iter()
+        .filter_map(|i| {
+            let start = i.started_at * 1000;
+            let end = i.ended_at.map_or(now, |at| at * 1000 + 999);
+            let window = (start.max(from), end.min(to));
+            (window.0 <= window.1).then_some((i, window))
+        })
+        .collect())
+}
+
+/// `since` and `until` are inclusive epoch seconds (`until` defaults to now).
+pub fn report(state: &AppState, since: i64, until: Option<i64>) -> Result<UsageReport, String> {
+    let until = until.unwrap_or_else(|| timestamp::now_millis() / 1000);
+    if until < since {
+        return Err("until: must not be before since".to_string());
+    }
+    let (from, to) = (since * 1000, until * 1000 + 999);
+    let range = (from, to);
+
+    let (sessions, mut truncated) = sessions(state, from, to)?;
+    let history = state.db.dock_history_between(from, to, SOURCE_LIMIT).map_err(|e| e.to_string())?;
+    truncated |= history.len() as i64 >= SOURCE_LIMIT;
+    let incidents = incidents(state, from, to)?;
+
+    let mut groups: BTreeMap<(UsageGroup, String), Totals> = BTreeMap::new();
+    let mut host_labels: HashMap<String, String> = HashMap::new();
+    let mut host_label = |scope: &str| -> String {
+        host_labels
+            .entry(scope.to_string())
+            .or_insert_with(|| match scope.strip_prefix("ssh:") {
+                Some(id) => state.db.hosts_get(id).ok().flatten().map_or_else(|| scope.to_string(), |h| h.label),
+                None if scope == "local" => "Local".to_string(),
+                None => scope.to_string(),
+            })
+            .clone()
+    };
+    for s in &sessions {
+        let env = s.environment_tag.as_deref().unwrap_or("UNKNOWN");
+        Totals::of(&mut groups, UsageGroup::Host, &s.scope, || host_label(&s.scope)).add_session(s, range);
+        Totals::of(&mut groups, UsageGroup::Environment, env, || env.to_string()).add_session(s, range);
+        for (incident, window) in &incidents {
+            if touches(s, *window) {
+                Totals::of(&mut groups, UsageGroup::Incident, &incident.id, || incident.title.clone())
+                    .add_session(s, *window);
+            }
+        }
+    }
+
+    for h in &history {
+        let runs = h.watch_runs.unwrap_or(1);
+        let scope = h.scope.as_deref().unwrap_or("local");
+        Totals::of(&mut groups, UsageGroup::Host, scope, || host_label(scope)).commands += runs;
+        Totals::of(&mut groups, UsageGroup::Environment, &h.environment_tag, || h.environment_tag.clone()).commands +=
+            runs;
+        for (incident, window) in &incidents {
+            if (window.0..=window.1).contains(&h.created_at) {
+                Totals::of(&mut groups, UsageGroup::Incident, &incident.id, || incident.title.clone()).commands += runs;
+            }
+        }
+    }
+    // Incidents in the range are listed even when nothing was connected during them.
+    for (incident, _) in &incidents {
+        Totals::of(&mut groups, UsageGroup::Incident, &incident.id, || incident.title.clone());
+    }
+
+    let mut rows: Vec<UsageRow> = groups
+        .into_iter()
+        .map(|((group, key), t)| UsageRow {
+            group,
+            key,
+            label: t.label,
+            sessions: t.sessions,
+            connected_secs: t.connected_ms / 1000,
+            commands: t.commands,
+            unclosed_sessions: t.unclosed,
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        a.group
+            .cmp(&b.group)
+            .then(b.connected_secs.cmp(&a.connected_secs))
+            .then(b.commands.cmp(&a.commands))
+            .then(a.label.cmp(&b.label))
+    });
+    Ok(UsageReport {
+        since: from,
+        until: to,
+        generated_at: timestamp::now_millis(),
+        truncated,
+        rows,
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The report as CSV, one row per host, environment and incident.
+pub fn csv(report: &UsageReport) -> String {
+    let mut out = String::from("group,key,label,sessions,hours_connected,commands,unclosed_sessions\n");
+    for row in &report.rows {
+        out.push_str(&format!(
+            "{},{},{},{},{:.2},{},{}\n",
+            row.group.label(),
+            csv_field(&row.key),
+            csv_field(&row.label),
+            row.sessions,
+            row.connected_secs as f64 / 3600.0,
+            row.commands,
+            row.unclosed_sessions,
+        ));
+    }
+    out
+}
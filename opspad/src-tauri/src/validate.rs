@@ -0,0 +1,132 @@
+//! Checks on hosts and CommandDock commands before they're stored, so a bad form submission (or
+//! a script calling the commands directly) can't persist something ssh or the dock will choke on.
+//!
+//! Failures name the offending fields: the error string has one `field: message` line per
+//! problem, with field names as the frontend spells them (`hostname`, `environmentTag`, ...).
+
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::db::Db;
+
+const MAX_LABEL_CHARS: usize = 200;
+const MAX_TEMPLATE_BYTES: usize = 64 * 1024;
+const MAX_HOSTNAME_CHARS: usize = 253;
+
+#[derive(Debug, Default)]
+struct FieldErrors(Vec<(&'static str, String)>);
+
+impl FieldErrors {
+    fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push((field, message.into()));
+    }
+
+    fn into_result(self) -> Result<(), String> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.to_string())
+        }
+    }
+}
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (field, message)) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{field}: {message}")?;
+        }
+        Ok(())
+    }
+}
+
+fn label_ok(errors: &mut FieldErrors, field: &'static str, value: &str) -> bool {
+    if value.trim().is_empty() {
+        errors.add(field, "must not be empty");
+        return false;
+    }
+    if value.chars().count() > MAX_LABEL_CHARS {
+        errors.add(field, format!("must be at most {MAX_LABEL_CHARS} characters"));
+        return false;
+    }
+    true
+}
+
+/// A DNS name, an IPv4/IPv6 address (bracketed or not), or an ssh config alias of the same
+/// characters. Never anything ssh could read as an option.
+fn hostname_error(hostname: &str) -> Option<&'static str> {
+    let h = hostname.trim();
+    if h.is_empty() {
+        return Some("must not be empty");
+    }
+    if h.starts_with('-') {
+        return Some("must not start with '-'");
+    }
+    let unbracketed = h.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(h);
+    if unbracketed.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    if h.len() > MAX_HOSTNAME_CHARS {
+        return Some("is too long for a hostname");
+    }
+    let label_ok = |l: &str| {
+        !l.is_empty()
+            && l.len() <= 63
+            && !l.starts_with('-')
+            && !l.ends_with('-')
+            && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    // A trailing dot is a fully qualified name.
+    if !h.strip_suffix('.').unwrap_or(h).split('.').all(label_ok) {
+        return Some("is not a valid hostname or IP address");
+    }
+    None
+}
+
+/// Fields shared by host create and update. `id` is the host being updated, if any, so it doesn't
+/// collide with its own label.
+pub fn host(
+    db: &Db,
+    id: Option<&str>,
+    label: &str,
+    hostname: &str,
+    port: u16,
+    username: &str,
+    environment_tag: &str,
+) -> Result<(), String> {
+    let mut errors = FieldErrors::default();
+    if label_ok(&mut errors, "label", label)
+        && db.hosts_label_taken(label.trim(), id).map_err(|e| e.to_string())?
+    {
+        errors.add("label", format!("another host is already called \"{}\"", label.trim()));
+    }
+    if let Some(message) = hostname_error(hostname) {
+        errors.add("hostname", message);
+    }
+    if port == 0 {
+        errors.add("port", "must be between 1 and 65535");
+    }
+    let user = username.trim();
+    if user.is_empty() {
+        errors.add("username", "must not be empty");
+    } else if user.starts_with('-') || user.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        errors.add("username", "must not start with '-' or contain spaces");
+    }
+    if environment_tag.trim().is_empty() {
+        errors.add("environmentTag", "must not be empty");
+    }
+    errors.into_result()
+}
+
+pub fn dock_command(title: &str, command: &str) -> Result<(), String> {
+    let mut errors = FieldErrors::default();
+    label_ok(&mut errors, "title", title);
+    if command.trim().is_empty() {
+        errors.add("command", "must not be empty");
+    } else if command.len() > MAX_TEMPLATE_BYTES {
+        errors.add("command", format!("must be at most {} KiB", MAX_TEMPLATE_BYTES / 1024));
+    }
+    errors.into_result()
+}
@@ -0,0 +1,282 @@
+//! Auto-lock for the vault.
+//!
+//! Once an unlock passphrase is set, the vault can lock itself after a period of inactivity, when
+//! the OS session locks, or when the machine wakes from sleep. While locked every secret read or
+//! write fails with "vault is locked" until `unlock` is called with the passphrase. The passphrase
+//! itself is never stored, only a salted PBKDF2 hash of it.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use base64::Engine as _;
+use hmac_sha256::HMAC;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::arch::screen_lock;
+use crate::arch::vault::VaultLock;
+use crate::db::Db;
+use crate::exec;
+use crate::AppState;
+
+const CONFIG_KEY: &str = "vault.lock";
+const PASSPHRASE_KEY: &str = "vault.lock.passphrase";
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// A wall-clock jump this far past the watch interval means the machine was asleep.
+pub(crate) const SLEEP_GAP: Duration = Duration::from_secs(60);
+const PBKDF2_ROUNDS: u32 = 100_000;
+/// Slows down guessing; PBKDF2 alone is quick enough to try thousands of passphrases an hour.
+pub(crate) const FAILED_UNLOCK_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLockConfig {
+    /// Lock after this many minutes without vault use or terminal input; 0 disables.
+    #[serde(default)]
+    pub idle_minutes: u32,
+    /// Lock when the OS session locks or the machine wakes from sleep.
+    #[serde(default)]
+    pub lock_on_os_lock: bool,
+}
+
+impl VaultLockConfig {
+    pub fn load(db: &Db) -> Self {
+        db.settings_get(CONFIG_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &Db) -> Result<(), String> {
+        if self.enabled() && Verifier::load(db).is_none() {
+            return Err("set an unlock passphrase before enabling auto-lock".to_string());
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db.settings_set(CONFIG_KEY, &json).map_err(|e| e.to_string())
+    }
+
+    fn enabled(&self) -> bool {
+        self.idle_minutes > 0 || self.lock_on_os_lock
+    }
+}
+
+/// Why the vault locked.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockReason {
+    Manual,
+    Idle,
+    OsLocked,
+    Resumed,
+    /// The app lock engaged (see `app_lock.rs`).
+    AppLocked,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub locked: bool,
+    pub has_passphrase: bool,
+    pub idle_minutes: u32,
+    pub lock_on_os_lock: bool,
+    /// Seconds until the idle timer locks the vault, when it's running.
+    pub locks_in_secs: Option<u64>,
+}
+
+/// Payload of the `vault:state` event.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultStateEvent {
+    locked: bool,
+    reason: Option<LockReason>,
+}
+
+/// A salted PBKDF2 hash of a passphrase (or PIN), for checking it without storing it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Verifier {
+    salt: String,
+    rounds: u32,
+    hash: String,
+}
+
+impl Verifier {
+    fn load(db: &Db) -> Option<Self> {
+        Self::load_key(db, PASSPHRASE_KEY)
+    }
+
+    pub(crate) fn load_key(db: &Db, key: &str) -> Option<Self> {
+        db.settings_get(key)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+    }
+
+    pub(crate) fn new(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt).map_err(|e| e.to_string())?;
+        let hash = pbkdf2(passphrase.as_bytes(), &salt, PBKDF2_ROUNDS);
+        let b64 = base64::engine::general_purpose::STANDARD;
+        Ok(Self {
+            salt: b64.encode(salt),
+            rounds: PBKDF2_ROUNDS,
+            hash: b64.encode(hash),
+        })
+    }
+
+    pub(crate) fn matches(&self, passphrase: &str) -> bool {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let (Ok(salt), Ok(expected)) = (b64.decode(&self.salt), b64.decode(&self.hash)) else {
+            return false;
+        };
+        let actual = pbkdf2(passphrase.as_bytes(), &salt, self.rounds);
+        // Constant-time compare.
+        expected.len() == actual.len() && expected.iter().zip(actual).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+/// PBKDF2-HMAC-SHA256 with a single 32-byte output block.
+fn pbkdf2(passphrase: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut first = salt.to_vec();
+    first.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = HMAC::mac(&first, passphrase);
+    let mut out = u;
+    for _ in 1..rounds.max(1) {
+        u = HMAC::mac(u, passphrase);
+        for (o, b) in out.iter_mut().zip(u) {
+            *o ^= b;
+        }
+    }
+    out
+}
+
+/// Whether the vault should start locked: with auto-lock on, a fresh launch needs the passphrase.
+pub fn locked_at_startup(db: &Db) -> bool {
+    VaultLockConfig::load(db).enabled() && Verifier::load(db).is_some()
+}
+
+pub fn status(db: &Db, lock: &VaultLock) -> VaultStatus {
+    let config = VaultLockConfig::load(db);
+    let locked = lock.is_locked();
+    let locks_in_secs = (!locked && config.idle_minutes > 0).then(|| {
+        let timeout = u64::from(config.idle_minutes) * 60;
+        timeout.saturating_sub(lock.idle_for().as_secs())
+    });
+    VaultStatus {
+        locked,
+        has_passphrase: Verifier::load(db).is_some(),
+        idle_minutes: config.idle_minutes,
+        lock_on_os_lock: config.lock_on_os_lock,
+        locks_in_secs,
+    }
+}
+
+pub(crate) fn set_locked(app: &AppHandle, lock: &VaultLock, locked: bool, reason: Option<LockReason>) {
+    if lock.set_locked(locked) {
+        match reason {
+            Some(reason) => log::info!("vault locked ({reason:?})"),
+            None => log::info!("vault unlocked"),
+        }
+        let _ = app.emit("vault:state", VaultStateEvent { locked, reason });
+    }
+}
+
+/// Lock the vault now. Needs a passphrase, otherwise there'd be no way back in.
+pub fn lock(app: &AppHandle, db: &Db, lock: &VaultLock) -> Result<(), String> {
+    if Verifier::load(db).is_none() {
+        return Err("set an unlock passphrase before locking the vault".to_string());
+    }
+    set_locked(app, lock, true, Some(LockReason::Manual));
+    Ok(())
+}
+
+pub fn unlock(app: &AppHandle, db: &Db, lock: &VaultLock, passphrase: &str) -> Result<(), String> {
+    if let Some(verifier) = Verifier::load(db) {
+        if !verifier.matches(passphrase) {
+            thread::sleep(FAILED_UNLOCK_DELAY);
+            return Err("wrong passphrase".to_string());
+        }
+    }
+    set_locked(app, lock, false, None);
+    Ok(())
+}
+
+/// Set, change, or (with `new` = None) remove the unlock passphrase.
+///
+/// Changing or removing an existing passphrase requires it. Removing it turns auto-lock off.
+pub fn set_passphrase(
+    app: &AppHandle,
+    db: &Db,
+    lock: &VaultLock,
+    current: Option<&str>,
+    new: Option<&str>,
+) -> Result<(), String> {
+    if let Some(verifier) = Verifier::load(db) {
+        if !current.is_some_and(|c| verifier.matches(c)) {
+            thread::sleep(FAILED_UNLOCK_DELAY);
+            return Err("current passphrase is wrong".to_string());
+        }
+    }
+    match new {
+        Some("") => Err("passphrase cannot be empty".to_string()),
+        Some(p) => {
+            let json = serde_json::to_string(&Verifier::new(p)?).map_err(|e| e.to_string())?;
+            db.settings_set(PASSPHRASE_KEY, &json).map_err(|e| e.to_string())
+        }
+        None => {
+            VaultLockConfig::default().save(db)?;
+            // Settings have no delete; `null` reads back as "no passphrase".
+            db.settings_set(PASSPHRASE_KEY, "null").map_err(|e| e.to_string())?;
+            set_locked(app, lock, false, None);
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn os_session_locked() -> bool {
+    let Some(probe) = screen_lock::probe() else {
+        return false;
+    };
+    exec::run(&probe.command.program, &probe.command.args, PROBE_TIMEOUT)
+        .map(|out| !out.timed_out && probe.is_locked(&out.stdout))
+        .unwrap_or(false)
+}
+
+/// Watch for inactivity, OS session locks, and wake-from-sleep; emits `vault:state` on changes.
+pub fn spawn_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        let mut last_tick = SystemTime::now();
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            // Monotonic time stops during sleep; the wall clock doesn't.
+            let now = SystemTime::now();
+            let gap = now.duration_since(last_tick).unwrap_or_default();
+            last_tick = now;
+
+            let state = app.state::<Arc<AppState>>();
+            if state.vault_lock.is_locked() {
+                continue;
+            }
+            let config = VaultLockConfig::load(&state.db);
+            if !config.enabled() || Verifier::load(&state.db).is_none() {
+                continue;
+            }
+            let reason = if config.lock_on_os_lock && gap > WATCH_INTERVAL + SLEEP_GAP {
+                Some(LockReason::Resumed)
+            } else if config.idle_minutes > 0
+                && state.vault_lock.idle_for() >= Duration::from_secs(u64::from(config.idle_minutes) * 60)
+            {
+                Some(LockReason::Idle)
+            } else if config.lock_on_os_lock && os_session_locked() {
+                Some(LockReason::OsLocked)
+            } else {
+                None
+            };
+            if reason.is_some() {
+                set_locked(&app, &state.vault_lock, true, reason);
+            }
+        }
+    });
+}
@@ -0,0 +1,124 @@
+//! Window size, position and maximized state, kept in settings (`window_state:<key>`) and put
+//! back when the window is created.
+//!
+//! The main window starts hidden (see `tauri.conf.json`) and is shown once restored, so it
+//! doesn't jump. A saved position that no longer lands on a connected monitor (a laptop undocked
+//! since) is dropped and the OS places the window instead.
+
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, PhysicalPosition, PhysicalSize, Runtime, WebviewWindow, WindowEvent};
+
+use crate::db::Db;
+use crate::AppState;
+
+const SETTINGS_PREFIX: &str = "window_state:";
+/// Smaller than this is almost certainly a bad save; ignore it.
+const MIN_WIDTH: u32 = 400;
+const MIN_HEIGHT: u32 = 300;
+/// How much of the title bar must be on a monitor for a saved position to count.
+const VISIBLE_MARGIN: i32 = 64;
+
+/// Physical pixels. Size and position are the last non-maximized bounds, so un-maximizing after
+/// a restore goes back to where the window was.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl WindowState {
+    pub fn load(db: &Db, key: &str) -> Option<Self> {
+        db.settings_get(&format!("{SETTINGS_PREFIX}{key}"))
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::from_str(&v).ok())
+    }
+
+    fn save(&self, db: &Db, key: &str) {
+        let Ok(json) = serde_json::to_string(self) else {
+            return;
+        };
+        if let Err(e) = db.settings_set(&format!("{SETTINGS_PREFIX}{key}"), &json) {
+            log::warn!("window state not saved ({key}): {e}");
+        }
+    }
+}
+
+fn on_some_monitor<R: Runtime>(window: &WebviewWindow<R>, state: &WindowState) -> bool {
+    let Ok(monitors) = window.available_monitors() else {
+        return true;
+    };
+    monitors.iter().any(|m| {
+        let (pos, size) = (m.position(), m.size());
+        let right = pos.x + size.width as i32;
+        let bottom = pos.y + size.height as i32;
+        state.x + state.width as i32 - VISIBLE_MARGIN > pos.x
+            && state.x + VISIBLE_MARGIN < right
+            && state.y >= pos.y
+            && state.y + VISIBLE_MARGIN < bottom
+    })
+}
+
+/// Apply the saved state for `key`, if any; anything that fails to apply keeps its default.
+pub fn restore<R: Runtime>(window: &WebviewWindow<R>, db: &Db, key: &str) {
+    let Some(state) = WindowState::load(db, key) else {
+        return;
+    };
+    if state.width >= MIN_WIDTH && state.height >= MIN_HEIGHT {
+        let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+        if on_some_monitor(window, &state) {
+            let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+        }
+    }
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+fn snapshot<R: Runtime>(window: &WebviewWindow<R>, last: Option<WindowState>) -> Option<WindowState> {
+    // Minimized windows report a parked position (-32000 on Windows); keep the last good one.
+    if window.is_minimized().unwrap_or(false) {
+        return last;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    if let (true, Some(last)) = (maximized, last) {
+        return Some(WindowState { maximized, ..last });
+    }
+    let pos = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(WindowState {
+        x: pos.x,
+        y: pos.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+    })
+}
+
+/// Keep `key`'s saved state current. Moves and resizes are tracked in memory and written when the
+/// window loses focus or closes, not on every event of a drag.
+pub fn track<R: Runtime>(window: &WebviewWindow<R>, key: &str) {
+    let db = window.app_handle().state::<Arc<AppState>>().db.clone();
+    let current = Arc::new(Mutex::new(WindowState::load(&db, key)));
+    let key = key.to_string();
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            let mut current = current.lock().expect("poisoned window state lock");
+            *current = snapshot(&handle, *current);
+        }
+        WindowEvent::Focused(false) | WindowEvent::CloseRequested { .. } => {
+            if let Some(state) = *current.lock().expect("poisoned window state lock") {
+                state.save(&db, &key);
+            }
+        }
+        _ => {}
+    });
+}
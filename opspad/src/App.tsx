@@ -1,7 +1,11 @@
 import "./App.css";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+
 import { AppShell } from "./pages/AppShell";
+import { DETACHED_WINDOW_PREFIX, DetachedTerminal } from "./pages/DetachedTerminal";
 
 function App() {
+  if (getCurrentWindow().label.startsWith(DETACHED_WINDOW_PREFIX)) return <DetachedTerminal />;
   return <AppShell />;
 }
 
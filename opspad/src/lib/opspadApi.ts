@@ -26,10 +26,193 @@ export async function vaultGetSecret(key: string): Promise<string | null> {
   return new TextDecoder().decode(bytes);
 }
 
+/** Whether a secret is stored, without reading it out (never prompts). */
+export async function vaultHasSecret(key: string): Promise<boolean> {
+  return invoke("vault_has_secret", { key });
+}
+
 export async function vaultDeleteSecret(key: string): Promise<void> {
   await invoke("vault_delete_secret", { key });
 }
 
+/** Metadata for a stored secret; values are never listed. */
+export type VaultSecretInfo = {
+  key: string;
+  label: string | null;
+  createdAt: string;
+  updatedAt: string;
+  lastUsedAt: number | null;
+  /** Reading the value shows the platform verification prompt (Windows Hello, Touch ID) first. */
+  requirePresence: boolean;
+  /** Epoch seconds by which the secret should be rotated. */
+  rotateBy: number | null;
+  /** Belongs to a host that no longer exists. */
+  orphaned: boolean;
+};
+
+/** Store a TOTP seed (base32 secret or otpauth:// URI); codes are computed in the backend. */
+export async function vaultTotpSet(key: string, seed: string): Promise<void> {
+  await invoke("vault_totp_set", { key, seed });
+}
+
+export type TotpCode = {
+  code: string;
+  /** Seconds until the code changes. */
+  remainingSecs: number;
+  period: number;
+};
+
+export async function vaultTotpCode(key: string): Promise<TotpCode> {
+  return invoke("vault_totp_code", { key });
+}
+
+export async function vaultListKeys(): Promise<VaultSecretInfo[]> {
+  return invoke("vault_list_keys");
+}
+
+export async function vaultSetLabel(key: string, label: string | null): Promise<void> {
+  await invoke("vault_set_label", { key, label });
+}
+
+/** Turning this off requires passing the verification prompt. */
+export async function vaultSetRequirePresence(key: string, required: boolean): Promise<void> {
+  await invoke("vault_set_require_presence", { key, required });
+}
+
+/** Set or clear the rotation date (epoch seconds). Saving a new value near or after it clears it. */
+export async function vaultSetRotateBy(key: string, rotateBy: number | null): Promise<void> {
+  await invoke("vault_set_rotate_by", { key, rotateBy });
+}
+
+/** Payload item of the `vault:rotation-due` event, also returned by `vaultRotationDue`. */
+export type VaultRotationDue = Omit<VaultSecretInfo, "orphaned"> & {
+  overdue: boolean;
+  /** Whole days until the rotation date; negative once overdue. */
+  daysLeft: number;
+};
+
+export async function vaultRotationDue(): Promise<VaultRotationDue[]> {
+  return invoke("vault_rotation_due");
+}
+
+/** Deletes orphaned secrets; returns their keys. */
+export async function vaultPruneOrphans(): Promise<string[]> {
+  return invoke("vault_prune_orphans");
+}
+
+export type VaultStatus = {
+  locked: boolean;
+  hasPassphrase: boolean;
+  idleMinutes: number;
+  lockOnOsLock: boolean;
+  /** Seconds until the idle timer locks the vault, when it's running. */
+  locksInSecs: number | null;
+};
+
+export type VaultLockConfig = {
+  /** 0 disables the idle timer. */
+  idleMinutes: number;
+  /** Also lock when the OS session locks or the machine wakes from sleep. */
+  lockOnOsLock: boolean;
+};
+
+export type VaultLockReason = "manual" | "idle" | "osLocked" | "resumed" | "appLocked";
+
+/** Payload of the `vault:state` event. */
+export type VaultStateEvent = {
+  locked: boolean;
+  reason: VaultLockReason | null;
+};
+
+export async function vaultStatus(): Promise<VaultStatus> {
+  return invoke("vault_status");
+}
+
+export async function vaultLock(): Promise<VaultStatus> {
+  return invoke("vault_lock");
+}
+
+export async function vaultUnlock(passphrase: string): Promise<VaultStatus> {
+  return invoke("vault_unlock", { passphrase });
+}
+
+export async function vaultLockConfigGet(): Promise<VaultLockConfig> {
+  return invoke("vault_lock_config_get");
+}
+
+export async function vaultLockConfigSet(config: VaultLockConfig): Promise<VaultStatus> {
+  return invoke("vault_lock_config_set", { config });
+}
+
+/** Set, change, or remove (`next` = null) the unlock passphrase. */
+export async function vaultSetPassphrase(current: string | null, next: string | null): Promise<VaultStatus> {
+  return invoke("vault_set_passphrase", { current, new: next });
+}
+
+/** While the app is locked terminal input is refused, output is held until unlock, and the vault is locked. */
+export type AppLockStatus = {
+  locked: boolean;
+  hasPin: boolean;
+  idleMinutes: number;
+  lockOnOsLock: boolean;
+  osAuth: boolean;
+  /** Seconds until the idle timer locks the app, when it's running. */
+  locksInSecs: number | null;
+};
+
+export type AppLockConfig = {
+  /** Minutes without terminal input; 0 disables the idle timer. */
+  idleMinutes: number;
+  /** Also lock when the OS session locks or the machine wakes from sleep. */
+  lockOnOsLock: boolean;
+  /** Windows Hello / Touch ID / polkit can unlock instead of the PIN. */
+  osAuth: boolean;
+};
+
+/** Payload of the `app:lock` event. */
+export type AppLockEvent = {
+  locked: boolean;
+  reason: Exclude<VaultLockReason, "appLocked"> | null;
+};
+
+export async function appLockStatus(): Promise<AppLockStatus> {
+  return invoke("app_lock_status");
+}
+
+export async function appLockEngage(): Promise<AppLockStatus> {
+  return invoke("app_lock_engage");
+}
+
+/** Without a PIN, unlocks through the OS verification prompt (when `osAuth` is on). */
+export async function appUnlock(pin?: string): Promise<AppLockStatus> {
+  return invoke("app_unlock", { pin: pin ?? null });
+}
+
+export async function appLockConfigGet(): Promise<AppLockConfig> {
+  return invoke("app_lock_config_get");
+}
+
+export async function appLockConfigSet(config: AppLockConfig): Promise<AppLockStatus> {
+  return invoke("app_lock_config_set", { config });
+}
+
+/** Set, change, or remove (`next` = null) the app lock PIN. */
+export async function appLockSetPin(current: string | null, next: string | null): Promise<AppLockStatus> {
+  return invoke("app_lock_set_pin", { current, new: next });
+}
+
+/** "rdp" hosts open in a remote desktop client (rdpOpen) instead of a terminal. */
+export type ConnectionType = "ssh" | "rdp";
+
+/**
+ * "a" / "srv": the hostname is a discovery name whose A/AAAA or SRV records are the actual
+ * targets, resolved when a session opens.
+ */
+export type DnsExpansion = "off" | "a" | "srv";
+
+/** pick: the UI asks (hostResolveTargets). roundRobin: each session takes the next target. */
+export type DnsSelection = "pick" | "roundRobin";
+
 export type Host = {
   id: string;
   label: string;
@@ -39,10 +222,70 @@ export type Host = {
   environmentTag: string;
   identityFile?: string | null;
   color?: string | null;
+  groupId?: string | null;
+  /** Set for hosts from a team subscription; those are read-only. */
+  subscriptionId?: string | null;
+  /** Vault key typed when ssh asks for the login password. */
+  passwordVaultKey?: string | null;
+  /** Vault key typed when ssh asks for the key passphrase (defaults to `host:<id>:ssh_key_passphrase`). */
+  passphraseVaultKey?: string | null;
+  /** Listed first in the tray's quick connect menu. */
+  pinned?: boolean;
+  /** Epoch seconds of the last ssh or RDP session opened to the host (read-only). */
+  lastConnectedAt?: number | null;
+  connectionType?: ConnectionType;
+  /** ssh's IdentityAgent for this host (an agent socket, or "none"). */
+  identityAgent?: string | null;
+  /** ssh's SecurityKeyProvider: FIDO middleware for sk- keys when ssh has none built in. */
+  securityKeyProvider?: string | null;
+  dnsExpansion?: DnsExpansion;
+  dnsSelection?: DnsSelection;
+  /** Epoch seconds after which an ephemeral host is archived; null for ordinary hosts. */
+  expiresAt?: number | null;
+  /** Set once archived (read-only); archived hosts are left out of hostsList. */
+  archivedAt?: number | null;
+  /** The last successful post-connect probe of the server (read-only). */
+  serverInfo?: ServerInfo | null;
+  /** `color`, or the environment's color when the host has none (read-only). */
+  effectiveColor?: string | null;
 };
 
-export async function hostsList(): Promise<Host[]> {
-  return invoke("hosts_list");
+/** Filters and paging for `hostsList`; omitted fields don't filter. */
+export type HostQuery = {
+  groupId?: string | null;
+  environmentTag?: string | null;
+  /** Case-insensitive substring of the label, hostname or username. */
+  search?: string | null;
+  limit?: number | null;
+  offset?: number | null;
+  /** Order by the health monitor's last check instead of the stored order. */
+  sort?: HostSort;
+};
+
+/** reachableFirst: up, slow, unchecked, down. byStatus: down, slow, up, unchecked. */
+export type HostSort = "manual" | "reachableFirst" | "byStatus";
+
+/** All hosts, or with `query` a filtered, sorted page of them. */
+export async function hostsList(query?: HostQuery): Promise<Host[]> {
+  return invoke("hosts_list", { query: query ?? null });
+}
+
+/** How many hosts match `query`'s filters (its limit and offset are ignored). */
+export async function hostsCount(query?: HostQuery): Promise<number> {
+  return invoke("hosts_count", { query: query ?? null });
+}
+
+/**
+ * Field errors from a rejected host or command save: the backend reports one `field: message`
+ * line per problem. Empty if `e` isn't a validation error.
+ */
+export function fieldErrors(e: unknown): Record<string, string> {
+  const out: Record<string, string> = {};
+  for (const line of String(e).split("\n")) {
+    const m = /^([A-Za-z]+): (.+)$/.exec(line);
+    if (m) out[m[1]] = m[2];
+  }
+  return out;
 }
 
 export async function hostsCreate(input: {
@@ -53,6 +296,12 @@ export async function hostsCreate(input: {
   environmentTag: string;
   identityFile?: string | null;
   color?: string | null;
+  /** Defaults to "ssh"; the port defaults to 22, or 3389 for RDP. */
+  connectionType?: ConnectionType;
+  dnsExpansion?: DnsExpansion;
+  dnsSelection?: DnsSelection;
+  /** Makes the host ephemeral: epoch seconds, in the future. */
+  expiresAt?: number | null;
 }): Promise<Host> {
   return invoke("hosts_create", {
     input: {
@@ -63,12 +312,140 @@ export async function hostsCreate(input: {
       environmentTag: input.environmentTag,
       identityFile: input.identityFile ?? null,
       color: input.color ?? null,
+      connectionType: input.connectionType ?? "ssh",
+      dnsExpansion: input.dnsExpansion ?? "off",
+      dnsSelection: input.dnsSelection ?? "pick",
+      expiresAt: input.expiresAt ?? null,
     },
   });
 }
 
-export async function hostsDelete(id: string): Promise<void> {
-  await invoke("hosts_delete", { id });
+export type HostImportSource = "putty" | "termius" | "secureCrt";
+
+/** A host as translated from the other client. */
+export type ImportedHost = {
+  label: string;
+  hostname: string;
+  port: number;
+  username: string;
+  identityFile: string | null;
+  /** Folder/group in the source; becomes the host group of that name. */
+  group: string | null;
+  /** Set by inventory sources, which map it from a label. */
+  environmentTag?: string;
+};
+
+export type ImportNote = {
+  /** Session/host name in the source. */
+  entry: string;
+  message: string;
+};
+
+export type HostImportReport = {
+  imported: ImportedHost[];
+  skipped: ImportNote[];
+  /** Settings that had no equivalent and were dropped. */
+  notes: ImportNote[];
+};
+
+/**
+ * Import saved sessions from PuTTY (.reg export, or the registry on Windows when no path is
+ * given), Termius (CSV export) or SecureCRT (XML export). dryRun only reports.
+ */
+export async function hostsImport(
+  source: HostImportSource,
+  path: string | null,
+  environmentTag: string,
+  dryRun?: boolean,
+): Promise<HostImportReport> {
+  return invoke("hosts_import", { source, path, environmentTag, dryRun: dryRun ?? null });
+}
+
+export type InventoryProviderConfig =
+  | {
+      kind: "gcp";
+      project: string;
+      /** A gcloud account other than the active one. */
+      account?: string | null;
+      impersonateServiceAccount?: string | null;
+    }
+  | {
+      kind: "azure";
+      subscriptionId: string;
+      resourceGroup?: string | null;
+      /** Service principal secrets are set with inventorySecretSet. */
+      auth?: { method: "cli" } | { method: "servicePrincipal"; tenantId: string; clientId: string };
+    }
+  | {
+      /** The nodes of a cluster (kubectl get nodes); InternalIP is the private address. */
+      kind: "kubernetes";
+      context: string;
+      kubeconfig?: string | null;
+    };
+
+/** How an instance becomes a host; "labels" are GCP/Kubernetes labels or Azure tags. */
+export type InventoryHostMapping = {
+  environmentLabel?: string | null;
+  defaultEnvironment?: string;
+  groupLabel?: string | null;
+  usernameLabel?: string | null;
+  defaultUsername?: string | null;
+  address?: "privateIp" | "publicIp" | "dnsName";
+  /** Prepended to instance names. */
+  labelPrefix?: string | null;
+};
+
+export type InventorySource = {
+  /** Empty to create. */
+  id: string;
+  name: string;
+  provider: InventoryProviderConfig;
+  /** Only instances with all of these labels/tags. */
+  filter?: Record<string, string>;
+  mapping?: InventoryHostMapping;
+};
+
+export type InventorySyncReport = {
+  discovered: number;
+  created: ImportedHost[];
+  /** Existing hosts (matched by label) whose address or environment changed. */
+  updated: ImportedHost[];
+  unchanged: number;
+  skipped: ImportNote[];
+  notes: ImportNote[];
+};
+
+export async function inventorySourcesList(): Promise<InventorySource[]> {
+  return invoke("inventory_sources_list");
+}
+
+export async function inventorySourceSave(source: InventorySource): Promise<InventorySource> {
+  return invoke("inventory_source_save", { source });
+}
+
+export async function inventorySourceDelete(id: string): Promise<void> {
+  await invoke("inventory_source_delete", { id });
+}
+
+/** Client secret for an Azure service principal source; empty deletes it. */
+export async function inventorySecretSet(sourceId: string, secret: string): Promise<void> {
+  await invoke("inventory_secret_set", { sourceId, secret });
+}
+
+/** Create or update hosts from the source's instances. dryRun only reports. */
+export async function inventorySync(sourceId: string, dryRun?: boolean): Promise<InventorySyncReport> {
+  return invoke("inventory_sync", { sourceId, dryRun: dryRun ?? null });
+}
+
+/**
+ * Fails while a terminal to the host is open unless `force` (which closes those sessions).
+ * `deleteHistory` also drops the host's CommandDock history.
+ */
+export async function hostsDelete(
+  id: string,
+  opts?: { force?: boolean; deleteHistory?: boolean },
+): Promise<void> {
+  await invoke("hosts_delete", { id, force: opts?.force ?? null, deleteHistory: opts?.deleteHistory ?? null });
 }
 
 export async function hostsUpdate(input: {
@@ -80,6 +457,10 @@ export async function hostsUpdate(input: {
   environmentTag: string;
   identityFile?: string | null;
   color?: string | null;
+  /** Unchanged when omitted, like the DNS fields. */
+  connectionType?: ConnectionType;
+  dnsExpansion?: DnsExpansion;
+  dnsSelection?: DnsSelection;
 }): Promise<Host> {
   return invoke("hosts_update", {
     input: {
@@ -91,6 +472,9 @@ export async function hostsUpdate(input: {
       environmentTag: input.environmentTag,
       identityFile: input.identityFile ?? null,
       color: input.color ?? null,
+      connectionType: input.connectionType ?? null,
+      dnsExpansion: input.dnsExpansion ?? null,
+      dnsSelection: input.dnsSelection ?? null,
     },
   });
 }
@@ -99,6 +483,164 @@ export async function hostsReorder(ids: string[]): Promise<void> {
   await invoke("hosts_reorder", { ids });
 }
 
+/** Make a host ephemeral (archived after `expiresAt`, epoch seconds), or ordinary again with null. */
+export async function hostsSetExpiry(id: string, expiresAt: number | null): Promise<void> {
+  await invoke("hosts_set_expiry", { id, expiresAt });
+}
+
+/** Ephemeral hosts archived after expiry, most recent first. The sweep emits `hosts:archived` (Host[]). */
+export async function hostsArchivedList(): Promise<Host[]> {
+  return invoke("hosts_archived_list");
+}
+
+/** Restore an archived host as an ordinary, non-expiring one. */
+export async function hostsUnarchive(id: string): Promise<void> {
+  await invoke("hosts_unarchive", { id });
+}
+
+export async function hostsSetPinned(id: string, pinned: boolean): Promise<void> {
+  await invoke("hosts_set_pinned", { id, pinned });
+}
+
+export async function hostsSetGroup(id: string, groupId: string | null): Promise<void> {
+  await invoke("hosts_set_group", { id, groupId });
+}
+
+/** Bind vault keys for the login password and key passphrase; null unbinds. */
+export async function hostsSetCredentials(
+  id: string,
+  passwordVaultKey: string | null,
+  passphraseVaultKey: string | null,
+): Promise<Host> {
+  return invoke("hosts_set_credentials", { id, passwordVaultKey, passphraseVaultKey });
+}
+
+/** Machine-local ssh options for hardware security keys; null clears one. */
+export async function hostsSetSecurityKey(
+  id: string,
+  identityAgent: string | null,
+  securityKeyProvider: string | null,
+): Promise<Host> {
+  return invoke("hosts_set_security_key", { id, identityAgent, securityKeyProvider });
+}
+
+export type SecurityKeySupport = {
+  /** `ssh -V` output. */
+  sshVersion: string | null;
+  /** ssh knows the sk- key types (OpenSSH 8.2+). */
+  keyTypes: boolean;
+  /** libfido2 (or Windows Hello) is there for ssh to use without a SecurityKeyProvider. */
+  builtinProvider: boolean;
+  skHelper: string | null;
+  /** What's missing, in words. */
+  notes: string[];
+};
+
+export async function securityKeySupport(): Promise<SecurityKeySupport> {
+  return invoke("security_key_support");
+}
+
+export type HostGroup = {
+  id: string;
+  name: string;
+};
+
+export async function hostGroupsList(): Promise<HostGroup[]> {
+  return invoke("host_groups_list");
+}
+
+export async function hostGroupsCreate(name: string): Promise<HostGroup> {
+  return invoke("host_groups_create", { name });
+}
+
+export async function hostGroupsUpdate(input: HostGroup): Promise<HostGroup> {
+  return invoke("host_groups_update", { input });
+}
+
+export async function hostGroupsDelete(id: string): Promise<void> {
+  await invoke("host_groups_delete", { id });
+}
+
+/**
+ * What a destructive command needs in an environment: nothing extra, a click confirm, the
+ * environment name typed, a reason (kept in history), or it's refused.
+ */
+export type DestructivePolicy = "allow" | "confirm" | "typedName" | "reason" | "block";
+
+export type Environment = {
+  name: string;
+  /** Also the color of its hosts and single-environment dock commands that have none of their own. */
+  color: string | null;
+  destructivePolicy: DestructivePolicy;
+  /** Write a warning banner into new sessions in this environment. */
+  banner?: boolean;
+  /** Extra line shown in the banner. */
+  bannerText?: string | null;
+  /** Destructive commands also need a signed approval from a trusted teammate. */
+  fourEyes?: boolean;
+};
+
+export async function environmentsList(): Promise<Environment[]> {
+  return invoke("environments_list");
+}
+
+export async function environmentsUpsert(input: Environment): Promise<Environment> {
+  return invoke("environments_upsert", { input });
+}
+
+export async function environmentsDelete(name: string): Promise<void> {
+  await invoke("environments_delete", { name });
+}
+
+/** This installation's approver identity, to hand to teammates. */
+export type ApproverIdentity = {
+  name: string;
+  /** Base64 ed25519 public key. */
+  publicKey: string;
+  fingerprint: string;
+};
+
+/** A teammate whose four-eyes approvals are accepted. */
+export type TrustedApprover = ApproverIdentity;
+
+/** The command a request code asks a teammate to approve. */
+export type ApprovalRequest = {
+  environment: string;
+  command: string;
+  requestedBy: string;
+  requestedAt: string;
+};
+
+export async function fourEyesIdentity(): Promise<ApproverIdentity> {
+  return invoke("four_eyes_identity");
+}
+
+export async function fourEyesApproversList(): Promise<TrustedApprover[]> {
+  return invoke("four_eyes_approvers_list");
+}
+
+export async function fourEyesApproverAdd(name: string, publicKey: string): Promise<TrustedApprover> {
+  return invoke("four_eyes_approver_add", { name, publicKey });
+}
+
+export async function fourEyesApproverRemove(fingerprint: string): Promise<boolean> {
+  return invoke("four_eyes_approver_remove", { fingerprint });
+}
+
+/** A request code to send a teammate out-of-band. */
+export async function fourEyesRequest(environment: string, command: string): Promise<string> {
+  return invoke("four_eyes_request", { environment, command });
+}
+
+export async function fourEyesRequestInspect(code: string): Promise<ApprovalRequest> {
+  return invoke("four_eyes_request_inspect", { code });
+}
+
+/** Approve a teammate's request code; returns the approval token to send back. */
+export async function fourEyesApprove(code: string, validMinutes?: number): Promise<string> {
+  return invoke("four_eyes_approve", { code, validMinutes: validMinutes ?? null });
+}
+
 export async function terminalOpenLocal(): Promise<string> {
   return invoke("terminal_open_local", { environmentTag: "LOCAL" });
 }
@@ -123,104 +665,3154 @@ export async function terminalOpenSsh(args: {
   });
 }
 
-export async function terminalWrite(
-  sessionId: string,
-  data: string,
-  origin?: string,
-  meta?: {
-    dockCommandId?: string;
-    dockCommandTitle?: string;
-    dockCommandTemplate?: string;
-  },
-): Promise<void> {
-  // Keep payload minimal to avoid changing IPC args unless needed.
-  const payload: Record<string, unknown> = { sessionId, data };
-  if (origin) payload.origin = origin;
-  if (meta?.dockCommandId) payload.dockCommandId = meta.dockCommandId;
-  if (meta?.dockCommandTitle) payload.dockCommandTitle = meta.dockCommandTitle;
-  if (meta?.dockCommandTemplate) payload.dockCommandTemplate = meta.dockCommandTemplate;
-  await invoke("terminal_write", payload);
+export type ClipboardConfig = {
+  enabled: boolean;
+  maxEntries: number;
+};
+
+export type ClipboardEntry = {
+  id: string;
+  /** Redacted. */
+  text: string;
+  sessionId: string | null;
+  environmentTag: string | null;
+  /** Epoch seconds. */
+  copiedAt: number;
+  truncated: boolean;
+};
+
+export async function clipboardHistoryConfigGet(): Promise<ClipboardConfig> {
+  return invoke("clipboard_history_config_get");
 }
 
-export async function terminalResize(
-  sessionId: string,
-  cols: number,
-  rows: number,
-): Promise<void> {
-  await invoke("terminal_resize", { sessionId, cols, rows });
+/** Turning history off also drops what it collected. */
+export async function clipboardHistoryConfigSet(config: ClipboardConfig): Promise<ClipboardConfig> {
+  return invoke("clipboard_history_config_set", { config });
 }
 
-export async function terminalClose(sessionId: string): Promise<void> {
-  await invoke("terminal_close", { sessionId });
+/** Record text copied from a terminal (ignored unless clipboard history is on). */
+export async function clipboardHistoryAdd(text: string, sessionId?: string | null): Promise<ClipboardEntry | null> {
+  return invoke("clipboard_history_add", { text, sessionId: sessionId ?? null });
 }
 
-export async function terminalMarkExited(sessionId: string): Promise<void> {
-  await invoke("terminal_mark_exited", { sessionId });
+/** Newest first; memory only. */
+export async function clipboardHistoryList(): Promise<ClipboardEntry[]> {
+  return invoke("clipboard_history_list");
 }
 
-export type DockCommand = {
+export async function clipboardHistoryClear(): Promise<void> {
+  await invoke("clipboard_history_clear");
+}
+
+export type PaletteItem = {
+  kind: "session" | "host" | "command" | "runbook" | "history";
+  /** Session, host, dock command, runbook or history entry id, by kind. */
   id: string;
   title: string;
-  command: string;
-  requiresConfirm: boolean;
-  color?: string | null;
+  subtitle: string | null;
+  environmentTag: string | null;
+  score: number;
 };
 
-export async function dockCommandsList(): Promise<DockCommand[]> {
-  return invoke("dock_commands_list");
+/** One search across sessions, hosts, dock commands, runbooks and history; best match first. */
+export async function paletteQuery(text: string, limit?: number | null): Promise<PaletteItem[]> {
+  return invoke("palette_query", { text, limit: limit ?? null });
 }
 
-export async function dockCommandsCreate(input: {
-  title: string;
-  command: string;
-  requiresConfirm?: boolean;
-  color?: string | null;
-}): Promise<DockCommand> {
-  return invoke("dock_commands_create", {
-    input: {
-      title: input.title,
-      command: input.command,
-      requiresConfirm: input.requiresConfirm ?? false,
-      color: input.color ?? null,
-    },
-  });
+export type TerminalMark = {
+  id: string;
+  sessionId: string;
+  scope: string | null;
+  label: string;
+  /** ISO-8601, UTC. */
+  createdAt: string;
+};
+
+/** Mark a point in a live session's timeline (shown in its exported transcript and recording). */
+export async function terminalMark(sessionId: string, label: string): Promise<TerminalMark> {
+  return invoke("terminal_mark", { sessionId, label });
 }
 
-export async function dockCommandsUpdate(input: DockCommand): Promise<DockCommand> {
-  return invoke("dock_commands_update", { input });
+/** Oldest first. */
+export async function terminalMarksList(sessionId: string): Promise<TerminalMark[]> {
+  return invoke("terminal_marks_list", { sessionId });
 }
 
-export async function dockCommandsDelete(id: string): Promise<void> {
-  await invoke("dock_commands_delete", { id });
+/** Type a vault secret into a session, then Enter. The secret stays in the backend. */
+export async function terminalTypeSecret(sessionId: string, vaultKey: string): Promise<void> {
+  await invoke("terminal_type_secret", { sessionId, vaultKey });
 }
 
-export async function dockCommandsReorder(ids: string[]): Promise<void> {
-  await invoke("dock_commands_reorder", { ids });
+/** Type the current code of a stored TOTP seed into a session, then Enter. */
+export async function terminalTypeTotp(sessionId: string, vaultKey: string): Promise<void> {
+  await invoke("terminal_type_totp", { sessionId, vaultKey });
 }
 
-export async function dockRunbookGet(): Promise<string> {
-  return invoke("dock_runbook_get");
+export type PreflightCheck = {
+  name: "sshConfig" | "dns" | "proxy" | "tcp" | "identityFile" | "securityKey";
+  status: "pass" | "warn" | "fail" | "skipped";
+  detail: string;
+};
+
+export type Preflight = {
+  /** user@host:port as ssh will connect to it. */
+  target: string;
+  ok: boolean;
+  checks: PreflightCheck[];
+};
+
+/** DNS, TCP (or jump host) and identity file checks for a host, without connecting over ssh. */
+export async function hostPreflight(id: string): Promise<Preflight> {
+  return invoke("host_preflight", { id });
 }
 
-export async function dockRunbookSet(markdown: string): Promise<void> {
-  await invoke("dock_runbook_set", { markdown });
+export type PathHop = {
+  /** The hop as written in ProxyJump, or user@host for the target itself. */
+  hop: string;
+  isTarget: boolean;
+  status: "pass" | "fail" | "skipped";
+  /** Where a failed hop stopped. */
+  stage: "connect" | "hostKey" | "auth" | null;
+  detail: string;
+  durationMs: number;
+};
+
+export type PathReport = {
+  target: string;
+  ok: boolean;
+  /** Index into hops of the first failure. */
+  failedHop: number | null;
+  hops: PathHop[];
+};
+
+/** Log in to each jump host of a host's chain in turn, then the host (no prompts: BatchMode). */
+export type DnsTarget = {
+  /** An IP address (A/AAAA) or an SRV record's target name. */
+  address: string;
+  port: number;
+  priority: number | null;
+  weight: number | null;
+};
+
+export type ResolvedTargets = {
+  hostId: string;
+  name: string;
+  expansion: DnsExpansion;
+  selection: DnsSelection;
+  /** In preference order. */
+  targets: DnsTarget[];
+  /** Index of the target a session opened without picking would take. */
+  suggested: number;
+};
+
+/**
+ * Resolve a DNS-expanded host's targets for a picker; open the chosen one with
+ * terminalOpenSsh({ host: target.address, port: target.port, hostId }).
+ */
+export async function hostResolveTargets(id: string): Promise<ResolvedTargets> {
+  return invoke("host_resolve_targets", { id });
 }
 
-export type DockHistoryItem = {
+export type HostConnection = {
   id: string;
-  createdAt: number;
-  environmentTag: string;
-  commandText: string;
+  hostId: string;
+  sessionId: string;
+  /** The host's hostname when connected. */
+  hostname: string;
+  /** Where the session actually went. */
+  address: string;
+  port: number;
+  connectedAt: number;
 };
 
-export async function dockHistoryList(limit?: number): Promise<DockHistoryItem[]> {
-  return invoke("dock_history_list", { limit: limit ?? null });
+/** ssh sessions opened to a host, newest first. */
+export async function hostsConnections(hostId: string, limit?: number): Promise<HostConnection[]> {
+  return invoke("hosts_connections", { hostId, limit: limit ?? null });
 }
 
-export async function dockHistoryDelete(id: string): Promise<void> {
-  await invoke("dock_history_delete", { id });
+export async function hostVerifyPath(id: string): Promise<PathReport> {
+  return invoke("host_verify_path", { id });
 }
 
-export async function dockHistoryClear(): Promise<void> {
-  await invoke("dock_history_clear");
+export type PreflightConfig = {
+  /** Check before every ssh session and refuse to open on a failure. */
+  auto: boolean;
+  timeoutMs: number;
+};
+
+export type SudoAssistConfig = {
+  /** Type the stored password at sudo's prompt without asking. */
+  autoRespond: boolean;
+  /** For ssh sessions without a host:<id>:sudo_password secret, try the host's login password. */
+  useLoginPassword: boolean;
+  /** Fallback vault key for any session, including local ones. */
+  vaultKey: string | null;
+};
+
+/** Payload of the `terminal:sudo_prompt` event. */
+export type SudoPromptEvent = {
+  sessionId: string;
+  user: string | null;
+  /** Vault key that would answer it (for terminalTypeSecret), if one is stored. */
+  vaultKey: string | null;
+  autoAnswered: boolean;
+  /** Why it wasn't answered while auto-respond is on. */
+  note: string | null;
+};
+
+export type AuthPromptConfig = {
+  /** Route ssh's login questions through auth:prompt (OpenSSH 8.4+; new sessions only). */
+  enabled: boolean;
+};
+
+export type AuthPromptKind = "password" | "passphrase" | "otp" | "hostKey" | "confirm" | "notice" | "touch" | "other";
+
+/** Payload of the `auth:prompt` event: ssh is waiting for an answer (except "notice" and "touch"). */
+export type AuthPrompt = {
+  id: string;
+  /** Null when ssh asked before the session finished opening. */
+  sessionId: string | null;
+  kind: AuthPromptKind;
+  /** ssh's own text, e.g. "(alice@web-1) Verification code: ". */
+  prompt: string;
+  /** Whether the answer may be shown while typed. */
+  echo: boolean;
+};
+
+/** Payload of `auth:prompt_closed`: answered, cancelled or timed out. */
+export type AuthPromptClosedEvent = {
+  id: string;
+  answered: boolean;
+};
+
+export async function authPromptConfigGet(): Promise<AuthPromptConfig> {
+  return invoke("auth_prompt_config_get");
+}
+
+export async function authPromptConfigSet(config: AuthPromptConfig): Promise<void> {
+  await invoke("auth_prompt_config_set", { config });
+}
+
+export async function authPromptsPending(): Promise<AuthPrompt[]> {
+  return invoke("auth_prompts_pending");
+}
+
+/** Answer an auth:prompt; null cancels it. */
+export async function authPromptAnswer(id: string, answer: string | null): Promise<void> {
+  await invoke("auth_prompt_answer", { id, answer });
+}
+
+/** Payload of the `terminal:privileged` event: the session's shell went in or out of root. */
+export type TerminalPrivilegedEvent = {
+  sessionId: string;
+  privileged: boolean;
+  /** "shellIntegration" when the shell reported its uid, "prompt" when guessed from a `#` prompt. */
+  source: "shellIntegration" | "prompt";
+  environmentTag: string;
+};
+
+export async function sudoAssistConfigGet(): Promise<SudoAssistConfig> {
+  return invoke("sudo_assist_config_get");
+}
+
+export async function sudoAssistConfigSet(config: SudoAssistConfig): Promise<void> {
+  await invoke("sudo_assist_config_set", { config });
+}
+
+export async function preflightConfigGet(): Promise<PreflightConfig> {
+  return invoke("preflight_config_get");
+}
+
+export async function preflightConfigSet(config: PreflightConfig): Promise<void> {
+  await invoke("preflight_config_set", { config });
+}
+
+export type RestoreSession = {
+  scope: string;
+  kind: "local" | "ssh";
+  environmentTag: string;
+  cols: number;
+  rows: number;
+  /** The saved host, if the scope is one and it still exists. */
+  hostId: string | null;
+  label: string;
+  login: "none" | "keyFile" | "password" | "interactive";
+  /** Whether it can be reopened without the user (local, or a host with bound credentials). */
+  reconnect: boolean;
+};
+
+export type RestorePlan = {
+  /** When the sessions were saved (epoch seconds). */
+  savedAt: number;
+  sessions: RestoreSession[];
+  /** How the main window was arranged; panes name sessions by scope. */
+  layout: WorkspaceLayout | null;
+};
+
+/** The sessions open when the app last quit; null if there were none. */
+export async function sessionRestorePlan(): Promise<RestorePlan | null> {
+  return invoke("session_restore_plan");
+}
+
+/** Forget the saved sessions once they've been reopened or declined. */
+export async function sessionRestoreDismiss(): Promise<void> {
+  await invoke("session_restore_dismiss");
+}
+
+/** A tab's panes: one session (by the scope it was opened for), or a split into several. */
+export type LayoutPane =
+  | { type: "session"; scope: string; title?: string | null }
+  | {
+      type: "split";
+      /** horizontal: side by side; vertical: stacked. */
+      direction: "horizontal" | "vertical";
+      /** Each child's share; normalized to sum to 1 on save (empty: equal shares). */
+      sizes?: number[];
+      children: LayoutPane[];
+    };
+
+export type LayoutTab = {
+  id: string;
+  group?: string | null;
+  pane: LayoutPane;
+};
+
+export type TabGroup = {
+  id: string;
+  name: string;
+  color?: string | null;
+  collapsed?: boolean;
+};
+
+export type WorkspaceLayout = {
+  /** In display order. */
+  tabs: LayoutTab[];
+  groups: TabGroup[];
+  activeTab?: string | null;
+};
+
+export type SavedLayout = {
+  workspace: string;
+  layout: WorkspaceLayout;
+  updatedAt: string;
+};
+
+export type WorkspaceSummary = {
+  workspace: string;
+  tabs: number;
+  updatedAt: string;
+};
+
+/** Default workspace: "main". */
+export async function layoutGet(workspace?: string): Promise<SavedLayout | null> {
+  return invoke("layout_get", { workspace });
+}
+
+/** Save the arrangement (replacing the workspace's previous one). */
+export async function layoutSet(layout: WorkspaceLayout, workspace?: string): Promise<SavedLayout> {
+  return invoke("layout_set", { workspace, layout });
+}
+
+export async function layoutList(): Promise<WorkspaceSummary[]> {
+  return invoke("layout_list");
+}
+
+export async function layoutDelete(workspace: string): Promise<void> {
+  await invoke("layout_delete", { workspace });
+}
+
+export type KubeContext = {
+  name: string;
+  cluster: string | null;
+  user: string | null;
+  namespace: string | null;
+  current: boolean;
+  kubeconfig: string;
+};
+
+/** The kube context kubectl dock commands get in a scope; a host's own wins over its environment's. */
+export type K8sPref = {
+  scope: string;
+  kubeconfig: string | null;
+  context: string;
+  namespace: string | null;
+  updatedAt: string;
+};
+
+export async function k8sContextsList(kubeconfig?: string): Promise<KubeContext[]> {
+  return await invoke<KubeContext[]>("k8s_contexts_list", { kubeconfig: kubeconfig ?? null });
+}
+
+export async function k8sPrefsList(): Promise<K8sPref[]> {
+  return await invoke<K8sPref[]>("k8s_prefs_list");
+}
+
+export async function k8sPrefsSet(
+  scope: string,
+  context: string,
+  opts?: { kubeconfig?: string; namespace?: string },
+): Promise<K8sPref> {
+  return await invoke<K8sPref>("k8s_prefs_set", {
+    scope,
+    context,
+    kubeconfig: opts?.kubeconfig ?? null,
+    namespace: opts?.namespace ?? null,
+  });
+}
+
+export async function k8sPrefsDelete(scope: string): Promise<void> {
+  await invoke("k8s_prefs_delete", { scope });
+}
+
+/** Terminal sessions that receive the same keystrokes (in memory; ended sessions drop out). */
+export type BroadcastGroup = {
+  id: string;
+  name: string;
+  sessionIds: string[];
+};
+
+export type GroupOpen = {
+  /** The opened sessions, registered as a broadcast group named after the host group. */
+  broadcast: BroadcastGroup;
+  sessions: { sessionId: string; hostId: string }[];
+  /** Hosts whose session couldn't be started. */
+  failures: { hostId: string; label: string; error: string }[];
+};
+
+/** Open a session to every host in a host group (in batches of the work queue's parallelism). */
+/** A remote desktop client launched for an RDP host; there's no PTY behind it. */
+export type RdpSession = {
+  id: string;
+  hostId: string;
+  label: string;
+  /** "mstsc", "xfreerdp", ... */
+  client: string;
+  credentialsInjected: boolean;
+  startedAt: number;
+};
+
+/** Payload of "rdp:closed"; "rdp:opened" carries the RdpSession. */
+export type RdpClosedEvent = {
+  id: string;
+  hostId: string;
+  exitCode: number | null;
+  durationSecs: number;
+};
+
+/**
+ * Launch mstsc (Windows), Microsoft Remote Desktop (macOS) or FreeRDP for an RDP host.
+ * injectCredentials hands the client the host's bound password (not supported on macOS).
+ */
+export async function rdpOpen(hostId: string, injectCredentials?: boolean): Promise<RdpSession> {
+  return invoke("rdp_open", { hostId, injectCredentials: injectCredentials ?? null });
+}
+
+export async function rdpSessionsList(): Promise<RdpSession[]> {
+  return invoke("rdp_sessions_list");
+}
+
+/** Close the client; "rdp:closed" follows. */
+export async function rdpClose(id: string): Promise<void> {
+  await invoke("rdp_close", { id });
+}
+
+export async function terminalOpenGroup(groupId: string): Promise<GroupOpen> {
+  return invoke("terminal_open_group", { groupId });
+}
+
+export async function terminalBroadcastList(): Promise<BroadcastGroup[]> {
+  return invoke("terminal_broadcast_list");
+}
+
+/** Type `data` into every session of a broadcast group; resolves to how many got it. */
+export async function terminalBroadcastWrite(broadcastId: string, data: string): Promise<number> {
+  return invoke("terminal_broadcast_write", { broadcastId, data });
+}
+
+/** Forget a broadcast group; its sessions stay open. */
+export async function terminalBroadcastRemove(broadcastId: string): Promise<boolean> {
+  return invoke("terminal_broadcast_remove", { broadcastId });
+}
+
+export async function terminalWrite(
+  sessionId: string,
+  data: string,
+  origin?: string,
+  meta?: {
+    dockCommandId?: string;
+    dockCommandTitle?: string;
+    dockCommandTemplate?: string;
+    /** Typed confirmation (environment name) for policy-gated commands. */
+    confirmation?: string;
+    /** A teammate's approval token, in four-eyes environments. */
+    approval?: string;
+    /** `commandHash` of the preview that was confirmed. */
+    confirmedHash?: string;
+  },
+): Promise<void> {
+  // Keep payload minimal to avoid changing IPC args unless needed.
+  const payload: Record<string, unknown> = { sessionId, data };
+  if (origin) payload.origin = origin;
+  if (meta?.dockCommandId) payload.dockCommandId = meta.dockCommandId;
+  if (meta?.dockCommandTitle) payload.dockCommandTitle = meta.dockCommandTitle;
+  if (meta?.dockCommandTemplate) payload.dockCommandTemplate = meta.dockCommandTemplate;
+  if (meta?.confirmation) payload.confirmation = meta.confirmation;
+  if (meta?.approval) payload.approval = meta.approval;
+  if (meta?.confirmedHash) payload.confirmedHash = meta.confirmedHash;
+  await invoke("terminal_write", payload);
+}
+
+export async function terminalResize(
+  sessionId: string,
+  cols: number,
+  rows: number,
+): Promise<void> {
+  await invoke("terminal_resize", { sessionId, cols, rows });
+}
+
+/** Payload of `terminal:renamed` (title null: cleared). `terminal:exit` carries the title too. */
+export type TerminalRenamedEvent = {
+  sessionId: string;
+  title: string | null;
+};
+
+/** Title the session (up to 80 characters); an empty title clears it. */
+export async function terminalRename(sessionId: string, title: string): Promise<void> {
+  await invoke("terminal_rename", { sessionId, title });
+}
+
+/** Payload of `terminal:readonly`. */
+export type TerminalReadonlyEvent = {
+  sessionId: string;
+  readonly: boolean;
+};
+
+/** While read-only, the backend refuses every write to the session until this turns it off. */
+export async function terminalSetReadonly(sessionId: string, readonly: boolean): Promise<void> {
+  await invoke("terminal_set_readonly", { sessionId, readonly });
+}
+
+/** One pipeline stage's counters. Percentiles are power-of-two bucket bounds, in microseconds. */
+export type StageMetrics = {
+  count: number;
+  bytes: number;
+  meanUs: number;
+  maxUs: number;
+  p50Us: number;
+  p95Us: number;
+  p99Us: number;
+  bytesPerSec: number;
+};
+
+/** Also the `perf:metrics` payload, sent every second while the overlay is on. */
+export type PerfMetrics = {
+  /** When counting started (first activity after launch or a reset). */
+  since: string | null;
+  elapsedMs: number;
+  /** The `terminal_write` command, entry to PTY. Compare with the invoke round trip to see IPC cost. */
+  writeCommand: StageMetrics;
+  ptyWrite: StageMetrics;
+  /** Each PTY read's processing until delivered (includes `emit`). */
+  output: StageMetrics;
+  emit: StageMetrics;
+  overlay: boolean;
+};
+
+export async function perfMetrics(): Promise<PerfMetrics> {
+  return invoke("perf_metrics");
+}
+
+export async function perfMetricsReset(): Promise<PerfMetrics> {
+  return invoke("perf_metrics_reset");
+}
+
+export async function perfOverlaySet(enabled: boolean): Promise<PerfMetrics> {
+  return invoke("perf_overlay_set", { enabled });
+}
+
+export type BenchmarkReport = {
+  bytesRequested: number;
+  /** More than requested: the PTY turns each newline into CRLF. */
+  bytesReceived: number;
+  /** `terminal:data` events, one per PTY read. */
+  events: number;
+  firstByteMs: number;
+  elapsedMs: number;
+  bytesPerSec: number;
+  eventsPerSec: number;
+  meanEventBytes: number;
+  timedOut: boolean;
+  /** Pipeline counters after the run, other sessions' traffic included. */
+  pipeline: PerfMetrics;
+};
+
+/** Push `bytes` (default 64 MiB) of synthetic output through a PTY session and measure it. Hidden: for the console, not the UI. */
+export async function terminalBenchmark(bytes?: number): Promise<BenchmarkReport> {
+  return invoke("terminal_benchmark", { bytes: bytes ?? null });
+}
+
+export async function terminalClose(sessionId: string): Promise<void> {
+  await invoke("terminal_close", { sessionId });
+}
+
+/** A session moved into its own window; also the `terminal:reattached` payload. */
+export type DetachedSession = {
+  sessionId: string;
+  title: string;
+  environmentTag: string;
+};
+
+/** Move a live session into a new window. Its output goes only there until that window closes. */
+export async function terminalDetach(session: DetachedSession): Promise<void> {
+  await invoke("terminal_detach", session);
+}
+
+/** From a detached window: the session it shows. */
+export async function terminalDetachedGet(): Promise<DetachedSession> {
+  return invoke("terminal_detached_get");
+}
+
+/** From a detached window, once listening for `terminal:data`: flush output held during the move. */
+export async function terminalAttach(): Promise<void> {
+  await invoke("terminal_attach");
+}
+
+export async function terminalMarkExited(sessionId: string): Promise<void> {
+  await invoke("terminal_mark_exited", { sessionId });
+}
+
+export type ParamType = "string" | "integer" | "boolean" | "enum";
+
+export type ParamSpec = {
+  name: string;
+  type?: ParamType;
+  default?: string | null;
+  allowedValues?: string[];
+  required?: boolean;
+  sensitive?: boolean;
+};
+
+export type DockCommand = {
+  id: string;
+  title: string;
+  command: string;
+  requiresConfirm: boolean;
+  color?: string | null;
+  params?: ParamSpec[];
+  visibility?: CommandVisibility;
+  category?: string | null;
+  /** Bound key chord (e.g. "Ctrl+Shift+K"); managed via shortcutsSet/shortcutsClear. */
+  shortcut?: string | null;
+  /** Maintained by the backend on CommandDock writes. */
+  runCount?: number;
+  lastRunAt?: number | null;
+  /** Set for commands from a team subscription; those are read-only. */
+  subscriptionId?: string | null;
+  /** `color`, or for a command shown in a single environment, that environment's color (read-only). */
+  effectiveColor?: string | null;
+  /** Interrupt the command (Ctrl-C) after this many seconds; needs shell integration. */
+  timeoutSecs?: number | null;
+  /** Follow up with Ctrl-\ if Ctrl-C doesn't stop it. */
+  timeoutEscalate?: boolean;
+};
+
+/** Empty lists mean the command is shown everywhere. */
+export type CommandVisibility = {
+  environments?: string[];
+  hostIds?: string[];
+  groupIds?: string[];
+};
+
+export async function dockCommandsList(): Promise<DockCommand[]> {
+  return invoke("dock_commands_list");
+}
+
+export async function dockCommandsCreate(input: {
+  title: string;
+  command: string;
+  requiresConfirm?: boolean;
+  color?: string | null;
+}): Promise<DockCommand> {
+  return invoke("dock_commands_create", {
+    input: {
+      title: input.title,
+      command: input.command,
+      requiresConfirm: input.requiresConfirm ?? false,
+      color: input.color ?? null,
+    },
+  });
+}
+
+export async function dockCommandsUpdate(input: DockCommand): Promise<DockCommand> {
+  return invoke("dock_commands_update", { input });
+}
+
+export async function dockCommandsSetParams(id: string, params: ParamSpec[]): Promise<DockCommand> {
+  return invoke("dock_commands_set_params", { id, params });
+}
+
+export async function dockCommandsSetVisibility(id: string, visibility: CommandVisibility): Promise<void> {
+  await invoke("dock_commands_set_visibility", { id, visibility });
+}
+
+export async function dockCommandsSetCategory(id: string, category: string | null): Promise<void> {
+  await invoke("dock_commands_set_category", { id, category });
+}
+
+/** `timeoutSecs` null removes the timeout. */
+export async function dockCommandsSetTimeout(id: string, timeoutSecs: number | null, escalate: boolean): Promise<void> {
+  await invoke("dock_commands_set_timeout", { id, timeoutSecs, escalate });
+}
+
+export type ShortcutTarget = { kind: "dockCommand"; id: string } | { kind: "action"; action: string };
+
+export type Shortcut = {
+  id: string;
+  chord: string;
+  target: ShortcutTarget;
+};
+
+export async function shortcutsList(): Promise<Shortcut[]> {
+  return invoke("shortcuts_list");
+}
+
+/** Normalizes the chord and rejects conflicts; replaces any existing chord for the target. */
+export async function shortcutsSet(chord: string, target: ShortcutTarget): Promise<Shortcut> {
+  return invoke("shortcuts_set", { chord, target });
+}
+
+export async function shortcutsClear(target: ShortcutTarget): Promise<void> {
+  await invoke("shortcuts_clear", { target });
+}
+
+export type CommandPackExportInput = {
+  name: string;
+  version: string;
+  description?: string | null;
+  commandIds: string[];
+};
+
+export type CommandPackPreview = {
+  name: string;
+  version: string;
+  description?: string | null;
+  createdAt: number;
+  signed: boolean;
+  /** Short fingerprint of the signer's public key. */
+  signer?: string | null;
+  signedByMe: boolean;
+  commands: { title: string; category?: string | null; conflictId?: string | null }[];
+};
+
+export type PackConflictStrategy = "skip" | "overwrite" | "duplicate";
+
+export type CommandPackImportResult = { created: number; updated: number; skipped: number };
+
+/** Returns the signed pack as pretty-printed JSON. */
+export async function commandPackExport(input: CommandPackExportInput): Promise<string> {
+  return invoke("command_pack_export", {
+    input: { ...input, description: input.description ?? null },
+  });
+}
+
+export async function commandPackInspect(json: string): Promise<CommandPackPreview> {
+  return invoke("command_pack_inspect", { json });
+}
+
+export async function commandPackImport(
+  json: string,
+  strategy?: PackConflictStrategy,
+): Promise<CommandPackImportResult> {
+  return invoke("command_pack_import", { json, strategy: strategy ?? null });
+}
+
+export async function dockCommandsForScope(scope: string): Promise<DockCommand[]> {
+  return invoke("dock_commands_for_scope", { scope });
+}
+
+export async function dockCommandRender(
+  id: string,
+  values: Record<string, string>,
+  scope?: string | null,
+): Promise<string> {
+  return invoke("dock_command_render", { id, values, scope: scope ?? null });
+}
+
+export type PolicyDecision = {
+  environment: string;
+  /** requiresConfirm, or a "danger" lint finding. */
+  destructive: boolean;
+  /** Always "allow" for non-destructive commands. */
+  policy: DestructivePolicy;
+  /** A teammate's approval token is needed as well. */
+  fourEyes: boolean;
+};
+
+export type DockCommandPreview = {
+  /** Exactly the text that will be written. */
+  command: string;
+  /** Send back as `confirmedHash` with the confirmed write; the backend refuses a different command. */
+  commandHash: string;
+  findings: LintFinding[];
+  decision: PolicyDecision;
+};
+
+/** Renders like dockCommandRender (without remembering values) and adds lint + policy results. */
+export async function dockCommandPreview(
+  id: string,
+  values: Record<string, string>,
+  scope?: string | null,
+): Promise<DockCommandPreview> {
+  return invoke("dock_command_preview", { id, values, scope: scope ?? null });
+}
+
+export async function dockParamValuesGet(scope: string): Promise<Record<string, string>> {
+  return invoke("dock_param_values_get", { scope });
+}
+
+export async function dockCommandsDelete(id: string): Promise<void> {
+  await invoke("dock_commands_delete", { id });
+}
+
+export async function dockCommandsReorder(ids: string[]): Promise<void> {
+  await invoke("dock_commands_reorder", { ids });
+}
+
+/** Legacy single-runbook API; reads/writes the first runbook. */
+export async function dockRunbookGet(): Promise<string> {
+  return invoke("dock_runbook_get");
+}
+
+export async function dockRunbookSet(markdown: string): Promise<void> {
+  await invoke("dock_runbook_set", { markdown });
+}
+
+export type Runbook = {
+  id: string;
+  title: string;
+  markdown: string;
+  tags?: string[];
+  /** Schema for `{name}` placeholders; managed via runbooksSetParams. */
+  params?: ParamSpec[];
+  createdAt?: string;
+  updatedAt?: string;
+  /** Backing `.md` file when folder sync is on (managed by the sync). */
+  fileName?: string | null;
+  /** Changed both in OpsPad and on disk; resolve with runbookSyncResolve. */
+  syncConflict?: boolean;
+  /** Set for runbooks from a team subscription; those are read-only. */
+  subscriptionId?: string | null;
+};
+
+export type RunbookCreate = {
+  title: string;
+  markdown?: string;
+  tags?: string[];
+};
+
+export async function runbooksList(): Promise<Runbook[]> {
+  return invoke("runbooks_list");
+}
+
+export async function runbooksGet(id: string): Promise<Runbook | null> {
+  return invoke("runbooks_get", { id });
+}
+
+export async function runbooksCreate(input: RunbookCreate): Promise<Runbook> {
+  return invoke("runbooks_create", {
+    input: { title: input.title, markdown: input.markdown ?? null, tags: input.tags ?? null },
+  });
+}
+
+export async function runbooksUpdate(input: Runbook): Promise<Runbook> {
+  return invoke("runbooks_update", { input });
+}
+
+export async function runbooksSetParams(id: string, params: ParamSpec[]): Promise<Runbook> {
+  return invoke("runbooks_set_params", { id, params });
+}
+
+export type RenderedRunbook = {
+  markdown: string;
+  placeholders: string[];
+  /** Placeholders left as `{name}` because no value was given. */
+  missing: string[];
+  /** The `opspad://` links in the rendered markdown, in order. */
+  links: RunbookLink[];
+};
+
+/** An `opspad://` link in a runbook and what opening it does; `target` is null when it doesn't resolve. */
+export type RunbookLink = {
+  url: string;
+  text: string;
+  target: CliAction | null;
+  error: string | null;
+};
+
+export async function runbookRender(id: string, values: Record<string, string>): Promise<RenderedRunbook> {
+  return invoke("runbook_render", { id, values });
+}
+
+/** Follow an `opspad://` link from a runbook; it arrives as a `cli:action` like any other link. Returns a status message. */
+export async function runbookLinkOpen(url: string): Promise<string> {
+  return invoke("runbook_link_open", { url });
+}
+
+export type RunbookExportFormat = "html" | "pdf";
+
+/** Write a runbook to `path` as self-contained HTML, or PDF (needs Chrome/Chromium/Edge or wkhtmltopdf). */
+export async function runbookExport(id: string, format: RunbookExportFormat, path: string): Promise<void> {
+  await invoke("runbook_export", { id, format, path });
+}
+
+export async function runbooksDelete(id: string): Promise<void> {
+  await invoke("runbooks_delete", { id });
+}
+
+export async function runbooksReorder(ids: string[]): Promise<void> {
+  await invoke("runbooks_reorder", { ids });
+}
+
+export type RunbookFolderSyncConfig = {
+  enabled: boolean;
+  path?: string | null;
+};
+
+/** Titles of the runbooks affected by a sync pass. Also the payload of the "runbooks:synced" event. */
+export type RunbookSyncReport = {
+  imported: string[];
+  pulled: string[];
+  pushed: string[];
+  removed: string[];
+  conflicts: string[];
+};
+
+export async function runbookSyncConfigGet(): Promise<RunbookFolderSyncConfig> {
+  return invoke("runbook_sync_config_get");
+}
+
+/** Saves the config and runs a sync pass right away. */
+export async function runbookSyncConfigSet(config: RunbookFolderSyncConfig): Promise<RunbookSyncReport> {
+  return invoke("runbook_sync_config_set", { config: { enabled: config.enabled, path: config.path ?? null } });
+}
+
+export async function runbookSyncNow(): Promise<RunbookSyncReport> {
+  return invoke("runbook_sync_now");
+}
+
+export async function runbookSyncResolve(id: string, keep: "app" | "file"): Promise<void> {
+  await invoke("runbook_sync_resolve", { id, keep });
+}
+
+export type GitSyncConfig = {
+  enabled: boolean;
+  /** Local repository folder; created and initialized on first sync. */
+  path?: string | null;
+  /** Remote URL (pushed/pulled as `origin`). Omit to keep history local. */
+  remote?: string | null;
+};
+
+export type GitSyncStatus = {
+  enabled: boolean;
+  path: string | null;
+  remote: string | null;
+  lastCommit: string | null;
+  lastSyncAt: number | null;
+  lastError: string | null;
+  /** Items changed differently on both sides in the last sync; the local version was kept. */
+  conflicts: string[];
+  pendingChanges: boolean;
+};
+
+export type GitSyncReport = {
+  pulled: string[];
+  committed: string | null;
+  pushed: boolean;
+  conflicts: string[];
+};
+
+export async function syncConfigGet(): Promise<GitSyncConfig> {
+  return invoke("sync_config_get");
+}
+
+export async function syncConfigSet(config: GitSyncConfig): Promise<void> {
+  await invoke("sync_config_set", {
+    config: { enabled: config.enabled, path: config.path ?? null, remote: config.remote ?? null },
+  });
+}
+
+export async function syncStatus(): Promise<GitSyncStatus> {
+  return invoke("sync_status");
+}
+
+/** Sync hosts, dock commands, and runbooks with the Git repository (and its remote). */
+export async function syncNow(): Promise<GitSyncReport> {
+  return invoke("sync_now");
+}
+
+export type Subscription = {
+  id: string;
+  url: string;
+  name: string | null;
+  version: string | null;
+  /** Fingerprint of the key bundles must be signed with. */
+  signer: string | null;
+  lastCheckedAt: number | null;
+  lastError: string | null;
+  createdAt: string;
+  itemCount: number;
+};
+
+export async function subscriptionsList(): Promise<Subscription[]> {
+  return invoke("subscriptions_list");
+}
+
+/** Subscribe to a signed JSON/YAML config bundle; fails (and saves nothing) if the first fetch fails. */
+export async function subscriptionAdd(url: string, signer?: string | null): Promise<Subscription> {
+  return invoke("subscription_add", { url, signer: signer ?? null });
+}
+
+export async function subscriptionRefresh(id: string): Promise<Subscription> {
+  return invoke("subscription_refresh", { id });
+}
+
+/** Unsubscribe and remove everything the subscription provided. */
+export async function subscriptionsDelete(id: string): Promise<void> {
+  await invoke("subscriptions_delete", { id });
+}
+
+/** Sign a config bundle with this installation's key; returns signed JSON ready to publish. */
+export async function configBundleSign(content: string): Promise<string> {
+  return invoke("config_bundle_sign", { content });
+}
+
+export type RunbookVersion = {
+  id: string;
+  runbookId: string;
+  title: string;
+  /** Empty in list responses. */
+  markdown: string;
+  createdAt: string;
+};
+
+export type RunbookDiffLine = {
+  kind: "equal" | "insert" | "delete";
+  oldLine?: number | null;
+  newLine?: number | null;
+  text: string;
+};
+
+export type RunbookDiff = {
+  titleChanged: boolean;
+  inserted: number;
+  deleted: number;
+  lines: RunbookDiffLine[];
+  unified: string;
+};
+
+/** Newest first. */
+export async function runbookVersionsList(runbookId: string): Promise<RunbookVersion[]> {
+  return invoke("runbook_versions_list", { runbookId });
+}
+
+export async function runbookVersionsGet(id: string): Promise<RunbookVersion | null> {
+  return invoke("runbook_versions_get", { id });
+}
+
+/** Diff version `fromId` against `toId`, or against the current runbook when `toId` is omitted. */
+export async function runbookVersionsDiff(fromId: string, toId?: string | null): Promise<RunbookDiff> {
+  return invoke("runbook_versions_diff", { fromId, toId: toId ?? null });
+}
+
+export async function runbookVersionsRestore(id: string): Promise<Runbook> {
+  return invoke("runbook_versions_restore", { id });
+}
+
+export type ChecklistItem = {
+  /** Position among the runbook's checkbox items, from 0. */
+  index: number;
+  text: string;
+  checkedAt: number | null;
+  /** The OS account that checked it. */
+  checkedBy: string | null;
+};
+
+/** One execution of a runbook's `- [ ]` items; items are copied when the run starts. */
+export type ChecklistRun = {
+  id: string;
+  runbookId: string;
+  runbookTitle: string;
+  incidentId: string | null;
+  startedAt: number;
+  /** Set while every item is checked. */
+  completedAt: number | null;
+  checked: number;
+  total: number;
+  /** Empty in list responses. */
+  items: ChecklistItem[];
+};
+
+/** Start a checklist run; without `incidentId` it's filed under the active incident, if any. */
+export async function runbookChecklistStart(runbookId: string, incidentId?: string | null): Promise<ChecklistRun> {
+  return invoke("runbook_checklist_start", { runbookId, incidentId: incidentId ?? null });
+}
+
+export async function runbookChecklistToggle(runId: string, index: number, checked: boolean): Promise<ChecklistRun> {
+  return invoke("runbook_checklist_toggle", { runId, index, checked });
+}
+
+export async function runbookChecklistGet(runId: string): Promise<ChecklistRun | null> {
+  return invoke("runbook_checklist_get", { runId });
+}
+
+/** Newest first, without items. */
+export async function runbookChecklistsList(
+  filter?: { runbookId?: string | null; incidentId?: string | null },
+  limit?: number,
+): Promise<ChecklistRun[]> {
+  return invoke("runbook_checklists_list", {
+    runbookId: filter?.runbookId ?? null,
+    incidentId: filter?.incidentId ?? null,
+    limit: limit ?? null,
+  });
+}
+
+export async function runbookChecklistDelete(runId: string): Promise<void> {
+  await invoke("runbook_checklist_delete", { runId });
+}
+
+export type DockHistoryItem = {
+  id: string;
+  createdAt: string;
+  scope?: string | null;
+  environmentTag: string;
+  commandText: string;
+  pinned: boolean;
+  /** Set for destructive commands: the environment policy that let it through. */
+  policy?: DestructivePolicy | null;
+  policyReason?: string | null;
+  /** Set once the shell reports the command finished (needs shell integration). */
+  durationMs?: number | null;
+  exitStatus?: number | null;
+  /** Set when the entry stands for a dock watch: how many times it ran. */
+  watchRuns?: number | null;
+  /** The command overran its timeout and was interrupted. */
+  timedOut?: boolean;
+};
+
+/** Payload of the `command:finished` event. */
+export type CommandFinishedEvent = {
+  sessionId: string;
+  historyId: string;
+  commandText: string;
+  durationMs: number;
+  exitStatus: number | null;
+};
+
+/** Payload of the `command:timeout` event. */
+export type CommandTimeoutEvent = {
+  sessionId: string;
+  historyId: string;
+  timeoutSecs: number;
+  /** Ctrl-\ followed the Ctrl-C. */
+  escalated: boolean;
+};
+
+/** One page of a keyset-paginated listing; rows added meanwhile don't shift later pages. */
+export type Page<T> = {
+  items: T[];
+  /** Pass as `cursor` for the next page; null on the last one. */
+  nextCursor: string | null;
+};
+
+/** Newest first. */
+export async function dockHistoryList(
+  limit?: number,
+  pinnedOnly?: boolean,
+  cursor?: string | null,
+): Promise<Page<DockHistoryItem>> {
+  return invoke("dock_history_list", { limit: limit ?? null, pinnedOnly: pinnedOnly ?? null, cursor: cursor ?? null });
+}
+
+export async function dockHistoryForScope(scope: string, limit?: number): Promise<DockHistoryItem[]> {
+  return invoke("dock_history_for_scope", { scope, limit: limit ?? null });
+}
+
+/** Every run of one command text in one scope, as one row. */
+export type DockHistoryAggregate = {
+  scope: string | null;
+  commandText: string;
+  runCount: number;
+  firstRunAt: string;
+  lastRunAt: string;
+  /** The latest run's history entry, environment and outcome. */
+  lastId: string;
+  environmentTag: string;
+  lastDurationMs: number | null;
+  lastExitStatus: number | null;
+  /** Runs that exited non-zero (needs shell integration). */
+  failureCount: number;
+  /** Any of the runs is pinned. */
+  pinned: boolean;
+};
+
+/** History with identical commands collapsed per scope, most recently run first. */
+export async function dockHistoryAggregate(scope?: string | null, limit?: number): Promise<DockHistoryAggregate[]> {
+  return invoke("dock_history_aggregate", { scope: scope ?? null, limit: limit ?? null });
+}
+
+export async function dockHistoryPin(id: string, pinned: boolean): Promise<void> {
+  await invoke("dock_history_pin", { id, pinned });
+}
+
+export async function dockHistoryDelete(id: string): Promise<void> {
+  await invoke("dock_history_delete", { id });
+}
+
+export async function dockHistoryClear(): Promise<void> {
+  await invoke("dock_history_clear");
+}
+
+export type SequenceStep = {
+  dockCommandId: string;
+  values?: Record<string, string>;
+  delayMs?: number | null;
+  waitFor?: string | null;
+  timeoutMs?: number | null;
+};
+
+export type Sequence = {
+  id: string;
+  title: string;
+  steps: SequenceStep[];
+};
+
+/** Payload of the `sequence:progress` event. */
+export type SequenceProgressEvent = {
+  runId: string;
+  sequenceId: string;
+  sessionId: string;
+  step: number;
+  total: number;
+  status: "running" | "waiting" | "completed" | "failed" | "aborted";
+  message?: string | null;
+};
+
+export async function sequencesList(): Promise<Sequence[]> {
+  return invoke("sequences_list");
+}
+
+export async function sequencesCreate(input: { title: string; steps: SequenceStep[] }): Promise<Sequence> {
+  return invoke("sequences_create", { input });
+}
+
+export async function sequencesUpdate(input: Sequence): Promise<Sequence> {
+  return invoke("sequences_update", { input });
+}
+
+export async function sequencesDelete(id: string): Promise<void> {
+  await invoke("sequences_delete", { id });
+}
+
+export type SequencePreview = {
+  environment: string;
+  steps: DockCommandPreview[];
+  /** Hash of all the steps together; pass it to `sequenceRun` with a confirmation. */
+  commandHash: string;
+};
+
+/** Render every step; `sessionId` picks the environment policy. */
+export async function sequencePreview(id: string, sessionId?: string): Promise<SequencePreview> {
+  return invoke("sequence_preview", { id, sessionId: sessionId ?? null });
+}
+
+export async function sequenceRun(
+  id: string,
+  sessionId: string,
+  confirmation?: string,
+  approval?: string,
+  confirmedHash?: string,
+): Promise<string> {
+  return invoke("sequence_run", {
+    id,
+    sessionId,
+    confirmation: confirmation ?? null,
+    approval: approval ?? null,
+    confirmedHash: confirmedHash ?? null,
+  });
+}
+
+export async function sequenceAbort(runId: string): Promise<boolean> {
+  return invoke("sequence_abort", { runId });
+}
+
+export type DockWatch = {
+  sessionId: string;
+  dockCommandId: string;
+  title: string;
+  intervalSecs: number;
+  startedAt: string;
+  expiresAt: string;
+  runs: number;
+  /** The history entry every run is counted against. */
+  historyId: string | null;
+};
+
+/** Payload of the `dock:watch` event. */
+export type DockWatchEvent = DockWatch & {
+  status: "running" | "stopped" | "expired" | "ended";
+};
+
+export async function dockWatchStart(
+  sessionId: string,
+  dockCommandId: string,
+  values: Record<string, string>,
+  intervalSecs: number,
+  maxMinutes?: number,
+  confirmation?: string,
+  approval?: string,
+  confirmedHash?: string,
+): Promise<DockWatch> {
+  return invoke("dock_watch_start", {
+    sessionId,
+    dockCommandId,
+    values,
+    intervalSecs,
+    maxMinutes: maxMinutes ?? null,
+    confirmation: confirmation ?? null,
+    approval: approval ?? null,
+    confirmedHash: confirmedHash ?? null,
+  });
+}
+
+export async function dockWatchStop(sessionId: string): Promise<boolean> {
+  return invoke("dock_watch_stop", { sessionId });
+}
+
+export async function dockWatchList(): Promise<DockWatch[]> {
+  return invoke("dock_watch_list");
+}
+
+/** Input sent as-is (a line ending in "\r", or keys like Ctrl-C), after a pause. */
+export type MacroStep = {
+  text: string;
+  delayMs: number;
+};
+
+export type Macro = {
+  id: string;
+  title: string;
+  steps: MacroStep[];
+  createdAt: string;
+  updatedAt: string;
+};
+
+export type MacroInput = {
+  title: string;
+  steps: MacroStep[];
+};
+
+export type MacroRecording = {
+  sessionId: string;
+  steps: MacroStep[];
+  /** Lines typed at a password prompt, which were left out. */
+  skippedSecretInputs: number;
+};
+
+export type MacroPreviewStep = {
+  /** The input with control keys made visible ("⏎", "^C"). */
+  display: string;
+  delayMs: number;
+  /** When it's sent, from the start of the replay. */
+  atMs: number;
+  destructive: boolean;
+};
+
+export type MacroPreview = {
+  environment: string;
+  steps: MacroPreviewStep[];
+  totalMs: number;
+  /** Hash of the submitted lines; pass it to `macroReplay` with a confirmation. */
+  commandHash: string;
+};
+
+/** Payload of the `macro:progress` event. */
+export type MacroProgressEvent = {
+  runId: string;
+  macroId: string;
+  sessionId: string;
+  /** Zero-based step index (equal to `total` once the run has finished). */
+  step: number;
+  total: number;
+  status: "running" | "completed" | "failed" | "aborted";
+  message: string | null;
+};
+
+export async function macrosList(): Promise<Macro[]> {
+  return invoke("macros_list");
+}
+
+export async function macrosCreate(input: MacroInput): Promise<Macro> {
+  return invoke("macros_create", { input });
+}
+
+export async function macrosUpdate(id: string, input: MacroInput): Promise<Macro> {
+  return invoke("macros_update", { id, input });
+}
+
+export async function macrosDelete(id: string): Promise<void> {
+  await invoke("macros_delete", { id });
+}
+
+export async function macroRecordStart(sessionId: string): Promise<void> {
+  await invoke("macro_record_start", { sessionId });
+}
+
+export async function macroRecordStop(sessionId: string): Promise<MacroRecording> {
+  return invoke("macro_record_stop", { sessionId });
+}
+
+/** Session ids being recorded. */
+export async function macroRecordList(): Promise<string[]> {
+  return invoke("macro_record_list");
+}
+
+/** Dry run: what a replay would send and when; `sessionId` picks the environment policy. */
+export async function macroPreview(id: string, sessionId?: string, speed?: number): Promise<MacroPreview> {
+  return invoke("macro_preview", { id, sessionId: sessionId ?? null, speed: speed ?? null });
+}
+
+/** Returns the run id; progress arrives as `macro:progress` events. */
+export async function macroReplay(
+  id: string,
+  sessionId: string,
+  speed?: number,
+  confirmation?: string,
+  approval?: string,
+  confirmedHash?: string,
+): Promise<string> {
+  return invoke("macro_replay", {
+    id,
+    sessionId,
+    speed: speed ?? null,
+    confirmation: confirmation ?? null,
+    approval: approval ?? null,
+    confirmedHash: confirmedHash ?? null,
+  });
+}
+
+export async function macroReplayAbort(runId: string): Promise<boolean> {
+  return invoke("macro_replay_abort", { runId });
+}
+
+export type LintSeverity = "warning" | "danger";
+
+export type LintFinding = {
+  ruleId: string;
+  name: string;
+  message: string;
+  severity: LintSeverity;
+  matched: string;
+};
+
+export type LintRule = {
+  id: string;
+  name: string;
+  pattern: string;
+  /** Rule is suppressed when this pattern also matches (e.g. `--dry-run`). */
+  unless: string | null;
+  severity: LintSeverity;
+  message: string;
+  enabled: boolean;
+};
+
+export async function commandLint(text: string): Promise<LintFinding[]> {
+  return invoke("command_lint", { text });
+}
+
+export async function lintRulesList(): Promise<LintRule[]> {
+  return invoke("lint_rules_list");
+}
+
+export async function lintRulesCreate(input: {
+  name: string;
+  pattern: string;
+  unless?: string;
+  severity?: LintSeverity;
+  message?: string;
+  enabled?: boolean;
+}): Promise<LintRule> {
+  return invoke("lint_rules_create", {
+    input: {
+      name: input.name,
+      pattern: input.pattern,
+      unless: input.unless ?? null,
+      severity: input.severity ?? null,
+      message: input.message ?? null,
+      enabled: input.enabled ?? null,
+    },
+  });
+}
+
+export async function lintRulesUpdate(input: LintRule): Promise<LintRule> {
+  return invoke("lint_rules_update", { input });
+}
+
+export async function lintRulesDelete(id: string): Promise<void> {
+  await invoke("lint_rules_delete", { id });
+}
+
+export type FanoutHostResult = {
+  hostId: string;
+  hostLabel: string;
+  exitCode: number | null;
+  stdout: string;
+  stderr: string;
+  error: string | null;
+  durationMs: number;
+  timedOut: boolean;
+};
+
+export type FanoutRun = {
+  id: string;
+  createdAt: string;
+  commandId: string | null;
+  commandTitle: string;
+  commandText: string;
+  hostCount: number;
+  okCount: number;
+  failedCount: number;
+  results: FanoutHostResult[];
+};
+
+export async function commandFanout(
+  commandId: string,
+  hostIds: string[],
+  values?: Record<string, string>,
+  confirmation?: string,
+  approval?: string,
+  /** From `dockCommandPreview` without a scope. */
+  confirmedHash?: string,
+): Promise<FanoutRun> {
+  return invoke("command_fanout", {
+    commandId,
+    hostIds,
+    values: values ?? null,
+    confirmation: confirmation ?? null,
+    approval: approval ?? null,
+    confirmedHash: confirmedHash ?? null,
+  });
+}
+
+export async function fanoutRunsList(limit?: number): Promise<FanoutRun[]> {
+  return invoke("fanout_runs_list", { limit: limit ?? null });
+}
+
+export async function fanoutRunGet(id: string): Promise<FanoutRun | null> {
+  return invoke("fanout_run_get", { id });
+}
+
+export async function fanoutRunDelete(id: string): Promise<void> {
+  return invoke("fanout_run_delete", { id });
+}
+
+export type JobStatus = "queued" | "running" | "succeeded" | "failed" | "timedOut" | "cancelled";
+
+export type Job = {
+  id: string;
+  createdAt: string;
+  startedAt: number | null;
+  finishedAt: number | null;
+  /** "local" or "ssh:<hostId>" */
+  target: string;
+  targetLabel: string;
+  command: string;
+  status: JobStatus;
+  exitCode: number | null;
+  /** Empty in `jobsList` results; use `jobGet` for output. */
+  stdout: string;
+  stderr: string;
+  truncated: boolean;
+  error: string | null;
+  durationMs: number | null;
+};
+
+export type JobOutputEvent = {
+  jobId: string;
+  stream: "stdout" | "stderr";
+  data: string;
+};
+
+export type JobStatusEvent = {
+  jobId: string;
+  status: JobStatus;
+  exitCode: number | null;
+  error: string | null;
+};
+
+/** Lint and policy decision for a job command; its `commandHash` goes back to `jobStart`. */
+export async function jobPreview(target: string, command: string): Promise<DockCommandPreview> {
+  return invoke("job_preview", { target, command });
+}
+
+/** Destructive commands need the target environment's confirmation, as in CommandDock. */
+export async function jobStart(
+  target: string,
+  command: string,
+  timeoutSecs?: number,
+  confirmation?: string,
+  approval?: string,
+  confirmedHash?: string,
+): Promise<Job> {
+  return invoke("job_start", {
+    target,
+    command,
+    timeoutSecs: timeoutSecs ?? null,
+    confirmation: confirmation ?? null,
+    approval: approval ?? null,
+    confirmedHash: confirmedHash ?? null,
+  });
+}
+
+/** Newest first. */
+export async function jobsList(limit?: number, cursor?: string | null): Promise<Page<Job>> {
+  return invoke("jobs_list", { limit: limit ?? null, cursor: cursor ?? null });
+}
+
+export async function jobGet(id: string): Promise<Job | null> {
+  return invoke("job_get", { id });
+}
+
+export async function jobCancel(id: string): Promise<boolean> {
+  return invoke("job_cancel", { id });
+}
+
+export async function jobDelete(id: string): Promise<void> {
+  return invoke("job_delete", { id });
+}
+
+export type TransferStatus = "queued" | "running" | "paused" | "succeeded" | "failed" | "cancelled";
+
+export type Transfer = {
+  id: string;
+  createdAt: string;
+  updatedAt: string;
+  finishedAt: number | null;
+  hostId: string;
+  hostLabel: string;
+  direction: "upload" | "download";
+  localPath: string;
+  remotePath: string;
+  status: TransferStatus;
+  /** Runs started so far; later runs continue the partial file. */
+  attempts: number;
+  maxRetries: number;
+  retryBackoffMs: number;
+  error: string | null;
+};
+
+export type TransferCreate = {
+  hostId: string;
+  direction: "upload" | "download";
+  localPath: string;
+  remotePath: string;
+  /** Defaults to 3 (at most 10). */
+  maxRetries?: number | null;
+  /** Defaults to 5000; doubles per retry. */
+  retryBackoffMs?: number | null;
+};
+
+/** Payload of the `transfer:status` event. */
+export type TransferStatusEvent = {
+  transferId: string;
+  status: TransferStatus;
+  attempts: number;
+  error: string | null;
+};
+
+export async function transferEnqueue(input: TransferCreate): Promise<Transfer> {
+  return invoke("transfer_enqueue", { input });
+}
+
+export async function transfersList(limit?: number): Promise<Transfer[]> {
+  return invoke("transfers_list", { limit: limit ?? null });
+}
+
+export async function transferPause(id: string): Promise<Transfer> {
+  return invoke("transfer_pause", { id });
+}
+
+export async function transferResume(id: string): Promise<Transfer> {
+  return invoke("transfer_resume", { id });
+}
+
+export async function transferCancel(id: string): Promise<Transfer> {
+  return invoke("transfer_cancel", { id });
+}
+
+export async function transferDelete(id: string): Promise<void> {
+  return invoke("transfer_delete", { id });
+}
+
+/** A local folder kept mirrored with one on a host. */
+export type SyncProfile = {
+  id: string;
+  name: string;
+  hostId: string;
+  localPath: string;
+  remotePath: string;
+  /** "push": local to remote; "pull": remote to local. Nothing is ever deleted. */
+  direction: "push" | "pull";
+  /** Glob patterns (`*`, `?`) matched against each relative path and each of its parts. */
+  excludes: string[];
+  lastRunAt: number | null;
+};
+
+export type SyncProfileCreate = Omit<SyncProfile, "id" | "lastRunAt">;
+
+export type SyncItem = {
+  path: string;
+  size: number;
+  reason: "new" | "changed";
+};
+
+export type SyncReport = {
+  profileId: string;
+  dryRun: boolean;
+  direction: "push" | "pull";
+  /** Copied, or for a dry run the files that would be. */
+  items: SyncItem[];
+  bytes: number;
+  unchanged: number;
+  excluded: number;
+};
+
+export async function syncProfilesList(): Promise<SyncProfile[]> {
+  return invoke("sync_profiles_list");
+}
+
+export async function syncProfilesCreate(input: SyncProfileCreate): Promise<SyncProfile> {
+  return invoke("sync_profiles_create", { input });
+}
+
+export async function syncProfilesUpdate(input: SyncProfile): Promise<SyncProfile> {
+  return invoke("sync_profiles_update", { input });
+}
+
+export async function syncProfilesDelete(id: string): Promise<void> {
+  return invoke("sync_profiles_delete", { id });
+}
+
+export async function syncRun(profileId: string, dryRun?: boolean): Promise<SyncReport> {
+  return invoke("sync_run", { profileId, dryRun: dryRun ?? null });
+}
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error" | "fatal";
+
+export type LogFilters = {
+  /** Regexes; a line must match at least one (when any are given). */
+  include?: string[];
+  /** Regexes; a line matching any is dropped. */
+  exclude?: string[];
+  /** Lines with a lower detected level are dropped; lines with none are kept. */
+  minLevel?: LogLevel | null;
+};
+
+export type LogLine = {
+  text: string;
+  timestamp: string | null;
+  level: LogLevel | null;
+};
+
+/** Payload of the `log:lines` event. */
+export type LogLinesEvent = {
+  tailId: string;
+  lines: LogLine[];
+};
+
+/** Payload of the `log:ended` event; `error` is null when stopped on request. */
+export type LogEndedEvent = {
+  tailId: string;
+  error: string | null;
+};
+
+/** Follow a log file on a host (`tail -F` over ssh). Returns the tail id events carry. */
+export async function logTailStart(
+  hostId: string,
+  path: string,
+  filters?: LogFilters,
+  backlogLines?: number,
+): Promise<string> {
+  return invoke("log_tail_start", { hostId, path, filters: filters ?? null, backlogLines: backlogLines ?? null });
+}
+
+export async function logTailStop(tailId: string): Promise<boolean> {
+  return invoke("log_tail_stop", { tailId });
+}
+
+export type HighlightSeverity = "info" | "success" | "warning" | "error";
+
+/** A pattern marked in session output. */
+export type HighlightRule = {
+  id: string;
+  name: string;
+  /** Rust regex syntax. */
+  pattern: string;
+  /** null: every session. Otherwise "local", "ssh:<hostId>" or "env:<TAG>". */
+  scope: string | null;
+  severity: HighlightSeverity;
+  /** CSS color overriding the severity's (`#rrggbb` is used in the terminal). */
+  color: string | null;
+  annotation: string | null;
+  enabled: boolean;
+};
+
+export type HighlightRuleCreate = {
+  name: string;
+  pattern: string;
+  scope?: string | null;
+  severity?: HighlightSeverity | null;
+  color?: string | null;
+  annotation?: string | null;
+  enabled?: boolean | null;
+};
+
+/** A highlight rule's match in a `terminal:data` event, in UTF-16 offsets into its `data`. */
+export type HighlightSpan = {
+  start: number;
+  end: number;
+  ruleId: string;
+  severity: HighlightSeverity;
+  color: string | null;
+  annotation: string | null;
+};
+
+export async function highlightRulesList(): Promise<HighlightRule[]> {
+  return invoke("highlight_rules_list");
+}
+
+export async function highlightRulesCreate(input: HighlightRuleCreate): Promise<HighlightRule> {
+  return invoke("highlight_rules_create", { input });
+}
+
+export async function highlightRulesUpdate(input: HighlightRule): Promise<HighlightRule> {
+  return invoke("highlight_rules_update", { input });
+}
+
+export async function highlightRulesDelete(id: string): Promise<void> {
+  return invoke("highlight_rules_delete", { id });
+}
+
+/** Typing `trigger` as a word in a terminal expands it to `expansion` (see `terminalExpand`). */
+export type Snippet = {
+  id: string;
+  trigger: string;
+  /** May use `{name}` variables: `{host}`, `{hostname}`, `{user}`, `{port}`, `{env}`, `{date}`, `{time}` (UTC), or any dock command parameter remembered for the scope. */
+  expansion: string;
+  description: string | null;
+  /** null: every session. Otherwise "local", "ssh:<host id>" or "env:<TAG>"; the narrowest match wins. */
+  scope: string | null;
+  enabled: boolean;
+  createdAt: string;
+  updatedAt: string;
+};
+
+export type SnippetInput = {
+  trigger: string;
+  expansion: string;
+  description?: string | null;
+  scope?: string | null;
+  enabled?: boolean | null;
+};
+
+export type SnippetExpansion = {
+  /** The input with each trigger replaced; unchanged when nothing matched. */
+  text: string;
+  expanded: string[];
+  /** Variables left as `{name}` for lack of a value. */
+  missing: string[];
+};
+
+export async function snippetsList(): Promise<Snippet[]> {
+  return invoke("snippets_list");
+}
+
+export async function snippetsCreate(input: SnippetInput): Promise<Snippet> {
+  return invoke("snippets_create", { input });
+}
+
+export async function snippetsUpdate(id: string, input: SnippetInput): Promise<Snippet> {
+  return invoke("snippets_update", { id, input });
+}
+
+export async function snippetsDelete(id: string): Promise<void> {
+  return invoke("snippets_delete", { id });
+}
+
+/** Expand snippet triggers in typed `text` for a session in `scope` ("local", "ssh:<host id>"). */
+export async function terminalExpand(text: string, scope: string | null): Promise<SnippetExpansion> {
+  return invoke("terminal_expand", { text, scope });
+}
+
+/**
+ * A WebAssembly plugin in the app data `plugins` folder. Hooks: `before_command_run` (can rewrite
+ * or deny CommandDock commands), `after_session_open`, `on_output_match`, `on_history_add`.
+ */
+export type PluginInfo = {
+  name: string;
+  file: string;
+  enabled: boolean;
+  hooks: string[];
+  outputPatterns: string[];
+  /** Why it couldn't be loaded. */
+  error: string | null;
+};
+
+export async function pluginsList(): Promise<PluginInfo[]> {
+  return invoke("plugins_list");
+}
+
+export async function pluginsReload(): Promise<PluginInfo[]> {
+  return invoke("plugins_reload");
+}
+
+export async function pluginsSetEnabled(name: string, enabled: boolean): Promise<PluginInfo[]> {
+  return invoke("plugins_set_enabled", { name, enabled });
+}
+
+export type QueueConfig = {
+  maxParallel: number;
+  maxRetries: number;
+  retryBackoffMs: number;
+};
+
+export type QueueStatus = {
+  running: number;
+  pending: number;
+};
+
+export async function queueConfigGet(): Promise<QueueConfig> {
+  return invoke("queue_config_get");
+}
+
+export async function queueConfigSet(config: QueueConfig): Promise<QueueConfig> {
+  return invoke("queue_config_set", { config });
+}
+
+export async function queueStatus(): Promise<QueueStatus> {
+  return invoke("queue_status");
+}
+
+export type RedactionRule = {
+  id: string;
+  name: string;
+  pattern: string;
+  replacement: string;
+  enabled: boolean;
+};
+
+export async function redactionRulesList(): Promise<RedactionRule[]> {
+  return invoke("redaction_rules_list");
+}
+
+export async function redactionRulesCreate(input: {
+  name: string;
+  pattern: string;
+  replacement?: string | null;
+  enabled?: boolean;
+}): Promise<RedactionRule> {
+  return invoke("redaction_rules_create", {
+    input: {
+      name: input.name,
+      pattern: input.pattern,
+      replacement: input.replacement ?? null,
+      enabled: input.enabled ?? true,
+    },
+  });
+}
+
+export async function redactionRulesUpdate(input: RedactionRule): Promise<RedactionRule> {
+  return invoke("redaction_rules_update", { input });
+}
+
+export async function redactionRulesDelete(id: string): Promise<void> {
+  await invoke("redaction_rules_delete", { id });
+}
+
+export type LogLevel = "off" | "error" | "warn" | "info" | "debug" | "trace";
+
+export type LogConfig = {
+  level: LogLevel;
+};
+
+/** The last `n` lines (default 200) of the application log. */
+export async function logsTail(n?: number): Promise<string[]> {
+  return invoke("logs_tail", { n: n ?? null });
+}
+
+export async function logConfigGet(): Promise<LogConfig> {
+  return invoke("log_config_get");
+}
+
+export async function logConfigSet(config: LogConfig): Promise<void> {
+  await invoke("log_config_set", { config });
+}
+
+/** Write a support zip (no secrets) to an absolute `path`. */
+export async function diagnosticsExport(path: string): Promise<void> {
+  await invoke("diagnostics_export", { path });
+}
+
+export type CrashConfig = {
+  upload: boolean;
+  dsn: string | null;
+};
+
+export type CrashReport = {
+  id: string;
+  kind: "panic" | "error";
+  timestamp: number;
+  message: string;
+  location: string | null;
+  thread: string | null;
+  backtrace: string | null;
+  appVersion: string;
+  os: string;
+  arch: string;
+  uploaded: boolean;
+};
+
+export async function crashConfigGet(): Promise<CrashConfig> {
+  return invoke("crash_config_get");
+}
+
+/** Uploading is opt-in; reports are scrubbed of paths, user names and addresses before sending. */
+export async function crashConfigSet(config: CrashConfig): Promise<void> {
+  await invoke("crash_config_set", { config });
+}
+
+/** Crash reports kept on this machine, newest first. */
+export async function crashReportsList(): Promise<CrashReport[]> {
+  return invoke("crash_reports_list");
+}
+
+export async function crashReportsClear(): Promise<void> {
+  await invoke("crash_reports_clear");
+}
+
+/** System-wide hotkeys, in the same chord notation as in-app shortcuts; `null` = unbound. */
+export type GlobalHotkeys = {
+  toggleWindow: string | null;
+  quickConnect: string | null;
+  newTerminal: string | null;
+};
+
+/** Payload of the `global-hotkey` event (show/hide is handled by the backend). */
+export type GlobalHotkeyEvent = { action: "quickConnect" | "newTerminal" };
+
+export async function globalHotkeysGet(): Promise<GlobalHotkeys> {
+  return invoke("global_hotkeys_get");
+}
+
+/** Returns the normalized bindings; rejects (keeping the old ones) if a chord is taken or invalid. */
+export async function globalHotkeysSet(config: GlobalHotkeys): Promise<GlobalHotkeys> {
+  return invoke("global_hotkeys_set", { config });
+}
+
+export type NotificationConfig = {
+  enabled: boolean;
+  /** Skip notifications while the OpsPad window has focus. */
+  onlyWhenUnfocused: boolean;
+  sessionExit: boolean;
+  /** Long-running CommandDock commands finishing. */
+  commands: boolean;
+  jobs: boolean;
+  /** Limit job notifications to failures and timeouts. */
+  jobsFailedOnly: boolean;
+  fanout: boolean;
+  sequences: boolean;
+  alerts: boolean;
+};
+
+export async function notificationConfigGet(): Promise<NotificationConfig> {
+  return invoke("notification_config_get");
+}
+
+export async function notificationConfigSet(config: NotificationConfig): Promise<void> {
+  await invoke("notification_config_set", { config });
+}
+
+/** Show a sample notification; the first call triggers the OS permission prompt. */
+export async function notificationTest(): Promise<void> {
+  await invoke("notification_test");
+}
+
+export type MonitorCheckKind = "tcp" | "sshBanner" | "icmp";
+
+export type MonitorConfig = {
+  enabled: boolean;
+  intervalSecs: number;
+  timeoutMs: number;
+  /** Answers slower than this count as "slow". */
+  slowMs: number;
+  check: MonitorCheckKind;
+  /** Per-host check kind, keyed by host id. */
+  overrides: Record<string, MonitorCheckKind>;
+  /** Host ids never checked. */
+  skip: string[];
+};
+
+/** Latest check result for a host; also the `host:status` event payload (sent on changes). */
+export type HostStatus = {
+  hostId: string;
+  status: "up" | "slow" | "down";
+  latencyMs: number | null;
+  detail: string | null;
+  checkedAt: number;
+  since: number;
+  failures: number;
+};
+
+export type HostStatusEvent = {
+  id: number;
+  hostId: string;
+  status: "up" | "slow" | "down";
+  latencyMs: number | null;
+  detail: string | null;
+  createdAt: string;
+};
+
+export async function monitorConfigGet(): Promise<MonitorConfig> {
+  return invoke("monitor_config_get");
+}
+
+export async function monitorConfigSet(config: MonitorConfig): Promise<void> {
+  await invoke("monitor_config_set", { config });
+}
+
+/** Latest status of every checked host (empty while the monitor is off). */
+export async function hostStatusList(): Promise<HostStatus[]> {
+  return invoke("host_status_list");
+}
+
+/** A host's status transitions, newest first. */
+export async function hostStatusHistory(hostId: string, limit?: number): Promise<HostStatusEvent[]> {
+  return invoke("host_status_history", { hostId, limit: limit ?? null });
+}
+
+export async function hostCheckNow(hostId: string): Promise<HostStatus> {
+  return invoke("host_check_now", { hostId });
+}
+
+export type HttpHeader = { name: string; value: string };
+
+/** An HTTP(S) endpoint checked on the monitor's schedule. */
+export type HttpCheck = {
+  id: string;
+  name: string;
+  url: string;
+  expectedStatus: number;
+  /** Slower responses count as "slow"; null uses the monitor's threshold. */
+  slowMs: number | null;
+  headers: HttpHeader[];
+  /** The host the service runs on; null for standalone checks. */
+  hostId: string | null;
+  enabled: boolean;
+};
+
+export type HttpCheckCreate = {
+  name: string;
+  url: string;
+  expectedStatus?: number | null;
+  slowMs?: number | null;
+  headers?: HttpHeader[];
+  hostId?: string | null;
+  enabled?: boolean;
+};
+
+/** Latest result for an HTTP check; also the `http:status` event payload (sent on changes). */
+export type HttpCheckStatus = {
+  checkId: string;
+  hostId: string | null;
+  status: "up" | "slow" | "down";
+  httpStatus: number | null;
+  latencyMs: number | null;
+  detail: string | null;
+  checkedAt: number;
+  since: number;
+  failures: number;
+};
+
+export type HttpCheckEvent = {
+  id: number;
+  checkId: string;
+  status: "up" | "slow" | "down";
+  httpStatus: number | null;
+  latencyMs: number | null;
+  detail: string | null;
+  createdAt: string;
+};
+
+export async function httpChecksList(): Promise<HttpCheck[]> {
+  return invoke("http_checks_list");
+}
+
+export async function httpChecksCreate(input: HttpCheckCreate): Promise<HttpCheck> {
+  return invoke("http_checks_create", { input });
+}
+
+export async function httpChecksUpdate(input: HttpCheck): Promise<HttpCheck> {
+  return invoke("http_checks_update", { input });
+}
+
+export async function httpChecksDelete(id: string): Promise<void> {
+  await invoke("http_checks_delete", { id });
+}
+
+/** Latest status of every enabled HTTP check (empty while the monitor is off). */
+export async function httpCheckStatusList(): Promise<HttpCheckStatus[]> {
+  return invoke("http_check_status_list");
+}
+
+/** A check's status transitions, newest first. */
+export async function httpCheckHistory(checkId: string, limit?: number): Promise<HttpCheckEvent[]> {
+  return invoke("http_check_history", { checkId, limit: limit ?? null });
+}
+
+export async function httpCheckNow(checkId: string): Promise<HttpCheckStatus> {
+  return invoke("http_check_now", { checkId });
+}
+
+export type SamplerConfig = {
+  enabled: boolean;
+  intervalSecs: number;
+  /** Host ids to sample; nothing is sampled unless listed. */
+  hosts: string[];
+};
+
+export type DiskUsage = {
+  filesystem: string;
+  mount: string;
+  size: string;
+  used: string;
+  avail: string;
+  usePercent: number;
+};
+
+/** A parsed `uptime` / `df -hP` / `free -m` sample; also the `host:metrics` event payload. */
+export type HostMetrics = {
+  hostId: string;
+  sampledAt: number;
+  uptime: string | null;
+  load1: number | null;
+  load5: number | null;
+  load15: number | null;
+  memTotalMb: number | null;
+  memUsedMb: number | null;
+  memAvailableMb: number | null;
+  swapTotalMb: number | null;
+  swapUsedMb: number | null;
+  disks: DiskUsage[];
+  /** Set when the sample failed; the other fields are then empty. */
+  error: string | null;
+};
+
+export async function samplerConfigGet(): Promise<SamplerConfig> {
+  return invoke("sampler_config_get");
+}
+
+export async function samplerConfigSet(config: SamplerConfig): Promise<void> {
+  await invoke("sampler_config_set", { config });
+}
+
+export async function hostMetricsLatest(id: string): Promise<HostMetrics | null> {
+  return invoke("host_metrics_latest", { id });
+}
+
+export async function hostMetricsSampleNow(id: string): Promise<HostMetrics> {
+  return invoke("host_metrics_sample_now", { id });
+}
+
+export type AlertCondition =
+  /** The host failed this many checks in a row. */
+  | { kind: "hostDown"; checks: number }
+  /** An HTTP check failed this many times in a row. */
+  | { kind: "httpCheckFailing"; checks: number }
+  /** A filesystem is more than `percent` full; `mount` limits it to one mount point. */
+  | { kind: "diskUsage"; percent: number; mount: string | null }
+  /** A line of a headless job's output matches `pattern` (regex). */
+  | { kind: "outputMatch"; pattern: string };
+
+export type AlertRule = {
+  id: string;
+  name: string;
+  condition: AlertCondition;
+  /** Limit the rule to one host; null applies it to every host. */
+  hostId: string | null;
+  enabled: boolean;
+};
+
+export type AlertRuleCreate = {
+  name: string;
+  condition: AlertCondition;
+  hostId?: string | null;
+  enabled?: boolean;
+};
+
+export type AlertStatus = "open" | "acknowledged" | "resolved";
+
+/** Also the `alert:raised` / `alert:resolved` event payload. */
+export type Alert = {
+  id: string;
+  /** Null once the rule has been deleted. */
+  ruleId: string | null;
+  ruleName: string;
+  hostId: string | null;
+  /** What fired within the rule (a mount point, "down", "output"). */
+  subject: string;
+  message: string;
+  status: AlertStatus;
+  createdAt: string;
+  acknowledgedAt: number | null;
+  resolvedAt: number | null;
+};
+
+export async function alertRulesList(): Promise<AlertRule[]> {
+  return invoke("alert_rules_list");
+}
+
+export async function alertRulesCreate(input: AlertRuleCreate): Promise<AlertRule> {
+  return invoke("alert_rules_create", { input });
+}
+
+export async function alertRulesUpdate(input: AlertRule): Promise<AlertRule> {
+  return invoke("alert_rules_update", { input });
+}
+
+export async function alertRulesDelete(id: string): Promise<void> {
+  await invoke("alert_rules_delete", { id });
+}
+
+/** Alerts newest first, optionally only those with `status`. */
+export async function alertsList(status?: AlertStatus, limit?: number): Promise<Alert[]> {
+  return invoke("alerts_list", { status: status ?? null, limit: limit ?? null });
+}
+
+export async function alertAcknowledge(id: string): Promise<Alert> {
+  return invoke("alert_acknowledge", { id });
+}
+
+export async function alertResolve(id: string): Promise<Alert> {
+  return invoke("alert_resolve", { id });
+}
+
+/** What to post to Slack; every "Share to Slack" surface sends one of these. */
+export type SlackShare =
+  /** Command or job output, posted as a code block. */
+  | { kind: "output"; title: string; text: string }
+  /** The runbook section under `heading`, rendered with `values`. */
+  | { kind: "runbookSection"; runbookId: string; heading: string; values?: Record<string, string> }
+  /** A free-form incident summary, optionally followed by the open alerts. */
+  | { kind: "incident"; title: string; summary: string; includeAlerts?: boolean };
+
+/** Store the Slack incoming webhook URL in the vault; an empty string removes it. */
+export async function slackWebhookSet(url: string): Promise<void> {
+  await invoke("slack_webhook_set", { url });
+}
+
+export async function slackWebhookConfigured(): Promise<boolean> {
+  return invoke("slack_webhook_configured");
+}
+
+/** Post to the configured Slack webhook (after redaction). */
+export async function shareToSlack(share: SlackShare): Promise<void> {
+  await invoke("share_to_slack", { share });
+}
+
+export type OncallProvider = "none" | "pagerDuty" | "opsgenie";
+
+export type OncallConfig = {
+  provider: OncallProvider;
+  /** Opsgenie: whose alerts to list. */
+  email: string | null;
+  /** Opsgenie: use the EU API endpoint. */
+  eu: boolean;
+};
+
+/** An incident (PagerDuty) or alert (Opsgenie) assigned to you. */
+export type OncallIncident = {
+  provider: OncallProvider;
+  id: string;
+  /** PagerDuty incident number or Opsgenie tiny id. */
+  number: string | null;
+  title: string;
+  status: string;
+  /** PagerDuty urgency or Opsgenie priority. */
+  urgency: string | null;
+  service: string | null;
+  createdAt: string | null;
+  /** Web UI link, when the provider's API returns one. */
+  url: string | null;
+};
+
+export async function oncallConfigGet(): Promise<OncallConfig> {
+  return invoke("oncall_config_get");
+}
+
+export async function oncallConfigSet(config: OncallConfig): Promise<void> {
+  await invoke("oncall_config_set", { config });
+}
+
+/** Store the on-call API key in the vault; an empty string removes it. */
+export async function oncallApiKeySet(key: string): Promise<void> {
+  await invoke("oncall_api_key_set", { key });
+}
+
+export async function oncallApiKeyConfigured(): Promise<boolean> {
+  return invoke("oncall_api_key_configured");
+}
+
+/** Open incidents/alerts assigned to you, newest first (empty when no provider is set). */
+export async function oncallIncidentsList(): Promise<OncallIncident[]> {
+  return invoke("oncall_incidents_list");
+}
+
+/** A stretch of incident work; confirmations of destructive commands are filed under the active one. */
+export type Incident = {
+  id: string;
+  title: string;
+  /** The ticket, e.g. a PagerDuty incident number. */
+  reference: string | null;
+  startedAt: number;
+  /** Null while active. */
+  endedAt: number | null;
+};
+
+/** Start an incident; the active one (if any) ends. */
+export async function incidentStart(title: string, reference?: string | null): Promise<Incident> {
+  return invoke("incident_start", { title, reference: reference ?? null });
+}
+
+export async function incidentEnd(): Promise<void> {
+  await invoke("incident_end");
+}
+
+export async function incidentActive(): Promise<Incident | null> {
+  return invoke("incident_active");
+}
+
+export async function incidentsList(limit?: number): Promise<Incident[]> {
+  return invoke("incidents_list", { limit: limit ?? null });
+}
+
+/** Evidence that a destructive command was confirmed before it ran. */
+export type Confirmation = {
+  id: string;
+  confirmedAt: number;
+  /** The OS account OpsPad ran under. */
+  osUser: string;
+  incidentId: string | null;
+  /** Set for terminal runs; fan-out runs have a host instead. */
+  sessionId: string | null;
+  scope: string | null;
+  hostId: string | null;
+  environmentTag: string;
+  dockCommandId: string | null;
+  dockCommandTitle: string | null;
+  /** The rendered command, redacted like history. */
+  commandText: string;
+  policy: DestructivePolicy;
+  /** The typed environment name or reason. */
+  confirmationText: string | null;
+  historyId: string | null;
+  /** The second approver in a four-eyes environment: name and key fingerprint. */
+  approvedBy: string | null;
+};
+
+export type ConfirmationQuery = {
+  incidentId?: string | null;
+  scope?: string | null;
+  /** Epoch seconds, inclusive. */
+  since?: number | null;
+  until?: number | null;
+  limit?: number | null;
+};
+
+/** The confirmation audit trail, oldest first; pages are `query.limit` long. */
+export async function confirmationsList(
+  query?: ConfirmationQuery,
+  cursor?: string | null,
+): Promise<Page<Confirmation>> {
+  return invoke("confirmations_list", { query: query ?? null, cursor: cursor ?? null });
+}
+
+export type TimelineFormat = "json" | "markdown";
+
+export type TimelineEventKind =
+  | "command"
+  | "sessionOpened"
+  | "sessionClosed"
+  | "mark"
+  | "alertFired"
+  | "alertResolved"
+  | "confirmation";
+
+/** One entry of a `timelineExport` JSON document. */
+export type TimelineEvent = {
+  at: string;
+  kind: TimelineEventKind;
+  scope: string | null;
+  hostLabel: string | null;
+  environmentTag: string | null;
+  summary: string;
+  detail: string | null;
+  /** The history entry, mark, alert or confirmation id (the session id for session events). */
+  sourceId: string;
+};
+
+/** The JSON format's shape. */
+export type Timeline = {
+  since: string;
+  until: string;
+  incident: Incident | null;
+  exportedAt: string;
+  truncated: boolean;
+  events: TimelineEvent[];
+};
+
+/**
+ * History, session opens/closes, marks, alerts and confirmations merged in time order, redacted.
+ * Pass `incidentId` or a range (`until` defaults to now).
+ */
+export async function timelineExport(
+  range: { since?: number | null; until?: number | null; incidentId?: string | null },
+  format: TimelineFormat,
+): Promise<string> {
+  return invoke("timeline_export", {
+    since: range.since ?? null,
+    until: range.until ?? null,
+    incidentId: range.incidentId ?? null,
+    format,
+  });
+}
+
+export type UsageGroup = "host" | "environment" | "incident";
+
+export type UsageRow = {
+  group: UsageGroup;
+  /** The terminal scope ("ssh:<hostId>", "local"), environment tag or incident id. */
+  key: string;
+  /** The host's label, the environment tag or the incident's title. */
+  label: string;
+  sessions: number;
+  connectedSecs: number;
+  /** CommandDock commands, each run of a watch counting once. */
+  commands: number;
+  /** Sessions with no recorded close (the app quit under them); they add no connected time. */
+  unclosedSessions: number;
+};
+
+export type UsageReport = {
+  since: string;
+  until: string;
+  generatedAt: string;
+  truncated: boolean;
+  /** Hosts, then environments, then incidents; most connected first within each. */
+  rows: UsageRow[];
+};
+
+/** Hours connected and commands run over a range (epoch seconds; `until` defaults to now). */
+export async function usageReport(range: { since: number; until?: number | null }): Promise<UsageReport> {
+  return invoke("usage_report", { since: range.since, until: range.until ?? null });
+}
+
+/** `usageReport` as CSV: group, key, label, sessions, hours_connected, commands, unclosed_sessions. */
+export async function usageReportCsv(range: { since: number; until?: number | null }): Promise<string> {
+  return invoke("usage_report_csv", { since: range.since, until: range.until ?? null });
+}
+
+/** "local" is an OpenAI-compatible server on this machine (Ollama, llama.cpp). */
+export type SuggestProvider = "disabled" | "openAi" | "local";
+
+export type SuggestConfig = {
+  provider: SuggestProvider;
+  /** Overrides the provider's endpoint, up to and including /v1. */
+  baseUrl: string | null;
+  model: string | null;
+  /** Defaults to 3, at most 10. */
+  maxSuggestions: number | null;
+};
+
+/** A suggested command with the same lint and policy results as dockCommandPreview. */
+export type CommandSuggestion = DockCommandPreview & {
+  explanation: string | null;
+};
+
+export async function suggestConfigGet(): Promise<SuggestConfig> {
+  return invoke("suggest_config_get");
+}
+
+export async function suggestConfigSet(config: SuggestConfig): Promise<void> {
+  await invoke("suggest_config_set", { config });
+}
+
+/** Store the suggestion provider's API key in the vault; an empty string removes it. */
+export async function suggestApiKeySet(key: string): Promise<void> {
+  await invoke("suggest_api_key_set", { key });
+}
+
+export async function suggestApiKeyConfigured(): Promise<boolean> {
+  return invoke("suggest_api_key_configured");
+}
+
+/**
+ * Candidate commands for a plain-language prompt in a dock scope ("local", "ssh:<hostId>"). The
+ * prompt is redacted before it's sent; nothing is run.
+ */
+export async function commandSuggest(prompt: string, scope: string): Promise<CommandSuggestion[]> {
+  return invoke("command_suggest", { prompt, scope });
+}
+
+export type LanShareConfig = {
+  enabled: boolean;
+  /** Fixed port; null picks a free one. */
+  port: number | null;
+};
+
+/** The active LAN share; also the `lan-share:changed` event payload (null once stopped). */
+export type ShareInfo = {
+  sessionId: string;
+  /** Viewer URL, token included. */
+  url: string;
+  viewers: number;
+  startedAt: number;
+};
+
+/**
+ * Local HTTP API for scripts (127.0.0.1 only, bearer token). Off by default. While it runs, its
+ * URL and token are also written to `automation.json` in the app data folder.
+ */
+export type AutomationApiConfig = {
+  enabled: boolean;
+  /** Fixed port; null picks a free one. */
+  port: number | null;
+  /** Kept in the vault; null while it's locked. */
+  token: string | null;
+};
+
+export type AutomationApiStatus = {
+  url: string;
+  port: number;
+};
+
+/** Payload of `automation:session_opened`: a session opened through the API, to show as a tab. */
+export type AutomationSessionOpenedEvent = {
+  sessionId: string;
+  hostId: string | null;
+  title: string;
+  environmentTag: string;
+};
+
+export async function automationApiConfigGet(): Promise<AutomationApiConfig> {
+  return invoke("automation_api_config_get");
+}
+
+export async function automationApiConfigSet(config: AutomationApiConfig): Promise<AutomationApiStatus | null> {
+  return invoke("automation_api_config_set", { config });
+}
+
+export async function automationApiStatus(): Promise<AutomationApiStatus | null> {
+  return invoke("automation_api_status");
+}
+
+export async function automationApiRotateToken(): Promise<AutomationApiConfig> {
+  return invoke("automation_api_rotate_token");
+}
+
+export async function lanShareConfigGet(): Promise<LanShareConfig> {
+  return invoke("lan_share_config_get");
+}
+
+export async function lanShareConfigSet(config: LanShareConfig): Promise<void> {
+  await invoke("lan_share_config_set", { config });
+}
+
+/** Stream a session's output read-only to browsers on the LAN (fails unless enabled in settings). */
+export async function sessionShareStart(sessionId: string): Promise<ShareInfo> {
+  return invoke("session_share_start", { sessionId });
+}
+
+export async function sessionShareStop(): Promise<void> {
+  await invoke("session_share_stop");
+}
+
+export async function sessionShareStatus(): Promise<ShareInfo | null> {
+  return invoke("session_share_status");
+}
+
+/** A live session's output as Markdown (ANSI stripped, split at each CommandDock command and mark, redacted). */
+export async function sessionExportMarkdown(sessionId: string): Promise<string> {
+  return invoke("session_export_markdown", { sessionId });
+}
+
+export type TerminalLastOutput = {
+  command: string | null;
+  /** Plain text, redacted. */
+  output: string;
+  exitStatus: number | null;
+  /** Epoch seconds. */
+  startedAt: number;
+  endedAt: number;
+  /** The command's start is older than the kept output. */
+  truncated: boolean;
+};
+
+/** Output of the session's last finished command; null without shell integration or before one finishes. */
+export async function terminalLastOutput(sessionId: string): Promise<TerminalLastOutput | null> {
+  return invoke("terminal_last_output", { sessionId });
+}
+
+/** A session being recorded to an asciicast (`.cast`) file, with secrets and password input masked. */
+export type RecordingInfo = {
+  sessionId: string;
+  path: string;
+  startedAt: string;
+  paused: boolean;
+};
+
+/** Payload of the `recording:state` event. */
+export type RecordingStateEvent = RecordingInfo & {
+  status: "recording" | "paused" | "stopped";
+};
+
+export async function recordingStart(sessionId: string): Promise<RecordingInfo> {
+  return invoke("recording_start", { sessionId });
+}
+
+export async function recordingPause(sessionId: string): Promise<RecordingInfo | null> {
+  return invoke("recording_pause", { sessionId });
+}
+
+export async function recordingResume(sessionId: string): Promise<RecordingInfo | null> {
+  return invoke("recording_resume", { sessionId });
+}
+
+/** Resolves to the recording's file path. */
+export async function recordingStop(sessionId: string): Promise<string | null> {
+  return invoke("recording_stop", { sessionId });
+}
+
+export async function recordingList(): Promise<RecordingInfo[]> {
+  return invoke("recording_list");
+}
+
+/** Output evicted from sessions' in-memory transcripts, kept compressed on disk. Off by default. */
+export type ScrollbackConfig = {
+  enabled: boolean;
+  /** Compressed size kept per session (1-4096); the oldest pages go first. */
+  maxMbPerSession: number;
+  /** Ended sessions' scrollback is deleted after this many days. */
+  retentionDays: number;
+};
+
+export type SpooledSession = {
+  sessionId: string;
+  kind: "local" | "ssh";
+  environmentTag: string;
+  /** Given with terminalRename. */
+  title: string | null;
+  startedAt: string;
+  /** null while live (or if the app quit first). */
+  endedAt: string | null;
+  pages: number;
+  bytes: number;
+  /** The oldest output was deleted to stay within the limit. */
+  dropped: boolean;
+};
+
+export type ScrollbackPage = {
+  sessionId: string;
+  /** 0 is the oldest page kept. */
+  page: number;
+  pages: number;
+  text: string;
+  /** Epoch seconds of the page's first and last output. */
+  fromAt: number;
+  toAt: number;
+};
+
+export async function scrollbackConfigGet(): Promise<ScrollbackConfig> {
+  return invoke("scrollback_config_get");
+}
+
+/** Applies to sessions opened afterwards. */
+export async function scrollbackConfigSet(config: ScrollbackConfig): Promise<ScrollbackConfig> {
+  return invoke("scrollback_config_set", { config });
+}
+
+export async function scrollbackSessionsList(): Promise<SpooledSession[]> {
+  return invoke("scrollback_sessions_list");
+}
+
+/** A page of spilled scrollback. For a live session, the newest page is followed by its in-memory transcript. */
+export async function scrollbackPage(sessionId: string, page: number): Promise<ScrollbackPage> {
+  return invoke("scrollback_page", { sessionId, page });
+}
+
+/** Ended sessions only. */
+export async function scrollbackDelete(sessionId: string): Promise<void> {
+  return invoke("scrollback_delete", { sessionId });
+}
+
+/** Caps on recordings and spilled scrollback; `null` turns a limit off. Scrollback's age limit is
+ * `ScrollbackConfig.retentionDays`. */
+export type RetentionConfig = {
+  recordingMaxDays: number | null;
+  recordingMaxMb: number | null;
+  scrollbackMaxMb: number | null;
+};
+
+/** What a purge deleted; also the payload of the `storage:purged` event. */
+export type PurgeReport = {
+  recordingsDeleted: number;
+  scrollbackSessionsDeleted: number;
+  bytesFreed: number;
+};
+
+export type StorageArea = {
+  name: "database" | "recordings" | "scrollback" | "crashReports" | "logs";
+  path: string;
+  bytes: number;
+  files: number;
+};
+
+export type StorageUsage = {
+  areas: StorageArea[];
+  totalBytes: number;
+};
+
+export async function retentionConfigGet(): Promise<RetentionConfig> {
+  return invoke("retention_config_get");
+}
+
+/** Saves and enforces the limits right away. */
+export async function retentionConfigSet(config: RetentionConfig): Promise<PurgeReport> {
+  return invoke("retention_config_set", { config });
+}
+
+export async function storageUsage(): Promise<StorageUsage> {
+  return invoke("storage_usage");
+}
+
+export type TerminalSearchOptions = {
+  /** Treat the pattern as a regular expression (Rust syntax) rather than literal text. */
+  regex?: boolean;
+  caseSensitive?: boolean;
+  /** Lines around each match (default 2, at most 20). */
+  context?: number;
+  /** Default 200, at most 2000. */
+  maxResults?: number;
+};
+
+export type TerminalSearchMatch = {
+  /** From 0 at the oldest line kept. */
+  line: number;
+  /** Spilled page the line is on (see scrollbackPage); null when it's still in memory. */
+  page: number | null;
+  /** Epoch seconds. */
+  at: number;
+  text: string;
+  /** [start, end) offsets into text. */
+  ranges: [number, number][];
+  before: string[];
+  after: string[];
+};
+
+export type TerminalSearchResult = {
+  matches: TerminalSearchMatch[];
+  linesSearched: number;
+  /** Stopped at maxResults. */
+  limited: boolean;
+  /** The session's oldest output is no longer kept. */
+  incomplete: boolean;
+};
+
+/** Search a session's kept output, including scrollback spilled to disk; works after it ends too. */
+export async function terminalSearch(
+  sessionId: string,
+  pattern: string,
+  opts?: TerminalSearchOptions,
+): Promise<TerminalSearchResult> {
+  return invoke("terminal_search", { sessionId, pattern, opts });
+}
+
+/** A command bracketed by the shell's prompt marks (needs shell integration). */
+export type TerminalCommand = {
+  /** Counts up from 1 within the session. */
+  id: number;
+  /** As echoed by the shell; null when it doesn't mark where the command line starts. */
+  command: string | null;
+  /** Epoch ms. */
+  startedAt: number;
+  endedAt: number | null;
+  exitStatus: number | null;
+  /** Output bytes (marks not counted) before the command's prompt and before its output. */
+  promptOffset: number;
+  outputOffset: number;
+  /** The CommandDock history entry that sent it. */
+  historyId: string | null;
+};
+
+/** Payload of `terminal:command_start` and `terminal:command_end`. */
+export type TerminalCommandEvent = TerminalCommand & { sessionId: string };
+
+/** Finished commands in a live session, oldest first. */
+export async function terminalCommands(sessionId: string): Promise<TerminalCommand[]> {
+  return invoke("terminal_commands", { sessionId });
+}
+
+export type TerminalWatchdogConfig = {
+  enabled: boolean;
+  /** Minutes without output after input before a session is flagged (1-120). */
+  quietMinutes: number;
+};
+
+/**
+ * Payload of `terminal:suspect_hung`: input went unanswered for quietMinutes, or a probe got no
+ * reply. Output arriving for the session afterwards means it recovered.
+ */
+export type TerminalSuspectHungEvent = {
+  sessionId: string;
+  reason: "noOutput" | "probeFailed";
+  /** Epoch seconds of the unanswered input (or the probe). */
+  since: number;
+  lastOutputAt: number | null;
+  environmentTag: string;
+};
+
+export type TerminalProbeResult = {
+  responded: boolean;
+  /** Echo probes only: the shell ran the echo, rather than the keys just being echoed. */
+  executed: boolean | null;
+  latencyMs: number | null;
+  suggestReconnect: boolean;
+};
+
+export async function terminalWatchdogConfigGet(): Promise<TerminalWatchdogConfig> {
+  return invoke("terminal_watchdog_config_get");
+}
+
+export async function terminalWatchdogConfigSet(config: TerminalWatchdogConfig): Promise<TerminalWatchdogConfig> {
+  return invoke("terminal_watchdog_config_set", { config });
+}
+
+/** "lock" makes the session read-only (see `terminalSetReadonly`); "close" ends it. */
+export type IdleAction = "close" | "lock";
+
+export type IdleRule = {
+  /** 0 leaves idle sessions alone. */
+  idleMinutes: number;
+  action: IdleAction;
+  /** Output resets the clock too, not just input (off: a shell left running `top` still goes idle). */
+  outputCounts: boolean;
+};
+
+/** Closing or locking sessions nobody has typed into for a while. Off by default. */
+export type IdlePolicyConfig = {
+  enabled: boolean;
+  rule: IdleRule;
+  /** By environment tag, in place of `rule`. */
+  overrides: Record<string, IdleRule>;
+  /** How long before acting `terminal:idle_warning` is sent; 0 for no warning. */
+  warnMinutes: number;
+};
+
+/** Payload of `terminal:idle_warning`; any input before `actionAt` cancels it. */
+export type TerminalIdleWarningEvent = {
+  sessionId: string;
+  environmentTag: string;
+  action: IdleAction;
+  /** Epoch seconds. */
+  idleSince: number;
+  actionAt: number;
+};
+
+/** Payload of `terminal:idle_action`. */
+export type TerminalIdleActionEvent = {
+  sessionId: string;
+  environmentTag: string;
+  action: IdleAction;
+  idleSince: number;
+};
+
+export async function idlePolicyGet(): Promise<IdlePolicyConfig> {
+  return invoke("idle_policy_get");
+}
+
+export async function idlePolicySet(config: IdlePolicyConfig): Promise<IdlePolicyConfig> {
+  return invoke("idle_policy_set", { config });
+}
+
+/** Type a newline (default) or an echo into the session and wait up to 5 s for a reply. */
+export async function terminalProbe(sessionId: string, mode?: "newline" | "echo"): Promise<TerminalProbeResult> {
+  return invoke("terminal_probe", { sessionId, mode });
+}
+
+/** Checking live SSH sessions after the machine wakes or its network changes. */
+export type NetworkWatchConfig = {
+  enabled: boolean;
+  /** Replace dead sessions to saved hosts that log in with a key file or bound password. */
+  autoReconnect: boolean;
+};
+
+/** Payload of `network:changed`, sent before the checks' `terminal:connection` events. */
+export type NetworkChangedEvent = {
+  reason: "resumed" | "addressChanged";
+  /** Local address of the default route; null when offline. */
+  address: string | null;
+};
+
+/** Payload of `terminal:connection`. */
+export type TerminalConnectionEvent = {
+  sessionId: string;
+  /** "unconfirmed": nothing says it's dead, but it hasn't printed anything since (try terminalProbe). */
+  status: "checking" | "alive" | "unconfirmed" | "dead" | "reconnecting";
+  detail: string | null;
+  /** Reconnecting: the new session taking over; the old one is closed. */
+  replacementId: string | null;
+  /** Dead: a new session would log in without the user. */
+  reconnectable: boolean;
+};
+
+export async function networkWatchConfigGet(): Promise<NetworkWatchConfig> {
+  return invoke("network_watch_config_get");
+}
+
+export async function networkWatchConfigSet(config: NetworkWatchConfig): Promise<NetworkWatchConfig> {
+  return invoke("network_watch_config_set", { config });
+}
+
+/** What the post-connect probe found on an ssh session's server; fields it couldn't read are null. */
+export type ServerInfo = {
+  hostname: string | null;
+  /** `uname -a`. */
+  kernel: string | null;
+  distro: string | null;
+  uptime: string | null;
+  /** Epoch seconds. */
+  probedAt: number;
+  /** Why the probe failed (e.g. the host needs a password); the other fields are null then. */
+  error: string | null;
+};
+
+/** Payload of `terminal:server_info`. */
+export type TerminalServerInfoEvent = {
+  sessionId: string;
+  hostId: string | null;
+  info: ServerInfo;
+};
+
+/** Probing ssh sessions' servers as they open. */
+export type ServerInfoConfig = {
+  enabled: boolean;
+};
+
+export async function serverInfoConfigGet(): Promise<ServerInfoConfig> {
+  return invoke("server_info_config_get");
+}
+
+export async function serverInfoConfigSet(config: ServerInfoConfig): Promise<ServerInfoConfig> {
+  return invoke("server_info_config_set", { config });
+}
+
+/**
+ * One round-trip measurement of an ssh session. `networkMs` is the TCP connect time alone;
+ * `remoteMs` adds the remote sshd and shell, so a large gap between them points at the host.
+ */
+export type SessionLatency = {
+  /** Null when the session goes through a jump host or proxy. */
+  networkMs: number | null;
+  /** Null on Windows and for hosts that log in with a security key. */
+  remoteMs: number | null;
+  /** Epoch seconds. */
+  measuredAt: number;
+  error: string | null;
+};
+
+/** Payload of `terminal:latency`. */
+export type TerminalLatencyEvent = {
+  sessionId: string;
+  latency: SessionLatency;
+};
+
+/** Measuring open ssh sessions' latency in the background. Off by default. */
+export type LatencyConfig = {
+  enabled: boolean;
+  /** 5 to 3600. */
+  intervalSecs: number;
+};
+
+export async function latencyConfigGet(): Promise<LatencyConfig> {
+  return invoke("latency_config_get");
+}
+
+export async function latencyConfigSet(config: LatencyConfig): Promise<LatencyConfig> {
+  return invoke("latency_config_set", { config });
+}
+
+/** Measure now instead of waiting for the next probe; also emits `terminal:latency`. */
+export async function terminalLatencyProbe(sessionId: string): Promise<SessionLatency> {
+  return invoke("terminal_latency_probe", { sessionId });
+}
+
+export type IntegrationShell = "bash" | "zsh" | "fish" | "powerShell";
+
+export type ShellIntegrationStatus = {
+  shell: IntegrationShell;
+  profile: string;
+  /** The shell is on the PATH. */
+  available: boolean;
+  installed: boolean;
+};
+
+export type ShellIntegrationOutcome = {
+  shell: IntegrationShell;
+  profile: string;
+  /** False when there was nothing to add (or remove). */
+  changed: boolean;
+};
+
+/** Payload of `terminal:shell_integration`, sent when a session's shell sends its first prompt mark. */
+export type TerminalShellIntegrationEvent = {
+  sessionId: string;
+};
+
+/** `scope`: "local" or "ssh:<host id>" (remote hosts must log in with a key or agent). */
+export async function shellIntegrationStatus(scope: string): Promise<ShellIntegrationStatus[]> {
+  return invoke("shell_integration_status", { scope });
+}
+
+export async function shellIntegrationInstall(scope: string, shell: IntegrationShell): Promise<ShellIntegrationOutcome> {
+  return invoke("shell_integration_install", { scope, shell });
+}
+
+export async function shellIntegrationUninstall(scope: string, shell: IntegrationShell): Promise<ShellIntegrationOutcome> {
+  return invoke("shell_integration_uninstall", { scope, shell });
+}
+
+export async function shellIntegrationSnippet(shell: IntegrationShell): Promise<string> {
+  return invoke("shell_integration_snippet", { shell });
+}
+
+/** A request from `opspad connect|run ...` or an `opspad://` link, already resolved. */
+export type CliAction =
+  | { action: "connect"; host: Host }
+  | { action: "run"; host: Host; command: DockCommand }
+  | { action: "runbook"; runbook: Runbook }
+  /** Run in whichever terminal is active (tray menu). */
+  | { action: "command"; command: DockCommand };
+
+/** Take the pending CLI action, if any. Call on load and on each `cli:action` event. */
+export async function cliTakeAction(): Promise<CliAction | null> {
+  return invoke("cli_take_action");
 }
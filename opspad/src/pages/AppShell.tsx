@@ -1,10 +1,16 @@
 import { useEffect, useState, type CSSProperties } from "react";
+import { listen } from "@tauri-apps/api/event";
 
+import { cliTakeAction, type GlobalHotkeyEvent } from "../lib/opspadApi";
 import { CommandDock } from "../ui/CommandDock";
 import type { HostListItem } from "../ui/HostsSidebar";
 import { HostsSidebar } from "../ui/HostsSidebar";
 import { TerminalWorkspace } from "../ui/TerminalWorkspace";
 
+function nonce() {
+  return typeof crypto !== "undefined" && "randomUUID" in crypto ? crypto.randomUUID() : String(Date.now());
+}
+
 export function AppShell() {
   const [connectRequest, setConnectRequest] = useState<{
     host: HostListItem;
@@ -17,6 +23,10 @@ export function AppShell() {
     connected?: boolean;
   }>({ kind: "local", environmentTag: "LOCAL", hostId: null, connected: false });
   const [hostsCollapsed, setHostsCollapsed] = useState(false);
+  // A CLI "run" waits for its host's session to connect before reaching the dock.
+  const [pendingRun, setPendingRun] = useState<{ hostId: string; commandId: string } | null>(null);
+  const [runRequest, setRunRequest] = useState<{ commandId: string; nonce: string } | null>(null);
+  const [runbookRequest, setRunbookRequest] = useState<{ runbookId: string; nonce: string } | null>(null);
   const [terminalActive, setTerminalActive] = useState(false);
 
   // "Terminal focus mode": dim side panes briefly while the user is typing.
@@ -34,6 +44,57 @@ export function AppShell() {
     };
   }, []);
 
+  // `opspad connect|run ...` and `opspad://` links, from another launch or this one's arguments.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    const take = async () => {
+      const action = await cliTakeAction();
+      if (!action) return;
+      if (action.action === "runbook") {
+        setRunbookRequest({ runbookId: action.runbook.id, nonce: nonce() });
+        return;
+      }
+      if (action.action === "command") {
+        setRunRequest({ commandId: action.command.id, nonce: nonce() });
+        return;
+      }
+      setConnectRequest({ host: action.host, nonce: nonce() });
+      setPendingRun(action.action === "run" ? { hostId: action.host.id, commandId: action.command.id } : null);
+    };
+    (async () => {
+      unlisten = await listen("cli:action", () => void take().catch(() => {}));
+      await take();
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Global hotkeys pressed while another app had focus (the backend already raised the window).
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<GlobalHotkeyEvent>("global-hotkey", (ev) => {
+        if (ev.payload.action === "quickConnect") {
+          setHostsCollapsed(false);
+          // Let the sidebar expand before it takes focus.
+          setTimeout(() => window.dispatchEvent(new CustomEvent("opspad-quick-connect")), 0);
+        } else {
+          window.dispatchEvent(new CustomEvent("opspad-new-terminal"));
+        }
+      });
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!pendingRun || !ctx.connected || ctx.kind !== "ssh" || ctx.hostId !== pendingRun.hostId) return;
+    setRunRequest({ commandId: pendingRun.commandId, nonce: nonce() });
+    setPendingRun(null);
+  }, [pendingRun, ctx]);
+
   return (
     <div className={terminalActive ? "appRoot appRootFocus" : "appRoot"}>
       <header className="topBar" role="banner">
@@ -71,22 +132,19 @@ export function AppShell() {
             collapsed={hostsCollapsed}
             onToggleCollapsed={() => setHostsCollapsed((v) => !v)}
             activeHostId={ctx.kind === "ssh" ? (ctx.hostId ?? null) : null}
-            onConnect={(h) =>
-              setConnectRequest({
-                host: h,
-                nonce:
-                  typeof crypto !== "undefined" && "randomUUID" in crypto
-                    ? crypto.randomUUID()
-                    : String(Date.now()),
-              })
-            }
+            onConnect={(h) => setConnectRequest({ host: h, nonce: nonce() })}
           />
         </aside>
         <main className="centerPane">
           <TerminalWorkspace connectRequest={connectRequest} onContextChange={setCtx} />
         </main>
         <aside className="rightPane">
-          <CommandDock activeEnvironmentTag={ctx.environmentTag} />
+          <CommandDock
+            activeEnvironmentTag={ctx.environmentTag}
+            activeScope={ctx.kind === "ssh" && ctx.hostId ? `ssh:${ctx.hostId}` : "local"}
+            runRequest={runRequest}
+            runbookRequest={runbookRequest}
+          />
         </aside>
       </div>
     </div>
@@ -0,0 +1,84 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+
+import { terminalAttach, terminalDetachedGet, type DetachedSession } from "../lib/opspadApi";
+import { TerminalPane } from "../ui/TerminalPane";
+
+/** Label prefix of windows opened by `terminal_detach`. */
+export const DETACHED_WINDOW_PREFIX = "terminal-";
+
+/** A single session moved out of the main window. Closing this window hands it back. */
+export function DetachedTerminal() {
+  const [session, setSession] = useState<DetachedSession | null>(null);
+  const [statusText, setStatusText] = useState<string | null>(null);
+  const [ended, setEnded] = useState(false);
+  const attachedRef = useRef(false);
+
+  useEffect(() => {
+    void terminalDetachedGet()
+      .then(setSession)
+      .catch((e) => setStatusText(`Couldn't load session: ${String(e)}`));
+  }, []);
+
+  useEffect(() => {
+    if (!session) return;
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<{ sessionId: string }>("terminal:exit", (ev) => {
+        if (ev.payload.sessionId !== session.sessionId) return;
+        setStatusText("Session ended. Close this window.");
+        setEnded(true);
+      });
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [session]);
+
+  const onListening = () => {
+    if (attachedRef.current) return;
+    attachedRef.current = true;
+    void terminalAttach().catch((e) => setStatusText(`Couldn't attach: ${String(e)}`));
+  };
+
+  return (
+    <div className="appRoot">
+      <header className="topBar" role="banner">
+        <div className="brand">
+          <div className="brandMark" aria-hidden="true" />
+          <div className="brandText">
+            <div className="brandName">{session?.title ?? "OpsPad"}</div>
+            <div className="brandTag">Detached terminal · close to return it to the main window</div>
+          </div>
+        </div>
+        <div className="topBarRight">
+          {session ? (
+            <div className="envBadge" title="Session environment">
+              {session.environmentTag}
+            </div>
+          ) : null}
+        </div>
+      </header>
+
+      <main className="centerPane">
+        <section className="panel panelFlush" style={{ gridTemplateRows: "1fr" }}>
+          <div className="terminalFrame">
+            {session ? (
+              <TerminalPane
+                active
+                sessionId={ended ? null : session.sessionId}
+                statusText={statusText}
+                sessionLabel={session.title}
+                themeColor={null}
+                environmentTag={session.environmentTag}
+                onListening={onListening}
+              />
+            ) : (
+              <div className="hint">{statusText ?? "Loading..."}</div>
+            )}
+          </div>
+        </section>
+      </main>
+    </div>
+  );
+}
@@ -1,4 +1,5 @@
-import { useEffect, useMemo, useState } from "react";
+import { useEffect, useMemo, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import {
   DndContext,
   PointerSensor,
@@ -21,15 +22,61 @@ import {
   dockHistoryClear,
   dockHistoryDelete,
   dockHistoryList,
-  dockRunbookGet,
-  dockRunbookSet,
+  RenderedRunbook,
+  Runbook,
+  RunbookDiff,
+  RunbookVersion,
+  runbookVersionsDiff,
+  runbookVersionsList,
+  runbookVersionsRestore,
+  runbookSyncConfigGet,
+  runbookSyncConfigSet,
+  runbookSyncResolve,
+  runbookExport,
+  runbookRender,
+  runbooksCreate,
+  runbooksDelete,
+  runbooksList,
+  runbooksUpdate,
+  Shortcut,
+  dockCommandPreview,
+  shortcutsClear,
+  shortcutsList,
+  shortcutsSet,
 } from "../lib/opspadApi";
 import { ContextMenu, type ContextMenuItem } from "./ContextMenu";
 import { SelectMenu } from "./SelectMenu";
 
-type Mode = "view" | "new" | "edit" | "editRunbook" | "params" | "confirmDelete";
+type Mode = "view" | "new" | "edit" | "editRunbook" | "runbookHistory" | "params" | "confirmDelete";
 type ParamAction = "paste" | "run";
 type DockTab = "commands" | "history";
+type CommandSort = "manual" | "mostUsed";
+
+/** Commands not run for this long are flagged as stale. */
+const STALE_AFTER_SECS = 90 * 24 * 60 * 60;
+
+function isStale(c: DockCommand): boolean {
+  const last = c.lastRunAt ?? null;
+  return last === null || Date.now() / 1000 - last > STALE_AFTER_SECS;
+}
+
+/** "850 ms", "42 s", "3 m 12 s". */
+function formatDuration(ms: number): string {
+  if (ms < 1000) return `${ms} ms`;
+  const secs = Math.round(ms / 1000);
+  if (secs < 60) return `${secs} s`;
+  return `${Math.floor(secs / 60)} m ${secs % 60} s`;
+}
+
+const MODIFIER_KEYS = new Set(["Control", "Alt", "Shift", "Meta"]);
+
+/** Chord for a key event in the backend's normalized form ("Ctrl+Shift+K"), or null for bare modifiers. */
+function chordFromEvent(e: KeyboardEvent): string | null {
+  if (MODIFIER_KEYS.has(e.key)) return null;
+  const key = e.key === " " ? "Space" : e.key.length === 1 ? e.key.toUpperCase() : e.key;
+  const mods = [e.ctrlKey && "Ctrl", e.altKey && "Alt", e.shiftKey && "Shift", e.metaKey && "Meta"].filter(Boolean);
+  return [...mods, key].join("+");
+}
 
 function extractParams(template: string): string[] {
   const re = /\{([a-zA-Z0-9_:-]+)\}/g;
@@ -46,10 +93,6 @@ function extractParams(template: string): string[] {
   return out;
 }
 
-function substituteParams(template: string, values: Record<string, string>): string {
-  return template.replace(/\{([a-zA-Z0-9_:-]+)\}/g, (_, name: string) => values[name] ?? "");
-}
-
 function loadParamDefaults(commandId: string): Record<string, string> {
   try {
     const raw = localStorage.getItem(`opspad.cmdparams.${commandId}`);
@@ -69,9 +112,32 @@ function saveParamDefaults(commandId: string, values: Record<string, string>) {
   }
 }
 
-export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: string }) {
+export function CommandDock({
+  activeEnvironmentTag,
+  activeScope,
+  runRequest,
+  runbookRequest,
+}: {
+  activeEnvironmentTag: string;
+  activeScope: string;
+  /** Run a command from outside the dock (CLI), through the usual params/confirmation flow. */
+  runRequest?: { commandId: string; nonce: string } | null;
+  /** Show a runbook (from an `opspad://runbook/<id>` link). */
+  runbookRequest?: { runbookId: string; nonce: string } | null;
+}) {
   const [commands, setCommands] = useState<DockCommand[]>([]);
-  const [runbook, setRunbook] = useState<string>("");
+  const [runbooks, setRunbooks] = useState<Runbook[]>([]);
+  const [runbookId, setRunbookId] = useState<string | null>(null);
+  const [runbookDraft, setRunbookDraft] = useState<{ id?: string; title: string; markdown: string }>({
+    title: "",
+    markdown: "",
+  });
+  const runbook = runbooks.find((r) => r.id === runbookId) ?? runbooks[0] ?? null;
+  const [runbookValues, setRunbookValues] = useState<Record<string, string>>({});
+  const [renderedRunbook, setRenderedRunbook] = useState<RenderedRunbook | null>(null);
+  const [runbookRenderError, setRunbookRenderError] = useState<string | null>(null);
+  const [runbookVersions, setRunbookVersions] = useState<RunbookVersion[]>([]);
+  const [runbookDiff, setRunbookDiff] = useState<{ versionId: string; diff: RunbookDiff } | null>(null);
   const [search, setSearch] = useState("");
   const [dockTab, setDockTab] = useState<DockTab>("commands");
   const [mode, setMode] = useState<Mode>("view");
@@ -110,29 +176,96 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     if (mode !== "view") setManageMode(false);
   }, [mode]);
 
+  const [actionShortcuts, setActionShortcuts] = useState<Shortcut[]>([]);
+  const searchRef = useRef<HTMLInputElement | null>(null);
+
   const refresh = async () => {
-    const [cmds, rb] = await Promise.all([dockCommandsList(), dockRunbookGet()]);
+    const [cmds, rbs, shortcuts] = await Promise.all([dockCommandsList(), runbooksList(), shortcutsList()]);
     setCommands(cmds);
-    setRunbook(rb);
+    setRunbooks(rbs);
+    setActionShortcuts(shortcuts.filter((s) => s.target.kind === "action"));
+  };
+
+  // The backend renders the command and decides the environment policy, so what is shown here is
+  // exactly what gets written; it still enforces the policy on write. Lint findings are shown in
+  // the prompt. Returns null when the user cancels (or the policy blocks it).
+  const confirmCommand = async (
+    cmd: DockCommand,
+    action: ParamAction,
+    values: Record<string, string>,
+  ): Promise<{ text: string; confirmation?: string; commandHash: string } | null> => {
+    let preview;
+    try {
+      preview = await dockCommandPreview(cmd.id, values, activeScope);
+    } catch (e) {
+      setError(String(e));
+      return null;
+    }
+    const { command: text, commandHash, findings, decision } = preview;
+    const lintNote = findings.length
+      ? `\n\n${findings.map((f) => `[${f.severity}] ${f.name}: ${f.message}`).join("\n")}`
+      : "";
+
+    const env = decision.environment || activeEnvironmentTag.toUpperCase();
+    if (decision.policy === "block") {
+      setError(`${env} policy blocks destructive commands.`);
+      return null;
+    }
+    if (decision.policy === "typedName") {
+      const typed = window.prompt(`${env}: type "${env}" to ${action} "${cmd.title}".\n\n${text}${lintNote}`);
+      if (typed === null) return null;
+      return { text, confirmation: typed, commandHash };
+    }
+    if (decision.policy === "reason") {
+      const reason = window.prompt(`${env}: why are you running "${cmd.title}"? (kept in history)\n\n${text}${lintNote}`);
+      if (reason === null) return null;
+      return { text, confirmation: reason, commandHash };
+    }
+    if (decision.policy === "confirm") {
+      if (!window.confirm(`${env}: ${action === "paste" ? "Paste" : "Run"} "${cmd.title}" in the active terminal?\n\n${text}${lintNote}`)) {
+        return null;
+      }
+      return { text, confirmation: "confirmed", commandHash };
+    }
+    if (action === "paste" && !findings.length) return { text, commandHash };
+    const inProd = env === "PROD";
+    if (cmd.requiresConfirm || inProd || findings.length) {
+      const msg = cmd.requiresConfirm
+        ? inProd
+          ? "PROD: Run this command in the active terminal? (Confirm)"
+          : "Run this command in the active terminal? (Confirm)"
+        : inProd
+          ? "PROD: Run this command in the active terminal?"
+          : `${action === "paste" ? "Paste" : "Run"} this command in the active terminal?`;
+      if (!window.confirm(`${msg}\n\n${text}${lintNote}`)) return null;
+    }
+    return { text, commandHash };
   };
 
   useEffect(() => {
     void refresh().catch((e) => setError(String(e)));
   }, []);
 
+  const [commandSort, setCommandSort] = useState<CommandSort>("manual");
+
   const filtered = useMemo(() => {
     const q = search.trim().toLowerCase();
-    if (!q) return commands;
-    return commands.filter((c) => {
-      const hay = `${c.title} ${c.command}`.toLowerCase();
-      return hay.includes(q);
-    });
-  }, [commands, search]);
+    const matched = q
+      ? commands.filter((c) => {
+          const hay = `${c.title} ${c.command}`.toLowerCase();
+          return hay.includes(q);
+        })
+      : commands;
+    if (commandSort === "manual") return matched;
+    return [...matched].sort(
+      (a, b) => (b.runCount ?? 0) - (a.runCount ?? 0) || (b.lastRunAt ?? 0) - (a.lastRunAt ?? 0),
+    );
+  }, [commands, search, commandSort]);
 
   const [history, setHistory] = useState<DockHistoryItem[]>([]);
   const refreshHistory = async () => {
     const h = await dockHistoryList(200);
-    setHistory(h);
+    setHistory(h.items);
   };
 
   useEffect(() => {
@@ -149,6 +282,26 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     return () => window.removeEventListener("opspad-history-updated", onUpdated as EventListener);
   }, [dockTab]);
 
+  // A command's duration lands in history once the shell reports it finished.
+  useEffect(() => {
+    if (dockTab !== "history") return;
+    let unlisten: (() => void) | null = null;
+    void listen("command:finished", () => void refreshHistory().catch(() => {}))
+      .then((u) => (unlisten = u))
+      .catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [dockTab]);
+
+  // Run counts change on every CommandDock write; keep the "most used" order current.
+  useEffect(() => {
+    if (commandSort !== "mostUsed") return;
+    const onUpdated = () => void dockCommandsList().then(setCommands).catch(() => {});
+    window.addEventListener("opspad-history-updated", onUpdated as EventListener);
+    return () => window.removeEventListener("opspad-history-updated", onUpdated as EventListener);
+  }, [commandSort]);
+
   const paste = (
     text: string,
     opts?: {
@@ -156,6 +309,8 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
       dockCommandId?: string;
       dockCommandTitle?: string;
       dockCommandTemplate?: string;
+      confirmation?: string;
+      confirmedHash?: string;
     },
   ) => {
     window.dispatchEvent(
@@ -166,29 +321,25 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
           dockCommandId: opts?.dockCommandId,
           dockCommandTitle: opts?.dockCommandTitle,
           dockCommandTemplate: opts?.dockCommandTemplate,
+          confirmation: opts?.confirmation,
+          confirmedHash: opts?.confirmedHash,
         },
       }),
     );
   };
 
-  const run = (cmd: DockCommand) => {
-    const inProd = activeEnvironmentTag.toUpperCase() === "PROD";
-    if (cmd.requiresConfirm || inProd) {
-      const msg = cmd.requiresConfirm
-        ? inProd
-          ? "PROD: Run this command in the active terminal? (Confirm)"
-          : "Run this command in the active terminal? (Confirm)"
-        : "PROD: Run this command in the active terminal?";
-      const ok = window.confirm(msg);
-      if (!ok) return;
-    }
+  const run = async (cmd: DockCommand) => {
+    const confirmed = await confirmCommand(cmd, "run", {});
+    if (!confirmed) return;
     window.dispatchEvent(
       new CustomEvent("opspad-terminal-run", {
         detail: {
-          text: cmd.command,
+          text: confirmed.text,
           dockCommandId: cmd.id,
           dockCommandTitle: cmd.title,
           dockCommandTemplate: cmd.command,
+          confirmation: confirmed.confirmation,
+          confirmedHash: confirmed.commandHash,
         },
       }),
     );
@@ -201,16 +352,22 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     const params = extractParams(cmd.command);
     if (params.length === 0) {
       if (action === "paste") {
-        paste(cmd.command, {
-          origin: "commanddock",
-          dockCommandId: cmd.id,
-          dockCommandTitle: cmd.title,
-          dockCommandTemplate: cmd.command,
-        });
-        setPulseCmdId(cmd.id);
-        window.dispatchEvent(new CustomEvent("opspad-terminal-flash"));
+        void (async () => {
+          const confirmed = await confirmCommand(cmd, "paste", {});
+          if (!confirmed) return;
+          paste(confirmed.text, {
+            origin: "commanddock",
+            dockCommandId: cmd.id,
+            dockCommandTitle: cmd.title,
+            dockCommandTemplate: cmd.command,
+            confirmation: confirmed.confirmation,
+            confirmedHash: confirmed.commandHash,
+          });
+          setPulseCmdId(cmd.id);
+          window.dispatchEvent(new CustomEvent("opspad-terminal-flash"));
+        })();
       }
-      else run(cmd);
+      else void run(cmd);
       return;
     }
 
@@ -223,6 +380,59 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     setMode("params");
   };
 
+  useEffect(() => {
+    if (!runRequest) return;
+    const cmd = commands.find((c) => c.id === runRequest.commandId);
+    if (cmd) runOrParam(cmd, "run");
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [runRequest?.nonce]);
+
+  useEffect(() => {
+    if (!runbookRequest) return;
+    setDockTab("commands");
+    setRunbookId(runbookRequest.runbookId);
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [runbookRequest?.nonce]);
+
+  // Shortcuts are stored backend-side; dock command chords come back on each DockCommand.
+  useEffect(() => {
+    const onKeyDown = (e: KeyboardEvent) => {
+      if (mode !== "view") return;
+      const chord = chordFromEvent(e);
+      if (!chord) return;
+      const cmd = commands.find((c) => c.shortcut === chord);
+      const action = actionShortcuts.find((s) => s.chord === chord)?.target;
+      if (!cmd && !action) return;
+      e.preventDefault();
+      e.stopPropagation();
+      if (cmd) {
+        runOrParam(cmd, "run");
+        return;
+      }
+      if (action?.kind !== "action") return;
+      if (action.action === "dock.search") searchRef.current?.focus();
+      else if (action.action === "dock.newCommand") openNew();
+      else if (action.action === "dock.history") setDockTab("history");
+      else if (action.action === "dock.editRunbook") openRunbookEditor(runbook);
+    };
+    // Capture phase so chords win over the focused terminal.
+    window.addEventListener("keydown", onKeyDown, true);
+    return () => window.removeEventListener("keydown", onKeyDown, true);
+  });
+
+  const editShortcut = async (cmd: DockCommand) => {
+    const chord = window.prompt(`Shortcut for "${cmd.title}" (e.g. Ctrl+Shift+K; empty to clear)`, cmd.shortcut ?? "");
+    if (chord === null) return;
+    setError(null);
+    try {
+      if (chord.trim()) await shortcutsSet(chord, { kind: "dockCommand", id: cmd.id });
+      else await shortcutsClear({ kind: "dockCommand", id: cmd.id });
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
   const openNew = () => {
     setError(null);
     setDraft({ title: "", command: "", requiresConfirm: false });
@@ -295,11 +505,27 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     }
   };
 
+  /** Edit `rb`, or start a new runbook when null. */
+  const openRunbookEditor = (rb: Runbook | null) => {
+    setError(null);
+    setRunbookDraft(rb ? { id: rb.id, title: rb.title, markdown: rb.markdown } : { title: "", markdown: "" });
+    setMode("editRunbook");
+  };
+
   const saveRunbook = async () => {
     setError(null);
+    const title = runbookDraft.title.trim();
+    if (!title) {
+      setError("Title is required.");
+      return;
+    }
     setSaving(true);
     try {
-      await dockRunbookSet(runbook);
+      const existing = runbooks.find((r) => r.id === runbookDraft.id);
+      const saved = existing
+        ? await runbooksUpdate({ ...existing, title, markdown: runbookDraft.markdown })
+        : await runbooksCreate({ title, markdown: runbookDraft.markdown });
+      setRunbookId(saved.id);
       await refresh();
       setMode("view");
     } catch (e) {
@@ -309,10 +535,135 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
     }
   };
 
+  const openRunbookHistory = async (rb: Runbook) => {
+    setError(null);
+    setRunbookDiff(null);
+    try {
+      setRunbookVersions(await runbookVersionsList(rb.id));
+      setMode("runbookHistory");
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const showRunbookDiff = async (versionId: string) => {
+    setError(null);
+    try {
+      setRunbookDiff({ versionId, diff: await runbookVersionsDiff(versionId) });
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const restoreRunbookVersion = async (v: RunbookVersion) => {
+    if (!window.confirm(`Restore the version from ${new Date(v.createdAt).toLocaleString()}?`)) return;
+    setError(null);
+    try {
+      await runbookVersionsRestore(v.id);
+      await refresh();
+      setMode("view");
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  useEffect(() => setRunbookValues({}), [runbook?.id]);
+
+  // Placeholders are filled in by the backend with the same rules as dock commands.
+  useEffect(() => {
+    if (!runbook) {
+      setRenderedRunbook(null);
+      return;
+    }
+    let cancelled = false;
+    const t = window.setTimeout(() => {
+      runbookRender(runbook.id, runbookValues)
+        .then((r) => {
+          if (cancelled) return;
+          setRenderedRunbook(r);
+          setRunbookRenderError(null);
+        })
+        .catch((e) => {
+          if (!cancelled) setRunbookRenderError(String(e));
+        });
+    }, 150);
+    return () => {
+      cancelled = true;
+      window.clearTimeout(t);
+    };
+  }, [runbook?.id, runbook?.markdown, runbookValues]);
+
+  // Folder sync and team subscriptions refresh in the backend; reload when they change things.
+  useEffect(() => {
+    const unlisteners: (() => void)[] = [];
+    void (async () => {
+      unlisteners.push(await listen("runbooks:synced", () => void refresh().catch(() => {})));
+      unlisteners.push(await listen("subscriptions:refreshed", () => void refresh().catch(() => {})));
+    })().catch(() => {});
+    return () => {
+      unlisteners.forEach((u) => u());
+    };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  const configureRunbookSync = async () => {
+    setError(null);
+    try {
+      const current = await runbookSyncConfigGet();
+      const path = window.prompt(
+        "Sync runbooks with this folder of .md files (empty to turn sync off):",
+        current.enabled ? (current.path ?? "") : "",
+      );
+      if (path === null) return;
+      const report = await runbookSyncConfigSet({ enabled: path.trim().length > 0, path: path.trim() || current.path });
+      if (report.conflicts.length) setError(`Sync conflicts: ${report.conflicts.join(", ")}`);
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const resolveRunbookConflict = async (rb: Runbook) => {
+    const keepApp = window.confirm(
+      `"${rb.title}" changed both in OpsPad and in ${rb.fileName ?? "its file"}.\n\n` +
+        "OK keeps the OpsPad version (overwrites the file). Cancel keeps the file.",
+    );
+    setError(null);
+    try {
+      await runbookSyncResolve(rb.id, keepApp ? "app" : "file");
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const exportRunbook = async (rb: Runbook) => {
+    const path = window.prompt(`Export "${rb.title}" to this full file path (ending .html or .pdf):`);
+    if (!path || !path.trim()) return;
+    setError(null);
+    try {
+      await runbookExport(rb.id, path.trim().toLowerCase().endsWith(".pdf") ? "pdf" : "html", path.trim());
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const deleteRunbook = async (rb: Runbook) => {
+    if (!window.confirm(`Delete runbook "${rb.title}"?`)) return;
+    setError(null);
+    try {
+      await runbooksDelete(rb.id);
+      setRunbookId(null);
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
   const showModal = mode !== "view";
 
   // In delete/manage mode we disable drag listeners so clicks on the delete button are reliable.
-  const canReorder = search.trim().length === 0 && mode === "view" && !manageMode;
+  const canReorder = search.trim().length === 0 && commandSort === "manual" && mode === "view" && !manageMode;
   const sensors = useSensors(useSensor(PointerSensor, { activationConstraint: { distance: 6 } }));
 
   const ctxItems: ContextMenuItem[] = useMemo(() => {
@@ -323,9 +674,11 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
       { label: "Paste", onClick: () => runOrParam(cmd, "paste") },
       { kind: "sep" },
       { label: "Copy", onClick: () => void navigator.clipboard?.writeText(cmd.command).catch(() => {}) },
-      { label: "Edit", onClick: () => openEdit(cmd) },
-      { kind: "sep" },
-      { label: "Delete", onClick: () => void deleteCmd(cmd) },
+      ...(cmd.subscriptionId ? [] : [{ label: "Edit", onClick: () => openEdit(cmd) }]),
+      { label: cmd.shortcut ? `Shortcut (${cmd.shortcut})...` : "Set shortcut...", onClick: () => void editShortcut(cmd) },
+      ...(cmd.subscriptionId
+        ? []
+        : ([{ kind: "sep" }, { label: "Delete", onClick: () => void deleteCmd(cmd) }] as ContextMenuItem[])),
     ];
     return items;
     // eslint-disable-next-line react-hooks/exhaustive-deps
@@ -359,6 +712,17 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
           <div className="dockRowTitle">
             {c.title}
             {c.requiresConfirm ? <span className="pill pillWarn">Confirm</span> : null}
+            {c.shortcut ? <span className="pill">{c.shortcut}</span> : null}
+            {c.subscriptionId ? (
+              <span className="pill" title="From a team subscription (read-only)">
+                Team
+              </span>
+            ) : null}
+            {isStale(c) ? (
+              <span className="pill" title={c.lastRunAt ? "Not run in the last 90 days" : "Never run"}>
+                Stale
+              </span>
+            ) : null}
           </div>
           <div className="dockRowCmd">{c.command}</div>
         </div>
@@ -383,7 +747,7 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
           >
             Run
           </button>
-          {manageMode ? (
+          {manageMode && !c.subscriptionId ? (
             <button
               className="itemX itemXDanger"
               type="button"
@@ -430,6 +794,7 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
           <span className="fieldLabel">Search</span>
           <input
             className="textInput"
+            ref={searchRef}
             placeholder={dockTab === "history" ? "search history..." : "kubectl, nginx, deploy..."}
             value={search}
             onChange={(e) => setSearch(e.target.value)}
@@ -439,20 +804,91 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
         {dockTab === "commands" ? (
           <div className="dockCard">
             <div className="dockCardTitleRow">
-              <div className="dockCardTitle">Runbook</div>
-              <button className="miniButton miniButtonGhost" type="button" onClick={() => setMode("editRunbook")}>
-                Edit
-              </button>
+              {runbooks.length > 1 ? (
+                <SelectMenu
+                  label="Runbook"
+                  value={runbook?.id ?? ""}
+                  options={runbooks.map((r) => ({ value: r.id, label: r.title }))}
+                  onChange={setRunbookId}
+                />
+              ) : (
+                <div className="dockCardTitle">{runbook?.title ?? "Runbook"}</div>
+              )}
+              <div className="panelHeaderActions">
+                <button className="miniButton miniButtonGhost" type="button" onClick={() => openRunbookEditor(null)}>
+                  New
+                </button>
+                {runbook && !runbook.subscriptionId ? (
+                  <button className="miniButton miniButtonGhost" type="button" onClick={() => openRunbookEditor(runbook)}>
+                    Edit
+                  </button>
+                ) : null}
+                {runbook?.subscriptionId ? (
+                  <span className="pill" title="From a team subscription (read-only)">
+                    Team
+                  </span>
+                ) : null}
+                {runbook?.syncConflict ? (
+                  <button className="miniButton" type="button" onClick={() => void resolveRunbookConflict(runbook)}>
+                    Resolve conflict
+                  </button>
+                ) : null}
+                <button
+                  className="miniButton miniButtonGhost"
+                  type="button"
+                  onClick={() => void configureRunbookSync()}
+                  title={runbook?.fileName ? `Synced with ${runbook.fileName}` : "Sync runbooks with a folder"}
+                >
+                  Folder
+                </button>
+                {runbook ? (
+                  <button className="miniButton miniButtonGhost" type="button" onClick={() => void openRunbookHistory(runbook)}>
+                    History
+                  </button>
+                ) : null}
+                {runbook ? (
+                  <button className="miniButton miniButtonGhost" type="button" onClick={() => void exportRunbook(runbook)}>
+                    Export
+                  </button>
+                ) : null}
+                {runbook && runbooks.length > 1 && !runbook.subscriptionId ? (
+                  <button className="miniButton miniButtonGhost" type="button" onClick={() => void deleteRunbook(runbook)}>
+                    Delete
+                  </button>
+                ) : null}
+              </div>
             </div>
+            {renderedRunbook?.placeholders.map((p) => (
+              <label key={p} className="field">
+                <span className="fieldLabel">{p}</span>
+                <input
+                  className="textInput"
+                  value={runbookValues[p] ?? ""}
+                  onChange={(e) => setRunbookValues((prev) => ({ ...prev, [p]: e.target.value }))}
+                  placeholder={`{${p}}`}
+                />
+              </label>
+            ))}
+            {runbookRenderError ? <div className="formError">{runbookRenderError}</div> : null}
             <div className="dockMarkdown">
-              <pre>{runbook}</pre>
+              <pre>{renderedRunbook?.markdown ?? runbook?.markdown ?? ""}</pre>
             </div>
           </div>
         ) : null}
 
         {dockTab === "commands" ? (
           <div className="dockCard">
-            <div className="dockCardTitle">Commands</div>
+            <div className="dockCardTitleRow">
+              <div className="dockCardTitle">Commands</div>
+              <button
+                className={commandSort === "mostUsed" ? "miniButton miniButtonActive" : "miniButton miniButtonGhost"}
+                type="button"
+                onClick={() => setCommandSort((s) => (s === "mostUsed" ? "manual" : "mostUsed"))}
+                title="Sort by run count"
+              >
+                Most used
+              </button>
+            </div>
             <div className="dockList">
               <DndContext
                 sensors={sensors}
@@ -505,6 +941,17 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                     <div className="dockRowTitle">
                       <span className="pill pillLocal">{h.environmentTag}</span>
                       <span style={{ opacity: 0.9 }}>Ran</span>
+                      {h.policy && h.policy !== "allow" ? (
+                        <span style={{ opacity: 0.7 }} title={h.policyReason ?? undefined}>
+                          · {h.policyReason ? `reason: ${h.policyReason}` : h.policy === "typedName" ? "name typed" : "confirmed"}
+                        </span>
+                      ) : null}
+                      {h.durationMs != null ? (
+                        <span style={{ opacity: 0.7 }}>
+                          · {formatDuration(h.durationMs)}
+                          {h.exitStatus ? ` · exit ${h.exitStatus}` : ""}
+                        </span>
+                      ) : null}
                     </div>
                     <div className="dockRowCmd">{h.commandText}</div>
                   </div>
@@ -585,8 +1032,12 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                   ? "Fill parameters"
                   : mode === "confirmDelete"
                     ? "Delete command"
+                  : mode === "runbookHistory"
+                    ? "Runbook history"
                   : mode === "editRunbook"
-                    ? "Edit runbook"
+                    ? runbookDraft.id
+                      ? "Edit runbook"
+                      : "New runbook"
                     : mode === "edit"
                       ? "Edit command"
                       : "New command"}
@@ -627,16 +1078,59 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                     </label>
                   ))}
                 </>
+              ) : mode === "runbookHistory" ? (
+                <>
+                  {runbookVersions.length === 0 ? (
+                    <div className="hint" style={{ marginTop: 0 }}>
+                      No earlier versions yet. One is kept each time the runbook is saved.
+                    </div>
+                  ) : null}
+                  {runbookVersions.map((v) => (
+                    <div key={v.id} className="dockCardTitleRow">
+                      <div className="hint" style={{ marginTop: 0 }}>
+                        {new Date(v.createdAt).toLocaleString()} - {v.title}
+                      </div>
+                      <div className="panelHeaderActions">
+                        <button className="miniButton miniButtonGhost" type="button" onClick={() => void showRunbookDiff(v.id)}>
+                          Diff
+                        </button>
+                        <button className="miniButton miniButtonGhost" type="button" onClick={() => void restoreRunbookVersion(v)}>
+                          Restore
+                        </button>
+                      </div>
+                    </div>
+                  ))}
+                  {runbookDiff ? (
+                    <div className="dockMarkdown">
+                      <div className="hint" style={{ marginTop: 0 }}>
+                        vs current: +{runbookDiff.diff.inserted} -{runbookDiff.diff.deleted}
+                        {runbookDiff.diff.titleChanged ? " (title changed)" : ""}
+                      </div>
+                      <pre>{runbookDiff.diff.unified || "No changes."}</pre>
+                    </div>
+                  ) : null}
+                </>
               ) : mode === "editRunbook" ? (
-                <label className="field">
-                  <span className="fieldLabel">Markdown</span>
-                  <textarea
-                    className="textInput"
-                    style={{ height: 240, paddingTop: 10, paddingBottom: 10, resize: "vertical" }}
-                    value={runbook}
-                    onChange={(e) => setRunbook(e.target.value)}
-                  />
-                </label>
+                <>
+                  <label className="field">
+                    <span className="fieldLabel">Title</span>
+                    <input
+                      className="textInput"
+                      value={runbookDraft.title}
+                      onChange={(e) => setRunbookDraft((p) => ({ ...p, title: e.target.value }))}
+                      placeholder="Failover procedure"
+                    />
+                  </label>
+                  <label className="field">
+                    <span className="fieldLabel">Markdown</span>
+                    <textarea
+                      className="textInput"
+                      style={{ height: 240, paddingTop: 10, paddingBottom: 10, resize: "vertical" }}
+                      value={runbookDraft.markdown}
+                      onChange={(e) => setRunbookDraft((p) => ({ ...p, markdown: e.target.value }))}
+                    />
+                  </label>
+                </>
               ) : (
                 <>
                   <label className="field">
@@ -679,6 +1173,7 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                 <button
                   className="miniButton"
                   type="button"
+                  hidden={mode === "runbookHistory"}
                   disabled={saving}
                   onClick={() =>
                     void (mode === "params"
@@ -693,18 +1188,10 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                             }
                           }
                           saveParamDefaults(paramTarget.cmd.id, paramValues);
-                          const finalCmd = substituteParams(paramTarget.cmd.command, paramValues);
+                          const confirmed = await confirmCommand(paramTarget.cmd, paramTarget.action, paramValues);
+                          if (!confirmed) return;
+                          const finalCmd = confirmed.text;
                           if (paramTarget.action === "run") {
-                            const inProd = activeEnvironmentTag.toUpperCase() === "PROD";
-                            if (paramTarget.cmd.requiresConfirm || inProd) {
-                              const msg = paramTarget.cmd.requiresConfirm
-                                ? inProd
-                                  ? "PROD: Run this command in the active terminal? (Confirm)"
-                                  : "Run this command in the active terminal? (Confirm)"
-                                : "PROD: Run this command in the active terminal?";
-                              const ok = window.confirm(msg);
-                              if (!ok) return;
-                            }
                             window.dispatchEvent(
                               new CustomEvent("opspad-terminal-run", {
                                 detail: {
@@ -712,6 +1199,8 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                                   dockCommandId: paramTarget.cmd.id,
                                   dockCommandTitle: paramTarget.cmd.title,
                                   dockCommandTemplate: paramTarget.cmd.command,
+                                  confirmation: confirmed.confirmation,
+                                  confirmedHash: confirmed.commandHash,
                                 },
                               }),
                             );
@@ -721,6 +1210,8 @@ export function CommandDock({ activeEnvironmentTag }: { activeEnvironmentTag: st
                               dockCommandId: paramTarget.cmd.id,
                               dockCommandTitle: paramTarget.cmd.title,
                               dockCommandTemplate: paramTarget.cmd.command,
+                              confirmation: confirmed.confirmation,
+                              confirmedHash: confirmed.commandHash,
                             });
                           }
                           setParamTarget(null);
@@ -1,4 +1,5 @@
-import { useEffect, useMemo, useState } from "react";
+import { useEffect, useMemo, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import {
   DndContext,
   PointerSensor,
@@ -10,14 +11,26 @@ import {
 import { SortableContext, useSortable, verticalListSortingStrategy, arrayMove } from "@dnd-kit/sortable";
 import { CSS } from "@dnd-kit/utilities";
 import {
+  hostStatusList,
+  httpCheckStatusList,
   hostsCreate,
   hostsDelete,
   hostsReorder,
+  type HostSort,
   hostsList,
+  hostsSetCredentials,
+  hostsSetPinned,
   hostsUpdate,
   vaultDeleteSecret,
   vaultGetSecret,
+  hostPreflight,
+  vaultHasSecret,
   vaultSetSecret,
+  type HostStatus,
+  type HttpCheckStatus,
+  type ConnectionType,
+  type DnsExpansion,
+  type DnsSelection,
 } from "../lib/opspadApi";
 import { ContextMenu, type ContextMenuItem } from "./ContextMenu";
 import { SelectMenu } from "./SelectMenu";
@@ -31,6 +44,17 @@ export type HostListItem = {
   environmentTag: string;
   identityFile?: string | null;
   color?: string | null;
+  /** Set for hosts from a team subscription; those are read-only. */
+  subscriptionId?: string | null;
+  passwordVaultKey?: string | null;
+  passphraseVaultKey?: string | null;
+  pinned?: boolean;
+  connectionType?: ConnectionType;
+  identityAgent?: string | null;
+  securityKeyProvider?: string | null;
+  dnsExpansion?: DnsExpansion;
+  dnsSelection?: DnsSelection;
+  effectiveColor?: string | null;
 };
 
 function envClass(env: string) {
@@ -59,6 +83,19 @@ export function HostsSidebar({
 }) {
   const [hosts, setHosts] = useState<HostListItem[]>([]);
   const [filter, setFilter] = useState("");
+  const [sort, setSort] = useState<HostSort>(() => (localStorage.getItem("opspad.hostSort") as HostSort | null) ?? "manual");
+  const sortRef = useRef(sort);
+  const filterRef = useRef<HTMLInputElement | null>(null);
+
+  // Quick connect (global hotkey): jump to the filter; Enter connects to the first match.
+  useEffect(() => {
+    const onQuickConnect = () => {
+      filterRef.current?.focus();
+      filterRef.current?.select();
+    };
+    window.addEventListener("opspad-quick-connect", onQuickConnect);
+    return () => window.removeEventListener("opspad-quick-connect", onQuickConnect);
+  }, []);
   const [manageMode, setManageMode] = useState(false);
   const [ctxMenu, setCtxMenu] = useState<{ open: boolean; x: number; y: number; host: HostListItem | null }>({
     open: false,
@@ -74,6 +111,8 @@ export function HostsSidebar({
   const [hasPassphrase, setHasPassphrase] = useState(false);
   const [passphrase, setPassphrase] = useState("");
   const [passphraseRevealed, setPassphraseRevealed] = useState(false);
+  const [hasPassword, setHasPassword] = useState(false);
+  const [password, setPassword] = useState("");
   const [form, setForm] = useState({
     label: "",
     hostname: "",
@@ -84,7 +123,11 @@ export function HostsSidebar({
     color: "",
   });
 
-  const passphraseKey = editingId ? `host:${editingId}:ssh_key_passphrase` : null;
+  const editingHost = editingId ? hosts.find((h) => h.id === editingId) ?? null : null;
+  const passphraseKey = editingId
+    ? editingHost?.passphraseVaultKey ?? `host:${editingId}:ssh_key_passphrase`
+    : null;
+  const passwordKey = editingId ? editingHost?.passwordVaultKey ?? `host:${editingId}:ssh_password` : null;
 
   // On edit modal open: check whether a passphrase exists in keyring (do not reveal it).
   useEffect(() => {
@@ -94,31 +137,103 @@ export function HostsSidebar({
 
     let cancelled = false;
     setHasPassphrase(false);
-    void vaultGetSecret(passphraseKey)
-      .then((s) => {
+    void vaultHasSecret(passphraseKey)
+      .then((has) => {
         if (cancelled) return;
-        setHasPassphrase(s != null && s.length > 0);
+        setHasPassphrase(has);
       })
       .catch(() => {
         if (cancelled) return;
         setHasPassphrase(false);
       });
+    setHasPassword(false);
+    setPassword("");
+    if (passwordKey && editingHost?.passwordVaultKey) {
+      void vaultHasSecret(passwordKey)
+        .then((has) => {
+          if (cancelled) return;
+          setHasPassword(has);
+        })
+        .catch(() => {});
+    }
 
     return () => {
       cancelled = true;
     };
-  }, [modalOpen, modalMode, passphraseKey]);
+  }, [modalOpen, modalMode, passphraseKey, passwordKey, editingHost?.passwordVaultKey]);
 
   const refresh = async () => {
-    const h = await hostsList();
+    const h = await hostsList(sortRef.current === "manual" ? undefined : { sort: sortRef.current });
     setHosts(h);
   };
 
+  const SORT_LABELS: Record<HostSort, string> = {
+    manual: "Manual order",
+    reachableFirst: "Reachable first",
+    byStatus: "Grouped by status",
+  };
+  const cycleSort = () => {
+    const order: HostSort[] = ["manual", "reachableFirst", "byStatus"];
+    const next = order[(order.indexOf(sort) + 1) % order.length];
+    sortRef.current = next;
+    setSort(next);
+    localStorage.setItem("opspad.hostSort", next);
+    void refresh().catch(() => {});
+  };
+
+  // Health badges from the host monitor (only populated while it's enabled).
+  const [statuses, setStatuses] = useState<Record<string, HostStatus>>({});
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    void hostStatusList()
+      .then((list) => setStatuses(Object.fromEntries(list.map((s) => [s.hostId, s]))))
+      .catch(() => {});
+    void (async () => {
+      unlisten = await listen<HostStatus>("host:status", (ev) => {
+        setStatuses((prev) => ({ ...prev, [ev.payload.hostId]: ev.payload }));
+        // A status change can move the host when sorting by status.
+        if (sortRef.current !== "manual") void refresh().catch(() => {});
+      });
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // HTTP checks attached to hosts; a host's badge shows its worst check.
+  const [httpStatuses, setHttpStatuses] = useState<Record<string, HttpCheckStatus>>({});
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    void httpCheckStatusList()
+      .then((list) => setHttpStatuses(Object.fromEntries(list.map((s) => [s.checkId, s]))))
+      .catch(() => {});
+    void (async () => {
+      unlisten = await listen<HttpCheckStatus>("http:status", (ev) =>
+        setHttpStatuses((prev) => ({ ...prev, [ev.payload.checkId]: ev.payload })),
+      );
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Team subscriptions refresh in the background and can add, change, or remove hosts.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      unlisten = await listen("subscriptions:refreshed", () => void refresh().catch(() => {}));
+    })().catch(() => {});
+    return () => {
+      if (unlisten) unlisten();
+    };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   useEffect(() => {
     let cancelled = false;
     (async () => {
       try {
-        const h = await hostsList();
+        const h = await hostsList(sortRef.current === "manual" ? undefined : { sort: sortRef.current });
         if (!cancelled) setHosts(h);
       } catch {
         if (!cancelled) setHosts([]);
@@ -248,7 +363,13 @@ export function HostsSidebar({
     const ok = window.confirm(`Delete host "${h.label}" (${h.username}@${h.hostname})?`);
     if (!ok) return;
     try {
-      await hostsDelete(h.id);
+      try {
+        await hostsDelete(h.id);
+      } catch (e) {
+        if (!String(e).includes("open terminal session")) throw e;
+        if (!window.confirm(`${String(e)}.\n\nClose them and delete "${h.label}" anyway?`)) return;
+        await hostsDelete(h.id, { force: true });
+      }
       // Best-effort cleanup of any host secrets.
       void vaultDeleteSecret(`host:${h.id}:ssh_key_passphrase`).catch(() => {});
       await refresh();
@@ -257,6 +378,16 @@ export function HostsSidebar({
     }
   };
 
+  const runPreflight = async (h: HostListItem) => {
+    try {
+      const result = await hostPreflight(h.id);
+      const lines = result.checks.map((c) => `${c.status.toUpperCase()}  ${c.name}: ${c.detail}`);
+      window.alert(`${result.ok ? "Ready to connect" : "Connection problems"}: ${result.target}\n\n${lines.join("\n")}`);
+    } catch (e) {
+      window.alert(`Pre-flight failed: ${String(e)}`);
+    }
+  };
+
   const openAddModal = () => {
     setModalError(null);
     setModalMode("add");
@@ -276,7 +407,7 @@ export function HostsSidebar({
     setModalOpen(true);
   };
 
-  const canReorder = !collapsed && filter.trim().length === 0;
+  const canReorder = !collapsed && filter.trim().length === 0 && sort === "manual";
   const sensors = useSensors(useSensor(PointerSensor, { activationConstraint: { distance: 6 } }));
 
   const palette = [
@@ -317,6 +448,15 @@ export function HostsSidebar({
     }
   };
 
+  const toggleHostPinned = async (h: HostListItem) => {
+    try {
+      await hostsSetPinned(h.id, !h.pinned);
+      await refresh();
+    } catch (e) {
+      window.alert(`Failed to update host: ${String(e)}`);
+    }
+  };
+
   const openEditModal = (h: HostListItem) => {
     setModalError(null);
     setModalMode("edit");
@@ -339,9 +479,19 @@ export function HostsSidebar({
   const ctxItems: ContextMenuItem[] = useMemo(() => {
     const h = ctxMenu.host;
     if (!h) return [];
+    if (h.subscriptionId) {
+      return [
+        { label: "Connect", onClick: () => onConnect(h) },
+        { label: h.pinned ? "Unpin from tray" : "Pin to tray", onClick: () => void toggleHostPinned(h) },
+        { kind: "sep" },
+        { kind: "header", label: "From a team subscription (read-only)" },
+      ];
+    }
     return [
       { label: "Connect", onClick: () => onConnect(h) },
+      { label: "Check connection", onClick: () => void runPreflight(h) },
       { label: "Edit", onClick: () => openEditModal(h) },
+      { label: h.pinned ? "Unpin from tray" : "Pin to tray", onClick: () => void toggleHostPinned(h) },
       { kind: "sep" },
       { kind: "header", label: "Color" },
       ...palette.map((p) => ({
@@ -353,7 +503,7 @@ export function HostsSidebar({
       { label: "Delete", onClick: () => void doDelete(h) },
     ];
     // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [ctxMenu.host?.id, ctxMenu.host?.color]);
+  }, [ctxMenu.host?.id, ctxMenu.host?.color, ctxMenu.host?.pinned]);
 
   function HostRow({ h }: { h: HostListItem }) {
     const { attributes, listeners, setNodeRef, transform, transition, isDragging } = useSortable({
@@ -367,7 +517,14 @@ export function HostsSidebar({
       opacity: isDragging ? 0.75 : 1,
     } as React.CSSProperties;
 
-    const dotClass = h.color ? `colorDot colorDot-${h.color}` : "colorDot";
+    const color = h.effectiveColor ?? h.color;
+    const dotClass = color ? `colorDot colorDot-${color}` : "colorDot";
+    const health = statuses[h.id];
+    const httpChecks = Object.values(httpStatuses).filter((s) => s.hostId === h.id);
+    const httpWorst =
+      httpChecks.find((s) => s.status === "down") ??
+      httpChecks.find((s) => s.status === "slow") ??
+      httpChecks[0];
     const isActive = !!activeHostId && activeHostId === h.id;
 
     const envAmbientClass = `hostRowEnv-${h.environmentTag.trim().toUpperCase()}`;
@@ -410,7 +567,30 @@ export function HostsSidebar({
           >
             <span className={dotClass} aria-hidden="true" />
             <div className="listRowMain">
-              <div className="listRowTitle">{h.label}</div>
+              <div className="listRowTitle">
+                {h.label}
+                {h.subscriptionId ? (
+                  <span className="pill" title="From a team subscription (read-only)">
+                    Team
+                  </span>
+                ) : null}
+                {health ? (
+                  <span
+                    className={`pill hostHealth hostHealth-${health.status}`}
+                    title={health.detail ?? (health.latencyMs !== null ? `${health.latencyMs} ms` : undefined)}
+                  >
+                    {health.status}
+                  </span>
+                ) : null}
+                {httpWorst ? (
+                  <span
+                    className={`pill hostHealth hostHealth-${httpWorst.status}`}
+                    title={`${httpChecks.length} HTTP check${httpChecks.length === 1 ? "" : "s"}: ${httpChecks.filter((s) => s.status === "down").length} down`}
+                  >
+                    http
+                  </span>
+                ) : null}
+              </div>
               <div className="listRowSub">
                 {h.username}@{h.hostname}:{h.port}
               </div>
@@ -419,7 +599,7 @@ export function HostsSidebar({
           </button>
         )}
 
-        {manageMode ? (
+        {manageMode && !h.subscriptionId ? (
           <button
             className="itemX itemXDanger"
             type="button"
@@ -442,6 +622,16 @@ export function HostsSidebar({
       <div className="panelHeader">
         {!collapsed ? <div className="panelTitle">Hosts</div> : <div className="panelTitle panelTitleHidden">Hosts</div>}
         <div className="panelHeaderActions">
+          {!collapsed ? (
+            <button
+              className="miniButton"
+              type="button"
+              onClick={cycleSort}
+              title="Host order (click to change); status orders use the health monitor"
+            >
+              {SORT_LABELS[sort]}
+            </button>
+          ) : null}
           <button
             className={collapsed ? "miniButton miniButtonIconOnly" : "miniButton"}
             type="button"
@@ -462,10 +652,14 @@ export function HostsSidebar({
           <label className="field">
             <span className="fieldLabel">Filter</span>
             <input
+              ref={filterRef}
               className="textInput"
               placeholder="tag:prod user:root ..."
               value={filter}
               onChange={(e) => setFilter(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === "Enter" && filtered.length > 0) onConnect(filtered[0]);
+              }}
             />
           </label>
         ) : null}
@@ -597,8 +791,8 @@ export function HostsSidebar({
                 <div className="dockCard">
                   <div className="dockCardTitle">Credentials (OS keyring)</div>
                   <div className="hint">
-                    SSH passphrase is stored in the OS keyring and never written to SQLite.
-                    OpsPad does not inject passwords into `ssh` in the MVP.
+                    Passphrase and password are stored in the OS keyring and never written to SQLite.
+                    OpsPad types them when `ssh` asks, unless the vault is locked.
                   </div>
                   <div className="field" style={{ marginTop: 10 }}>
                     <span className="fieldLabel">
@@ -663,6 +857,54 @@ export function HostsSidebar({
                       Save passphrase
                     </button>
                   </div>
+                  <div className="field" style={{ marginTop: 10 }}>
+                    <span className="fieldLabel">Login password {hasPassword ? "(saved)" : "(not set)"}</span>
+                    <input
+                      className="textInput"
+                      type="password"
+                      value={password}
+                      onChange={(e) => setPassword(e.target.value)}
+                      placeholder="(optional)"
+                    />
+                  </div>
+                  <div className="formRow">
+                    <button
+                      className="miniButton"
+                      type="button"
+                      onClick={() => {
+                        if (!editingId || !passwordKey) return;
+                        void vaultDeleteSecret(passwordKey)
+                          .then(() => hostsSetCredentials(editingId, null, editingHost?.passphraseVaultKey ?? null))
+                          .then(() => {
+                            setHasPassword(false);
+                            setPassword("");
+                            return refresh();
+                          })
+                          .catch((e) => setModalError(String(e)));
+                      }}
+                    >
+                      Clear
+                    </button>
+                    <button
+                      className="miniButton"
+                      type="button"
+                      onClick={() => {
+                        if (!editingId || !passwordKey || !password) return;
+                        void vaultSetSecret(passwordKey, password)
+                          .then(() =>
+                            hostsSetCredentials(editingId, passwordKey, editingHost?.passphraseVaultKey ?? null),
+                          )
+                          .then(() => {
+                            setHasPassword(true);
+                            setPassword("");
+                            return refresh();
+                          })
+                          .catch((e) => setModalError(String(e)));
+                      }}
+                    >
+                      Save password
+                    </button>
+                  </div>
                 </div>
               ) : null}
 
@@ -2,10 +2,17 @@ import "@xterm/xterm/css/xterm.css";
 
 import { FitAddon } from "@xterm/addon-fit";
 import { listen } from "@tauri-apps/api/event";
-import { Terminal } from "@xterm/xterm";
+import { Terminal, type IMarker } from "@xterm/xterm";
 import { useEffect, useLayoutEffect, useRef, useState } from "react";
 
-import { terminalResize, terminalWrite } from "../lib/opspadApi";
+import {
+  clipboardHistoryAdd,
+  terminalExpand,
+  terminalLastOutput,
+  terminalResize,
+  terminalWrite,
+  type HighlightSpan,
+} from "../lib/opspadApi";
 
 type Props = {
   sessionId: string | null;
@@ -14,14 +21,50 @@ type Props = {
   themeColor?: string | null;
   environmentTag?: string | null;
   connectionMeta?: string | null;
+  /** "local" or "ssh:<host id>"; picks which snippets expand. */
+  scope?: string | null;
   active?: boolean;
+  /** Called once data events for `sessionId` are being shown (a detached window flushes then). */
+  onListening?: () => void;
 };
 
 type TerminalDataEvent = {
   sessionId: string;
   data: string;
+  /** Matches of the session's highlight rules in `data`. */
+  spans?: HighlightSpan[];
 };
 
+const SEVERITY_SGR: Record<HighlightSpan["severity"], string> = {
+  info: "36",
+  success: "32",
+  warning: "33",
+  error: "31",
+};
+const ESCAPES = /\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]/g;
+
+function spanSgr(span: HighlightSpan): string {
+  const hex = /^#([0-9a-f]{2})([0-9a-f]{2})([0-9a-f]{2})$/i.exec(span.color ?? "");
+  if (hex) return `38;2;${parseInt(hex[1], 16)};${parseInt(hex[2], 16)};${parseInt(hex[3], 16)}`;
+  return SEVERITY_SGR[span.severity];
+}
+
+/** Color highlight spans in. Spans overlapping each other or an escape sequence are left alone. */
+function applySpans(data: string, spans: HighlightSpan[] | undefined): string {
+  if (!spans || spans.length === 0) return data;
+  const escapes = Array.from(data.matchAll(ESCAPES), (m) => [m.index, m.index + m[0].length] as const);
+  const sorted = [...spans].sort((a, b) => a.start - b.start);
+  let out = "";
+  let at = 0;
+  for (const span of sorted) {
+    if (span.start < at) continue;
+    if (escapes.some(([s, e]) => span.start < e && s < span.end)) continue;
+    out += `${data.slice(at, span.start)}\x1b[${spanSgr(span)}m${data.slice(span.start, span.end)}\x1b[39m`;
+    at = span.end;
+  }
+  return out + data.slice(at);
+}
+
 declare global {
   interface WindowEventMap {
     "opspad-terminal-paste": CustomEvent<
@@ -32,9 +75,21 @@ declare global {
           dockCommandId?: string;
           dockCommandTitle?: string;
           dockCommandTemplate?: string;
+          confirmation?: string;
+          confirmedHash?: string;
+        }
+    >;
+    "opspad-terminal-run": CustomEvent<
+      | string
+      | {
+          text: string;
+          dockCommandId?: string;
+          dockCommandTitle?: string;
+          dockCommandTemplate?: string;
+          confirmation?: string;
+          confirmedHash?: string;
         }
     >;
-    "opspad-terminal-run": CustomEvent<string | { text: string; dockCommandId?: string; dockCommandTitle?: string; dockCommandTemplate?: string }>;
     "opspad-terminal-activity": CustomEvent<void>;
     "opspad-terminal-flash": CustomEvent<void>;
   }
@@ -47,7 +102,9 @@ export function TerminalPane({
   themeColor,
   environmentTag,
   connectionMeta,
+  scope = null,
   active = false,
+  onListening,
 }: Props) {
   const hostRef = useRef<HTMLDivElement | null>(null);
   const mountRef = useRef<HTMLDivElement | null>(null);
@@ -68,6 +125,14 @@ export function TerminalPane({
   useEffect(() => {
     activeRef.current = !!active;
   }, [active]);
+  const scopeRef = useRef(scope);
+  useEffect(() => {
+    scopeRef.current = scope;
+  }, [scope]);
+  const onListeningRef = useRef(onListening);
+  useEffect(() => {
+    onListeningRef.current = onListening;
+  }, [onListening]);
 
   useLayoutEffect(() => {
     if (!hostRef.current || !mountRef.current) return;
@@ -118,6 +183,45 @@ export function TerminalPane({
       })
       .catch(() => {});
 
+    const copyLastOutput = async (sid: string) => {
+      const last = await terminalLastOutput(sid).catch(() => null);
+      if (!last) {
+        setBanner({ text: "No finished command to copy (needs shell integration).", kind: "warn" });
+      } else {
+        await navigator.clipboard.writeText(last.output);
+        setBanner({ text: `Copied output of ${last.command ?? "the last command"}.`, kind: "ok" });
+      }
+      window.setTimeout(() => setBanner(null), 2200);
+    };
+
+    // Shell integration marks each prompt (OSC 133 ; A); Ctrl+Shift+Up/Down jump between them.
+    const prompts: IMarker[] = [];
+    const promptMarks = term.parser.registerOscHandler(133, (data) => {
+      if (data === "A" || data.startsWith("A;")) {
+        const marker = term.registerMarker(0);
+        if (marker) {
+          prompts.push(marker);
+          marker.onDispose(() => prompts.splice(prompts.indexOf(marker), 1));
+        }
+      }
+      return false;
+    });
+    term.attachCustomKeyEventHandler((ev) => {
+      // Ctrl+Shift+O copies the last command's output.
+      if (ev.type === "keydown" && ev.ctrlKey && ev.shiftKey && ev.code === "KeyO") {
+        const sid = sessionIdRef.current;
+        if (sid) void copyLastOutput(sid);
+        return false;
+      }
+      const up = ev.key === "ArrowUp";
+      if (ev.type !== "keydown" || !ev.ctrlKey || !ev.shiftKey || (!up && ev.key !== "ArrowDown")) return true;
+      const top = term.buffer.active.viewportY;
+      const target = up ? [...prompts].reverse().find((m) => m.line < top) : prompts.find((m) => m.line > top);
+      if (target) term.scrollToLine(target.line);
+      else if (!up) term.scrollToBottom();
+      return false;
+    });
+
     termRef.current = term;
     fitRef.current = fit;
     setReady(true);
@@ -125,6 +229,13 @@ export function TerminalPane({
     const hostEl = hostRef.current;
     const onMouseDown = () => term.focus();
     hostEl.addEventListener("mousedown", onMouseDown);
+    // Copies out of the terminal feed the (opt-in) clipboard history; the backend ignores them
+    // while it's off.
+    const onCopy = () => {
+      const text = term.getSelection();
+      if (text) void clipboardHistoryAdd(text, sessionIdRef.current).catch(() => {});
+    };
+    hostEl.addEventListener("copy", onCopy);
 
     const ro = new ResizeObserver(() => {
       const t = termRef.current;
@@ -147,6 +258,8 @@ export function TerminalPane({
     return () => {
       ro.disconnect();
       hostEl.removeEventListener("mousedown", onMouseDown);
+      hostEl.removeEventListener("copy", onCopy);
+      promptMarks.dispose();
       term.dispose();
       termRef.current = null;
       fitRef.current = null;
@@ -251,12 +364,14 @@ export function TerminalPane({
         const sid = ev.payload.sessionId;
         const mySid = sessionIdRef.current;
         if (!mySid || sid !== mySid) return;
-        if (ready) termRef.current?.write(ev.payload.data);
+        const data = applySpans(ev.payload.data, ev.payload.spans);
+        if (ready) termRef.current?.write(data);
         else {
           const prev = pendingBySessionRef.current.get(sid) ?? "";
-          pendingBySessionRef.current.set(sid, prev + ev.payload.data);
+          pendingBySessionRef.current.set(sid, prev + data);
         }
       });
+      if (ready) onListeningRef.current?.();
     })().catch(() => {});
 
     return () => {
@@ -300,14 +415,39 @@ export function TerminalPane({
     let cleanupSession: (() => void) | null = null;
 
     (async () => {
+      // The word being typed, for snippet expansion. Writes are chained so keys typed while a
+      // word is being expanded still reach the session after it, in order.
+      let word = "";
+      let writes: Promise<unknown> = Promise.resolve();
       const disposable = termRef.current?.onData((data) => {
         if (!activeRef.current) return;
         const sid = sessionIdRef.current;
         if (!sid) return;
         window.dispatchEvent(new CustomEvent("opspad-terminal-activity"));
-        void terminalWrite(sid, data).catch((e) => {
-          termRef.current?.writeln(`\r\n[opspad] write failed: ${String(e)}\r\n`);
-        });
+        let send: () => Promise<unknown> = () => terminalWrite(sid, data);
+        if (data === " " || data === "\r" || data === "\t") {
+          const typed = word;
+          word = "";
+          if (typed) {
+            send = async () => {
+              const expansion = await terminalExpand(typed, scopeRef.current).catch(() => null);
+              if (!expansion?.expanded.length) return terminalWrite(sid, data);
+              // Erase the trigger the shell already echoed, then type the expansion.
+              return terminalWrite(sid, "\x7f".repeat([...typed].length) + expansion.text + data);
+            };
+          }
+        } else if (data === "\x7f") {
+          word = [...word].slice(0, -1).join("");
+        } else if ([...data].length === 1 && data >= " ") {
+          word += data;
+        } else {
+          word = "";
+        }
+        writes = writes
+          .then(send)
+          .catch((e) => {
+            termRef.current?.writeln(`\r\n[opspad] write failed: ${String(e)}\r\n`);
+          });
       });
 
       const onPaste = (ev: CustomEvent<string>) => {
@@ -325,6 +465,8 @@ export function TerminalPane({
                   dockCommandId?: string;
                   dockCommandTitle?: string;
                   dockCommandTemplate?: string;
+                  confirmation?: string;
+                  confirmedHash?: string;
                 })
               : { text: "" };
 
@@ -339,6 +481,8 @@ export function TerminalPane({
             dockCommandId: payload.dockCommandId,
             dockCommandTitle: payload.dockCommandTitle,
             dockCommandTemplate: payload.dockCommandTemplate,
+            confirmation: payload.confirmation,
+            confirmedHash: payload.confirmedHash,
           },
         )
           .then(() => {
@@ -357,6 +501,8 @@ export function TerminalPane({
               dockCommandId?: string;
               dockCommandTitle?: string;
               dockCommandTemplate?: string;
+              confirmation?: string;
+              confirmedHash?: string;
             }
         >,
       ) => {
@@ -368,7 +514,14 @@ export function TerminalPane({
           typeof d === "string"
             ? { text: d }
             : d && typeof d === "object" && "text" in (d as Record<string, unknown>)
-              ? (d as { text: string; dockCommandId?: string; dockCommandTitle?: string; dockCommandTemplate?: string })
+              ? (d as {
+                  text: string;
+                  dockCommandId?: string;
+                  dockCommandTitle?: string;
+                  dockCommandTemplate?: string;
+                  confirmation?: string;
+                  confirmedHash?: string;
+                })
               : { text: "" };
 
         const text = payload.text ?? "";
@@ -382,6 +535,8 @@ export function TerminalPane({
             dockCommandId: payload.dockCommandId,
             dockCommandTitle: payload.dockCommandTitle,
             dockCommandTemplate: payload.dockCommandTemplate,
+            confirmation: payload.confirmation,
+            confirmedHash: payload.confirmedHash,
           },
         ).catch((e) => {
           termRef.current?.writeln(`\r\n[opspad] run failed: ${String(e)}\r\n`);
@@ -12,7 +12,36 @@ import { SortableContext, useSortable, horizontalListSortingStrategy, arrayMove
 import { CSS } from "@dnd-kit/utilities";
 
 import type { HostListItem } from "./HostsSidebar";
-import { terminalClose, terminalMarkExited, terminalOpenLocal, terminalOpenSsh } from "../lib/opspadApi";
+import {
+  hostsList,
+  layoutSet,
+  terminalClose,
+  terminalDetach,
+  terminalMarkExited,
+  terminalOpenLocal,
+  terminalOpenSsh,
+  terminalOpenGroup,
+  authPromptAnswer,
+  rdpOpen,
+  sessionExportMarkdown,
+  terminalMark,
+  terminalRename,
+  terminalTypeSecret,
+  sessionRestoreDismiss,
+  sessionRestorePlan,
+  sessionShareStart,
+  sessionShareStatus,
+  sessionShareStop,
+  type AuthPrompt,
+  type AuthPromptClosedEvent,
+  type AutomationSessionOpenedEvent,
+  type DetachedSession,
+  type ShareInfo,
+  type SudoPromptEvent,
+  type TerminalPrivilegedEvent,
+  type TerminalRenamedEvent,
+  type WorkspaceLayout,
+} from "../lib/opspadApi";
 import { TerminalPane } from "./TerminalPane";
 
 type ConnectRequest = {
@@ -35,8 +64,12 @@ type TermTab = {
   id: string;
   kind: "local" | "ssh";
   title: string;
+  /** Given by the user (double-click the tab); shown instead of title. */
+  customTitle?: string | null;
   sessionId: string | null;
   statusText?: string | null;
+  /** Set while the shell is running as root. */
+  root?: { environmentTag: string } | null;
   ssh?: SshMeta;
   bornAt: number;
 };
@@ -49,6 +82,11 @@ function newId(prefix: string) {
   return `${prefix}-${u}`;
 }
 
+/** The scope a tab's session is opened for, as the backend keys sessions and layouts. */
+function scopeOf(t: TermTab) {
+  return t.kind === "ssh" && t.ssh ? `ssh:${t.ssh.hostId}` : "local";
+}
+
 function kindLabel(kind: "local" | "ssh") {
   // Avoid calling local tabs "Local" everywhere; from the user's POV these are just terminals.
   return kind === "ssh" ? "SSH" : "TERM";
@@ -113,11 +151,62 @@ export function TerminalWorkspace({
     if (localId) setActiveId(localId);
   };
 
+  // Tabs whose session was moved to its own window, keyed by session id, so closing that
+  // window brings the same tab back.
+  const detachedRef = useRef<Map<string, TermTab>>(new Map());
+  // Notices shown as a tab's status (a security key waiting for a touch), by prompt id.
+  const noticeSessionsRef = useRef<Map<string, string>>(new Map());
+
   const closeTab = (tabId: string) => {
     const tab = tabsRef.current.find((t) => t.id === tabId);
     if (!tab) return;
     if (tab.sessionId) void terminalClose(tab.sessionId).catch(() => {});
+    removeTab(tabId);
+  };
+
+  const detachTab = async (tabId: string) => {
+    const tab = tabsRef.current.find((t) => t.id === tabId);
+    if (!tab?.sessionId) return;
+    try {
+      await terminalDetach({
+        sessionId: tab.sessionId,
+        title: tab.customTitle ?? tab.title,
+        environmentTag: tab.kind === "ssh" ? (tab.ssh?.environmentTag ?? "UNKNOWN") : "LOCAL",
+      });
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Detach failed: ${String(e)}` } : t)));
+      return;
+    }
+    detachedRef.current.set(tab.sessionId, tab);
+    removeTab(tabId);
+  };
+
+  const renameTab = async (tab: TermTab) => {
+    if (!tab.sessionId) return;
+    const title = window.prompt("Tab title (empty to reset):", tab.customTitle ?? tab.title);
+    if (title === null) return;
+    try {
+      await terminalRename(tab.sessionId, title);
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tab.id ? { ...t, statusText: `Rename failed: ${String(e)}` } : t)));
+    }
+  };
+
+  // Titles and the active tab from a saved layout, onto tabs reopened in its order.
+  const applyLayout = (layout: WorkspaceLayout) => {
+    const pending = [...tabsRef.current];
+    for (const lt of layout.tabs) {
+      if (lt.pane.type !== "session") continue;
+      const scope = lt.pane.scope;
+      const i = pending.findIndex((t) => scopeOf(t) === scope);
+      if (i < 0) continue;
+      const [tab] = pending.splice(i, 1);
+      if (lt.pane.title && tab.sessionId) void terminalRename(tab.sessionId, lt.pane.title).catch(() => {});
+      if (lt.id === layout.activeTab) setActiveId(tab.id);
+    }
+  };
 
+  const removeTab = (tabId: string) => {
     setTabs((prev) => prev.filter((t) => t.id !== tabId));
     if (activeIdRef.current === tabId) {
       // Prefer any remaining local session, else fall back to the first remaining tab.
@@ -157,13 +246,34 @@ export function TerminalWorkspace({
         type="button"
         aria-selected={t.id === activeTab.id}
         onClick={() => setActiveId(t.id)}
+        onDoubleClick={() => void renameTab(t)}
         title={t.kind === "ssh" && t.ssh ? `${t.ssh.username}@${t.ssh.hostname}` : t.title}
         {...attributes}
         {...listeners}
       >
         <span className={`tabStatus tabStatus-${sk}`} aria-hidden="true" />
-        <span className="tabTitle">{t.title}</span>
+        <span className="tabTitle">{t.customTitle ?? t.title}</span>
+        {t.root ? (
+          <span className="pill pillRoot" title="This shell is running as root">
+            {t.root.environmentTag.toUpperCase() === "PROD" ? "ROOT ON PROD" : "ROOT"}
+          </span>
+        ) : null}
         <span className={t.kind === "ssh" ? "pill pillStage" : "pill pillLocal"}>{kindLabel(t.kind)}</span>
+        {t.sessionId ? (
+          <span
+            className="tabClose"
+            role="button"
+            aria-label="Move to new window"
+            title="Move to new window"
+            onClick={(e) => {
+              e.preventDefault();
+              e.stopPropagation();
+              void detachTab(t.id);
+            }}
+          >
+            ⧉
+          </span>
+        ) : null}
         <span
           className="tabClose"
           role="button"
@@ -210,6 +320,15 @@ export function TerminalWorkspace({
   };
 
   const connectOrActivateSsh = async (host: HostListItem) => {
+    if (host.connectionType === "rdp") {
+      // The remote desktop opens in its own client window; there's no tab for it.
+      try {
+        await rdpOpen(host.id, Boolean(host.passwordVaultKey));
+      } catch (e) {
+        window.alert(`RDP launch failed: ${String(e)}`);
+      }
+      return;
+    }
     const existing = tabsRef.current.find((t) => t.kind === "ssh" && t.ssh?.hostId === host.id);
     if (existing) {
       setActiveId(existing.id);
@@ -231,6 +350,8 @@ export function TerminalWorkspace({
             environmentTag: host.environmentTag,
           });
           setTabs((prev) => prev.map((t) => (t.id === existing.id ? { ...t, sessionId: sid, statusText: null } : t)));
+          // Keep the tab's title on the new session.
+          if (existing.customTitle) void terminalRename(sid, existing.customTitle).catch(() => {});
         } catch (e) {
           setTabs((prev) =>
             prev.map((t) =>
@@ -251,7 +372,7 @@ export function TerminalWorkspace({
       username: host.username,
       environmentTag: host.environmentTag,
       identityFile: host.identityFile ?? null,
-      color: host.color ?? null,
+      color: host.effectiveColor ?? host.color ?? null,
     };
 
     setTabs((prev) => [
@@ -297,7 +418,92 @@ export function TerminalWorkspace({
       return;
     }
     didBootstrapRef.current = true;
-    void openLocalTab();
+    void (async () => {
+      // Offer to reopen what was open at the last exit; sessions that would need a login are
+      // listed but left for the user to open.
+      const plan = await sessionRestorePlan().catch(() => null);
+      if (plan) {
+        const ready = plan.sessions.filter((s) => s.reconnect);
+        const manual = plan.sessions.filter((s) => !s.reconnect).map((s) => s.label);
+        const when = new Date(plan.savedAt * 1000).toLocaleString();
+        const note = manual.length ? `\n\nNeeds a login, not reopened: ${manual.join(", ")}` : "";
+        const reopen =
+          ready.length > 0 &&
+          window.confirm(`Reopen your ${ready.length} session${ready.length === 1 ? "" : "s"} from ${when}?${note}`);
+        void sessionRestoreDismiss().catch(() => {});
+        if (reopen) {
+          const hosts = ready.some((s) => s.hostId) ? await hostsList().catch(() => []) : [];
+          // In the saved tab order; sessions the layout doesn't mention go last.
+          const order = (plan.layout?.tabs ?? []).map((t) => (t.pane.type === "session" ? t.pane.scope : null));
+          const rank = (scope: string) => (order.includes(scope) ? order.indexOf(scope) : order.length);
+          ready.sort((a, b) => rank(a.scope) - rank(b.scope));
+          for (const s of ready) {
+            const host = s.hostId ? hosts.find((h) => h.id === s.hostId) : undefined;
+            if (host) await connectOrActivateSsh(host);
+            else if (s.kind === "local") await openLocalTab();
+          }
+          if (plan.layout) {
+            const layout = plan.layout;
+            setTimeout(() => applyLayout(layout), 0);
+          }
+          if (ready.some((s) => s.kind === "local")) return;
+        }
+      }
+      void openLocalTab();
+    })();
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  useEffect(() => {
+    const onNewTerminal = () => void openLocalTab();
+    window.addEventListener("opspad-new-terminal", onNewTerminal);
+    return () => window.removeEventListener("opspad-new-terminal", onNewTerminal);
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
+  // One tab per session when a whole host group is opened (`opspad-open-group` with { groupId }).
+  useEffect(() => {
+    const onOpenGroup = async (e: Event) => {
+      const groupId = (e as CustomEvent<{ groupId: string }>).detail?.groupId;
+      if (!groupId) return;
+      try {
+        const [opened, hosts] = await Promise.all([terminalOpenGroup(groupId), hostsList()]);
+        const added: TermTab[] = opened.sessions.flatMap(({ sessionId, hostId }) => {
+          const host = hosts.find((h) => h.id === hostId);
+          if (!host) return [];
+          return [
+            {
+              id: newId("ssh"),
+              kind: "ssh" as const,
+              title: host.label,
+              sessionId,
+              ssh: {
+                hostId: host.id,
+                label: host.label,
+                hostname: host.hostname,
+                port: host.port,
+                username: host.username,
+                environmentTag: host.environmentTag,
+                identityFile: host.identityFile ?? null,
+                color: host.effectiveColor ?? host.color ?? null,
+              },
+              bornAt: Date.now(),
+            },
+          ];
+        });
+        setTabs((prev) => [...prev, ...added]);
+        if (added.length) setActiveId(added[0].id);
+        if (opened.failures.length) {
+          window.alert(
+            `Couldn't open:\n${opened.failures.map((f) => `${f.label}: ${f.error}`).join("\n")}`,
+          );
+        }
+      } catch (err) {
+        window.alert(`Failed to open group: ${String(err)}`);
+      }
+    };
+    window.addEventListener("opspad-open-group", onOpenGroup);
+    return () => window.removeEventListener("opspad-open-group", onOpenGroup);
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
@@ -315,6 +521,7 @@ export function TerminalWorkspace({
       unlisten = await listen<{ sessionId: string }>("terminal:exit", (ev) => {
         const sid = ev.payload.sessionId;
         void terminalMarkExited(sid).catch(() => {});
+        detachedRef.current.delete(sid);
         const tab = tabsRef.current.find((t) => t.sessionId === sid);
         if (!tab) return;
 
@@ -344,6 +551,253 @@ export function TerminalWorkspace({
     };
   }, []);
 
+  // LAN share of one session (read-only); the tab bar shows it with a stop control.
+  const [share, setShare] = useState<ShareInfo | null>(null);
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    void sessionShareStatus()
+      .then(setShare)
+      .catch(() => {});
+    (async () => {
+      unlisten = await listen<ShareInfo | null>("lan-share:changed", (ev) => setShare(ev.payload));
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  const startShare = async (tabId: string) => {
+    const tab = tabsRef.current.find((t) => t.id === tabId);
+    if (!tab?.sessionId) return;
+    try {
+      const info = await sessionShareStart(tab.sessionId);
+      setShare(info);
+      void navigator.clipboard?.writeText(info.url).catch(() => {});
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Share failed: ${String(e)}` } : t)));
+    }
+  };
+
+  const copyTranscript = async (tabId: string) => {
+    const tab = tabsRef.current.find((t) => t.id === tabId);
+    if (!tab?.sessionId) return;
+    try {
+      await navigator.clipboard.writeText(await sessionExportMarkdown(tab.sessionId));
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Export failed: ${String(e)}` } : t)));
+    }
+  };
+
+  const addMark = async (tabId: string) => {
+    const tab = tabsRef.current.find((t) => t.id === tabId);
+    if (!tab?.sessionId) return;
+    const label = window.prompt("Mark this point in the session (e.g. began failover):");
+    if (!label?.trim()) return;
+    try {
+      await terminalMark(tab.sessionId, label);
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Marked: ${label.trim()}` } : t)));
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Mark failed: ${String(e)}` } : t)));
+    }
+  };
+
+  // The secret goes from the vault straight to the PTY; only its key passes through here.
+  const typeSecret = async (tabId: string) => {
+    const tab = tabsRef.current.find((t) => t.id === tabId);
+    if (!tab?.sessionId) return;
+    const key = window.prompt("Vault key of the secret to type (followed by Enter):");
+    if (!key?.trim()) return;
+    try {
+      await terminalTypeSecret(tab.sessionId, key.trim());
+    } catch (e) {
+      setTabs((prev) => prev.map((t) => (t.id === tabId ? { ...t, statusText: `Type secret failed: ${String(e)}` } : t)));
+    }
+  };
+
+  // sudo asked for a password: say so on the tab, and offer the stored one if there is one.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<SudoPromptEvent>("terminal:sudo_prompt", (ev) => {
+        const { sessionId, user, vaultKey, autoAnswered, note } = ev.payload;
+        const tab = tabsRef.current.find((t) => t.sessionId === sessionId);
+        if (!tab) return;
+        const who = user ? ` for ${user}` : "";
+        const statusText = autoAnswered
+          ? `sudo password${who} typed from the vault`
+          : note
+            ? `sudo is asking for a password${who}: ${note}`
+            : `sudo is asking for a password${who}`;
+        setTabs((prev) => prev.map((t) => (t.id === tab.id ? { ...t, statusText } : t)));
+        if (!autoAnswered && vaultKey && !note && tab.id === activeIdRef.current) {
+          if (window.confirm(`sudo is asking for the password${who}. Type the one stored in the vault?`)) {
+            void terminalTypeSecret(sessionId, vaultKey).catch(() => {});
+          }
+        }
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // The shell went in or out of root: flag the tab.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<TerminalPrivilegedEvent>("terminal:privileged", (ev) => {
+        const { sessionId, privileged, environmentTag } = ev.payload;
+        const root = privileged ? { environmentTag } : null;
+        setTabs((prev) => prev.map((t) => (t.sessionId === sessionId ? { ...t, root } : t)));
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Keep the backend's copy of the arrangement current, so a restart can put it back.
+  useEffect(() => {
+    if (tabs.length === 0) return;
+    const timer = setTimeout(() => {
+      void layoutSet({
+        tabs: tabs.map((t) => ({
+          id: t.id,
+          pane: { type: "session", scope: scopeOf(t), title: t.customTitle ?? null },
+        })),
+        groups: [],
+        activeTab: activeId,
+      }).catch(() => {});
+    }, 1000);
+    return () => clearTimeout(timer);
+  }, [tabs, activeId]);
+
+  // A session was renamed (here or in a detached window).
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<TerminalRenamedEvent>("terminal:renamed", (ev) => {
+        const { sessionId, title } = ev.payload;
+        setTabs((prev) => prev.map((t) => (t.sessionId === sessionId ? { ...t, customTitle: title } : t)));
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // A notice (e.g. "touch your security key") is over: clear it from its tab.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<AuthPromptClosedEvent>("auth:prompt_closed", (ev) => {
+        const sessionId = noticeSessionsRef.current.get(ev.payload.id);
+        if (!sessionId) return;
+        noticeSessionsRef.current.delete(ev.payload.id);
+        setTabs((prev) => prev.map((t) => (t.sessionId === sessionId ? { ...t, statusText: null } : t)));
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // ssh asked a login question through the askpass bridge: show it on the tab and ask the user.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<AuthPrompt>("auth:prompt", (ev) => {
+        const { id, sessionId, kind, prompt } = ev.payload;
+        const text = prompt.trim();
+        if (sessionId) {
+          setTabs((prev) => prev.map((t) => (t.sessionId === sessionId ? { ...t, statusText: text } : t)));
+        }
+        if (kind === "notice" || kind === "touch") {
+          if (sessionId) noticeSessionsRef.current.set(id, sessionId);
+          return;
+        }
+        let answer: string | null;
+        if (kind === "hostKey") {
+          answer = window.confirm(text) ? "yes" : "no";
+        } else if (kind === "confirm") {
+          answer = window.confirm(text) ? "yes" : null;
+        } else {
+          answer = window.prompt(text);
+        }
+        void authPromptAnswer(id, answer).catch(() => {});
+        if (sessionId) {
+          setTabs((prev) => prev.map((t) => (t.sessionId === sessionId ? { ...t, statusText: null } : t)));
+        }
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // A script opened a session through the automation API: give it a tab.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<AutomationSessionOpenedEvent>("automation:session_opened", async (ev) => {
+        const { sessionId, hostId, title } = ev.payload;
+        const host = hostId ? (await hostsList().catch(() => [])).find((h) => h.id === hostId) : undefined;
+        const tab: TermTab = host
+          ? {
+              id: newId("ssh"),
+              kind: "ssh",
+              title: host.label,
+              sessionId,
+              ssh: {
+                hostId: host.id,
+                label: host.label,
+                hostname: host.hostname,
+                port: host.port,
+                username: host.username,
+                environmentTag: host.environmentTag,
+                identityFile: host.identityFile ?? null,
+                color: host.effectiveColor ?? host.color ?? null,
+              },
+              bornAt: Date.now(),
+            }
+          : { id: newId("local"), kind: "local", title, sessionId, bornAt: Date.now() };
+        setTabs((prev) => [...prev, tab]);
+        setActiveId(tab.id);
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // A detached window closed with its session still running: bring the tab back.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      unlisten = await listen<DetachedSession>("terminal:reattached", (ev) => {
+        const { sessionId, title } = ev.payload;
+        const prior = detachedRef.current.get(sessionId);
+        detachedRef.current.delete(sessionId);
+        const tab: TermTab = prior
+          ? { ...prior, bornAt: Date.now() }
+          : { id: newId("local"), kind: "local", title, sessionId, bornAt: Date.now() };
+        setTabs((prev) => [...prev, tab]);
+        setActiveId(tab.id);
+      });
+    })().catch(() => {});
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   if (!activeTab) {
     return (
       <section className="panel panelFlush">
@@ -370,7 +824,7 @@ export function TerminalWorkspace({
       t.kind === "ssh" && t.ssh
         ? `Connected to: ${t.ssh.label}  ·  ${t.ssh.environmentTag}  ·  ${t.ssh.hostname}:${t.ssh.port}`
         : null;
-    return { sessionLabel, environmentTag, connectionMeta };
+    return { sessionLabel, environmentTag, connectionMeta, scope: scopeOf(t) };
   };
 
   return (
@@ -396,6 +850,55 @@ export function TerminalWorkspace({
           </SortableContext>
         </DndContext>
         <div className="tabBarSpacer" />
+        {share ? (
+          <button
+            className="miniButton"
+            type="button"
+            onClick={() => void sessionShareStop().then(() => setShare(null))}
+            title={`Shared read-only at ${share.url} (click to stop)`}
+          >
+            Sharing · {share.viewers} watching · Stop
+          </button>
+        ) : activeTab.sessionId ? (
+          <button
+            className="miniButton"
+            type="button"
+            onClick={() => void startShare(activeTab.id)}
+            title="Share this session read-only on the LAN (copies the viewer link)"
+          >
+            Share
+          </button>
+        ) : null}
+        {activeTab.sessionId ? (
+          <button
+            className="miniButton"
+            type="button"
+            onClick={() => void copyTranscript(activeTab.id)}
+            title="Copy this session's transcript as Markdown"
+          >
+            Copy transcript
+          </button>
+        ) : null}
+        {activeTab.sessionId ? (
+          <button
+            className="miniButton"
+            type="button"
+            onClick={() => void addMark(activeTab.id)}
+            title="Mark this point in the session's timeline"
+          >
+            Mark
+          </button>
+        ) : null}
+        {activeTab.sessionId ? (
+          <button
+            className="miniButton"
+            type="button"
+            onClick={() => void typeSecret(activeTab.id)}
+            title="Type a secret from the vault into this session"
+          >
+            Type secret
+          </button>
+        ) : null}
         <button className="miniButton" type="button" onClick={() => void openLocalTab()} title="New terminal">
           +
         </button>
@@ -403,7 +906,7 @@ export function TerminalWorkspace({
 
       <div className="terminalFrame">
         {tabs.map((t) => {
-          const { sessionLabel, environmentTag, connectionMeta } = panePropsFor(t);
+          const { sessionLabel, environmentTag, connectionMeta, scope } = panePropsFor(t);
           const isActive = t.id === activeTab.id;
           return (
             <div key={t.id} style={{ display: isActive ? "block" : "none", height: "100%" }}>
@@ -415,6 +918,7 @@ export function TerminalWorkspace({
                 themeColor={null}
                 environmentTag={environmentTag}
                 connectionMeta={connectionMeta}
+                scope={scope}
               />
             </div>
           );